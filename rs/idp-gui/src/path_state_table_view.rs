@@ -1,6 +1,7 @@
 use crate::{Grid, Message, PathStatePreview};
 use iced::Element;
 use idp_core::Datahost;
+use idp_datahost_storage::PathStatesFilter;
 
 pub struct PathStateTableView {}
 
@@ -24,9 +25,13 @@ impl PathStateTableView {
             debug,
         );
 
+        use futures::TryStreamExt;
         use pollster::FutureExt;
-        for (row_inserted_at, row_updated_at, path_state) in
-            datahost.select_path_states(None).block_on().unwrap()
+        for (row_inserted_at, row_updated_at, path_state) in datahost
+            .select_path_states(PathStatesFilter::default(), None)
+            .try_collect::<Vec<_>>()
+            .block_on()
+            .unwrap()
         {
             grid = PathStatePreview.grid_view_push_row(
                 grid,
@@ -47,9 +52,13 @@ impl PathStateTableView {
 
         // TODO: Render a real table
 
+        use futures::TryStreamExt;
         use pollster::FutureExt;
-        for (row_inserted_at, row_updated_at, path_state) in
-            datahost.select_path_states(None).block_on().unwrap()
+        for (row_inserted_at, row_updated_at, path_state) in datahost
+            .select_path_states(PathStatesFilter::default(), None)
+            .try_collect::<Vec<_>>()
+            .block_on()
+            .unwrap()
         {
             col = col.push(PathStatePreview.view(
                 Some(row_inserted_at),
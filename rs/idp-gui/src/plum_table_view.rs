@@ -1,6 +1,7 @@
 use crate::{Message, PlumPreview};
 use iced::Element;
 use idp_core::Datahost;
+use idp_datahost_storage::PlumHeadsFilter;
 
 pub struct PlumTableView {}
 
@@ -18,9 +19,13 @@ impl PlumTableView {
 
         // TODO: Render a real table
 
+        use futures::TryStreamExt;
         use pollster::FutureExt;
-        for (row_inserted_at, plum_head_seal, _plum_head) in
-            datahost.select_plum_heads(None).block_on().unwrap()
+        for (row_inserted_at, plum_head_seal, _plum_head) in datahost
+            .select_plum_heads(PlumHeadsFilter::default(), None)
+            .try_collect::<Vec<_>>()
+            .block_on()
+            .unwrap()
         {
             col = col.push(PlumPreview.view(
                 Some(row_inserted_at),
@@ -1,7 +1,13 @@
 pub use idp_core as core;
 pub use idp_datahost_storage as datahost_storage;
+#[cfg(feature = "in-memory")]
+pub use idp_datahost_storage_in_memory as datahost_storage_in_memory;
+#[cfg(feature = "postgres")]
+pub use idp_datahost_storage_postgres as datahost_storage_postgres;
 #[cfg(feature = "sqlite")]
 pub use idp_datahost_storage_sqlite as datahost_storage_sqlite;
+#[cfg(feature = "p2p")]
+pub use idp_p2p as p2p;
 pub use idp_proto as proto;
 #[cfg(feature = "server")]
 pub use idp_server as server;
@@ -0,0 +1,137 @@
+//! Helpers shared by the integration tests.  Each test crate only uses some of them.
+#![allow(dead_code)]
+
+use async_lock::RwLock;
+use idp_core::{BranchNodeBuilder, Datahost, DirNode};
+use idp_proto::{
+    BranchSetHeadRequest, ContentEncoding, ContentFormat, Path, Plum, PlumBuilder, PlumHeadSeal,
+    SealKey,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Returns a content Plum having the given content, made unique by appending a UUID.
+pub fn content_plum(content: &str) -> Plum {
+    PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("{}, {}", content, Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass")
+}
+
+/// Returns a content Plum having the given content, made unique by appending a UUID, whose seals are
+/// keyed using the given SealKey.
+pub fn keyed_content_plum(content: &str, seal_key: &SealKey) -> Plum {
+    PlumBuilder::new()
+        .with_seal_key(seal_key.clone())
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("{}, {}", content, Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass")
+}
+
+/// Returns a DirNode Plum whose entries ("entry-0", "entry-1", ...) are the given Plums.
+pub fn dir_node_plum(entry_plum_v: &[Plum]) -> Plum {
+    PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &DirNode {
+                entry_m: entry_plum_v
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry_plum)| {
+                        (
+                            format!("entry-{}", i),
+                            PlumHeadSeal::from(&entry_plum.plum_head),
+                        )
+                    })
+                    .collect(),
+            },
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass")
+}
+
+pub async fn assert_has_plums(
+    datahost_la: &Arc<RwLock<Datahost>>,
+    plum_v: &[Plum],
+    expected: bool,
+) {
+    for plum in plum_v.iter() {
+        assert_eq!(
+            datahost_la
+                .read()
+                .await
+                .has_plum(&PlumHeadSeal::from(&plum.plum_head), None)
+                .await
+                .expect("pass"),
+            expected
+        );
+    }
+}
+
+/// Builds a BranchNode Plum (with the given ancestor, if any) and stores it, along with the Plums it
+/// refers to, in the given Datahost.
+pub async fn store_branch_node_plum(
+    datahost_la: &Arc<RwLock<Datahost>>,
+    ancestor_o: Option<&Plum>,
+    label: &str,
+) -> Plum {
+    let metadata_plum = content_plum(&format!("{} metadata", label));
+    let content_plum = content_plum(&format!("{} content", label));
+    let mut branch_node_builder = BranchNodeBuilder::new()
+        .with_metadata(PlumHeadSeal::from(&metadata_plum.plum_head))
+        .with_content(PlumHeadSeal::from(&content_plum.plum_head));
+    if let Some(ancestor) = ancestor_o {
+        branch_node_builder = branch_node_builder.with_ancestor(ancestor).expect("pass");
+    }
+    let branch_node_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &branch_node_builder.build().expect("pass"),
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    datahost_la
+        .read()
+        .await
+        .store_plum_v(
+            &[metadata_plum, content_plum, branch_node_plum.clone()],
+            None,
+        )
+        .await
+        .expect("pass");
+    branch_node_plum
+}
+
+pub async fn set_local_branch_head(
+    datahost_la: &Arc<RwLock<Datahost>>,
+    branch_path: &Path,
+    value: idp_proto::branch_set_head_request::Value,
+) {
+    datahost_la
+        .read()
+        .await
+        .branch_set_head(
+            BranchSetHeadRequest {
+                branch_path: branch_path.clone(),
+                value: Some(value),
+                expected_current_branch_head_o: None,
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+}
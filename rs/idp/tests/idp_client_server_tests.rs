@@ -0,0 +1,2456 @@
+mod common;
+
+use async_lock::RwLock;
+use common::{
+    assert_has_plums, content_plum, dir_node_plum, keyed_content_plum, set_local_branch_head,
+    store_branch_node_plum,
+};
+use futures::TryStreamExt;
+use idp_core::{
+    AccessControlList, AccessPolicy, BearerTokenCredentialProvider, BranchError, BranchNodeBuilder,
+    BranchUpdate, ClientTLSConfig, CredentialProvider, Datahost, IDPClient, PathStateError,
+    TransferReport,
+};
+use idp_datahost_storage::DatahostStorageError;
+use idp_datahost_storage_in_memory::DatahostStorageInMemory;
+use idp_proto::{
+    path_state_change, BranchSetHeadRequest, ContentEncoding, ContentFormat, HasPlumsRequest,
+    IndoorDataPlumbingClient, Nonce, Path, PathState, Plum, PlumBodyChunk, PlumBodyOffset,
+    PlumBuilder, PlumHeadSeal, PlumRelationFlags, PushRequest, SealKey, SealKeyProvider,
+    UnixNanoseconds, AUTHORIZATION_METADATA_KEY, PLUM_BODY_CHUNK_SIZE,
+};
+use idp_server::{
+    Authenticator, BearerTokenAuthenticator, DIDKeyAuthenticator, IDPServer, ServerTLSConfig,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Spawns an IDPServer listening on the given port of localhost, using the given Datahost.
+fn spawn_idp_server(datahost_la: Arc<RwLock<Datahost>>, port: u16) -> tokio::task::JoinHandle<()> {
+    spawn_configured_idp_server(IDPServer::new(datahost_la), port)
+}
+
+/// Spawns the given IDPServer listening on the given port of localhost.
+fn spawn_configured_idp_server(idp_server: IDPServer, port: u16) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+        idp_server.listen_on(addr).await.expect("pass");
+    })
+}
+
+/// Connects an IDPClient to the IDPServer on the given port of localhost, retrying while the server
+/// is still spinning up.
+async fn connect_idp_client(datahost_la: Arc<RwLock<Datahost>>, port: u16) -> IDPClient {
+    connect_authenticated_idp_client(datahost_la, port, || None).await
+}
+
+/// Like connect_idp_client, but using the CredentialProvider produced by credential_provider_f (which
+/// is called for each connection attempt).
+async fn connect_authenticated_idp_client(
+    datahost_la: Arc<RwLock<Datahost>>,
+    port: u16,
+    credential_provider_f: impl Fn() -> Option<Box<dyn CredentialProvider>>,
+) -> IDPClient {
+    connect_configured_idp_client(
+        format!("http://127.0.0.1:{}", port),
+        datahost_la,
+        credential_provider_f,
+        None,
+    )
+    .await
+}
+
+/// Like connect_authenticated_idp_client, but connecting to the given URL, using the given
+/// ClientTLSConfig.
+async fn connect_configured_idp_client(
+    url: String,
+    datahost_la: Arc<RwLock<Datahost>>,
+    credential_provider_f: impl Fn() -> Option<Box<dyn CredentialProvider>>,
+    tls_config_o: Option<&ClientTLSConfig>,
+) -> IDPClient {
+    for _ in 0..50 {
+        if let Ok(idp_client) = IDPClient::connect(
+            url.clone(),
+            datahost_la.clone(),
+            credential_provider_f(),
+            tls_config_o,
+        )
+        .await
+        {
+            return idp_client;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("could not connect to IDPServer at {}", url);
+}
+
+/// A two-level tree of DirNodes with content Plums at the leaves.
+struct TestTree {
+    content_plum_vv: Vec<Vec<Plum>>,
+    subdir_plum_v: Vec<Plum>,
+    root_plum: Plum,
+}
+
+impl TestTree {
+    fn new(label: &str) -> Self {
+        let content_plum_vv = (0..3)
+            .map(|i| {
+                (0..100)
+                    .map(|j| content_plum(&format!("{} content {}-{}", label, i, j)))
+                    .collect::<Vec<Plum>>()
+            })
+            .collect::<Vec<Vec<Plum>>>();
+        let subdir_plum_v = content_plum_vv
+            .iter()
+            .map(|content_plum_v| dir_node_plum(content_plum_v))
+            .collect::<Vec<Plum>>();
+        let root_plum = dir_node_plum(&subdir_plum_v);
+        Self {
+            content_plum_vv,
+            subdir_plum_v,
+            root_plum,
+        }
+    }
+    fn root_plum_head_seal(&self) -> PlumHeadSeal {
+        PlumHeadSeal::from(&self.root_plum.plum_head)
+    }
+    /// Returns all the Plums in the tree, root first.
+    fn plum_v(&self) -> Vec<Plum> {
+        let mut plum_v = vec![self.root_plum.clone()];
+        plum_v.extend(self.subdir_plum_v.iter().cloned());
+        plum_v.extend(self.content_plum_vv.iter().flatten().cloned());
+        plum_v
+    }
+    /// Returns a copy of this tree with one leaf changed.  Only that leaf and the DirNodes above it
+    /// differ from this tree.
+    fn with_changed_leaf(&self, label: &str) -> Self {
+        let mut content_plum_vv = self.content_plum_vv.clone();
+        content_plum_vv[1][50] = content_plum(&format!("{} changed content", label));
+        let mut subdir_plum_v = self.subdir_plum_v.clone();
+        subdir_plum_v[1] = dir_node_plum(&content_plum_vv[1]);
+        let root_plum = dir_node_plum(&subdir_plum_v);
+        Self {
+            content_plum_vv,
+            subdir_plum_v,
+            root_plum,
+        }
+    }
+    /// Returns the Plums that with_changed_leaf changed, root first.
+    fn changed_plum_v(&self) -> Vec<Plum> {
+        vec![
+            self.root_plum.clone(),
+            self.subdir_plum_v[1].clone(),
+            self.content_plum_vv[1][50].clone(),
+        ]
+    }
+}
+
+fn dependency_mask() -> PlumRelationFlags {
+    PlumRelationFlags::CONTENT_DEPENDENCY | PlumRelationFlags::METADATA_DEPENDENCY
+}
+
+#[tokio::test]
+async fn test_client_server_push() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50061;
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+
+    let test_tree = TestTree::new("test_client_server_push");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(&test_tree.plum_v(), None)
+        .await
+        .expect("pass");
+
+    // The first push sends everything.
+    assert_eq!(
+        idp_client
+            .push(&test_tree.root_plum_head_seal())
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: test_tree.plum_v().len(),
+            plum_body_count: test_tree.plum_v().len(),
+        }
+    );
+    assert_has_plums(&server_datahost_la, &test_tree.plum_v(), true).await;
+
+    // Pushing again sends nothing.
+    assert!(idp_client
+        .push(&test_tree.root_plum_head_seal())
+        .await
+        .expect("pass")
+        .is_empty());
+
+    // Changing one leaf only sends that leaf and the DirNodes above it.
+    let changed_test_tree = test_tree.with_changed_leaf("test_client_server_push");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(&changed_test_tree.changed_plum_v(), None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        idp_client
+            .push(&changed_test_tree.root_plum_head_seal())
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 3,
+            plum_body_count: 3,
+        }
+    );
+    assert!(server_datahost_la
+        .read()
+        .await
+        .check_completeness(
+            &changed_test_tree.root_plum_head_seal(),
+            dependency_mask(),
+            None
+        )
+        .await
+        .expect("pass")
+        .is_complete());
+
+    // If the server has a Plum but not its dependencies (e.g. because it was stored without them),
+    // then the push doesn't stop at that Plum.  It's offered again (though its PlumBody isn't), and
+    // the missing dependencies are sent.
+    let partial_test_tree = TestTree::new("test_client_server_push partial");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(&partial_test_tree.plum_v(), None)
+        .await
+        .expect("pass");
+    server_datahost_la
+        .read()
+        .await
+        .store_plum(&partial_test_tree.root_plum, None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        idp_client
+            .push(&partial_test_tree.root_plum_head_seal())
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: partial_test_tree.plum_v().len(),
+            plum_body_count: partial_test_tree.plum_v().len() - 1,
+        }
+    );
+    assert!(server_datahost_la
+        .read()
+        .await
+        .check_completeness(
+            &partial_test_tree.root_plum_head_seal(),
+            dependency_mask(),
+            None
+        )
+        .await
+        .expect("pass")
+        .is_complete());
+
+    // A Plum that fails verification is rejected as an invalid argument, rather than as an internal
+    // error.
+    let mut tampered_plum = content_plum("tampered");
+    tampered_plum.plum_body.plum_body_content.content_byte_v = b"not the sealed content".to_vec();
+    let mut grpc_client = IndoorDataPlumbingClient::connect(format!("http://127.0.0.1:{}", PORT))
+        .await
+        .expect("pass");
+    let status = grpc_client
+        .push(futures::stream::iter(vec![PushRequest {
+            value: Some(idp_proto::push_request::Value::HereHaveAPlum(tampered_plum)),
+        }]))
+        .await
+        .expect("pass")
+        .into_inner()
+        .message()
+        .await
+        .expect_err("fail");
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_pull() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50062;
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+
+    let test_tree = TestTree::new("test_client_server_pull");
+    let changed_test_tree = test_tree.with_changed_leaf("test_client_server_pull");
+    server_datahost_la
+        .read()
+        .await
+        .store_plum_v(&test_tree.plum_v(), None)
+        .await
+        .expect("pass");
+
+    // Only the changed tree's root and subdir are stored on the server, not the changed leaf, so
+    // pulling it fails, and nothing is stored on the client.
+    server_datahost_la
+        .read()
+        .await
+        .store_plum_v(&changed_test_tree.changed_plum_v()[..2], None)
+        .await
+        .expect("pass");
+    idp_client
+        .pull(&changed_test_tree.root_plum_head_seal())
+        .await
+        .expect_err("fail");
+    assert_has_plums(&client_datahost_la, &changed_test_tree.plum_v(), false).await;
+
+    // The first pull receives everything.
+    assert_eq!(
+        idp_client
+            .pull(&test_tree.root_plum_head_seal())
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: test_tree.plum_v().len(),
+            plum_body_count: test_tree.plum_v().len(),
+        }
+    );
+    assert_has_plums(&client_datahost_la, &test_tree.plum_v(), true).await;
+
+    // Pulling again receives nothing.
+    assert!(idp_client
+        .pull(&test_tree.root_plum_head_seal())
+        .await
+        .expect("pass")
+        .is_empty());
+
+    // Once the server has the changed leaf, only it and the DirNodes above it are pulled.
+    server_datahost_la
+        .read()
+        .await
+        .store_plum_v(&changed_test_tree.changed_plum_v()[2..], None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        idp_client
+            .pull(&changed_test_tree.root_plum_head_seal())
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 3,
+            plum_body_count: 3,
+        }
+    );
+    assert!(client_datahost_la
+        .read()
+        .await
+        .check_completeness(
+            &changed_test_tree.root_plum_head_seal(),
+            dependency_mask(),
+            None
+        )
+        .await
+        .expect("pass")
+        .is_complete());
+
+    server_handle.abort();
+}
+
+/// Returns Plums which all have the same PlumBody, but distinct PlumHeads.
+fn plum_v_with_shared_plum_body(content: &str, count: usize) -> Vec<Plum> {
+    let content = format!("{}, {}", content, Uuid::new_v4());
+    (0..count)
+        .map(|_| {
+            PlumBuilder::new()
+                .with_plum_head_nonce(Nonce::generate())
+                .with_plum_relations_and_plum_body_content_from(
+                    &content,
+                    Some(&ContentFormat::charset_us_ascii()),
+                    ContentEncoding::none(),
+                )
+                .expect("pass")
+                .build()
+                .expect("pass")
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_client_server_shared_plum_bodies_and_shallow_pull() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50063;
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+
+    // 10 Plums sharing one PlumBody, in a DirNode, plus another DirNode whose 10 Plums share a PlumBody
+    // that the server will already have.
+    let shared_plum_v = plum_v_with_shared_plum_body("test_client_server_shared_plum_bodies", 10);
+    let dir_plum = dir_node_plum(&shared_plum_v);
+    let mut plum_v = vec![dir_plum.clone()];
+    plum_v.extend(shared_plum_v.iter().cloned());
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(&plum_v, None)
+        .await
+        .expect("pass");
+
+    let other_shared_plum_v =
+        plum_v_with_shared_plum_body("test_client_server_shared_plum_bodies other", 11);
+    server_datahost_la
+        .read()
+        .await
+        .store_plum(&other_shared_plum_v[10], None)
+        .await
+        .expect("pass");
+    let other_dir_plum = dir_node_plum(&other_shared_plum_v[..10]);
+    let mut other_plum_v = vec![other_dir_plum.clone()];
+    other_plum_v.extend(other_shared_plum_v[..10].iter().cloned());
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(&other_plum_v, None)
+        .await
+        .expect("pass");
+
+    // Each PlumBody is only pushed once, and not at all if the server already has it.
+    assert_eq!(
+        idp_client
+            .push(&PlumHeadSeal::from(&dir_plum.plum_head))
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 11,
+            plum_body_count: 2,
+        }
+    );
+    assert_has_plums(&server_datahost_la, &plum_v, true).await;
+    assert_eq!(
+        idp_client
+            .push(&PlumHeadSeal::from(&other_dir_plum.plum_head))
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 11,
+            plum_body_count: 1,
+        }
+    );
+    assert_has_plums(&server_datahost_la, &other_plum_v, true).await;
+
+    // Now pull into a fresh client Datahost.  A shallow pull doesn't transfer any PlumBody-s, so the
+    // Plums aren't complete, but their relations can be traversed.
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+    let dir_plum_head_seal = PlumHeadSeal::from(&dir_plum.plum_head);
+    assert_eq!(
+        idp_client
+            .pull_shallow(&dir_plum_head_seal)
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 11,
+            plum_body_count: 0,
+        }
+    );
+    assert_has_plums(&client_datahost_la, &plum_v, false).await;
+    for plum in plum_v.iter() {
+        assert!(client_datahost_la
+            .read()
+            .await
+            .has_plum_head(&PlumHeadSeal::from(&plum.plum_head), None)
+            .await
+            .expect("pass"));
+    }
+    assert_eq!(
+        client_datahost_la
+            .read()
+            .await
+            .accumulated_relations_recursive(&dir_plum_head_seal, dependency_mask(), None)
+            .await
+            .expect("pass")
+            .len(),
+        10
+    );
+    assert!(idp_client
+        .pull_shallow(&dir_plum_head_seal)
+        .await
+        .expect("pass")
+        .is_empty());
+
+    // A full pull afterward only transfers the PlumBody-s, each once.
+    assert_eq!(
+        idp_client.pull(&dir_plum_head_seal).await.expect("pass"),
+        TransferReport {
+            plum_count: 0,
+            plum_body_count: 2,
+        }
+    );
+    assert_has_plums(&client_datahost_la, &plum_v, true).await;
+
+    // A full pull from scratch transfers each shared PlumBody once.
+    assert_eq!(
+        idp_client
+            .pull(&PlumHeadSeal::from(&other_dir_plum.plum_head))
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 11,
+            plum_body_count: 2,
+        }
+    );
+    assert_has_plums(&client_datahost_la, &other_plum_v, true).await;
+
+    server_handle.abort();
+}
+
+/// Returns a Plum whose PlumBody is too large to fit in a single gRPC message (tonic's default
+/// maximum is 4 MiB), and so must be transferred in chunks.
+fn large_content_plum(content: &str) -> Plum {
+    content_plum(&format!(
+        "{} {}",
+        content,
+        "x".repeat(5 * PLUM_BODY_CHUNK_SIZE + 12345)
+    ))
+}
+
+/// Offers the given Plum's PlumBody and then pushes chunk_count of its PlumBodyChunk-s, starting at
+/// the given offset, directly (i.e. not using IDPClient) over the given connection, and then ends the
+/// push, as if it were interrupted.  If corrupt_first_chunk is true, the first PlumBodyChunk sent is
+/// corrupted.  Returns the server's response to ShouldISendThisPlumBody, and the error status if the
+/// server rejected a PlumBodyChunk.
+async fn push_plum_body_chunks_and_stop(
+    grpc_client: &mut IndoorDataPlumbingClient<tonic::transport::Channel>,
+    plum: &Plum,
+    offset: u64,
+    chunk_count: usize,
+    corrupt_first_chunk: bool,
+) -> (idp_proto::push_response::Value, Result<(), tonic::Status>) {
+    push_authorized_plum_body_chunks_and_stop(
+        grpc_client,
+        None,
+        plum,
+        offset,
+        chunk_count,
+        corrupt_first_chunk,
+    )
+    .await
+}
+
+/// Like push_plum_body_chunks_and_stop, but sending the given authorization value (if any) with the
+/// push.
+async fn push_authorized_plum_body_chunks_and_stop(
+    grpc_client: &mut IndoorDataPlumbingClient<tonic::transport::Channel>,
+    authorization_o: Option<&str>,
+    plum: &Plum,
+    offset: u64,
+    chunk_count: usize,
+    corrupt_first_chunk: bool,
+) -> (idp_proto::push_response::Value, Result<(), tonic::Status>) {
+    let plum_body_seal = plum.plum_head.plum_body_seal.clone();
+    let mut push_request_v = vec![PushRequest {
+        value: Some(idp_proto::push_request::Value::ShouldISendThisPlumBody(
+            plum_body_seal.clone(),
+        )),
+    }];
+    let mut plum_body_chunk_v = PlumBodyChunk::chunk_i_from(
+        &plum_body_seal,
+        &plum.plum_body,
+        offset,
+        PLUM_BODY_CHUNK_SIZE,
+    )
+    .expect("pass")
+    .take(chunk_count)
+    .collect::<Vec<PlumBodyChunk>>();
+    assert_eq!(plum_body_chunk_v.len(), chunk_count);
+    if corrupt_first_chunk {
+        plum_body_chunk_v[0].data[100] ^= 0xFF;
+    }
+    for plum_body_chunk in plum_body_chunk_v {
+        push_request_v.push(PushRequest {
+            value: Some(idp_proto::push_request::Value::HereHaveAPlumBodyChunk(
+                plum_body_chunk,
+            )),
+        });
+    }
+    let mut push_request = tonic::Request::new(futures::stream::iter(push_request_v));
+    if let Some(authorization) = authorization_o {
+        push_request.metadata_mut().insert(
+            AUTHORIZATION_METADATA_KEY,
+            authorization.parse().expect("pass"),
+        );
+    }
+    let mut push_response_stream = grpc_client
+        .push(push_request)
+        .await
+        .expect("pass")
+        .into_inner();
+
+    let value = push_response_stream
+        .message()
+        .await
+        .expect("pass")
+        .expect("pass")
+        .value
+        .expect("pass");
+    for _ in 0..chunk_count {
+        match push_response_stream.message().await {
+            Ok(push_response_o) => {
+                assert!(matches!(
+                    push_response_o.expect("pass").value,
+                    Some(idp_proto::push_response::Value::Ok(_))
+                ));
+            }
+            Err(status) => {
+                return (value, Err(status));
+            }
+        }
+    }
+    assert!(push_response_stream
+        .message()
+        .await
+        .expect("pass")
+        .is_none());
+    (value, Ok(()))
+}
+
+async fn assert_has_plum_body(datahost_la: &Arc<RwLock<Datahost>>, plum: &Plum, expected: bool) {
+    assert_eq!(
+        datahost_la
+            .read()
+            .await
+            .load_option_plum_body(&plum.plum_head.plum_body_seal, None)
+            .await
+            .expect("pass"),
+        if expected {
+            Some(plum.plum_body.clone())
+        } else {
+            None
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_client_server_chunked_plum_bodies() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50064;
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+
+    let large_plum_v = (0..3)
+        .map(|i| large_content_plum(&format!("test_client_server_chunked_plum_bodies {}", i)))
+        .collect::<Vec<Plum>>();
+    assert!(large_plum_v
+        .iter()
+        .all(|large_plum| large_plum.plum_body.requires_chunking()));
+    let dir_plum = dir_node_plum(&large_plum_v[..1]);
+    let dir_plum_head_seal = PlumHeadSeal::from(&dir_plum.plum_head);
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(
+            &[
+                dir_plum.clone(),
+                large_plum_v[0].clone(),
+                large_plum_v[1].clone(),
+                large_plum_v[2].clone(),
+            ],
+            None,
+        )
+        .await
+        .expect("pass");
+
+    // Push and then pull a large PlumBody.
+    assert_eq!(
+        idp_client.push(&dir_plum_head_seal).await.expect("pass"),
+        TransferReport {
+            plum_count: 2,
+            plum_body_count: 2,
+        }
+    );
+    assert_has_plum_body(&server_datahost_la, &large_plum_v[0], true).await;
+    {
+        let client_datahost_la =
+            Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+        let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+        assert_eq!(
+            idp_client.pull(&dir_plum_head_seal).await.expect("pass"),
+            TransferReport {
+                plum_count: 2,
+                plum_body_count: 2,
+            }
+        );
+        assert_has_plums(
+            &client_datahost_la,
+            &[dir_plum.clone(), large_plum_v[0].clone()],
+            true,
+        )
+        .await;
+        assert_has_plum_body(&client_datahost_la, &large_plum_v[0], true).await;
+    }
+
+    // An interrupted push of a PlumBody is resumed where it left off, even over a new connection.
+    let mut grpc_client = IndoorDataPlumbingClient::connect(format!("http://127.0.0.1:{}", PORT))
+        .await
+        .expect("pass");
+    let large_plum_body_seal = large_plum_v[1].plum_head.plum_body_seal.clone();
+    let resume_offset = 2 * PLUM_BODY_CHUNK_SIZE as u64;
+    let (value, result) =
+        push_plum_body_chunks_and_stop(&mut grpc_client, &large_plum_v[1], 0, 2, false).await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBody(large_plum_body_seal.clone())
+    );
+    result.expect("pass");
+    assert_has_plum_body(&server_datahost_la, &large_plum_v[1], false).await;
+    drop(grpc_client);
+    let mut grpc_client = IndoorDataPlumbingClient::connect(format!("http://127.0.0.1:{}", PORT))
+        .await
+        .expect("pass");
+    let (value, result) =
+        push_plum_body_chunks_and_stop(&mut grpc_client, &large_plum_v[1], resume_offset, 0, false)
+            .await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBodyStartingAt(PlumBodyOffset {
+            plum_body_seal: large_plum_body_seal.clone(),
+            offset: resume_offset,
+        })
+    );
+    result.expect("pass");
+    let remaining_chunk_count = PlumBodyChunk::chunk_i_from(
+        &large_plum_body_seal,
+        &large_plum_v[1].plum_body,
+        resume_offset,
+        PLUM_BODY_CHUNK_SIZE,
+    )
+    .expect("pass")
+    .count();
+    let (value, result) = push_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        &large_plum_v[1],
+        resume_offset,
+        remaining_chunk_count,
+        false,
+    )
+    .await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBodyStartingAt(PlumBodyOffset {
+            plum_body_seal: large_plum_body_seal,
+            offset: resume_offset,
+        })
+    );
+    result.expect("pass");
+    assert_has_plum_body(&server_datahost_la, &large_plum_v[1], true).await;
+    // Only the PlumHead, PlumMetadata, and PlumRelations remain to be pushed.
+    assert_eq!(
+        idp_client
+            .push(&PlumHeadSeal::from(&large_plum_v[1].plum_head))
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 1,
+            plum_body_count: 0,
+        }
+    );
+    assert_has_plums(&server_datahost_la, &large_plum_v[1..2], true).await;
+
+    // The reassembled PlumBody is checked against its PlumBodySeal, so resuming a push whose earlier
+    // chunks were corrupted fails, after which the push starts over.
+    let large_plum_body_seal = large_plum_v[2].plum_head.plum_body_seal.clone();
+    let (value, result) =
+        push_plum_body_chunks_and_stop(&mut grpc_client, &large_plum_v[2], 0, 1, true).await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBody(large_plum_body_seal.clone())
+    );
+    result.expect("pass");
+    let resume_offset = PLUM_BODY_CHUNK_SIZE as u64;
+    let remaining_chunk_count = PlumBodyChunk::chunk_i_from(
+        &large_plum_body_seal,
+        &large_plum_v[2].plum_body,
+        resume_offset,
+        PLUM_BODY_CHUNK_SIZE,
+    )
+    .expect("pass")
+    .count();
+    let (value, result) = push_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        &large_plum_v[2],
+        resume_offset,
+        remaining_chunk_count,
+        false,
+    )
+    .await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBodyStartingAt(PlumBodyOffset {
+            plum_body_seal: large_plum_body_seal.clone(),
+            offset: resume_offset,
+        })
+    );
+    assert_eq!(
+        result.expect_err("fail").code(),
+        tonic::Code::InvalidArgument
+    );
+    assert_has_plum_body(&server_datahost_la, &large_plum_v[2], false).await;
+    let (value, result) =
+        push_plum_body_chunks_and_stop(&mut grpc_client, &large_plum_v[2], 0, 0, false).await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBody(large_plum_body_seal)
+    );
+    result.expect("pass");
+    assert_eq!(
+        idp_client
+            .push(&PlumHeadSeal::from(&large_plum_v[2].plum_head))
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 1,
+            plum_body_count: 1,
+        }
+    );
+    assert_has_plum_body(&server_datahost_la, &large_plum_v[2], true).await;
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_partial_plum_body_limits() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50076;
+
+    let alice_token = format!("alice-{}", Uuid::new_v4());
+    let bob_token = format!("bob-{}", Uuid::new_v4());
+    let alice_authorization = format!("Bearer {}", alice_token);
+    let bob_authorization = format!("Bearer {}", bob_token);
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let partial_plum_body_expiry = std::time::Duration::from_secs(2);
+    let server_handle = spawn_configured_idp_server(
+        IDPServer::new(server_datahost_la.clone())
+            .with_authenticator(Arc::new(
+                BearerTokenAuthenticator::new(maplit::hashmap! {
+                    "alice".to_string() => alice_token,
+                    "bob".to_string() => bob_token,
+                })
+                .expect("pass"),
+            ))
+            .with_max_plum_body_length(8 * PLUM_BODY_CHUNK_SIZE as u64)
+            .with_partial_plum_body_limits(2, 8 * PLUM_BODY_CHUNK_SIZE as u64)
+            .with_partial_plum_body_expiry(partial_plum_body_expiry),
+        PORT,
+    );
+    let url = format!("http://127.0.0.1:{}", PORT);
+    let mut grpc_client = loop {
+        match IndoorDataPlumbingClient::connect(url.clone()).await {
+            Ok(grpc_client) => {
+                break grpc_client;
+            }
+            Err(_) => {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        }
+    };
+
+    let large_plum_v = (0..2)
+        .map(|i| large_content_plum(&format!("test_client_server_partial_plum_body_limits {}", i)))
+        .collect::<Vec<Plum>>();
+    let small_plum = content_plum(&format!(
+        "test_client_server_partial_plum_body_limits {}",
+        "x".repeat(PLUM_BODY_CHUNK_SIZE + 12345)
+    ));
+    let too_large_plum = content_plum(&format!(
+        "test_client_server_partial_plum_body_limits {}",
+        "x".repeat(8 * PLUM_BODY_CHUNK_SIZE)
+    ));
+
+    // A PlumBody longer than the maximum is rejected outright.
+    let (_, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&alice_authorization),
+        &too_large_plum,
+        0,
+        1,
+        false,
+    )
+    .await;
+    assert_eq!(
+        result.expect_err("fail").code(),
+        tonic::Code::InvalidArgument
+    );
+
+    // Partial PlumBody-s are kept per client, so bob can't resume alice's push.
+    let (_, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&alice_authorization),
+        &large_plum_v[0],
+        0,
+        2,
+        false,
+    )
+    .await;
+    result.expect("pass");
+    let (value, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&bob_authorization),
+        &large_plum_v[0],
+        0,
+        0,
+        false,
+    )
+    .await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBody(
+            large_plum_v[0].plum_head.plum_body_seal.clone()
+        )
+    );
+    result.expect("pass");
+
+    // Starting another push that would exceed the total length of partial PlumBody-s is rejected.
+    let (_, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&bob_authorization),
+        &large_plum_v[1],
+        0,
+        1,
+        false,
+    )
+    .await;
+    assert_eq!(
+        result.expect_err("fail").code(),
+        tonic::Code::ResourceExhausted
+    );
+    // As is starting another push that would exceed the number of partial PlumBody-s.
+    let (_, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&bob_authorization),
+        &small_plum,
+        0,
+        1,
+        false,
+    )
+    .await;
+    result.expect("pass");
+    let (_, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&alice_authorization),
+        &small_plum,
+        0,
+        1,
+        false,
+    )
+    .await;
+    assert_eq!(
+        result.expect_err("fail").code(),
+        tonic::Code::ResourceExhausted
+    );
+
+    // Once they expire, partial PlumBody-s are dropped, so their pushes start over, and they no
+    // longer count against the limits.
+    tokio::time::sleep(partial_plum_body_expiry).await;
+    let (value, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&alice_authorization),
+        &large_plum_v[0],
+        0,
+        0,
+        false,
+    )
+    .await;
+    assert_eq!(
+        value,
+        idp_proto::push_response::Value::SendThisPlumBody(
+            large_plum_v[0].plum_head.plum_body_seal.clone()
+        )
+    );
+    result.expect("pass");
+    let (_, result) = push_authorized_plum_body_chunks_and_stop(
+        &mut grpc_client,
+        Some(&bob_authorization),
+        &large_plum_v[1],
+        0,
+        1,
+        false,
+    )
+    .await;
+    result.expect("pass");
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_path_state_queries() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50065;
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+
+    let plum_v = (0..3)
+        .map(|i| content_plum(&format!("test_client_server_path_state_queries {}", i)))
+        .collect::<Vec<Plum>>();
+    server_datahost_la
+        .read()
+        .await
+        .store_plum_v(&plum_v[..2], None)
+        .await
+        .expect("pass");
+    let path_state_v = (0..5)
+        .map(|i| PathState {
+            path: Path::from(format!("dashboards/{}", i)),
+            current_state_plum_head_seal: PlumHeadSeal::from(&plum_v[i % 2].plum_head),
+        })
+        .collect::<Vec<_>>();
+    let other_path_state = PathState {
+        path: Path::from("other/0".to_string()),
+        current_state_plum_head_seal: PlumHeadSeal::from(&plum_v[0].plum_head),
+    };
+    for path_state in path_state_v
+        .iter()
+        .chain(std::iter::once(&other_path_state))
+    {
+        server_datahost_la
+            .read()
+            .await
+            .insert_path_state(path_state, None)
+            .await
+            .expect("pass");
+    }
+
+    // PathStateGet
+    assert_eq!(
+        idp_client
+            .path_state_get(&path_state_v[3].path)
+            .await
+            .expect("pass"),
+        path_state_v[3]
+    );
+    let e = idp_client
+        .path_state_get(&Path::from("dashboards/nonexistent".to_string()))
+        .await
+        .expect_err("fail");
+    assert_eq!(
+        e.downcast_ref::<tonic::Status>().expect("pass").code(),
+        tonic::Code::NotFound
+    );
+
+    // PathStateList, a page at a time.
+    let mut listed_path_state_v = Vec::new();
+    let mut page_count = 0;
+    let mut page_cursor_o = None;
+    loop {
+        let path_state_list_response = idp_client
+            .path_state_list(Some("dashboards/"), 2, page_cursor_o)
+            .await
+            .expect("pass");
+        assert!(path_state_list_response.path_state_v.len() <= 2);
+        listed_path_state_v.extend(path_state_list_response.path_state_v);
+        page_count += 1;
+        page_cursor_o = path_state_list_response.next_page_cursor_o;
+        if page_cursor_o.is_none() {
+            break;
+        }
+    }
+    assert_eq!(listed_path_state_v, path_state_v);
+    assert_eq!(page_count, 3);
+    // Without a prefix, everything is listed.
+    assert_eq!(
+        idp_client
+            .path_state_list(None, 100, None)
+            .await
+            .expect("pass")
+            .path_state_v
+            .len(),
+        6
+    );
+    idp_client
+        .path_state_list(None, 0, None)
+        .await
+        .expect_err("fail");
+
+    // HasPlums
+    assert_eq!(
+        idp_client
+            .has_plums(
+                &plum_v
+                    .iter()
+                    .map(|plum| PlumHeadSeal::from(&plum.plum_head))
+                    .collect::<Vec<_>>()
+            )
+            .await
+            .expect("pass"),
+        vec![true, true, false]
+    );
+    assert!(idp_client.has_plums(&[]).await.expect("pass").is_empty());
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_push_and_fetch_branch() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50066;
+
+    let a_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let b_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut a_idp_client = connect_idp_client(a_datahost_la.clone(), PORT).await;
+    let mut b_idp_client = connect_idp_client(b_datahost_la.clone(), PORT).await;
+
+    let branch_path = Path::from("main".to_string());
+    let remote_tracking_branch_path = IDPClient::remote_tracking_branch_path(&branch_path);
+    assert_eq!(remote_tracking_branch_path.value, "origin/main");
+    let server_branch_head = || async {
+        server_datahost_la
+            .read()
+            .await
+            .branch_get_head(&branch_path, None)
+            .await
+            .expect("pass")
+    };
+
+    // Pushing a branch that doesn't exist on the server creates it.
+    let b0 = store_branch_node_plum(&a_datahost_la, None, "b0").await;
+    a_datahost_la
+        .read()
+        .await
+        .branch_create(
+            &PathState {
+                path: branch_path.clone(),
+                current_state_plum_head_seal: PlumHeadSeal::from(&b0.plum_head),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(
+        a_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::Created
+    );
+    assert_eq!(
+        server_branch_head().await,
+        PlumHeadSeal::from(&b0.plum_head)
+    );
+    // Pushing records the pushed branch head as the remote-tracking PathState, as fetching does.
+    let a_remote_tracking_branch_head = || async {
+        a_datahost_la
+            .read()
+            .await
+            .load_path_state(&remote_tracking_branch_path, None)
+            .await
+            .expect("pass")
+            .current_state_plum_head_seal
+    };
+    assert_eq!(
+        a_remote_tracking_branch_head().await,
+        PlumHeadSeal::from(&b0.plum_head)
+    );
+
+    // Fast-forward
+    let b1 = store_branch_node_plum(&a_datahost_la, Some(&b0), "b1").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &b1.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        a_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::FastForwarded
+    );
+    assert_eq!(
+        server_branch_head().await,
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+    assert_eq!(
+        a_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::Unchanged
+    );
+
+    // Fetching records the server's branch head as a remote-tracking PathState, and pulls its history.
+    assert_eq!(
+        b_idp_client.fetch_branch(&branch_path).await.expect("pass"),
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+    assert_eq!(
+        b_datahost_la
+            .read()
+            .await
+            .load_path_state(&remote_tracking_branch_path, None)
+            .await
+            .expect("pass")
+            .current_state_plum_head_seal,
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+    assert_has_plums(&b_datahost_la, &[b0.clone(), b1.clone()], true).await;
+    assert!(!b_datahost_la
+        .read()
+        .await
+        .has_path_state(&branch_path, None)
+        .await
+        .expect("pass"));
+
+    let b2 = store_branch_node_plum(&a_datahost_la, Some(&b1), "b2").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &b2.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        a_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::FastForwarded
+    );
+    assert_eq!(
+        b_idp_client.fetch_branch(&branch_path).await.expect("pass"),
+        PlumHeadSeal::from(&b2.plum_head)
+    );
+    assert_eq!(
+        b_datahost_la
+            .read()
+            .await
+            .load_path_state(&remote_tracking_branch_path, None)
+            .await
+            .expect("pass")
+            .current_state_plum_head_seal,
+        PlumHeadSeal::from(&b2.plum_head)
+    );
+
+    // A local branch that diverged from the server's forks its history.
+    let b3 = store_branch_node_plum(&b_datahost_la, Some(&b1), "b3").await;
+    b_datahost_la
+        .read()
+        .await
+        .branch_create(
+            &PathState {
+                path: branch_path.clone(),
+                current_state_plum_head_seal: PlumHeadSeal::from(&b3.plum_head),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(
+        b_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::ForkedHistory
+    );
+    assert_eq!(
+        server_branch_head().await,
+        PlumHeadSeal::from(&b3.plum_head)
+    );
+
+    // Rewind.  The server's branch head (b3) isn't present locally, so it's pulled in order to find the
+    // closest common ancestor.
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchRewindTo(PlumHeadSeal::from(
+            &b1.plum_head,
+        )),
+    )
+    .await;
+    assert_has_plums(&a_datahost_la, std::slice::from_ref(&b3), false).await;
+    assert_eq!(
+        a_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::Rewound
+    );
+    assert_eq!(
+        server_branch_head().await,
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+    assert_has_plums(&a_datahost_la, &[b3], true).await;
+
+    // Total rewrite
+    let c0 = store_branch_node_plum(&a_datahost_la, None, "c0").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchTotallyRewriteTo(PlumHeadSeal::from(
+            &c0.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        a_idp_client.push_branch(&branch_path).await.expect("pass"),
+        BranchUpdate::TotallyRewritten
+    );
+    assert_eq!(
+        server_branch_head().await,
+        PlumHeadSeal::from(&c0.plum_head)
+    );
+    assert_eq!(
+        a_remote_tracking_branch_head().await,
+        PlumHeadSeal::from(&c0.plum_head)
+    );
+
+    // Fetching a branch that doesn't exist on the server fails.
+    b_idp_client
+        .fetch_branch(&Path::from("nonexistent".to_string()))
+        .await
+        .expect_err("fail");
+
+    server_handle.abort();
+}
+
+/// Asserts that the given error came from a tonic::Status having the given code.
+fn assert_status_code(error: &anyhow::Error, code: tonic::Code) {
+    assert_eq!(
+        error.downcast_ref::<tonic::Status>().expect("pass").code(),
+        code
+    );
+}
+
+#[tokio::test]
+async fn test_client_server_bearer_token_authentication() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50067;
+
+    let alice_token = format!("alice-{}", Uuid::new_v4());
+    let bob_token = format!("bob-{}", Uuid::new_v4());
+
+    // Tokens must be distinct.
+    assert!(BearerTokenAuthenticator::new(maplit::hashmap! {
+        "alice".to_string() => alice_token.clone(),
+        "bob".to_string() => alice_token.clone(),
+    })
+    .is_err());
+
+    let config_path = std::env::temp_dir().join(format!("bearer-tokens-{}.json", Uuid::new_v4()));
+    std::fs::write(
+        &config_path,
+        format!(
+            r#"{{ "alice": "{}", "bob": "{}" }}"#,
+            alice_token, bob_token
+        ),
+    )
+    .expect("pass");
+    let bearer_token_authenticator =
+        BearerTokenAuthenticator::load_from_config_file(&config_path).expect("pass");
+    std::fs::remove_file(&config_path).expect("pass");
+
+    assert_eq!(
+        bearer_token_authenticator
+            .authenticate(Some(&format!("Bearer {}", bob_token)))
+            .expect("pass")
+            .value,
+        "bob"
+    );
+    bearer_token_authenticator
+        .authenticate(Some(&format!("Basic {}", bob_token)))
+        .expect_err("fail");
+    bearer_token_authenticator
+        .authenticate(Some("Bearer nonsense"))
+        .expect_err("fail");
+    bearer_token_authenticator
+        .authenticate(None)
+        .expect_err("fail");
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_configured_idp_server(
+        IDPServer::new(server_datahost_la.clone())
+            .with_authenticator(Arc::new(bearer_token_authenticator)),
+        PORT,
+    );
+    let url = format!("http://127.0.0.1:{}", PORT);
+
+    let mut idp_client = connect_authenticated_idp_client(client_datahost_la.clone(), PORT, || {
+        Some(Box::new(BearerTokenCredentialProvider::new(
+            alice_token.clone(),
+        )))
+    })
+    .await;
+    let plum = content_plum("authenticated push");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum(&plum, None)
+        .await
+        .expect("pass");
+    idp_client
+        .push(&PlumHeadSeal::from(&plum.plum_head))
+        .await
+        .expect("pass");
+    assert_has_plums(&server_datahost_la, std::slice::from_ref(&plum), true).await;
+
+    // An invalid token is rejected when connecting.
+    let error = IDPClient::connect(
+        url.clone(),
+        client_datahost_la.clone(),
+        Some(Box::new(BearerTokenCredentialProvider::new(
+            "nonsense".to_string(),
+        ))),
+        None,
+    )
+    .await
+    .err()
+    .expect("fail");
+    assert_status_code(&error, tonic::Code::Unauthenticated);
+
+    // Without credentials, requests are rejected.
+    let mut unauthenticated_idp_client =
+        IDPClient::connect(url, client_datahost_la.clone(), None, None)
+            .await
+            .expect("pass");
+    let error = unauthenticated_idp_client
+        .has_plums(&[PlumHeadSeal::from(&plum.plum_head)])
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::Unauthenticated);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_did_key_authentication() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50068;
+
+    let priv_jwk = idp_sig::KeyType::Secp256k1
+        .generate_priv_jwk()
+        .expect("pass");
+    let did = idp_sig::did_key_from_jwk(&priv_jwk.to_public())
+        .expect("pass")
+        .to_string();
+    let other_priv_jwk = idp_sig::KeyType::Secp256k1
+        .generate_priv_jwk()
+        .expect("pass");
+    let other_did = idp_sig::did_key_from_jwk(&other_priv_jwk.to_public())
+        .expect("pass")
+        .to_string();
+    let url = format!("http://127.0.0.1:{}", PORT);
+
+    // Exercise the challenge/response directly.
+    let did_key_authenticator =
+        DIDKeyAuthenticator::new(url.clone(), std::time::Duration::from_secs(3600));
+    let challenge = did_key_authenticator
+        .authenticate(Some("DIDKey"))
+        .expect_err("fail")
+        .challenge_o
+        .expect("pass");
+    let authorization = idp_sig::did_key_authorization(&priv_jwk, &url, &challenge)
+        .await
+        .expect("pass");
+    assert_eq!(
+        did_key_authenticator
+            .authenticate(Some(&authorization))
+            .expect("pass")
+            .value,
+        did
+    );
+    // The answer is accepted for the rest of the session.
+    assert_eq!(
+        did_key_authenticator
+            .authenticate(Some(&authorization))
+            .expect("pass")
+            .value,
+        did
+    );
+    // Answering a challenge only proves possession of the signer's own key, so answering the same
+    // challenge with another key authenticates as that key's DID.
+    let other_authorization = idp_sig::did_key_authorization(&other_priv_jwk, &url, &challenge)
+        .await
+        .expect("pass");
+    assert_eq!(
+        did_key_authenticator
+            .authenticate(Some(&other_authorization))
+            .expect("pass")
+            .value,
+        other_did
+    );
+    // The answer must be signed for this server's audience, so that it can't have been relayed from
+    // another server.
+    let misdirected_authorization =
+        idp_sig::did_key_authorization(&priv_jwk, "http://elsewhere.example", &challenge)
+            .await
+            .expect("pass");
+    did_key_authenticator
+        .authenticate(Some(&misdirected_authorization))
+        .expect_err("fail");
+    // Challenges must have been issued by the server, including by another instance (whose secret
+    // differs).
+    let unissued_authorization =
+        idp_sig::did_key_authorization(&priv_jwk, &url, "0123456789ABCDEF")
+            .await
+            .expect("pass");
+    assert!(did_key_authenticator
+        .authenticate(Some(&unissued_authorization))
+        .expect_err("fail")
+        .challenge_o
+        .is_some());
+    let forged_challenge =
+        DIDKeyAuthenticator::new(url.clone(), std::time::Duration::from_secs(3600))
+            .authenticate(None)
+            .expect_err("fail")
+            .challenge_o
+            .expect("pass");
+    let forged_authorization = idp_sig::did_key_authorization(&priv_jwk, &url, &forged_challenge)
+        .await
+        .expect("pass");
+    did_key_authenticator
+        .authenticate(Some(&forged_authorization))
+        .expect_err("fail");
+    // The JWS must be over the challenge.
+    let challenge_2 = did_key_authenticator
+        .authenticate(None)
+        .expect_err("fail")
+        .challenge_o
+        .expect("pass");
+    let jws = authorization.split(' ').nth(2).expect("pass");
+    did_key_authenticator
+        .authenticate(Some(&format!("DIDKey {} {}", challenge_2, jws)))
+        .expect_err("fail");
+    // Answers expire along with their session.
+    let expiring_did_key_authenticator =
+        DIDKeyAuthenticator::new(url.clone(), std::time::Duration::ZERO);
+    let challenge = expiring_did_key_authenticator
+        .authenticate(Some("DIDKey"))
+        .expect_err("fail")
+        .challenge_o
+        .expect("pass");
+    let authorization = idp_sig::did_key_authorization(&priv_jwk, &url, &challenge)
+        .await
+        .expect("pass");
+    expiring_did_key_authenticator
+        .authenticate(Some(&authorization))
+        .expect_err("fail");
+
+    // Now authenticate an IDPClient.
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_configured_idp_server(
+        IDPServer::new(server_datahost_la.clone()).with_authenticator(Arc::new(
+            DIDKeyAuthenticator::new(url.clone(), std::time::Duration::from_secs(3600)),
+        )),
+        PORT,
+    );
+
+    let mut idp_client = connect_authenticated_idp_client(client_datahost_la.clone(), PORT, || {
+        Some(Box::new(idp_sig::DIDKeyCredentialProvider::new(
+            priv_jwk.clone(),
+            url.clone(),
+        )))
+    })
+    .await;
+    let plum = content_plum("did:key-authenticated push");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum(&plum, None)
+        .await
+        .expect("pass");
+    idp_client
+        .push(&PlumHeadSeal::from(&plum.plum_head))
+        .await
+        .expect("pass");
+    assert_has_plums(&server_datahost_la, std::slice::from_ref(&plum), true).await;
+    // Re-authenticating starts a new session.
+    idp_client.authenticate().await.expect("pass");
+    assert_eq!(
+        idp_client
+            .has_plums(&[PlumHeadSeal::from(&plum.plum_head)])
+            .await
+            .expect("pass"),
+        vec![true]
+    );
+
+    // The wrong authorization scheme is rejected when connecting.
+    let error = IDPClient::connect(
+        url.clone(),
+        client_datahost_la.clone(),
+        Some(Box::new(BearerTokenCredentialProvider::new(
+            "nonsense".to_string(),
+        ))),
+        None,
+    )
+    .await
+    .err()
+    .expect("fail");
+    assert_status_code(&error, tonic::Code::Unauthenticated);
+
+    // Without credentials, requests are rejected, and a challenge is issued.
+    let mut grpc_client = IndoorDataPlumbingClient::connect(url).await.expect("pass");
+    let status = grpc_client
+        .has_plums(HasPlumsRequest {
+            plum_head_seal_v: vec![PlumHeadSeal::from(&plum.plum_head)],
+        })
+        .await
+        .expect_err("fail");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    assert!(status
+        .metadata()
+        .get(idp_proto::AUTHORIZATION_CHALLENGE_METADATA_KEY)
+        .is_some());
+
+    server_handle.abort();
+}
+
+/// Builds a BranchNode Plum whose content is the given AccessPolicy, and stores it, along with the
+/// Plums it refers to, in the given Datahost.
+async fn store_access_policy_branch_node_plum(
+    datahost_la: &Arc<RwLock<Datahost>>,
+    ancestor_o: Option<&Plum>,
+    access_policy: &AccessPolicy,
+) -> Plum {
+    let metadata_plum = content_plum("access policy metadata");
+    let access_policy_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            access_policy,
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let mut branch_node_builder = BranchNodeBuilder::new()
+        .with_metadata(PlumHeadSeal::from(&metadata_plum.plum_head))
+        .with_content(PlumHeadSeal::from(&access_policy_plum.plum_head));
+    if let Some(ancestor) = ancestor_o {
+        branch_node_builder = branch_node_builder.with_ancestor(ancestor).expect("pass");
+    }
+    let branch_node_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &branch_node_builder.build().expect("pass"),
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    datahost_la
+        .read()
+        .await
+        .store_plum_v(
+            &[metadata_plum, access_policy_plum, branch_node_plum.clone()],
+            None,
+        )
+        .await
+        .expect("pass");
+    branch_node_plum
+}
+
+async fn create_local_branch(datahost_la: &Arc<RwLock<Datahost>>, branch_path: &Path, plum: &Plum) {
+    datahost_la
+        .read()
+        .await
+        .branch_create(
+            &PathState {
+                path: branch_path.clone(),
+                current_state_plum_head_seal: PlumHeadSeal::from(&plum.plum_head),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+}
+
+/// Returns the sorted paths of the PathStates listed by the server.
+async fn listed_paths(idp_client: &mut IDPClient) -> Vec<String> {
+    let mut path_v: Vec<String> = idp_client
+        .path_state_list(None, 16, None)
+        .await
+        .expect("pass")
+        .path_state_v
+        .into_iter()
+        .map(|path_state| path_state.path.value)
+        .collect();
+    path_v.sort();
+    path_v
+}
+
+#[tokio::test]
+async fn test_client_server_access_policy() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50069;
+
+    use idp_core::Permission::*;
+    let acl = |permission_sm: Vec<(&str, Vec<idp_core::Permission>)>| AccessControlList {
+        permission_sm: permission_sm
+            .into_iter()
+            .map(|(identity, permission_v)| {
+                (identity.to_string(), permission_v.into_iter().collect())
+            })
+            .collect(),
+    };
+    let mut access_policy = AccessPolicy {
+        acl_m: maplit::btreemap! {
+            "".to_string() => acl(vec![
+                ("alice", vec![Read, BranchCreate, BranchSetHead, Delete]),
+                ("bob", vec![Read]),
+                ("carol", vec![Read]),
+            ]),
+            "bob-".to_string() => acl(vec![("bob", vec![Read, BranchCreate, BranchSetHead, Delete])]),
+            "policy".to_string() => acl(vec![("alice", vec![Read, BranchSetHead])]),
+        },
+        plum_acl: acl(vec![("alice", vec![Read, Push]), ("bob", vec![Read, Push])]),
+    };
+    // The longest matching prefix applies.
+    assert!(access_policy.permits(Some("alice"), "main", Delete));
+    assert!(!access_policy.permits(Some("alice"), "bob-main", Read));
+    assert!(access_policy.permits(Some("bob"), "bob-main", Delete));
+    assert!(!access_policy.permits(Some("bob"), "main", BranchCreate));
+    // Access to Plums is governed separately from access to paths.
+    assert!(access_policy.permits_plum_access(Some("bob"), Push));
+    assert!(access_policy.permits(Some("carol"), "main", Read));
+    assert!(!access_policy.permits_plum_access(Some("carol"), Read));
+    assert!(!access_policy.permits_plum_access(None, Read));
+
+    let alice_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let bob_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let carol_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+
+    let policy_path = Path::from("policy".to_string());
+    let p0 = store_access_policy_branch_node_plum(&server_datahost_la, None, &access_policy).await;
+    create_local_branch(&server_datahost_la, &policy_path, &p0).await;
+
+    let alice_token = format!("alice-{}", Uuid::new_v4());
+    let bob_token = format!("bob-{}", Uuid::new_v4());
+    let carol_token = format!("carol-{}", Uuid::new_v4());
+    let bearer_token_authenticator = BearerTokenAuthenticator::new(maplit::hashmap! {
+        "alice".to_string() => alice_token.clone(),
+        "bob".to_string() => bob_token.clone(),
+        "carol".to_string() => carol_token.clone(),
+    })
+    .expect("pass");
+    let server_handle = spawn_configured_idp_server(
+        IDPServer::new(server_datahost_la.clone())
+            .with_authenticator(Arc::new(bearer_token_authenticator))
+            .with_access_policy_branch(policy_path.clone()),
+        PORT,
+    );
+    let mut alice_idp_client =
+        connect_authenticated_idp_client(alice_datahost_la.clone(), PORT, || {
+            Some(Box::new(BearerTokenCredentialProvider::new(
+                alice_token.clone(),
+            )))
+        })
+        .await;
+    let mut bob_idp_client =
+        connect_authenticated_idp_client(bob_datahost_la.clone(), PORT, || {
+            Some(Box::new(BearerTokenCredentialProvider::new(
+                bob_token.clone(),
+            )))
+        })
+        .await;
+    // carol can authenticate, and may read every path, but may not access Plums.
+    let mut carol_idp_client =
+        connect_authenticated_idp_client(carol_datahost_la.clone(), PORT, || {
+            Some(Box::new(BearerTokenCredentialProvider::new(
+                carol_token.clone(),
+            )))
+        })
+        .await;
+    let error = carol_idp_client
+        .has_plums(&[PlumHeadSeal::from(&p0.plum_head)])
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::PermissionDenied);
+
+    // bob can create branches only under "bob-".
+    let bob_main_path = Path::from("bob-main".to_string());
+    let main_path = Path::from("main".to_string());
+    let b0 = store_branch_node_plum(&bob_datahost_la, None, "b0").await;
+    create_local_branch(&bob_datahost_la, &bob_main_path, &b0).await;
+    create_local_branch(&bob_datahost_la, &main_path, &b0).await;
+    assert_eq!(
+        bob_idp_client
+            .push_branch(&bob_main_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::Created
+    );
+    let error = bob_idp_client
+        .push_branch(&main_path)
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::PermissionDenied);
+
+    let a0 = store_branch_node_plum(&alice_datahost_la, None, "a0").await;
+    create_local_branch(&alice_datahost_la, &main_path, &a0).await;
+    assert_eq!(
+        alice_idp_client
+            .push_branch(&main_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::Created
+    );
+
+    // PathStates that can't be read are omitted from listings.
+    assert_eq!(
+        listed_paths(&mut bob_idp_client).await,
+        vec!["bob-main", "main"]
+    );
+    assert_eq!(
+        listed_paths(&mut alice_idp_client).await,
+        vec!["main", "policy"]
+    );
+
+    let error = alice_idp_client
+        .fetch_branch(&bob_main_path)
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::PermissionDenied);
+    let error = bob_idp_client
+        .fetch_branch(&policy_path)
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::PermissionDenied);
+
+    // The AccessPolicy is versioned by its branch, which alice is permitted to advance.
+    assert_eq!(
+        alice_idp_client
+            .fetch_branch(&policy_path)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&p0.plum_head)
+    );
+    create_local_branch(&alice_datahost_la, &policy_path, &p0).await;
+    access_policy
+        .acl_m
+        .get_mut("bob-")
+        .expect("pass")
+        .permission_sm
+        .insert("alice".to_string(), maplit::btreeset! { Read });
+    let p1 =
+        store_access_policy_branch_node_plum(&alice_datahost_la, Some(&p0), &access_policy).await;
+    set_local_branch_head(
+        &alice_datahost_la,
+        &policy_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &p1.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        alice_idp_client
+            .push_branch(&policy_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::FastForwarded
+    );
+    // The new AccessPolicy takes effect immediately.
+    assert_eq!(
+        alice_idp_client
+            .fetch_branch(&bob_main_path)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b0.plum_head)
+    );
+
+    // The head of the access policy branch can't be set to a Plum whose content isn't an
+    // AccessPolicy.
+    let p2 = store_branch_node_plum(&alice_datahost_la, Some(&p1), "p2").await;
+    set_local_branch_head(
+        &alice_datahost_la,
+        &policy_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &p2.plum_head,
+        )),
+    )
+    .await;
+    let error = alice_idp_client
+        .push_branch(&policy_path)
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::InvalidArgument);
+
+    // bob can't change the AccessPolicy.
+    create_local_branch(&bob_datahost_la, &policy_path, &b0).await;
+    let error = bob_idp_client
+        .push_branch(&policy_path)
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::PermissionDenied);
+    assert_eq!(
+        server_datahost_la
+            .read()
+            .await
+            .branch_get_head(&policy_path, None)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&p1.plum_head)
+    );
+
+    server_handle.abort();
+}
+
+/// A certificate authority for tests, having a self-signed ECDSA P-256 certificate, which issues
+/// certificates for new keys.
+struct TestCA {
+    cert: rcgen::Certificate,
+    cert_pem: Vec<u8>,
+}
+
+impl TestCA {
+    fn new(common_name: &str) -> Self {
+        let mut cert_params = rcgen::CertificateParams::new(Vec::new());
+        cert_params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, common_name);
+        cert_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let cert = rcgen::Certificate::from_params(cert_params).expect("pass");
+        let cert_pem = cert.serialize_pem().expect("pass").into_bytes();
+        Self { cert, cert_pem }
+    }
+    /// Issues a certificate (having the given DNS subjectAltName) for a new key, returning the
+    /// PEM-encoded certificate and PKCS8 private key.
+    fn issue_cert_and_key_pem(&self, common_name: &str, dns_name: &str) -> (Vec<u8>, Vec<u8>) {
+        let mut cert_params = rcgen::CertificateParams::new(vec![dns_name.to_string()]);
+        cert_params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, common_name);
+        let cert = rcgen::Certificate::from_params(cert_params).expect("pass");
+        (
+            cert.serialize_pem_with_signer(&self.cert)
+                .expect("pass")
+                .into_bytes(),
+            cert.serialize_private_key_pem().into_bytes(),
+        )
+    }
+}
+
+/// Returns true iff an IDPClient can connect to the given URL using the given ClientTLSConfig, and
+/// make a request.
+async fn can_make_request(url: &str, tls_config_o: Option<&ClientTLSConfig>) -> bool {
+    let datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    match IDPClient::connect(url.to_string(), datahost_la, None, tls_config_o).await {
+        Ok(mut idp_client) => idp_client.has_plums(&[]).await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[tokio::test]
+async fn test_client_server_tls() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50070;
+
+    let server_ca = TestCA::new("server CA");
+    let (server_cert_pem, server_key_pem) =
+        server_ca.issue_cert_and_key_pem("IDPServer", "localhost");
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_configured_idp_server(
+        IDPServer::new(server_datahost_la.clone())
+            .with_tls_config(ServerTLSConfig::new(server_cert_pem, server_key_pem)),
+        PORT,
+    );
+    // The server's certificate is for "localhost", whereas the URL uses its IP address.
+    let url = format!("https://127.0.0.1:{}", PORT);
+    let tls_config =
+        ClientTLSConfig::new(server_ca.cert_pem.clone()).with_domain_name("localhost".to_string());
+
+    let mut idp_client = connect_configured_idp_client(
+        url.clone(),
+        client_datahost_la.clone(),
+        || None,
+        Some(&tls_config),
+    )
+    .await;
+    let plum = content_plum("pushed over TLS");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum(&plum, None)
+        .await
+        .expect("pass");
+    idp_client
+        .push(&PlumHeadSeal::from(&plum.plum_head))
+        .await
+        .expect("pass");
+    assert_has_plums(&server_datahost_la, std::slice::from_ref(&plum), true).await;
+
+    // The server's certificate must have been issued by a trusted CA.
+    let other_ca = TestCA::new("other CA");
+    assert!(
+        !can_make_request(
+            &url,
+            Some(
+                &ClientTLSConfig::new(other_ca.cert_pem.clone())
+                    .with_domain_name("localhost".to_string())
+            )
+        )
+        .await
+    );
+    // The server's certificate must be for the right domain name.
+    assert!(
+        !can_make_request(
+            &url,
+            Some(
+                &ClientTLSConfig::new(server_ca.cert_pem.clone())
+                    .with_domain_name("example.com".to_string())
+            )
+        )
+        .await
+    );
+    // The server's certificate wasn't issued by a well-known public CA.
+    assert!(
+        !can_make_request(
+            &url,
+            Some(&ClientTLSConfig::system_roots().with_domain_name("localhost".to_string()))
+        )
+        .await
+    );
+    // Trusting the system roots doesn't prevent also trusting other CAs.
+    assert!(can_make_request(&url, Some(&tls_config.clone().with_system_roots())).await);
+    // Plaintext connections are rejected.
+    assert!(!can_make_request(&format!("http://127.0.0.1:{}", PORT), None).await);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_mutual_tls() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50071;
+
+    let server_ca = TestCA::new("server CA");
+    let (server_cert_pem, server_key_pem) =
+        server_ca.issue_cert_and_key_pem("IDPServer", "localhost");
+    let client_ca = TestCA::new("client CA");
+    let (client_cert_pem, client_key_pem) =
+        client_ca.issue_cert_and_key_pem("IDPClient", "idp-client");
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_configured_idp_server(
+        IDPServer::new(server_datahost_la.clone()).with_tls_config(
+            ServerTLSConfig::new(server_cert_pem, server_key_pem)
+                .with_client_ca_bundle(client_ca.cert_pem.clone()),
+        ),
+        PORT,
+    );
+    let url = format!("https://127.0.0.1:{}", PORT);
+    let tls_config =
+        ClientTLSConfig::new(server_ca.cert_pem.clone()).with_domain_name("localhost".to_string());
+
+    let mut idp_client = connect_configured_idp_client(
+        url.clone(),
+        client_datahost_la.clone(),
+        || None,
+        Some(
+            &tls_config
+                .clone()
+                .with_client_cert_and_key(client_cert_pem.clone(), client_key_pem.clone()),
+        ),
+    )
+    .await;
+    let plum = content_plum("pushed over mutual TLS");
+    client_datahost_la
+        .read()
+        .await
+        .store_plum(&plum, None)
+        .await
+        .expect("pass");
+    idp_client
+        .push(&PlumHeadSeal::from(&plum.plum_head))
+        .await
+        .expect("pass");
+    assert_has_plums(&server_datahost_la, std::slice::from_ref(&plum), true).await;
+
+    // Clients must present a certificate.
+    assert!(!can_make_request(&url, Some(&tls_config)).await);
+    assert!(!can_make_request(&url, Some(&tls_config.clone().with_system_roots())).await);
+    // The client's certificate is presented regardless of which CAs the client trusts.
+    assert!(
+        can_make_request(
+            &url,
+            Some(
+                &tls_config
+                    .clone()
+                    .with_system_roots()
+                    .with_client_cert_and_key(client_cert_pem.clone(), client_key_pem.clone())
+            )
+        )
+        .await
+    );
+    // The client's certificate must have been issued by the client CA.
+    let (other_client_cert_pem, other_client_key_pem) =
+        server_ca.issue_cert_and_key_pem("IDPClient", "idp-client");
+    assert!(
+        !can_make_request(
+            &url,
+            Some(
+                &tls_config
+                    .clone()
+                    .with_client_cert_and_key(other_client_cert_pem, other_client_key_pem)
+            )
+        )
+        .await
+    );
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_structured_errors() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50072;
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+
+    let branch_path = Path::from("main".to_string());
+    let b0 = store_branch_node_plum(&client_datahost_la, None, "b0").await;
+    let b1 = store_branch_node_plum(&client_datahost_la, Some(&b0), "b1").await;
+    idp_client
+        .push(&PlumHeadSeal::from(&b1.plum_head))
+        .await
+        .expect("pass");
+    let branch_path_state = PathState {
+        path: branch_path.clone(),
+        current_state_plum_head_seal: PlumHeadSeal::from(&b1.plum_head),
+    };
+    idp_client
+        .branch_create(&branch_path_state)
+        .await
+        .expect("pass");
+    assert_eq!(
+        idp_client
+            .branch_get_head(&branch_path)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+
+    // The BranchError produced by the server is reconstructed, and the Status is still available.
+    let error = idp_client
+        .branch_set_head(BranchSetHeadRequest {
+            branch_path: branch_path.clone(),
+            value: Some(
+                idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+                    &b0.plum_head,
+                )),
+            ),
+            expected_current_branch_head_o: None,
+        })
+        .await
+        .expect_err("fail");
+    match error.downcast_ref::<BranchError>().expect("pass") {
+        BranchError::FastForwardExpectedDescendant {
+            current_branch_head,
+            new_branch_head,
+        } => {
+            assert_eq!(*current_branch_head, PlumHeadSeal::from(&b1.plum_head));
+            assert_eq!(*new_branch_head, PlumHeadSeal::from(&b0.plum_head));
+        }
+        branch_error => {
+            panic!("unexpected BranchError {:?}", branch_error);
+        }
+    }
+    assert_status_code(&error, tonic::Code::InvalidArgument);
+
+    // An otherwise valid operation is rejected if the branch head isn't the expected one, e.g. because
+    // it was updated concurrently.
+    let error = idp_client
+        .branch_set_head(BranchSetHeadRequest {
+            branch_path: branch_path.clone(),
+            value: Some(idp_proto::branch_set_head_request::Value::BranchRewindTo(
+                PlumHeadSeal::from(&b0.plum_head),
+            )),
+            expected_current_branch_head_o: Some(PlumHeadSeal::from(&b0.plum_head)),
+        })
+        .await
+        .expect_err("fail");
+    match error.downcast_ref::<BranchError>().expect("pass") {
+        BranchError::UnexpectedCurrentBranchHead {
+            current_branch_head,
+            expected_current_branch_head,
+        } => {
+            assert_eq!(*current_branch_head, PlumHeadSeal::from(&b1.plum_head));
+            assert_eq!(
+                *expected_current_branch_head,
+                PlumHeadSeal::from(&b0.plum_head)
+            );
+        }
+        branch_error => {
+            panic!("unexpected BranchError {:?}", branch_error);
+        }
+    }
+    assert_status_code(&error, tonic::Code::Aborted);
+    assert_eq!(
+        idp_client
+            .branch_get_head(&branch_path)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+
+    // Nested errors are reconstructed too.
+    let error = idp_client
+        .branch_create(&branch_path_state)
+        .await
+        .expect_err("fail");
+    assert!(matches!(
+        error.downcast_ref::<BranchError>().expect("pass"),
+        BranchError::PathStateError(PathStateError::PathAlreadyExists(path)) if *path == branch_path
+    ));
+    assert_status_code(&error, tonic::Code::AlreadyExists);
+
+    // As are DatahostStorageError-s.
+    let missing_path = Path::from("missing".to_string());
+    let error = idp_client
+        .path_state_get(&missing_path)
+        .await
+        .expect_err("fail");
+    assert!(matches!(
+        error.downcast_ref::<DatahostStorageError>().expect("pass"),
+        DatahostStorageError::PathNotFound(path) if *path == missing_path
+    ));
+    assert_status_code(&error, tonic::Code::NotFound);
+
+    idp_client.branch_delete(&branch_path).await.expect("pass");
+    let error = idp_client
+        .branch_get_head(&branch_path)
+        .await
+        .expect_err("fail");
+    assert_status_code(&error, tonic::Code::NotFound);
+
+    // Statuses that don't carry an ErrorDetail are returned as-is.
+    let error = idp_client
+        .path_state_list(None, 0, None)
+        .await
+        .expect_err("fail");
+    assert!(error.downcast_ref::<BranchError>().is_none());
+    assert_status_code(&error, tonic::Code::InvalidArgument);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_watch_path_states() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50073;
+
+    let a_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let w_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut a_idp_client = connect_idp_client(a_datahost_la.clone(), PORT).await;
+    let mut w_idp_client = connect_idp_client(w_datahost_la.clone(), PORT).await;
+
+    let branch_path = Path::from("main".to_string());
+    let other_branch_path = Path::from("other".to_string());
+    let mut path_state_change_s = Box::pin(
+        w_idp_client
+            .watch_path_states(std::slice::from_ref(&branch_path))
+            .await
+            .expect("pass"),
+    );
+
+    // Creating the watched branch is reported, and its head has been pulled by then.
+    let b0 = store_branch_node_plum(&a_datahost_la, None, "b0").await;
+    let b0_path_state = PathState {
+        path: branch_path.clone(),
+        current_state_plum_head_seal: PlumHeadSeal::from(&b0.plum_head),
+    };
+    create_local_branch(&a_datahost_la, &branch_path, &b0).await;
+    a_idp_client.push_branch(&branch_path).await.expect("pass");
+    assert_eq!(
+        path_state_change_s
+            .try_next()
+            .await
+            .expect("pass")
+            .expect("pass")
+            .value,
+        Some(path_state_change::Value::Inserted(b0_path_state))
+    );
+    assert_has_plums(&w_datahost_la, std::slice::from_ref(&b0), true).await;
+
+    // Unwatched PathStates aren't reported.
+    let other_b0 = store_branch_node_plum(&a_datahost_la, None, "other b0").await;
+    create_local_branch(&a_datahost_la, &other_branch_path, &other_b0).await;
+    a_idp_client
+        .push_branch(&other_branch_path)
+        .await
+        .expect("pass");
+
+    // Updating the watched branch is reported.
+    let b1 = store_branch_node_plum(&a_datahost_la, Some(&b0), "b1").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &b1.plum_head,
+        )),
+    )
+    .await;
+    a_idp_client.push_branch(&branch_path).await.expect("pass");
+    assert_eq!(
+        path_state_change_s
+            .try_next()
+            .await
+            .expect("pass")
+            .expect("pass")
+            .value,
+        Some(path_state_change::Value::Updated(PathState {
+            path: branch_path.clone(),
+            current_state_plum_head_seal: PlumHeadSeal::from(&b1.plum_head),
+        }))
+    );
+    assert_has_plums(&w_datahost_la, &[b0.clone(), b1.clone()], true).await;
+    assert_has_plums(&w_datahost_la, std::slice::from_ref(&other_b0), false).await;
+
+    // Deleting the watched branch is reported.
+    a_idp_client
+        .branch_delete(&branch_path)
+        .await
+        .expect("pass");
+    assert_eq!(
+        path_state_change_s
+            .try_next()
+            .await
+            .expect("pass")
+            .expect("pass")
+            .value,
+        Some(path_state_change::Value::Deleted(branch_path.clone()))
+    );
+    drop(path_state_change_s);
+
+    // Polling reports the PathStates updated since the given time, and what time to poll from next.
+    let (path_state_v, updated_at_begin) = w_idp_client
+        .path_states_updated_since(None, UnixNanoseconds::from(0))
+        .await
+        .expect("pass");
+    assert_eq!(
+        path_state_v
+            .iter()
+            .map(|path_state| path_state.path.value.as_str())
+            .collect::<Vec<_>>(),
+        vec!["other"]
+    );
+    let other_b1 = store_branch_node_plum(&a_datahost_la, Some(&other_b0), "other b1").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &other_branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &other_b1.plum_head,
+        )),
+    )
+    .await;
+    a_idp_client
+        .push_branch(&other_branch_path)
+        .await
+        .expect("pass");
+    let (path_state_v, next_updated_at_begin) = w_idp_client
+        .path_states_updated_since(None, updated_at_begin)
+        .await
+        .expect("pass");
+    assert_eq!(
+        path_state_v.last().expect("pass"),
+        &PathState {
+            path: other_branch_path.clone(),
+            current_state_plum_head_seal: PlumHeadSeal::from(&other_b1.plum_head),
+        }
+    );
+    assert!(next_updated_at_begin.value > updated_at_begin.value);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_server_keyed_seals() {
+    let _ = env_logger::try_init();
+
+    const PORT: u16 = 50074;
+    const KEYED_PORT: u16 = 50075;
+
+    let seal_key = SealKey::new(
+        "test_client_server_keyed_seals".to_string(),
+        b"the test_client_server_keyed_seals secret".to_vec(),
+    );
+    let seal_key_provider_a: Arc<dyn SealKeyProvider + Send + Sync> = Arc::new(seal_key.clone());
+    // The large PlumBody is chunked, so that the reassembled PlumBody's keyed seal is checked too.
+    let keyed_plum_v = vec![
+        keyed_content_plum("test_client_server_keyed_seals", &seal_key),
+        keyed_content_plum(&"x".repeat(5 * PLUM_BODY_CHUNK_SIZE), &seal_key),
+    ];
+    assert!(keyed_plum_v[1].plum_body.requires_chunking());
+    let dir_plum = dir_node_plum(&keyed_plum_v);
+    let dir_plum_head_seal = PlumHeadSeal::from(&dir_plum.plum_head);
+    let mut plum_v = vec![dir_plum];
+    plum_v.extend(keyed_plum_v.iter().cloned());
+
+    let client_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    client_datahost_la
+        .read()
+        .await
+        .store_plum_v(&plum_v, None)
+        .await
+        .expect("pass");
+
+    // A server without the SealKey can't check the keyed seals, so it rejects the pushed Plums.
+    let server_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let server_handle = spawn_idp_server(server_datahost_la.clone(), PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), PORT).await;
+    idp_client
+        .push(&dir_plum_head_seal)
+        .await
+        .expect_err("fail");
+    assert_has_plums(&server_datahost_la, &plum_v, false).await;
+
+    // Whereas a server with the SealKey checks and accepts them.
+    let keyed_server_datahost_la = Arc::new(RwLock::new(
+        Datahost::open(DatahostStorageInMemory::new())
+            .with_seal_key_provider(seal_key_provider_a.clone()),
+    ));
+    let keyed_server_handle = spawn_idp_server(keyed_server_datahost_la.clone(), KEYED_PORT);
+    let mut idp_client = connect_idp_client(client_datahost_la.clone(), KEYED_PORT).await;
+    idp_client.push(&dir_plum_head_seal).await.expect("pass");
+    assert_has_plums(&keyed_server_datahost_la, &plum_v, true).await;
+
+    // Likewise, a client without the SealKey rejects the pulled Plums, and so stores none of them.
+    let pull_datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let mut idp_client = connect_idp_client(pull_datahost_la.clone(), KEYED_PORT).await;
+    idp_client
+        .pull(&dir_plum_head_seal)
+        .await
+        .expect_err("fail");
+    assert_has_plums(&pull_datahost_la, &plum_v, false).await;
+    let mut idp_client = connect_idp_client(pull_datahost_la.clone(), KEYED_PORT)
+        .await
+        .with_seal_key_provider(seal_key_provider_a);
+    idp_client.pull(&dir_plum_head_seal).await.expect("pass");
+    assert_has_plums(&pull_datahost_la, &plum_v, true).await;
+
+    server_handle.abort();
+    keyed_server_handle.abort();
+}
@@ -0,0 +1,523 @@
+mod common;
+
+use async_lock::RwLock;
+use common::{
+    assert_has_plums, content_plum, dir_node_plum, keyed_content_plum, set_local_branch_head,
+    store_branch_node_plum,
+};
+use idp_core::{BranchError, BranchUpdate, Datahost, TransferReport};
+use idp_datahost_storage::DatahostStorageError;
+use idp_datahost_storage_in_memory::DatahostStorageInMemory;
+use idp_p2p::{IDPPeer, Keypair, Multiaddr, IDP_P2P_BATCH_SIZE};
+use idp_proto::{
+    BranchSetHeadRequest, Path, PathState, Plum, PlumHeadSeal, PlumRelationFlags, SealKey,
+    PLUM_BODY_CHUNK_SIZE,
+};
+use std::sync::Arc;
+
+/// Creates an IDPPeer (with a fresh identity and an empty in-memory Datahost) listening on the given
+/// port of the memory transport.  Each test uses its own ports, since the memory transport's ports
+/// are shared by the whole process.
+async fn spawn_idp_peer(memory_port: u64) -> (Arc<RwLock<Datahost>>, IDPPeer, Multiaddr) {
+    let datahost_la = Arc::new(RwLock::new(Datahost::open(DatahostStorageInMemory::new())));
+    let idp_peer = IDPPeer::new(datahost_la.clone(), Keypair::generate_ed25519()).expect("pass");
+    let multiaddr = idp_peer
+        .listen_on(
+            format!("/memory/{}", memory_port)
+                .parse::<Multiaddr>()
+                .expect("pass"),
+        )
+        .await
+        .expect("pass");
+    (datahost_la, idp_peer, multiaddr)
+}
+
+/// Returns a two-level tree of DirNodes with content Plums at the leaves, root first.
+fn tree_plum_v(label: &str) -> Vec<Plum> {
+    let content_plum_vv = (0..3)
+        .map(|i| {
+            (0..10)
+                .map(|j| content_plum(&format!("{} content {}-{}", label, i, j)))
+                .collect::<Vec<Plum>>()
+        })
+        .collect::<Vec<Vec<Plum>>>();
+    let subdir_plum_v = content_plum_vv
+        .iter()
+        .map(|content_plum_v| dir_node_plum(content_plum_v))
+        .collect::<Vec<Plum>>();
+    let mut plum_v = vec![dir_node_plum(&subdir_plum_v)];
+    plum_v.extend(subdir_plum_v);
+    plum_v.extend(content_plum_vv.into_iter().flatten());
+    plum_v
+}
+
+#[tokio::test]
+async fn test_p2p_push_and_pull() {
+    let _ = env_logger::try_init();
+
+    let (a_datahost_la, a_idp_peer, _) = spawn_idp_peer(1001).await;
+    let (b_datahost_la, b_idp_peer, b_multiaddr) = spawn_idp_peer(1002).await;
+    let b_peer_id = *b_idp_peer.local_peer_id();
+    a_idp_peer
+        .dial(&b_peer_id, b_multiaddr.clone())
+        .await
+        .expect("pass");
+    // Dialing an already-connected peer is fine.
+    a_idp_peer
+        .dial(&b_peer_id, b_multiaddr)
+        .await
+        .expect("pass");
+
+    // Push a tree from a to b.
+    let a_plum_v = tree_plum_v("a");
+    let a_root_plum_head_seal = PlumHeadSeal::from(&a_plum_v[0].plum_head);
+    a_datahost_la
+        .read()
+        .await
+        .store_plum_v(&a_plum_v, None)
+        .await
+        .expect("pass");
+    assert_has_plums(&b_datahost_la, &a_plum_v, false).await;
+    assert_eq!(
+        a_idp_peer
+            .push(&b_peer_id, &a_root_plum_head_seal)
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: a_plum_v.len(),
+            plum_body_count: a_plum_v.len(),
+        }
+    );
+    assert_has_plums(&b_datahost_la, &a_plum_v, true).await;
+    // Pushing it again transfers nothing.
+    assert!(a_idp_peer
+        .push(&b_peer_id, &a_root_plum_head_seal)
+        .await
+        .expect("pass")
+        .is_empty());
+
+    // Pull a tree from b to a, where one of its subtrees is already present on a.
+    let b_content_plum = content_plum("b content");
+    let b_root_plum = dir_node_plum(&[b_content_plum.clone(), a_plum_v[1].clone()]);
+    let b_root_plum_head_seal = PlumHeadSeal::from(&b_root_plum.plum_head);
+    b_datahost_la
+        .read()
+        .await
+        .store_plum_v(&[b_content_plum.clone(), b_root_plum.clone()], None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        a_idp_peer
+            .has_plums(
+                &b_peer_id,
+                &[
+                    b_root_plum_head_seal.clone(),
+                    PlumHeadSeal::from(&content_plum("absent").plum_head)
+                ]
+            )
+            .await
+            .expect("pass"),
+        vec![true, false]
+    );
+    assert_eq!(
+        a_idp_peer
+            .pull(&b_peer_id, &b_root_plum_head_seal)
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: 2,
+            plum_body_count: 2,
+        }
+    );
+    assert_has_plums(&a_datahost_la, &[b_content_plum, b_root_plum], true).await;
+    assert!(a_idp_peer
+        .pull(&b_peer_id, &b_root_plum_head_seal)
+        .await
+        .expect("pass")
+        .is_empty());
+
+    // Pulling a Plum that the peer doesn't have fails with the peer's DatahostStorageError.
+    let absent_plum_head_seal = PlumHeadSeal::from(&content_plum("absent").plum_head);
+    let error = a_idp_peer
+        .pull(&b_peer_id, &absent_plum_head_seal)
+        .await
+        .expect_err("fail");
+    assert!(
+        error.downcast_ref::<DatahostStorageError>().is_some(),
+        "{:#}",
+        error
+    );
+
+    // If b has a Plum but not its dependencies (e.g. because it was stored without them), then the
+    // push doesn't stop at that Plum, and the missing dependencies are sent.
+    let partial_plum_v = tree_plum_v("partial");
+    let partial_root_plum_head_seal = PlumHeadSeal::from(&partial_plum_v[0].plum_head);
+    a_datahost_la
+        .read()
+        .await
+        .store_plum_v(&partial_plum_v, None)
+        .await
+        .expect("pass");
+    b_datahost_la
+        .read()
+        .await
+        .store_plum(&partial_plum_v[0], None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        a_idp_peer
+            .push(&b_peer_id, &partial_root_plum_head_seal)
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: partial_plum_v.len(),
+            plum_body_count: partial_plum_v.len(),
+        }
+    );
+    assert!(b_datahost_la
+        .read()
+        .await
+        .check_completeness(
+            &partial_root_plum_head_seal,
+            PlumRelationFlags::CONTENT_DEPENDENCY | PlumRelationFlags::METADATA_DEPENDENCY,
+            None
+        )
+        .await
+        .expect("pass")
+        .is_complete());
+
+    // Neither peer has a SealKeyProvider, so neither can check keyed seals, and so neither accepts a
+    // keyed Plum from the other.
+    let seal_key = SealKey::new(
+        "test_p2p_push_and_pull".to_string(),
+        b"the test_p2p_push_and_pull secret".to_vec(),
+    );
+    let a_keyed_plum = keyed_content_plum("a keyed content", &seal_key);
+    let a_keyed_plum_head_seal = PlumHeadSeal::from(&a_keyed_plum.plum_head);
+    a_datahost_la
+        .read()
+        .await
+        .store_plum(&a_keyed_plum, None)
+        .await
+        .expect("pass");
+    a_idp_peer
+        .push(&b_peer_id, &a_keyed_plum_head_seal)
+        .await
+        .expect_err("fail");
+    assert_has_plums(&b_datahost_la, &[a_keyed_plum], false).await;
+    let b_keyed_plum = keyed_content_plum("b keyed content", &seal_key);
+    let b_keyed_plum_head_seal = PlumHeadSeal::from(&b_keyed_plum.plum_head);
+    b_datahost_la
+        .read()
+        .await
+        .store_plum(&b_keyed_plum, None)
+        .await
+        .expect("pass");
+    a_idp_peer
+        .pull(&b_peer_id, &b_keyed_plum_head_seal)
+        .await
+        .expect_err("fail");
+    assert_has_plums(&a_datahost_la, &[b_keyed_plum], false).await;
+}
+
+#[tokio::test]
+async fn test_p2p_batched_and_chunked_transfer() {
+    let _ = env_logger::try_init();
+
+    let (a_datahost_la, a_idp_peer, _) = spawn_idp_peer(1005).await;
+    let (b_datahost_la, b_idp_peer, b_multiaddr) = spawn_idp_peer(1006).await;
+    let (c_datahost_la, c_idp_peer, _) = spawn_idp_peer(1007).await;
+    let b_peer_id = *b_idp_peer.local_peer_id();
+    a_idp_peer
+        .dial(&b_peer_id, b_multiaddr.clone())
+        .await
+        .expect("pass");
+    c_idp_peer
+        .dial(&b_peer_id, b_multiaddr)
+        .await
+        .expect("pass");
+
+    // The medium Plums don't require chunking, but together they exceed IDP_P2P_BATCH_SIZE, so they're
+    // sent in several batches.  The large Plum's PlumBody requires chunking, and itself exceeds
+    // IDP_P2P_BATCH_SIZE, so its PlumBodyChunk-s are sent in several batches too.
+    let mut plum_v = (0..40)
+        .map(|i| {
+            content_plum(&format!(
+                "medium {} {}",
+                i,
+                "x".repeat(PLUM_BODY_CHUNK_SIZE / 2)
+            ))
+        })
+        .collect::<Vec<Plum>>();
+    assert!(plum_v
+        .iter()
+        .all(|plum| !plum.plum_body.requires_chunking()));
+    let large_plum = content_plum(&format!("large {}", "x".repeat(IDP_P2P_BATCH_SIZE + 12345)));
+    assert!(large_plum.plum_body.requires_chunking());
+    plum_v.push(large_plum.clone());
+    let root_plum = dir_node_plum(&plum_v);
+    let root_plum_head_seal = PlumHeadSeal::from(&root_plum.plum_head);
+    plum_v.push(root_plum);
+    a_datahost_la
+        .read()
+        .await
+        .store_plum_v(&plum_v, None)
+        .await
+        .expect("pass");
+
+    // Push from a to b.
+    assert_eq!(
+        a_idp_peer
+            .push(&b_peer_id, &root_plum_head_seal)
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: plum_v.len(),
+            plum_body_count: plum_v.len(),
+        }
+    );
+    assert_has_plums(&b_datahost_la, &plum_v, true).await;
+
+    // Pull from b to c.
+    assert_eq!(
+        c_idp_peer
+            .pull(&b_peer_id, &root_plum_head_seal)
+            .await
+            .expect("pass"),
+        TransferReport {
+            plum_count: plum_v.len(),
+            plum_body_count: plum_v.len(),
+        }
+    );
+    assert_has_plums(&c_datahost_la, &plum_v, true).await;
+    assert_eq!(
+        c_datahost_la
+            .read()
+            .await
+            .load_option_plum_body(&large_plum.plum_head.plum_body_seal, None)
+            .await
+            .expect("pass"),
+        Some(large_plum.plum_body)
+    );
+}
+
+#[tokio::test]
+async fn test_p2p_push_and_fetch_branch() {
+    let _ = env_logger::try_init();
+
+    let (a_datahost_la, a_idp_peer, a_multiaddr) = spawn_idp_peer(1003).await;
+    let (b_datahost_la, b_idp_peer, b_multiaddr) = spawn_idp_peer(1004).await;
+    let a_peer_id = *a_idp_peer.local_peer_id();
+    let b_peer_id = *b_idp_peer.local_peer_id();
+    a_idp_peer
+        .dial(&b_peer_id, b_multiaddr)
+        .await
+        .expect("pass");
+    b_idp_peer
+        .dial(&a_peer_id, a_multiaddr)
+        .await
+        .expect("pass");
+
+    let branch_path = Path::from("main".to_string());
+    let remote_tracking_branch_path =
+        IDPPeer::remote_tracking_branch_path(&a_peer_id, &branch_path);
+    assert_eq!(
+        remote_tracking_branch_path.value,
+        format!("{}/main", a_peer_id)
+    );
+
+    // The branch doesn't exist on b yet.
+    let error = a_idp_peer
+        .branch_get_head(&b_peer_id, &branch_path)
+        .await
+        .expect_err("fail");
+    assert!(
+        matches!(
+            error.downcast_ref::<BranchError>(),
+            Some(BranchError::DatahostStorageError(
+                DatahostStorageError::PathNotFound(_)
+            ))
+        ),
+        "{:#}",
+        error
+    );
+
+    // Pushing a branch that doesn't exist on the peer creates it.
+    let b0 = store_branch_node_plum(&a_datahost_la, None, "b0").await;
+    a_datahost_la
+        .read()
+        .await
+        .branch_create(
+            &PathState {
+                path: branch_path.clone(),
+                current_state_plum_head_seal: PlumHeadSeal::from(&b0.plum_head),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(
+        a_idp_peer
+            .push_branch(&b_peer_id, &branch_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::Created
+    );
+    assert_eq!(
+        a_idp_peer
+            .branch_get_head(&b_peer_id, &branch_path)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b0.plum_head)
+    );
+
+    // Fast-forward
+    let b1 = store_branch_node_plum(&a_datahost_la, Some(&b0), "b1").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &b1.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        a_idp_peer
+            .push_branch(&b_peer_id, &branch_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::FastForwarded
+    );
+    assert_eq!(
+        a_idp_peer
+            .push_branch(&b_peer_id, &branch_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::Unchanged
+    );
+    // Pushing records the pushed branch head as the remote-tracking PathState for b.
+    assert_eq!(
+        a_datahost_la
+            .read()
+            .await
+            .load_path_state(
+                &IDPPeer::remote_tracking_branch_path(&b_peer_id, &branch_path),
+                None
+            )
+            .await
+            .expect("pass")
+            .current_state_plum_head_seal,
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+    assert_eq!(
+        a_idp_peer
+            .path_state_get(&b_peer_id, &branch_path)
+            .await
+            .expect("pass")
+            .current_state_plum_head_seal,
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+
+    // Requests go both ways over the same connection; b fetches a's branch.
+    let b2 = store_branch_node_plum(&a_datahost_la, Some(&b1), "b2").await;
+    set_local_branch_head(
+        &a_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &b2.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        b_idp_peer
+            .fetch_branch(&a_peer_id, &branch_path)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b2.plum_head)
+    );
+    assert_eq!(
+        b_datahost_la
+            .read()
+            .await
+            .load_path_state(&remote_tracking_branch_path, None)
+            .await
+            .expect("pass")
+            .current_state_plum_head_seal,
+        PlumHeadSeal::from(&b2.plum_head)
+    );
+    assert_has_plums(&b_datahost_la, &[b0.clone(), b1.clone(), b2.clone()], true).await;
+    // The local branch on b wasn't modified.
+    assert_eq!(
+        b_datahost_la
+            .read()
+            .await
+            .branch_get_head(&branch_path, None)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b1.plum_head)
+    );
+
+    // A branch that diverged from the peer's forks its history.
+    let b3 = store_branch_node_plum(&b_datahost_la, Some(&b1), "b3").await;
+    set_local_branch_head(
+        &b_datahost_la,
+        &branch_path,
+        idp_proto::branch_set_head_request::Value::BranchFastForwardTo(PlumHeadSeal::from(
+            &b3.plum_head,
+        )),
+    )
+    .await;
+    assert_eq!(
+        b_idp_peer
+            .push_branch(&a_peer_id, &branch_path)
+            .await
+            .expect("pass"),
+        BranchUpdate::ForkedHistory
+    );
+    assert_eq!(
+        a_datahost_la
+            .read()
+            .await
+            .branch_get_head(&branch_path, None)
+            .await
+            .expect("pass"),
+        PlumHeadSeal::from(&b3.plum_head)
+    );
+
+    // A BranchSetHeadRequest operation that doesn't apply fails with the peer's BranchError.
+    let error = a_idp_peer
+        .branch_set_head(
+            &b_peer_id,
+            BranchSetHeadRequest {
+                branch_path: branch_path.clone(),
+                value: Some(
+                    idp_proto::branch_set_head_request::Value::BranchFastForwardTo(
+                        PlumHeadSeal::from(&b0.plum_head),
+                    ),
+                ),
+                expected_current_branch_head_o: None,
+            },
+        )
+        .await
+        .expect_err("fail");
+    assert!(
+        matches!(
+            error.downcast_ref::<BranchError>(),
+            Some(BranchError::FastForwardExpectedDescendant { .. })
+        ),
+        "{:#}",
+        error
+    );
+
+    // Deleting the branch on the peer.
+    a_idp_peer
+        .branch_delete(&b_peer_id, &branch_path)
+        .await
+        .expect("pass");
+    assert!(!b_datahost_la
+        .read()
+        .await
+        .has_path_state(&branch_path, None)
+        .await
+        .expect("pass"));
+}
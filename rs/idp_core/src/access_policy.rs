@@ -0,0 +1,116 @@
+use crate::Permission;
+use anyhow::Result;
+use idp_proto::{PlumHeadSeal, PlumRelationFlags};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Maps identities to the Permissions granted to them.
+#[derive(Clone, Debug, Default, serde::Deserialize, Eq, PartialEq, serde::Serialize)]
+pub struct AccessControlList {
+    /// Maps each identity (i.e. the value of the AuthenticatedIdentity established by the server's
+    /// Authenticator) to the Permissions granted to it.  The Permissions granted to ANY_IDENTITY are
+    /// granted to every client, including unauthenticated ones.
+    pub permission_sm: BTreeMap<String, BTreeSet<Permission>>,
+}
+
+impl AccessControlList {
+    /// The identity whose Permissions are granted to every client.
+    pub const ANY_IDENTITY: &'static str = "*";
+
+    /// Returns true iff the given identity (or None for an unauthenticated client) has the given
+    /// Permission.
+    pub fn permits(&self, identity_o: Option<&str>, permission: Permission) -> bool {
+        let has_permission = |identity: &str| {
+            self.permission_sm
+                .get(identity)
+                .map(|permission_s| permission_s.contains(&permission))
+                .unwrap_or(false)
+        };
+        has_permission(Self::ANY_IDENTITY) || identity_o.map(has_permission).unwrap_or(false)
+    }
+}
+
+/// Specifies which identities may perform which operations on which paths of an IDPServer, and
+/// which may push and pull Plums.  An IDPServer keeps its AccessPolicy as the content of the head
+/// of a branch (see IDPServer::with_access_policy_branch), so that it's versioned, and changing it
+/// is itself governed by the AccessPolicy.
+#[derive(Clone, Debug, Default, serde::Deserialize, Eq, PartialEq, serde::Serialize)]
+pub struct AccessPolicy {
+    /// Maps each path prefix to the AccessControlList for the paths having that prefix.  Only the
+    /// AccessControlList having the longest prefix of a given path applies to it.  The
+    /// AccessControlList for the empty prefix (if present) thus applies to all paths not covered by
+    /// any other.  Paths not covered by any AccessControlList permit nothing.
+    pub acl_m: BTreeMap<String, AccessControlList>,
+    /// The AccessControlList that governs the operations that don't involve a path, i.e. pushing
+    /// Plums (requiring Permission::Push) and pulling Plums or checking for their presence (requiring
+    /// Permission::Read).  This is separate from acl_m, so that granting access to all paths doesn't
+    /// also grant access to all Plums, and vice versa.
+    #[serde(default)]
+    pub plum_acl: AccessControlList,
+}
+
+impl AccessPolicy {
+    /// Returns the AccessControlList that applies to the given path, i.e. the one having the longest
+    /// prefix of path, if any.
+    pub fn acl_for_path(&self, path: &str) -> Option<&AccessControlList> {
+        self.acl_m
+            .iter()
+            .filter(|(path_prefix, _)| path.starts_with(path_prefix.as_str()))
+            .max_by_key(|(path_prefix, _)| path_prefix.len())
+            .map(|(_, acl)| acl)
+    }
+    /// Returns true iff the given identity (or None for an unauthenticated client) has the given
+    /// Permission on the given path.
+    pub fn permits(&self, identity_o: Option<&str>, path: &str, permission: Permission) -> bool {
+        self.acl_for_path(path)
+            .map(|acl| acl.permits(identity_o, permission))
+            .unwrap_or(false)
+    }
+    /// Returns true iff the given identity (or None for an unauthenticated client) has the given
+    /// Permission on Plums (see AccessPolicy::plum_acl).
+    pub fn permits_plum_access(&self, identity_o: Option<&str>, permission: Permission) -> bool {
+        self.plum_acl.permits(identity_o, permission)
+    }
+}
+
+impl idp_proto::ContentClassifiable for AccessPolicy {
+    fn content_class_str() -> &'static str {
+        "application/x.idp.AccessPolicy"
+    }
+    fn derive_content_class_str(&self) -> &'static str {
+        Self::content_class_str()
+    }
+    fn default_content_format(&self) -> Option<idp_proto::ContentFormat> {
+        None
+    }
+    fn validate_content_format(&self, content_format: &idp_proto::ContentFormat) -> Result<()> {
+        idp_proto::validate_is_serde_format(content_format)
+    }
+}
+
+impl idp_proto::Deserializable for AccessPolicy {
+    fn deserialize_using_format(
+        content_format: &idp_proto::ContentFormat,
+        reader: &mut dyn std::io::Read,
+    ) -> Result<Self> {
+        idp_proto::deserialize_using_serde_format(content_format, reader)
+    }
+}
+
+impl idp_proto::Serializable for AccessPolicy {
+    fn serialize_using_format(
+        &self,
+        content_format: &idp_proto::ContentFormat,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<()> {
+        idp_proto::serialize_using_serde_format(self, content_format, writer)
+    }
+}
+
+impl idp_proto::PlumRelational for AccessPolicy {
+    fn accumulate_plum_relations_nonrecursive(
+        &self,
+        _plum_relation_flags_m: &mut HashMap<PlumHeadSeal, PlumRelationFlags>,
+    ) {
+        // AccessPolicy has no relations.
+    }
+}
@@ -0,0 +1,26 @@
+use crate::CredentialProvider;
+use anyhow::Result;
+
+/// Authenticates using a static bearer token, which the server is configured to accept (see
+/// idp_server::BearerTokenAuthenticator).
+#[derive(Clone)]
+pub struct BearerTokenCredentialProvider {
+    token: String,
+}
+
+impl BearerTokenCredentialProvider {
+    pub fn new(token: String) -> Self {
+        Self { token }
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialProvider for BearerTokenCredentialProvider {
+    async fn authorization(&self, _challenge_o: Option<&str>) -> Result<String> {
+        Ok(format!(
+            "{} {}",
+            idp_proto::BEARER_AUTHORIZATION_SCHEME,
+            self.token
+        ))
+    }
+}
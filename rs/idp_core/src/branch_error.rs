@@ -1,6 +1,6 @@
 use crate::PathStateError;
 use idp_datahost_storage::DatahostStorageError;
-use idp_proto::PlumHeadSeal;
+use idp_proto::{branch_error_detail, BranchErrorDetail, MalformedErrorDetail, PlumHeadSeal};
 
 #[derive(Debug, thiserror::Error)]
 pub enum BranchError {
@@ -36,6 +36,11 @@ pub enum BranchError {
         plum_head_seal: PlumHeadSeal,
         description: String,
     },
+    #[error("Plum {plum_head_seal} was expected to be dependency-complete, but {} Plums were missing or incomplete on this Datahost", incomplete_plum_head_seal_v.len())]
+    PlumIsNotDependencyComplete {
+        plum_head_seal: PlumHeadSeal,
+        incomplete_plum_head_seal_v: Vec<PlumHeadSeal>,
+    },
     #[error("Branch rewind operation expected new branch head ({new_branch_head}) to be an ancestor of current branch head ({current_branch_head})")]
     RewindExpectedAncestor {
         current_branch_head: PlumHeadSeal,
@@ -46,6 +51,11 @@ pub enum BranchError {
         current_branch_head: PlumHeadSeal,
         new_branch_head: PlumHeadSeal,
     },
+    #[error("Branch set-head operation expected current branch head to be {expected_current_branch_head}, but it was {current_branch_head}")]
+    UnexpectedCurrentBranchHead {
+        current_branch_head: PlumHeadSeal,
+        expected_current_branch_head: PlumHeadSeal,
+    },
 }
 
 impl From<DatahostStorageError> for BranchError {
@@ -60,45 +70,191 @@ impl From<PathStateError> for BranchError {
     }
 }
 
-#[cfg(feature = "tonic")]
-impl From<BranchError> for tonic::Status {
-    fn from(branch_error: BranchError) -> Self {
-        // It would be better to serialize the BranchError and deserialize it on the IDPClient side.
-        match branch_error {
-            BranchError::BranchNodeAncestorPlumIsMissing(_) => {
-                tonic::Status::failed_precondition(branch_error.to_string())
+impl From<&BranchError> for BranchErrorDetail {
+    fn from(branch_error: &BranchError) -> Self {
+        use branch_error_detail::{BranchHeads, Value};
+        let branch_heads =
+            |current_branch_head: &PlumHeadSeal, new_branch_head: &PlumHeadSeal| BranchHeads {
+                current_branch_head: current_branch_head.clone(),
+                new_branch_head: new_branch_head.clone(),
+            };
+        let value = match branch_error {
+            BranchError::BranchNodeAncestorPlumIsMissing(plum_head_seal) => {
+                Value::BranchNodeAncestorPlumIsMissing(plum_head_seal.clone())
             }
-            BranchError::BranchNodePlumMustAlreadyExist(_) => {
-                tonic::Status::failed_precondition(branch_error.to_string())
+            BranchError::BranchNodePlumMustAlreadyExist(plum_head_seal) => {
+                Value::BranchNodePlumMustAlreadyExist(plum_head_seal.clone())
             }
             BranchError::DatahostStorageError(datahost_storage_error) => {
-                return datahost_storage_error.into();
+                Value::DatahostStorageError(datahost_storage_error.into())
             }
-            BranchError::FastForwardExpectedDescendant { .. } => {
-                tonic::Status::invalid_argument(branch_error.to_string())
+            BranchError::FastForwardExpectedDescendant {
+                current_branch_head,
+                new_branch_head,
+            } => Value::FastForwardExpectedDescendant(branch_heads(
+                current_branch_head,
+                new_branch_head,
+            )),
+            BranchError::ForkHistoryExpectedCommonAncestor {
+                current_branch_head,
+                new_branch_head,
+            } => Value::ForkHistoryExpectedCommonAncestor(branch_heads(
+                current_branch_head,
+                new_branch_head,
+            )),
+            BranchError::InternalError { description } => Value::InternalError(description.clone()),
+            BranchError::MalformedRequest { description } => {
+                Value::MalformedRequest(description.clone())
             }
-            BranchError::ForkHistoryExpectedCommonAncestor { .. } => {
-                tonic::Status::invalid_argument(branch_error.to_string())
+            BranchError::MaxAncestorDepthReached { lhs, rhs } => {
+                Value::MaxAncestorDepthReached(branch_error_detail::MaxAncestorDepthReached {
+                    lhs: lhs.clone(),
+                    rhs: rhs.clone(),
+                })
             }
-            BranchError::InternalError { .. } => tonic::Status::internal(branch_error.to_string()),
-            BranchError::MalformedRequest { .. } => {
-                tonic::Status::invalid_argument(branch_error.to_string())
+            BranchError::PathStateError(path_state_error) => {
+                Value::PathStateError(path_state_error.into())
             }
-            BranchError::MaxAncestorDepthReached { .. } => {
-                tonic::Status::internal(branch_error.to_string())
+            BranchError::PlumIsNotABranchNode {
+                plum_head_seal,
+                description,
+            } => Value::PlumIsNotABranchNode(branch_error_detail::PlumIsNotABranchNode {
+                plum_head_seal: plum_head_seal.clone(),
+                description: description.clone(),
+            }),
+            BranchError::PlumIsNotDependencyComplete {
+                plum_head_seal,
+                incomplete_plum_head_seal_v,
+            } => Value::PlumIsNotDependencyComplete(
+                branch_error_detail::PlumIsNotDependencyComplete {
+                    plum_head_seal: plum_head_seal.clone(),
+                    incomplete_plum_head_seal_v: incomplete_plum_head_seal_v.clone(),
+                },
+            ),
+            BranchError::RewindExpectedAncestor {
+                current_branch_head,
+                new_branch_head,
+            } => Value::RewindExpectedAncestor(branch_heads(current_branch_head, new_branch_head)),
+            BranchError::TotalRewriteExpectedNoCommonAncestor {
+                current_branch_head,
+                new_branch_head,
+            } => Value::TotalRewriteExpectedNoCommonAncestor(branch_heads(
+                current_branch_head,
+                new_branch_head,
+            )),
+            BranchError::UnexpectedCurrentBranchHead {
+                current_branch_head,
+                expected_current_branch_head,
+            } => Value::UnexpectedCurrentBranchHead(
+                branch_error_detail::UnexpectedCurrentBranchHead {
+                    current_branch_head: current_branch_head.clone(),
+                    expected_current_branch_head: expected_current_branch_head.clone(),
+                },
+            ),
+        };
+        Self { value: Some(value) }
+    }
+}
+
+impl TryFrom<BranchErrorDetail> for BranchError {
+    type Error = MalformedErrorDetail;
+    fn try_from(branch_error_detail: BranchErrorDetail) -> Result<Self, Self::Error> {
+        use branch_error_detail::Value;
+        let value = branch_error_detail.value.ok_or(MalformedErrorDetail {
+            message_name: "BranchErrorDetail",
+        })?;
+        Ok(match value {
+            Value::BranchNodeAncestorPlumIsMissing(plum_head_seal) => {
+                BranchError::BranchNodeAncestorPlumIsMissing(plum_head_seal)
             }
-            BranchError::PathStateError(path_state_error) => {
-                return path_state_error.into();
+            Value::BranchNodePlumMustAlreadyExist(plum_head_seal) => {
+                BranchError::BranchNodePlumMustAlreadyExist(plum_head_seal)
+            }
+            Value::DatahostStorageError(datahost_storage_error_detail) => {
+                BranchError::DatahostStorageError(datahost_storage_error_detail.try_into()?)
+            }
+            Value::FastForwardExpectedDescendant(x) => BranchError::FastForwardExpectedDescendant {
+                current_branch_head: x.current_branch_head,
+                new_branch_head: x.new_branch_head,
+            },
+            Value::ForkHistoryExpectedCommonAncestor(x) => {
+                BranchError::ForkHistoryExpectedCommonAncestor {
+                    current_branch_head: x.current_branch_head,
+                    new_branch_head: x.new_branch_head,
+                }
             }
-            BranchError::PlumIsNotABranchNode { .. } => {
-                tonic::Status::failed_precondition(branch_error.to_string())
+            Value::InternalError(description) => BranchError::InternalError { description },
+            Value::MalformedRequest(description) => BranchError::MalformedRequest { description },
+            Value::MaxAncestorDepthReached(x) => BranchError::MaxAncestorDepthReached {
+                lhs: x.lhs,
+                rhs: x.rhs,
+            },
+            Value::PathStateError(path_state_error_detail) => {
+                BranchError::PathStateError(path_state_error_detail.try_into()?)
             }
-            BranchError::RewindExpectedAncestor { .. } => {
-                tonic::Status::invalid_argument(branch_error.to_string())
+            Value::PlumIsNotABranchNode(x) => BranchError::PlumIsNotABranchNode {
+                plum_head_seal: x.plum_head_seal,
+                description: x.description,
+            },
+            Value::PlumIsNotDependencyComplete(x) => BranchError::PlumIsNotDependencyComplete {
+                plum_head_seal: x.plum_head_seal,
+                incomplete_plum_head_seal_v: x.incomplete_plum_head_seal_v,
+            },
+            Value::RewindExpectedAncestor(x) => BranchError::RewindExpectedAncestor {
+                current_branch_head: x.current_branch_head,
+                new_branch_head: x.new_branch_head,
+            },
+            Value::TotalRewriteExpectedNoCommonAncestor(x) => {
+                BranchError::TotalRewriteExpectedNoCommonAncestor {
+                    current_branch_head: x.current_branch_head,
+                    new_branch_head: x.new_branch_head,
+                }
             }
+            Value::UnexpectedCurrentBranchHead(x) => BranchError::UnexpectedCurrentBranchHead {
+                current_branch_head: x.current_branch_head,
+                expected_current_branch_head: x.expected_current_branch_head,
+            },
+        })
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl BranchError {
+    /// The tonic::Code that best describes this error.
+    pub fn tonic_code(&self) -> tonic::Code {
+        match self {
+            BranchError::BranchNodeAncestorPlumIsMissing(_) => tonic::Code::FailedPrecondition,
+            BranchError::BranchNodePlumMustAlreadyExist(_) => tonic::Code::FailedPrecondition,
+            BranchError::DatahostStorageError(datahost_storage_error) => {
+                datahost_storage_error.tonic_code()
+            }
+            BranchError::FastForwardExpectedDescendant { .. } => tonic::Code::InvalidArgument,
+            BranchError::ForkHistoryExpectedCommonAncestor { .. } => tonic::Code::InvalidArgument,
+            BranchError::InternalError { .. } => tonic::Code::Internal,
+            BranchError::MalformedRequest { .. } => tonic::Code::InvalidArgument,
+            BranchError::MaxAncestorDepthReached { .. } => tonic::Code::Internal,
+            BranchError::PathStateError(path_state_error) => path_state_error.tonic_code(),
+            BranchError::PlumIsNotABranchNode { .. } => tonic::Code::FailedPrecondition,
+            BranchError::PlumIsNotDependencyComplete { .. } => tonic::Code::FailedPrecondition,
+            BranchError::RewindExpectedAncestor { .. } => tonic::Code::InvalidArgument,
             BranchError::TotalRewriteExpectedNoCommonAncestor { .. } => {
-                tonic::Status::invalid_argument(branch_error.to_string())
+                tonic::Code::InvalidArgument
             }
+            // This is a conflict with a concurrent update, so the client may retry.
+            BranchError::UnexpectedCurrentBranchHead { .. } => tonic::Code::Aborted,
+        }
+    }
+}
+
+/// The resulting tonic::Status carries an ErrorDetail, from which IDPClient reconstructs this error.
+#[cfg(feature = "tonic")]
+impl From<BranchError> for tonic::Status {
+    fn from(branch_error: BranchError) -> Self {
+        idp_proto::ErrorDetail {
+            value: Some(idp_proto::error_detail::Value::BranchError(
+                (&branch_error).into(),
+            )),
         }
+        .into_status(branch_error.tonic_code(), branch_error.to_string())
     }
 }
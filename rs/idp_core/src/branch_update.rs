@@ -0,0 +1,17 @@
+/// Describes how IDPClient::push_branch updated the branch on the server.  Other than Created and
+/// Unchanged, these correspond to the BranchSetHeadRequest operations.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BranchUpdate {
+    /// The branch didn't exist on the server, so it was created.
+    Created,
+    /// The branch head on the server was already the pushed branch head.
+    Unchanged,
+    /// The pushed branch head was a descendant of the branch head on the server.
+    FastForwarded,
+    /// The pushed branch head was an ancestor of the branch head on the server.
+    Rewound,
+    /// The pushed branch head and the branch head on the server had diverged from a common ancestor.
+    ForkedHistory,
+    /// The pushed branch head and the branch head on the server had no common ancestor.
+    TotallyRewritten,
+}
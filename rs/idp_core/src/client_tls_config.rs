@@ -0,0 +1,160 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio_rustls::rustls;
+
+/// TLS configuration for IDPClient (see IDPClient::connect), which is needed in order to connect to
+/// an "https://" URL.
+#[derive(Clone, Debug)]
+pub struct ClientTLSConfig {
+    /// PEM-encoded CA certificates, any of which may have issued the server's certificate.
+    ca_bundle_pem: Vec<u8>,
+    /// If true, then the server's certificate may also have been issued by any of the well-known
+    /// public CAs (see ClientTLSConfig::with_system_roots).
+    use_system_roots: bool,
+    /// PEM-encoded certificate chain and private key that the client presents to the server, for
+    /// servers that require mutual TLS (mTLS).
+    client_cert_and_key_pem_o: Option<(Vec<u8>, Vec<u8>)>,
+    /// The domain name which the server's certificate is verified against, if it's not the host of
+    /// the URL (e.g. when connecting using an IP address).
+    domain_name_o: Option<String>,
+}
+
+impl ClientTLSConfig {
+    /// The server's certificate must have been issued by one of the CAs in the given PEM-encoded
+    /// CA bundle.
+    pub fn new(ca_bundle_pem: Vec<u8>) -> Self {
+        Self {
+            ca_bundle_pem,
+            use_system_roots: false,
+            client_cert_and_key_pem_o: None,
+            domain_name_o: None,
+        }
+    }
+    /// The server's certificate must have been issued by one of the well-known public CAs (see
+    /// ClientTLSConfig::with_system_roots), as is the case for most servers on the internet.
+    pub fn system_roots() -> Self {
+        Self::new(Vec::new()).with_system_roots()
+    }
+    /// Also trusts the well-known public CAs, i.e. the root certificates of Mozilla's CA program, as
+    /// bundled by the webpki-roots crate (so they don't depend on the OS's certificate store).
+    pub fn with_system_roots(mut self) -> Self {
+        self.use_system_roots = true;
+        self
+    }
+    /// Presents the given PEM-encoded certificate chain and (PKCS8 or RSA) private key to the server,
+    /// as required by servers that use mutual TLS (mTLS).
+    pub fn with_client_cert_and_key(
+        mut self,
+        client_cert_pem: Vec<u8>,
+        client_key_pem: Vec<u8>,
+    ) -> Self {
+        self.client_cert_and_key_pem_o = Some((client_cert_pem, client_key_pem));
+        self
+    }
+    /// Verifies the server's certificate against the given domain name instead of the host of the URL.
+    pub fn with_domain_name(mut self, domain_name: String) -> Self {
+        self.domain_name_o = Some(domain_name);
+        self
+    }
+    /// Connects to the given URL over TLS using this ClientTLSConfig.
+    pub(crate) async fn connect(&self, url: String) -> Result<tonic::transport::Channel> {
+        if !self.use_system_roots {
+            return Ok(tonic::transport::Endpoint::from_shared(url)?
+                .tls_config(self.to_tonic_client_tls_config())?
+                .connect()
+                .await?);
+        }
+        // tonic can only trust the system roots via a crate feature, which would make every
+        // ClientTlsConfig in the build trust them, so in this case the TLS connection is made here.
+        // Because tonic refuses to connect to an "https://" URL without its own TLS, the Endpoint
+        // has the corresponding "http://" URL, and the original URL is used as the requests' origin.
+        let uri: tonic::transport::Uri = url.parse()?;
+        let url_host = uri
+            .host()
+            .ok_or_else(|| anyhow::anyhow!("URL {} has no host", uri))?
+            .to_string();
+        // IPv6 addresses are bracketed in URLs.
+        let host = url_host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = uri.port_u16().unwrap_or(443);
+        let server_name =
+            rustls::ServerName::try_from(self.domain_name_o.as_deref().unwrap_or(&host))?;
+        // The connector ignores the Endpoint's URL, so its port doesn't matter.
+        let endpoint =
+            tonic::transport::Endpoint::from_shared(format!("http://{}", url_host))?.origin(uri);
+        let tls_connector =
+            tokio_rustls::TlsConnector::from(Arc::new(self.to_rustls_client_config()?));
+        let connector = tower::service_fn(move |_: tonic::transport::Uri| {
+            let tls_connector = tls_connector.clone();
+            let server_name = server_name.clone();
+            let host = host.clone();
+            async move {
+                let tcp_stream = tokio::net::TcpStream::connect((host.as_str(), port)).await?;
+                tcp_stream.set_nodelay(true)?;
+                let tls_stream = tls_connector.connect(server_name, tcp_stream).await?;
+                if tls_stream.get_ref().1.alpn_protocol() != Some(b"h2".as_slice()) {
+                    anyhow::bail!("server at {}:{} did not negotiate HTTP/2", host, port);
+                }
+                Ok(tls_stream)
+            }
+        });
+        Ok(endpoint.connect_with_connector(connector).await?)
+    }
+    fn to_rustls_client_config(&self) -> Result<rustls::ClientConfig> {
+        let mut root_cert_store = rustls::RootCertStore::empty();
+        if self.use_system_roots {
+            root_cert_store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(
+                |trust_anchor| {
+                    rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                        trust_anchor.subject,
+                        trust_anchor.spki,
+                        trust_anchor.name_constraints,
+                    )
+                },
+            ));
+        }
+        for ca_cert_der in rustls_pemfile::certs(&mut self.ca_bundle_pem.as_slice())? {
+            root_cert_store.add(&rustls::Certificate(ca_cert_der))?;
+        }
+        let client_config_builder = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_cert_store);
+        let mut client_config = match self.client_cert_and_key_pem_o.as_ref() {
+            Some((client_cert_pem, client_key_pem)) => {
+                let client_cert_v = rustls_pemfile::certs(&mut client_cert_pem.as_slice())?
+                    .into_iter()
+                    .map(rustls::Certificate)
+                    .collect();
+                let client_key = rustls_pemfile::read_all(&mut client_key_pem.as_slice())?
+                    .into_iter()
+                    .find_map(|item| match item {
+                        rustls_pemfile::Item::PKCS8Key(key_der)
+                        | rustls_pemfile::Item::RSAKey(key_der)
+                        | rustls_pemfile::Item::ECKey(key_der) => Some(rustls::PrivateKey(key_der)),
+                        _ => None,
+                    })
+                    .ok_or_else(|| anyhow::anyhow!("client key PEM contains no private key"))?;
+                client_config_builder.with_single_cert(client_cert_v, client_key)?
+            }
+            None => client_config_builder.with_no_client_auth(),
+        };
+        client_config.alpn_protocols = vec![b"h2".to_vec()];
+        Ok(client_config)
+    }
+    fn to_tonic_client_tls_config(&self) -> tonic::transport::ClientTlsConfig {
+        let mut client_tls_config = tonic::transport::ClientTlsConfig::new()
+            .ca_certificate(tonic::transport::Certificate::from_pem(&self.ca_bundle_pem));
+        if let Some((client_cert_pem, client_key_pem)) = self.client_cert_and_key_pem_o.as_ref() {
+            client_tls_config = client_tls_config.identity(tonic::transport::Identity::from_pem(
+                client_cert_pem,
+                client_key_pem,
+            ));
+        }
+        if let Some(domain_name) = self.domain_name_o.as_ref() {
+            client_tls_config = client_tls_config.domain_name(domain_name);
+        }
+        client_tls_config
+    }
+}
@@ -0,0 +1,52 @@
+use idp_proto::PlumHeadSeal;
+use std::collections::BTreeMap;
+
+/// Describes which components of a particular Plum are present in a Datahost.  If the PlumHead
+/// isn't present, then the seals of the other components aren't known, so they're all reported
+/// as not present.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PlumCompleteness {
+    pub has_plum_head: bool,
+    pub has_plum_metadata: bool,
+    pub has_plum_relations: bool,
+    pub has_plum_body: bool,
+}
+
+impl PlumCompleteness {
+    /// Returns true if all components of the Plum are present.
+    pub fn is_complete(&self) -> bool {
+        self.has_plum_head
+            && self.has_plum_metadata
+            && self.has_plum_relations
+            && self.has_plum_body
+    }
+}
+
+/// Describes the result of Datahost::check_completeness, i.e. which components of each Plum in the
+/// relation DAG rooted at root_plum_head_seal are present.  Note that if a Plum's PlumHead or
+/// PlumRelations aren't present, then the Plums it refers to can't be determined, so they won't
+/// appear in plum_completeness_m.  Thus pulling the missing pieces and checking again may reveal
+/// further missing Plums.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompletenessReport {
+    pub root_plum_head_seal: PlumHeadSeal,
+    /// Contains an entry for each Plum that was reached, including root_plum_head_seal.
+    pub plum_completeness_m: BTreeMap<PlumHeadSeal, PlumCompleteness>,
+}
+
+impl CompletenessReport {
+    /// Returns true if every Plum that was reached is complete.
+    pub fn is_complete(&self) -> bool {
+        self.plum_completeness_m
+            .values()
+            .all(|plum_completeness| plum_completeness.is_complete())
+    }
+    /// Returns the (sorted) PlumHeadSeals of the Plums which are missing at least one component.
+    pub fn incomplete_plum_head_seal_v(&self) -> Vec<PlumHeadSeal> {
+        self.plum_completeness_m
+            .iter()
+            .filter(|(_, plum_completeness)| !plum_completeness.is_complete())
+            .map(|(plum_head_seal, _)| plum_head_seal.clone())
+            .collect()
+    }
+}
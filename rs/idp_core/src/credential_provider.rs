@@ -0,0 +1,11 @@
+use anyhow::Result;
+
+/// Supplies the credentials that IDPClient attaches to each of its requests to an IDPServer, i.e. the
+/// value of the request's "authorization" metadata entry (see idp_proto::AUTHORIZATION_METADATA_KEY).
+#[async_trait::async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Returns the authorization value to send.  If the server rejected the previous authorization value
+    /// and issued a challenge, then that challenge is given in challenge_o, and the returned value
+    /// should answer it.
+    async fn authorization(&self, challenge_o: Option<&str>) -> Result<String>;
+}
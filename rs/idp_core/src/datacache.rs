@@ -30,6 +30,9 @@ pub struct Datacache {
     // so that it's possible to do smart, non-total cache clearing (a kind of approximate garbage
     // collection).  TODO: Rename to cached_value_mla
     cached_value_mla: Arc<RwLock<HashMap<PlumHeadSeal, UntypedValue>>>,
+    /// Used when pulling from a remote whose PlumURI calls for TLS (see PlumURIRemote::remote_server_url).
+    #[cfg(feature = "client")]
+    client_tls_config_o: Option<crate::ClientTLSConfig>,
 }
 
 impl Datacache {
@@ -39,8 +42,16 @@ impl Datacache {
         Self {
             datahost_la,
             cached_value_mla,
+            #[cfg(feature = "client")]
+            client_tls_config_o: None,
         }
     }
+    /// Uses the given ClientTLSConfig when pulling from remotes over TLS.
+    #[cfg(feature = "client")]
+    pub fn with_client_tls_config(mut self, client_tls_config: crate::ClientTLSConfig) -> Self {
+        self.client_tls_config_o = Some(client_tls_config);
+        self
+    }
     /// If the given value (indexed by its PlumHeadSeal) already exists in this Datacache, return
     /// it.  Otherwise load the Plum it refers to, deserialize its body as T, put that value in
     /// an Arc<T>, store a copy of that Arc<T> in this Datacache's cached values, and then return
@@ -93,6 +104,8 @@ impl Datacache {
                             let mut idp_client = crate::IDPClient::connect(
                                 plum_uri_remote.remote_server_url(),
                                 self.datahost_la.clone(),
+                                None,
+                                self.client_tls_config_o.as_ref(),
                             )
                             .await?;
                             idp_client.pull(&plum_uri_remote.plum_head_seal).await?;
@@ -1,25 +1,87 @@
+use crate::dependency_complete_cache::DependencyCompleteCache;
+use crate::relation_traversal::topologically_sorted;
 use crate::{
-    BranchError, BranchNode, DirNode, FragmentQueryResult, FragmentQueryable,
-    LoadPlumAndDeserializeError, PathStateError,
+    BranchError, BranchNode, BranchUpdate, CompletenessReport, DirNode, FragmentQueryResult,
+    FragmentQueryable, GarbageCollectionReport, LoadPlumAndDeserializeError, PathStateError,
+    PlumCompleteness, RelationTraversal, RelationTraversalLimits,
 };
 use anyhow::Result;
-use idp_datahost_storage::{DatahostStorage, DatahostStorageError, DatahostStorageTransaction};
+use futures::{stream::BoxStream, TryStreamExt};
+use idp_datahost_storage::{
+    DatahostStorage, DatahostStorageError, DatahostStorageTransaction, PathStatesFilter,
+    PlumFilter, PlumHeadsFilter, SelectCursor, SelectPage,
+};
 use idp_proto::{
-    BranchSetHeadRequest, Path, PathState, Plum, PlumBody, PlumBodySeal, PlumHead, PlumHeadSeal,
-    PlumRelationFlags, PlumRelations, PlumRelationsSeal, UnixNanoseconds,
+    path_state_change, BranchSetHeadRequest, Path, PathState, PathStateChange, Plum, PlumBody,
+    PlumBodySeal, PlumHead, PlumHeadMetadataAndRelations, PlumHeadSeal, PlumRelationFlags,
+    PlumRelations, PlumRelationsSeal, SealKeyProvider, UnixNanoseconds,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    convert::TryFrom,
+    sync::{Arc, Mutex},
 };
-use std::{collections::HashMap, convert::TryFrom};
+
+/// The number of rows retrieved per page by the select methods.
+const SELECT_PAGE_SIZE: u32 = 256;
+/// The number of PathStateChange-s that a subscriber (see Datahost::subscribe_to_path_state_changes)
+/// can fall behind by before it's unsubscribed.
+const PATH_STATE_CHANGE_CHANNEL_CAPACITY: usize = 1024;
+/// The default for Datahost::with_garbage_collection_grace_period.
+const DEFAULT_GARBAGE_COLLECTION_GRACE_PERIOD: std::time::Duration =
+    std::time::Duration::from_secs(60 * 60);
 
 pub struct Datahost {
     datahost_storage_b: Box<dyn DatahostStorage>,
+    /// See Datahost::subscribe_to_path_state_changes.  This is shared with the on-commit callbacks
+    /// that send the PathStateChange-s (see DatahostStorageTransaction::on_commit).
+    path_state_change_sender_vla: Arc<Mutex<Vec<futures::channel::mpsc::Sender<PathStateChange>>>>,
+    /// See Datahost::ensure_dependency_complete.  This is shared with the on-commit callbacks that
+    /// update it (see DatahostStorageTransaction::on_commit).
+    dependency_complete_cache_la: Arc<Mutex<DependencyCompleteCache>>,
+    /// See Datahost::seal_key_provider.
+    seal_key_provider_ao: Option<Arc<dyn SealKeyProvider + Send + Sync>>,
+    /// See Datahost::with_garbage_collection_grace_period.
+    garbage_collection_grace_period: std::time::Duration,
 }
 
 impl Datahost {
     pub fn open(datahost_storage: impl DatahostStorage + 'static) -> Self {
         Self {
             datahost_storage_b: Box::new(datahost_storage),
+            path_state_change_sender_vla: Arc::new(Mutex::new(Vec::new())),
+            dependency_complete_cache_la: Arc::new(Mutex::new(DependencyCompleteCache::default())),
+            seal_key_provider_ao: None,
+            garbage_collection_grace_period: DEFAULT_GARBAGE_COLLECTION_GRACE_PERIOD,
         }
     }
+    /// Uses the SealKey-s from the given SealKeyProvider to verify the keyed seals of Plums received
+    /// from elsewhere (see Datahost::seal_key_provider).
+    pub fn with_seal_key_provider(
+        mut self,
+        seal_key_provider_a: Arc<dyn SealKeyProvider + Send + Sync>,
+    ) -> Self {
+        self.seal_key_provider_ao = Some(seal_key_provider_a);
+        self
+    }
+    /// Datahost::garbage_collect doesn't remove any PlumHead that was stored within this long before it
+    /// runs, nor any Plum reachable from such a PlumHead.  Plums are stored (e.g. pushed by an
+    /// IDPClient) before a PathState refers to them (e.g. via BranchSetHead), and this keeps them from
+    /// being removed in between.  The default is one hour.
+    pub fn with_garbage_collection_grace_period(
+        mut self,
+        garbage_collection_grace_period: std::time::Duration,
+    ) -> Self {
+        self.garbage_collection_grace_period = garbage_collection_grace_period;
+        self
+    }
+    /// The SealKeyProvider with which Plums received from elsewhere (e.g. pushed by an IDPClient, or
+    /// pulled from a peer) are verified, using Plum::verify_with_seal_key_provider.  A received keyed
+    /// seal whose SealKey isn't provided is rejected, since it can't be checked.  In particular, if
+    /// Datahost::with_seal_key_provider wasn't used, then every received keyed seal is rejected.
+    pub fn seal_key_provider(&self) -> &dyn SealKeyProvider {
+        &self.seal_key_provider_ao
+    }
 
     pub async fn begin_transaction(
         &self,
@@ -95,37 +157,188 @@ impl Datahost {
         tx.finish().await?;
         Ok(has_plum)
     }
-    pub async fn select_plum_heads(
+    /// Bulk version of has_plum.  The returned Vec corresponds elementwise with plum_head_seal_v.
+    /// The PlumHeads, and then each kind of component, are checked in batches, so the number of
+    /// storage round trips doesn't depend on the number of Plums.
+    pub async fn has_plum_v(
         &self,
+        plum_head_seal_v: &[PlumHeadSeal],
         transaction_o: Option<&mut dyn DatahostStorageTransaction>,
-    ) -> Result<Vec<(UnixNanoseconds, PlumHeadSeal, PlumHead)>> {
+    ) -> Result<Vec<bool>> {
         // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
         let mut tx =
             EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
                 .await?;
-        let query_v = self
+        let plum_head_ov = self
             .datahost_storage_b
-            .select_plum_heads(tx.as_mut())
+            .load_option_plum_head_v(tx.as_mut(), plum_head_seal_v)
             .await?;
+        let plum_head_v = plum_head_ov.iter().flatten().collect::<Vec<_>>();
+        let plum_metadata_seal_v = plum_head_v
+            .iter()
+            .map(|plum_head| plum_head.plum_metadata_seal.clone())
+            .collect::<Vec<_>>();
+        let plum_relations_seal_v = plum_head_v
+            .iter()
+            .map(|plum_head| plum_head.plum_relations_seal.clone())
+            .collect::<Vec<_>>();
+        let plum_body_seal_v = plum_head_v
+            .iter()
+            .map(|plum_head| plum_head.plum_body_seal.clone())
+            .collect::<Vec<_>>();
+        // These correspond elementwise with plum_head_v.
+        let mut has_plum_metadata_i = self
+            .datahost_storage_b
+            .has_plum_metadata_v(tx.as_mut(), &plum_metadata_seal_v)
+            .await?
+            .into_iter();
+        let mut has_plum_relations_i = self
+            .datahost_storage_b
+            .has_plum_relations_v(tx.as_mut(), &plum_relations_seal_v)
+            .await?
+            .into_iter();
+        let mut has_plum_body_i = self
+            .datahost_storage_b
+            .has_plum_body_v(tx.as_mut(), &plum_body_seal_v)
+            .await?
+            .into_iter();
         tx.finish().await?;
-        Ok(query_v)
+
+        Ok(plum_head_ov
+            .iter()
+            .map(|plum_head_o| match plum_head_o {
+                Some(_) => {
+                    // Each iterator must be advanced for every present PlumHead.
+                    let has_plum_metadata = has_plum_metadata_i.next().unwrap();
+                    let has_plum_relations = has_plum_relations_i.next().unwrap();
+                    let has_plum_body = has_plum_body_i.next().unwrap();
+                    has_plum_metadata && has_plum_relations && has_plum_body
+                }
+                None => false,
+            })
+            .collect())
+    }
+    /// Returns a stream of row_inserted_at, PlumHeadSeal, and PlumHead for the PlumHeads matching
+    /// the given filter, ordered by row_inserted_at.  The rows are retrieved a page at a time.  If
+    /// transaction_o is None, then a transaction is begun upon the first poll of the stream, and is
+    /// committed once the stream is exhausted.
+    pub fn select_plum_heads<'a>(
+        &'a self,
+        filter: PlumHeadsFilter,
+        transaction_o: Option<&'a mut dyn DatahostStorageTransaction>,
+    ) -> BoxStream<'a, Result<(UnixNanoseconds, PlumHeadSeal, PlumHead)>> {
+        // The EnsuredTransaction can't be created until the first page is selected, so transaction_o is
+        // held in the state until then.  The EnsuredTransaction is finished upon selecting the last page.
+        idp_datahost_storage::paginate(
+            (transaction_o, None, filter),
+            move |(transaction_o, tx_o, filter): (_, Option<EnsuredTransaction<'a>>, _),
+                  cursor_o| async move {
+                let mut tx = self.ensure_select_transaction(transaction_o, tx_o).await?;
+                let select_page = self
+                    .datahost_storage_b
+                    .select_plum_heads_page(
+                        tx.as_mut(),
+                        &filter,
+                        cursor_o.as_ref(),
+                        SELECT_PAGE_SIZE,
+                    )
+                    .await?;
+                if select_page.next_cursor_o.is_none() {
+                    tx.finish().await?;
+                    return Ok(((None, None, filter), select_page));
+                }
+                Ok(((None, Some(tx), filter), select_page))
+            },
+        )
     }
-    /// Returns row_inserted_at, row_updated_at, and PathState.
+    /// Returns a stream of row_inserted_at, row_updated_at, and PathState for the PathStates matching
+    /// the given filter, ordered by row_updated_at.  The transaction handling is as in select_plum_heads.
     // TODO: Consider returning PathStateRow or some other struct instead of a tuple.
-    pub async fn select_path_states(
+    pub fn select_path_states<'a>(
+        &'a self,
+        filter: PathStatesFilter,
+        transaction_o: Option<&'a mut dyn DatahostStorageTransaction>,
+    ) -> BoxStream<'a, Result<(UnixNanoseconds, UnixNanoseconds, PathState)>> {
+        // See select_plum_heads.
+        idp_datahost_storage::paginate(
+            (transaction_o, None, filter),
+            move |(transaction_o, tx_o, filter): (_, Option<EnsuredTransaction<'a>>, _),
+                  cursor_o| async move {
+                let mut tx = self.ensure_select_transaction(transaction_o, tx_o).await?;
+                let select_page = self
+                    .datahost_storage_b
+                    .select_path_states_page(
+                        tx.as_mut(),
+                        &filter,
+                        cursor_o.as_ref(),
+                        SELECT_PAGE_SIZE,
+                    )
+                    .await?;
+                if select_page.next_cursor_o.is_none() {
+                    tx.finish().await?;
+                    return Ok(((None, None, filter), select_page));
+                }
+                Ok(((None, Some(tx), filter), select_page))
+            },
+        )
+    }
+    /// Used by select_plum_heads and select_path_states to begin the EnsuredTransaction (if tx_o is
+    /// None) when the first page is selected.
+    async fn ensure_select_transaction<'a>(
+        &'a self,
+        transaction_o: Option<&'a mut dyn DatahostStorageTransaction>,
+        tx_o: Option<EnsuredTransaction<'a>>,
+    ) -> Result<EnsuredTransaction<'a>> {
+        match tx_o {
+            Some(tx) => Ok(tx),
+            None => {
+                // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+                Ok(EnsuredTransaction::new(
+                    transaction_o,
+                    self.datahost_storage_b.begin_transaction(),
+                )
+                .await?)
+            }
+        }
+    }
+    /// Returns one page (of at most limit rows) of the PathStates matching the given filter, in the
+    /// order of select_path_states, beginning immediately after the given cursor, if specified.  This
+    /// is for callers that need to paginate across calls (e.g. IDPServer), rather than stream.
+    pub async fn select_path_states_page(
         &self,
+        filter: &PathStatesFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
         transaction_o: Option<&mut dyn DatahostStorageTransaction>,
-    ) -> Result<Vec<(UnixNanoseconds, UnixNanoseconds, PathState)>> {
+    ) -> Result<SelectPage<(UnixNanoseconds, UnixNanoseconds, PathState)>> {
         // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
         let mut tx =
             EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
                 .await?;
-        let query_v = self
+        let select_page = self
             .datahost_storage_b
-            .select_path_states(tx.as_mut())
+            .select_path_states_page(tx.as_mut(), filter, cursor_o, limit)
             .await?;
         tx.finish().await?;
-        Ok(query_v)
+        Ok(select_page)
+    }
+    /// Returns the PlumHeadSeals of the Plums matching the given filter (e.g. by content class, content
+    /// format, or creation time), in the order that their PlumHeads were inserted.
+    pub async fn select_plum_head_seals_matching(
+        &self,
+        filter: &PlumFilter,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Vec<PlumHeadSeal>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let plum_head_seal_v = self
+            .datahost_storage_b
+            .select_plum_head_seals_matching(tx.as_mut(), filter)
+            .await?;
+        tx.finish().await?;
+        Ok(plum_head_seal_v)
     }
     pub async fn store_plum_head(
         &self,
@@ -149,14 +362,17 @@ impl Datahost {
         Ok(plum_head_seal)
     }
 
+    /// Stores the PlumRelations under the given PlumRelationsSeal, after verifying it (a keyed seal can't be
+    /// verified without its SealKey; see DatahostStorage::store_plum_metadata).
     pub async fn store_plum_relations(
         &self,
         plum_relations: &PlumRelations,
+        plum_relations_seal: &PlumRelationsSeal,
         transaction_o: Option<&mut dyn DatahostStorageTransaction>,
     ) -> Result<PlumRelationsSeal> {
         log::trace!(
             "Datahost::store_plum_relations; PlumRelationsSeal is {}",
-            PlumRelationsSeal::from(plum_relations)
+            plum_relations_seal
         );
 
         // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
@@ -165,20 +381,23 @@ impl Datahost {
                 .await?;
         let plum_relations_seal = self
             .datahost_storage_b
-            .store_plum_relations(tx.as_mut(), plum_relations)
+            .store_plum_relations(tx.as_mut(), plum_relations, plum_relations_seal)
             .await?;
         tx.finish().await?;
         Ok(plum_relations_seal)
     }
 
+    /// Stores the PlumBody under the given PlumBodySeal, after verifying it (a keyed seal can't be
+    /// verified without its SealKey; see DatahostStorage::store_plum_metadata).
     pub async fn store_plum_body(
         &self,
         plum_body: &PlumBody,
+        plum_body_seal: &PlumBodySeal,
         transaction_o: Option<&mut dyn DatahostStorageTransaction>,
     ) -> Result<PlumBodySeal> {
         log::trace!(
             "Datahost::store_plum_body; PlumBodySeal is {}",
-            PlumBodySeal::from(plum_body)
+            plum_body_seal
         );
 
         // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
@@ -187,7 +406,7 @@ impl Datahost {
                 .await?;
         let plum_body_seal = self
             .datahost_storage_b
-            .store_plum_body(tx.as_mut(), plum_body)
+            .store_plum_body(tx.as_mut(), plum_body, plum_body_seal)
             .await?;
         tx.finish().await?;
         Ok(plum_body_seal)
@@ -213,6 +432,67 @@ impl Datahost {
         tx.finish().await?;
         Ok(plum_head_seal)
     }
+    /// Stores all the given Plums, which is much more efficient than calling store_plum on each one
+    /// (e.g. when importing a large number of Plums).  The returned Vec corresponds elementwise with
+    /// plum_v.
+    pub async fn store_plum_v(
+        &self,
+        plum_v: &[Plum],
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Vec<PlumHeadSeal>> {
+        log::debug!("Datahost::store_plum_v; storing {} Plums", plum_v.len());
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let plum_head_seal_v = self
+            .datahost_storage_b
+            .store_plum_v(tx.as_mut(), plum_v)
+            .await?;
+        tx.finish().await?;
+        Ok(plum_head_seal_v)
+    }
+
+    /// Stores everything in a Plum except for its PlumBody, after verifying it.  This is for when the
+    /// PlumBody is already present (e.g. because it's shared with another Plum), or isn't wanted (e.g.
+    /// for a "shallow" pull).  The PlumHead is stored last.
+    pub async fn store_plum_head_metadata_and_relations(
+        &self,
+        plum_head_metadata_and_relations: &PlumHeadMetadataAndRelations,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<PlumHeadSeal> {
+        log::trace!(
+            "Datahost::store_plum_head_metadata_and_relations; PlumHeadSeal is {}",
+            PlumHeadSeal::from(&plum_head_metadata_and_relations.plum_head)
+        );
+        plum_head_metadata_and_relations.verify()?;
+        let plum_head = &plum_head_metadata_and_relations.plum_head;
+
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        self.datahost_storage_b
+            .store_plum_metadata(
+                tx.as_mut(),
+                &plum_head_metadata_and_relations.plum_metadata,
+                &plum_head.plum_metadata_seal,
+            )
+            .await?;
+        self.datahost_storage_b
+            .store_plum_relations(
+                tx.as_mut(),
+                &plum_head_metadata_and_relations.plum_relations,
+                &plum_head.plum_relations_seal,
+            )
+            .await?;
+        let plum_head_seal = self
+            .datahost_storage_b
+            .store_plum_head(tx.as_mut(), plum_head)
+            .await?;
+        tx.finish().await?;
+        Ok(plum_head_seal)
+    }
 
     /// If the specified PlumHead doesn't exist in this Datahost, returns error.
     pub async fn load_plum_head(
@@ -266,6 +546,110 @@ impl Datahost {
         tx.finish().await?;
         Ok(plum)
     }
+    /// Loads all the specified Plums.  If any of them isn't fully present in this Datahost, returns error.
+    /// The returned Vec corresponds elementwise with plum_head_seal_v.
+    pub async fn load_plum_v(
+        &self,
+        plum_head_seal_v: &[PlumHeadSeal],
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Vec<Plum>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let plum_v = self
+            .datahost_storage_b
+            .load_plum_v(tx.as_mut(), plum_head_seal_v)
+            .await?;
+        tx.finish().await?;
+        Ok(plum_v)
+    }
+    /// If any of the PlumHead, PlumMetadata, or PlumRelations for the specified Plum doesn't exist in
+    /// this Datahost, returns None.  The PlumBody need not be present.
+    pub async fn load_option_plum_head_metadata_and_relations(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Option<PlumHeadMetadataAndRelations>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let plum_head_metadata_and_relations_o = match self
+            .datahost_storage_b
+            .load_option_plum_head(tx.as_mut(), plum_head_seal)
+            .await?
+        {
+            Some(plum_head) => {
+                let plum_metadata_o = self
+                    .datahost_storage_b
+                    .load_option_plum_metadata(tx.as_mut(), &plum_head.plum_metadata_seal)
+                    .await?;
+                let plum_relations_o = self
+                    .datahost_storage_b
+                    .load_option_plum_relations(tx.as_mut(), &plum_head.plum_relations_seal)
+                    .await?;
+                match (plum_metadata_o, plum_relations_o) {
+                    (Some(plum_metadata), Some(plum_relations)) => {
+                        Some(PlumHeadMetadataAndRelations {
+                            plum_head,
+                            plum_metadata,
+                            plum_relations,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        tx.finish().await?;
+        Ok(plum_head_metadata_and_relations_o)
+    }
+    /// Loads the PlumHead, PlumMetadata, and PlumRelations of each of the specified Plums.  If any of
+    /// them isn't present in this Datahost, returns error.  The PlumBody-s need not be present.  The
+    /// returned Vec corresponds elementwise with plum_head_seal_v.
+    pub async fn load_plum_head_metadata_and_relations_v(
+        &self,
+        plum_head_seal_v: &[PlumHeadSeal],
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Vec<PlumHeadMetadataAndRelations>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let mut plum_head_metadata_and_relations_v = Vec::with_capacity(plum_head_seal_v.len());
+        for plum_head_seal in plum_head_seal_v.iter() {
+            let plum_head_metadata_and_relations = self
+                .load_option_plum_head_metadata_and_relations(plum_head_seal, Some(tx.as_mut()))
+                .await?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "PlumHead, PlumMetadata, or PlumRelations of Plum {} not found",
+                        plum_head_seal
+                    )
+                })?;
+            plum_head_metadata_and_relations_v.push(plum_head_metadata_and_relations);
+        }
+        tx.finish().await?;
+        Ok(plum_head_metadata_and_relations_v)
+    }
+    /// If the specified PlumBody doesn't exist in this Datahost, returns None.
+    pub async fn load_option_plum_body(
+        &self,
+        plum_body_seal: &PlumBodySeal,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Option<PlumBody>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let plum_body_o = self
+            .datahost_storage_b
+            .load_option_plum_body(tx.as_mut(), plum_body_seal)
+            .await?;
+        tx.finish().await?;
+        Ok(plum_body_o)
+    }
     /// Load the specified Plum, check the PlumBody's ContentType against that expected by T,
     /// and then deserialize the PlumBody content into T.
     // TODO: Consider having it return the Plum or PlumHead as well, potentially deserializing
@@ -287,129 +671,723 @@ impl Datahost {
     // Methods for determining plum_relations between Plums
     //
 
+    /// Returns all Plums that the given Plum refers to, either directly or indirectly, via relations
+    /// whose PlumRelationFlags intersect mask.  Each such Plum maps to the union of the masked
+    /// PlumRelationFlags of the relations to it.  If any traversed Plum's PlumHead or PlumRelations
+    /// aren't present in this Datahost, then this returns an error; see check_completeness.
     pub async fn accumulated_relations_recursive(
         &self,
         plum_head_seal: &PlumHeadSeal,
         mask: PlumRelationFlags,
         transaction_o: Option<&mut dyn DatahostStorageTransaction>,
     ) -> Result<HashMap<PlumHeadSeal, PlumRelationFlags>> {
+        Ok(self
+            .traverse_relations(
+                plum_head_seal,
+                mask,
+                RelationTraversalLimits::default(),
+                transaction_o,
+            )
+            .await?
+            .plum_relation_flags_m)
+    }
+
+    /// Does a breadth-first traversal of the relation DAG rooted at the given Plum, following relations
+    /// whose PlumRelationFlags intersect mask, subject to the given limits.  The returned
+    /// RelationTraversal also gives the traversed Plums in topological order.  If any traversed Plum's
+    /// PlumHead or PlumRelations aren't present in this Datahost, then this returns an error.
+    pub async fn traverse_relations(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        mask: PlumRelationFlags,
+        limits: RelationTraversalLimits,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<RelationTraversal> {
         // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
         let mut tx =
             EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
                 .await?;
-        let mut plum_relation_flags_m = HashMap::new();
-        self.accumulate_relations_recursive_impl(
-            tx.as_mut(),
-            plum_head_seal,
-            mask,
-            &mut plum_relation_flags_m,
-        )
-        .await?;
+        let relation_traversal = self
+            .traverse_relations_impl(
+                tx.as_mut(),
+                std::slice::from_ref(plum_head_seal),
+                mask,
+                limits,
+            )
+            .await?;
         tx.finish().await?;
-        Ok(plum_relation_flags_m)
+        Ok(relation_traversal)
     }
 
-    /// The reason this is Pin<Box<dyn Future<Output = ...>>> is to allow recursive async calls.
-    /// See `rustc --explain E0733` for an explanation.  The explicit lifetimes are needed, see:
-    /// https://stackoverflow.com/questions/59538812/why-recursive-async-functions-require-static-parameters-in-rust
-    fn accumulate_relations_recursive_impl<'a>(
-        &'a self,
-        transaction: &'a mut dyn DatahostStorageTransaction,
-        plum_head_seal: &'a PlumHeadSeal,
+    /// Traverses the relation DAG a level (i.e. depth) at a time, starting from all the given roots,
+    /// so that the PlumHeads and PlumRelations for each level can be loaded in a batch.
+    async fn traverse_relations_impl(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        root_plum_head_seal_v: &[PlumHeadSeal],
         mask: PlumRelationFlags,
-        plum_relation_flags_m: &'a mut HashMap<PlumHeadSeal, PlumRelationFlags>,
-        // TODO: Need some way of indicating which Plums didn't have plum_relations present in the Datahost,
-        // so that the client can act appropriately.
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
-        Box::pin(async move {
-            // Note that this assumes there are no cycles, which is true by construction because relations
-            // are defined using the PlumHeadSeal of the target Plum, and therefore it would be infeasible
-            // to construct a cycle.
-
-            if plum_relation_flags_m.contains_key(plum_head_seal) {
-                // Already traversed; nothing to do.
-                return Ok(());
+        limits: RelationTraversalLimits,
+    ) -> Result<RelationTraversal> {
+        // Note that this assumes there are no cycles, which is true by construction because relations
+        // are defined using the PlumHeadSeal of the target Plum, and therefore it would be infeasible
+        // to construct a cycle.
+
+        let mut plum_relation_flags_m: HashMap<PlumHeadSeal, PlumRelationFlags> = HashMap::new();
+        let mut truncated = false;
+        // All traversed Plums, in the order they were discovered.
+        let mut discovered_plum_head_seal_v: Vec<PlumHeadSeal> = Vec::new();
+        let mut discovered_plum_head_seal_s: HashSet<PlumHeadSeal> = HashSet::new();
+        // Maps each traversed Plum to the traversed Plums it refers to (via masked relations).
+        let mut target_plum_head_seal_vm: HashMap<PlumHeadSeal, Vec<PlumHeadSeal>> = HashMap::new();
+
+        for root_plum_head_seal in root_plum_head_seal_v {
+            if discovered_plum_head_seal_s.insert(root_plum_head_seal.clone()) {
+                discovered_plum_head_seal_v.push(root_plum_head_seal.clone());
             }
+        }
+        let mut level_plum_head_seal_v = discovered_plum_head_seal_v.clone();
+        let mut depth = 0u32;
+        while !level_plum_head_seal_v.is_empty() {
+            log::trace!(
+                "Datahost::traverse_relations_impl; depth {} has {} Plums",
+                depth,
+                level_plum_head_seal_v.len()
+            );
 
-            // Recurse on the plum_relations for this plum_head_seal.
-            let inner_relation_flags_m = {
-                let mut inner_relation_flags_m: HashMap<PlumHeadSeal, PlumRelationFlags> =
-                    HashMap::new();
+            let relation_target_vv = self
+                .load_relation_targets_v_impl(&mut *transaction, &level_plum_head_seal_v, mask)
+                .await?;
 
-                // TODO: This could be more efficient with more-specific DatahostStorage methods.
-                let plum_head = self
-                    .datahost_storage_b
-                    .load_plum_head(&mut *transaction, plum_head_seal)
-                    .await?;
-                let plum_relations = self
-                    .datahost_storage_b
-                    .load_plum_relations(&mut *transaction, &plum_head.plum_relations_seal)
-                    .await?;
+            let mut next_level_plum_head_seal_v = Vec::new();
+            for (plum_head_seal, relation_target_v) in
+                level_plum_head_seal_v.iter().zip(relation_target_vv)
+            {
+                let mut target_plum_head_seal_v = Vec::with_capacity(relation_target_v.len());
+                for (target_plum_head_seal, masked_relation_flags) in relation_target_v {
+                    if !discovered_plum_head_seal_s.contains(&target_plum_head_seal) {
+                        let depth_exceeded = match limits.max_depth_o {
+                            Some(max_depth) => depth >= max_depth,
+                            None => false,
+                        };
+                        let nodes_exceeded = match limits.max_nodes_o {
+                            Some(max_nodes) => discovered_plum_head_seal_v.len() >= max_nodes,
+                            None => false,
+                        };
+                        if depth_exceeded || nodes_exceeded {
+                            truncated = true;
+                            continue;
+                        }
+                        log::trace!(
+                            "Datahost::traverse_relations_impl; {} -> {}",
+                            plum_head_seal,
+                            target_plum_head_seal
+                        );
+                        discovered_plum_head_seal_s.insert(target_plum_head_seal.clone());
+                        discovered_plum_head_seal_v.push(target_plum_head_seal.clone());
+                        next_level_plum_head_seal_v.push(target_plum_head_seal.clone());
+                    }
+                    // NOTE that the full mask is used when traversing the target, instead of the masked
+                    // flags, meaning that the full mask will "bypass" any RelationFlag "bottleneck" imposed
+                    // by a particular data type.  For example, only CONTENT_DEPENDENCY is used by DirNode,
+                    // but if mask includes METADATA_DEPENDENCY, then on querying a child of the DirNode for
+                    // its plum_relations, METADATA_DEPENDENCY will be fair game again.  This may or may not
+                    // be what is actually desired.  Will determine through testing.
+                    *plum_relation_flags_m
+                        .entry(target_plum_head_seal.clone())
+                        .or_insert(PlumRelationFlags::NONE) |= masked_relation_flags;
+                    target_plum_head_seal_v.push(target_plum_head_seal);
+                }
+                target_plum_head_seal_vm.insert(plum_head_seal.clone(), target_plum_head_seal_v);
+            }
 
-                for plum_relation_flags_mapping in plum_relations.plum_relation_flags_mapping_v {
-                    log::trace!(
-                        "accumulate_relations_recursive_impl; {} -> {}",
-                        plum_head_seal,
-                        plum_relation_flags_mapping.target_plum_head_seal
-                    );
-                    let plum_relation_flags = PlumRelationFlags::try_from(
+            level_plum_head_seal_v = next_level_plum_head_seal_v;
+            depth += 1;
+        }
+
+        let plum_head_seal_v =
+            topologically_sorted(&discovered_plum_head_seal_v, &target_plum_head_seal_vm);
+
+        Ok(RelationTraversal {
+            plum_relation_flags_m,
+            plum_head_seal_v,
+            truncated,
+        })
+    }
+
+    /// Returns, for each of the given Plums, the Plums it directly refers to via relations whose
+    /// PlumRelationFlags intersect mask, each paired with the masked PlumRelationFlags, in the order
+    /// the relations appear in its PlumRelations.  If any of the given Plums' PlumHead or PlumRelations
+    /// aren't present in this Datahost, then this returns an error.
+    pub async fn load_relation_targets_v(
+        &self,
+        plum_head_seal_v: &[PlumHeadSeal],
+        mask: PlumRelationFlags,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<Vec<Vec<(PlumHeadSeal, PlumRelationFlags)>>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let relation_target_vv = self
+            .load_relation_targets_v_impl(tx.as_mut(), plum_head_seal_v, mask)
+            .await?;
+        tx.finish().await?;
+        Ok(relation_target_vv)
+    }
+
+    /// Loads the PlumHeads and then the PlumRelations for the given Plums in batches.
+    async fn load_relation_targets_v_impl(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal_v: &[PlumHeadSeal],
+        mask: PlumRelationFlags,
+    ) -> Result<Vec<Vec<(PlumHeadSeal, PlumRelationFlags)>>> {
+        let plum_head_ov = self
+            .datahost_storage_b
+            .load_option_plum_head_v(&mut *transaction, plum_head_seal_v)
+            .await?;
+        let mut plum_relations_seal_v = Vec::with_capacity(plum_head_ov.len());
+        for (plum_head_seal, plum_head_o) in plum_head_seal_v.iter().zip(plum_head_ov) {
+            let plum_head = plum_head_o
+                .ok_or_else(|| DatahostStorageError::PlumHeadNotFound(plum_head_seal.clone()))?;
+            plum_relations_seal_v.push(plum_head.plum_relations_seal);
+        }
+        let plum_relations_ov = self
+            .datahost_storage_b
+            .load_option_plum_relations_v(&mut *transaction, &plum_relations_seal_v)
+            .await?;
+
+        let mut relation_target_vv = Vec::with_capacity(plum_relations_ov.len());
+        for (plum_relations_seal, plum_relations_o) in
+            plum_relations_seal_v.iter().zip(plum_relations_ov)
+        {
+            let plum_relations = plum_relations_o.ok_or_else(|| {
+                DatahostStorageError::PlumRelationsNotFound(plum_relations_seal.clone())
+            })?;
+            let mut relation_target_v = Vec::new();
+            for plum_relation_flags_mapping in plum_relations.plum_relation_flags_mapping_v {
+                let masked_relation_flags = mask
+                    & PlumRelationFlags::try_from(
                         plum_relation_flags_mapping.plum_relation_flags_raw,
                     )?;
-                    let masked_relation_flags = mask & plum_relation_flags;
-                    // Only do anything if the masked flags are nonzero.
-                    if masked_relation_flags != PlumRelationFlags::NONE {
-                        match inner_relation_flags_m
-                            .get_mut(&plum_relation_flags_mapping.target_plum_head_seal)
+                // Only do anything if the masked flags are nonzero.
+                if masked_relation_flags == PlumRelationFlags::NONE {
+                    continue;
+                }
+                relation_target_v.push((
+                    plum_relation_flags_mapping.target_plum_head_seal,
+                    masked_relation_flags,
+                ));
+            }
+            relation_target_vv.push(relation_target_v);
+        }
+        Ok(relation_target_vv)
+    }
+
+    /// This is the inverse of accumulated_relations_recursive.  It returns all Plums that refer to the
+    /// given Plum, either directly or indirectly, via relations whose PlumRelationFlags intersect mask.
+    /// Each such Plum maps to the union of the masked PlumRelationFlags of its relations to the given
+    /// Plum or to other Plums in the returned map.  The given Plum itself isn't included (there can't
+    /// be cycles).  Note that this only finds referring Plums whose PlumHead and PlumRelations are
+    /// present in this Datahost, whereas the given Plum itself need not be present.
+    pub async fn accumulated_reverse_relations_recursive(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        mask: PlumRelationFlags,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<HashMap<PlumHeadSeal, PlumRelationFlags>> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let mut plum_relation_flags_m: HashMap<PlumHeadSeal, PlumRelationFlags> = HashMap::new();
+        // This uses an explicit stack of Plums whose referrers have yet to be selected.
+        let mut pending_plum_head_seal_v = vec![plum_head_seal.clone()];
+        while let Some(target_plum_head_seal) = pending_plum_head_seal_v.pop() {
+            for (referring_plum_head_seal, plum_relation_flags) in self
+                .datahost_storage_b
+                .select_plum_relations_referring_to(tx.as_mut(), &target_plum_head_seal)
+                .await?
+            {
+                let masked_relation_flags = mask & plum_relation_flags;
+                // Only do anything if the masked flags are nonzero.
+                if masked_relation_flags == PlumRelationFlags::NONE {
+                    continue;
+                }
+                log::trace!(
+                    "Datahost::accumulated_reverse_relations_recursive; {} -> {}",
+                    referring_plum_head_seal,
+                    target_plum_head_seal
+                );
+                match plum_relation_flags_m.get_mut(&referring_plum_head_seal) {
+                    Some(referring_relation_flags) => {
+                        // Already traversed; just accumulate the flags.
+                        *referring_relation_flags |= masked_relation_flags;
+                    }
+                    None => {
+                        plum_relation_flags_m
+                            .insert(referring_plum_head_seal.clone(), masked_relation_flags);
+                        pending_plum_head_seal_v.push(referring_plum_head_seal);
+                    }
+                }
+            }
+        }
+        tx.finish().await?;
+        Ok(plum_relation_flags_m)
+    }
+
+    /// Walks the relation DAG rooted at the given Plum (following relations whose PlumRelationFlags
+    /// intersect mask, as in accumulated_relations_recursive) and reports which components of each
+    /// reached Plum are present in this Datahost.  Unlike accumulated_relations_recursive, a missing
+    /// Plum is not an error; it's recorded in the report, so that the client can pull exactly the
+    /// missing pieces.  A Plum is dependency-complete if the report with PlumRelationFlags::ALL is
+    /// complete.
+    pub async fn check_completeness(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        mask: PlumRelationFlags,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<CompletenessReport> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let plum_completeness_m = self
+            .check_completeness_impl(plum_head_seal, mask, |_| false, tx.as_mut())
+            .await?;
+        tx.finish().await?;
+        Ok(CompletenessReport {
+            root_plum_head_seal: plum_head_seal.clone(),
+            plum_completeness_m,
+        })
+    }
+
+    /// Returns true iff the given Plum and every Plum in the relation DAG rooted at it (following all
+    /// PlumRelationFlags) are entirely present in this Datahost.  This uses (and populates) the same
+    /// cache of known dependency-complete Plums as branch_create and branch_set_head, so repeated
+    /// queries within an already-checked DAG are cheap.
+    pub async fn is_dependency_complete(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<bool> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        let is_dependency_complete = match self
+            .ensure_dependency_complete(plum_head_seal, None, tx.as_mut())
+            .await
+        {
+            Ok(()) => true,
+            Err(BranchError::PlumIsNotDependencyComplete { .. }) => false,
+            Err(e) => {
+                return Err(e.into());
+            }
+        };
+        tx.finish().await?;
+        Ok(is_dependency_complete)
+    }
+
+    /// Does the work of check_completeness, except that the relation DAG isn't walked past (and
+    /// doesn't include) any Plum for which is_known_complete returns true.  The DAG is walked one
+    /// level at a time, so that the components of each level can be loaded in batches.
+    async fn check_completeness_impl(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        mask: PlumRelationFlags,
+        is_known_complete: impl Fn(&PlumHeadSeal) -> bool,
+        transaction: &mut dyn DatahostStorageTransaction,
+    ) -> Result<BTreeMap<PlumHeadSeal, PlumCompleteness>> {
+        let mut plum_completeness_m: BTreeMap<PlumHeadSeal, PlumCompleteness> = BTreeMap::new();
+        // Contains every Plum that has been added to a level, so that each is only checked once.
+        let mut reached_plum_head_seal_s = BTreeSet::new();
+        let mut level_plum_head_seal_v = Vec::new();
+        if !is_known_complete(plum_head_seal) {
+            reached_plum_head_seal_s.insert(plum_head_seal.clone());
+            level_plum_head_seal_v.push(plum_head_seal.clone());
+        }
+        while !level_plum_head_seal_v.is_empty() {
+            let plum_head_ov = self
+                .datahost_storage_b
+                .load_option_plum_head_v(transaction, &level_plum_head_seal_v)
+                .await?;
+            let plum_head_v = plum_head_ov.iter().flatten().collect::<Vec<_>>();
+            let plum_metadata_seal_v = plum_head_v
+                .iter()
+                .map(|plum_head| plum_head.plum_metadata_seal.clone())
+                .collect::<Vec<_>>();
+            let plum_relations_seal_v = plum_head_v
+                .iter()
+                .map(|plum_head| plum_head.plum_relations_seal.clone())
+                .collect::<Vec<_>>();
+            let plum_body_seal_v = plum_head_v
+                .iter()
+                .map(|plum_head| plum_head.plum_body_seal.clone())
+                .collect::<Vec<_>>();
+            // These correspond elementwise with plum_head_v.
+            let mut has_plum_metadata_i = self
+                .datahost_storage_b
+                .has_plum_metadata_v(transaction, &plum_metadata_seal_v)
+                .await?
+                .into_iter();
+            let mut plum_relations_oi = self
+                .datahost_storage_b
+                .load_option_plum_relations_v(transaction, &plum_relations_seal_v)
+                .await?
+                .into_iter();
+            let mut has_plum_body_i = self
+                .datahost_storage_b
+                .has_plum_body_v(transaction, &plum_body_seal_v)
+                .await?
+                .into_iter();
+
+            let mut next_level_plum_head_seal_v = Vec::new();
+            for (current_plum_head_seal, plum_head_o) in
+                level_plum_head_seal_v.into_iter().zip(plum_head_ov)
+            {
+                let mut plum_completeness = PlumCompleteness::default();
+                if plum_head_o.is_some() {
+                    plum_completeness.has_plum_head = true;
+                    plum_completeness.has_plum_metadata = has_plum_metadata_i.next().unwrap();
+                    plum_completeness.has_plum_body = has_plum_body_i.next().unwrap();
+                    if let Some(plum_relations) = plum_relations_oi.next().unwrap() {
+                        plum_completeness.has_plum_relations = true;
+                        for plum_relation_flags_mapping in
+                            plum_relations.plum_relation_flags_mapping_v
                         {
-                            Some(inner_relation_flags) => {
-                                *inner_relation_flags |= masked_relation_flags;
-                            }
-                            None => {
-                                inner_relation_flags_m.insert(
-                                    plum_relation_flags_mapping.target_plum_head_seal.clone(),
-                                    masked_relation_flags,
-                                );
+                            let plum_relation_flags = PlumRelationFlags::try_from(
+                                plum_relation_flags_mapping.plum_relation_flags_raw,
+                            )?;
+                            let target_plum_head_seal =
+                                plum_relation_flags_mapping.target_plum_head_seal;
+                            // Only follow the relation if the masked flags are nonzero.
+                            if mask & plum_relation_flags != PlumRelationFlags::NONE
+                                && !reached_plum_head_seal_s.contains(&target_plum_head_seal)
+                                && !is_known_complete(&target_plum_head_seal)
+                            {
+                                reached_plum_head_seal_s.insert(target_plum_head_seal.clone());
+                                next_level_plum_head_seal_v.push(target_plum_head_seal);
                             }
                         }
                     }
                 }
-                inner_relation_flags_m
-            };
-
-            // Now go through the accumulated inner_relation_flags_m and recurse.
-            for (inner_plum_head_seal, inner_relation_flags) in inner_relation_flags_m.iter() {
-                // Just make sure that inner_relation_flags obeys the mask constraint.
-                assert_eq!(*inner_relation_flags & !mask, PlumRelationFlags::NONE);
-
-                // NOTE that we're passing mask here, instead of inner_relation_flags, meaning that the
-                // full mask will "bypass" any RelationFlag "bottleneck" imposed by a particular data type.
-                // For example, only CONTENT_DEPENDENCY is used by DirNode, but if mask includes
-                // METADATA_DEPENDENCY, then on querying a child of the DirNode for its plum_relations,
-                // METADATA_DEPENDENCY will be fair game again.  This may or may not be what is actually
-                // desired.  Will determine through testing.
                 log::trace!(
-                    "accumulate_relations_recursive_impl; recursing on {}",
-                    inner_plum_head_seal
+                    "Datahost::check_completeness; {}: {:?}",
+                    current_plum_head_seal,
+                    plum_completeness
                 );
-                self.accumulate_relations_recursive_impl(
-                    &mut *transaction,
-                    inner_plum_head_seal,
-                    mask.clone(),
-                    plum_relation_flags_m,
-                )
+                plum_completeness_m.insert(current_plum_head_seal, plum_completeness);
+            }
+            level_plum_head_seal_v = next_level_plum_head_seal_v;
+        }
+        Ok(plum_completeness_m)
+    }
+
+    /// Returns BranchError::PlumIsNotDependencyComplete if any Plum in the relation DAG rooted at
+    /// the given Plum is missing any of its components.  The walk stops at Plums already known to
+    /// be dependency-complete, namely known_complete_plum_head_seal_o (e.g. the previous head of a
+    /// branch, which was checked when it was set) and those recorded in dependency_complete_cache_la
+    /// by previous calls.  If the check succeeds, then the Plums it walked are recorded there once
+    /// the transaction commits.
+    async fn ensure_dependency_complete(
+        &self,
+        plum_head_seal: &PlumHeadSeal,
+        known_complete_plum_head_seal_o: Option<&PlumHeadSeal>,
+        transaction: &mut dyn DatahostStorageTransaction,
+    ) -> Result<(), BranchError> {
+        let generation = self
+            .dependency_complete_cache_la
+            .lock()
+            .unwrap()
+            .generation();
+        let plum_completeness_m = self
+            .check_completeness_impl(
+                plum_head_seal,
+                PlumRelationFlags::ALL,
+                |plum_head_seal| {
+                    Some(plum_head_seal) == known_complete_plum_head_seal_o
+                        || self
+                            .dependency_complete_cache_la
+                            .lock()
+                            .unwrap()
+                            .contains(plum_head_seal)
+                },
+                transaction,
+            )
+            .await
+            .map_err(|e| BranchError::InternalError {
+                description: e.to_string(),
+            })?;
+        let completeness_report = CompletenessReport {
+            root_plum_head_seal: plum_head_seal.clone(),
+            plum_completeness_m,
+        };
+        if !completeness_report.is_complete() {
+            return Err(BranchError::PlumIsNotDependencyComplete {
+                plum_head_seal: plum_head_seal.clone(),
+                incomplete_plum_head_seal_v: completeness_report.incomplete_plum_head_seal_v(),
+            });
+        }
+        // Every Plum that was walked is the root of a complete sub-DAG.
+        let plum_head_seal_v = completeness_report
+            .plum_completeness_m
+            .into_keys()
+            .collect::<Vec<_>>();
+        let dependency_complete_cache_la = self.dependency_complete_cache_la.clone();
+        transaction.on_commit(Box::new(move || {
+            dependency_complete_cache_la
+                .lock()
+                .unwrap()
+                .insert_v(generation, plum_head_seal_v);
+        }));
+        Ok(())
+    }
+
+    //
+    // Methods for garbage collection
+    //
+
+    /// Removes all Plums that aren't reachable from any PathState or from any of the pinned Plums,
+    /// where reachability is determined by accumulated_relations_recursive over all PlumRelationFlags.
+    /// The PlumMetadata, PlumRelations, and PlumBody of each unreachable PlumHead are removed as well,
+    /// unless they're also referred to by a reachable PlumHead (e.g. identical content with different
+    /// PlumHead nonces shares a PlumBody).  If dry_run is true, then nothing is removed, but the report
+    /// still describes what would have been removed.
+    ///
+    /// A root whose PlumHead isn't present in this Datahost is skipped, since there's nothing to traverse.
+    /// However, if a Plum that is reachable from a present root is missing (e.g. because only part of a
+    /// Plum tree was pulled), then this returns an error and nothing is removed.
+    ///
+    /// PlumHeads stored within the grace period (see Datahost::with_garbage_collection_grace_period)
+    /// are kept as well, along with whatever is reachable from them, since they may be part of a push
+    /// that hasn't yet been made reachable from a PathState.  Because such a push may still be in
+    /// progress, Plums missing from beneath them are simply skipped.
+    pub async fn garbage_collect(
+        &self,
+        pinned_plum_head_seal_so: Option<&HashSet<PlumHeadSeal>>,
+        dry_run: bool,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<GarbageCollectionReport> {
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
                 .await?;
 
-                // Add inner_plum_head_seal with its computed inner_relation_flags to mark as traversed.
-                log::trace!(
-                "accumulate_relations_recursive_impl; adding to plum_relation_flags_m: {} -> {:?}",
-                inner_plum_head_seal,
-                inner_relation_flags
+        // Mark phase.  The roots are the PathStates' Plums and the pinned Plums.
+        let mut root_plum_head_seal_v = self
+            .datahost_storage_b
+            .select_path_states(tx.as_mut(), PathStatesFilter::default(), SELECT_PAGE_SIZE)
+            .map_ok(|(_, _, path_state)| path_state.current_state_plum_head_seal)
+            .try_collect::<Vec<_>>()
+            .await?;
+        if let Some(pinned_plum_head_seal_s) = pinned_plum_head_seal_so {
+            root_plum_head_seal_v.extend(pinned_plum_head_seal_s.iter().cloned());
+        }
+        // Traversing from all roots at once means each Plum is only traversed once.
+        let mut present_root_plum_head_seal_v = Vec::with_capacity(root_plum_head_seal_v.len());
+        for root_plum_head_seal in root_plum_head_seal_v.iter() {
+            if !self
+                .datahost_storage_b
+                .has_plum_head(tx.as_mut(), root_plum_head_seal)
+                .await?
+            {
+                log::warn!(
+                    "Datahost::garbage_collect; skipping root {}, since its PlumHead is not present",
+                    root_plum_head_seal
+                );
+                continue;
+            }
+            present_root_plum_head_seal_v.push(root_plum_head_seal.clone());
+        }
+        let mut reachable_plum_head_seal_s = self
+            .traverse_relations_impl(
+                tx.as_mut(),
+                &present_root_plum_head_seal_v,
+                PlumRelationFlags::ALL,
+                RelationTraversalLimits::default(),
+            )
+            .await?
+            .plum_head_seal_v
+            .into_iter()
+            .collect::<HashSet<PlumHeadSeal>>();
+        let grace_period_begin =
+            UnixNanoseconds::from(UnixNanoseconds::now().value.saturating_sub(
+                i64::try_from(self.garbage_collection_grace_period.as_nanos()).unwrap_or(i64::MAX),
+            ));
+        let recent_plum_head_seal_v = self
+            .datahost_storage_b
+            .select_plum_heads(
+                tx.as_mut(),
+                PlumHeadsFilter {
+                    inserted_at_begin_o: Some(grace_period_begin),
+                    ..Default::default()
+                },
+                SELECT_PAGE_SIZE,
+            )
+            .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+            .try_collect::<Vec<_>>()
+            .await?;
+        self.mark_reachable_skipping_missing(
+            tx.as_mut(),
+            recent_plum_head_seal_v,
+            &mut reachable_plum_head_seal_s,
+        )
+        .await?;
+
+        // Sweep phase.  PlumHead components referred to by any reachable PlumHead must be kept.  This
+        // makes two passes over the PlumHeads, so that they never all have to be held in memory at once.
+        let mut kept_plum_metadata_seal_s = HashSet::new();
+        let mut kept_plum_relations_seal_s = HashSet::new();
+        let mut kept_plum_body_seal_s = HashSet::new();
+        {
+            let mut plum_head_s = self.datahost_storage_b.select_plum_heads(
+                tx.as_mut(),
+                PlumHeadsFilter::default(),
+                SELECT_PAGE_SIZE,
             );
-                plum_relation_flags_m.insert(inner_plum_head_seal.clone(), *inner_relation_flags);
+            while let Some((_, plum_head_seal, plum_head)) = plum_head_s.try_next().await? {
+                if reachable_plum_head_seal_s.contains(&plum_head_seal) {
+                    kept_plum_metadata_seal_s.insert(plum_head.plum_metadata_seal);
+                    kept_plum_relations_seal_s.insert(plum_head.plum_relations_seal);
+                    kept_plum_body_seal_s.insert(plum_head.plum_body_seal);
+                }
             }
+        }
+        let mut removed_plum_metadata_seal_s = HashSet::new();
+        let mut removed_plum_relations_seal_s = HashSet::new();
+        let mut removed_plum_body_seal_s = HashSet::new();
+        let mut report = GarbageCollectionReport {
+            dry_run,
+            ..Default::default()
+        };
+        {
+            let mut plum_head_s = self.datahost_storage_b.select_plum_heads(
+                tx.as_mut(),
+                PlumHeadsFilter::default(),
+                SELECT_PAGE_SIZE,
+            );
+            while let Some((_, plum_head_seal, plum_head)) = plum_head_s.try_next().await? {
+                if reachable_plum_head_seal_s.contains(&plum_head_seal) {
+                    continue;
+                }
+                report.plum_head_seal_v.push(plum_head_seal);
+                if !kept_plum_metadata_seal_s.contains(&plum_head.plum_metadata_seal) {
+                    removed_plum_metadata_seal_s.insert(plum_head.plum_metadata_seal);
+                }
+                if !kept_plum_relations_seal_s.contains(&plum_head.plum_relations_seal) {
+                    removed_plum_relations_seal_s.insert(plum_head.plum_relations_seal);
+                }
+                if !kept_plum_body_seal_s.contains(&plum_head.plum_body_seal) {
+                    removed_plum_body_seal_s.insert(plum_head.plum_body_seal);
+                }
+            }
+        }
+        report.plum_head_seal_v.sort();
+        report.plum_metadata_seal_v = removed_plum_metadata_seal_s.into_iter().collect();
+        report.plum_metadata_seal_v.sort();
+        report.plum_relations_seal_v = removed_plum_relations_seal_s.into_iter().collect();
+        report.plum_relations_seal_v.sort();
+        report.plum_body_seal_v = removed_plum_body_seal_s.into_iter().collect();
+        report.plum_body_seal_v.sort();
 
-            Ok(())
-        })
+        log::debug!(
+            "Datahost::garbage_collect; dry_run: {}, removing {} PlumHeads, {} PlumMetadatas, {} PlumRelations, {} PlumBodies",
+            dry_run,
+            report.plum_head_seal_v.len(),
+            report.plum_metadata_seal_v.len(),
+            report.plum_relations_seal_v.len(),
+            report.plum_body_seal_v.len()
+        );
+
+        if !dry_run {
+            if !report.plum_head_seal_v.is_empty() {
+                // Invalidate now, so that a concurrent ensure_dependency_complete can't record Plums
+                // that are about to be removed, and again upon commit, in case one recorded them
+                // in the meantime.
+                self.dependency_complete_cache_la
+                    .lock()
+                    .unwrap()
+                    .invalidate();
+                let dependency_complete_cache_la = self.dependency_complete_cache_la.clone();
+                tx.as_mut().on_commit(Box::new(move || {
+                    dependency_complete_cache_la.lock().unwrap().invalidate();
+                }));
+            }
+            // Remove the PlumHeads first, so that no PlumHead ever refers to removed components.
+            for plum_head_seal in report.plum_head_seal_v.iter() {
+                self.datahost_storage_b
+                    .delete_plum_head(tx.as_mut(), plum_head_seal)
+                    .await?;
+            }
+            for plum_metadata_seal in report.plum_metadata_seal_v.iter() {
+                self.datahost_storage_b
+                    .delete_plum_metadata(tx.as_mut(), plum_metadata_seal)
+                    .await?;
+            }
+            for plum_relations_seal in report.plum_relations_seal_v.iter() {
+                self.datahost_storage_b
+                    .delete_plum_relations(tx.as_mut(), plum_relations_seal)
+                    .await?;
+            }
+            for plum_body_seal in report.plum_body_seal_v.iter() {
+                self.datahost_storage_b
+                    .delete_plum_body(tx.as_mut(), plum_body_seal)
+                    .await?;
+            }
+        }
+
+        tx.finish().await?;
+        Ok(report)
+    }
+    /// Adds the given Plums, and all Plums reachable from them via relations of any kind, to
+    /// reachable_plum_head_seal_s.  Unlike traverse_relations_impl, a Plum whose PlumHead or
+    /// PlumRelations isn't present is skipped instead of causing an error.  Plums already in
+    /// reachable_plum_head_seal_s aren't traversed again.
+    async fn mark_reachable_skipping_missing(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        root_plum_head_seal_v: Vec<PlumHeadSeal>,
+        reachable_plum_head_seal_s: &mut HashSet<PlumHeadSeal>,
+    ) -> Result<()> {
+        let mut level_plum_head_seal_v = root_plum_head_seal_v
+            .into_iter()
+            .filter(|plum_head_seal| reachable_plum_head_seal_s.insert(plum_head_seal.clone()))
+            .collect::<Vec<_>>();
+        while !level_plum_head_seal_v.is_empty() {
+            let plum_relations_seal_v = self
+                .datahost_storage_b
+                .load_option_plum_head_v(&mut *transaction, &level_plum_head_seal_v)
+                .await?
+                .into_iter()
+                .flatten()
+                .map(|plum_head| plum_head.plum_relations_seal)
+                .collect::<Vec<_>>();
+            let plum_relations_ov = self
+                .datahost_storage_b
+                .load_option_plum_relations_v(&mut *transaction, &plum_relations_seal_v)
+                .await?;
+            let mut next_level_plum_head_seal_v = Vec::new();
+            for plum_relations in plum_relations_ov.into_iter().flatten() {
+                for plum_relation_flags_mapping in plum_relations.plum_relation_flags_mapping_v {
+                    // This matches traverse_relations_impl with PlumRelationFlags::ALL.
+                    if PlumRelationFlags::try_from(
+                        plum_relation_flags_mapping.plum_relation_flags_raw,
+                    )? == PlumRelationFlags::NONE
+                    {
+                        continue;
+                    }
+                    if reachable_plum_head_seal_s
+                        .insert(plum_relation_flags_mapping.target_plum_head_seal.clone())
+                    {
+                        next_level_plum_head_seal_v
+                            .push(plum_relation_flags_mapping.target_plum_head_seal);
+                    }
+                }
+            }
+            level_plum_head_seal_v = next_level_plum_head_seal_v;
+        }
+        Ok(())
     }
 
     //
@@ -563,6 +1541,10 @@ impl Datahost {
         self.datahost_storage_b
             .insert_path_state(tx.as_mut(), path_state)
             .await?;
+        self.notify_path_state_change_on_commit(
+            tx.as_mut(),
+            path_state_change::Value::Inserted(path_state.clone()),
+        );
         tx.finish().await?;
         Ok(())
     }
@@ -583,6 +1565,10 @@ impl Datahost {
         self.datahost_storage_b
             .update_path_state(tx.as_mut(), path_state)
             .await?;
+        self.notify_path_state_change_on_commit(
+            tx.as_mut(),
+            path_state_change::Value::Updated(path_state.clone()),
+        );
         tx.finish().await?;
         Ok(())
     }
@@ -600,9 +1586,48 @@ impl Datahost {
             .datahost_storage_b
             .delete_path_state(tx.as_mut(), path)
             .await?;
+        self.notify_path_state_change_on_commit(
+            tx.as_mut(),
+            path_state_change::Value::Deleted(path.clone()),
+        );
         tx.finish().await?;
         Ok(retval)
     }
+    /// Returns a receiver for each subsequent change to this Datahost's PathStates, as made by
+    /// insert_path_state, update_path_state, and delete_path_state (and therefore by the branch
+    /// operations).  A change is sent once the transaction making it commits, which, if an outer
+    /// transaction was passed in, is when that transaction commits (and not at all if it's rolled
+    /// back).  Dropping the receiver ends the subscription.  A receiver that falls more than
+    /// PATH_STATE_CHANGE_CHANNEL_CAPACITY changes behind is unsubscribed, which ends its stream, so
+    /// that a slow receiver can't make changes accumulate without bound; its owner should then reload
+    /// whatever PathStates it's interested in and subscribe again.
+    pub fn subscribe_to_path_state_changes(
+        &self,
+    ) -> futures::channel::mpsc::Receiver<PathStateChange> {
+        let (sender, receiver) =
+            futures::channel::mpsc::channel(PATH_STATE_CHANGE_CHANNEL_CAPACITY);
+        self.path_state_change_sender_vla
+            .lock()
+            .unwrap()
+            .push(sender);
+        receiver
+    }
+    /// Sends the given PathStateChange to the subscribers once the given transaction commits.
+    fn notify_path_state_change_on_commit(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        value: path_state_change::Value,
+    ) {
+        let path_state_change_sender_vla = self.path_state_change_sender_vla.clone();
+        transaction.on_commit(Box::new(move || {
+            let path_state_change = PathStateChange { value: Some(value) };
+            // Senders whose receivers have been dropped, or have fallen too far behind, are removed.
+            path_state_change_sender_vla
+                .lock()
+                .unwrap()
+                .retain_mut(|sender| sender.try_send(path_state_change.clone()).is_ok());
+        }));
+    }
 
     //
     // Methods for Branch operations
@@ -657,7 +1682,13 @@ impl Datahost {
             )
             .into());
         }
-        // TODO: Check that req.branch_path_state.current_state_plum_head_seal is dependency-complete.
+        // Check that the BranchNode Plum is dependency-complete, i.e. its whole relation DAG is present.
+        self.ensure_dependency_complete(
+            &branch_path_state.current_state_plum_head_seal,
+            None,
+            tx.as_mut(),
+        )
+        .await?;
 
         // TODO: Move this BranchNode validation stuff into helper function
 
@@ -800,13 +1831,31 @@ impl Datahost {
                 new_branch_head_plum_head_seal,
             ));
         }
-        // TODO: Check that branch_node_plum_head_seal is dependency-complete.
 
         // Get the current branch PlumHeadSeal.
         let current_branch_head_plum_head_seal = self
             .load_path_state(&req.branch_path, Some(tx.as_mut()))
             .await?
             .current_state_plum_head_seal;
+        // If the client specified which branch head it computed the operation from, then the branch
+        // must not have been updated since.
+        if let Some(expected_current_branch_head) = req.expected_current_branch_head_o {
+            if expected_current_branch_head != current_branch_head_plum_head_seal {
+                return Err(BranchError::UnexpectedCurrentBranchHead {
+                    current_branch_head: current_branch_head_plum_head_seal,
+                    expected_current_branch_head,
+                });
+            }
+        }
+
+        // Check that the BranchNode Plum is dependency-complete, i.e. its whole relation DAG is
+        // present.  The current branch head was checked when it was set, so the walk stops there.
+        self.ensure_dependency_complete(
+            &new_branch_head_plum_head_seal,
+            Some(&current_branch_head_plum_head_seal),
+            tx.as_mut(),
+        )
+        .await?;
 
         // TODO: Move this BranchNode validation stuff into helper function
 
@@ -1004,6 +2053,83 @@ impl Datahost {
             rhs: rhs.clone(),
         });
     }
+    /// Returns the BranchSetHeadRequest operation that moves a branch whose head is branch_head to
+    /// new_branch_head, along with the corresponding BranchUpdate, as determined by their closest
+    /// common ancestor.  These are the same conditions that branch_set_head checks, so this is how
+    /// IDPClient::push_branch (and its peer-to-peer counterpart) decides how to update the remote
+    /// branch.  Both BranchNode Plums (and their ancestors) must be present in this Datahost, and
+    /// they're expected to differ (otherwise the update is BranchUpdate::Unchanged).
+    pub async fn branch_set_head_operation(
+        &self,
+        branch_head: &PlumHeadSeal,
+        new_branch_head: &PlumHeadSeal,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<(idp_proto::branch_set_head_request::Value, BranchUpdate), BranchError> {
+        let common_ancestor_o = self
+            .closest_common_branch_node_ancestor(branch_head, new_branch_head, transaction_o)
+            .await?;
+        let new_branch_head = new_branch_head.clone();
+        Ok(if common_ancestor_o.as_ref() == Some(branch_head) {
+            (
+                idp_proto::branch_set_head_request::Value::BranchFastForwardTo(new_branch_head),
+                BranchUpdate::FastForwarded,
+            )
+        } else if common_ancestor_o.as_ref() == Some(&new_branch_head) {
+            (
+                idp_proto::branch_set_head_request::Value::BranchRewindTo(new_branch_head),
+                BranchUpdate::Rewound,
+            )
+        } else if common_ancestor_o.is_some() {
+            (
+                idp_proto::branch_set_head_request::Value::BranchForkHistoryTo(new_branch_head),
+                BranchUpdate::ForkedHistory,
+            )
+        } else {
+            (
+                idp_proto::branch_set_head_request::Value::BranchTotallyRewriteTo(new_branch_head),
+                BranchUpdate::TotallyRewritten,
+            )
+        })
+    }
+    /// Returns the path of the PathState that tracks the head of the given branch on the given remote
+    /// (e.g. "origin" for the IDPServer that an IDPClient is connected to, or the PeerId of another
+    /// IDPPeer), i.e. remote_name, followed by '/', followed by branch_path.  Because a branch path
+    /// can't contain '/' (see Path::validate), this can't collide with a local branch, and each
+    /// remote's branches are tracked separately.
+    pub fn remote_tracking_branch_path(remote_name: &str, branch_path: &Path) -> Path {
+        Path::from(format!("{}/{}", remote_name, branch_path.value))
+    }
+    /// Records remote_branch_head as the head of the given branch on the given remote, in the PathState
+    /// given by remote_tracking_branch_path, which is created or updated as needed.  The local branch
+    /// itself (if any) isn't modified.
+    pub async fn set_remote_tracking_branch_head(
+        &self,
+        remote_name: &str,
+        branch_path: &Path,
+        remote_branch_head: &PlumHeadSeal,
+        transaction_o: Option<&mut dyn DatahostStorageTransaction>,
+    ) -> Result<(), DatahostStorageError> {
+        let remote_tracking_branch_path_state = PathState {
+            path: Self::remote_tracking_branch_path(remote_name, branch_path),
+            current_state_plum_head_seal: remote_branch_head.clone(),
+        };
+        // Note that the self.datahost_storage_b.begin_transaction() simply returns a Future, it doesn't actually begin the transaction.
+        let mut tx =
+            EnsuredTransaction::new(transaction_o, self.datahost_storage_b.begin_transaction())
+                .await?;
+        if self
+            .has_path_state(&remote_tracking_branch_path_state.path, Some(tx.as_mut()))
+            .await?
+        {
+            self.update_path_state(&remote_tracking_branch_path_state, Some(tx.as_mut()))
+                .await?;
+        } else {
+            self.insert_path_state(&remote_tracking_branch_path_state, Some(tx.as_mut()))
+                .await?;
+        }
+        tx.finish().await?;
+        Ok(())
+    }
 }
 
 impl Drop for Datahost {
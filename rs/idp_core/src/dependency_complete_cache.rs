@@ -0,0 +1,44 @@
+use idp_proto::PlumHeadSeal;
+use std::collections::HashSet;
+
+/// Remembers which Plums are known to be dependency-complete (see Datahost::check_completeness), so
+/// that Datahost::ensure_dependency_complete can stop walking the relation DAG at them.  Since Plums
+/// are immutable, a dependency-complete Plum stays that way until some Plum is deleted, so deleting
+/// Plums invalidates the whole cache.
+///
+/// The generation is incremented upon each invalidation.  A completeness check records the
+/// generation before it starts, and its results are only inserted (once its transaction commits) if
+/// the generation hasn't changed since, so that a check that overlapped a deletion can't insert
+/// stale results.
+#[derive(Debug, Default)]
+pub(crate) struct DependencyCompleteCache {
+    generation: u64,
+    plum_head_seal_s: HashSet<PlumHeadSeal>,
+}
+
+impl DependencyCompleteCache {
+    /// The cache is cleared when it would otherwise exceed this many entries.
+    const CAPACITY: usize = 1 << 16;
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+    pub(crate) fn contains(&self, plum_head_seal: &PlumHeadSeal) -> bool {
+        self.plum_head_seal_s.contains(plum_head_seal)
+    }
+    /// Inserts the given PlumHeadSeal-s, unless the cache has been invalidated since the given
+    /// generation.
+    pub(crate) fn insert_v(&mut self, generation: u64, plum_head_seal_v: Vec<PlumHeadSeal>) {
+        if generation != self.generation {
+            return;
+        }
+        if self.plum_head_seal_s.len() + plum_head_seal_v.len() > Self::CAPACITY {
+            self.plum_head_seal_s.clear();
+        }
+        self.plum_head_seal_s.extend(plum_head_seal_v);
+    }
+    pub(crate) fn invalidate(&mut self) {
+        self.generation += 1;
+        self.plum_head_seal_s.clear();
+    }
+}
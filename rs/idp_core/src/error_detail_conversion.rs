@@ -0,0 +1,59 @@
+use crate::{BranchError, PathStateError};
+use idp_datahost_storage::DatahostStorageError;
+use idp_proto::{error_detail, ErrorDetail};
+use std::convert::TryFrom;
+
+/// Returns the ErrorDetail representing the given error, if it's (or has context attached to) a
+/// DatahostStorageError, PathStateError, or BranchError, so that whoever receives it (e.g. in a
+/// PeerError) can reconstruct the error using error_from_error_detail.
+pub fn error_detail_from_error(error: &anyhow::Error) -> Option<ErrorDetail> {
+    let error_detail_value_o =
+        if let Some(datahost_storage_error) = error.downcast_ref::<DatahostStorageError>() {
+            Some(error_detail::Value::DatahostStorageError(
+                datahost_storage_error.into(),
+            ))
+        } else if let Some(path_state_error) = error.downcast_ref::<PathStateError>() {
+            Some(error_detail::Value::PathStateError(path_state_error.into()))
+        } else {
+            error
+                .downcast_ref::<BranchError>()
+                .map(|branch_error| error_detail::Value::BranchError(branch_error.into()))
+        };
+    error_detail_value_o.map(|value| ErrorDetail { value: Some(value) })
+}
+
+/// Converts an error received from the other side of a connection (e.g. the tonic::Status returned
+/// by IDPServer, or the PeerError returned by another IDPPeer) into an error.  If error_detail_o is
+/// present, then the DatahostStorageError, PathStateError, or BranchError that it represents is
+/// reconstructed, so that it can be recovered using anyhow::Error::downcast_ref, and context is
+/// attached to it, so that it too can be recovered that way.  Otherwise (or if the ErrorDetail is
+/// malformed), the error is just context.
+pub fn error_from_error_detail<C>(error_detail_o: Option<ErrorDetail>, context: C) -> anyhow::Error
+where
+    C: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+{
+    let error_r = match error_detail_o.and_then(|error_detail| error_detail.value) {
+        Some(error_detail::Value::DatahostStorageError(datahost_storage_error_detail)) => {
+            DatahostStorageError::try_from(datahost_storage_error_detail).map(anyhow::Error::from)
+        }
+        Some(error_detail::Value::PathStateError(path_state_error_detail)) => {
+            PathStateError::try_from(path_state_error_detail).map(anyhow::Error::from)
+        }
+        Some(error_detail::Value::BranchError(branch_error_detail)) => {
+            BranchError::try_from(branch_error_detail).map(anyhow::Error::from)
+        }
+        None => {
+            return anyhow::Error::msg(context);
+        }
+    };
+    match error_r {
+        Ok(error) => error.context(context),
+        Err(malformed_error_detail) => {
+            log::warn!(
+                "received a malformed ErrorDetail; {}",
+                malformed_error_detail
+            );
+            anyhow::Error::msg(context)
+        }
+    }
+}
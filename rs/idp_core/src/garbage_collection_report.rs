@@ -0,0 +1,23 @@
+use idp_proto::{PlumBodySeal, PlumHeadSeal, PlumMetadataSeal, PlumRelationsSeal};
+
+/// Describes what Datahost::garbage_collect removed, or in the case of a dry run, what it would
+/// have removed.  The seals are sorted within each Vec, so that reports are deterministic.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GarbageCollectionReport {
+    /// If true, then nothing was actually removed.
+    pub dry_run: bool,
+    pub plum_head_seal_v: Vec<PlumHeadSeal>,
+    pub plum_metadata_seal_v: Vec<PlumMetadataSeal>,
+    pub plum_relations_seal_v: Vec<PlumRelationsSeal>,
+    pub plum_body_seal_v: Vec<PlumBodySeal>,
+}
+
+impl GarbageCollectionReport {
+    /// Returns true if nothing was (or would have been) removed.
+    pub fn is_empty(&self) -> bool {
+        self.plum_head_seal_v.is_empty()
+            && self.plum_metadata_seal_v.is_empty()
+            && self.plum_relations_seal_v.is_empty()
+            && self.plum_body_seal_v.is_empty()
+    }
+}
@@ -1,454 +1,1159 @@
-use crate::Datahost;
+use crate::{
+    error_from_error_detail, relation_traversal::topologically_sorted, BranchUpdate,
+    ClientTLSConfig, CredentialProvider, Datahost, TransferReport,
+};
 use anyhow::Result;
 use async_lock::RwLock;
-use idp_proto::{IndoorDataPlumbingClient, PlumHeadSeal, PullRequest, PushRequest};
-use std::sync::Arc;
+use idp_proto::{
+    path_state_change, BranchCreateRequest, BranchDeleteRequest, BranchGetHeadRequest,
+    BranchSetHeadRequest, ErrorDetail, HasPlumsRequest, IndoorDataPlumbingClient, PageCursor,
+    PartialPlumBody, Path, PathState, PathStateChange, PathStateGetRequest, PathStateListRequest,
+    PathStateListResponse, Plum, PlumBodyChunk, PlumBodyOffset, PlumBodySeal, PlumHeadSeal,
+    PullRequest, PushRequest, SealKeyProvider, UnixNanoseconds, WatchPathStatesRequest,
+    AUTHORIZATION_CHALLENGE_METADATA_KEY, AUTHORIZATION_METADATA_KEY, PLUM_BODY_CHUNK_SIZE,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    sync::Arc,
+};
+
+/// The maximum number of pushed Plums whose PlumHead, PlumMetadata, and PlumRelations are loaded at
+/// once using Datahost::load_plum_head_metadata_and_relations_v.  Their PlumBody-s are loaded one at
+/// a time.
+const PUSH_LOAD_BATCH_SIZE: usize = 256;
+/// The maximum number of HereHaveAPlum, HereHaveAPlumHeadMetadataAndRelations, and
+/// HereHaveAPlumBodyChunk requests that IDPClient::push sends ahead of their responses.  Since each
+/// PlumBodyChunk carries up to PLUM_BODY_CHUNK_SIZE bytes, this bounds the memory used by queued
+/// requests.
+const PUSH_MAX_OUTSTANDING_REQUEST_COUNT: usize = 16;
+/// The number of PathStates requested per page by IDPClient::path_states_updated_since.
+const POLL_PAGE_SIZE: u32 = 256;
+/// The remote name (see Datahost::remote_tracking_branch_path) under which IDPClient::fetch_branch
+/// tracks the server's branches.
+const REMOTE_NAME: &str = "origin";
+
+/// Receives the response to a HereHaveAPlum, HereHaveAPlumHeadMetadataAndRelations, or
+/// HereHaveAPlumBodyChunk request, which must be Ok.
+async fn receive_push_ok_response(
+    push_response_stream: &mut tonic::Streaming<idp_proto::PushResponse>,
+) -> Result<()> {
+    let push_response = push_response_stream
+        .message()
+        .await
+        .map_err(error_from_status)?
+        .ok_or_else(|| anyhow::anyhow!("IDPServer ended the push response stream prematurely"))?;
+    match push_response.value {
+        Some(idp_proto::push_response::Value::Ok(_)) => Ok(()),
+        _ => {
+            anyhow::bail!(
+                "IDPServer returned an unexpected response to HereHaveAPlum, HereHaveAPlumHeadMetadataAndRelations, or HereHaveAPlumBodyChunk"
+            );
+        }
+    }
+}
+
+/// Sends a HereHaveAPlum, HereHaveAPlumHeadMetadataAndRelations, or HereHaveAPlumBodyChunk request,
+/// first receiving the response to the earliest outstanding one if PUSH_MAX_OUTSTANDING_REQUEST_COUNT
+/// of them are already awaiting their responses.
+async fn send_push_request_with_backpressure(
+    push_request_tx: &futures::channel::mpsc::UnboundedSender<PushRequest>,
+    push_response_stream: &mut tonic::Streaming<idp_proto::PushResponse>,
+    outstanding_request_count: &mut usize,
+    push_request_value: idp_proto::push_request::Value,
+) -> Result<()> {
+    if *outstanding_request_count >= PUSH_MAX_OUTSTANDING_REQUEST_COUNT {
+        receive_push_ok_response(push_response_stream).await?;
+        *outstanding_request_count -= 1;
+    }
+    push_request_tx.unbounded_send(PushRequest {
+        value: Some(push_request_value),
+    })?;
+    *outstanding_request_count += 1;
+    Ok(())
+}
+
+/// Attaches the current authorization value (if any) to each request.  The value is shared with
+/// IDPClient so that IDPClient::authenticate can replace it.
+#[derive(Clone)]
+struct AuthorizationInterceptor {
+    authorization_ola: Arc<std::sync::RwLock<Option<tonic::metadata::AsciiMetadataValue>>>,
+}
+
+impl tonic::service::Interceptor for AuthorizationInterceptor {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        if let Some(authorization) = self.authorization_ola.read().unwrap().as_ref() {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_METADATA_KEY, authorization.clone());
+        }
+        Ok(request)
+    }
+}
+
+/// Converts a tonic::Status returned by IDPServer into an error.  If the Status carries an ErrorDetail,
+/// then the DatahostStorageError, PathStateError, or BranchError that it represents is reconstructed,
+/// so that it can be recovered using anyhow::Error::downcast_ref.  The Status itself is attached as
+/// the context of that error, so it too can be recovered that way.
+fn error_from_status(status: tonic::Status) -> anyhow::Error {
+    error_from_error_detail(ErrorDetail::from_status(&status), status)
+}
 
 pub struct IDPClient {
     datahost_la: Arc<RwLock<Datahost>>,
-    // TODO: Handle to GRPC connection
-    grpc_client: IndoorDataPlumbingClient<tonic::transport::Channel>,
+    grpc_client: IndoorDataPlumbingClient<
+        tonic::codegen::InterceptedService<tonic::transport::Channel, AuthorizationInterceptor>,
+    >,
+    /// Supplies the credentials sent with each request, if the server requires authentication.
+    credential_provider_bo: Option<Box<dyn CredentialProvider>>,
+    /// The authorization value most recently obtained from credential_provider_bo.
+    authorization_ola: Arc<std::sync::RwLock<Option<tonic::metadata::AsciiMetadataValue>>>,
+    /// Chunked PlumBody-s whose pull was interrupted (e.g. by a dropped connection).  These are kept
+    /// so that pulling again resumes them instead of starting over.
+    partial_plum_body_m: HashMap<PlumBodySeal, PartialPlumBody>,
+    /// See IDPClient::with_seal_key_provider.
+    seal_key_provider_ao: Option<Arc<dyn SealKeyProvider + Send + Sync>>,
 }
 
 impl IDPClient {
-    pub async fn connect(url: String, datahost_la: Arc<RwLock<Datahost>>) -> Result<Self> {
-        // let channel = tonic::transport::Channel::from_static("http://0.0.0.0:50051")
-        //     .connect()
-        //     .await?;
-
-        // let mut grpc_client = IndoorDataPlumbingClient::with_interceptor(channel.clone(), {
-        //     let token = tonic::metadata::MetadataValue::from_static("Bearer some-secret-token");
-        //     move |mut req: tonic::Request<()>| {
-        //         req.metadata_mut().insert("authorization", token.clone());
-        //         Ok(req)
-        //     }
-        // });
-        // let grpc_client = IndoorDataPlumbingClient::connect("http://0.0.0.0:50051").await?;
-        let grpc_client = IndoorDataPlumbingClient::connect(url).await?;
-        Ok(Self {
+    /// Connects to the IDPServer at the given URL.  Connecting to an "https://" URL requires tls_config_o.
+    /// If credential_provider_bo is given, then it supplies the credentials sent with each request, and
+    /// they're checked against the server (see IDPClient::authenticate) before this returns.
+    pub async fn connect(
+        url: String,
+        datahost_la: Arc<RwLock<Datahost>>,
+        credential_provider_bo: Option<Box<dyn CredentialProvider>>,
+        tls_config_o: Option<&ClientTLSConfig>,
+    ) -> Result<Self> {
+        let channel = match tls_config_o {
+            Some(tls_config) => tls_config.connect(url).await?,
+            None => {
+                tonic::transport::Endpoint::from_shared(url)?
+                    .connect()
+                    .await?
+            }
+        };
+        let authorization_ola = Arc::new(std::sync::RwLock::new(None));
+        let grpc_client = IndoorDataPlumbingClient::with_interceptor(
+            channel,
+            AuthorizationInterceptor {
+                authorization_ola: authorization_ola.clone(),
+            },
+        );
+        let mut idp_client = Self {
             datahost_la,
             grpc_client,
-        })
+            credential_provider_bo,
+            authorization_ola,
+            partial_plum_body_m: HashMap::new(),
+            seal_key_provider_ao: None,
+        };
+        if idp_client.credential_provider_bo.is_some() {
+            idp_client.authenticate().await?;
+        }
+        Ok(idp_client)
+    }
+    /// Uses the SealKey-s from the given SealKeyProvider to verify the keyed seals of pulled Plums,
+    /// using Plum::verify_with_seal_key_provider.  A pulled keyed seal whose SealKey isn't provided is
+    /// rejected, since it can't be checked.  In particular, if this isn't used, then every pulled keyed
+    /// seal is rejected.
+    pub fn with_seal_key_provider(
+        mut self,
+        seal_key_provider_a: Arc<dyn SealKeyProvider + Send + Sync>,
+    ) -> Self {
+        self.seal_key_provider_ao = Some(seal_key_provider_a);
+        self
+    }
+    /// Obtains credentials from the CredentialProvider and checks them against the server, answering
+    /// the challenge that the server issues if it rejects them (as it does for the DIDKey scheme).
+    /// This is done by IDPClient::connect, but must be done again if the server later rejects the
+    /// credentials (e.g. because a DIDKey session expired).
+    pub async fn authenticate(&mut self) -> Result<()> {
+        let credential_provider = self
+            .credential_provider_bo
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("IDPClient has no CredentialProvider"))?;
+        let mut challenge_o: Option<String> = None;
+        loop {
+            let authorization = credential_provider
+                .authorization(challenge_o.as_deref())
+                .await?;
+            *self.authorization_ola.write().unwrap() = Some(authorization.parse()?);
+            // HasPlums for no Plums does nothing, so it's used to check the credentials.  Note that
+            // PermissionDenied means that the credentials were accepted, but the server's access
+            // policy doesn't permit this identity to use HasPlums.
+            let status = match self
+                .grpc_client
+                .has_plums(HasPlumsRequest {
+                    plum_head_seal_v: Vec::new(),
+                })
+                .await
+            {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(status) if status.code() == tonic::Code::PermissionDenied => {
+                    return Ok(());
+                }
+                Err(status) => status,
+            };
+            let issued_challenge_o = status
+                .metadata()
+                .get(AUTHORIZATION_CHALLENGE_METADATA_KEY)
+                .map(|challenge| challenge.to_str())
+                .transpose()?;
+            match issued_challenge_o {
+                // Only one challenge is answered, so that a server that keeps rejecting the answer
+                // can't cause an infinite loop.
+                Some(issued_challenge)
+                    if status.code() == tonic::Code::Unauthenticated && challenge_o.is_none() =>
+                {
+                    challenge_o = Some(issued_challenge.to_string());
+                }
+                _ => {
+                    return Err(status.into());
+                }
+            }
+        }
     }
+    /// Pushes the given Plum and its dependencies (i.e. the Plums it refers to via CONTENT_DEPENDENCY
+    /// or METADATA_DEPENDENCY relations, recursively) to the server.  The PlumHeadSeals are offered to
+    /// the server a level at a time (breadth-first, starting with the given Plum) using
+    /// ShouldISendThisPlum, and only the Plums that the server asks for are recursed into, since the
+    /// server only declines a Plum once it has confirmed that it has that Plum and all of its
+    /// dependencies (a Plum that the server has but isn't dependency-complete is asked for, and so is
+    /// re-sent and recursed into).  The Plums that the server asked for are then sent
+    /// dependencies-first, so that the server never has a Plum without its dependencies.  Thus re-pushing a mostly-synced tree only costs a few round trips.  Similarly,
+    /// each PlumBody is offered using ShouldISendThisPlumBody before being sent, so that a PlumBody
+    /// that the server already has (e.g. because it's shared with another Plum) isn't sent again.
+    /// A PlumBody too large to fit in a single message is sent as a sequence of PlumBodyChunk-s,
+    /// starting from wherever an earlier, interrupted push of it left off.
     // Technically this could be &self, not mutable.
-    pub async fn push(&mut self, plum_head_seal: &PlumHeadSeal) -> Result<()> {
-        // TODO: Stream the results from the DB query instead of reading the whole thing into memory.
-        let plum_relation_flags_m = self
-            .datahost_la
-            .read()
+    pub async fn push(&mut self, plum_head_seal: &PlumHeadSeal) -> Result<TransferReport> {
+        let mask = idp_proto::PlumRelationFlags::CONTENT_DEPENDENCY
+            | idp_proto::PlumRelationFlags::METADATA_DEPENDENCY;
+
+        // The requests are sent through a channel so that the responses can be read as they arrive.
+        // The channel is unbounded so that sending a large level of (small) ShouldISendThisPlum
+        // requests can't block while the server is waiting for its responses to be read.  The larger
+        // requests that send the Plums are limited by PUSH_MAX_OUTSTANDING_REQUEST_COUNT instead.
+        let (push_request_tx, push_request_rx) = futures::channel::mpsc::unbounded();
+        let mut push_response_stream = self
+            .grpc_client
+            .push(push_request_rx)
             .await
-            .accumulated_relations_recursive(
-                plum_head_seal,
-                idp_proto::PlumRelationFlags::CONTENT_DEPENDENCY
-                    | idp_proto::PlumRelationFlags::METADATA_DEPENDENCY,
-                None,
-            )
-            .await?;
-        log::trace!(
-            "IDPClient::push({}); plum_relation_flags_m ({} entries):",
-            plum_head_seal,
-            plum_relation_flags_m.len()
-        );
-        for plum_relation_flags in plum_relation_flags_m.iter() {
+            .map_err(error_from_status)?
+            .into_inner();
+
+        // Negotiate which Plums to send.
+        let mut offered_plum_head_seal_s = HashSet::new();
+        offered_plum_head_seal_s.insert(plum_head_seal.clone());
+        let mut level_plum_head_seal_v = vec![plum_head_seal.clone()];
+        // The Plums that the server asked for, in the order they were offered.
+        let mut wanted_plum_head_seal_v = Vec::new();
+        // Maps each wanted Plum to the Plums it refers to (via masked relations).
+        let mut target_plum_head_seal_vm: HashMap<PlumHeadSeal, Vec<PlumHeadSeal>> = HashMap::new();
+        while !level_plum_head_seal_v.is_empty() {
+            for offered_plum_head_seal in level_plum_head_seal_v.iter() {
+                push_request_tx.unbounded_send(PushRequest {
+                    value: Some(idp_proto::push_request::Value::ShouldISendThisPlum(
+                        offered_plum_head_seal.clone(),
+                    )),
+                })?;
+            }
+            // The server responds to each request in order.
+            let mut wanted_level_plum_head_seal_v = Vec::new();
+            for offered_plum_head_seal in level_plum_head_seal_v.iter() {
+                let push_response = push_response_stream
+                    .message()
+                    .await
+                    .map_err(error_from_status)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("IDPServer ended the push response stream prematurely")
+                    })?;
+                match push_response.value {
+                    Some(idp_proto::push_response::Value::SendThisPlum(
+                        response_plum_head_seal,
+                    )) if response_plum_head_seal == *offered_plum_head_seal => {
+                        wanted_level_plum_head_seal_v.push(response_plum_head_seal);
+                    }
+                    Some(idp_proto::push_response::Value::DontSendThisPlum(
+                        response_plum_head_seal,
+                    )) if response_plum_head_seal == *offered_plum_head_seal => {}
+                    _ => {
+                        anyhow::bail!(
+                            "IDPServer returned an unexpected response to ShouldISendThisPlum({})",
+                            offered_plum_head_seal
+                        );
+                    }
+                }
+            }
             log::trace!(
-                "    {} -> {:?}",
-                plum_relation_flags.0,
-                plum_relation_flags.1
+                "IDPClient::push({}); IDPServer wants {} of {} offered Plums",
+                plum_head_seal,
+                wanted_level_plum_head_seal_v.len(),
+                level_plum_head_seal_v.len()
             );
+
+            let relation_target_vv = self
+                .datahost_la
+                .read()
+                .await
+                .load_relation_targets_v(&wanted_level_plum_head_seal_v, mask, None)
+                .await?;
+            let mut next_level_plum_head_seal_v = Vec::new();
+            for (wanted_plum_head_seal, relation_target_v) in
+                wanted_level_plum_head_seal_v.iter().zip(relation_target_vv)
+            {
+                let mut target_plum_head_seal_v = Vec::with_capacity(relation_target_v.len());
+                for (target_plum_head_seal, _) in relation_target_v {
+                    if offered_plum_head_seal_s.insert(target_plum_head_seal.clone()) {
+                        next_level_plum_head_seal_v.push(target_plum_head_seal.clone());
+                    }
+                    target_plum_head_seal_v.push(target_plum_head_seal);
+                }
+                target_plum_head_seal_vm
+                    .insert(wanted_plum_head_seal.clone(), target_plum_head_seal_v);
+            }
+            wanted_plum_head_seal_v.extend(wanted_level_plum_head_seal_v);
+            level_plum_head_seal_v = next_level_plum_head_seal_v;
         }
 
-        // Accumulated plum_relations for a plum should not include the plum itself.
-        assert!(!plum_relation_flags_m.contains_key(plum_head_seal));
-        // NOTE: Because the plum_relations are collected via HashMap, they will be in a random order,
-        // which is not ideal for short-circuiting push operations when the target Datahost already
-        // has most of the Plum-s.  One solution to this is to accumulate the plum_relations and store
-        // them in (probably) a breadth-first traversal order.  A better solution would be to stream
-        // the plum_relations accumulations and only bother recursing on Plum-s that the target Datahost
-        // asks to receive.
-        let mut accumulated_plum_head_seal_v = Vec::with_capacity(plum_relation_flags_m.len() + 1);
-        // First, add this Plum.
-        accumulated_plum_head_seal_v.push(plum_head_seal.clone());
-        accumulated_plum_head_seal_v.extend(plum_relation_flags_m.keys().into_iter().cloned());
-
-        let datahost_la = self.datahost_la.clone();
-
-        // TEMP HACK -- just retrieve all the plums to send, don't bother with streaming efficiently for now.
-        let mut push_request_v = Vec::with_capacity(accumulated_plum_head_seal_v.len());
-        for accumulated_plum_head_seal in accumulated_plum_head_seal_v.iter() {
-            // log::trace!("IDPClient::push; pushing plum_head_seal {}", accumulated_plum_head_seal);
-            log::trace!(
-                "IDPClient::push; pushing plum with plum_head_seal {}",
-                accumulated_plum_head_seal
-            );
-            // TODO Temp hack and just send every Plum.  very wasteful, but just to test transport.
-            push_request_v.push(PushRequest {
-                // value: Some(idp_proto::push_request::Value::ShouldISendThisPlum(
-                //     accumulated_plum_head_seal.clone(),
-                // )),
-                value: Some(idp_proto::push_request::Value::HereHaveAPlum(
-                    datahost_la
-                        .read()
-                        .await
-                        .load_plum(&accumulated_plum_head_seal, None)
-                        .await
-                        .expect("TODO: handle this error for realsies"),
-                )),
-            });
+        // Send the wanted Plums, dependencies first.  For each chunk, first offer the PlumBody-s that
+        // haven't been offered yet, and only send the ones that the server asks for.  Each PlumBody is
+        // only loaded once it's about to be sent, and a large one is sent as PlumBodyChunk-s that are
+        // produced as they're sent.  At most PUSH_MAX_OUTSTANDING_REQUEST_COUNT of these requests are
+        // sent ahead of their responses, which bounds how much data is queued in the push channel.
+        let mut send_plum_head_seal_v =
+            topologically_sorted(&wanted_plum_head_seal_v, &target_plum_head_seal_vm);
+        send_plum_head_seal_v.reverse();
+        let mut transfer_report = TransferReport::default();
+        let mut offered_plum_body_seal_s = HashSet::new();
+        let mut outstanding_request_count = 0;
+        for send_plum_head_seal_c in send_plum_head_seal_v.chunks(PUSH_LOAD_BATCH_SIZE) {
+            let plum_head_metadata_and_relations_v = self
+                .datahost_la
+                .read()
+                .await
+                .load_plum_head_metadata_and_relations_v(send_plum_head_seal_c, None)
+                .await?;
+
+            let mut offered_chunk_plum_body_seal_v = Vec::new();
+            for plum_head_metadata_and_relations in plum_head_metadata_and_relations_v.iter() {
+                let plum_body_seal = &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+                if offered_plum_body_seal_s.insert(plum_body_seal.clone()) {
+                    offered_chunk_plum_body_seal_v.push(plum_body_seal.clone());
+                    push_request_tx.unbounded_send(PushRequest {
+                        value: Some(idp_proto::push_request::Value::ShouldISendThisPlumBody(
+                            plum_body_seal.clone(),
+                        )),
+                    })?;
+                }
+            }
+            // Maps each wanted PlumBody to the offset to start sending it from.
+            let mut wanted_plum_body_offset_m = HashMap::new();
+            for offered_plum_body_seal in offered_chunk_plum_body_seal_v.iter() {
+                let push_response = push_response_stream
+                    .message()
+                    .await
+                    .map_err(error_from_status)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("IDPServer ended the push response stream prematurely")
+                    })?;
+                match push_response.value {
+                    Some(idp_proto::push_response::Value::SendThisPlumBody(
+                        response_plum_body_seal,
+                    )) if response_plum_body_seal == *offered_plum_body_seal => {
+                        wanted_plum_body_offset_m.insert(response_plum_body_seal, 0);
+                    }
+                    Some(idp_proto::push_response::Value::SendThisPlumBodyStartingAt(
+                        PlumBodyOffset {
+                            plum_body_seal: response_plum_body_seal,
+                            offset,
+                        },
+                    )) if response_plum_body_seal == *offered_plum_body_seal => {
+                        wanted_plum_body_offset_m.insert(response_plum_body_seal, offset);
+                    }
+                    Some(idp_proto::push_response::Value::DontSendThisPlumBody(
+                        response_plum_body_seal,
+                    )) if response_plum_body_seal == *offered_plum_body_seal => {}
+                    _ => {
+                        anyhow::bail!(
+                            "IDPServer returned an unexpected response to ShouldISendThisPlumBody({})",
+                            offered_plum_body_seal
+                        );
+                    }
+                }
+            }
+
+            for plum_head_metadata_and_relations in plum_head_metadata_and_relations_v {
+                log::trace!(
+                    "IDPClient::push; pushing plum with plum_head_seal {}",
+                    PlumHeadSeal::from(&plum_head_metadata_and_relations.plum_head)
+                );
+                let plum_body_seal = &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+                // Only the first Plum having a wanted PlumBody sends it.  The server processes the
+                // requests in order, so it will have the PlumBody by the time it gets the others.
+                let value = match wanted_plum_body_offset_m.remove(plum_body_seal) {
+                    Some(offset) => {
+                        let plum_body = self
+                            .datahost_la
+                            .read()
+                            .await
+                            .load_option_plum_body(plum_body_seal, None)
+                            .await?
+                            .ok_or_else(|| {
+                                anyhow::anyhow!("PlumBody {} not found", plum_body_seal)
+                            })?;
+                        transfer_report.plum_body_count += 1;
+                        if offset == 0 && !plum_body.requires_chunking() {
+                            idp_proto::push_request::Value::HereHaveAPlum(Plum {
+                                plum_head: plum_head_metadata_and_relations.plum_head,
+                                plum_metadata: plum_head_metadata_and_relations.plum_metadata,
+                                plum_relations: plum_head_metadata_and_relations.plum_relations,
+                                plum_body,
+                            })
+                        } else {
+                            for plum_body_chunk in PlumBodyChunk::chunk_i_from(
+                                plum_body_seal,
+                                &plum_body,
+                                offset,
+                                PLUM_BODY_CHUNK_SIZE,
+                            )? {
+                                send_push_request_with_backpressure(
+                                    &push_request_tx,
+                                    &mut push_response_stream,
+                                    &mut outstanding_request_count,
+                                    idp_proto::push_request::Value::HereHaveAPlumBodyChunk(
+                                        plum_body_chunk,
+                                    ),
+                                )
+                                .await?;
+                            }
+                            idp_proto::push_request::Value::HereHaveAPlumHeadMetadataAndRelations(
+                                plum_head_metadata_and_relations,
+                            )
+                        }
+                    }
+                    None => idp_proto::push_request::Value::HereHaveAPlumHeadMetadataAndRelations(
+                        plum_head_metadata_and_relations,
+                    ),
+                };
+                transfer_report.plum_count += 1;
+                send_push_request_with_backpressure(
+                    &push_request_tx,
+                    &mut push_response_stream,
+                    &mut outstanding_request_count,
+                    value,
+                )
+                .await?;
+            }
+            // The responses to the outstanding requests have to be read before the next chunk's
+            // ShouldISendThisPlumBody responses.
+            while outstanding_request_count > 0 {
+                receive_push_ok_response(&mut push_response_stream).await?;
+                outstanding_request_count -= 1;
+            }
         }
 
-        // TODO: Figure out if this can be refactored to not require tokio_stream crate.
-        // use tokio_stream::StreamExt;
-        self.grpc_client
-            .push(
-                tokio_stream::iter(push_request_v),
-                // tokio_stream::iter(accumulated_plum_head_seal_v).map(move |plum_head_seal| {
-                //     // log::trace!("IDPClient::push; pushing plum_head_seal {}", plum_head_seal);
-                //     log::trace!(
-                //         "IDPClient::push; pushing plum with plum_head_seal {}",
-                //         plum_head_seal
-                //     );
-                //     // TODO Temp hack and just send every Plum.  very wasteful, but just to test transport.
-                //     PushRequest {
-                //         // value: Some(idp_proto::push_request::Value::ShouldISendThisPlum(
-                //         //     plum_head_seal.clone(),
-                //         // )),
-                //         value: Some(idp_proto::push_request::Value::HereHaveAPlum(
-                //             datahost_la
-                //                 .read()
-                //                 .await
-                //                 .load_plum(&plum_head_seal)
-                //                 .await
-                //                 .expect("TODO: handle this error for realsies"),
-                //         )),
-                //     }
-                // }),
-            )
-            .await?;
-        Ok(())
+        // Ending the request stream causes the server to end the response stream.
+        drop(push_request_tx);
+        if push_response_stream
+            .message()
+            .await
+            .map_err(error_from_status)?
+            .is_some()
+        {
+            anyhow::bail!("IDPServer returned an unexpected extra PushResponse");
+        }
+
+        Ok(transfer_report)
+    }
+    /// Pulls the given Plum and its dependencies (i.e. the Plums it refers to via CONTENT_DEPENDENCY
+    /// or METADATA_DEPENDENCY relations, recursively) from the server.  The Plums are requested a level
+    /// at a time (breadth-first, starting with the given Plum), and each received PlumHead, PlumMetadata,
+    /// and PlumRelations are verified and the PlumRelations are inspected to determine which of its
+    /// dependencies to request next, skipping any that are already present in the local Datahost (which
+    /// is then assumed to have their dependencies as well).  PlumBody-s are requested separately,
+    /// alongside the next level, and only if they're not already present, so that a PlumBody shared
+    /// by several Plums is only transferred once.  Anything other than what was requested is rejected.
+    /// The received data is only stored (in a single transaction) once all of it has been received.
+    /// A PlumBody too large to fit in a single message is received as a sequence of PlumBodyChunk-s;
+    /// if the pull is interrupted, the chunks received so far are kept, and pulling again resumes
+    /// from there.
+    pub async fn pull(&mut self, plum_head_seal: &PlumHeadSeal) -> Result<TransferReport> {
+        self.pull_impl(plum_head_seal, true).await
+    }
+    /// Like pull, but doesn't pull any PlumBody-s, i.e. only the PlumHead, PlumMetadata, and
+    /// PlumRelations of each Plum are pulled.  This is enough to traverse relations, but the pulled
+    /// Plums won't be complete (see Datahost::check_completeness) until their PlumBody-s are pulled
+    /// (e.g. using pull).  Plums whose PlumHead is already present locally are skipped.
+    pub async fn pull_shallow(&mut self, plum_head_seal: &PlumHeadSeal) -> Result<TransferReport> {
+        self.pull_impl(plum_head_seal, false).await
     }
-    pub async fn pull(&mut self, plum_head_seal: &PlumHeadSeal) -> Result<()> {
-        // TEMP HACK -- simply request the Plum, and the server will return all its recursive dependencies,
-        // and the client will trust that the server is sending all the right stuff.
-        let mut stream = self
+    async fn pull_impl(
+        &mut self,
+        plum_head_seal: &PlumHeadSeal,
+        include_plum_bodies: bool,
+    ) -> Result<TransferReport> {
+        let mask = idp_proto::PlumRelationFlags::CONTENT_DEPENDENCY
+            | idp_proto::PlumRelationFlags::METADATA_DEPENDENCY;
+
+        // See push regarding the channel.
+        let (pull_request_tx, pull_request_rx) = futures::channel::mpsc::unbounded();
+        let mut pull_response_stream = self
             .grpc_client
-            .pull(PullRequest {
-                value: Some(idp_proto::pull_request::Value::IWantThisPlum(
-                    plum_head_seal.clone(),
-                )),
-            })
-            .await?
+            .pull(pull_request_rx)
+            .await
+            .map_err(error_from_status)?
             .into_inner();
 
-        use tokio_stream::StreamExt;
-        while let Some(pull_response_r) = stream.next().await {
-            let pull_response = pull_response_r?;
-            match pull_response.value {
-                Some(idp_proto::pull_response::Value::Plum(plum)) => {
-                    // TODO: Check if we actually asked for this Plum.
-                    self.datahost_la
+        let mut visited_plum_head_seal_s = HashSet::new();
+        visited_plum_head_seal_s.insert(plum_head_seal.clone());
+        let mut requested_plum_body_seal_s = HashSet::new();
+        // Plums that haven't yet been checked for local presence.
+        let mut candidate_plum_head_seal_v = vec![plum_head_seal.clone()];
+        let mut level_plum_head_seal_v = Vec::new();
+        let mut level_plum_body_seal_v = Vec::new();
+        // All received data, in the order it was requested.
+        let mut plum_head_metadata_and_relations_v = Vec::new();
+        let mut plum_body_m = HashMap::new();
+        loop {
+            while !candidate_plum_head_seal_v.is_empty() {
+                // Plums whose PlumHead, PlumMetadata, and PlumRelations are already present locally, but
+                // which (for a full pull) are missing their PlumBody or some of their dependencies.
+                let mut local_plum_head_seal_v = Vec::new();
+                for candidate_plum_head_seal in std::mem::take(&mut candidate_plum_head_seal_v) {
+                    let datahost_g = self.datahost_la.read().await;
+                    if include_plum_bodies
+                        && datahost_g.has_plum(&candidate_plum_head_seal, None).await?
+                    {
+                        continue;
+                    }
+                    if datahost_g
+                        .has_plum_head(&candidate_plum_head_seal, None)
+                        .await?
+                    {
+                        if include_plum_bodies {
+                            local_plum_head_seal_v.push(candidate_plum_head_seal);
+                        }
+                    } else {
+                        level_plum_head_seal_v.push(candidate_plum_head_seal);
+                    }
+                }
+                if local_plum_head_seal_v.is_empty() {
+                    break;
+                }
+                let datahost_g = self.datahost_la.read().await;
+                let relation_target_vv = datahost_g
+                    .load_relation_targets_v(&local_plum_head_seal_v, mask, None)
+                    .await?;
+                for (local_plum_head_seal, relation_target_v) in
+                    local_plum_head_seal_v.iter().zip(relation_target_vv)
+                {
+                    let plum_body_seal = datahost_g
+                        .load_plum_head(local_plum_head_seal, None)
+                        .await?
+                        .plum_body_seal;
+                    if !requested_plum_body_seal_s.contains(&plum_body_seal)
+                        && !datahost_g.has_plum_body(&plum_body_seal, None).await?
+                    {
+                        requested_plum_body_seal_s.insert(plum_body_seal.clone());
+                        level_plum_body_seal_v.push(plum_body_seal);
+                    }
+                    for (target_plum_head_seal, _) in relation_target_v {
+                        if visited_plum_head_seal_s.insert(target_plum_head_seal.clone()) {
+                            candidate_plum_head_seal_v.push(target_plum_head_seal);
+                        }
+                    }
+                }
+            }
+            if level_plum_head_seal_v.is_empty() && level_plum_body_seal_v.is_empty() {
+                break;
+            }
+
+            for requested_plum_head_seal in level_plum_head_seal_v.iter() {
+                pull_request_tx.unbounded_send(PullRequest {
+                    value: Some(
+                        idp_proto::pull_request::Value::IWantThisPlumHeadMetadataAndRelations(
+                            requested_plum_head_seal.clone(),
+                        ),
+                    ),
+                })?;
+            }
+            for requested_plum_body_seal in level_plum_body_seal_v.iter() {
+                let value = match self.partial_plum_body_m.get(requested_plum_body_seal) {
+                    Some(partial_plum_body) => {
+                        idp_proto::pull_request::Value::IWantThisPlumBodyStartingAt(
+                            PlumBodyOffset {
+                                plum_body_seal: requested_plum_body_seal.clone(),
+                                offset: partial_plum_body.offset(),
+                            },
+                        )
+                    }
+                    None => idp_proto::pull_request::Value::IWantThisPlumBody(
+                        requested_plum_body_seal.clone(),
+                    ),
+                };
+                pull_request_tx.unbounded_send(PullRequest { value: Some(value) })?;
+            }
+
+            // The server responds to each request in order.
+            let mut next_level_plum_body_seal_v = Vec::new();
+            for requested_plum_head_seal in level_plum_head_seal_v.iter() {
+                let pull_response = pull_response_stream
+                    .message()
+                    .await
+                    .map_err(error_from_status)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("IDPServer ended the pull response stream prematurely")
+                    })?;
+                let plum_head_metadata_and_relations = match pull_response.value {
+                    Some(idp_proto::pull_response::Value::PlumHeadMetadataAndRelations(
+                        plum_head_metadata_and_relations,
+                    )) => plum_head_metadata_and_relations,
+                    Some(idp_proto::pull_response::Value::IDontHaveThisPlum(
+                        response_plum_head_seal,
+                    )) if response_plum_head_seal == *requested_plum_head_seal => {
+                        anyhow::bail!(
+                            "IDPServer indicated that it doesn't have requested Plum {}",
+                            requested_plum_head_seal
+                        );
+                    }
+                    _ => {
+                        anyhow::bail!(
+                            "IDPServer returned an unexpected response to IWantThisPlumHeadMetadataAndRelations({})",
+                            requested_plum_head_seal
+                        );
+                    }
+                };
+                // Compute the PlumHeadSeal ourselves, and check the other seals, so that the
+                // PlumRelations can be trusted.
+                let received_plum_head_seal =
+                    PlumHeadSeal::from(&plum_head_metadata_and_relations.plum_head);
+                anyhow::ensure!(
+                    received_plum_head_seal == *requested_plum_head_seal,
+                    "IDPServer sent Plum {} which wasn't requested (expected Plum {})",
+                    received_plum_head_seal,
+                    requested_plum_head_seal
+                );
+                plum_head_metadata_and_relations
+                    .verify_with_seal_key_provider(&self.seal_key_provider_ao)?;
+
+                let plum_body_seal = &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+                if include_plum_bodies
+                    && !requested_plum_body_seal_s.contains(plum_body_seal)
+                    && !self
+                        .datahost_la
                         .read()
                         .await
-                        .store_plum(&plum, None)
-                        .await?;
+                        .has_plum_body(plum_body_seal, None)
+                        .await?
+                {
+                    requested_plum_body_seal_s.insert(plum_body_seal.clone());
+                    next_level_plum_body_seal_v.push(plum_body_seal.clone());
                 }
-                Some(idp_proto::pull_response::Value::IDontHaveThisPlum(plum_head_seal)) => {
+                for plum_relation_flags_mapping in plum_head_metadata_and_relations
+                    .plum_relations
+                    .plum_relation_flags_mapping_v
+                    .iter()
+                {
+                    let masked_relation_flags = mask
+                        & idp_proto::PlumRelationFlags::try_from(
+                            plum_relation_flags_mapping.plum_relation_flags_raw,
+                        )?;
+                    if masked_relation_flags == idp_proto::PlumRelationFlags::NONE {
+                        continue;
+                    }
+                    let target_plum_head_seal = &plum_relation_flags_mapping.target_plum_head_seal;
+                    if visited_plum_head_seal_s.insert(target_plum_head_seal.clone()) {
+                        candidate_plum_head_seal_v.push(target_plum_head_seal.clone());
+                    }
+                }
+                plum_head_metadata_and_relations_v.push(plum_head_metadata_and_relations);
+            }
+            for requested_plum_body_seal in level_plum_body_seal_v.iter() {
+                // A chunked PlumBody produces one response per PlumBodyChunk.
+                let plum_body = loop {
+                    let pull_response = pull_response_stream
+                        .message()
+                        .await
+                        .map_err(error_from_status)?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("IDPServer ended the pull response stream prematurely")
+                        })?;
+                    match pull_response.value {
+                        Some(idp_proto::pull_response::Value::PlumBody(plum_body)) => {
+                            self.partial_plum_body_m.remove(requested_plum_body_seal);
+                            break plum_body;
+                        }
+                        Some(idp_proto::pull_response::Value::PlumBodyChunk(plum_body_chunk))
+                            if plum_body_chunk.plum_body_seal == *requested_plum_body_seal =>
+                        {
+                            let partial_plum_body = self
+                                .partial_plum_body_m
+                                .entry(requested_plum_body_seal.clone())
+                                .or_insert_with(|| {
+                                    PartialPlumBody::new(
+                                        requested_plum_body_seal.clone(),
+                                        plum_body_chunk.total_length,
+                                    )
+                                });
+                            partial_plum_body.append_chunk(plum_body_chunk)?;
+                            if partial_plum_body.is_complete() {
+                                // This verifies the reassembled PlumBody against its PlumBodySeal.
+                                break self
+                                    .partial_plum_body_m
+                                    .remove(requested_plum_body_seal)
+                                    .unwrap()
+                                    .into_plum_body(Some(&self.seal_key_provider_ao))?;
+                            }
+                        }
+                        Some(idp_proto::pull_response::Value::IDontHaveThisPlumBody(
+                            response_plum_body_seal,
+                        )) if response_plum_body_seal == *requested_plum_body_seal => {
+                            anyhow::bail!(
+                                "IDPServer indicated that it doesn't have requested PlumBody {}",
+                                requested_plum_body_seal
+                            );
+                        }
+                        _ => {
+                            anyhow::bail!(
+                                "IDPServer returned an unexpected response to IWantThisPlumBody({})",
+                                requested_plum_body_seal
+                            );
+                        }
+                    }
+                };
+                // A keyed PlumBodySeal whose SealKey isn't provided can't be checked, so it's rejected.
+                if let Err(e) =
+                    requested_plum_body_seal.verify(&plum_body, Some(&self.seal_key_provider_ao))
+                {
                     anyhow::bail!(
-                        "IDPServer indicated that it doesn't have requested Plum {}",
-                        plum_head_seal
+                        "IDPServer sent a PlumBody which couldn't be verified as requested PlumBody {}; {}",
+                        requested_plum_body_seal,
+                        e
                     );
                 }
+                plum_body_m.insert(requested_plum_body_seal.clone(), plum_body);
+            }
+            log::trace!(
+                "IDPClient::pull({}); received {} Plums and {} PlumBody-s",
+                plum_head_seal,
+                level_plum_head_seal_v.len(),
+                level_plum_body_seal_v.len()
+            );
+
+            level_plum_head_seal_v.clear();
+            level_plum_body_seal_v = next_level_plum_body_seal_v;
+        }
+
+        // Ending the request stream causes the server to end the response stream.
+        drop(pull_request_tx);
+        if pull_response_stream
+            .message()
+            .await
+            .map_err(error_from_status)?
+            .is_some()
+        {
+            anyhow::bail!("IDPServer returned an unexpected extra PullResponse");
+        }
+
+        // Check the constraints between each received Plum and its PlumBody, if it was received.
+        for plum_head_metadata_and_relations in plum_head_metadata_and_relations_v.iter() {
+            if let Some(plum_body) =
+                plum_body_m.get(&plum_head_metadata_and_relations.plum_head.plum_body_seal)
+            {
+                plum_head_metadata_and_relations.verify_plum_body_with_seal_key_provider(
+                    plum_body,
+                    &self.seal_key_provider_ao,
+                )?;
+            }
+        }
+
+        let transfer_report = TransferReport {
+            plum_count: plum_head_metadata_and_relations_v.len(),
+            plum_body_count: plum_body_m.len(),
+        };
+
+        // Everything has been received, so store it all at once.  Each received Plum whose PlumBody
+        // was also received is stored using Datahost::store_plum_v.  The other received Plums (whose
+        // PlumBody-s are already present, or weren't requested) and PlumBody-s (whose Plums are
+        // already present) are stored individually.
+        let mut plum_v: Vec<Plum> = Vec::new();
+        // Maps the PlumBodySeal of each PlumBody moved into plum_v to the index of its Plum, so that a
+        // PlumBody shared by several received Plums can be cloned from there.
+        let mut plum_v_index_m: HashMap<PlumBodySeal, usize> = HashMap::new();
+        let mut bodiless_plum_head_metadata_and_relations_v = Vec::new();
+        for plum_head_metadata_and_relations in plum_head_metadata_and_relations_v {
+            let plum_body_seal = plum_head_metadata_and_relations
+                .plum_head
+                .plum_body_seal
+                .clone();
+            let plum_body_o = plum_body_m.remove(&plum_body_seal).or_else(|| {
+                plum_v_index_m
+                    .get(&plum_body_seal)
+                    .map(|&plum_v_index| plum_v[plum_v_index].plum_body.clone())
+            });
+            match plum_body_o {
+                Some(plum_body) => {
+                    plum_v_index_m.entry(plum_body_seal).or_insert(plum_v.len());
+                    plum_v.push(Plum {
+                        plum_head: plum_head_metadata_and_relations.plum_head,
+                        plum_metadata: plum_head_metadata_and_relations.plum_metadata,
+                        plum_relations: plum_head_metadata_and_relations.plum_relations,
+                        plum_body,
+                    });
+                }
                 None => {
-                    anyhow::bail!("IDPServer returned a malformed response");
+                    bodiless_plum_head_metadata_and_relations_v
+                        .push(plum_head_metadata_and_relations);
                 }
             }
         }
-        // Stream is dropped here and the disconnect info is sent to server.  This is not ideal,
-        // we would prefer to keep the connection and simply send more requests.  But for now,
-        // just get it working and optimize later.
-
-        // let mut traverse_this_plum_v = VecDeque::with_capacity(1);
-        // traverse_this_plum_v.push_back(plum_head_seal.clone());
-
-        // let mut pull_request_v: VecDeque<PullRequest> = VecDeque::new();
-
-        // while let Some(traverse_this_plum) = traverse_this_plum_v.pop_front() {
-        //     // Check if we have the components of this Plum.
-        //     let has_plum_head = self.datahost_la.read().has_plum_head(&traverse_this_plum)?;
-        //     let has_plum_relations = self
-        //         .datahost_la
-        //         .read()
-        //         .has_plum_relations_for(&traverse_this_plum)?;
-        //     match (has_plum_head, has_plum_relations) {
-        //         (false, false) => {
-        //             pull_request_v.push_back(PullRequest {
-        //                 value: Some(
-        //                     idp_proto::pull_request::Value::IWantThisPlumHeadAndRelations(
-        //                         traverse_this_plum.clone(),
-        //                     ),
-        //                 ),
-        //             });
-        //         }
-        //         (false, true) => {
-        //             pull_request_v.push_back(PullRequest {
-        //                 value: Some(idp_proto::pull_request::Value::IWantThisPlumHead(
-        //                     traverse_this_plum.clone(),
-        //                 )),
-        //             });
-        //         }
-        //         (true, false) => {
-        //             pull_request_v.push_back(PullRequest {
-        //                 value: Some(idp_proto::pull_request::Value::IWantThisPlumRelations(
-        //                     traverse_this_plum.clone(),
-        //                 )),
-        //             });
-        //         }
-        //         (true, true) => {
-        //             // Traverse to the related Plums.
-        //             unimplemented!("TODO");
-        //         }
-        //     }
-
-        //     if has_plum_head {
-        //         let plum_head = self
-        //             .datahost_la
-        //             .read()
-        //             .select_plum_head_row(plum_head_seal)?;
-        //         let has_plum_body = self
-        //             .datahost_la
-        //             .read()
-        //             .has_plum_body(&plum_head.body_seal)?;
-        //         if !has_plum_body {
-        //             pull_request_v.push_back(PullRequest {
-        //                 value: Some(idp_proto::pull_request::Value::IWantThisPlumBody(
-        //                     plum_head.body_seal.clone(),
-        //                 )),
-        //             });
-        //         }
-        //     } else {
-        //         // This is a bit inelegant, but if we don't have the PlumHead, then we're not able to
-        //         // complete the Plum traversal, so put this traversal back at the front of the queue;
-        //         // the server should send the PlumHead in the request below, and then we can resume
-        //         // traversal.
-        //         traverse_this_plum_v.push_front(traverse_this_plum);
-        //     }
-
-        //     // TODO: Stream all existing pull requests
-
-        //     // Issue the next PullRequest in the deque (if there are any), and process its stream of responses.
-        //     if let Some(pull_request) = pull_request_v.pop_front() {
-        //         let mut stream = self
-        //             .grpc_client
-        //             .pull(pull_request)
-        //             .await
-        //             .unwrap()
-        //             .into_inner();
-
-        //         use tokio_stream::StreamExt;
-        //         while let Some(pull_response_r) = stream.next().await {
-        //             let pull_response = pull_response_r?;
-        //             match pull_response.value {
-        //                 Some(idp_proto::pull_response::Value::PlumHead(plum_head)) => {
-        //                     // Compute the PlumHeadSeal ourselves.
-        //                     let plum_head_seal = PlumHeadSeal::from(&plum_head);
-        //                     if self.datahost_la.read().has_plum_head(&plum_head_seal)? {
-        //                         panic!("programmer error (potentially in the server): we shouldn't be receiving a PlumHead that we already have (and therefore didn't ask for)");
-        //                     } else {
-        //                         self.datahost_la.read().store_plum_head(&plum_head)?;
-        //                     }
-        //                 }
-        //                 Some(idp_proto::pull_response::Value::PlumHeadSealAndRelations(
-        //                     plum_head_seal_and_relations,
-        //                 )) => {
-        //                     // Compute the PlumRelationsSeal ourselves.
-        //                     let plum_relations_seal = PlumRelationsSeal::from(
-        //                         &plum_head_seal_and_relations.plum_relations,
-        //                     );
-        //                     // Check that the PlumRelationsSeal match that stored in the the PlumHead (that we already
-        //                     // have -- NOTE: This assumes we already have the PlumHead, which may not be true.)
-        //                     {
-        //                         let plum_head = self
-        //                             .datahost_la
-        //                             .read()
-        //                             .load_plum_head(&plum_head_seal_and_relations.plum_head_seal)
-        //                             .context("PlumRelations received before PlumHead")?;
-        //                         plum_head.verify_plum_relations_seal(plum_relations_seal)?;
-        //                         // anyhow::ensure!(plum_head.plum_relations_seal_o.is_some());
-        //                         // anyhow::ensure!(
-        //                         //     plum_relations_seal
-        //                         //         == *plum_head.plum_relations_seal_o.as_ref().unwrap()
-        //                         // );
-        //                     }
-        //                     if self.datahost_la.read().has_plum_relations_for(
-        //                         &plum_head_seal_and_relations.plum_head_seal,
-        //                     )? {
-        //                         panic!("programmer error (potentially in the server): we shouldn't be receiving a PlumHead that we already have (and therefore didn't ask for)");
-        //                     } else {
-        //                         self.datahost_la.read().store_plum_relations(
-        //                             &plum_head_seal_and_relations.plum_head_seal,
-        //                             &plum_head_seal_and_relations.plum_relations,
-        //                         )?;
-        //                     }
-        //                 }
-        //                 Some(idp_proto::pull_response::Value::PlumHeadAndRelations(
-        //                     plum_head_and_relations,
-        //                 )) => {
-        //                     // Compute the PlumHeadSeal ourselves.
-        //                     let plum_head_seal =
-        //                         PlumHeadSeal::from(&plum_head_and_relations.plum_head);
-        //                     // Compute the PlumRelationsSeal ourselves.
-        //                     let plum_relations_seal =
-        //                         PlumRelationsSeal::from(&plum_head_and_relations.plum_relations);
-        //                     // Check that the PlumRelationsSeal match that stored in the PlumHead.
-        //                     {
-        //                         anyhow::ensure!(plum_head_and_relations
-        //                             .plum_head
-        //                             .plum_relations_seal_o
-        //                             .is_some());
-        //                         anyhow::ensure!(
-        //                             plum_relations_seal
-        //                                 == *plum_head_and_relations
-        //                                     .plum_head
-        //                                     .plum_relations_seal_o
-        //                                     .as_ref()
-        //                                     .unwrap()
-        //                         );
-        //                     }
-        //                     if self.datahost_la.read().has_plum_head(&plum_head_seal)? {
-        //                         panic!("programmer error (potentially in the server): we shouldn't be receiving a PlumHead that we already have (and therefore didn't ask for)");
-        //                     } else {
-        //                         self.datahost_la
-        //                             .read()
-        //                             .store_plum_head(&plum_head_and_relations.plum_head)?;
-        //                     }
-        //                     if self
-        //                         .datahost_la
-        //                         .read()
-        //                         .has_plum_relations_for(&plum_head_seal)?
-        //                     {
-        //                         panic!("programmer error (potentially in the server): we shouldn't be receiving a PlumHead that we already have (and therefore didn't ask for)");
-        //                     } else {
-        //                         self.datahost_la.read().store_plum_relations(
-        //                             &plum_head_seal,
-        //                             &plum_head_and_relations.plum_relations,
-        //                         )?;
-        //                     }
-        //                 }
-        //                 Some(idp_proto::pull_response::Value::PlumBody(plum_body)) => {
-        //                     // Compute the PlumBodySeal ourselves.
-        //                     // TODO: Figure out how to stream it -- this would involve streaming chunks of the
-        //                     // PlumBody, and probably writing those chunks to the filesystem, since writing
-        //                     // chunks to a DB is maybe not feasible.  Or the DB could store chunks directly,
-        //                     // though that makes other operations difficult, such as deserializing a PlumBody.
-        //                     let plum_body_seal = PlumBodySeal::from(&plum_body);
-        //                     if self.datahost_la.read().has_plum_body(&plum_body_seal)? {
-        //                         panic!("programmer error (potentially in the server): we shouldn't be receiving a PlumBody that we already have (and therefore didn't ask for)");
-        //                     } else {
-        //                         self.datahost_la.read().store_plum_body(&plum_body)?;
-        //                     }
-        //                 }
-        //                 Some(idp_proto::pull_response::Value::Plum(plum)) => {
-        //                     let plum_head_seal = PlumHeadSeal::from(&plum.plum_head);
-        //                     plum_head_seal.
-        //                 }
-        //                 None => {
-        //                     anyhow::bail!("malformed PullResponse");
-        //                 }
-        //             }
-        //         }
-        //         // Stream is dropped here and the disconnect info is sent to server.  This is not ideal,
-        //         // we would prefer to keep the connection and simply send more requests.  But for now,
-        //         // just get it working and optimize later.
-        //     }
-        // }
-
-        // let (has_plum_head, has_plum_relations) = {
-        //     let datahost_g = self.datahost_la.read();
-        //     let has_plum_head = datahost_g.has_plum_head(plum_head_seal)?;
-        //     let has_plum_relations = datahost_g.has_plum_relations_for(plum_head_seal)?;
-        //     if has_plum_head && has_plum_relations {
-        //         let plum_head = datahost_g.select_plum_head_row(plum_head_seal)?;
-        //         if datahost_g.has_plum_body(&plum_head.body_seal) {
-        //             // If we already have this PlumHead, its PlumRelations, and its PlumBody, then
-        //             // there's nothing to do (assuming the dependency graph hasn't been broken by
-        //             // some outside process or action on the DB).
-        //             // TODO: Could do a check that all recursive dependencies are present (i.e. the
-        //             // DB is consistent).
-        //             return Ok(());
-        //         }
-        //     }
-        //     (has_plum_head, has_plum_relations)
-        // };
-
-        // // TODO: This could be made into a bidirectional streaming call, but for now we just want
-        // // to get it working, and not prematurely optimize.
-
-        // let mut pull_request_v = VecDeque::new();
-        // match (has_plum_head, has_plum_relations) {
-        //     (true, true)
-        // }
-        // if has_plum_head {
-        //     if has_plum_relations {
-
-        //     } else {
-
-        //     }
-        // } else {
-        //     if has_plum_relations {
-
-        //     } else {
-
-        //     }
-        // }
-        // pull_request_v.push_back(PullRequest { value: idp_proto::pull_request::Value::IWantThisPlumHead(())})
-
-        // while let Some(i_want_this_plum_head) = take_any_element_from_hash_set(&mut i_want_this_plum_head_s) {
-        //     let mut stream = self
-        //         .grpc_client
-        //         .pull(PullRequest { value: Some(idp_proto::pull_request::Value::IWantThisPlumHead(i_want_this_plum)) })
-        //         .await
-        //         .unwrap()
-        //         .into_inner();
-
-        //     use tokio_stream::StreamExt;
-        //     while let Some(pull_response_r) = stream.next().await {
-        //         let pull_response = pull_response_r?;
-        //         match pull_response.value {
-        //             Some(idp_proto::pull_response::Value::PlumHead(plum_head)) => {
-        //                 // Compute the PlumHeadSeal ourselves.
-        //                 let plum_head_seal = PlumHeadSeal::from(&plum_head);
-        //                 // Check if we already have (1) this PlumHead, (2) its PlumRelations, and (3) its PlumBody.
-        //                 if self.datahost_la.read().has_plum_head(&plum_head_seal)? {
-        //                     panic!("programmer error (potentially in the server): we shouldn't be receiving a PlumHead that we already have (and therefore didn't ask for)");
-        //                 } else {
-        //                     self.datahost_la.read().store_plum_head(&plum_head)?;
-        //                 }
-
-        //                 if !self.datahost_la.read().has_plum_relations_for(&plum_head_seal)? {
-        //                     i_want_this_plum_relations_s.insert(plum_head_seal.clone());
-        //                 }
-
-        //                 if !self.datahost_la.read().has_plum_body(&plum_head.body_seal)? {
-        //                     i_want_this_plum_body_s.insert(plum_head.body_seal.clone());
-        //                 }
-        //             }
-        //             Some(idp_proto::pull_response::Value::PlumRelations(plum_relations)) => {
-        //                 self.datahost_la.read().store_plum_relations(source_head_seal, &plum_relations)
-        //             }
-        //             Some(idp_proto::pull_response::Value::HereHaveAPlum(plum)) => {
-        //                 self.datahost_la.read().store_plum(&plum)?;
-        //             }
-        //             None => {
-        //                 anyhow::bail!("malformed PullResponse");
-        //             }
-        //         }
-        //     }
-        //     // Stream is dropped here and the disconnect info is sent to server.  This is not ideal,
-        //     // we would prefer to keep the connection and simply send more requests.  But for now,
-        //     // just get it working and optimize later.
-        // }
+        let datahost_g = self.datahost_la.read().await;
+        let mut transaction = datahost_g.begin_transaction().await?;
+        for (plum_body_seal, plum_body) in plum_body_m.iter() {
+            datahost_g
+                .store_plum_body(plum_body, plum_body_seal, Some(transaction.as_mut()))
+                .await?;
+        }
+        datahost_g
+            .store_plum_v(&plum_v, Some(transaction.as_mut()))
+            .await?;
+        for plum_head_metadata_and_relations in bodiless_plum_head_metadata_and_relations_v.iter() {
+            datahost_g
+                .store_plum_head_metadata_and_relations(
+                    plum_head_metadata_and_relations,
+                    Some(transaction.as_mut()),
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(transfer_report)
+    }
+    /// Pushes the head of the given local branch (and therefore its history and content), and then
+    /// creates the branch on the server or sets its head, using whichever BranchSetHeadRequest
+    /// operation is appropriate, as determined by the closest common ancestor of the local and server
+    /// branch heads.  If the local Datahost doesn't have the server's branch head, it's pulled first,
+    /// since that's needed in order to find the closest common ancestor.  Note that this will rewind
+    /// or rewrite the history of the branch on the server if that's what the local branch requires.
+    /// However, if the server's branch head changes after it was read (e.g. due to a concurrent push),
+    /// then the server rejects the operation (see BranchSetHeadRequest::expected_current_branch_head_o)
+    /// with BranchError::UnexpectedCurrentBranchHead, instead of clobbering that change.  Upon success,
+    /// the remote-tracking PathState (see remote_tracking_branch_path) is updated to the pushed head,
+    /// as in fetch_branch.
+    pub async fn push_branch(&mut self, branch_path: &Path) -> Result<BranchUpdate> {
+        let branch_head = self
+            .datahost_la
+            .read()
+            .await
+            .branch_get_head(branch_path, None)
+            .await?;
+        let transfer_report = self.push(&branch_head).await?;
+        log::trace!(
+            "IDPClient::push_branch({:?}); pushed branch head {}; {:?}",
+            branch_path,
+            branch_head,
+            transfer_report
+        );
+
+        let branch_update = self
+            .set_remote_branch_head(branch_path, &branch_head)
+            .await?;
+
+        self.datahost_la
+            .read()
+            .await
+            .set_remote_tracking_branch_head(REMOTE_NAME, branch_path, &branch_head, None)
+            .await?;
+
+        Ok(branch_update)
+    }
+    /// Creates the branch on the server with the given (already pushed) head, or sets its head, for
+    /// push_branch.
+    async fn set_remote_branch_head(
+        &mut self,
+        branch_path: &Path,
+        branch_head: &PlumHeadSeal,
+    ) -> Result<BranchUpdate> {
+        let remote_branch_head = match self
+            .grpc_client
+            .branch_get_head(BranchGetHeadRequest {
+                branch_path: branch_path.clone(),
+            })
+            .await
+        {
+            Ok(response) => response.into_inner().branch_head_plum_head_seal,
+            Err(status) if status.code() == tonic::Code::NotFound => {
+                self.grpc_client
+                    .branch_create(BranchCreateRequest {
+                        branch_path_state: PathState {
+                            path: branch_path.clone(),
+                            current_state_plum_head_seal: branch_head.clone(),
+                        },
+                    })
+                    .await
+                    .map_err(error_from_status)?;
+                return Ok(BranchUpdate::Created);
+            }
+            Err(status) => {
+                return Err(error_from_status(status));
+            }
+        };
+        if &remote_branch_head == branch_head {
+            return Ok(BranchUpdate::Unchanged);
+        }
 
+        self.pull(&remote_branch_head).await?;
+        let (value, branch_update) = self
+            .datahost_la
+            .read()
+            .await
+            .branch_set_head_operation(&remote_branch_head, branch_head, None)
+            .await?;
+        self.grpc_client
+            .branch_set_head(BranchSetHeadRequest {
+                branch_path: branch_path.clone(),
+                value: Some(value),
+                expected_current_branch_head_o: Some(remote_branch_head),
+            })
+            .await
+            .map_err(error_from_status)?;
+        Ok(branch_update)
+    }
+    /// Pulls the server's head of the given branch (and therefore its history and content), and
+    /// records it in the local Datahost as the PathState given by remote_tracking_branch_path, which
+    /// is created or updated as needed.  The local branch itself (if any) isn't modified.  Returns
+    /// the server's branch head.
+    pub async fn fetch_branch(&mut self, branch_path: &Path) -> Result<PlumHeadSeal> {
+        let remote_branch_head = self
+            .grpc_client
+            .branch_get_head(BranchGetHeadRequest {
+                branch_path: branch_path.clone(),
+            })
+            .await
+            .map_err(error_from_status)?
+            .into_inner()
+            .branch_head_plum_head_seal;
+        let transfer_report = self.pull(&remote_branch_head).await?;
+        log::trace!(
+            "IDPClient::fetch_branch({:?}); pulled branch head {}; {:?}",
+            branch_path,
+            remote_branch_head,
+            transfer_report
+        );
+
+        self.datahost_la
+            .read()
+            .await
+            .set_remote_tracking_branch_head(REMOTE_NAME, branch_path, &remote_branch_head, None)
+            .await?;
+
+        Ok(remote_branch_head)
+    }
+    /// Returns the path of the local PathState that fetch_branch uses to track the server's head of
+    /// the given branch, i.e. "origin/" followed by branch_path (see
+    /// Datahost::remote_tracking_branch_path).
+    pub fn remote_tracking_branch_path(branch_path: &Path) -> Path {
+        Datahost::remote_tracking_branch_path(REMOTE_NAME, branch_path)
+    }
+    /// Creates a branch on the server, as in Datahost::branch_create.  The BranchNode Plum that the
+    /// branch refers to must have already been pushed.
+    pub async fn branch_create(&mut self, branch_path_state: &PathState) -> Result<()> {
+        self.grpc_client
+            .branch_create(BranchCreateRequest {
+                branch_path_state: branch_path_state.clone(),
+            })
+            .await
+            .map_err(error_from_status)?;
+        Ok(())
+    }
+    /// Deletes a branch on the server, as in Datahost::branch_delete.
+    pub async fn branch_delete(&mut self, branch_path: &Path) -> Result<()> {
+        self.grpc_client
+            .branch_delete(BranchDeleteRequest {
+                branch_path: branch_path.clone(),
+            })
+            .await
+            .map_err(error_from_status)?;
+        Ok(())
+    }
+    /// Returns the head of the given branch on the server, as in Datahost::branch_get_head.
+    pub async fn branch_get_head(&mut self, branch_path: &Path) -> Result<PlumHeadSeal> {
+        Ok(self
+            .grpc_client
+            .branch_get_head(BranchGetHeadRequest {
+                branch_path: branch_path.clone(),
+            })
+            .await
+            .map_err(error_from_status)?
+            .into_inner()
+            .branch_head_plum_head_seal)
+    }
+    /// Sets the head of a branch on the server, as in Datahost::branch_set_head.  The new branch head
+    /// must have already been pushed.  If the operation doesn't apply (e.g. a fast-forward to a
+    /// non-descendant), then the error can be downcast to the corresponding BranchError.
+    pub async fn branch_set_head(
+        &mut self,
+        branch_set_head_request: BranchSetHeadRequest,
+    ) -> Result<()> {
+        self.grpc_client
+            .branch_set_head(branch_set_head_request)
+            .await
+            .map_err(error_from_status)?;
         Ok(())
     }
+    /// Returns the server's PathState for the given path.
+    pub async fn path_state_get(&mut self, path: &Path) -> Result<PathState> {
+        Ok(self
+            .grpc_client
+            .path_state_get(PathStateGetRequest { path: path.clone() })
+            .await
+            .map_err(error_from_status)?
+            .into_inner()
+            .path_state)
+    }
+    /// Returns one page of the server's PathStates (optionally only those whose path begins with
+    /// path_prefix_o), ordered by when they were last updated.  To get the next page, pass in the
+    /// next_page_cursor_o from the returned PathStateListResponse; if it's None, there are no more.
+    /// The server may return fewer than page_size PathStates, even if there are more.
+    pub async fn path_state_list(
+        &mut self,
+        path_prefix_o: Option<&str>,
+        page_size: u32,
+        page_cursor_o: Option<PageCursor>,
+    ) -> Result<PathStateListResponse> {
+        Ok(self
+            .grpc_client
+            .path_state_list(PathStateListRequest {
+                path_prefix_o: path_prefix_o.map(str::to_string),
+                page_size,
+                page_cursor_o,
+                updated_at_begin_o: None,
+            })
+            .await
+            .map_err(error_from_status)?
+            .into_inner())
+    }
+    /// Polling alternative to watch_path_states.  Returns the server's PathStates (optionally only
+    /// those whose path begins with path_prefix_o) that were inserted or last updated at or after
+    /// updated_at_begin, in the order they were updated, along with the updated_at_begin to pass to
+    /// the next poll.  Because that bound is inclusive, the most recently updated PathState is
+    /// returned again by the next poll.  Note that deletions of PathStates aren't reported.
+    pub async fn path_states_updated_since(
+        &mut self,
+        path_prefix_o: Option<&str>,
+        updated_at_begin: UnixNanoseconds,
+    ) -> Result<(Vec<PathState>, UnixNanoseconds)> {
+        let mut path_state_v = Vec::new();
+        let mut next_updated_at_begin = updated_at_begin;
+        let mut page_cursor_o = None;
+        loop {
+            let path_state_list_response = self
+                .grpc_client
+                .path_state_list(PathStateListRequest {
+                    path_prefix_o: path_prefix_o.map(str::to_string),
+                    page_size: POLL_PAGE_SIZE,
+                    page_cursor_o,
+                    updated_at_begin_o: Some(updated_at_begin),
+                })
+                .await
+                .map_err(error_from_status)?
+                .into_inner();
+            path_state_v.extend(path_state_list_response.path_state_v);
+            if let Some(last_updated_at) = path_state_list_response.last_updated_at_o {
+                next_updated_at_begin = last_updated_at;
+            }
+            page_cursor_o = path_state_list_response.next_page_cursor_o;
+            if page_cursor_o.is_none() {
+                break;
+            }
+        }
+        Ok((path_state_v, next_updated_at_begin))
+    }
+    /// Watches the given PathStates on the server (or all of them that this client may read, if
+    /// path_v is empty), returning a stream of their subsequent changes.  Each time a watched PathState
+    /// is inserted or updated, the Plum it refers to (and its dependencies) is pulled before the change
+    /// is yielded, so that the new state is already present locally when it's handled.  The stream
+    /// ends when the server ends the watch (with a ResourceExhausted error if this client fell too far
+    /// behind, in which case the PathStates should be reloaded and watched again), and the watch ends
+    /// when the stream is dropped.
+    pub async fn watch_path_states(
+        &mut self,
+        path_v: &[Path],
+    ) -> Result<impl futures::Stream<Item = Result<PathStateChange>> + '_> {
+        let watch_path_states_response_stream = self
+            .grpc_client
+            .watch_path_states(WatchPathStatesRequest {
+                path_v: path_v.to_vec(),
+            })
+            .await
+            .map_err(error_from_status)?
+            .into_inner();
+        Ok(futures::stream::try_unfold(
+            (self, watch_path_states_response_stream),
+            |(idp_client, mut watch_path_states_response_stream)| async move {
+                let watch_path_states_response = match watch_path_states_response_stream
+                    .message()
+                    .await
+                    .map_err(error_from_status)?
+                {
+                    Some(watch_path_states_response) => watch_path_states_response,
+                    None => {
+                        return Ok(None);
+                    }
+                };
+                let path_state_change = watch_path_states_response.path_state_change;
+                match path_state_change.value.as_ref() {
+                    Some(path_state_change::Value::Inserted(path_state))
+                    | Some(path_state_change::Value::Updated(path_state)) => {
+                        let transfer_report = idp_client
+                            .pull(&path_state.current_state_plum_head_seal)
+                            .await?;
+                        log::trace!(
+                            "IDPClient::watch_path_states; pulled {} for {:?}; {:?}",
+                            path_state.current_state_plum_head_seal,
+                            path_state.path,
+                            transfer_report
+                        );
+                    }
+                    Some(path_state_change::Value::Deleted(_)) => {}
+                    None => {
+                        anyhow::bail!("IDPServer sent a PathStateChange having no value");
+                    }
+                }
+                Ok(Some((
+                    path_state_change,
+                    (idp_client, watch_path_states_response_stream),
+                )))
+            },
+        ))
+    }
+    /// Returns which of the given Plums the server has (including their PlumBody-s), without
+    /// transferring them.  The returned Vec corresponds elementwise with plum_head_seal_v.
+    pub async fn has_plums(&mut self, plum_head_seal_v: &[PlumHeadSeal]) -> Result<Vec<bool>> {
+        let has_plum_v = self
+            .grpc_client
+            .has_plums(HasPlumsRequest {
+                plum_head_seal_v: plum_head_seal_v.to_vec(),
+            })
+            .await
+            .map_err(error_from_status)?
+            .into_inner()
+            .has_plum_v;
+        anyhow::ensure!(
+            has_plum_v.len() == plum_head_seal_v.len(),
+            "IDPServer returned {} results to HasPlums for {} Plums",
+            has_plum_v.len(),
+            plum_head_seal_v.len()
+        );
+        Ok(has_plum_v)
+    }
 }
 
 // /// This will remove and return the "first" element (in the randomized order that the HashSet stores
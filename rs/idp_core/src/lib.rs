@@ -1,27 +1,58 @@
+mod access_policy;
+#[cfg(feature = "client")]
+mod bearer_token_credential_provider;
 mod branch_error;
 mod branch_node;
 mod branch_node_builder;
+mod branch_update;
+#[cfg(feature = "client")]
+mod client_tls_config;
+mod completeness_report;
+#[cfg(feature = "client")]
+mod credential_provider;
 mod datacache;
 mod datahost;
+mod dependency_complete_cache;
 mod dir_node;
+mod error_detail_conversion;
 mod fragment;
+mod garbage_collection_report;
 #[cfg(feature = "client")]
 mod idp_client;
 mod load_plum_and_deserialize_error;
 mod path_state_error;
+mod permission;
 mod plum_ref;
 mod plum_uri;
+mod plum_uri_parse_error;
+mod relation_traversal;
+mod transfer_report;
 
+pub use access_policy::{AccessControlList, AccessPolicy};
+#[cfg(feature = "client")]
+pub use bearer_token_credential_provider::BearerTokenCredentialProvider;
 pub use branch_error::BranchError;
 pub use branch_node::BranchNode;
 pub use branch_node_builder::BranchNodeBuilder;
+pub use branch_update::BranchUpdate;
+#[cfg(feature = "client")]
+pub use client_tls_config::ClientTLSConfig;
+pub use completeness_report::{CompletenessReport, PlumCompleteness};
+#[cfg(feature = "client")]
+pub use credential_provider::CredentialProvider;
 pub use datacache::Datacache;
 pub use datahost::Datahost;
 pub use dir_node::DirNode;
+pub use error_detail_conversion::{error_detail_from_error, error_from_error_detail};
 pub use fragment::{FragmentQueryResult, FragmentQueryable};
+pub use garbage_collection_report::GarbageCollectionReport;
 #[cfg(feature = "client")]
 pub use idp_client::IDPClient;
 pub use load_plum_and_deserialize_error::LoadPlumAndDeserializeError;
 pub use path_state_error::PathStateError;
+pub use permission::Permission;
 pub use plum_ref::PlumRef;
 pub use plum_uri::{PlumURI, PlumURILocal, PlumURIRemote};
+pub use plum_uri_parse_error::PlumURIParseError;
+pub use relation_traversal::{topologically_sorted, RelationTraversal, RelationTraversalLimits};
+pub use transfer_report::TransferReport;
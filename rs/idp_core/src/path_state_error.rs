@@ -1,4 +1,6 @@
-use idp_proto::{Path, PlumHeadSeal};
+use idp_proto::{
+    path_state_error_detail, MalformedErrorDetail, Path, PathStateErrorDetail, PlumHeadSeal,
+};
 
 #[derive(Debug, derive_more::From, thiserror::Error)]
 pub enum PathStateError {
@@ -10,20 +12,66 @@ pub enum PathStateError {
     PlumMustAlreadyExist(PlumHeadSeal),
 }
 
-#[cfg(feature = "tonic")]
-impl From<PathStateError> for tonic::Status {
-    fn from(path_state_error: PathStateError) -> Self {
-        // It would be better to serialize the BranchError and deserialize it on the IDPClient side.
-        match path_state_error {
-            PathStateError::InvalidPath { .. } => {
-                tonic::Status::invalid_argument(path_state_error.to_string())
+impl From<&PathStateError> for PathStateErrorDetail {
+    fn from(path_state_error: &PathStateError) -> Self {
+        use path_state_error_detail::Value;
+        let value = match path_state_error {
+            PathStateError::InvalidPath { path, reason } => {
+                Value::InvalidPath(path_state_error_detail::InvalidPath {
+                    path: path.clone(),
+                    reason: reason.clone(),
+                })
             }
-            PathStateError::PathAlreadyExists(_) => {
-                tonic::Status::already_exists(path_state_error.to_string())
+            PathStateError::PathAlreadyExists(path) => Value::PathAlreadyExists(path.clone()),
+            PathStateError::PlumMustAlreadyExist(plum_head_seal) => {
+                Value::PlumMustAlreadyExist(plum_head_seal.clone())
             }
-            PathStateError::PlumMustAlreadyExist(_) => {
-                tonic::Status::failed_precondition(path_state_error.to_string())
+        };
+        Self { value: Some(value) }
+    }
+}
+
+impl TryFrom<PathStateErrorDetail> for PathStateError {
+    type Error = MalformedErrorDetail;
+    fn try_from(path_state_error_detail: PathStateErrorDetail) -> Result<Self, Self::Error> {
+        use path_state_error_detail::Value;
+        let value = path_state_error_detail.value.ok_or(MalformedErrorDetail {
+            message_name: "PathStateErrorDetail",
+        })?;
+        Ok(match value {
+            Value::InvalidPath(x) => PathStateError::InvalidPath {
+                path: x.path,
+                reason: x.reason,
+            },
+            Value::PathAlreadyExists(path) => PathStateError::PathAlreadyExists(path),
+            Value::PlumMustAlreadyExist(plum_head_seal) => {
+                PathStateError::PlumMustAlreadyExist(plum_head_seal)
             }
+        })
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl PathStateError {
+    /// The tonic::Code that best describes this error.
+    pub fn tonic_code(&self) -> tonic::Code {
+        match self {
+            PathStateError::InvalidPath { .. } => tonic::Code::InvalidArgument,
+            PathStateError::PathAlreadyExists(_) => tonic::Code::AlreadyExists,
+            PathStateError::PlumMustAlreadyExist(_) => tonic::Code::FailedPrecondition,
+        }
+    }
+}
+
+/// The resulting tonic::Status carries an ErrorDetail, from which IDPClient reconstructs this error.
+#[cfg(feature = "tonic")]
+impl From<PathStateError> for tonic::Status {
+    fn from(path_state_error: PathStateError) -> Self {
+        idp_proto::ErrorDetail {
+            value: Some(idp_proto::error_detail::Value::PathStateError(
+                (&path_state_error).into(),
+            )),
         }
+        .into_status(path_state_error.tonic_code(), path_state_error.to_string())
     }
 }
@@ -0,0 +1,17 @@
+/// An operation that an AccessControlList can permit an identity to perform.
+#[derive(
+    Clone, Copy, Debug, serde::Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Permission {
+    /// Read PathStates and branch heads, and (in AccessPolicy::plum_acl) pull Plums.
+    Read,
+    /// (In AccessPolicy::plum_acl) push Plums.
+    Push,
+    /// Create branches.
+    BranchCreate,
+    /// Set branch heads.
+    BranchSetHead,
+    /// Delete branches.
+    Delete,
+}
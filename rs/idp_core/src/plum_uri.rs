@@ -1,4 +1,5 @@
-use idp_proto::PlumHeadSeal;
+use crate::PlumURIParseError;
+use idp_proto::{CanonicalStr, PlumHeadSeal};
 
 #[derive(
     Clone,
@@ -81,7 +82,53 @@ pub enum PlumURI {
     Remote(PlumURIRemote),
 }
 
-// TODO: PlumURI parsing from str
+impl std::str::FromStr for PlumURI {
+    type Err = PlumURIParseError;
+    /// Inverse of Display, i.e. parses `idp:///<plum-head-seal>` as PlumURILocal and
+    /// `idp://<hostname>[:<port>]/<plum-head-seal>` as PlumURIRemote.  The PlumHeadSeal can be in any
+    /// form that PlumHeadSeal's FromStr accepts.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (authority, plum_head_seal_str) = s
+            .strip_prefix("idp://")
+            .and_then(|rest| rest.split_once('/'))
+            .ok_or_else(|| PlumURIParseError::MalformedURI { uri: s.to_string() })?;
+        let plum_head_seal = plum_head_seal_str
+            .parse::<PlumHeadSeal>()
+            .map_err(|reason| PlumURIParseError::MalformedPlumHeadSeal {
+                uri: s.to_string(),
+                reason,
+            })?;
+        if authority.is_empty() {
+            return Ok(PlumURI::from(PlumURILocal::from(plum_head_seal)));
+        }
+        let (hostname, port_o) = match authority.rsplit_once(':') {
+            // A colon within a bracketed IPv6 address doesn't separate the port.
+            Some((hostname, port)) if !hostname.contains(':') || hostname.ends_with(']') => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| PlumURIParseError::MalformedPort { uri: s.to_string() })?;
+                (hostname, Some(port))
+            }
+            _ => (authority, None),
+        };
+        if hostname.is_empty() {
+            return Err(PlumURIParseError::EmptyHostname { uri: s.to_string() });
+        }
+        Ok(PlumURI::from(PlumURIRemote {
+            hostname: hostname.to_string(),
+            port_o,
+            plum_head_seal,
+        }))
+    }
+}
+
+impl CanonicalStr for PlumURI {
+    /// A PlumURI whose PlumHeadSeal isn't canonical (see Seal::is_canonical) has no canonical string
+    /// form.
+    fn has_canonical_str(&self) -> bool {
+        self.get_plum_head_seal().value.is_canonical()
+    }
+}
 
 impl From<PlumHeadSeal> for PlumURI {
     fn from(plum_head_seal: PlumHeadSeal) -> Self {
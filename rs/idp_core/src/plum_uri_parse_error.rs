@@ -0,0 +1,18 @@
+use idp_proto::TextDecodeError;
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum PlumURIParseError {
+    #[error("PlumURI `{uri}` has an empty hostname")]
+    EmptyHostname { uri: String },
+    #[error("PlumURI `{uri}` has a malformed port")]
+    MalformedPort { uri: String },
+    #[error("PlumURI `{uri}` has a malformed PlumHeadSeal -- {reason}")]
+    MalformedPlumHeadSeal {
+        uri: String,
+        reason: TextDecodeError,
+    },
+    #[error(
+        "PlumURI `{uri}` doesn't have the form `idp://[<hostname>[:<port>]]/<plum-head-seal>`"
+    )]
+    MalformedURI { uri: String },
+}
@@ -0,0 +1,70 @@
+use idp_proto::{PlumHeadSeal, PlumRelationFlags};
+use std::collections::{HashMap, VecDeque};
+
+/// Limits for Datahost::traverse_relations.  The default value doesn't limit anything.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RelationTraversalLimits {
+    /// If specified, Plums which are more than this many relations away from the root aren't traversed.
+    pub max_depth_o: Option<u32>,
+    /// If specified, at most this many Plums (including the root) are traversed.
+    pub max_nodes_o: Option<usize>,
+}
+
+/// The result of Datahost::traverse_relations.
+#[derive(Clone, Debug)]
+pub struct RelationTraversal {
+    /// Maps each traversed Plum that is referred to by a traversed Plum (which normally excludes the
+    /// root) to the union of the masked PlumRelationFlags of those relations.  This is the same as
+    /// what Datahost::accumulated_relations_recursive returns.
+    pub plum_relation_flags_m: HashMap<PlumHeadSeal, PlumRelationFlags>,
+    /// All traversed Plums (including the root) in topological order, i.e. each Plum comes before
+    /// every Plum that it refers to.  Thus the root comes first, and iterating in reverse visits each
+    /// Plum's dependencies before the Plum itself.
+    pub plum_head_seal_v: Vec<PlumHeadSeal>,
+    /// True if the limits prevented any Plum from being traversed.
+    pub truncated: bool,
+}
+
+/// Returns plum_head_seal_v in topological order (i.e. each Plum comes before every Plum that it refers
+/// to), using Kahn's algorithm and breaking ties by the order in plum_head_seal_v.  The relations are
+/// given by target_plum_head_seal_vm, and targets that aren't in plum_head_seal_v are ignored.  There
+/// must not be any cycles, which is true by construction for relations between Plums.
+pub fn topologically_sorted(
+    plum_head_seal_v: &[PlumHeadSeal],
+    target_plum_head_seal_vm: &HashMap<PlumHeadSeal, Vec<PlumHeadSeal>>,
+) -> Vec<PlumHeadSeal> {
+    let mut in_degree_m: HashMap<&PlumHeadSeal, usize> = plum_head_seal_v
+        .iter()
+        .map(|plum_head_seal| (plum_head_seal, 0))
+        .collect();
+    for plum_head_seal in plum_head_seal_v {
+        if let Some(target_plum_head_seal_v) = target_plum_head_seal_vm.get(plum_head_seal) {
+            for target_plum_head_seal in target_plum_head_seal_v {
+                if let Some(in_degree) = in_degree_m.get_mut(target_plum_head_seal) {
+                    *in_degree += 1;
+                }
+            }
+        }
+    }
+    let mut ready_plum_head_seal_v = plum_head_seal_v
+        .iter()
+        .filter(|plum_head_seal| in_degree_m[plum_head_seal] == 0)
+        .collect::<VecDeque<&PlumHeadSeal>>();
+    let mut sorted_plum_head_seal_v = Vec::with_capacity(plum_head_seal_v.len());
+    while let Some(plum_head_seal) = ready_plum_head_seal_v.pop_front() {
+        sorted_plum_head_seal_v.push(plum_head_seal.clone());
+        if let Some(target_plum_head_seal_v) = target_plum_head_seal_vm.get(plum_head_seal) {
+            for target_plum_head_seal in target_plum_head_seal_v {
+                if let Some(in_degree) = in_degree_m.get_mut(target_plum_head_seal) {
+                    *in_degree -= 1;
+                    if *in_degree == 0 {
+                        ready_plum_head_seal_v.push_back(target_plum_head_seal);
+                    }
+                }
+            }
+        }
+    }
+    // Because there are no cycles, every Plum was output.
+    assert_eq!(sorted_plum_head_seal_v.len(), plum_head_seal_v.len());
+    sorted_plum_head_seal_v
+}
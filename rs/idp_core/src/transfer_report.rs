@@ -0,0 +1,18 @@
+/// Describes what IDPClient::push or IDPClient::pull (or their peer-to-peer counterparts in idp_p2p)
+/// transferred.  Because a PlumBody may be shared by several Plums, and because a "shallow" pull
+/// doesn't transfer PlumBody-s at all, the number of PlumBody-s transferred may be less than the
+/// number of Plums transferred.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TransferReport {
+    /// The number of Plums whose PlumHead, PlumMetadata, and PlumRelations were transferred.
+    pub plum_count: usize,
+    /// The number of PlumBody-s that were transferred.
+    pub plum_body_count: usize,
+}
+
+impl TransferReport {
+    /// Returns true if nothing was transferred.
+    pub fn is_empty(&self) -> bool {
+        self.plum_count == 0 && self.plum_body_count == 0
+    }
+}
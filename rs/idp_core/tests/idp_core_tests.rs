@@ -1,14 +1,24 @@
 use async_lock::RwLock;
+use futures::{FutureExt, StreamExt, TryStreamExt};
 use idp_core::{
-    BranchNode, BranchNodeBuilder, Datacache, Datahost, DirNode, FragmentQueryResult,
-    FragmentQueryable, PlumRef, PlumURI, PlumURILocal,
+    BranchError, BranchNode, BranchNodeBuilder, Datacache, Datahost, DirNode, FragmentQueryResult,
+    FragmentQueryable, GarbageCollectionReport, PlumCompleteness, PlumRef, PlumURI, PlumURILocal,
+    PlumURIParseError, PlumURIRemote, RelationTraversalLimits,
 };
+use idp_datahost_storage::{PathStatesFilter, PlumFilter, PlumHeadsFilter};
+use idp_datahost_storage_in_memory::DatahostStorageInMemory;
+use idp_datahost_storage_postgres::DatahostStoragePostgres;
 use idp_datahost_storage_sqlite::DatahostStorageSQLite;
 use idp_proto::{
-    branch_set_head_request, BranchSetHeadRequest, ContentEncoding, ContentFormat, Path, PathState,
-    Plum, PlumBodySeal, PlumBuilder, PlumHeadSeal, PlumRelationFlags, Sha256Sum,
+    branch_set_head_request, path_state_change, BranchSetHeadRequest, ContentClass,
+    ContentEncoding, ContentFormat, Nonce, Path, PathState, Plum, PlumBody, PlumBodySeal,
+    PlumBuilder, PlumHeadSeal, PlumRelationFlags, SealAlgorithm, SealKey, Sha256Sum, TextEncoding,
+    UnixNanoseconds,
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
 };
-use std::{collections::BTreeMap, sync::Arc};
 use uuid::Uuid;
 
 /// This will run once at load time (i.e. presumably before main function is called).
@@ -17,21 +27,34 @@ fn overall_init() {
     env_logger::init();
 }
 
-/// Convenience function for opening the DB whose URL is specified by the DATABASE_URL env var.
-/// If that var isn't set, then it defaults to using an in-memory DB.
-// TODO: Actually make this function do what the comment says.
+/// Convenience function for opening the DB whose URL is specified by the IDP_CORE_TESTS_DATABASE_URL
+/// env var.  If that var isn't set, then it defaults to using a SQLite DB file.  A "postgres://" or
+/// "postgresql://" URL will use DatahostStoragePostgres, "memory:" will use DatahostStorageInMemory,
+/// and anything else will use DatahostStorageSQLite.
+/// Note that DATABASE_URL isn't used for this, since that's what sqlx uses to check its queries at compile time.
 async fn datahost_from_env_var() -> Datahost {
     // Regarding `?mode=rwc`, see https://github.com/launchbadge/sqlx/issues/1114#issuecomment-827815038
-    let database_url = "sqlite:idp_core_tests.db?mode=rwc";
+    let database_url = std::env::var("IDP_CORE_TESTS_DATABASE_URL")
+        .unwrap_or_else(|_| "sqlite:idp_core_tests.db?mode=rwc".to_string());
     log::info!(
         "datahost_from_env_var is using {:?} as database_url",
         database_url
     );
-    Datahost::open(
-        DatahostStorageSQLite::connect_and_run_migrations(database_url)
-            .await
-            .expect("pass"),
-    )
+    if database_url == "memory:" {
+        Datahost::open(DatahostStorageInMemory::new())
+    } else if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        Datahost::open(
+            DatahostStoragePostgres::connect_and_run_migrations(&database_url)
+                .await
+                .expect("pass"),
+        )
+    } else {
+        Datahost::open(
+            DatahostStorageSQLite::connect_and_run_migrations(&database_url)
+                .await
+                .expect("pass"),
+        )
+    }
 }
 
 #[test]
@@ -45,6 +68,114 @@ fn display_sha256sum() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_plum_uri_parse_roundtrip() {
+    let plum_head_seal = PlumHeadSeal::from(SealAlgorithm::Blake3.compute_seal("blah".as_bytes()));
+
+    let plum_uri = PlumURI::from(plum_head_seal.clone());
+    let plum_uri_string = plum_uri.to_string();
+    assert_eq!(plum_uri_string, format!("idp:///{}", plum_head_seal));
+    match plum_uri_string.parse::<PlumURI>().expect("pass") {
+        PlumURI::Local(plum_uri_local) => {
+            assert_eq!(plum_uri_local.get_plum_head_seal(), &plum_head_seal)
+        }
+        PlumURI::Remote(_) => panic!("expected PlumURI::Local"),
+    }
+
+    for (hostname, port_o) in [
+        ("localhost", None),
+        ("example.com", Some(50051)),
+        ("[::1]", None),
+        ("[::1]", Some(8080)),
+    ] {
+        let plum_uri = PlumURI::from(PlumURIRemote {
+            hostname: hostname.to_string(),
+            port_o,
+            plum_head_seal: plum_head_seal.clone(),
+        });
+        let plum_uri_string = plum_uri.to_string();
+        log::debug!("plum_uri_string: {}", plum_uri_string);
+        match plum_uri_string.parse::<PlumURI>().expect("pass") {
+            PlumURI::Remote(plum_uri_remote) => {
+                assert_eq!(plum_uri_remote.hostname, hostname);
+                assert_eq!(plum_uri_remote.port_o, port_o);
+                assert_eq!(plum_uri_remote.plum_head_seal, plum_head_seal);
+            }
+            PlumURI::Local(_) => panic!("expected PlumURI::Remote"),
+        }
+
+        // Human-readable formats use the string form if it's opted into.
+        #[derive(serde::Deserialize, serde::Serialize)]
+        struct OptedIn {
+            #[serde(with = "idp_proto::human_readable_str")]
+            plum_uri: PlumURI,
+        }
+        let json = serde_json::to_string(&OptedIn {
+            plum_uri: plum_uri.clone(),
+        })
+        .expect("pass");
+        assert_eq!(json, format!(r#"{{"plum_uri":"{}"}}"#, plum_uri_string));
+        assert_eq!(
+            serde_json::from_str::<OptedIn>(&json)
+                .expect("pass")
+                .plum_uri
+                .to_string(),
+            plum_uri_string
+        );
+        // Otherwise the derived form is used.
+        let json = serde_json::to_string(&plum_uri).expect("pass");
+        assert!(json.starts_with(r#"{"Remote":"#));
+        assert_eq!(
+            serde_json::from_str::<PlumURI>(&json)
+                .expect("pass")
+                .to_string(),
+            plum_uri_string
+        );
+    }
+
+    // The PlumHeadSeal can be in any TextEncoding.
+    let plum_uri_string = format!(
+        "idp://example.com/{}",
+        plum_head_seal.to_string_using(TextEncoding::Base58)
+    );
+    assert_eq!(
+        plum_uri_string
+            .parse::<PlumURI>()
+            .expect("pass")
+            .get_plum_head_seal(),
+        &plum_head_seal
+    );
+
+    // Malformed PlumURI-s.
+    let seal_string = plum_head_seal.to_string();
+    assert!(matches!(
+        format!("http:///{}", seal_string)
+            .parse::<PlumURI>()
+            .expect_err("fail"),
+        PlumURIParseError::MalformedURI { .. }
+    ));
+    assert!(matches!(
+        "idp://example.com".parse::<PlumURI>().expect_err("fail"),
+        PlumURIParseError::MalformedURI { .. }
+    ));
+    assert!(matches!(
+        format!("idp://:50051/{}", seal_string)
+            .parse::<PlumURI>()
+            .expect_err("fail"),
+        PlumURIParseError::EmptyHostname { .. }
+    ));
+    assert!(matches!(
+        format!("idp://example.com:http/{}", seal_string)
+            .parse::<PlumURI>()
+            .expect_err("fail"),
+        PlumURIParseError::MalformedPort { .. }
+    ));
+    assert!(matches!(
+        "idp:///fabc".parse::<PlumURI>().expect_err("fail"),
+        PlumURIParseError::MalformedPlumHeadSeal { .. }
+    ));
+}
+
 #[tokio::test]
 #[serial_test::serial]
 async fn open_datahost() {
@@ -112,14 +243,14 @@ async fn test_datahost_create_plum_body() {
     let datahost = datahost_from_env_var().await;
 
     let plum_body_seal = datahost
-        .store_plum_body(&plum.plum_body, None)
+        .store_plum_body(&plum.plum_body, &plum.plum_head.plum_body_seal, None)
         .await
         .expect("pass");
     assert_eq!(plum_body_seal, PlumBodySeal::from(&plum.plum_body));
 
     // store_plum_body again and ensure it worked again.
     let body_seal_2 = datahost
-        .store_plum_body(&plum.plum_body, None)
+        .store_plum_body(&plum.plum_body, &plum.plum_head.plum_body_seal, None)
         .await
         .expect("pass");
     assert_eq!(body_seal_2, PlumBodySeal::from(&plum.plum_body));
@@ -214,6 +345,107 @@ async fn test_datahost_create_plums_with_identical_bodies() {
     // );
 }
 
+#[tokio::test]
+#[serial_test::serial]
+async fn test_datahost_create_plums_with_different_seal_algorithms() {
+    let string = format!(
+        "test_datahost_create_plums_with_different_seal_algorithms, {}",
+        Uuid::new_v4()
+    );
+
+    let datahost = datahost_from_env_var().await;
+
+    // Plums with identical content but different SealAlgorithm-s are distinct, and can coexist.
+    let mut plum_head_seal_s = HashSet::new();
+    for seal_algorithm in [
+        SealAlgorithm::Sha256,
+        SealAlgorithm::Sha512,
+        SealAlgorithm::Blake3,
+    ] {
+        let plum = PlumBuilder::new()
+            .with_seal_algorithm(seal_algorithm)
+            .with_plum_relations_and_plum_body_content_from(
+                &string,
+                Some(&ContentFormat::charset_us_ascii()),
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass");
+
+        let head_seal = datahost.store_plum(&plum, None).await.expect("pass");
+        assert_eq!(head_seal, PlumHeadSeal::from(&plum.plum_head));
+        assert_eq!(
+            head_seal.value.seal_algorithm().expect("pass"),
+            seal_algorithm
+        );
+
+        // store_plum again and ensure it worked again
+        let head_seal_2 = datahost.store_plum(&plum, None).await.expect("pass");
+        assert_eq!(head_seal_2, head_seal);
+
+        let loaded_plum = datahost.load_plum(&head_seal, None).await.expect("pass");
+        loaded_plum.verify().expect("pass");
+        assert_eq!(loaded_plum, plum);
+
+        plum_head_seal_s.insert(head_seal);
+    }
+    assert_eq!(plum_head_seal_s.len(), 3);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_datahost_create_plum_with_seal_key() {
+    let seal_key = SealKey::new(
+        "test_datahost_create_plum_with_seal_key".to_string(),
+        Uuid::new_v4().as_bytes().to_vec(),
+    );
+    let plum = PlumBuilder::new()
+        .with_seal_key(seal_key.clone())
+        .with_plum_relations_and_plum_body_content_from(
+            &format!(
+                "test_datahost_create_plum_with_seal_key, {}",
+                Uuid::new_v4()
+            ),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    assert!(plum.plum_head.plum_body_seal.value.is_keyed());
+
+    let datahost = datahost_from_env_var().await;
+
+    // The Datahost doesn't have the SealKey, but can still store and load the Plum.
+    let head_seal = datahost.store_plum(&plum, None).await.expect("pass");
+    assert_eq!(head_seal, PlumHeadSeal::from(&plum.plum_head));
+    let loaded_plum = datahost.load_plum(&head_seal, None).await.expect("pass");
+    assert_eq!(loaded_plum, plum);
+    loaded_plum
+        .verify_with_seal_key_provider(&seal_key)
+        .expect("pass");
+
+    let plum_body_seal = datahost
+        .store_plum_body(&plum.plum_body, &plum.plum_head.plum_body_seal, None)
+        .await
+        .expect("pass");
+    assert_eq!(plum_body_seal, plum.plum_head.plum_body_seal);
+    // A PlumBody can't be stored under an unkeyed PlumBodySeal that doesn't match it.
+    let unkeyed_plum_body_seal = PlumBodySeal::from(&plum.plum_body);
+    datahost
+        .store_plum_body(
+            &PlumBody {
+                plum_body_nonce_o: Some(Nonce::from(b"xy".to_vec())),
+                plum_body_content: plum.plum_body.plum_body_content.clone(),
+            },
+            &unkeyed_plum_body_seal,
+            None,
+        )
+        .await
+        .expect_err("fail");
+}
+
 #[tokio::test]
 #[serial_test::serial]
 async fn test_datahost_branch_node() {
@@ -501,6 +733,122 @@ async fn test_datahost_branch_node() {
         assert!(plum_relation_flags_m.contains_key(&content_2_plum_head_seal));
     }
 
+    //
+    // Now traverse_relations with and without limits.
+    //
+
+    {
+        let relation_traversal = datahost
+            .traverse_relations(
+                &branch_node_2_plum_head_seal,
+                PlumRelationFlags::ALL,
+                RelationTraversalLimits::default(),
+                None,
+            )
+            .await
+            .expect("pass");
+        log::debug!("relation_traversal: {:?}", relation_traversal);
+        assert!(!relation_traversal.truncated);
+        assert_eq!(relation_traversal.plum_relation_flags_m.len(), 7);
+        assert_eq!(relation_traversal.plum_head_seal_v.len(), 8);
+        assert_eq!(
+            relation_traversal.plum_head_seal_v[0],
+            branch_node_2_plum_head_seal
+        );
+        // Check that each Plum comes before the Plums it refers to.
+        let position = |plum_head_seal: &PlumHeadSeal| {
+            relation_traversal
+                .plum_head_seal_v
+                .iter()
+                .position(|x| x == plum_head_seal)
+                .expect("pass")
+        };
+        for (source_plum_head_seal, target_plum_head_seal) in [
+            (&branch_node_2_plum_head_seal, &branch_node_1_plum_head_seal),
+            (&branch_node_2_plum_head_seal, &metadata_2_plum_head_seal),
+            (&branch_node_2_plum_head_seal, &content_2_plum_head_seal),
+            (&branch_node_1_plum_head_seal, &branch_node_0_plum_head_seal),
+            (&branch_node_1_plum_head_seal, &metadata_1_plum_head_seal),
+            (&branch_node_1_plum_head_seal, &content_1_plum_head_seal),
+            (&branch_node_0_plum_head_seal, &metadata_0_plum_head_seal),
+        ] {
+            assert!(position(source_plum_head_seal) < position(target_plum_head_seal));
+        }
+    }
+    {
+        let relation_traversal = datahost
+            .traverse_relations(
+                &branch_node_2_plum_head_seal,
+                PlumRelationFlags::ALL,
+                RelationTraversalLimits {
+                    max_depth_o: Some(1),
+                    max_nodes_o: None,
+                },
+                None,
+            )
+            .await
+            .expect("pass");
+        log::debug!("relation_traversal: {:?}", relation_traversal);
+        assert!(relation_traversal.truncated);
+        assert_eq!(
+            relation_traversal
+                .plum_head_seal_v
+                .iter()
+                .collect::<HashSet<_>>(),
+            maplit::hashset! {
+                &branch_node_2_plum_head_seal,
+                &branch_node_1_plum_head_seal,
+                &metadata_2_plum_head_seal,
+                &content_2_plum_head_seal,
+            }
+        );
+        assert_eq!(relation_traversal.plum_relation_flags_m.len(), 3);
+    }
+    {
+        let relation_traversal = datahost
+            .traverse_relations(
+                &branch_node_2_plum_head_seal,
+                PlumRelationFlags::ALL,
+                RelationTraversalLimits {
+                    max_depth_o: None,
+                    max_nodes_o: Some(2),
+                },
+                None,
+            )
+            .await
+            .expect("pass");
+        log::debug!("relation_traversal: {:?}", relation_traversal);
+        assert!(relation_traversal.truncated);
+        assert_eq!(relation_traversal.plum_head_seal_v.len(), 2);
+        assert_eq!(
+            relation_traversal.plum_head_seal_v[0],
+            branch_node_2_plum_head_seal
+        );
+    }
+    {
+        // The limits don't matter if nothing would be excluded.
+        let relation_traversal = datahost
+            .traverse_relations(
+                &branch_node_0_plum_head_seal,
+                PlumRelationFlags::ALL,
+                RelationTraversalLimits {
+                    max_depth_o: Some(1),
+                    max_nodes_o: Some(2),
+                },
+                None,
+            )
+            .await
+            .expect("pass");
+        assert!(!relation_traversal.truncated);
+        assert_eq!(
+            relation_traversal.plum_head_seal_v,
+            vec![
+                branch_node_0_plum_head_seal.clone(),
+                metadata_0_plum_head_seal.clone()
+            ]
+        );
+    }
+
     //
     // Testing FragmentQueryable
     //
@@ -865,6 +1213,80 @@ async fn test_datahost_dir_node() {
         assert!(plum_relation_flags_m.contains_key(&PlumHeadSeal::from(&content_1_plum)));
     }
 
+    //
+    // Now accumulated_reverse_relations_recursive and check the results.  Note that dir_node_0_plum
+    // is identical across test runs, so it may have other referrers in the DB, and isn't checked here.
+    //
+
+    {
+        let plum_relation_flags_m = datahost
+            .accumulated_reverse_relations_recursive(
+                &PlumHeadSeal::from(&content_0_plum),
+                PlumRelationFlags::ALL,
+                None,
+            )
+            .await
+            .expect("pass");
+        log::debug!("plum_relation_flags_m: {:?}", plum_relation_flags_m);
+        // These are the Plums that depend on content_0_plum, directly or (in the case of
+        // dir_node_4_plum) indirectly.  DirNode only uses CONTENT_DEPENDENCY.
+        assert_eq!(
+            plum_relation_flags_m,
+            maplit::hashmap! {
+                dir_node_1_plum_head_seal.clone() => PlumRelationFlags::CONTENT_DEPENDENCY,
+                dir_node_2_plum_head_seal.clone() => PlumRelationFlags::CONTENT_DEPENDENCY,
+                dir_node_3_plum_head_seal.clone() => PlumRelationFlags::CONTENT_DEPENDENCY,
+                dir_node_4_plum_head_seal.clone() => PlumRelationFlags::CONTENT_DEPENDENCY,
+            }
+        );
+    }
+    {
+        let plum_relation_flags_m = datahost
+            .accumulated_reverse_relations_recursive(
+                &PlumHeadSeal::from(&content_1_plum),
+                PlumRelationFlags::ALL,
+                None,
+            )
+            .await
+            .expect("pass");
+        log::debug!("plum_relation_flags_m: {:?}", plum_relation_flags_m);
+        assert_eq!(
+            plum_relation_flags_m
+                .keys()
+                .cloned()
+                .collect::<HashSet<_>>(),
+            maplit::hashset! {
+                dir_node_2_plum_head_seal.clone(),
+                dir_node_3_plum_head_seal.clone(),
+                dir_node_4_plum_head_seal.clone(),
+            }
+        );
+    }
+    {
+        // No relations survive this mask.
+        let plum_relation_flags_m = datahost
+            .accumulated_reverse_relations_recursive(
+                &PlumHeadSeal::from(&content_0_plum),
+                PlumRelationFlags::METADATA_DEPENDENCY,
+                None,
+            )
+            .await
+            .expect("pass");
+        assert!(plum_relation_flags_m.is_empty());
+    }
+    {
+        // Nothing refers to dir_node_4_plum.
+        let plum_relation_flags_m = datahost
+            .accumulated_reverse_relations_recursive(
+                &PlumHeadSeal::from(&dir_node_4_plum),
+                PlumRelationFlags::ALL,
+                None,
+            )
+            .await
+            .expect("pass");
+        assert!(plum_relation_flags_m.is_empty());
+    }
+
     //
     // Test FragmentQueryable
     //
@@ -1250,37 +1672,602 @@ async fn test_path_state() {
     assert!(!datahost.has_path_state(&path, None).await.expect("pass"));
 }
 
-async fn build_and_store_random_branch_node_and_plum_with_ancestor(
-    ancestor_o: Option<&Plum>,
-    datahost: &Datahost,
-) -> (BranchNode, Plum, PlumHeadSeal) {
-    let metadata_plum = PlumBuilder::new()
-        .with_plum_relations_and_plum_body_content_from(
-            &format!("BranchNode metadata {}", Uuid::new_v4()),
-            Some(&ContentFormat::charset_us_ascii()),
-            ContentEncoding::none(),
-        )
-        .expect("pass")
-        .build()
+#[tokio::test]
+#[serial_test::serial]
+async fn test_path_state_change_subscription() {
+    let datahost = datahost_from_env_var().await;
+
+    let path = Path::from(format!("watchedpath-{}", Uuid::new_v4()));
+    let mut plum_head_seal_v = Vec::new();
+    for i in 0..2 {
+        let plum = PlumBuilder::new()
+            .with_plum_relations_and_plum_body_content_from(
+                &format!("PathState change {}, {}", i, Uuid::new_v4()),
+                Some(&ContentFormat::charset_us_ascii()),
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass");
+        plum_head_seal_v.push(datahost.store_plum(&plum, None).await.expect("pass"));
+    }
+    let path_state_0 = PathState {
+        path: path.clone(),
+        current_state_plum_head_seal: plum_head_seal_v[0].clone(),
+    };
+    let path_state_1 = PathState {
+        path: path.clone(),
+        current_state_plum_head_seal: plum_head_seal_v[1].clone(),
+    };
+
+    let mut path_state_change_receiver = datahost.subscribe_to_path_state_changes();
+    // A dropped receiver doesn't affect the others.
+    drop(datahost.subscribe_to_path_state_changes());
+
+    datahost
+        .insert_path_state(&path_state_0, None)
+        .await
+        .expect("pass");
+    datahost
+        .update_path_state(&path_state_1, None)
+        .await
         .expect("pass");
+    datahost.delete_path_state(&path, None).await.expect("pass");
 
-    let metadata_plum_head_seal = datahost
-        .store_plum(&metadata_plum, None)
+    let mut next_path_state_change_value = || {
+        path_state_change_receiver
+            .next()
+            .now_or_never()
+            .expect("pass")
+            .expect("pass")
+            .value
+            .expect("pass")
+    };
+    assert_eq!(
+        next_path_state_change_value(),
+        path_state_change::Value::Inserted(path_state_0.clone())
+    );
+    assert_eq!(
+        next_path_state_change_value(),
+        path_state_change::Value::Updated(path_state_1)
+    );
+    assert_eq!(
+        next_path_state_change_value(),
+        path_state_change::Value::Deleted(path.clone())
+    );
+    // Nothing else changed.
+    assert!(path_state_change_receiver.next().now_or_never().is_none());
+
+    // A change made within an outer transaction is only sent once that transaction commits, and not
+    // at all if it's rolled back.
+    let mut transaction = datahost.begin_transaction().await.expect("pass");
+    datahost
+        .insert_path_state(&path_state_0, Some(transaction.as_mut()))
         .await
         .expect("pass");
+    assert!(path_state_change_receiver.next().now_or_never().is_none());
+    transaction.rollback().await.expect("pass");
+    assert!(path_state_change_receiver.next().now_or_never().is_none());
+    let mut transaction = datahost.begin_transaction().await.expect("pass");
+    datahost
+        .insert_path_state(&path_state_0, Some(transaction.as_mut()))
+        .await
+        .expect("pass");
+    assert!(path_state_change_receiver.next().now_or_never().is_none());
+    transaction.commit().await.expect("pass");
+    assert_eq!(
+        path_state_change_receiver
+            .next()
+            .now_or_never()
+            .expect("pass")
+            .expect("pass")
+            .value
+            .expect("pass"),
+        path_state_change::Value::Inserted(path_state_0)
+    );
+    datahost.delete_path_state(&path, None).await.expect("pass");
+}
 
-    let content_plum = PlumBuilder::new()
+#[tokio::test]
+async fn test_path_state_change_subscription_lag() {
+    let datahost = Datahost::open(DatahostStorageInMemory::new());
+
+    let plum = PlumBuilder::new()
         .with_plum_relations_and_plum_body_content_from(
-            &format!("BranchNode content {}", Uuid::new_v4()),
+            &"test_path_state_change_subscription_lag".to_string(),
             Some(&ContentFormat::charset_us_ascii()),
             ContentEncoding::none(),
         )
         .expect("pass")
         .build()
         .expect("pass");
-
-    let content_plum_head_seal = datahost
-        .store_plum(&content_plum, None)
+    let path_state = PathState {
+        path: Path::from("laggedpath".to_string()),
+        current_state_plum_head_seal: datahost.store_plum(&plum, None).await.expect("pass"),
+    };
+    datahost
+        .insert_path_state(&path_state, None)
+        .await
+        .expect("pass");
+
+    // A receiver that falls too far behind is unsubscribed, so it only receives the changes that
+    // were buffered before then, after which its stream ends.
+    const UPDATE_COUNT: usize = 4096;
+    let mut path_state_change_receiver = datahost.subscribe_to_path_state_changes();
+    for _ in 0..UPDATE_COUNT {
+        datahost
+            .update_path_state(&path_state, None)
+            .await
+            .expect("pass");
+    }
+    let mut received_count = 0;
+    while let Some(path_state_change) = path_state_change_receiver
+        .next()
+        .now_or_never()
+        .expect("pass")
+    {
+        assert_eq!(
+            path_state_change.value.expect("pass"),
+            path_state_change::Value::Updated(path_state.clone())
+        );
+        received_count += 1;
+    }
+    assert!(received_count > 0);
+    assert!(received_count < UPDATE_COUNT);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_select_plum_heads_and_path_states() {
+    let datahost = datahost_from_env_var().await;
+
+    // The DB may already contain rows from other tests, so only the rows inserted after this are considered.
+    let began_at = UnixNanoseconds::now();
+
+    let mut plum_v = (0..3)
+        .map(|i| {
+            PlumBuilder::new()
+                .with_plum_relations_and_plum_body_content_from(
+                    &format!(
+                        "test_select_plum_heads_and_path_states {}, {}",
+                        i,
+                        Uuid::new_v4()
+                    ),
+                    Some(&ContentFormat::charset_us_ascii()),
+                    ContentEncoding::none(),
+                )
+                .expect("pass")
+                .build()
+                .expect("pass")
+        })
+        .collect::<Vec<_>>();
+    plum_v.push(
+        PlumBuilder::new()
+            .with_plum_relations_and_plum_body_content_from(
+                &Uuid::new_v4().as_bytes().to_vec(),
+                None,
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass"),
+    );
+    let mut plum_head_seal_v = Vec::new();
+    for plum in plum_v.iter() {
+        plum_head_seal_v.push(datahost.store_plum(plum, None).await.expect("pass"));
+    }
+
+    let began_at_filter = PlumHeadsFilter {
+        inserted_at_begin_o: Some(began_at),
+        ..Default::default()
+    };
+    assert_eq!(
+        datahost
+            .select_plum_heads(began_at_filter.clone(), None)
+            .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass"),
+        plum_head_seal_v
+    );
+    // The same, but within an explicit transaction.
+    {
+        let mut transaction = datahost.begin_transaction().await.expect("pass");
+        let row_v = datahost
+            .select_plum_heads(began_at_filter.clone(), Some(transaction.as_mut()))
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass");
+        assert_eq!(row_v.len(), plum_v.len());
+        for ((_, plum_head_seal, plum_head), plum) in row_v.iter().zip(plum_v.iter()) {
+            assert_eq!(*plum_head_seal, PlumHeadSeal::from(&plum.plum_head));
+            assert_eq!(*plum_head, plum.plum_head);
+        }
+        transaction.commit().await.expect("pass");
+    }
+    // Filter by content class.
+    assert_eq!(
+        datahost
+            .select_plum_heads(
+                PlumHeadsFilter {
+                    plum_filter: PlumFilter {
+                        content_class_o: Some(ContentClass::text_plain()),
+                        ..Default::default()
+                    },
+                    ..began_at_filter.clone()
+                },
+                None
+            )
+            .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass"),
+        plum_head_seal_v[..3]
+    );
+    assert_eq!(
+        datahost
+            .select_plum_heads(
+                PlumHeadsFilter {
+                    plum_filter: PlumFilter {
+                        content_class_o: Some(ContentClass::from(
+                            "application/octet-stream".to_string()
+                        )),
+                        ..Default::default()
+                    },
+                    ..began_at_filter.clone()
+                },
+                None
+            )
+            .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass"),
+        plum_head_seal_v[3..]
+    );
+    // The end of the inserted-at range is exclusive.
+    let row_v = datahost
+        .select_plum_heads(
+            PlumHeadsFilter {
+                inserted_at_end_o: Some(began_at),
+                ..Default::default()
+            },
+            None,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("pass");
+    assert!(row_v
+        .iter()
+        .all(
+            |(row_inserted_at, plum_head_seal, _)| row_inserted_at.value < began_at.value
+                && !plum_head_seal_v.contains(plum_head_seal)
+        ));
+
+    // PathStates are ordered by row_updated_at.
+    let path_v = (0..2)
+        .map(|i| {
+            Path::from(format!(
+                "test_select_plum_heads_and_path_states-{}-{}",
+                i,
+                Uuid::new_v4()
+            ))
+        })
+        .collect::<Vec<_>>();
+    for path in path_v.iter() {
+        datahost
+            .insert_path_state(
+                &PathState {
+                    path: path.clone(),
+                    current_state_plum_head_seal: plum_head_seal_v[0].clone(),
+                },
+                None,
+            )
+            .await
+            .expect("pass");
+    }
+    let updated_at_filter = PathStatesFilter {
+        updated_at_begin_o: Some(began_at),
+        ..Default::default()
+    };
+    assert_eq!(
+        datahost
+            .select_path_states(updated_at_filter.clone(), None)
+            .map_ok(|(_, _, path_state)| path_state.path)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass"),
+        path_v
+    );
+    datahost
+        .update_path_state(
+            &PathState {
+                path: path_v[0].clone(),
+                current_state_plum_head_seal: plum_head_seal_v[1].clone(),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(
+        datahost
+            .select_path_states(updated_at_filter.clone(), None)
+            .map_ok(|(_, _, path_state)| path_state)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass"),
+        vec![
+            PathState {
+                path: path_v[1].clone(),
+                current_state_plum_head_seal: plum_head_seal_v[0].clone(),
+            },
+            PathState {
+                path: path_v[0].clone(),
+                current_state_plum_head_seal: plum_head_seal_v[1].clone(),
+            },
+        ]
+    );
+
+    for path in path_v.iter() {
+        datahost.delete_path_state(path, None).await.expect("pass");
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_select_path_states_by_path_prefix() {
+    let datahost = datahost_from_env_var().await;
+
+    let plum_head_seal = datahost
+        .store_plum(
+            &PlumBuilder::new()
+                .with_plum_relations_and_plum_body_content_from(
+                    &format!("test_select_path_states_by_path_prefix, {}", Uuid::new_v4()),
+                    Some(&ContentFormat::charset_us_ascii()),
+                    ContentEncoding::none(),
+                )
+                .expect("pass")
+                .build()
+                .expect("pass"),
+            None,
+        )
+        .await
+        .expect("pass");
+
+    // The prefix contains characters that are special in SQL LIKE patterns, which must be matched
+    // literally.
+    let path_prefix = format!(
+        "test_select_path_states_by_path_prefix/{}/50%_off/",
+        Uuid::new_v4()
+    );
+    let path_v = ["a", "b", "c/d"]
+        .iter()
+        .map(|suffix| Path::from(format!("{}{}", path_prefix, suffix)))
+        .collect::<Vec<_>>();
+    let other_path = Path::from(path_prefix.replace("50%_off", "50%Xoff") + "a");
+    for path in path_v.iter().chain(std::iter::once(&other_path)) {
+        datahost
+            .insert_path_state(
+                &PathState {
+                    path: path.clone(),
+                    current_state_plum_head_seal: plum_head_seal.clone(),
+                },
+                None,
+            )
+            .await
+            .expect("pass");
+    }
+
+    let filter = PathStatesFilter {
+        path_prefix_o: Some(path_prefix.clone()),
+        ..Default::default()
+    };
+    assert_eq!(
+        datahost
+            .select_path_states(filter.clone(), None)
+            .map_ok(|(_, _, path_state)| path_state.path)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass"),
+        path_v
+    );
+
+    // Paginate with a page size of 2.
+    let select_page = datahost
+        .select_path_states_page(&filter, None, 2, None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        select_page
+            .row_v
+            .iter()
+            .map(|(_, _, path_state)| path_state.path.clone())
+            .collect::<Vec<_>>(),
+        path_v[..2].to_vec()
+    );
+    assert!(select_page.next_cursor_o.is_some());
+    let select_page = datahost
+        .select_path_states_page(&filter, select_page.next_cursor_o.as_ref(), 2, None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        select_page
+            .row_v
+            .iter()
+            .map(|(_, _, path_state)| path_state.path.clone())
+            .collect::<Vec<_>>(),
+        path_v[2..].to_vec()
+    );
+    assert!(select_page.next_cursor_o.is_none());
+
+    for path in path_v.iter().chain(std::iter::once(&other_path)) {
+        datahost.delete_path_state(path, None).await.expect("pass");
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_select_plum_head_seals_matching() {
+    let datahost = datahost_from_env_var().await;
+
+    // The DB may already contain Plums from other tests, so use creation times that are unique to this test.
+    let t = UnixNanoseconds::now().value;
+    let text_plum_builder = |name: &str| {
+        PlumBuilder::new()
+            .with_plum_relations_and_plum_body_content_from(
+                &format!(
+                    "test_select_plum_head_seals_matching {}, {}",
+                    name,
+                    Uuid::new_v4()
+                ),
+                Some(&ContentFormat::charset_us_ascii()),
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+    };
+    let plum_0 = text_plum_builder("0")
+        .with_plum_created_at(UnixNanoseconds::from(t + 1))
+        .build()
+        .expect("pass");
+    // The content class and format of this Plum can only be determined from its PlumBody.
+    let plum_1 = text_plum_builder("1")
+        .with_plum_created_at(UnixNanoseconds::from(t + 2))
+        .plum_metadata_should_not_include_plum_body_content_metadata()
+        .build()
+        .expect("pass");
+    let plum_2 = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &Uuid::new_v4().as_bytes().to_vec(),
+            None,
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .with_plum_created_at(UnixNanoseconds::from(t + 3))
+        .build()
+        .expect("pass");
+    // This Plum has no creation time, so it never matches a filter on creation time.
+    let plum_3 = text_plum_builder("3").build().expect("pass");
+
+    let mut plum_head_seal_v = Vec::new();
+    for plum in [&plum_0, &plum_1, &plum_2, &plum_3] {
+        plum_head_seal_v.push(datahost.store_plum(plum, None).await.expect("pass"));
+    }
+
+    let created_at_filter = PlumFilter {
+        created_at_begin_o: Some(UnixNanoseconds::from(t + 1)),
+        created_at_end_o: Some(UnixNanoseconds::from(t + 4)),
+        ..Default::default()
+    };
+    assert_eq!(
+        datahost
+            .select_plum_head_seals_matching(&created_at_filter, None)
+            .await
+            .expect("pass"),
+        plum_head_seal_v[..3]
+    );
+    // The end of the creation time range is exclusive.
+    assert_eq!(
+        datahost
+            .select_plum_head_seals_matching(
+                &PlumFilter {
+                    created_at_begin_o: Some(UnixNanoseconds::from(t + 2)),
+                    created_at_end_o: Some(UnixNanoseconds::from(t + 3)),
+                    ..Default::default()
+                },
+                None
+            )
+            .await
+            .expect("pass"),
+        plum_head_seal_v[1..2]
+    );
+    assert_eq!(
+        datahost
+            .select_plum_head_seals_matching(
+                &PlumFilter {
+                    content_class_o: Some(ContentClass::text_plain()),
+                    ..created_at_filter.clone()
+                },
+                None
+            )
+            .await
+            .expect("pass"),
+        plum_head_seal_v[..2]
+    );
+    assert_eq!(
+        datahost
+            .select_plum_head_seals_matching(
+                &PlumFilter {
+                    content_format_o: Some(ContentFormat::charset_us_ascii()),
+                    ..created_at_filter.clone()
+                },
+                None
+            )
+            .await
+            .expect("pass"),
+        plum_head_seal_v[..2]
+    );
+    assert_eq!(
+        datahost
+            .select_plum_head_seals_matching(
+                &PlumFilter {
+                    content_class_o: Some(ContentClass::from(
+                        "application/octet-stream".to_string()
+                    )),
+                    ..created_at_filter.clone()
+                },
+                None
+            )
+            .await
+            .expect("pass"),
+        plum_head_seal_v[2..3]
+    );
+    // Without a filter on creation time, plum_3 matches too.
+    let text_plum_head_seal_v = datahost
+        .select_plum_head_seals_matching(
+            &PlumFilter {
+                content_class_o: Some(ContentClass::text_plain()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    assert!(text_plum_head_seal_v.contains(&plum_head_seal_v[0]));
+    assert!(text_plum_head_seal_v.contains(&plum_head_seal_v[1]));
+    assert!(!text_plum_head_seal_v.contains(&plum_head_seal_v[2]));
+    assert!(text_plum_head_seal_v.contains(&plum_head_seal_v[3]));
+}
+
+async fn build_and_store_random_branch_node_and_plum_with_ancestor(
+    ancestor_o: Option<&Plum>,
+    datahost: &Datahost,
+) -> (BranchNode, Plum, PlumHeadSeal) {
+    let metadata_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("BranchNode metadata {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+
+    let metadata_plum_head_seal = datahost
+        .store_plum(&metadata_plum, None)
+        .await
+        .expect("pass");
+
+    let content_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("BranchNode content {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+
+    let content_plum_head_seal = datahost
+        .store_plum(&content_plum, None)
         .await
         .expect("pass");
 
@@ -1823,6 +2810,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchFastForwardTo(
                     branch_node_a4_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1841,6 +2829,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchFastForwardTo(
                     branch_node_1_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1860,6 +2849,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchFastForwardTo(
                     other_branch_node_0_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1879,6 +2869,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchRewindTo(
                     branch_node_1_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1897,6 +2888,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchRewindTo(
                     branch_node_a4_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1916,6 +2908,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchRewindTo(
                     other_branch_node_2_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1935,6 +2928,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchFastForwardTo(
                     branch_node_b4_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1953,6 +2947,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchForkHistoryTo(
                     branch_node_a4_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1971,6 +2966,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchForkHistoryTo(
                     other_branch_node_2_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -1992,6 +2988,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchTotallyRewriteTo(
                     other_branch_node_2_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -2010,6 +3007,7 @@ async fn test_branch() {
                 value: Some(branch_set_head_request::Value::BranchTotallyRewriteTo(
                     other_branch_node_1_plum_head_seal.clone(),
                 )),
+                expected_current_branch_head_o: None,
             },
             None,
         )
@@ -2043,6 +3041,7 @@ async fn test_branch() {
                     value: Some(branch_set_head_request::Value::BranchFastForwardTo(
                         non_branch_node_plum_head_seal.clone(),
                     )),
+                    expected_current_branch_head_o: None,
                 },
                 None,
             )
@@ -2067,3 +3066,729 @@ async fn test_branch() {
         .await
         .expect_err("fail");
 }
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_garbage_collect_grace_period() {
+    let garbage_collection_grace_period = std::time::Duration::from_secs(1);
+    let datahost = datahost_from_env_var()
+        .await
+        .with_garbage_collection_grace_period(garbage_collection_grace_period);
+
+    let (_branch_node_0, branch_node_0_plum, branch_node_0_plum_head_seal) =
+        build_and_store_random_branch_node_and_plum_with_ancestor(None, &datahost).await;
+    let path = Path::from(format!(
+        "test_garbage_collect_grace_period-{}",
+        Uuid::new_v4()
+    ));
+    datahost
+        .branch_create(
+            &PathState {
+                path: path.clone(),
+                current_state_plum_head_seal: branch_node_0_plum_head_seal.clone(),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    let old_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_garbage_collect_grace_period old, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let old_plum_head_seal = datahost.store_plum(&old_plum, None).await.expect("pass");
+    let garbage_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!(
+                "test_garbage_collect_grace_period garbage, {}",
+                Uuid::new_v4()
+            ),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let garbage_plum_head_seal = datahost
+        .store_plum(&garbage_plum, None)
+        .await
+        .expect("pass");
+
+    std::thread::sleep(garbage_collection_grace_period);
+
+    // Simulate a push of a new BranchNode, whose DirNode refers to old_plum, which is unreachable
+    // but wasn't pushed, since the Datahost already had it.
+    let (_branch_node_1, _branch_node_1_plum, branch_node_1_plum_head_seal) =
+        build_and_store_random_branch_node_and_plum_with_ancestor(
+            Some(&branch_node_0_plum),
+            &datahost,
+        )
+        .await;
+    let dir_node_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &DirNode {
+                entry_m: maplit::btreemap! {
+                    "old.txt".to_string() => old_plum_head_seal.clone(),
+                },
+            },
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let dir_node_plum_head_seal = datahost
+        .store_plum(&dir_node_plum, None)
+        .await
+        .expect("pass");
+
+    // Garbage collection between the push and the BranchSetHead must keep the pushed Plums, and what
+    // they refer to, but the old garbage is still removed.
+    let report = datahost
+        .garbage_collect(None, false, None)
+        .await
+        .expect("pass");
+    assert!(report.plum_head_seal_v.contains(&garbage_plum_head_seal));
+    for plum_head_seal in [
+        &branch_node_1_plum_head_seal,
+        &dir_node_plum_head_seal,
+        &old_plum_head_seal,
+    ] {
+        assert!(!report.plum_head_seal_v.contains(plum_head_seal));
+    }
+
+    datahost
+        .branch_set_head(
+            BranchSetHeadRequest {
+                branch_path: path.clone(),
+                value: Some(branch_set_head_request::Value::BranchFastForwardTo(
+                    branch_node_1_plum_head_seal.clone(),
+                )),
+                expected_current_branch_head_o: None,
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+    for plum_head_seal in [&dir_node_plum_head_seal, &old_plum_head_seal] {
+        datahost
+            .load_plum(plum_head_seal, None)
+            .await
+            .expect("pass");
+    }
+    let completeness_report = datahost
+        .check_completeness(&branch_node_1_plum_head_seal, PlumRelationFlags::ALL, None)
+        .await
+        .expect("pass");
+    assert!(completeness_report.is_complete());
+
+    // Once the grace period has passed, the Plums that never became reachable are garbage.
+    std::thread::sleep(garbage_collection_grace_period);
+    let report = datahost
+        .garbage_collect(None, false, None)
+        .await
+        .expect("pass");
+    assert!(report.plum_head_seal_v.contains(&dir_node_plum_head_seal));
+    assert!(report.plum_head_seal_v.contains(&old_plum_head_seal));
+    assert!(!report
+        .plum_head_seal_v
+        .contains(&branch_node_1_plum_head_seal));
+
+    datahost.delete_path_state(&path, None).await.expect("pass");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_garbage_collect() {
+    // Without a grace period, the Plums stored by this test are garbage as soon as they're unreachable.
+    let datahost = datahost_from_env_var()
+        .await
+        .with_garbage_collection_grace_period(std::time::Duration::ZERO);
+
+    let content = format!("test_garbage_collect content, {}", Uuid::new_v4());
+    let content_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &content,
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    // This has the same PlumBody as content_plum, but a different PlumHead and PlumMetadata.
+    let shared_body_plum = PlumBuilder::new()
+        .with_plum_head_nonce(Nonce::generate())
+        .with_plum_metadata_nonce(Nonce::generate())
+        .with_plum_relations_and_plum_body_content_from(
+            &content,
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    assert_eq!(
+        content_plum.plum_head.plum_body_seal,
+        shared_body_plum.plum_head.plum_body_seal
+    );
+    assert_ne!(
+        content_plum.plum_head.plum_metadata_seal,
+        shared_body_plum.plum_head.plum_metadata_seal
+    );
+    // PlumMetadata only contains the content metadata (e.g. length), so it's easily shared between
+    // otherwise unrelated Plums.  The nonce makes this PlumMetadata unique.
+    let garbage_plum = PlumBuilder::new()
+        .with_plum_metadata_nonce(Nonce::generate())
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_garbage_collect garbage, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let pinned_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_garbage_collect pinned, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+
+    let content_plum_head_seal = datahost
+        .store_plum(&content_plum, None)
+        .await
+        .expect("pass");
+    let shared_body_plum_head_seal = datahost
+        .store_plum(&shared_body_plum, None)
+        .await
+        .expect("pass");
+    let garbage_plum_head_seal = datahost
+        .store_plum(&garbage_plum, None)
+        .await
+        .expect("pass");
+    let pinned_plum_head_seal = datahost.store_plum(&pinned_plum, None).await.expect("pass");
+
+    // The DirNode makes content_plum reachable from the PathState only through its PlumRelations.
+    let dir_node = DirNode {
+        entry_m: maplit::btreemap! {
+            "content.txt".to_string() => content_plum_head_seal.clone(),
+        },
+    };
+    let dir_node_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &dir_node,
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let dir_node_plum_head_seal = datahost
+        .store_plum(&dir_node_plum, None)
+        .await
+        .expect("pass");
+
+    let path = Path::from(format!("test_garbage_collect-{}", Uuid::new_v4()));
+    datahost
+        .insert_path_state(
+            &PathState {
+                path: path.clone(),
+                current_state_plum_head_seal: dir_node_plum_head_seal.clone(),
+            },
+            None,
+        )
+        .await
+        .expect("pass");
+
+    let pinned_plum_head_seal_s = maplit::hashset! { pinned_plum_head_seal.clone() };
+
+    // Note that because the DB may be shared with other tests, the report may contain other Plums as well.
+    let dry_run_report = datahost
+        .garbage_collect(Some(&pinned_plum_head_seal_s), true, None)
+        .await
+        .expect("pass");
+    log::debug!("dry_run_report: {:?}", dry_run_report);
+    assert!(dry_run_report.dry_run);
+    for unreachable_plum in [&shared_body_plum, &garbage_plum] {
+        assert!(dry_run_report
+            .plum_head_seal_v
+            .contains(&PlumHeadSeal::from(&unreachable_plum.plum_head)));
+        assert!(dry_run_report
+            .plum_metadata_seal_v
+            .contains(&unreachable_plum.plum_head.plum_metadata_seal));
+    }
+    assert!(dry_run_report
+        .plum_body_seal_v
+        .contains(&garbage_plum.plum_head.plum_body_seal));
+    // The shared PlumBody (and the PlumRelations, which are determined by it) must be kept.
+    assert!(!dry_run_report
+        .plum_body_seal_v
+        .contains(&content_plum.plum_head.plum_body_seal));
+    assert!(!dry_run_report
+        .plum_relations_seal_v
+        .contains(&content_plum.plum_head.plum_relations_seal));
+    for reachable_plum_head_seal in [
+        &dir_node_plum_head_seal,
+        &content_plum_head_seal,
+        &pinned_plum_head_seal,
+    ] {
+        assert!(!dry_run_report
+            .plum_head_seal_v
+            .contains(reachable_plum_head_seal));
+    }
+    // Nothing should have been removed by the dry run.
+    assert!(datahost
+        .has_plum(&garbage_plum_head_seal, None)
+        .await
+        .expect("pass"));
+    assert!(datahost
+        .has_plum(&shared_body_plum_head_seal, None)
+        .await
+        .expect("pass"));
+
+    let report = datahost
+        .garbage_collect(Some(&pinned_plum_head_seal_s), false, None)
+        .await
+        .expect("pass");
+    assert!(!report.dry_run);
+    assert_eq!(
+        GarbageCollectionReport {
+            dry_run: true,
+            ..report.clone()
+        },
+        dry_run_report
+    );
+
+    assert!(!datahost
+        .has_plum_head(&garbage_plum_head_seal, None)
+        .await
+        .expect("pass"));
+    assert!(!datahost
+        .has_plum_body(&garbage_plum.plum_head.plum_body_seal, None)
+        .await
+        .expect("pass"));
+    assert!(!datahost
+        .has_plum_head(&shared_body_plum_head_seal, None)
+        .await
+        .expect("pass"));
+    for reachable_plum_head_seal in [
+        &dir_node_plum_head_seal,
+        &content_plum_head_seal,
+        &pinned_plum_head_seal,
+    ] {
+        datahost
+            .load_plum(reachable_plum_head_seal, None)
+            .await
+            .expect("pass");
+    }
+
+    // Without the pin, pinned_plum is garbage.
+    let report = datahost
+        .garbage_collect(None, false, None)
+        .await
+        .expect("pass");
+    assert!(report.plum_head_seal_v.contains(&pinned_plum_head_seal));
+    assert!(!datahost
+        .has_plum_head(&pinned_plum_head_seal, None)
+        .await
+        .expect("pass"));
+
+    // Once the PathState is deleted, everything else is garbage too.
+    datahost.delete_path_state(&path, None).await.expect("pass");
+    let report = datahost
+        .garbage_collect(None, false, None)
+        .await
+        .expect("pass");
+    assert!(report.plum_head_seal_v.contains(&dir_node_plum_head_seal));
+    assert!(report.plum_head_seal_v.contains(&content_plum_head_seal));
+    assert!(report
+        .plum_body_seal_v
+        .contains(&content_plum.plum_head.plum_body_seal));
+    assert!(!datahost
+        .has_plum_body(&content_plum.plum_head.plum_body_seal, None)
+        .await
+        .expect("pass"));
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_check_completeness() {
+    let datahost = datahost_from_env_var()
+        .await
+        .with_garbage_collection_grace_period(std::time::Duration::ZERO);
+
+    let content_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_check_completeness content, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let content_plum_head_seal = PlumHeadSeal::from(&content_plum.plum_head);
+    let dir_node_plum = PlumBuilder::new()
+        .with_plum_metadata_nonce(Nonce::generate())
+        .with_plum_relations_and_plum_body_content_from(
+            &DirNode {
+                entry_m: maplit::btreemap! {
+                    "content.txt".to_string() => content_plum_head_seal.clone(),
+                },
+            },
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let dir_node_plum_head_seal = PlumHeadSeal::from(&dir_node_plum.plum_head);
+
+    // Nothing has been stored yet, so only the root can be reached.
+    let completeness_report = datahost
+        .check_completeness(&dir_node_plum_head_seal, PlumRelationFlags::ALL, None)
+        .await
+        .expect("pass");
+    log::debug!("completeness_report: {:?}", completeness_report);
+    assert_eq!(
+        completeness_report.root_plum_head_seal,
+        dir_node_plum_head_seal
+    );
+    assert_eq!(
+        completeness_report.plum_completeness_m,
+        maplit::btreemap! { dir_node_plum_head_seal.clone() => PlumCompleteness::default() }
+    );
+    assert!(!completeness_report.is_complete());
+
+    // Store everything but the PlumMetadata of the DirNode, so that its relations can be followed.
+    datahost
+        .store_plum_head(&dir_node_plum.plum_head, None)
+        .await
+        .expect("pass");
+    datahost
+        .store_plum_relations(
+            &dir_node_plum.plum_relations,
+            &dir_node_plum.plum_head.plum_relations_seal,
+            None,
+        )
+        .await
+        .expect("pass");
+    datahost
+        .store_plum_body(
+            &dir_node_plum.plum_body,
+            &dir_node_plum.plum_head.plum_body_seal,
+            None,
+        )
+        .await
+        .expect("pass");
+    let completeness_report = datahost
+        .check_completeness(&dir_node_plum_head_seal, PlumRelationFlags::ALL, None)
+        .await
+        .expect("pass");
+    log::debug!("completeness_report: {:?}", completeness_report);
+    assert_eq!(
+        completeness_report.plum_completeness_m,
+        maplit::btreemap! {
+            dir_node_plum_head_seal.clone() => PlumCompleteness {
+                has_plum_head: true,
+                has_plum_metadata: false,
+                has_plum_relations: true,
+                has_plum_body: true,
+            },
+            content_plum_head_seal.clone() => PlumCompleteness::default(),
+        }
+    );
+    let mut expected_incomplete_plum_head_seal_v = vec![
+        dir_node_plum_head_seal.clone(),
+        content_plum_head_seal.clone(),
+    ];
+    expected_incomplete_plum_head_seal_v.sort();
+    assert_eq!(
+        completeness_report.incomplete_plum_head_seal_v(),
+        expected_incomplete_plum_head_seal_v
+    );
+    // The DirNode only has CONTENT_DEPENDENCY relations, so they aren't followed with this mask.
+    let completeness_report = datahost
+        .check_completeness(
+            &dir_node_plum_head_seal,
+            PlumRelationFlags::METADATA_DEPENDENCY,
+            None,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(completeness_report.plum_completeness_m.len(), 1);
+
+    // A BranchNode whose content is the DirNode can't be used to create a branch yet.
+    let metadata_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_check_completeness metadata, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let metadata_plum_head_seal = datahost
+        .store_plum(&metadata_plum, None)
+        .await
+        .expect("pass");
+    let branch_node = BranchNodeBuilder::new()
+        .with_metadata(metadata_plum_head_seal)
+        .with_content(dir_node_plum_head_seal.clone())
+        .build()
+        .expect("pass");
+    let branch_node_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &branch_node,
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let branch_node_plum_head_seal = datahost
+        .store_plum(&branch_node_plum, None)
+        .await
+        .expect("pass");
+    let branch_path_state = PathState {
+        path: Path::from(format!("test_check_completeness-{}", Uuid::new_v4())),
+        current_state_plum_head_seal: branch_node_plum_head_seal.clone(),
+    };
+    match datahost
+        .branch_create(&branch_path_state, None)
+        .await
+        .expect_err("fail")
+    {
+        BranchError::PlumIsNotDependencyComplete {
+            plum_head_seal,
+            incomplete_plum_head_seal_v,
+        } => {
+            assert_eq!(plum_head_seal, branch_node_plum_head_seal);
+            assert_eq!(
+                incomplete_plum_head_seal_v,
+                expected_incomplete_plum_head_seal_v
+            );
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+    assert!(!datahost
+        .has_path_state(&branch_path_state.path, None)
+        .await
+        .expect("pass"));
+
+    // Store the rest, and now the BranchNode is dependency-complete.
+    datahost
+        .store_plum(&dir_node_plum, None)
+        .await
+        .expect("pass");
+    datahost
+        .store_plum(&content_plum, None)
+        .await
+        .expect("pass");
+    let completeness_report = datahost
+        .check_completeness(&branch_node_plum_head_seal, PlumRelationFlags::ALL, None)
+        .await
+        .expect("pass");
+    assert!(completeness_report.is_complete());
+    assert!(completeness_report.incomplete_plum_head_seal_v().is_empty());
+    for plum_head_seal in [
+        &branch_node_plum_head_seal,
+        &dir_node_plum_head_seal,
+        &content_plum_head_seal,
+    ] {
+        assert!(completeness_report
+            .plum_completeness_m
+            .contains_key(plum_head_seal));
+    }
+    datahost
+        .branch_create(&branch_path_state, None)
+        .await
+        .expect("pass");
+
+    // Garbage-collecting the Plums must invalidate whatever the Datahost remembered about their
+    // completeness, so that re-storing all but the content Plum doesn't make the BranchNode look
+    // dependency-complete.
+    datahost
+        .delete_path_state(&branch_path_state.path, None)
+        .await
+        .expect("pass");
+    let garbage_collection_report = datahost
+        .garbage_collect(None, false, None)
+        .await
+        .expect("pass");
+    assert!(garbage_collection_report
+        .plum_head_seal_v
+        .contains(&content_plum_head_seal));
+    for plum in [&branch_node_plum, &dir_node_plum, &metadata_plum] {
+        datahost.store_plum(plum, None).await.expect("pass");
+    }
+    match datahost
+        .branch_create(&branch_path_state, None)
+        .await
+        .expect_err("fail")
+    {
+        BranchError::PlumIsNotDependencyComplete {
+            incomplete_plum_head_seal_v,
+            ..
+        } => {
+            assert_eq!(incomplete_plum_head_seal_v, vec![content_plum_head_seal]);
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_store_plum_v_and_load_plum_v() {
+    let datahost = datahost_from_env_var().await;
+
+    // Use enough Plums (and enough relations in a single PlumRelations) that any chunking of
+    // multi-row inserts is exercised.
+    let content_plum_v = (0..300)
+        .map(|i| {
+            PlumBuilder::new()
+                .with_plum_relations_and_plum_body_content_from(
+                    &format!("test_store_plum_v content {}, {}", i, Uuid::new_v4()),
+                    Some(&ContentFormat::charset_us_ascii()),
+                    ContentEncoding::none(),
+                )
+                .expect("pass")
+                .build()
+                .expect("pass")
+        })
+        .collect::<Vec<Plum>>();
+    let dir_node_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &DirNode {
+                entry_m: content_plum_v
+                    .iter()
+                    .enumerate()
+                    .map(|(i, content_plum)| {
+                        (
+                            format!("content-{}.txt", i),
+                            PlumHeadSeal::from(&content_plum.plum_head),
+                        )
+                    })
+                    .collect(),
+            },
+            Some(&ContentFormat::json()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+
+    // One of the Plums is already present, and one is present twice in the batch.
+    datahost
+        .store_plum(&content_plum_v[0], None)
+        .await
+        .expect("pass");
+    let mut plum_v = vec![dir_node_plum.clone()];
+    plum_v.extend(content_plum_v.iter().cloned());
+    plum_v.push(content_plum_v[1].clone());
+
+    let plum_head_seal_v = datahost.store_plum_v(&plum_v, None).await.expect("pass");
+    assert_eq!(
+        plum_head_seal_v,
+        plum_v
+            .iter()
+            .map(|plum| PlumHeadSeal::from(&plum.plum_head))
+            .collect::<Vec<PlumHeadSeal>>()
+    );
+    for plum_head_seal in plum_head_seal_v.iter() {
+        assert!(datahost.has_plum(plum_head_seal, None).await.expect("pass"));
+    }
+    // Storing them again is fine.
+    assert_eq!(
+        datahost.store_plum_v(&plum_v, None).await.expect("pass"),
+        plum_head_seal_v
+    );
+
+    let loaded_plum_v = datahost
+        .load_plum_v(&plum_head_seal_v, None)
+        .await
+        .expect("pass");
+    assert_eq!(loaded_plum_v, plum_v);
+
+    // The PlumRelations were stored correctly.
+    let plum_relation_flags_m = datahost
+        .accumulated_relations_recursive(&plum_head_seal_v[0], PlumRelationFlags::ALL, None)
+        .await
+        .expect("pass");
+    assert_eq!(plum_relation_flags_m.len(), content_plum_v.len());
+    for content_plum in content_plum_v.iter() {
+        assert_eq!(
+            plum_relation_flags_m.get(&PlumHeadSeal::from(&content_plum.plum_head)),
+            Some(&PlumRelationFlags::CONTENT_DEPENDENCY)
+        );
+    }
+
+    // load_plum_v fails if any Plum is missing.
+    let missing_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_store_plum_v missing, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    datahost
+        .load_plum_v(
+            &[
+                plum_head_seal_v[0].clone(),
+                PlumHeadSeal::from(&missing_plum.plum_head),
+            ],
+            None,
+        )
+        .await
+        .expect_err("fail");
+
+    // store_plum_v stores nothing if any Plum fails to verify.
+    let mut invalid_plum = missing_plum.clone();
+    invalid_plum.plum_head.plum_body_seal = dir_node_plum.plum_head.plum_body_seal.clone();
+    datahost
+        .store_plum_v(&[missing_plum.clone(), invalid_plum], None)
+        .await
+        .expect_err("fail");
+    assert!(!datahost
+        .has_plum(&PlumHeadSeal::from(&missing_plum.plum_head), None)
+        .await
+        .expect("pass"));
+
+    // has_plum_v distinguishes present, missing, and incomplete Plums.
+    let incomplete_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("test_store_plum_v incomplete, {}", Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    datahost
+        .store_plum_head(&incomplete_plum.plum_head, None)
+        .await
+        .expect("pass");
+    assert_eq!(
+        datahost
+            .has_plum_v(
+                &[
+                    plum_head_seal_v[0].clone(),
+                    PlumHeadSeal::from(&missing_plum.plum_head),
+                    PlumHeadSeal::from(&incomplete_plum.plum_head),
+                    plum_head_seal_v[1].clone(),
+                ],
+                None,
+            )
+            .await
+            .expect("pass"),
+        vec![true, false, false, true]
+    );
+}
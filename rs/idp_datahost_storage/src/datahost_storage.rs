@@ -1,7 +1,11 @@
-use crate::{DatahostStorageError, DatahostStorageTransaction};
+use crate::{
+    DatahostStorageError, DatahostStorageTransaction, PathStatesFilter, PlumFilter,
+    PlumHeadsFilter, SelectCursor, SelectPage,
+};
+use futures::{stream::BoxStream, TryStreamExt};
 use idp_proto::{
     Path, PathState, Plum, PlumBody, PlumBodySeal, PlumHead, PlumHeadSeal, PlumMetadata,
-    PlumMetadataSeal, PlumRelations, PlumRelationsSeal, UnixNanoseconds,
+    PlumMetadataSeal, PlumRelationFlags, PlumRelations, PlumRelationsSeal, UnixNanoseconds,
 };
 
 #[async_trait::async_trait]
@@ -32,6 +36,54 @@ pub trait DatahostStorage: Send + Sync {
         transaction: &mut dyn DatahostStorageTransaction,
         plum_body_seal: &PlumBodySeal,
     ) -> Result<bool, DatahostStorageError>;
+    /// Batch version of has_plum_metadata.  The returned Vec corresponds elementwise with
+    /// plum_metadata_seal_v.  The default implementation checks each PlumMetadata individually; a
+    /// backend for which round trips are expensive should override it.
+    async fn has_plum_metadata_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal_v: &[PlumMetadataSeal],
+    ) -> Result<Vec<bool>, DatahostStorageError> {
+        let mut has_plum_metadata_v = Vec::with_capacity(plum_metadata_seal_v.len());
+        for plum_metadata_seal in plum_metadata_seal_v {
+            has_plum_metadata_v.push(
+                self.has_plum_metadata(transaction, plum_metadata_seal)
+                    .await?,
+            );
+        }
+        Ok(has_plum_metadata_v)
+    }
+    /// Batch version of has_plum_relations.  The returned Vec corresponds elementwise with
+    /// plum_relations_seal_v.  The default implementation checks each PlumRelations individually; a
+    /// backend for which round trips are expensive should override it.
+    async fn has_plum_relations_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal_v: &[PlumRelationsSeal],
+    ) -> Result<Vec<bool>, DatahostStorageError> {
+        let mut has_plum_relations_v = Vec::with_capacity(plum_relations_seal_v.len());
+        for plum_relations_seal in plum_relations_seal_v {
+            has_plum_relations_v.push(
+                self.has_plum_relations(transaction, plum_relations_seal)
+                    .await?,
+            );
+        }
+        Ok(has_plum_relations_v)
+    }
+    /// Batch version of has_plum_body.  The returned Vec corresponds elementwise with
+    /// plum_body_seal_v.  The default implementation checks each PlumBody individually; a backend
+    /// for which round trips are expensive should override it.
+    async fn has_plum_body_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal_v: &[PlumBodySeal],
+    ) -> Result<Vec<bool>, DatahostStorageError> {
+        let mut has_plum_body_v = Vec::with_capacity(plum_body_seal_v.len());
+        for plum_body_seal in plum_body_seal_v {
+            has_plum_body_v.push(self.has_plum_body(transaction, plum_body_seal).await?);
+        }
+        Ok(has_plum_body_v)
+    }
     async fn has_plum(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
@@ -69,38 +121,135 @@ pub trait DatahostStorage: Send + Sync {
         Ok(true)
     }
 
-    // TODO: Can this return an iterator?
-    async fn select_plum_heads(
+    /// Returns one page (of at most limit rows) of the PlumHeads matching the given filter, ordered
+    /// by row_inserted_at (ties broken by rowid), and beginning immediately after the given cursor,
+    /// if specified.  Each row is row_inserted_at, PlumHeadSeal, and PlumHead.
+    async fn select_plum_heads_page(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
-    ) -> Result<Vec<(UnixNanoseconds, PlumHeadSeal, PlumHead)>, DatahostStorageError>;
-    /// Returns row_inserted_at, row_updated_at, and PathState.
+        filter: &PlumHeadsFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
+    ) -> Result<SelectPage<(UnixNanoseconds, PlumHeadSeal, PlumHead)>, DatahostStorageError>;
+    /// Returns one page (of at most limit rows) of the PathStates matching the given filter, ordered
+    /// by row_updated_at (ties broken by rowid), and beginning immediately after the given cursor,
+    /// if specified.  Each row is row_inserted_at, row_updated_at, and PathState.
     // TODO: Consider returning PathStateRow or some other struct instead of a tuple.
-    // TODO: Can this return an iterator?
-    async fn select_path_states(
+    async fn select_path_states_page(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
-    ) -> Result<Vec<(UnixNanoseconds, UnixNanoseconds, PathState)>, DatahostStorageError>;
+        filter: &PathStatesFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
+    ) -> Result<SelectPage<(UnixNanoseconds, UnixNanoseconds, PathState)>, DatahostStorageError>;
+    /// Returns a stream of the PlumHeads matching the given filter, in the order defined by
+    /// select_plum_heads_page, which is used to retrieve them page_size rows at a time.
+    fn select_plum_heads<'a>(
+        &'a self,
+        transaction: &'a mut dyn DatahostStorageTransaction,
+        filter: PlumHeadsFilter,
+        page_size: u32,
+    ) -> BoxStream<'a, Result<(UnixNanoseconds, PlumHeadSeal, PlumHead), DatahostStorageError>>
+    {
+        assert!(
+            page_size > 0,
+            "programmer error: page_size must be positive"
+        );
+        crate::paginate(
+            (transaction, filter),
+            move |(transaction, filter), cursor_o| async move {
+                let select_page = self
+                    .select_plum_heads_page(transaction, &filter, cursor_o.as_ref(), page_size)
+                    .await?;
+                Ok(((transaction, filter), select_page))
+            },
+        )
+    }
+    /// Returns a stream of the PathStates matching the given filter, in the order defined by
+    /// select_path_states_page, which is used to retrieve them page_size rows at a time.
+    fn select_path_states<'a>(
+        &'a self,
+        transaction: &'a mut dyn DatahostStorageTransaction,
+        filter: PathStatesFilter,
+        page_size: u32,
+    ) -> BoxStream<'a, Result<(UnixNanoseconds, UnixNanoseconds, PathState), DatahostStorageError>>
+    {
+        assert!(
+            page_size > 0,
+            "programmer error: page_size must be positive"
+        );
+        crate::paginate(
+            (transaction, filter),
+            move |(transaction, filter), cursor_o| async move {
+                let select_page = self
+                    .select_path_states_page(transaction, &filter, cursor_o.as_ref(), page_size)
+                    .await?;
+                Ok(((transaction, filter), select_page))
+            },
+        )
+    }
 
+    /// Returns the PlumHeadSeals of the Plums matching the given filter, in the order that their
+    /// PlumHeads were inserted.  See PlumFilter for the details of the matching.
+    async fn select_plum_head_seals_matching(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        filter: &PlumFilter,
+    ) -> Result<Vec<PlumHeadSeal>, DatahostStorageError> {
+        const PAGE_SIZE: u32 = 1024;
+        self.select_plum_heads(
+            transaction,
+            PlumHeadsFilter {
+                plum_filter: filter.clone(),
+                ..Default::default()
+            },
+            PAGE_SIZE,
+        )
+        .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+        .try_collect()
+        .await
+    }
+
+    /// Returns the PlumHeadSeals of the Plums whose PlumRelations refer to the given target Plum, along
+    /// with the PlumRelationFlags of each such relation, in the order that their PlumHeads were inserted.
+    /// This is the inverse of the relations recorded in PlumRelations.  Note that distinct PlumHeads
+    /// may share the same PlumRelations, in which case each is returned.
+    async fn select_plum_relations_referring_to(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        target_plum_head_seal: &PlumHeadSeal,
+    ) -> Result<Vec<(PlumHeadSeal, PlumRelationFlags)>, DatahostStorageError>;
+
+    /// The PlumHeadSeal is computed using the SealAlgorithm of the PlumHead's plum_body_seal (see
+    /// PlumHeadSeal::from).
     async fn store_plum_head(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_head: &PlumHead,
     ) -> Result<PlumHeadSeal, DatahostStorageError>;
+    /// The PlumMetadata is stored under the given PlumMetadataSeal, which should be the
+    /// plum_metadata_seal of the PlumHead(s) that refer to it, and which is verified against the
+    /// PlumMetadata first.  A keyed seal can't be verified without its SealKey, which DatahostStorage
+    /// doesn't have, so the caller is responsible for verifying keyed seals (see
+    /// Plum::verify_with_seal_key_provider).  The same goes for store_plum_relations and
+    /// store_plum_body.
     async fn store_plum_metadata(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_metadata: &PlumMetadata,
+        plum_metadata_seal: &PlumMetadataSeal,
     ) -> Result<PlumMetadataSeal, DatahostStorageError>;
     async fn store_plum_relations(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_relations: &PlumRelations,
+        plum_relations_seal: &PlumRelationsSeal,
     ) -> Result<PlumRelationsSeal, DatahostStorageError>;
     async fn store_plum_body(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_body: &PlumBody,
+        plum_body_seal: &PlumBodySeal,
     ) -> Result<PlumBodySeal, DatahostStorageError>;
 
     async fn store_plum(
@@ -111,18 +260,60 @@ pub trait DatahostStorage: Send + Sync {
         // Verify the Plum before storing anything.
         plum.verify()?;
 
-        // Now store its components.  Note that this computes the seals redundantly, which is not ideal, but is fine for now.
-        self.store_plum_metadata(transaction, &plum.plum_metadata)
+        // Now store its components.  Note that this verifies the seals redundantly, which is not ideal, but is fine for now.
+        self.store_plum_metadata(
+            transaction,
+            &plum.plum_metadata,
+            &plum.plum_head.plum_metadata_seal,
+        )
+        .await?;
+        self.store_plum_relations(
+            transaction,
+            &plum.plum_relations,
+            &plum.plum_head.plum_relations_seal,
+        )
+        .await?;
+        self.store_plum_body(transaction, &plum.plum_body, &plum.plum_head.plum_body_seal)
             .await?;
-        self.store_plum_relations(transaction, &plum.plum_relations)
-            .await?;
-        self.store_plum_body(transaction, &plum.plum_body).await?;
 
         // Storing the plum head last ensures that the plum is fully stored before we commit to it.
         let plum_head_seal = self.store_plum_head(transaction, &plum.plum_head).await?;
 
         Ok(plum_head_seal)
     }
+    /// Batch version of store_plum, for bulk import.  All the Plums are verified before anything is
+    /// stored.  The returned Vec corresponds elementwise with plum_v.  The default implementation
+    /// stores the components of each Plum individually (without verifying each Plum again, as
+    /// store_plum would); a backend that supports multi-row inserts should override it.
+    async fn store_plum_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_v: &[Plum],
+    ) -> Result<Vec<PlumHeadSeal>, DatahostStorageError> {
+        for plum in plum_v {
+            plum.verify()?;
+        }
+        let mut plum_head_seal_v = Vec::with_capacity(plum_v.len());
+        for plum in plum_v {
+            self.store_plum_metadata(
+                transaction,
+                &plum.plum_metadata,
+                &plum.plum_head.plum_metadata_seal,
+            )
+            .await?;
+            self.store_plum_relations(
+                transaction,
+                &plum.plum_relations,
+                &plum.plum_head.plum_relations_seal,
+            )
+            .await?;
+            self.store_plum_body(transaction, &plum.plum_body, &plum.plum_head.plum_body_seal)
+                .await?;
+            // As in store_plum, the PlumHead is stored last.
+            plum_head_seal_v.push(self.store_plum_head(transaction, &plum.plum_head).await?);
+        }
+        Ok(plum_head_seal_v)
+    }
 
     async fn load_option_plum_head(
         &self,
@@ -144,6 +335,40 @@ pub trait DatahostStorage: Send + Sync {
         transaction: &mut dyn DatahostStorageTransaction,
         plum_body_seal: &PlumBodySeal,
     ) -> Result<Option<PlumBody>, DatahostStorageError>;
+    /// Batch version of load_option_plum_head.  The returned Vec corresponds elementwise with
+    /// plum_head_seal_v.  The default implementation loads each PlumHead individually; a backend
+    /// for which round trips are expensive should override it.
+    async fn load_option_plum_head_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal_v: &[PlumHeadSeal],
+    ) -> Result<Vec<Option<PlumHead>>, DatahostStorageError> {
+        let mut plum_head_ov = Vec::with_capacity(plum_head_seal_v.len());
+        for plum_head_seal in plum_head_seal_v {
+            plum_head_ov.push(
+                self.load_option_plum_head(transaction, plum_head_seal)
+                    .await?,
+            );
+        }
+        Ok(plum_head_ov)
+    }
+    /// Batch version of load_option_plum_relations.  The returned Vec corresponds elementwise with
+    /// plum_relations_seal_v.  The default implementation loads each PlumRelations individually; a
+    /// backend for which round trips are expensive should override it.
+    async fn load_option_plum_relations_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal_v: &[PlumRelationsSeal],
+    ) -> Result<Vec<Option<PlumRelations>>, DatahostStorageError> {
+        let mut plum_relations_ov = Vec::with_capacity(plum_relations_seal_v.len());
+        for plum_relations_seal in plum_relations_seal_v {
+            plum_relations_ov.push(
+                self.load_option_plum_relations(transaction, plum_relations_seal)
+                    .await?,
+            );
+        }
+        Ok(plum_relations_ov)
+    }
     async fn load_plum_head(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
@@ -259,6 +484,88 @@ pub trait DatahostStorage: Send + Sync {
 
         Ok(plum)
     }
+    /// Batch version of load_plum.  The returned Vec corresponds elementwise with plum_head_seal_v.
+    /// If any component of any of the Plums is missing, then this returns an error.  The default
+    /// implementation loads the PlumHeads and PlumRelations in batches (see load_option_plum_head_v
+    /// and load_option_plum_relations_v) and the rest individually.
+    async fn load_plum_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal_v: &[PlumHeadSeal],
+    ) -> Result<Vec<Plum>, DatahostStorageError> {
+        let mut plum_head_v = Vec::with_capacity(plum_head_seal_v.len());
+        for (plum_head_seal, plum_head_o) in plum_head_seal_v.iter().zip(
+            self.load_option_plum_head_v(transaction, plum_head_seal_v)
+                .await?,
+        ) {
+            plum_head_v.push(
+                plum_head_o.ok_or_else(|| {
+                    DatahostStorageError::PlumHeadNotFound(plum_head_seal.clone())
+                })?,
+            );
+        }
+        let plum_relations_seal_v = plum_head_v
+            .iter()
+            .map(|plum_head| plum_head.plum_relations_seal.clone())
+            .collect::<Vec<PlumRelationsSeal>>();
+        let plum_relations_ov = self
+            .load_option_plum_relations_v(transaction, &plum_relations_seal_v)
+            .await?;
+
+        let mut plum_v = Vec::with_capacity(plum_head_v.len());
+        for ((plum_head, plum_relations_seal), plum_relations_o) in plum_head_v
+            .into_iter()
+            .zip(plum_relations_seal_v)
+            .zip(plum_relations_ov)
+        {
+            let plum_relations = plum_relations_o.ok_or(
+                DatahostStorageError::PlumRelationsNotFound(plum_relations_seal),
+            )?;
+            let plum_metadata = self
+                .load_plum_metadata(transaction, &plum_head.plum_metadata_seal)
+                .await?;
+            let plum_body = self
+                .load_plum_body(transaction, &plum_head.plum_body_seal)
+                .await?;
+            // Verify each Plum, as in load_plum.
+            let plum = Plum {
+                plum_head,
+                plum_metadata,
+                plum_relations,
+                plum_body,
+            };
+            plum.verify()?;
+            plum_v.push(plum);
+        }
+        Ok(plum_v)
+    }
+
+    /// Deletes the PlumHead with the given seal, if present.  Note that this doesn't delete the
+    /// PlumMetadata, PlumRelations, or PlumBody that it refers to, since those may be shared by
+    /// other PlumHeads.
+    async fn delete_plum_head(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<(), DatahostStorageError>;
+    /// Deletes the PlumMetadata with the given seal, if present.
+    async fn delete_plum_metadata(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal: &PlumMetadataSeal,
+    ) -> Result<(), DatahostStorageError>;
+    /// Deletes the PlumRelations with the given seal, if present.
+    async fn delete_plum_relations(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal: &PlumRelationsSeal,
+    ) -> Result<(), DatahostStorageError>;
+    /// Deletes the PlumBody with the given seal, if present.
+    async fn delete_plum_body(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<(), DatahostStorageError>;
 
     async fn has_path_state(
         &self,
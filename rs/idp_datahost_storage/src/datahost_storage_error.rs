@@ -1,5 +1,6 @@
 use idp_proto::{
-    Path, PlumBodySeal, PlumHeadSeal, PlumMetadataSeal, PlumRelationsSeal, PlumVerifyError,
+    datahost_storage_error_detail, DatahostStorageErrorDetail, MalformedErrorDetail, Path,
+    PlumBodySeal, PlumHeadSeal, PlumMetadataSeal, PlumRelationsSeal, PlumVerifyError,
 };
 
 #[derive(Debug, derive_more::From, thiserror::Error)]
@@ -12,8 +13,9 @@ pub enum DatahostStorageError {
         column_name: &'static str,
         reason: String,
     },
-    // #[error("Path {0} already exists")]
-    // PathAlreadyExists(Path),
+    #[error("Path {0} already exists")]
+    #[from(ignore)]
+    PathAlreadyExists(Path),
     #[error("Path {0} not found")]
     PathNotFound(Path),
     #[error("PlumHead {0} not found")]
@@ -24,30 +26,153 @@ pub enum DatahostStorageError {
     PlumRelationsNotFound(PlumRelationsSeal),
     #[error("PlumBody {0} not found")]
     PlumBodyNotFound(PlumBodySeal),
+    #[error("PlumBody {0} failed integrity check; its stored content doesn't match its seal")]
+    #[from(ignore)]
+    PlumBodyIntegrityCheckFailed(PlumBodySeal),
+    #[error(transparent)]
+    IoError(std::io::Error),
     #[error(transparent)]
     PlumVerifyError(PlumVerifyError),
+    #[error("Transaction conflicted with another transaction that committed first")]
+    #[from(ignore)]
+    TransactionConflict,
     #[cfg(feature = "sqlx-error")]
     #[error(transparent)]
     SqlxError(sqlx::Error),
 }
 
+impl From<&DatahostStorageError> for DatahostStorageErrorDetail {
+    fn from(datahost_storage_error: &DatahostStorageError) -> Self {
+        use datahost_storage_error_detail::Value;
+        let value = match datahost_storage_error {
+            DatahostStorageError::Generic(_) => Value::Generic(datahost_storage_error.to_string()),
+            DatahostStorageError::InvalidValueInDB {
+                table_name,
+                column_name,
+                reason,
+            } => Value::InvalidValueInDb(datahost_storage_error_detail::InvalidValueInDb {
+                table_name: table_name.to_string(),
+                column_name: column_name.to_string(),
+                reason: reason.clone(),
+            }),
+            DatahostStorageError::PathAlreadyExists(path) => Value::PathAlreadyExists(path.clone()),
+            DatahostStorageError::PathNotFound(path) => Value::PathNotFound(path.clone()),
+            DatahostStorageError::PlumHeadNotFound(plum_head_seal) => {
+                Value::PlumHeadNotFound(plum_head_seal.clone())
+            }
+            DatahostStorageError::PlumMetadataNotFound(plum_metadata_seal) => {
+                Value::PlumMetadataNotFound(plum_metadata_seal.clone())
+            }
+            DatahostStorageError::PlumRelationsNotFound(plum_relations_seal) => {
+                Value::PlumRelationsNotFound(plum_relations_seal.clone())
+            }
+            DatahostStorageError::PlumBodyNotFound(plum_body_seal) => {
+                Value::PlumBodyNotFound(plum_body_seal.clone())
+            }
+            DatahostStorageError::PlumBodyIntegrityCheckFailed(plum_body_seal) => {
+                Value::PlumBodyIntegrityCheckFailed(plum_body_seal.clone())
+            }
+            DatahostStorageError::IoError(io_error) => Value::IoError(io_error.to_string()),
+            DatahostStorageError::PlumVerifyError(plum_verify_error) => {
+                Value::PlumVerifyError(plum_verify_error.into())
+            }
+            DatahostStorageError::TransactionConflict => {
+                Value::TransactionConflict(datahost_storage_error_detail::TransactionConflict {})
+            }
+            #[cfg(feature = "sqlx-error")]
+            DatahostStorageError::SqlxError(_) => {
+                Value::Generic(datahost_storage_error.to_string())
+            }
+        };
+        Self { value: Some(value) }
+    }
+}
+
+impl TryFrom<DatahostStorageErrorDetail> for DatahostStorageError {
+    type Error = MalformedErrorDetail;
+    fn try_from(
+        datahost_storage_error_detail: DatahostStorageErrorDetail,
+    ) -> Result<Self, Self::Error> {
+        use datahost_storage_error_detail::Value;
+        let value = datahost_storage_error_detail
+            .value
+            .ok_or(MalformedErrorDetail {
+                message_name: "DatahostStorageErrorDetail",
+            })?;
+        Ok(match value {
+            Value::Generic(message) => DatahostStorageError::Generic(message.into()),
+            // The table and column names are &'static str, so this can't be reconstructed exactly.
+            Value::InvalidValueInDb(x) => DatahostStorageError::Generic(
+                format!(
+                    "Read an invalid value from table {} column {}",
+                    x.table_name, x.column_name
+                )
+                .into(),
+            ),
+            Value::PathAlreadyExists(path) => DatahostStorageError::PathAlreadyExists(path),
+            Value::PathNotFound(path) => DatahostStorageError::PathNotFound(path),
+            Value::PlumHeadNotFound(plum_head_seal) => {
+                DatahostStorageError::PlumHeadNotFound(plum_head_seal)
+            }
+            Value::PlumMetadataNotFound(plum_metadata_seal) => {
+                DatahostStorageError::PlumMetadataNotFound(plum_metadata_seal)
+            }
+            Value::PlumRelationsNotFound(plum_relations_seal) => {
+                DatahostStorageError::PlumRelationsNotFound(plum_relations_seal)
+            }
+            Value::PlumBodyNotFound(plum_body_seal) => {
+                DatahostStorageError::PlumBodyNotFound(plum_body_seal)
+            }
+            Value::PlumBodyIntegrityCheckFailed(plum_body_seal) => {
+                DatahostStorageError::PlumBodyIntegrityCheckFailed(plum_body_seal)
+            }
+            Value::IoError(message) => DatahostStorageError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                message,
+            )),
+            Value::PlumVerifyError(plum_verify_error_detail) => {
+                DatahostStorageError::PlumVerifyError(plum_verify_error_detail.try_into()?)
+            }
+            Value::TransactionConflict(_) => DatahostStorageError::TransactionConflict,
+        })
+    }
+}
+
 #[cfg(feature = "tonic")]
-impl From<DatahostStorageError> for tonic::Status {
-    fn from(datahost_storage_error: DatahostStorageError) -> Self {
-        // It would be better to serialize the DatahostStorageError and deserialize it on the IDPClient side.
-        let code = match &datahost_storage_error {
+impl DatahostStorageError {
+    /// The tonic::Code that best describes this error.
+    pub fn tonic_code(&self) -> tonic::Code {
+        match self {
             DatahostStorageError::Generic(_) => tonic::Code::Unknown,
             DatahostStorageError::InvalidValueInDB { .. } => tonic::Code::DataLoss,
-            // DatahostStorageError::PathAlreadyExists(_) => tonic::Code::AlreadyExists,
+            DatahostStorageError::PathAlreadyExists(_) => tonic::Code::AlreadyExists,
             DatahostStorageError::PathNotFound(_) => tonic::Code::NotFound,
             DatahostStorageError::PlumHeadNotFound(_) => tonic::Code::NotFound,
             DatahostStorageError::PlumMetadataNotFound(_) => tonic::Code::NotFound,
             DatahostStorageError::PlumRelationsNotFound(_) => tonic::Code::NotFound,
             DatahostStorageError::PlumBodyNotFound(_) => tonic::Code::NotFound,
+            DatahostStorageError::PlumBodyIntegrityCheckFailed(_) => tonic::Code::DataLoss,
+            DatahostStorageError::IoError(_) => tonic::Code::Internal,
             DatahostStorageError::PlumVerifyError(_) => tonic::Code::InvalidArgument,
+            DatahostStorageError::TransactionConflict => tonic::Code::Aborted,
             #[cfg(feature = "sqlx-error")]
             DatahostStorageError::SqlxError(_) => tonic::Code::Internal,
-        };
-        tonic::Status::new(code, datahost_storage_error.to_string())
+        }
+    }
+}
+
+/// The resulting tonic::Status carries an ErrorDetail, from which IDPClient reconstructs this error.
+#[cfg(feature = "tonic")]
+impl From<DatahostStorageError> for tonic::Status {
+    fn from(datahost_storage_error: DatahostStorageError) -> Self {
+        idp_proto::ErrorDetail {
+            value: Some(idp_proto::error_detail::Value::DatahostStorageError(
+                (&datahost_storage_error).into(),
+            )),
+        }
+        .into_status(
+            datahost_storage_error.tonic_code(),
+            datahost_storage_error.to_string(),
+        )
     }
 }
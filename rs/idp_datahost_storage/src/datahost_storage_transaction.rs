@@ -6,6 +6,12 @@ pub trait DatahostStorageTransaction: Send + 'static {
     /// https://stackoverflow.com/questions/33687447/how-to-get-a-reference-to-a-concrete-type-from-a-trait-object
     /// Alternate solution: https://crates.io/crates/mopa
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+    /// Registers a callback to be called once this transaction has been successfully committed.  If
+    /// the transaction is rolled back (explicitly or by being dropped) or the commit fails, then the
+    /// callback is dropped without being called.  This lets layers above DatahostStorage defer side
+    /// effects (e.g. updating caches) until the changes they depend on are durable.  See
+    /// OnCommitCallbacks.
+    fn on_commit(&mut self, callback: Box<dyn FnOnce() + Send>);
     async fn commit(self: Box<Self>) -> Result<(), DatahostStorageError>;
     async fn rollback(self: Box<Self>) -> Result<(), DatahostStorageError>;
 }
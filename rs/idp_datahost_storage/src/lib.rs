@@ -1,7 +1,23 @@
 mod datahost_storage;
 mod datahost_storage_error;
 mod datahost_storage_transaction;
+mod on_commit_callbacks;
+mod paginate;
+mod path_states_filter;
+mod plum_filter;
+mod plum_heads_filter;
+mod seal_from_db;
+mod select_cursor;
+mod select_page;
 
 pub use datahost_storage::DatahostStorage;
 pub use datahost_storage_error::DatahostStorageError;
 pub use datahost_storage_transaction::{downcast_transaction_mut, DatahostStorageTransaction};
+pub use on_commit_callbacks::OnCommitCallbacks;
+pub use paginate::paginate;
+pub use path_states_filter::PathStatesFilter;
+pub use plum_filter::PlumFilter;
+pub use plum_heads_filter::PlumHeadsFilter;
+pub use seal_from_db::seal_from_db;
+pub use select_cursor::SelectCursor;
+pub use select_page::SelectPage;
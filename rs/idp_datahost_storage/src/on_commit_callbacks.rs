@@ -0,0 +1,27 @@
+/// Holds the callbacks registered on a transaction via DatahostStorageTransaction::on_commit.  A
+/// DatahostStorageTransaction implementation should call run after (and only after) it has
+/// successfully committed; dropping this instead simply discards the callbacks.
+#[derive(Default)]
+pub struct OnCommitCallbacks {
+    callback_v: Vec<Box<dyn FnOnce() + Send>>,
+}
+
+impl OnCommitCallbacks {
+    pub fn push(&mut self, callback: Box<dyn FnOnce() + Send>) {
+        self.callback_v.push(callback);
+    }
+    /// Calls the callbacks in the order they were registered.
+    pub fn run(self) {
+        for callback in self.callback_v {
+            callback();
+        }
+    }
+}
+
+impl std::fmt::Debug for OnCommitCallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("OnCommitCallbacks")
+            .field("len", &self.callback_v.len())
+            .finish()
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{SelectCursor, SelectPage};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+
+/// Returns a stream of the rows of the pages returned by select_page_f, which is called first with
+/// cursor_o None, and then with the next_cursor_o of each page, until that is None.  The state (e.g. a
+/// transaction and filter) is moved into each call of select_page_f and returned along with the page,
+/// so that each call can borrow it.
+pub fn paginate<'a, S, T, E, F, Fut>(state: S, select_page_f: F) -> BoxStream<'a, Result<T, E>>
+where
+    S: Send + 'a,
+    T: Send + 'a,
+    E: Send + 'a,
+    F: Fn(S, Option<SelectCursor>) -> Fut + Send + 'a,
+    Fut: std::future::Future<Output = Result<(S, SelectPage<T>), E>> + Send + 'a,
+{
+    // The Option<Option<SelectCursor>> is None when there are no more pages, and Some(cursor_o) when
+    // the next page should begin immediately after cursor_o.
+    futures::stream::try_unfold(
+        (state, Some(None)),
+        move |(state, cursor_oo): (S, Option<Option<SelectCursor>>)| {
+            let select_page_fut_o = cursor_oo.map(|cursor_o| select_page_f(state, cursor_o));
+            async move {
+                let (state, select_page) = match select_page_fut_o {
+                    Some(select_page_fut) => select_page_fut.await?,
+                    None => return Ok(None),
+                };
+                let row_s = futures::stream::iter(select_page.row_v.into_iter().map(Ok));
+                let next_cursor_oo = select_page.next_cursor_o.map(Some);
+                Ok(Some((row_s, (state, next_cursor_oo))))
+            }
+        },
+    )
+    .try_flatten()
+    .boxed()
+}
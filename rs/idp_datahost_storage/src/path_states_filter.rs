@@ -0,0 +1,13 @@
+use idp_proto::UnixNanoseconds;
+
+/// Filter for DatahostStorage::select_path_states and select_path_states_page.  The default value
+/// doesn't filter anything.
+#[derive(Clone, Debug, Default)]
+pub struct PathStatesFilter {
+    /// If specified, only PathStates whose row_updated_at is at or after this are selected.
+    pub updated_at_begin_o: Option<UnixNanoseconds>,
+    /// If specified, only PathStates whose row_updated_at is before this are selected.
+    pub updated_at_end_o: Option<UnixNanoseconds>,
+    /// If specified, only PathStates whose path begins with this are selected.
+    pub path_prefix_o: Option<String>,
+}
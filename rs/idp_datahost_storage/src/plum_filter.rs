@@ -0,0 +1,22 @@
+use idp_proto::{ContentClass, ContentFormat, UnixNanoseconds};
+
+/// Filter for DatahostStorage::select_plum_head_seals_matching (and, as part of PlumHeadsFilter,
+/// select_plum_heads).  A Plum matches if it matches all of the specified criteria.  The default
+/// value matches all Plums.
+///
+/// The content class and format of a Plum are taken from its PlumMetadata if it includes the
+/// PlumBody content metadata, and otherwise from its PlumBody (if present).  In particular, if the
+/// stored PlumMetadata and PlumBody disagree, the PlumBody is ignored.  The creation time of a
+/// Plum is only taken from its PlumMetadata, so a Plum without plum_created_at_o doesn't match if
+/// either bound of the creation time range is specified.
+#[derive(Clone, Debug, Default)]
+pub struct PlumFilter {
+    /// If specified, only Plums whose PlumBody content has this content class match.
+    pub content_class_o: Option<ContentClass>,
+    /// If specified, only Plums whose PlumBody content has this content format match.
+    pub content_format_o: Option<ContentFormat>,
+    /// If specified, only Plums created at or after this match.
+    pub created_at_begin_o: Option<UnixNanoseconds>,
+    /// If specified, only Plums created before this match.
+    pub created_at_end_o: Option<UnixNanoseconds>,
+}
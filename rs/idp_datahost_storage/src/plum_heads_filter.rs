@@ -0,0 +1,14 @@
+use crate::PlumFilter;
+use idp_proto::UnixNanoseconds;
+
+/// Filter for DatahostStorage::select_plum_heads and select_plum_heads_page.  The default value
+/// doesn't filter anything.
+#[derive(Clone, Debug, Default)]
+pub struct PlumHeadsFilter {
+    /// Only PlumHeads of Plums matching this are selected.  See PlumFilter for the details.
+    pub plum_filter: PlumFilter,
+    /// If specified, only PlumHeads whose row_inserted_at is at or after this are selected.
+    pub inserted_at_begin_o: Option<UnixNanoseconds>,
+    /// If specified, only PlumHeads whose row_inserted_at is before this are selected.
+    pub inserted_at_end_o: Option<UnixNanoseconds>,
+}
@@ -0,0 +1,19 @@
+use crate::DatahostStorageError;
+use idp_proto::SealError;
+
+/// Decodes a seal (e.g. PlumHeadSeal) that was read from the given table and column, where it was
+/// stored as Seal::as_bytes.  A malformed stored seal produces DatahostStorageError::InvalidValueInDB.
+pub fn seal_from_db<S>(
+    byte_v: Vec<u8>,
+    table_name: &'static str,
+    column_name: &'static str,
+) -> Result<S, DatahostStorageError>
+where
+    S: TryFrom<Vec<u8>, Error = SealError>,
+{
+    S::try_from(byte_v).map_err(|e| DatahostStorageError::InvalidValueInDB {
+        table_name,
+        column_name,
+        reason: e.to_string(),
+    })
+}
@@ -0,0 +1,29 @@
+use idp_proto::UnixNanoseconds;
+
+/// Identifies the position of a row within the results of a paginated select method (e.g.
+/// DatahostStorage::select_plum_heads_page), so that the next page can begin immediately after it.
+/// Rows are ordered by a timestamp (e.g. row_inserted_at), with ties broken by rowid, which is an
+/// implementation-specific row identifier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectCursor {
+    pub row_at: UnixNanoseconds,
+    pub rowid: i64,
+}
+
+impl From<idp_proto::PageCursor> for SelectCursor {
+    fn from(page_cursor: idp_proto::PageCursor) -> Self {
+        Self {
+            row_at: page_cursor.row_at,
+            rowid: page_cursor.rowid,
+        }
+    }
+}
+
+impl From<SelectCursor> for idp_proto::PageCursor {
+    fn from(select_cursor: SelectCursor) -> Self {
+        Self {
+            row_at: select_cursor.row_at,
+            rowid: select_cursor.rowid,
+        }
+    }
+}
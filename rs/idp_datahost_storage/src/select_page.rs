@@ -0,0 +1,10 @@
+use crate::SelectCursor;
+
+/// One page of the results of a paginated select method (e.g. DatahostStorage::select_plum_heads_page).
+#[derive(Clone, Debug)]
+pub struct SelectPage<T> {
+    pub row_v: Vec<T>,
+    /// This is the cursor to pass in order to get the next page, or None if this is the last page.
+    /// Note that the next page may be empty.
+    pub next_cursor_o: Option<SelectCursor>,
+}
@@ -0,0 +1,890 @@
+use crate::{in_memory_transaction_mut, DatahostStorageInMemoryTransaction};
+use idp_datahost_storage::{
+    DatahostStorage, DatahostStorageError, DatahostStorageTransaction, PathStatesFilter,
+    PlumFilter, PlumHeadsFilter, SelectCursor, SelectPage,
+};
+use idp_proto::{
+    Path, PathState, PlumBody, PlumBodySeal, PlumHead, PlumHeadSeal, PlumMetadata,
+    PlumMetadataSeal, PlumRelationFlags, PlumRelations, PlumRelationsSeal, UnixNanoseconds,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::{Arc, RwLock},
+};
+
+#[derive(Clone)]
+pub(crate) struct PlumHeadRow {
+    /// This is used to order rows that have the same row_inserted_at.
+    rowid: i64,
+    row_inserted_at: UnixNanoseconds,
+    plum_head: PlumHead,
+}
+
+#[derive(Clone)]
+pub(crate) struct PathStateRow {
+    /// This is used to order rows that have the same row_updated_at.
+    rowid: i64,
+    row_inserted_at: UnixNanoseconds,
+    row_updated_at: UnixNanoseconds,
+    current_state_plum_head_seal: PlumHeadSeal,
+}
+
+/// The "tables" of DatahostStorageInMemory.  Each table is a persistent (i.e. structurally shared)
+/// map, so that cloning Tables (e.g. to snapshot them for a transaction) is O(1), and each write only
+/// copies the O(log n) nodes on the path to the written entry.  PlumBody-s are additionally stored
+/// in Arc, since they can be large.
+#[derive(Clone, Default)]
+pub(crate) struct Tables {
+    next_rowid: i64,
+    plum_head_m: im::HashMap<PlumHeadSeal, PlumHeadRow>,
+    plum_metadata_m: im::HashMap<PlumMetadataSeal, PlumMetadata>,
+    plum_relations_m: im::HashMap<PlumRelationsSeal, PlumRelations>,
+    plum_body_m: im::HashMap<PlumBodySeal, Arc<PlumBody>>,
+    path_state_m: im::HashMap<String, PathStateRow>,
+}
+
+impl Tables {
+    fn next_rowid(&mut self) -> i64 {
+        let rowid = self.next_rowid;
+        self.next_rowid += 1;
+        rowid
+    }
+    /// Merges the writes of a transaction into these (committed) tables, where base_tables is the
+    /// transaction's snapshot of the committed tables as of when it began, and tables are its tables
+    /// (including its writes).  A write to a row that no other transaction has changed since
+    /// base_tables is applied, as is a write that has the same effect as the committed row already
+    /// has (e.g. storing the same Plum).  Otherwise the write conflicts with that of a transaction
+    /// that committed first, and TransactionConflict is returned.  In that case, self may have been
+    /// partially written, so it should be a clone that's discarded upon error.
+    pub(crate) fn merge_writes(
+        &mut self,
+        base_tables: &Tables,
+        tables: &Tables,
+        written_keys: &WrittenKeys,
+    ) -> Result<(), DatahostStorageError> {
+        // The Plum tables are content-addressed, so rows having the same key are the same.
+        let plum_head_seal_v = merge_table(
+            &mut self.plum_head_m,
+            &base_tables.plum_head_m,
+            &tables.plum_head_m,
+            &written_keys.plum_head_seal_s,
+            |_, _| true,
+        )?;
+        merge_table(
+            &mut self.plum_metadata_m,
+            &base_tables.plum_metadata_m,
+            &tables.plum_metadata_m,
+            &written_keys.plum_metadata_seal_s,
+            |_, _| true,
+        )?;
+        merge_table(
+            &mut self.plum_relations_m,
+            &base_tables.plum_relations_m,
+            &tables.plum_relations_m,
+            &written_keys.plum_relations_seal_s,
+            |_, _| true,
+        )?;
+        merge_table(
+            &mut self.plum_body_m,
+            &base_tables.plum_body_m,
+            &tables.plum_body_m,
+            &written_keys.plum_body_seal_s,
+            |_, _| true,
+        )?;
+        let path_v = merge_table(
+            &mut self.path_state_m,
+            &base_tables.path_state_m,
+            &tables.path_state_m,
+            &written_keys.path_s,
+            |lhs, rhs| lhs.current_state_plum_head_seal == rhs.current_state_plum_head_seal,
+        )?;
+
+        // The rowids of the merged rows were allocated from the transaction's snapshot, so they may
+        // collide with those allocated by other transactions.  Reallocate them, preserving their order.
+        let mut plum_head_rowid_v = plum_head_seal_v
+            .into_iter()
+            .map(|plum_head_seal| (self.plum_head_m[&plum_head_seal].rowid, plum_head_seal))
+            .collect::<Vec<_>>();
+        plum_head_rowid_v.sort_by_key(|(rowid, _)| *rowid);
+        for (_, plum_head_seal) in plum_head_rowid_v {
+            let rowid = self.next_rowid();
+            self.plum_head_m.get_mut(&plum_head_seal).unwrap().rowid = rowid;
+        }
+        let mut path_rowid_v = path_v
+            .into_iter()
+            .map(|path| (self.path_state_m[&path].rowid, path))
+            .collect::<Vec<_>>();
+        path_rowid_v.sort_by_key(|(rowid, _)| *rowid);
+        for (_, path) in path_rowid_v {
+            let rowid = self.next_rowid();
+            self.path_state_m.get_mut(&path).unwrap().rowid = rowid;
+        }
+
+        Ok(())
+    }
+}
+
+/// Merges a transaction's writes to a single table into committed_m (see Tables::merge_writes),
+/// returning the keys of the rows that were inserted or updated.  is_same_row determines if two rows
+/// having the same key are equivalent.
+fn merge_table<K, V>(
+    committed_m: &mut im::HashMap<K, V>,
+    base_m: &im::HashMap<K, V>,
+    m: &im::HashMap<K, V>,
+    written_key_s: &HashSet<K>,
+    is_same_row: impl Fn(&V, &V) -> bool,
+) -> Result<Vec<K>, DatahostStorageError>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    let is_same = |lhs_o: Option<&V>, rhs_o: Option<&V>| match (lhs_o, rhs_o) {
+        (Some(lhs), Some(rhs)) => is_same_row(lhs, rhs),
+        (None, None) => true,
+        _ => false,
+    };
+    let mut upserted_key_v = Vec::new();
+    for key in written_key_s {
+        let row_o = m.get(key);
+        let committed_row_o = committed_m.get(key);
+        if is_same(committed_row_o, row_o) {
+            // The committed row already reflects this write, so there's nothing to do.
+            continue;
+        }
+        if !is_same(committed_row_o, base_m.get(key)) {
+            return Err(DatahostStorageError::TransactionConflict);
+        }
+        match row_o {
+            Some(row) => {
+                committed_m.insert(key.clone(), row.clone());
+                upserted_key_v.push(key.clone());
+            }
+            None => {
+                committed_m.remove(key);
+            }
+        }
+    }
+    Ok(upserted_key_v)
+}
+
+/// The keys of the rows that a transaction has written (i.e. inserted, updated, or deleted), by table.
+/// These determine which rows are merged into the committed tables (see Tables::merge_writes).
+#[derive(Default)]
+pub(crate) struct WrittenKeys {
+    plum_head_seal_s: HashSet<PlumHeadSeal>,
+    plum_metadata_seal_s: HashSet<PlumMetadataSeal>,
+    plum_relations_seal_s: HashSet<PlumRelationsSeal>,
+    plum_body_seal_s: HashSet<PlumBodySeal>,
+    path_s: HashSet<String>,
+}
+
+impl WrittenKeys {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.plum_head_seal_s.is_empty()
+            && self.plum_metadata_seal_s.is_empty()
+            && self.plum_relations_seal_s.is_empty()
+            && self.plum_body_seal_s.is_empty()
+            && self.path_s.is_empty()
+    }
+}
+
+/// The committed state of DatahostStorageInMemory.  The generation is incremented upon each commit
+/// of a transaction that wrote something, so that a transaction that began at the current generation
+/// can simply replace the tables instead of merging its writes into them.
+#[derive(Default)]
+pub(crate) struct Committed {
+    pub(crate) generation: u64,
+    pub(crate) tables: Tables,
+}
+
+/// Pure-Rust, in-memory implementation of DatahostStorage.  This doesn't depend on any particular
+/// async runtime, since none of its operations actually block (other than briefly on a lock when
+/// beginning and committing transactions).  See README.md for the transaction semantics.
+#[derive(Default)]
+pub struct DatahostStorageInMemory {
+    committed_la: Arc<RwLock<Committed>>,
+}
+
+impl DatahostStorageInMemory {
+    /// Creates an empty DatahostStorageInMemory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Returns true iff row_at is within the range [begin_o, end_o), where an unspecified bound is unbounded.
+fn is_within_range(
+    row_at: UnixNanoseconds,
+    begin_o: Option<UnixNanoseconds>,
+    end_o: Option<UnixNanoseconds>,
+) -> bool {
+    if let Some(begin) = begin_o {
+        if row_at.value < begin.value {
+            return false;
+        }
+    }
+    if let Some(end) = end_o {
+        if row_at.value >= end.value {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns true iff path begins with the given prefix, if specified.
+fn has_prefix(path: &str, prefix_o: Option<&String>) -> bool {
+    match prefix_o {
+        Some(prefix) => path.starts_with(prefix.as_str()),
+        None => true,
+    }
+}
+
+/// Returns true iff the row identified by (row_at, rowid) comes after the given cursor, if specified.
+fn is_after_cursor(row_at: UnixNanoseconds, rowid: i64, cursor_o: Option<&SelectCursor>) -> bool {
+    match cursor_o {
+        Some(cursor) => (row_at.value, rowid) > (cursor.row_at.value, cursor.rowid),
+        None => true,
+    }
+}
+
+/// Returns true iff the Plum with the given PlumHead matches the given PlumFilter.
+fn plum_matches(tables: &Tables, plum_head: &PlumHead, filter: &PlumFilter) -> bool {
+    let plum_metadata_o = tables.plum_metadata_m.get(&plum_head.plum_metadata_seal);
+    // The PlumMetadata's copy of the PlumBody content metadata takes precedence over the PlumBody.
+    let content_metadata_o = plum_metadata_o
+        .and_then(|plum_metadata| plum_metadata.plum_body_content_metadata_o.as_ref())
+        .or_else(|| {
+            tables
+                .plum_body_m
+                .get(&plum_head.plum_body_seal)
+                .map(|plum_body| &plum_body.plum_body_content.content_metadata)
+        });
+    if let Some(content_class) = filter.content_class_o.as_ref() {
+        if content_metadata_o.map(|content_metadata| &content_metadata.content_class)
+            != Some(content_class)
+        {
+            return false;
+        }
+    }
+    if let Some(content_format) = filter.content_format_o.as_ref() {
+        if content_metadata_o.map(|content_metadata| &content_metadata.content_format)
+            != Some(content_format)
+        {
+            return false;
+        }
+    }
+    if filter.created_at_begin_o.is_some() || filter.created_at_end_o.is_some() {
+        match plum_metadata_o.and_then(|plum_metadata| plum_metadata.plum_created_at_o) {
+            Some(plum_created_at) => is_within_range(
+                plum_created_at,
+                filter.created_at_begin_o,
+                filter.created_at_end_o,
+            ),
+            None => false,
+        }
+    } else {
+        true
+    }
+}
+
+#[async_trait::async_trait]
+impl DatahostStorage for DatahostStorageInMemory {
+    async fn begin_transaction(
+        &self,
+    ) -> Result<Box<dyn DatahostStorageTransaction>, DatahostStorageError> {
+        let committed = self
+            .committed_la
+            .read()
+            .expect("programmer error: DatahostStorageInMemory lock was poisoned");
+        Ok(Box::new(DatahostStorageInMemoryTransaction::new(
+            self.committed_la.clone(),
+            committed.generation,
+            committed.tables.clone(),
+        )))
+    }
+
+    async fn has_plum_head(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<bool, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_head_m
+            .contains_key(plum_head_seal))
+    }
+    async fn has_plum_metadata(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal: &PlumMetadataSeal,
+    ) -> Result<bool, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_metadata_m
+            .contains_key(plum_metadata_seal))
+    }
+    async fn has_plum_relations(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal: &PlumRelationsSeal,
+    ) -> Result<bool, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_relations_m
+            .contains_key(plum_relations_seal))
+    }
+    async fn has_plum_body(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<bool, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_body_m
+            .contains_key(plum_body_seal))
+    }
+
+    async fn select_plum_heads_page(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        filter: &PlumHeadsFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
+    ) -> Result<SelectPage<(UnixNanoseconds, PlumHeadSeal, PlumHead)>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        let tables = in_memory_transaction.tables();
+        let mut row_v = tables
+            .plum_head_m
+            .iter()
+            .filter(|(_, row)| {
+                plum_matches(tables, &row.plum_head, &filter.plum_filter)
+                    && is_within_range(
+                        row.row_inserted_at,
+                        filter.inserted_at_begin_o,
+                        filter.inserted_at_end_o,
+                    )
+                    && is_after_cursor(row.row_inserted_at, row.rowid, cursor_o)
+            })
+            .collect::<Vec<_>>();
+        row_v.sort_by_key(|(_, row)| (row.row_inserted_at.value, row.rowid));
+        row_v.truncate(limit as usize);
+        let next_cursor_o = if row_v.len() == limit as usize {
+            row_v.last().map(|(_, row)| SelectCursor {
+                row_at: row.row_inserted_at,
+                rowid: row.rowid,
+            })
+        } else {
+            None
+        };
+        Ok(SelectPage {
+            row_v: row_v
+                .into_iter()
+                .map(|(plum_head_seal, row)| {
+                    (
+                        row.row_inserted_at,
+                        plum_head_seal.clone(),
+                        row.plum_head.clone(),
+                    )
+                })
+                .collect(),
+            next_cursor_o,
+        })
+    }
+    async fn select_path_states_page(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        filter: &PathStatesFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
+    ) -> Result<SelectPage<(UnixNanoseconds, UnixNanoseconds, PathState)>, DatahostStorageError>
+    {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        let mut row_v = in_memory_transaction
+            .tables()
+            .path_state_m
+            .iter()
+            .filter(|(path, row)| {
+                is_within_range(
+                    row.row_updated_at,
+                    filter.updated_at_begin_o,
+                    filter.updated_at_end_o,
+                ) && has_prefix(path, filter.path_prefix_o.as_ref())
+                    && is_after_cursor(row.row_updated_at, row.rowid, cursor_o)
+            })
+            .collect::<Vec<_>>();
+        row_v.sort_by_key(|(_, row)| (row.row_updated_at.value, row.rowid));
+        row_v.truncate(limit as usize);
+        let next_cursor_o = if row_v.len() == limit as usize {
+            row_v.last().map(|(_, row)| SelectCursor {
+                row_at: row.row_updated_at,
+                rowid: row.rowid,
+            })
+        } else {
+            None
+        };
+        Ok(SelectPage {
+            row_v: row_v
+                .into_iter()
+                .map(|(path, row)| {
+                    (
+                        row.row_inserted_at,
+                        row.row_updated_at,
+                        PathState {
+                            path: Path::from(path.clone()),
+                            current_state_plum_head_seal: row.current_state_plum_head_seal.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            next_cursor_o,
+        })
+    }
+
+    async fn select_plum_relations_referring_to(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        target_plum_head_seal: &PlumHeadSeal,
+    ) -> Result<Vec<(PlumHeadSeal, PlumRelationFlags)>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        let tables = in_memory_transaction.tables();
+        // There's no index here, so this scans all the PlumRelations and PlumHeads.
+        let mut plum_relation_flags_m = HashMap::new();
+        for (plum_relations_seal, plum_relations) in tables.plum_relations_m.iter() {
+            for plum_relation_flags_mapping in plum_relations.plum_relation_flags_mapping_v.iter() {
+                if plum_relation_flags_mapping.target_plum_head_seal == *target_plum_head_seal {
+                    plum_relation_flags_m.insert(
+                        plum_relations_seal,
+                        PlumRelationFlags::try_from(
+                            plum_relation_flags_mapping.plum_relation_flags_raw,
+                        )
+                        .map_err(|e| {
+                            DatahostStorageError::InvalidValueInDB {
+                                table_name: "plum_relations",
+                                column_name: "plum_relation_flags",
+                                reason: e.to_string(),
+                            }
+                        })?,
+                    );
+                }
+            }
+        }
+        let mut row_v = tables
+            .plum_head_m
+            .iter()
+            .filter_map(|(plum_head_seal, row)| {
+                plum_relation_flags_m
+                    .get(&row.plum_head.plum_relations_seal)
+                    .map(|plum_relation_flags| (plum_head_seal, row, *plum_relation_flags))
+            })
+            .collect::<Vec<_>>();
+        row_v.sort_by_key(|(_, row, _)| (row.row_inserted_at.value, row.rowid));
+        Ok(row_v
+            .into_iter()
+            .map(|(plum_head_seal, _, plum_relation_flags)| {
+                (plum_head_seal.clone(), plum_relation_flags)
+            })
+            .collect())
+    }
+
+    async fn store_plum_head(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head: &PlumHead,
+    ) -> Result<PlumHeadSeal, DatahostStorageError> {
+        let plum_head_seal = PlumHeadSeal::from(plum_head);
+        log::debug!(
+            "store_plum_head; storing plum_head with seal: {}",
+            plum_head_seal
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        // Ignore collision.  The PlumHeadSeal being identical should guarantee that the row is identical
+        // except for the rowid and row_inserted_at.
+        if in_memory_transaction
+            .tables()
+            .plum_head_m
+            .contains_key(&plum_head_seal)
+        {
+            log::debug!(
+                "store_plum_head; already had plum_head with seal: {}",
+                plum_head_seal
+            );
+            return Ok(plum_head_seal);
+        }
+
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        let rowid = tables.next_rowid();
+        tables.plum_head_m.insert(
+            plum_head_seal.clone(),
+            PlumHeadRow {
+                rowid,
+                row_inserted_at: UnixNanoseconds::now(),
+                plum_head: plum_head.clone(),
+            },
+        );
+        written_keys.plum_head_seal_s.insert(plum_head_seal.clone());
+
+        Ok(plum_head_seal)
+    }
+    async fn store_plum_metadata(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata: &PlumMetadata,
+        plum_metadata_seal: &PlumMetadataSeal,
+    ) -> Result<PlumMetadataSeal, DatahostStorageError> {
+        plum_metadata_seal.verify(plum_metadata, None)?;
+        let plum_metadata_seal = plum_metadata_seal.clone();
+        log::debug!(
+            "store_plum_metadata; storing plum_metadata with seal: {}",
+            plum_metadata_seal
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        if in_memory_transaction
+            .tables()
+            .plum_metadata_m
+            .contains_key(&plum_metadata_seal)
+        {
+            log::debug!(
+                "store_plum_metadata; already had plum_metadata with seal: {}",
+                plum_metadata_seal
+            );
+            return Ok(plum_metadata_seal);
+        }
+
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        tables
+            .plum_metadata_m
+            .insert(plum_metadata_seal.clone(), plum_metadata.clone());
+        written_keys
+            .plum_metadata_seal_s
+            .insert(plum_metadata_seal.clone());
+
+        Ok(plum_metadata_seal)
+    }
+    async fn store_plum_relations(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations: &PlumRelations,
+        plum_relations_seal: &PlumRelationsSeal,
+    ) -> Result<PlumRelationsSeal, DatahostStorageError> {
+        plum_relations_seal.verify(plum_relations, None)?;
+        let plum_relations_seal = plum_relations_seal.clone();
+        log::debug!(
+            "store_plum_relations; storing plum_relations with seal: {}",
+            plum_relations_seal
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        if in_memory_transaction
+            .tables()
+            .plum_relations_m
+            .contains_key(&plum_relations_seal)
+        {
+            log::debug!(
+                "store_plum_relations; already had plum_relations with seal: {}",
+                plum_relations_seal
+            );
+            return Ok(plum_relations_seal);
+        }
+
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        tables
+            .plum_relations_m
+            .insert(plum_relations_seal.clone(), plum_relations.clone());
+        written_keys
+            .plum_relations_seal_s
+            .insert(plum_relations_seal.clone());
+
+        Ok(plum_relations_seal)
+    }
+    async fn store_plum_body(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body: &PlumBody,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<PlumBodySeal, DatahostStorageError> {
+        plum_body_seal.verify(plum_body, None)?;
+        let plum_body_seal = plum_body_seal.clone();
+        log::debug!(
+            "store_plum_body; storing plum_body with seal: {}",
+            plum_body_seal
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        if in_memory_transaction
+            .tables()
+            .plum_body_m
+            .contains_key(&plum_body_seal)
+        {
+            log::debug!(
+                "store_plum_body; already had plum_body with seal: {}",
+                plum_body_seal
+            );
+            return Ok(plum_body_seal);
+        }
+
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        tables
+            .plum_body_m
+            .insert(plum_body_seal.clone(), Arc::new(plum_body.clone()));
+        written_keys.plum_body_seal_s.insert(plum_body_seal.clone());
+
+        Ok(plum_body_seal)
+    }
+
+    async fn load_option_plum_head(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<Option<PlumHead>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_head_m
+            .get(plum_head_seal)
+            .map(|row| row.plum_head.clone()))
+    }
+    async fn load_option_plum_metadata(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal: &PlumMetadataSeal,
+    ) -> Result<Option<PlumMetadata>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_metadata_m
+            .get(plum_metadata_seal)
+            .cloned())
+    }
+    async fn load_option_plum_relations(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal: &PlumRelationsSeal,
+    ) -> Result<Option<PlumRelations>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_relations_m
+            .get(plum_relations_seal)
+            .cloned())
+    }
+    async fn load_option_plum_body(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<Option<PlumBody>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .plum_body_m
+            .get(plum_body_seal)
+            .map(|plum_body_a| plum_body_a.as_ref().clone()))
+    }
+
+    async fn delete_plum_head(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<(), DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        // Only write if there's actually something to delete, so that the transaction's commit
+        // doesn't have to check this row for conflicts.
+        if in_memory_transaction
+            .tables()
+            .plum_head_m
+            .contains_key(plum_head_seal)
+        {
+            let (tables, written_keys) = in_memory_transaction.tables_mut();
+            tables.plum_head_m.remove(plum_head_seal);
+            written_keys.plum_head_seal_s.insert(plum_head_seal.clone());
+        }
+        Ok(())
+    }
+    async fn delete_plum_metadata(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal: &PlumMetadataSeal,
+    ) -> Result<(), DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        // Only write if there's actually something to delete, so that the transaction's commit
+        // doesn't have to check this row for conflicts.
+        if in_memory_transaction
+            .tables()
+            .plum_metadata_m
+            .contains_key(plum_metadata_seal)
+        {
+            let (tables, written_keys) = in_memory_transaction.tables_mut();
+            tables.plum_metadata_m.remove(plum_metadata_seal);
+            written_keys
+                .plum_metadata_seal_s
+                .insert(plum_metadata_seal.clone());
+        }
+        Ok(())
+    }
+    async fn delete_plum_relations(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal: &PlumRelationsSeal,
+    ) -> Result<(), DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        // Only write if there's actually something to delete, so that the transaction's commit
+        // doesn't have to check this row for conflicts.
+        if in_memory_transaction
+            .tables()
+            .plum_relations_m
+            .contains_key(plum_relations_seal)
+        {
+            let (tables, written_keys) = in_memory_transaction.tables_mut();
+            tables.plum_relations_m.remove(plum_relations_seal);
+            written_keys
+                .plum_relations_seal_s
+                .insert(plum_relations_seal.clone());
+        }
+        Ok(())
+    }
+    async fn delete_plum_body(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<(), DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        // Only write if there's actually something to delete, so that the transaction's commit
+        // doesn't have to check this row for conflicts.
+        if in_memory_transaction
+            .tables()
+            .plum_body_m
+            .contains_key(plum_body_seal)
+        {
+            let (tables, written_keys) = in_memory_transaction.tables_mut();
+            tables.plum_body_m.remove(plum_body_seal);
+            written_keys.plum_body_seal_s.insert(plum_body_seal.clone());
+        }
+        Ok(())
+    }
+
+    async fn has_path_state(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        path: &Path,
+    ) -> Result<bool, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .path_state_m
+            .contains_key(&path.value))
+    }
+    async fn load_option_path_state(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        path: &Path,
+    ) -> Result<Option<PathState>, DatahostStorageError> {
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        Ok(in_memory_transaction
+            .tables()
+            .path_state_m
+            .get(&path.value)
+            .map(|row| PathState {
+                path: path.clone(),
+                current_state_plum_head_seal: row.current_state_plum_head_seal.clone(),
+            }))
+    }
+    async fn insert_path_state(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        path_state: &PathState,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageInMemory::insert_path_state; path_state: {:?}",
+            path_state,
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        if in_memory_transaction
+            .tables()
+            .path_state_m
+            .contains_key(&path_state.path.value)
+        {
+            return Err(DatahostStorageError::PathAlreadyExists(
+                path_state.path.clone(),
+            ));
+        }
+
+        let now = UnixNanoseconds::now();
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        let rowid = tables.next_rowid();
+        tables.path_state_m.insert(
+            path_state.path.value.clone(),
+            PathStateRow {
+                rowid,
+                row_inserted_at: now,
+                row_updated_at: now,
+                current_state_plum_head_seal: path_state.current_state_plum_head_seal.clone(),
+            },
+        );
+        written_keys.path_s.insert(path_state.path.value.clone());
+
+        Ok(())
+    }
+    async fn update_path_state(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        path_state: &PathState,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageInMemory::update_path_state; path_state: {:?}",
+            path_state,
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        if !in_memory_transaction
+            .tables()
+            .path_state_m
+            .contains_key(&path_state.path.value)
+        {
+            return Err(DatahostStorageError::PathNotFound(path_state.path.clone()));
+        }
+
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        let rowid = tables.next_rowid();
+        let row = tables.path_state_m.get_mut(&path_state.path.value).unwrap();
+        // The rowid is updated along with row_updated_at, so that select_path_states orders the updates
+        // of rows that have the same row_updated_at in the order that they happened.
+        row.rowid = rowid;
+        row.row_updated_at = UnixNanoseconds::now();
+        row.current_state_plum_head_seal = path_state.current_state_plum_head_seal.clone();
+        written_keys.path_s.insert(path_state.path.value.clone());
+
+        Ok(())
+    }
+    async fn delete_path_state(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        path: &Path,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageInMemory::delete_path_state; path: {:?}",
+            path
+        );
+
+        let in_memory_transaction = in_memory_transaction_mut(transaction);
+        if !in_memory_transaction
+            .tables()
+            .path_state_m
+            .contains_key(&path.value)
+        {
+            return Err(DatahostStorageError::PathNotFound(path.clone()));
+        }
+
+        let (tables, written_keys) = in_memory_transaction.tables_mut();
+        tables.path_state_m.remove(&path.value);
+        written_keys.path_s.insert(path.value.clone());
+
+        Ok(())
+    }
+}
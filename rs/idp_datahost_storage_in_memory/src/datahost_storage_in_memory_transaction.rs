@@ -0,0 +1,93 @@
+use crate::{Committed, Tables, WrittenKeys};
+use idp_datahost_storage::{DatahostStorageError, DatahostStorageTransaction, OnCommitCallbacks};
+use std::sync::{Arc, RwLock};
+
+/// A transaction on DatahostStorageInMemory.  This holds a snapshot of the committed tables as of
+/// when the transaction began, to which its writes are made, along with the keys of the rows that it
+/// wrote, which are merged into the committed tables upon commit.  Dropping this without committing
+/// discards the writes, i.e. rolls back the transaction.
+pub struct DatahostStorageInMemoryTransaction {
+    committed_la: Arc<RwLock<Committed>>,
+    /// The generation of the committed state when this transaction began.
+    base_generation: u64,
+    /// The committed tables when this transaction began.
+    base_tables: Tables,
+    tables: Tables,
+    written_keys: WrittenKeys,
+    on_commit_callbacks: OnCommitCallbacks,
+}
+
+impl DatahostStorageInMemoryTransaction {
+    pub(crate) fn new(
+        committed_la: Arc<RwLock<Committed>>,
+        base_generation: u64,
+        tables: Tables,
+    ) -> Self {
+        Self {
+            committed_la,
+            base_generation,
+            base_tables: tables.clone(),
+            tables,
+            written_keys: WrittenKeys::default(),
+            on_commit_callbacks: OnCommitCallbacks::default(),
+        }
+    }
+    pub(crate) fn tables(&self) -> &Tables {
+        &self.tables
+    }
+    /// Any write to the tables must go through this method, and must record the key of each row
+    /// that it writes, so that the write is merged into the committed tables upon commit.
+    pub(crate) fn tables_mut(&mut self) -> (&mut Tables, &mut WrittenKeys) {
+        (&mut self.tables, &mut self.written_keys)
+    }
+}
+
+#[async_trait::async_trait]
+impl DatahostStorageTransaction for DatahostStorageInMemoryTransaction {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self as &mut dyn std::any::Any
+    }
+    fn on_commit(&mut self, callback: Box<dyn FnOnce() + Send>) {
+        self.on_commit_callbacks.push(callback);
+    }
+    async fn commit(self: Box<Self>) -> Result<(), DatahostStorageError> {
+        let DatahostStorageInMemoryTransaction {
+            committed_la,
+            base_generation,
+            base_tables,
+            tables,
+            written_keys,
+            on_commit_callbacks,
+        } = *self;
+        // A read-only transaction has nothing to commit.
+        if !written_keys.is_empty() {
+            let mut committed = committed_la
+                .write()
+                .expect("programmer error: DatahostStorageInMemory lock was poisoned");
+            if committed.generation == base_generation {
+                // No other transaction committed writes since this one began, so this transaction's
+                // tables are exactly the committed tables plus its writes.
+                committed.tables = tables;
+            } else {
+                // Otherwise committing this transaction's tables would clobber those writes, so its
+                // writes are merged row by row.  This is cheap, since cloning Tables is O(1).
+                let mut merged_tables = committed.tables.clone();
+                merged_tables.merge_writes(&base_tables, &tables, &written_keys)?;
+                committed.tables = merged_tables;
+            }
+            committed.generation += 1;
+        }
+        on_commit_callbacks.run();
+        Ok(())
+    }
+    async fn rollback(self: Box<Self>) -> Result<(), DatahostStorageError> {
+        // Nothing needs to be done; the snapshot is simply dropped.
+        Ok(())
+    }
+}
+
+pub fn in_memory_transaction_mut(
+    transaction: &mut dyn DatahostStorageTransaction,
+) -> &mut DatahostStorageInMemoryTransaction {
+    idp_datahost_storage::downcast_transaction_mut(transaction)
+}
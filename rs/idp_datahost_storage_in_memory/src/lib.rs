@@ -0,0 +1,8 @@
+mod datahost_storage_in_memory;
+mod datahost_storage_in_memory_transaction;
+
+pub use datahost_storage_in_memory::DatahostStorageInMemory;
+pub use datahost_storage_in_memory_transaction::DatahostStorageInMemoryTransaction;
+
+pub(crate) use datahost_storage_in_memory::{Committed, Tables, WrittenKeys};
+pub(crate) use datahost_storage_in_memory_transaction::in_memory_transaction_mut;
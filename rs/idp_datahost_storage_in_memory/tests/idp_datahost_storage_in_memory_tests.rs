@@ -0,0 +1,594 @@
+use futures::{executor::block_on, TryStreamExt};
+use idp_datahost_storage::{
+    DatahostStorage, DatahostStorageError, PathStatesFilter, PlumFilter, PlumHeadsFilter,
+};
+use idp_datahost_storage_in_memory::DatahostStorageInMemory;
+use idp_proto::{
+    ContentClass, ContentEncoding, ContentFormat, Path, PathState, Plum, PlumBuilder, PlumHead,
+    PlumHeadSeal,
+};
+use uuid::Uuid;
+
+/// This will run once at load time (i.e. presumably before main function is called).
+#[ctor::ctor]
+fn overall_init() {
+    env_logger::init();
+}
+
+fn test_plum(name: &str) -> Plum {
+    PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("{}, {}.", name, Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass")
+}
+
+#[test]
+fn test_datahost_storage_in_memory_store_and_load() {
+    block_on(async {
+        let datahost_storage = DatahostStorageInMemory::new();
+        let plum = test_plum("test_datahost_storage_in_memory_store_and_load");
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        let plum_head_seal = datahost_storage
+            .store_plum_head(transaction.as_mut(), &plum.plum_head)
+            .await
+            .expect("pass");
+        let plum_metadata_seal = datahost_storage
+            .store_plum_metadata(
+                transaction.as_mut(),
+                &plum.plum_metadata,
+                &plum.plum_head.plum_metadata_seal,
+            )
+            .await
+            .expect("pass");
+        let plum_relations_seal = datahost_storage
+            .store_plum_relations(
+                transaction.as_mut(),
+                &plum.plum_relations,
+                &plum.plum_head.plum_relations_seal,
+            )
+            .await
+            .expect("pass");
+        let plum_body_seal = datahost_storage
+            .store_plum_body(
+                transaction.as_mut(),
+                &plum.plum_body,
+                &plum.plum_head.plum_body_seal,
+            )
+            .await
+            .expect("pass");
+        // Storing again should be a no-op.
+        assert_eq!(
+            datahost_storage
+                .store_plum_head(transaction.as_mut(), &plum.plum_head)
+                .await
+                .expect("pass"),
+            plum_head_seal
+        );
+        transaction.commit().await.expect("pass");
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        assert!(datahost_storage
+            .has_plum_head(transaction.as_mut(), &plum_head_seal)
+            .await
+            .expect("pass"));
+        assert_eq!(
+            datahost_storage
+                .load_plum_head(transaction.as_mut(), &plum_head_seal)
+                .await
+                .expect("pass"),
+            plum.plum_head
+        );
+        assert_eq!(
+            datahost_storage
+                .load_plum_metadata(transaction.as_mut(), &plum_metadata_seal)
+                .await
+                .expect("pass"),
+            plum.plum_metadata
+        );
+        assert_eq!(
+            datahost_storage
+                .load_plum_relations(transaction.as_mut(), &plum_relations_seal)
+                .await
+                .expect("pass"),
+            plum.plum_relations
+        );
+        assert_eq!(
+            datahost_storage
+                .load_plum_body(transaction.as_mut(), &plum_body_seal)
+                .await
+                .expect("pass"),
+            plum.plum_body
+        );
+        let plum_head_v = datahost_storage
+            .select_plum_heads(transaction.as_mut(), PlumHeadsFilter::default(), 16)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass");
+        assert_eq!(plum_head_v.len(), 1);
+        assert_eq!(plum_head_v[0].1, plum_head_seal);
+        transaction.commit().await.expect("pass");
+    });
+}
+
+#[test]
+fn test_datahost_storage_in_memory_rollback() {
+    block_on(async {
+        let datahost_storage = DatahostStorageInMemory::new();
+        let plum = test_plum("test_datahost_storage_in_memory_rollback");
+        let plum_head_seal = PlumHeadSeal::from(&plum.plum_head);
+
+        // Dropping the transaction without committing should roll it back.
+        {
+            let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+            datahost_storage
+                .store_plum_head(transaction.as_mut(), &plum.plum_head)
+                .await
+                .expect("pass");
+            // The transaction sees its own writes.
+            assert!(datahost_storage
+                .has_plum_head(transaction.as_mut(), &plum_head_seal)
+                .await
+                .expect("pass"));
+        }
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        assert!(!datahost_storage
+            .has_plum_head(transaction.as_mut(), &plum_head_seal)
+            .await
+            .expect("pass"));
+        transaction.commit().await.expect("pass");
+
+        // Same for an explicit rollback.
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        datahost_storage
+            .store_plum_head(transaction.as_mut(), &plum.plum_head)
+            .await
+            .expect("pass");
+        transaction.rollback().await.expect("pass");
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        assert!(!datahost_storage
+            .has_plum_head(transaction.as_mut(), &plum_head_seal)
+            .await
+            .expect("pass"));
+        transaction.commit().await.expect("pass");
+    });
+}
+
+#[test]
+fn test_datahost_storage_in_memory_transaction_conflict() {
+    block_on(async {
+        let datahost_storage = DatahostStorageInMemory::new();
+        let plum_0 = test_plum("test_datahost_storage_in_memory_transaction_conflict 0");
+        let plum_1 = test_plum("test_datahost_storage_in_memory_transaction_conflict 1");
+        let plum_head_seal_0 = PlumHeadSeal::from(&plum_0.plum_head);
+        let plum_head_seal_1 = PlumHeadSeal::from(&plum_1.plum_head);
+
+        let mut transaction_0 = datahost_storage.begin_transaction().await.expect("pass");
+        let mut transaction_1 = datahost_storage.begin_transaction().await.expect("pass");
+        let mut read_only_transaction = datahost_storage.begin_transaction().await.expect("pass");
+
+        datahost_storage
+            .store_plum_head(transaction_0.as_mut(), &plum_0.plum_head)
+            .await
+            .expect("pass");
+        // Uncommitted writes aren't visible to other transactions.
+        assert!(!datahost_storage
+            .has_plum_head(transaction_1.as_mut(), &plum_head_seal_0)
+            .await
+            .expect("pass"));
+        transaction_0.commit().await.expect("pass");
+
+        // Committed writes aren't visible to transactions that began before the commit either.
+        assert!(!datahost_storage
+            .has_plum_head(read_only_transaction.as_mut(), &plum_head_seal_0)
+            .await
+            .expect("pass"));
+        // A read-only transaction can always commit.
+        read_only_transaction.commit().await.expect("pass");
+
+        // Writes to rows that weren't changed since the transaction began don't conflict, and neither
+        // do writes having the same effect as those that were committed since then.
+        datahost_storage
+            .store_plum_head(transaction_1.as_mut(), &plum_0.plum_head)
+            .await
+            .expect("pass");
+        datahost_storage
+            .store_plum_head(transaction_1.as_mut(), &plum_1.plum_head)
+            .await
+            .expect("pass");
+        transaction_1.commit().await.expect("pass");
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        assert!(datahost_storage
+            .has_plum_head(transaction.as_mut(), &plum_head_seal_0)
+            .await
+            .expect("pass"));
+        assert!(datahost_storage
+            .has_plum_head(transaction.as_mut(), &plum_head_seal_1)
+            .await
+            .expect("pass"));
+        let path = Path::from("test_datahost_storage_in_memory_transaction_conflict".to_string());
+        datahost_storage
+            .insert_path_state(
+                transaction.as_mut(),
+                &PathState {
+                    path: path.clone(),
+                    current_state_plum_head_seal: plum_head_seal_0.clone(),
+                },
+            )
+            .await
+            .expect("pass");
+        transaction.commit().await.expect("pass");
+
+        // Different writes to the same row do conflict, and the first one committed wins.
+        let mut transaction_0 = datahost_storage.begin_transaction().await.expect("pass");
+        let mut transaction_1 = datahost_storage.begin_transaction().await.expect("pass");
+        datahost_storage
+            .update_path_state(
+                transaction_0.as_mut(),
+                &PathState {
+                    path: path.clone(),
+                    current_state_plum_head_seal: plum_head_seal_1.clone(),
+                },
+            )
+            .await
+            .expect("pass");
+        datahost_storage
+            .delete_path_state(transaction_1.as_mut(), &path)
+            .await
+            .expect("pass");
+        transaction_0.commit().await.expect("pass");
+        match transaction_1.commit().await {
+            Err(DatahostStorageError::TransactionConflict) => {}
+            _ => panic!("expected DatahostStorageError::TransactionConflict"),
+        }
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        let path_state = datahost_storage
+            .load_option_path_state(transaction.as_mut(), &path)
+            .await
+            .expect("pass")
+            .expect("pass");
+        assert_eq!(path_state.current_state_plum_head_seal, plum_head_seal_1);
+        transaction.commit().await.expect("pass");
+    });
+}
+
+#[test]
+fn test_datahost_storage_in_memory_path_state() {
+    block_on(async {
+        let datahost_storage = DatahostStorageInMemory::new();
+        let plum_0 = test_plum("test_datahost_storage_in_memory_path_state 0");
+        let plum_1 = test_plum("test_datahost_storage_in_memory_path_state 1");
+        let path = Path::from(format!(
+            "test_datahost_storage_in_memory_path_state_{}",
+            Uuid::new_v4()
+        ));
+        let path_state_0 = PathState {
+            path: path.clone(),
+            current_state_plum_head_seal: PlumHeadSeal::from(&plum_0.plum_head),
+        };
+        let path_state_1 = PathState {
+            path: path.clone(),
+            current_state_plum_head_seal: PlumHeadSeal::from(&plum_1.plum_head),
+        };
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        match datahost_storage
+            .update_path_state(transaction.as_mut(), &path_state_0)
+            .await
+        {
+            Err(DatahostStorageError::PathNotFound(_)) => {}
+            _ => panic!("expected DatahostStorageError::PathNotFound"),
+        }
+        datahost_storage
+            .insert_path_state(transaction.as_mut(), &path_state_0)
+            .await
+            .expect("pass");
+        match datahost_storage
+            .insert_path_state(transaction.as_mut(), &path_state_1)
+            .await
+        {
+            Err(DatahostStorageError::PathAlreadyExists(_)) => {}
+            _ => panic!("expected DatahostStorageError::PathAlreadyExists"),
+        }
+        datahost_storage
+            .update_path_state(transaction.as_mut(), &path_state_1)
+            .await
+            .expect("pass");
+        assert_eq!(
+            datahost_storage
+                .load_path_state(transaction.as_mut(), &path)
+                .await
+                .expect("pass"),
+            path_state_1
+        );
+        transaction.commit().await.expect("pass");
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        let path_state_v = datahost_storage
+            .select_path_states(transaction.as_mut(), PathStatesFilter::default(), 16)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass");
+        assert_eq!(path_state_v.len(), 1);
+        assert_eq!(path_state_v[0].2, path_state_1);
+        datahost_storage
+            .delete_path_state(transaction.as_mut(), &path)
+            .await
+            .expect("pass");
+        assert!(!datahost_storage
+            .has_path_state(transaction.as_mut(), &path)
+            .await
+            .expect("pass"));
+        match datahost_storage
+            .delete_path_state(transaction.as_mut(), &path)
+            .await
+        {
+            Err(DatahostStorageError::PathNotFound(_)) => {}
+            _ => panic!("expected DatahostStorageError::PathNotFound"),
+        }
+        transaction.commit().await.expect("pass");
+    });
+}
+
+#[test]
+fn test_datahost_storage_in_memory_select_plum_heads_pagination() {
+    block_on(async {
+        let datahost_storage = DatahostStorageInMemory::new();
+        let plum_v = (0..7)
+            .map(|i| {
+                test_plum(&format!(
+                    "test_datahost_storage_in_memory_select_plum_heads_pagination {}",
+                    i
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+        for plum in plum_v.iter() {
+            datahost_storage
+                .store_plum(transaction.as_mut(), plum)
+                .await
+                .expect("pass");
+        }
+
+        // Walk the pages by hand.  Each full page has a cursor, even if the next page is empty.
+        let mut plum_head_seal_v = Vec::new();
+        let mut cursor_o = None;
+        let mut page_count = 0;
+        loop {
+            let select_page = datahost_storage
+                .select_plum_heads_page(
+                    transaction.as_mut(),
+                    &PlumHeadsFilter::default(),
+                    cursor_o.as_ref(),
+                    3,
+                )
+                .await
+                .expect("pass");
+            page_count += 1;
+            assert!(select_page.row_v.len() <= 3);
+            plum_head_seal_v.extend(
+                select_page
+                    .row_v
+                    .into_iter()
+                    .map(|(_, plum_head_seal, _)| plum_head_seal),
+            );
+            if select_page.next_cursor_o.is_none() {
+                break;
+            }
+            cursor_o = select_page.next_cursor_o;
+        }
+        assert_eq!(page_count, 3);
+        // The PlumHeads come back in insertion order.
+        assert_eq!(
+            plum_head_seal_v,
+            plum_v
+                .iter()
+                .map(|plum| PlumHeadSeal::from(&plum.plum_head))
+                .collect::<Vec<_>>()
+        );
+
+        // The stream should produce the same thing, regardless of page size.
+        for page_size in [1, 3, 7, 100] {
+            assert_eq!(
+                datahost_storage
+                    .select_plum_heads(transaction.as_mut(), PlumHeadsFilter::default(), page_size)
+                    .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .expect("pass"),
+                plum_head_seal_v
+            );
+        }
+
+        // Filter by inserted-at range.
+        let row_v = datahost_storage
+            .select_plum_heads(transaction.as_mut(), PlumHeadsFilter::default(), 16)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass");
+        let inserted_at_begin = row_v[2].0;
+        let inserted_at_end = row_v[5].0;
+        let filtered_row_v = datahost_storage
+            .select_plum_heads(
+                transaction.as_mut(),
+                PlumHeadsFilter {
+                    inserted_at_begin_o: Some(inserted_at_begin),
+                    inserted_at_end_o: Some(inserted_at_end),
+                    ..Default::default()
+                },
+                2,
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass");
+        assert_eq!(
+            filtered_row_v,
+            row_v
+                .iter()
+                .filter(
+                    |(row_inserted_at, _, _)| row_inserted_at.value >= inserted_at_begin.value
+                        && row_inserted_at.value < inserted_at_end.value
+                )
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+
+        // Filter by content class.  All the test Plums are text/plain.
+        assert_eq!(
+            datahost_storage
+                .select_plum_heads(
+                    transaction.as_mut(),
+                    PlumHeadsFilter {
+                        plum_filter: PlumFilter {
+                            content_class_o: Some(ContentClass::text_plain()),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    4,
+                )
+                .try_collect::<Vec<_>>()
+                .await
+                .expect("pass")
+                .len(),
+            plum_v.len()
+        );
+        assert!(datahost_storage
+            .select_plum_heads(
+                transaction.as_mut(),
+                PlumHeadsFilter {
+                    plum_filter: PlumFilter {
+                        content_class_o: Some(ContentClass::from(
+                            "application/x.nonexistent".to_string()
+                        )),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                4,
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass")
+            .is_empty());
+
+        transaction.commit().await.expect("pass");
+    });
+}
+
+#[test]
+fn test_datahost_storage_in_memory_plum_filter_prefers_plum_metadata() {
+    block_on(async {
+        let datahost_storage = DatahostStorageInMemory::new();
+        let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+
+        // A Plum can't be built with PlumMetadata and PlumBody that disagree on the content
+        // metadata, so store the components separately: text/plain PlumMetadata with an
+        // application/octet-stream PlumBody.
+        let text_plum =
+            test_plum("test_datahost_storage_in_memory_plum_filter_prefers_plum_metadata");
+        let binary_plum = PlumBuilder::new()
+            .with_plum_relations_and_plum_body_content_from(
+                &Uuid::new_v4().as_bytes().to_vec(),
+                None,
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass");
+        let plum_head = PlumHead {
+            plum_head_nonce_o: None,
+            plum_metadata_seal: text_plum.plum_head.plum_metadata_seal.clone(),
+            plum_relations_seal: binary_plum.plum_head.plum_relations_seal.clone(),
+            plum_body_seal: binary_plum.plum_head.plum_body_seal.clone(),
+        };
+        datahost_storage
+            .store_plum_metadata(
+                transaction.as_mut(),
+                &text_plum.plum_metadata,
+                &plum_head.plum_metadata_seal,
+            )
+            .await
+            .expect("pass");
+        datahost_storage
+            .store_plum_relations(
+                transaction.as_mut(),
+                &binary_plum.plum_relations,
+                &plum_head.plum_relations_seal,
+            )
+            .await
+            .expect("pass");
+        datahost_storage
+            .store_plum_body(
+                transaction.as_mut(),
+                &binary_plum.plum_body,
+                &plum_head.plum_body_seal,
+            )
+            .await
+            .expect("pass");
+        let plum_head_seal = datahost_storage
+            .store_plum_head(transaction.as_mut(), &plum_head)
+            .await
+            .expect("pass");
+
+        // The PlumMetadata determines the content class and format; the PlumBody is ignored.
+        for (content_class, should_match) in [
+            (ContentClass::text_plain(), true),
+            (
+                ContentClass::from("application/octet-stream".to_string()),
+                false,
+            ),
+        ] {
+            let plum_filter = PlumFilter {
+                content_class_o: Some(content_class),
+                ..Default::default()
+            };
+            assert_eq!(
+                datahost_storage
+                    .select_plum_head_seals_matching(transaction.as_mut(), &plum_filter)
+                    .await
+                    .expect("pass")
+                    .contains(&plum_head_seal),
+                should_match
+            );
+            assert_eq!(
+                datahost_storage
+                    .select_plum_heads(
+                        transaction.as_mut(),
+                        PlumHeadsFilter {
+                            plum_filter,
+                            ..Default::default()
+                        },
+                        16,
+                    )
+                    .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .expect("pass")
+                    .contains(&plum_head_seal),
+                should_match
+            );
+        }
+        assert!(datahost_storage
+            .select_plum_head_seals_matching(
+                transaction.as_mut(),
+                &PlumFilter {
+                    content_format_o: Some(ContentFormat::charset_us_ascii()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("pass")
+            .contains(&plum_head_seal));
+
+        transaction.commit().await.expect("pass");
+    });
+}
@@ -0,0 +1,12 @@
+-- Tear everything down in reverse order.
+
+DROP TABLE IF EXISTS path_states;
+
+DROP TABLE IF EXISTS plum_bodies;
+DROP TABLE IF EXISTS plum_relation_mappings;
+DROP TABLE IF EXISTS plum_relations;
+DROP TABLE IF EXISTS plum_metadatas;
+DROP INDEX IF EXISTS plum_head_body_references;
+DROP INDEX IF EXISTS plum_head_relations_references;
+DROP INDEX IF EXISTS plum_head_metadata_references;
+DROP TABLE IF EXISTS plum_heads;
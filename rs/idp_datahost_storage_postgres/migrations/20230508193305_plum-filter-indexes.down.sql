@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS plum_body_content_formats;
+DROP INDEX IF EXISTS plum_body_content_classes;
+DROP INDEX IF EXISTS plum_metadata_created_ats;
+DROP INDEX IF EXISTS plum_metadata_content_formats;
+DROP INDEX IF EXISTS plum_metadata_content_classes;
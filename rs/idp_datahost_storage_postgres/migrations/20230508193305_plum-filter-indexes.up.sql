@@ -0,0 +1,8 @@
+-- These indexes are used so that selecting Plums by content class, content format, and creation
+-- time (see DatahostStorage::select_plum_head_seals_matching) is efficient.  Content class and format
+-- are indexed in both plum_metadatas and plum_bodies, since PlumMetadata may omit them.
+CREATE INDEX plum_metadata_content_classes ON plum_metadatas(plum_body_content_class_o);
+CREATE INDEX plum_metadata_content_formats ON plum_metadatas(plum_body_content_format_o);
+CREATE INDEX plum_metadata_created_ats ON plum_metadatas(plum_created_at_o);
+CREATE INDEX plum_body_content_classes ON plum_bodies(plum_body_content_class);
+CREATE INDEX plum_body_content_formats ON plum_bodies(plum_body_content_format);
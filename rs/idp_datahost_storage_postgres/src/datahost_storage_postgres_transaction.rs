@@ -0,0 +1,165 @@
+use futures::{future::BoxFuture, stream::BoxStream};
+use idp_datahost_storage::{DatahostStorageError, DatahostStorageTransaction, OnCommitCallbacks};
+
+#[derive(Debug, derive_more::Deref, derive_more::DerefMut)]
+pub struct DatahostStoragePostgresTransaction {
+    #[deref]
+    #[deref_mut]
+    transaction: sqlx::Transaction<'static, sqlx::Postgres>,
+    on_commit_callbacks: OnCommitCallbacks,
+}
+
+impl From<sqlx::Transaction<'static, sqlx::Postgres>> for DatahostStoragePostgresTransaction {
+    fn from(transaction: sqlx::Transaction<'static, sqlx::Postgres>) -> Self {
+        Self {
+            transaction,
+            on_commit_callbacks: OnCommitCallbacks::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DatahostStorageTransaction for DatahostStoragePostgresTransaction {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self as &mut dyn std::any::Any
+    }
+    fn on_commit(&mut self, callback: Box<dyn FnOnce() + Send>) {
+        self.on_commit_callbacks.push(callback);
+    }
+    async fn commit(self: Box<Self>) -> Result<(), DatahostStorageError> {
+        let DatahostStoragePostgresTransaction {
+            transaction,
+            on_commit_callbacks,
+        } = *self;
+        transaction.commit().await?;
+        on_commit_callbacks.run();
+        Ok(())
+    }
+    async fn rollback(self: Box<Self>) -> Result<(), DatahostStorageError> {
+        Ok((*self).transaction.rollback().await?)
+    }
+}
+
+pub fn postgres_transaction_mut<'a>(
+    transaction: &'a mut dyn DatahostStorageTransaction,
+) -> &'a mut DatahostStoragePostgresTransaction {
+    idp_datahost_storage::downcast_transaction_mut(transaction)
+}
+
+impl<'c> sqlx::Executor<'c> for &'c mut DatahostStoragePostgresTransaction {
+    type Database = sqlx::Postgres;
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.transaction.describe(sql)
+    }
+    fn execute<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>>
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.execute(query)
+    }
+    fn execute_many<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<<Self::Database as sqlx::Database>::QueryResult, sqlx::Error>>
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.execute_many(query)
+    }
+    fn fetch<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.fetch(query)
+    }
+    fn fetch_all<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Vec<<Self::Database as sqlx::Database>::Row>, sqlx::Error>>
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.fetch_all(query)
+    }
+    fn fetch_many<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxStream<
+        'e,
+        Result<
+            sqlx::Either<
+                <Self::Database as sqlx::Database>::QueryResult,
+                <Self::Database as sqlx::Database>::Row,
+            >,
+            sqlx::Error,
+        >,
+    >
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.fetch_many(query)
+    }
+    fn fetch_one<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<<Self::Database as sqlx::Database>::Row, sqlx::Error>>
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.fetch_one(query)
+    }
+    fn fetch_optional<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>>
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        self.transaction.fetch_optional(query)
+    }
+    fn prepare<'e, 'q: 'e>(
+        self,
+        query: &'q str,
+    ) -> BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::database::HasStatement<'q>>::Statement, sqlx::Error>,
+    >
+    where
+        'c: 'e,
+    {
+        self.transaction.prepare(query)
+    }
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
+    ) -> BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::database::HasStatement<'q>>::Statement, sqlx::Error>,
+    >
+    where
+        'c: 'e,
+    {
+        self.transaction.prepare_with(sql, parameters)
+    }
+}
@@ -0,0 +1,8 @@
+mod datahost_storage_postgres;
+mod datahost_storage_postgres_transaction;
+
+pub use datahost_storage_postgres::DatahostStoragePostgres;
+
+pub(crate) use datahost_storage_postgres_transaction::{
+    postgres_transaction_mut, DatahostStoragePostgresTransaction,
+};
@@ -0,0 +1,43 @@
+-- Make the PlumBody content bytes column non-nullable again.  Note that this will fail if any PlumBody
+-- content is stored in the plum body content dir, since that content can't be moved back into the DB here.
+
+CREATE TABLE plum_bodies_old (
+    -- Primary key
+    plum_bodies_rowid INTEGER NOT NULL PRIMARY KEY,
+
+    -- Other DB-oriented attributes.
+    row_inserted_at BIGINT,
+
+    -- This is the "real key" for a PlumBody, i.e. its globally unique ID.
+    plum_body_seal BLOB NOT NULL,
+
+    -- PlumBody attributes
+    plum_body_nonce_o BLOB,
+    plum_body_content_length BIGINT NOT NULL,
+    plum_body_content_class TEXT NOT NULL,
+    plum_body_content_format TEXT NOT NULL,
+    plum_body_content_encoding TEXT NOT NULL,
+    plum_body_content_byte_v BLOB NOT NULL,
+
+    UNIQUE(plum_body_seal)
+);
+
+INSERT INTO plum_bodies_old
+SELECT
+    plum_bodies_rowid,
+    row_inserted_at,
+    plum_body_seal,
+    plum_body_nonce_o,
+    plum_body_content_length,
+    plum_body_content_class,
+    plum_body_content_format,
+    plum_body_content_encoding,
+    plum_body_content_byte_vo
+FROM plum_bodies;
+
+DROP INDEX IF EXISTS plum_body_seals;
+DROP TABLE plum_bodies;
+ALTER TABLE plum_bodies_old RENAME TO plum_bodies;
+
+-- This index is used so that plum_body_seal lookups are fast.
+CREATE INDEX plum_body_seals ON plum_bodies(plum_body_seal);
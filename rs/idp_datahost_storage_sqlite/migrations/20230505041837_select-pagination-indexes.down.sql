@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS path_state_row_updated_at_pagination;
+DROP INDEX IF EXISTS plum_head_row_inserted_at_pagination;
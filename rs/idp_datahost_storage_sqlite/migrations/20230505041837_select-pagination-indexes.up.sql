@@ -0,0 +1,4 @@
+-- These indexes are used so that paginated selects (which are ordered by row timestamp, with ties
+-- broken by rowid) are efficient.
+CREATE INDEX plum_head_row_inserted_at_pagination ON plum_heads(row_inserted_at, plum_heads_rowid);
+CREATE INDEX path_state_row_updated_at_pagination ON path_states(row_updated_at, path_states_rowid);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS plum_relation_mapping_targets;
@@ -0,0 +1,3 @@
+-- This index is used so that selecting the PlumRelations that refer to a given Plum (i.e. following
+-- relations in reverse; see DatahostStorage::select_plum_relations_referring_to) is efficient.
+CREATE INDEX plum_relation_mapping_targets ON plum_relation_mappings(target_plum_head_seal);
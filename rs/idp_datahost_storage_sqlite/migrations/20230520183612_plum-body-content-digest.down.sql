@@ -0,0 +1 @@
+ALTER TABLE plum_bodies DROP COLUMN plum_body_content_digest_o;
@@ -0,0 +1,5 @@
+-- An unkeyed digest (i.e. the bytes of an unkeyed Seal) of the PlumBody content bytes, which is set if
+-- and only if the content bytes are stored in the plum body content dir.  This is used to check the
+-- integrity of the content file upon load, which the PlumBodySeal can't be used for if it's keyed,
+-- since the SealKey isn't available to DatahostStorageSQLite.
+ALTER TABLE plum_bodies ADD COLUMN plum_body_content_digest_o BLOB;
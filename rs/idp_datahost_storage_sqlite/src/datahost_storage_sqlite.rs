@@ -1,14 +1,133 @@
-use crate::{sqlite_transaction_mut, DatahostStorageSQLiteTransaction};
-use idp_datahost_storage::{DatahostStorage, DatahostStorageError, DatahostStorageTransaction};
+use crate::{sqlite_transaction_mut, DatahostStorageSQLiteTransaction, PlumBodyContentDir};
+use idp_datahost_storage::{
+    seal_from_db, DatahostStorage, DatahostStorageError, DatahostStorageTransaction,
+    PathStatesFilter, PlumHeadsFilter, SelectCursor, SelectPage,
+};
 use idp_proto::{
     Content, ContentClass, ContentEncoding, ContentFormat, ContentMetadata, Nonce, Path, PathState,
-    PlumBody, PlumBodySeal, PlumHead, PlumHeadSeal, PlumMetadata, PlumMetadataSeal,
-    PlumRelationFlags, PlumRelationFlagsMapping, PlumRelations, PlumRelationsSeal, Seal, Sha256Sum,
-    UnixNanoseconds,
+    Plum, PlumBody, PlumBodySeal, PlumHead, PlumHeadSeal, PlumMetadata, PlumMetadataSeal,
+    PlumRelationFlags, PlumRelationFlagsMapping, PlumRelations, PlumRelationsSeal, Seal,
+    SealAlgorithm, UnixNanoseconds,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
 };
 
+/// The maximum number of rows per multi-row INSERT (or seal values per IN clause) in the batch methods.
+/// SQLite limits the number of parameters per statement (to 999, prior to version 3.32.0), and the
+/// widest row inserted this way (plum_metadatas) has 13 columns.
+const STORE_CHUNK_SIZE: usize = 64;
+
+/// The values of the plum_metadatas columns derived from a PlumMetadata, i.e. all but
+/// plum_metadatas_rowid, row_inserted_at, and plum_metadata_seal.
+struct PlumMetadatasColumns<'a> {
+    plum_metadata_nonce_o: Option<&'a [u8]>,
+    plum_created_at_o: Option<i64>,
+    plum_body_content_length_o: Option<i64>,
+    plum_body_content_class_o: Option<&'a str>,
+    plum_body_content_format_o: Option<&'a str>,
+    plum_body_content_encoding_o: Option<&'a str>,
+    additional_content_length_o: Option<i64>,
+    additional_content_class_o: Option<&'a str>,
+    additional_content_format_o: Option<&'a str>,
+    additional_content_encoding_o: Option<&'a str>,
+    additional_content_byte_vo: Option<&'a [u8]>,
+}
+
+impl<'a> From<&'a PlumMetadata> for PlumMetadatasColumns<'a> {
+    fn from(plum_metadata: &'a PlumMetadata) -> Self {
+        let plum_body_content_metadata_o = plum_metadata.plum_body_content_metadata_o.as_ref();
+        let additional_content_metadata_o = plum_metadata
+            .additional_content_o
+            .as_ref()
+            .map(|additional_content| &additional_content.content_metadata);
+        Self {
+            plum_metadata_nonce_o: plum_metadata
+                .plum_metadata_nonce_o
+                .as_ref()
+                .map(|plum_metadata_nonce| plum_metadata_nonce.value.as_slice()),
+            plum_created_at_o: plum_metadata
+                .plum_created_at_o
+                .map(|created_at| created_at.value),
+            // SQLite doesn't support u64, so we have to check for overflow.
+            plum_body_content_length_o: plum_body_content_metadata_o.map(|content_metadata| {
+                if content_metadata.content_length > (i64::MAX as u64) {
+                    panic!("plum_body_content_length too large (exceeds max of i64) in PlumMetadata");
+                }
+                content_metadata.content_length as i64
+            }),
+            plum_body_content_class_o: plum_body_content_metadata_o
+                .map(|content_metadata| content_metadata.content_class.as_str()),
+            plum_body_content_format_o: plum_body_content_metadata_o
+                .map(|content_metadata| content_metadata.content_format.as_str()),
+            plum_body_content_encoding_o: plum_body_content_metadata_o
+                .map(|content_metadata| content_metadata.content_encoding.as_str()),
+            additional_content_length_o: additional_content_metadata_o.map(|content_metadata| {
+                if content_metadata.content_length > (i64::MAX as u64) {
+                    panic!(
+                        "content_length too large (exceeds max of i64) in PlumMetadata additional_content"
+                    );
+                }
+                content_metadata.content_length as i64
+            }),
+            additional_content_class_o: additional_content_metadata_o
+                .map(|content_metadata| content_metadata.content_class.as_str()),
+            additional_content_format_o: additional_content_metadata_o
+                .map(|content_metadata| content_metadata.content_format.as_str()),
+            additional_content_encoding_o: additional_content_metadata_o
+                .map(|content_metadata| content_metadata.content_encoding.as_str()),
+            additional_content_byte_vo: plum_metadata
+                .additional_content_o
+                .as_ref()
+                .map(|additional_content| additional_content.content_byte_v.as_slice()),
+        }
+    }
+}
+
+/// The values of the plum_bodies columns derived from a PlumBody, other than
+/// plum_body_content_byte_vo and plum_body_content_digest_o, which depend on whether the content is
+/// stored in the plum body content dir.  Also excludes plum_bodies_rowid, row_inserted_at, and
+/// plum_body_seal.
+struct PlumBodiesColumns<'a> {
+    plum_body_nonce_o: Option<&'a [u8]>,
+    plum_body_content_length: i64,
+    plum_body_content_class: &'a str,
+    plum_body_content_format: &'a str,
+    plum_body_content_encoding: &'a str,
+}
+
+impl<'a> From<&'a PlumBody> for PlumBodiesColumns<'a> {
+    fn from(plum_body: &'a PlumBody) -> Self {
+        let content_metadata = &plum_body.plum_body_content.content_metadata;
+        // SQLite doesn't support u64, so we have to check for overflow.
+        if content_metadata.content_length > (i64::MAX as u64) {
+            panic!("plum_body_content_length too large (exceeds max of i64) in PlumMetadata");
+        }
+        Self {
+            plum_body_nonce_o: plum_body
+                .plum_body_nonce_o
+                .as_ref()
+                .map(|plum_body_nonce| plum_body_nonce.value.as_slice()),
+            plum_body_content_length: content_metadata.content_length as i64,
+            plum_body_content_class: content_metadata.content_class.as_str(),
+            plum_body_content_format: content_metadata.content_format.as_str(),
+            plum_body_content_encoding: content_metadata.content_encoding.as_str(),
+        }
+    }
+}
+
+/// Computes the value of the plum_body_content_digest_o column for content stored in the plum body
+/// content dir.  This is unkeyed, so that the content file can be checked upon load even if the
+/// PlumBodySeal is keyed.
+fn plum_body_content_digest(content_byte_v: &[u8]) -> Seal {
+    SealAlgorithm::default().compute_seal(content_byte_v)
+}
+
 pub struct DatahostStorageSQLite {
     pool: sqlx::SqlitePool,
+    /// If set, then PlumBody content bytes are stored in this dir instead of inline in the plum_bodies table.
+    plum_body_content_dir_o: Option<Arc<PlumBodyContentDir>>,
 }
 
 impl DatahostStorageSQLite {
@@ -21,12 +140,26 @@ impl DatahostStorageSQLite {
         );
         let pool = Self::pool_connect(url).await?;
         sqlx::migrate!().run(&pool).await?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            plum_body_content_dir_o: None,
+        })
     }
     /// Convenience method for connecting to an ephemeral, in-memory DB.
     pub async fn new_in_memory() -> Result<Self, sqlx::Error> {
         Self::connect_and_run_migrations(":memory:").await
     }
+    /// Causes the content bytes of subsequently stored PlumBody-s to be stored in a content-addressed,
+    /// sharded directory structure (in the style of git objects) rooted at the given dir, instead of inline
+    /// in the DB, in which case the plum_bodies table only stores the rest of the PlumBody.  This keeps
+    /// large PlumBody-s from bloating the DB.  PlumBody-s already stored inline can still be loaded.
+    /// An unkeyed digest of the content is stored in the plum_bodies row, and upon loading a PlumBody
+    /// whose content is stored in the dir, the digest is recomputed in order to check the integrity of
+    /// the content file, even if its PlumBodySeal is keyed.
+    pub fn with_plum_body_content_dir(mut self, root_dir: impl Into<std::path::PathBuf>) -> Self {
+        self.plum_body_content_dir_o = Some(Arc::new(PlumBodyContentDir::new(root_dir.into())));
+        self
+    }
 
     /// This sets the SQL statement logging to sane levels -- trace for statements, and warn for slow statements.
     async fn pool_connect(url: &str) -> Result<sqlx::SqlitePool, sqlx::Error> {
@@ -42,6 +175,67 @@ impl DatahostStorageSQLite {
             .connect_with(connect_options)
             .await
     }
+
+    /// Returns those of the given seal values which are present in the given column of the given
+    /// table, querying at most STORE_CHUNK_SIZE of them at a time.
+    async fn select_present_seal_values(
+        sqlite_transaction: &mut DatahostStorageSQLiteTransaction,
+        table_name: &str,
+        seal_column_name: &str,
+        seal_value_v: &[&[u8]],
+    ) -> Result<HashSet<Vec<u8>>, DatahostStorageError> {
+        let mut present_seal_value_s = HashSet::new();
+        for seal_value_chunk in seal_value_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(format!(
+                "SELECT {} FROM {} WHERE {} IN (",
+                seal_column_name, table_name, seal_column_name
+            ));
+            let mut separated = query_builder.separated(", ");
+            for seal_value in seal_value_chunk {
+                separated.push_bind(*seal_value);
+            }
+            separated.push_unseparated(")");
+            for (seal_value,) in query_builder
+                .build_query_as::<(Vec<u8>,)>()
+                .fetch_all(&mut *sqlite_transaction)
+                .await?
+            {
+                present_seal_value_s.insert(seal_value);
+            }
+        }
+        Ok(present_seal_value_s)
+    }
+    /// Inserts a plum_relation_mappings row for each (plum_relations_rowid, PlumRelationFlagsMapping)
+    /// pair, using multi-row INSERTs of at most STORE_CHUNK_SIZE rows.
+    async fn insert_plum_relation_mappings(
+        sqlite_transaction: &mut DatahostStorageSQLiteTransaction,
+        plum_relation_mapping_v: &[(i64, &PlumRelationFlagsMapping)],
+    ) -> Result<(), DatahostStorageError> {
+        for plum_relation_mapping_chunk in plum_relation_mapping_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT INTO plum_relation_mappings (plum_relations_rowid, target_plum_head_seal, plum_relation_flags) ",
+            );
+            query_builder.push_values(
+                plum_relation_mapping_chunk,
+                |mut values, (plum_relations_rowid, plum_relation_flags_mapping)| {
+                    values
+                        .push_bind(*plum_relations_rowid)
+                        .push_bind(
+                            plum_relation_flags_mapping
+                                .target_plum_head_seal
+                                .value
+                                .as_bytes(),
+                        )
+                        .push_bind(plum_relation_flags_mapping.plum_relation_flags_raw.value);
+                },
+            );
+            query_builder
+                .build()
+                .execute(&mut *sqlite_transaction)
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -49,8 +243,10 @@ impl DatahostStorage for DatahostStorageSQLite {
     async fn begin_transaction(
         &self,
     ) -> Result<Box<dyn DatahostStorageTransaction>, DatahostStorageError> {
-        Ok(Box::new(DatahostStorageSQLiteTransaction::from(
+        Ok(Box::new(DatahostStorageSQLiteTransaction::new(
             self.pool.begin().await?,
+            self.pool.clone(),
+            self.plum_body_content_dir_o.clone(),
         )))
     }
 
@@ -60,9 +256,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         plum_head_seal: &PlumHeadSeal,
     ) -> Result<bool, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_head_seal_bytes = plum_head_seal.value.as_bytes();
         let value = sqlx::query!(
             "SELECT EXISTS(SELECT 1 FROM plum_heads WHERE plum_head_seal = $1) AS value",
-            plum_head_seal.value.sha256sum.value
+            plum_head_seal_bytes
         )
         .fetch_one(sqlite_transaction)
         .await?
@@ -75,9 +272,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         plum_metadata_seal: &PlumMetadataSeal,
     ) -> Result<bool, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_metadata_seal_bytes = plum_metadata_seal.value.as_bytes();
         let value = sqlx::query!(
             "SELECT EXISTS(SELECT 1 FROM plum_metadatas WHERE plum_metadata_seal = $1) AS value",
-            plum_metadata_seal.value.sha256sum.value
+            plum_metadata_seal_bytes
         )
         .fetch_one(sqlite_transaction)
         .await?
@@ -90,9 +288,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         plum_relations_seal: &PlumRelationsSeal,
     ) -> Result<bool, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_relations_seal_bytes = plum_relations_seal.value.as_bytes();
         let value = sqlx::query!(
             "SELECT EXISTS(SELECT 1 FROM plum_relations WHERE plum_relations_seal = $1) AS value",
-            plum_relations_seal.value.sha256sum.value
+            plum_relations_seal_bytes
         )
         .fetch_one(sqlite_transaction)
         .await?
@@ -105,59 +304,298 @@ impl DatahostStorage for DatahostStorageSQLite {
         plum_body_seal: &PlumBodySeal,
     ) -> Result<bool, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_body_seal_bytes = plum_body_seal.value.as_bytes();
         let value = sqlx::query!(
             r#"SELECT EXISTS(SELECT 1 FROM plum_bodies WHERE plum_body_seal = $1) AS value"#,
-            plum_body_seal.value.sha256sum.value
+            plum_body_seal_bytes
         )
         .fetch_one(sqlite_transaction)
         .await?
         .value;
         Ok(value != 0)
     }
+    async fn has_plum_metadata_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal_v: &[PlumMetadataSeal],
+    ) -> Result<Vec<bool>, DatahostStorageError> {
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_metadata_seal_value_v = plum_metadata_seal_v
+            .iter()
+            .map(|plum_metadata_seal| plum_metadata_seal.value.as_bytes())
+            .collect::<Vec<_>>();
+        let present_seal_value_s = Self::select_present_seal_values(
+            sqlite_transaction,
+            "plum_metadatas",
+            "plum_metadata_seal",
+            &plum_metadata_seal_value_v,
+        )
+        .await?;
+        Ok(plum_metadata_seal_value_v
+            .into_iter()
+            .map(|seal_value| present_seal_value_s.contains(seal_value))
+            .collect())
+    }
+    async fn has_plum_relations_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal_v: &[PlumRelationsSeal],
+    ) -> Result<Vec<bool>, DatahostStorageError> {
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_relations_seal_value_v = plum_relations_seal_v
+            .iter()
+            .map(|plum_relations_seal| plum_relations_seal.value.as_bytes())
+            .collect::<Vec<_>>();
+        let present_seal_value_s = Self::select_present_seal_values(
+            sqlite_transaction,
+            "plum_relations",
+            "plum_relations_seal",
+            &plum_relations_seal_value_v,
+        )
+        .await?;
+        Ok(plum_relations_seal_value_v
+            .into_iter()
+            .map(|seal_value| present_seal_value_s.contains(seal_value))
+            .collect())
+    }
+    async fn has_plum_body_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal_v: &[PlumBodySeal],
+    ) -> Result<Vec<bool>, DatahostStorageError> {
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_body_seal_value_v = plum_body_seal_v
+            .iter()
+            .map(|plum_body_seal| plum_body_seal.value.as_bytes())
+            .collect::<Vec<_>>();
+        let present_seal_value_s = Self::select_present_seal_values(
+            sqlite_transaction,
+            "plum_bodies",
+            "plum_body_seal",
+            &plum_body_seal_value_v,
+        )
+        .await?;
+        Ok(plum_body_seal_value_v
+            .into_iter()
+            .map(|seal_value| present_seal_value_s.contains(seal_value))
+            .collect())
+    }
 
-    async fn select_plum_heads(
+    async fn select_plum_heads_page(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
-    ) -> Result<Vec<(UnixNanoseconds, PlumHeadSeal, PlumHead)>, DatahostStorageError> {
+        filter: &PlumHeadsFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
+    ) -> Result<SelectPage<(UnixNanoseconds, PlumHeadSeal, PlumHead)>, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
-        Ok(sqlx::query!(
-            "SELECT row_inserted_at, plum_head_seal, plum_head_nonce_o, plum_metadata_seal, plum_relations_seal, plum_body_seal FROM plum_heads ORDER BY row_inserted_at"
+        // Due to https://github.com/launchbadge/sqlx/issues/1430 it seems that these temps are unavoidable.
+        let content_class_o = filter
+            .plum_filter
+            .content_class_o
+            .as_ref()
+            .map(|content_class| content_class.value.as_str());
+        let content_format_o = filter
+            .plum_filter
+            .content_format_o
+            .as_ref()
+            .map(|content_format| content_format.value.as_str());
+        let created_at_begin_o = filter.plum_filter.created_at_begin_o.map(|t| t.value);
+        let created_at_end_o = filter.plum_filter.created_at_end_o.map(|t| t.value);
+        let inserted_at_begin_o = filter.inserted_at_begin_o.map(|t| t.value);
+        let inserted_at_end_o = filter.inserted_at_end_o.map(|t| t.value);
+        let cursor_row_at_o = cursor_o.map(|cursor| cursor.row_at.value);
+        let cursor_rowid_o = cursor_o.map(|cursor| cursor.rowid);
+        let limit = limit as i64;
+        // The PlumBody content class and format columns of plum_metadatas are either both NULL or
+        // both non-NULL, so COALESCE takes both from the PlumMetadata if it has them, and otherwise
+        // from the PlumBody, as PlumFilter specifies.
+        let row_v = sqlx::query!(
+            r#"
+            SELECT
+                plum_heads.plum_heads_rowid AS "plum_heads_rowid!",
+                plum_heads.row_inserted_at AS "row_inserted_at!",
+                plum_heads.plum_head_seal AS "plum_head_seal!",
+                plum_heads.plum_head_nonce_o,
+                plum_heads.plum_metadata_seal AS "plum_metadata_seal!",
+                plum_heads.plum_relations_seal AS "plum_relations_seal!",
+                plum_heads.plum_body_seal AS "plum_body_seal!"
+            FROM plum_heads
+            LEFT JOIN plum_metadatas ON plum_metadatas.plum_metadata_seal = plum_heads.plum_metadata_seal
+            LEFT JOIN plum_bodies ON plum_bodies.plum_body_seal = plum_heads.plum_body_seal
+            WHERE
+                ($1 IS NULL OR COALESCE(plum_metadatas.plum_body_content_class_o, plum_bodies.plum_body_content_class) = $1) AND
+                ($2 IS NULL OR COALESCE(plum_metadatas.plum_body_content_format_o, plum_bodies.plum_body_content_format) = $2) AND
+                ($3 IS NULL OR plum_metadatas.plum_created_at_o >= $3) AND
+                ($4 IS NULL OR plum_metadatas.plum_created_at_o < $4) AND
+                ($5 IS NULL OR plum_heads.row_inserted_at >= $5) AND
+                ($6 IS NULL OR plum_heads.row_inserted_at < $6) AND
+                ($7 IS NULL OR (plum_heads.row_inserted_at, plum_heads.plum_heads_rowid) > ($7, $8))
+            ORDER BY plum_heads.row_inserted_at, plum_heads.plum_heads_rowid
+            LIMIT $9
+            "#,
+            content_class_o,
+            content_format_o,
+            created_at_begin_o,
+            created_at_end_o,
+            inserted_at_begin_o,
+            inserted_at_end_o,
+            cursor_row_at_o,
+            cursor_rowid_o,
+            limit,
         )
         .fetch_all(sqlite_transaction)
-        .await?
-        .into_iter()
-        .map(|row| (
-            row.row_inserted_at.into(),
-            row.plum_head_seal.into(),
-            PlumHead {
-                plum_head_nonce_o: row.plum_head_nonce_o.map(|v| Nonce::from(v)),
-                plum_metadata_seal: row.plum_metadata_seal.into(),
-                plum_relations_seal: row.plum_relations_seal.into(),
-                plum_body_seal: row.plum_body_seal.into(),
-            },
-        ))
-        .collect::<Vec<_>>())
+        .await?;
+        let next_cursor_o = if row_v.len() as i64 == limit {
+            row_v.last().map(|row| SelectCursor {
+                row_at: row.row_inserted_at.into(),
+                rowid: row.plum_heads_rowid,
+            })
+        } else {
+            None
+        };
+        Ok(SelectPage {
+            row_v: row_v
+                .into_iter()
+                .map(|row| {
+                    Ok((
+                        row.row_inserted_at.into(),
+                        seal_from_db(row.plum_head_seal, "plum_heads", "plum_head_seal")?,
+                        PlumHead {
+                            plum_head_nonce_o: row.plum_head_nonce_o.map(Nonce::from),
+                            plum_metadata_seal: seal_from_db(
+                                row.plum_metadata_seal,
+                                "plum_heads",
+                                "plum_metadata_seal",
+                            )?,
+                            plum_relations_seal: seal_from_db(
+                                row.plum_relations_seal,
+                                "plum_heads",
+                                "plum_relations_seal",
+                            )?,
+                            plum_body_seal: seal_from_db(
+                                row.plum_body_seal,
+                                "plum_heads",
+                                "plum_body_seal",
+                            )?,
+                        },
+                    ))
+                })
+                .collect::<Result<_, DatahostStorageError>>()?,
+            next_cursor_o,
+        })
     }
-    async fn select_path_states(
+    async fn select_path_states_page(
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
-    ) -> Result<Vec<(UnixNanoseconds, UnixNanoseconds, PathState)>, DatahostStorageError> {
+        filter: &PathStatesFilter,
+        cursor_o: Option<&SelectCursor>,
+        limit: u32,
+    ) -> Result<SelectPage<(UnixNanoseconds, UnixNanoseconds, PathState)>, DatahostStorageError>
+    {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
-        Ok(sqlx::query!(
-            "SELECT row_inserted_at, row_updated_at, path, current_state_plum_head_seal FROM path_states ORDER BY row_updated_at"
+        // Due to https://github.com/launchbadge/sqlx/issues/1430 it seems that these temps are unavoidable.
+        let updated_at_begin_o = filter.updated_at_begin_o.map(|t| t.value);
+        let updated_at_end_o = filter.updated_at_end_o.map(|t| t.value);
+        let cursor_row_at_o = cursor_o.map(|cursor| cursor.row_at.value);
+        let cursor_rowid_o = cursor_o.map(|cursor| cursor.rowid);
+        let limit = limit as i64;
+        let path_prefix_o = filter.path_prefix_o.as_deref();
+        // substr is used instead of LIKE so that the prefix needn't be escaped.
+        let row_v = sqlx::query!(
+            r#"
+            SELECT path_states_rowid, row_inserted_at, row_updated_at, path, current_state_plum_head_seal
+            FROM path_states
+            WHERE
+                ($1 IS NULL OR row_updated_at >= $1) AND
+                ($2 IS NULL OR row_updated_at < $2) AND
+                ($3 IS NULL OR (row_updated_at, path_states_rowid) > ($3, $4)) AND
+                ($6 IS NULL OR substr(path, 1, length($6)) = $6)
+            ORDER BY row_updated_at, path_states_rowid
+            LIMIT $5
+            "#,
+            updated_at_begin_o,
+            updated_at_end_o,
+            cursor_row_at_o,
+            cursor_rowid_o,
+            limit,
+            path_prefix_o,
         )
         .fetch_all(sqlite_transaction)
-        .await?
-        .into_iter()
-        .map(|row| (
-            row.row_inserted_at.into(),
-            row.row_updated_at.into(),
-            PathState {
-                path: row.path.into(),
-                current_state_plum_head_seal: row.current_state_plum_head_seal.into(),
-            },
-        ))
-        .collect::<Vec<_>>())
+        .await?;
+        let next_cursor_o = if row_v.len() as i64 == limit {
+            row_v.last().map(|row| SelectCursor {
+                row_at: row.row_updated_at.into(),
+                rowid: row.path_states_rowid,
+            })
+        } else {
+            None
+        };
+        Ok(SelectPage {
+            row_v: row_v
+                .into_iter()
+                .map(|row| {
+                    Ok((
+                        row.row_inserted_at.into(),
+                        row.row_updated_at.into(),
+                        PathState {
+                            path: row.path.into(),
+                            current_state_plum_head_seal: seal_from_db(
+                                row.current_state_plum_head_seal,
+                                "path_states",
+                                "current_state_plum_head_seal",
+                            )?,
+                        },
+                    ))
+                })
+                .collect::<Result<_, DatahostStorageError>>()?,
+            next_cursor_o,
+        })
+    }
+
+    async fn select_plum_relations_referring_to(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        target_plum_head_seal: &PlumHeadSeal,
+    ) -> Result<Vec<(PlumHeadSeal, PlumRelationFlags)>, DatahostStorageError> {
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let target_plum_head_seal_bytes = target_plum_head_seal.value.as_bytes();
+        let record_v = sqlx::query!(
+            r#"
+            SELECT plum_heads.plum_head_seal, plum_relation_mappings.plum_relation_flags
+            FROM plum_relation_mappings
+            JOIN plum_relations ON plum_relations.plum_relations_rowid = plum_relation_mappings.plum_relations_rowid
+            JOIN plum_heads ON plum_heads.plum_relations_seal = plum_relations.plum_relations_seal
+            WHERE plum_relation_mappings.target_plum_head_seal = $1
+            ORDER BY plum_heads.row_inserted_at, plum_heads.plum_heads_rowid
+            "#,
+            target_plum_head_seal_bytes
+        )
+        .fetch_all(sqlite_transaction)
+        .await?;
+        let mut referring_v = Vec::with_capacity(record_v.len());
+        for record in record_v {
+            if record.plum_relation_flags < 0 || record.plum_relation_flags > (u32::MAX as i64) {
+                return Err(DatahostStorageError::InvalidValueInDB {
+                    table_name: "plum_relation_mappings",
+                    column_name: "plum_relation_flags",
+                    reason: "column value was outside of the range of u32".to_string(),
+                });
+            }
+            let plum_relation_flags =
+                PlumRelationFlags::try_from(record.plum_relation_flags as u32).map_err(|e| {
+                    DatahostStorageError::InvalidValueInDB {
+                        table_name: "plum_relation_mappings",
+                        column_name: "plum_relation_flags",
+                        reason: e.to_string(),
+                    }
+                })?;
+            referring_v.push((
+                seal_from_db(record.plum_head_seal, "plum_heads", "plum_head_seal")?,
+                plum_relation_flags,
+            ));
+        }
+        Ok(referring_v)
     }
 
     async fn store_plum_head(
@@ -196,6 +634,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         // Ignore collision.  The PlumHeadSeal being identical should guarantee that the plum_heads row is
         // identical except for the plum_heads_rowid and row_inserted_at.  However, it might be good to add
         // a check upon collision that the row is actually identical.
+        let plum_head_seal_bytes = plum_head_seal.value.as_bytes();
+        let plum_metadata_seal_bytes = plum_head.plum_metadata_seal.value.as_bytes();
+        let plum_relations_seal_bytes = plum_head.plum_relations_seal.value.as_bytes();
+        let plum_body_seal_bytes = plum_head.plum_body_seal.value.as_bytes();
         let _plum_heads_rowid = sqlx::query!(
             r#"INSERT INTO plum_heads (
                 row_inserted_at,
@@ -211,11 +653,11 @@ impl DatahostStorage for DatahostStorageSQLite {
             -- NOTE: This doesn't work
             --SELECT last_insert_rowid() AS plum_heads_rowid"#,
             now.value,
-            plum_head_seal.value.sha256sum.value,
+            plum_head_seal_bytes,
             plum_head_nonce_o,
-            plum_head.plum_metadata_seal.value.sha256sum.value,
-            plum_head.plum_relations_seal.value.sha256sum.value,
-            plum_head.plum_body_seal.value.sha256sum.value,
+            plum_metadata_seal_bytes,
+            plum_relations_seal_bytes,
+            plum_body_seal_bytes,
         )
         .fetch_one(sqlite_transaction)
         .await?
@@ -227,8 +669,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_metadata: &PlumMetadata,
+        plum_metadata_seal: &PlumMetadataSeal,
     ) -> Result<PlumMetadataSeal, DatahostStorageError> {
-        let plum_metadata_seal = PlumMetadataSeal::from(plum_metadata);
+        plum_metadata_seal.verify(plum_metadata, None)?;
+        let plum_metadata_seal = plum_metadata_seal.clone();
         log::debug!(
             "store_plum_metadata; storing plum_metadata with seal: {}",
             plum_metadata_seal
@@ -251,73 +695,11 @@ impl DatahostStorage for DatahostStorageSQLite {
 
         let now = UnixNanoseconds::now();
 
-        // Due to https://github.com/launchbadge/sqlx/issues/1430 it seems that these temps are unavoidable.
-        let plum_metadata_nonce_o = plum_metadata
-            .plum_metadata_nonce_o
-            .as_ref()
-            .map(|plum_metadata_nonce| &plum_metadata_nonce.value);
-
-        let plum_created_at_o = plum_metadata
-            .plum_created_at_o
-            .map(|created_at| created_at.value);
-
-        let plum_body_content_length_o = if let Some(plum_body_content_metadata) =
-            plum_metadata.plum_body_content_metadata_o.as_ref()
-        {
-            // SQLite doesn't support u64, so we have to check for overflow.
-            if plum_body_content_metadata.content_length > (i64::MAX as u64) {
-                panic!("plum_body_content_length too large (exceeds max of i64) in PlumMetadata");
-            }
-            Some(plum_body_content_metadata.content_length as i64)
-        } else {
-            None
-        };
-        let plum_body_content_class_o = plum_metadata
-            .plum_body_content_metadata_o
-            .as_ref()
-            .map(|x| x.content_class.as_str());
-        let plum_body_content_format_o = plum_metadata
-            .plum_body_content_metadata_o
-            .as_ref()
-            .map(|x| x.content_format.as_str());
-        let plum_body_content_encoding_o = plum_metadata
-            .plum_body_content_metadata_o
-            .as_ref()
-            .map(|x| x.content_encoding.as_str());
-
-        let additional_content_length_o = if let Some(additional_content) =
-            plum_metadata.additional_content_o.as_ref()
-        {
-            // SQLite doesn't support u64, so we have to check for overflow.
-            if additional_content.content_metadata.content_length > (i64::MAX as u64) {
-                panic!(
-                        "content_length too large (exceeds max of i64) in PlumMetadata additional_content"
-                    );
-            }
-            Some(additional_content.content_metadata.content_length as i64)
-        } else {
-            None
-        };
-        let additional_content_class_o = plum_metadata
-            .additional_content_o
-            .as_ref()
-            .map(|x| x.content_metadata.content_class.as_str());
-        let additional_content_format_o = plum_metadata
-            .additional_content_o
-            .as_ref()
-            .map(|x| x.content_metadata.content_format.as_str());
-        let additional_content_encoding_o = plum_metadata
-            .additional_content_o
-            .as_ref()
-            .map(|x| x.content_metadata.content_encoding.as_str());
-        let additional_content_byte_vo = plum_metadata
-            .additional_content_o
-            .as_ref()
-            .map(|x| x.content_byte_v.as_slice());
-
+        let columns = PlumMetadatasColumns::from(plum_metadata);
         // Ignore collision.  The PlumMetadataSeal being identical should guarantee that the plum_metadatas row is
         // identical except for the plum_metadatas_rowid and row_inserted_at.  However, it might be good to add
         // a check upon collision that the row is actually identical.
+        let plum_metadata_seal_bytes = plum_metadata_seal.value.as_bytes();
         let _plum_metadatas_rowid = sqlx::query!(
             r#"INSERT INTO plum_metadatas (
                 row_inserted_at,
@@ -340,18 +722,18 @@ impl DatahostStorage for DatahostStorageSQLite {
             -- NOTE: This doesn't work
             --SELECT last_insert_rowid() AS plum_metadatas_rowid"#,
             now.value,
-            plum_metadata_seal.value.sha256sum.value,
-            plum_metadata_nonce_o,
-            plum_created_at_o,
-            plum_body_content_length_o,
-            plum_body_content_class_o,
-            plum_body_content_format_o,
-            plum_body_content_encoding_o,
-            additional_content_length_o,
-            additional_content_class_o,
-            additional_content_format_o,
-            additional_content_encoding_o,
-            additional_content_byte_vo,
+            plum_metadata_seal_bytes,
+            columns.plum_metadata_nonce_o,
+            columns.plum_created_at_o,
+            columns.plum_body_content_length_o,
+            columns.plum_body_content_class_o,
+            columns.plum_body_content_format_o,
+            columns.plum_body_content_encoding_o,
+            columns.additional_content_length_o,
+            columns.additional_content_class_o,
+            columns.additional_content_format_o,
+            columns.additional_content_encoding_o,
+            columns.additional_content_byte_vo,
         )
         .fetch_one(sqlite_transaction)
         .await?
@@ -363,8 +745,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_relations: &PlumRelations,
+        plum_relations_seal: &PlumRelationsSeal,
     ) -> Result<PlumRelationsSeal, DatahostStorageError> {
-        let plum_relations_seal = PlumRelationsSeal::from(plum_relations);
+        plum_relations_seal.verify(plum_relations, None)?;
+        let plum_relations_seal = plum_relations_seal.clone();
         log::debug!(
             "store_plum_relations; storing plum_relations with seal: {}",
             plum_relations_seal
@@ -409,9 +793,9 @@ impl DatahostStorage for DatahostStorageSQLite {
         //     --;
         //     --SELECT last_insert_rowid() AS plum_relations_rowid"#,
         //     now.value,
-        //     plum_relations_seal.value.sha256sum.value,
+        //     plum_relations_seal.value.as_bytes(),
         //     plum_relations_nonce_o,
-        //     plum_relations.source_plum_body_seal.value.sha256sum.value,
+        //     plum_relations.source_plum_body_seal.value.as_bytes(),
         // )
         // .fetch_one(&mut *sqlite_transaction)
         // .await?
@@ -420,6 +804,8 @@ impl DatahostStorage for DatahostStorageSQLite {
         // // .await?
         // // .map(|record| record.plum_relations_rowid);
 
+        let plum_relations_seal_bytes = plum_relations_seal.value.as_bytes();
+        let source_plum_body_seal_bytes = plum_relations.source_plum_body_seal.value.as_bytes();
         let plum_relations_rowid = sqlx::query!(
             r#"INSERT INTO plum_relations (
                 row_inserted_at,
@@ -429,9 +815,9 @@ impl DatahostStorage for DatahostStorageSQLite {
             ) VALUES ($1, $2, $3, $4)
             RETURNING plum_relations_rowid"#,
             now.value,
-            plum_relations_seal.value.sha256sum.value,
+            plum_relations_seal_bytes,
             plum_relations_nonce_o,
-            plum_relations.source_plum_body_seal.value.sha256sum.value,
+            source_plum_body_seal_bytes,
         )
         .fetch_one(&mut *sqlite_transaction)
         .await?
@@ -444,25 +830,17 @@ impl DatahostStorage for DatahostStorageSQLite {
         // }
         // let plum_relations_rowid = plum_relations_rowid_o.unwrap();
 
-        // TODO: Figure out how to do this efficiently (i.e. batch insert perhaps with appropriate chunking)
-        for plum_relation_flags_mapping in plum_relations.plum_relation_flags_mapping_v.iter() {
-            sqlx::query!(
-                r#"INSERT INTO plum_relation_mappings (
-                    plum_relations_rowid,
-                    target_plum_head_seal,
-                    plum_relation_flags
-                ) VALUES ($1, $2, $3);"#,
-                plum_relations_rowid,
-                plum_relation_flags_mapping
-                    .target_plum_head_seal
-                    .value
-                    .sha256sum
-                    .value,
-                plum_relation_flags_mapping.plum_relation_flags_raw.value,
-            )
-            .execute(&mut *sqlite_transaction)
-            .await?;
-        }
+        Self::insert_plum_relation_mappings(
+            sqlite_transaction,
+            &plum_relations
+                .plum_relation_flags_mapping_v
+                .iter()
+                .map(|plum_relation_flags_mapping| {
+                    (plum_relations_rowid, plum_relation_flags_mapping)
+                })
+                .collect::<Vec<_>>(),
+        )
+        .await?;
 
         Ok(plum_relations_seal)
     }
@@ -470,8 +848,10 @@ impl DatahostStorage for DatahostStorageSQLite {
         &self,
         transaction: &mut dyn DatahostStorageTransaction,
         plum_body: &PlumBody,
+        plum_body_seal: &PlumBodySeal,
     ) -> Result<PlumBodySeal, DatahostStorageError> {
-        let plum_body_seal = PlumBodySeal::from(plum_body);
+        plum_body_seal.verify(plum_body, None)?;
+        let plum_body_seal = plum_body_seal.clone();
         log::debug!(
             "store_plum_body; storing plum_body with seal: {:?}",
             plum_body_seal
@@ -497,37 +877,32 @@ impl DatahostStorage for DatahostStorageSQLite {
         // and probably actually write them to disk.
         let now = UnixNanoseconds::now();
 
-        // Due to https://github.com/launchbadge/sqlx/issues/1430 it seems that these temps are unavoidable.
-        let plum_body_nonce_o = plum_body
-            .plum_body_nonce_o
-            .as_ref()
-            .map(|plum_body_nonce| &plum_body_nonce.value);
-        // SQLite doesn't support u64, so we have to check for overflow.
-        let plum_body_content_length =
-            if plum_body.plum_body_content.content_metadata.content_length > (i64::MAX as u64) {
-                panic!("plum_body_content_length too large (exceeds max of i64) in PlumMetadata");
+        let columns = PlumBodiesColumns::from(plum_body);
+
+        // If the content is stored in the plum body content dir, then it's written before the row is
+        // inserted, so that the row never refers to content that doesn't exist.
+        let (plum_body_content_byte_vo, plum_body_content_digest_o) =
+            if let Some(plum_body_content_dir) = self.plum_body_content_dir_o.as_ref() {
+                let content_file_pin = plum_body_content_dir
+                    .store_content(&plum_body_seal, &plum_body.plum_body_content.content_byte_v)
+                    .await?;
+                sqlite_transaction.content_file_stored(content_file_pin);
+                (
+                    None,
+                    Some(plum_body_content_digest(
+                        &plum_body.plum_body_content.content_byte_v,
+                    )),
+                )
             } else {
-                plum_body.plum_body_content.content_metadata.content_length as i64
+                (Some(&plum_body.plum_body_content.content_byte_v), None)
             };
-        let plum_body_content_class = plum_body
-            .plum_body_content
-            .content_metadata
-            .content_class
-            .as_str();
-        let plum_body_content_format = plum_body
-            .plum_body_content
-            .content_metadata
-            .content_format
-            .as_str();
-        let plum_body_content_encoding = plum_body
-            .plum_body_content
-            .content_metadata
-            .content_encoding
-            .as_str();
 
         // Ignore collision.  The PlumBodySeal being identical should guarantee that the plum_bodies row is
         // identical except for the plum_bodies_rowid and row_inserted_at.  However, it might be good to add
         // a check upon collision that the row is actually identical.
+        let plum_body_seal_bytes = plum_body_seal.value.as_bytes();
+        let plum_body_content_digest_bytes_o =
+            plum_body_content_digest_o.as_ref().map(Seal::as_bytes);
         let _plum_bodies_rowid = sqlx::query!(
             r#"INSERT INTO plum_bodies (
                 row_inserted_at,
@@ -537,20 +912,22 @@ impl DatahostStorage for DatahostStorageSQLite {
                 plum_body_content_class,
                 plum_body_content_format,
                 plum_body_content_encoding,
-                plum_body_content_byte_v
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                plum_body_content_byte_vo,
+                plum_body_content_digest_o
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             --ON CONFLICT(plum_body_seal) DO NOTHING
             RETURNING plum_bodies_rowid
             --;
             --SELECT last_insert_rowid() AS plum_bodies_rowid"#,
             now.value,
-            plum_body_seal.value.sha256sum.value,
-            plum_body_nonce_o,
-            plum_body_content_length,
-            plum_body_content_class,
-            plum_body_content_format,
-            plum_body_content_encoding,
-            plum_body.plum_body_content.content_byte_v,
+            plum_body_seal_bytes,
+            columns.plum_body_nonce_o,
+            columns.plum_body_content_length,
+            columns.plum_body_content_class,
+            columns.plum_body_content_format,
+            columns.plum_body_content_encoding,
+            plum_body_content_byte_vo,
+            plum_body_content_digest_bytes_o,
         )
         .fetch_one(sqlite_transaction)
         .await?
@@ -558,6 +935,305 @@ impl DatahostStorage for DatahostStorageSQLite {
 
         Ok(plum_body_seal)
     }
+    async fn store_plum_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_v: &[Plum],
+    ) -> Result<Vec<PlumHeadSeal>, DatahostStorageError> {
+        // Verify all the Plums before storing anything.
+        for plum in plum_v {
+            plum.verify()?;
+        }
+
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let now = UnixNanoseconds::now();
+
+        // Determine which PlumMetadata-s are new, then insert them using multi-row INSERTs.  Note that
+        // the seals were verified above, so they don't need to be recomputed.
+        let mut new_plum_metadata_v: Vec<(&PlumMetadataSeal, &PlumMetadata)> = Vec::new();
+        {
+            let mut plum_metadata_seal_s = HashSet::new();
+            for plum in plum_v {
+                if plum_metadata_seal_s.insert(&plum.plum_head.plum_metadata_seal) {
+                    new_plum_metadata_v
+                        .push((&plum.plum_head.plum_metadata_seal, &plum.plum_metadata));
+                }
+            }
+            let present_seal_value_s = Self::select_present_seal_values(
+                &mut *sqlite_transaction,
+                "plum_metadatas",
+                "plum_metadata_seal",
+                &new_plum_metadata_v
+                    .iter()
+                    .map(|(plum_metadata_seal, _)| plum_metadata_seal.value.as_bytes())
+                    .collect::<Vec<&[u8]>>(),
+            )
+            .await?;
+            new_plum_metadata_v.retain(|(plum_metadata_seal, _)| {
+                !present_seal_value_s.contains(plum_metadata_seal.value.as_bytes())
+            });
+        }
+        for new_plum_metadata_chunk in new_plum_metadata_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"INSERT INTO plum_metadatas (
+                    row_inserted_at,
+                    plum_metadata_seal,
+                    plum_metadata_nonce_o,
+                    plum_created_at_o,
+                    plum_body_content_length_o,
+                    plum_body_content_class_o,
+                    plum_body_content_format_o,
+                    plum_body_content_encoding_o,
+                    additional_content_length_o,
+                    additional_content_class_o,
+                    additional_content_format_o,
+                    additional_content_encoding_o,
+                    additional_content_byte_vo
+                ) "#,
+            );
+            query_builder.push_values(
+                new_plum_metadata_chunk,
+                |mut values, (plum_metadata_seal, plum_metadata)| {
+                    let columns = PlumMetadatasColumns::from(*plum_metadata);
+                    values
+                        .push_bind(now.value)
+                        .push_bind(plum_metadata_seal.value.as_bytes())
+                        .push_bind(columns.plum_metadata_nonce_o)
+                        .push_bind(columns.plum_created_at_o)
+                        .push_bind(columns.plum_body_content_length_o)
+                        .push_bind(columns.plum_body_content_class_o)
+                        .push_bind(columns.plum_body_content_format_o)
+                        .push_bind(columns.plum_body_content_encoding_o)
+                        .push_bind(columns.additional_content_length_o)
+                        .push_bind(columns.additional_content_class_o)
+                        .push_bind(columns.additional_content_format_o)
+                        .push_bind(columns.additional_content_encoding_o)
+                        .push_bind(columns.additional_content_byte_vo);
+                },
+            );
+            query_builder
+                .build()
+                .execute(&mut *sqlite_transaction)
+                .await?;
+        }
+
+        // Likewise for the PlumBody-s.  If the content is stored in the plum body content dir, then
+        // the content files are written first, so that no row ever refers to content that doesn't
+        // exist.
+        let mut new_plum_body_v: Vec<(&PlumBodySeal, &PlumBody)> = Vec::new();
+        {
+            let mut plum_body_seal_s = HashSet::new();
+            for plum in plum_v {
+                if plum_body_seal_s.insert(&plum.plum_head.plum_body_seal) {
+                    new_plum_body_v.push((&plum.plum_head.plum_body_seal, &plum.plum_body));
+                }
+            }
+            let present_seal_value_s = Self::select_present_seal_values(
+                &mut *sqlite_transaction,
+                "plum_bodies",
+                "plum_body_seal",
+                &new_plum_body_v
+                    .iter()
+                    .map(|(plum_body_seal, _)| plum_body_seal.value.as_bytes())
+                    .collect::<Vec<&[u8]>>(),
+            )
+            .await?;
+            new_plum_body_v.retain(|(plum_body_seal, _)| {
+                !present_seal_value_s.contains(plum_body_seal.value.as_bytes())
+            });
+        }
+        if let Some(plum_body_content_dir) = self.plum_body_content_dir_o.as_ref() {
+            for (plum_body_seal, plum_body) in new_plum_body_v.iter() {
+                let content_file_pin = plum_body_content_dir
+                    .store_content(plum_body_seal, &plum_body.plum_body_content.content_byte_v)
+                    .await?;
+                sqlite_transaction.content_file_stored(content_file_pin);
+            }
+        }
+        for new_plum_body_chunk in new_plum_body_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"INSERT INTO plum_bodies (
+                    row_inserted_at,
+                    plum_body_seal,
+                    plum_body_nonce_o,
+                    plum_body_content_length,
+                    plum_body_content_class,
+                    plum_body_content_format,
+                    plum_body_content_encoding,
+                    plum_body_content_byte_vo,
+                    plum_body_content_digest_o
+                ) "#,
+            );
+            query_builder.push_values(
+                new_plum_body_chunk,
+                |mut values, (plum_body_seal, plum_body)| {
+                    let columns = PlumBodiesColumns::from(*plum_body);
+                    values
+                        .push_bind(now.value)
+                        .push_bind(plum_body_seal.value.as_bytes())
+                        .push_bind(columns.plum_body_nonce_o)
+                        .push_bind(columns.plum_body_content_length)
+                        .push_bind(columns.plum_body_content_class)
+                        .push_bind(columns.plum_body_content_format)
+                        .push_bind(columns.plum_body_content_encoding)
+                        .push_bind(if self.plum_body_content_dir_o.is_some() {
+                            None
+                        } else {
+                            Some(plum_body.plum_body_content.content_byte_v.as_slice())
+                        })
+                        .push_bind(if self.plum_body_content_dir_o.is_some() {
+                            Some(
+                                plum_body_content_digest(
+                                    &plum_body.plum_body_content.content_byte_v,
+                                )
+                                .as_bytes()
+                                .to_vec(),
+                            )
+                        } else {
+                            None
+                        });
+                },
+            );
+            query_builder
+                .build()
+                .execute(&mut *sqlite_transaction)
+                .await?;
+        }
+
+        // Determine which PlumRelations-s are new, then insert them using multi-row INSERTs, followed by
+        // their plum_relation_mappings rows.  Note that the seals were verified above, so they don't need
+        // to be recomputed.
+        let mut new_plum_relations_v: Vec<(&PlumRelationsSeal, &PlumRelations)> = Vec::new();
+        {
+            let mut plum_relations_seal_s = HashSet::new();
+            for plum in plum_v {
+                if plum_relations_seal_s.insert(&plum.plum_head.plum_relations_seal) {
+                    new_plum_relations_v
+                        .push((&plum.plum_head.plum_relations_seal, &plum.plum_relations));
+                }
+            }
+            let present_seal_value_s = Self::select_present_seal_values(
+                &mut *sqlite_transaction,
+                "plum_relations",
+                "plum_relations_seal",
+                &new_plum_relations_v
+                    .iter()
+                    .map(|(plum_relations_seal, _)| plum_relations_seal.value.as_bytes())
+                    .collect::<Vec<&[u8]>>(),
+            )
+            .await?;
+            new_plum_relations_v.retain(|(plum_relations_seal, _)| {
+                !present_seal_value_s.contains(plum_relations_seal.value.as_bytes())
+            });
+        }
+        let mut plum_relation_mapping_v = Vec::new();
+        for new_plum_relations_chunk in new_plum_relations_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT INTO plum_relations (row_inserted_at, plum_relations_seal, plum_relations_nonce_o, source_plum_body_seal) ",
+            );
+            query_builder.push_values(
+                new_plum_relations_chunk,
+                |mut values, (plum_relations_seal, plum_relations)| {
+                    values
+                        .push_bind(now.value)
+                        .push_bind(plum_relations_seal.value.as_bytes())
+                        .push_bind(
+                            plum_relations
+                                .plum_relations_nonce_o
+                                .as_ref()
+                                .map(|plum_relations_nonce| plum_relations_nonce.value.as_slice()),
+                        )
+                        .push_bind(plum_relations.source_plum_body_seal.value.as_bytes());
+                },
+            );
+            // SQLite doesn't guarantee the order of the RETURNING rows, so the seal is used to match them up.
+            query_builder.push(" RETURNING plum_relations_rowid, plum_relations_seal");
+            let plum_relations_rowid_m = query_builder
+                .build_query_as::<(i64, Vec<u8>)>()
+                .fetch_all(&mut *sqlite_transaction)
+                .await?
+                .into_iter()
+                .map(|(plum_relations_rowid, plum_relations_seal_value)| {
+                    (plum_relations_seal_value, plum_relations_rowid)
+                })
+                .collect::<HashMap<Vec<u8>, i64>>();
+            for (plum_relations_seal, plum_relations) in new_plum_relations_chunk {
+                let plum_relations_rowid =
+                    plum_relations_rowid_m[plum_relations_seal.value.as_bytes()];
+                plum_relation_mapping_v.extend(
+                    plum_relations.plum_relation_flags_mapping_v.iter().map(
+                        |plum_relation_flags_mapping| {
+                            (plum_relations_rowid, plum_relation_flags_mapping)
+                        },
+                    ),
+                );
+            }
+        }
+        Self::insert_plum_relation_mappings(&mut *sqlite_transaction, &plum_relation_mapping_v)
+            .await?;
+
+        // Storing the PlumHeads last ensures that the Plums are fully stored before we commit to them.
+        let plum_head_seal_v = plum_v
+            .iter()
+            .map(|plum| PlumHeadSeal::from(&plum.plum_head))
+            .collect::<Vec<PlumHeadSeal>>();
+        let mut new_plum_head_v: Vec<(&PlumHeadSeal, &PlumHead)> = Vec::new();
+        {
+            let mut plum_head_seal_s = HashSet::new();
+            for (plum_head_seal, plum) in plum_head_seal_v.iter().zip(plum_v.iter()) {
+                if plum_head_seal_s.insert(plum_head_seal) {
+                    new_plum_head_v.push((plum_head_seal, &plum.plum_head));
+                }
+            }
+            let present_seal_value_s = Self::select_present_seal_values(
+                &mut *sqlite_transaction,
+                "plum_heads",
+                "plum_head_seal",
+                &new_plum_head_v
+                    .iter()
+                    .map(|(plum_head_seal, _)| plum_head_seal.value.as_bytes())
+                    .collect::<Vec<&[u8]>>(),
+            )
+            .await?;
+            new_plum_head_v.retain(|(plum_head_seal, _)| {
+                !present_seal_value_s.contains(plum_head_seal.value.as_bytes())
+            });
+        }
+        for new_plum_head_chunk in new_plum_head_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT INTO plum_heads (row_inserted_at, plum_head_seal, plum_head_nonce_o, plum_metadata_seal, plum_relations_seal, plum_body_seal) ",
+            );
+            query_builder.push_values(
+                new_plum_head_chunk,
+                |mut values, (plum_head_seal, plum_head)| {
+                    values
+                        .push_bind(now.value)
+                        .push_bind(plum_head_seal.value.as_bytes())
+                        .push_bind(
+                            plum_head
+                                .plum_head_nonce_o
+                                .as_ref()
+                                .map(|plum_head_nonce| plum_head_nonce.value.as_slice()),
+                        )
+                        .push_bind(plum_head.plum_metadata_seal.value.as_bytes())
+                        .push_bind(plum_head.plum_relations_seal.value.as_bytes())
+                        .push_bind(plum_head.plum_body_seal.value.as_bytes());
+                },
+            );
+            query_builder
+                .build()
+                .execute(&mut *sqlite_transaction)
+                .await?;
+        }
+        log::debug!(
+            "store_plum_v; stored {} Plums, of which {} PlumHeads and {} PlumRelations were new",
+            plum_v.len(),
+            new_plum_head_v.len(),
+            new_plum_relations_v.len()
+        );
+
+        Ok(plum_head_seal_v)
+    }
 
     async fn load_option_plum_head(
         &self,
@@ -566,6 +1242,7 @@ impl DatahostStorage for DatahostStorageSQLite {
     ) -> Result<Option<PlumHead>, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
 
+        let plum_head_seal_bytes = plum_head_seal.value.as_bytes();
         let plum_heads_row_r = sqlx::query!(
             r#"SELECT
                 plum_head_nonce_o,
@@ -574,7 +1251,7 @@ impl DatahostStorage for DatahostStorageSQLite {
                 plum_body_seal
             FROM plum_heads
             WHERE plum_head_seal = $1"#,
-            plum_head_seal.value.sha256sum.value
+            plum_head_seal_bytes
         )
         .fetch_one(sqlite_transaction)
         .await;
@@ -583,15 +1260,21 @@ impl DatahostStorage for DatahostStorageSQLite {
             Ok(plum_heads_row) => Ok(Some({
                 PlumHead {
                     plum_head_nonce_o: plum_heads_row.plum_head_nonce_o.map(Nonce::from),
-                    plum_metadata_seal: PlumMetadataSeal::from(Seal::from(Sha256Sum::from(
+                    plum_metadata_seal: seal_from_db(
                         plum_heads_row.plum_metadata_seal,
-                    ))),
-                    plum_relations_seal: PlumRelationsSeal::from(Seal::from(Sha256Sum::from(
+                        "plum_heads",
+                        "plum_metadata_seal",
+                    )?,
+                    plum_relations_seal: seal_from_db(
                         plum_heads_row.plum_relations_seal,
-                    ))),
-                    plum_body_seal: PlumBodySeal::from(Seal::from(Sha256Sum::from(
+                        "plum_heads",
+                        "plum_relations_seal",
+                    )?,
+                    plum_body_seal: seal_from_db(
                         plum_heads_row.plum_body_seal,
-                    ))),
+                        "plum_heads",
+                        "plum_body_seal",
+                    )?,
                 }
             })),
             Err(sqlx::Error::RowNotFound) => Ok(None),
@@ -605,6 +1288,7 @@ impl DatahostStorage for DatahostStorageSQLite {
     ) -> Result<Option<PlumMetadata>, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
 
+        let plum_metadata_seal_bytes = plum_metadata_seal.value.as_bytes();
         let plum_metadatas_row_r = sqlx::query!(
             r#"SELECT
                 plum_metadata_nonce_o,
@@ -620,7 +1304,7 @@ impl DatahostStorage for DatahostStorageSQLite {
                 additional_content_byte_vo
             FROM plum_metadatas
             WHERE plum_metadata_seal = $1"#,
-            plum_metadata_seal.value.sha256sum.value
+            plum_metadata_seal_bytes
         )
         .fetch_one(sqlite_transaction)
         .await;
@@ -727,6 +1411,7 @@ impl DatahostStorage for DatahostStorageSQLite {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
 
         // TODO: No need to query the whole row, only need a few column values.
+        let plum_relations_seal_bytes = plum_relations_seal.value.as_bytes();
         let plum_relations_row_r = sqlx::query!(
             r#"SELECT
                 plum_relations_rowid,
@@ -736,7 +1421,7 @@ impl DatahostStorage for DatahostStorageSQLite {
                 source_plum_body_seal
             FROM plum_relations
             WHERE plum_relations_seal = $1"#,
-            plum_relations_seal.value.sha256sum.value
+            plum_relations_seal_bytes
         )
         .fetch_one(&mut *sqlite_transaction)
         .await;
@@ -772,9 +1457,11 @@ impl DatahostStorage for DatahostStorageSQLite {
                 }
                 let plum_relation_flags = record.plum_relation_flags as u32;
                 Ok(PlumRelationFlagsMapping {
-                    target_plum_head_seal: PlumHeadSeal::from(Seal::from(Sha256Sum::from(
+                    target_plum_head_seal: seal_from_db(
                         record.target_plum_head_seal,
-                    ))),
+                        "plum_relation_mappings",
+                        "target_plum_head_seal",
+                    )?,
                     plum_relation_flags_raw: PlumRelationFlags::try_from(plum_relation_flags)
                         .map_err(|e| DatahostStorageError::InvalidValueInDB {
                             table_name: "plum_relation_mappings",
@@ -789,9 +1476,11 @@ impl DatahostStorage for DatahostStorageSQLite {
 
         Ok(Some(PlumRelations {
             plum_relations_nonce_o: plum_relations_row.plum_relations_nonce_o.map(Nonce::from),
-            source_plum_body_seal: PlumBodySeal::from(Seal::from(Sha256Sum::from(
+            source_plum_body_seal: seal_from_db(
                 plum_relations_row.source_plum_body_seal,
-            ))),
+                "plum_relations",
+                "source_plum_body_seal",
+            )?,
             plum_relation_flags_mapping_v,
         }))
     }
@@ -802,6 +1491,7 @@ impl DatahostStorage for DatahostStorageSQLite {
     ) -> Result<Option<PlumBody>, DatahostStorageError> {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
 
+        let plum_body_seal_bytes = plum_body_seal.value.as_bytes();
         let record_r = sqlx::query!(
             r#"SELECT
                 plum_body_nonce_o,
@@ -809,10 +1499,11 @@ impl DatahostStorage for DatahostStorageSQLite {
                 plum_body_content_class,
                 plum_body_content_format,
                 plum_body_content_encoding,
-                plum_body_content_byte_v
+                plum_body_content_byte_vo,
+                plum_body_content_digest_o
             FROM plum_bodies
             WHERE plum_body_seal = $1"#,
-            plum_body_seal.value.sha256sum.value
+            plum_body_seal_bytes
         )
         .fetch_one(&mut *sqlite_transaction)
         .await;
@@ -826,7 +1517,24 @@ impl DatahostStorage for DatahostStorageSQLite {
                         reason: "column value was negative".to_string(),
                     });
                 }
-                Ok(Some(PlumBody {
+                // A NULL plum_body_content_byte_vo means that the content is in the plum body content dir.
+                let (content_byte_v, is_from_plum_body_content_dir) = match record
+                    .plum_body_content_byte_vo
+                {
+                    Some(content_byte_v) => (content_byte_v, false),
+                    None => {
+                        let plum_body_content_dir = self
+                                .plum_body_content_dir_o
+                                .as_ref()
+                                .ok_or_else(|| DatahostStorageError::InvalidValueInDB {
+                                    table_name: "plum_bodies",
+                                    column_name: "plum_body_content_byte_vo",
+                                    reason: "column value was NULL, meaning the content is stored in the plum body content dir, but no plum body content dir was specified".to_string(),
+                                })?;
+                        (plum_body_content_dir.load_content(plum_body_seal)?, true)
+                    }
+                };
+                let plum_body = PlumBody {
                     plum_body_nonce_o: record.plum_body_nonce_o.map(Nonce::from),
                     plum_body_content: Content {
                         content_metadata: ContentMetadata {
@@ -837,14 +1545,326 @@ impl DatahostStorage for DatahostStorageSQLite {
                                 record.plum_body_content_encoding,
                             ),
                         },
-                        content_byte_v: record.plum_body_content_byte_v,
+                        content_byte_v,
                     },
-                }))
+                };
+                // Files on disk are much more exposed to tampering and corruption than the DB, so check them
+                // against plum_body_content_digest_o, which (unlike a keyed PlumBodySeal) can be checked
+                // without the SealKey.
+                if is_from_plum_body_content_dir {
+                    let stored_plum_body_content_digest = Seal::try_from_bytes(
+                        record.plum_body_content_digest_o.as_deref().ok_or_else(|| {
+                            DatahostStorageError::InvalidValueInDB {
+                                table_name: "plum_bodies",
+                                column_name: "plum_body_content_digest_o",
+                                reason: "column value was NULL, but the content is stored in the plum body content dir".to_string(),
+                            }
+                        })?,
+                    )
+                    .map_err(|e| DatahostStorageError::InvalidValueInDB {
+                        table_name: "plum_bodies",
+                        column_name: "plum_body_content_digest_o",
+                        reason: e.to_string(),
+                    })?;
+                    if stored_plum_body_content_digest.is_keyed()
+                        || !plum_body_content_digest(&plum_body.plum_body_content.content_byte_v)
+                            .constant_time_eq(&stored_plum_body_content_digest)
+                    {
+                        return Err(DatahostStorageError::PlumBodyIntegrityCheckFailed(
+                            plum_body_seal.clone(),
+                        ));
+                    }
+                }
+                Ok(Some(plum_body))
             }
             Err(sqlx::Error::RowNotFound) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
+    async fn load_option_plum_head_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal_v: &[PlumHeadSeal],
+    ) -> Result<Vec<Option<PlumHead>>, DatahostStorageError> {
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+
+        let mut plum_head_m = HashMap::new();
+        for plum_head_seal_chunk in plum_head_seal_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"SELECT
+                    plum_head_seal,
+                    plum_head_nonce_o,
+                    plum_metadata_seal,
+                    plum_relations_seal,
+                    plum_body_seal
+                FROM plum_heads
+                WHERE plum_head_seal IN ("#,
+            );
+            let mut separated = query_builder.separated(", ");
+            for plum_head_seal in plum_head_seal_chunk {
+                separated.push_bind(plum_head_seal.value.as_bytes());
+            }
+            separated.push_unseparated(")");
+            // Columns are plum_head_seal, plum_head_nonce_o, plum_metadata_seal, plum_relations_seal, plum_body_seal.
+            for (
+                plum_head_seal,
+                plum_head_nonce_o,
+                plum_metadata_seal,
+                plum_relations_seal,
+                plum_body_seal,
+            ) in query_builder
+                .build_query_as::<(Vec<u8>, Option<Vec<u8>>, Vec<u8>, Vec<u8>, Vec<u8>)>()
+                .fetch_all(&mut *sqlite_transaction)
+                .await?
+            {
+                plum_head_m.insert(
+                    plum_head_seal,
+                    PlumHead {
+                        plum_head_nonce_o: plum_head_nonce_o.map(Nonce::from),
+                        plum_metadata_seal: seal_from_db(
+                            plum_metadata_seal,
+                            "plum_heads",
+                            "plum_metadata_seal",
+                        )?,
+                        plum_relations_seal: seal_from_db(
+                            plum_relations_seal,
+                            "plum_heads",
+                            "plum_relations_seal",
+                        )?,
+                        plum_body_seal: seal_from_db(
+                            plum_body_seal,
+                            "plum_heads",
+                            "plum_body_seal",
+                        )?,
+                    },
+                );
+            }
+        }
+        Ok(plum_head_seal_v
+            .iter()
+            .map(|plum_head_seal| plum_head_m.get(plum_head_seal.value.as_bytes()).cloned())
+            .collect())
+    }
+    async fn load_option_plum_relations_v(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal_v: &[PlumRelationsSeal],
+    ) -> Result<Vec<Option<PlumRelations>>, DatahostStorageError> {
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+
+        // Columns are plum_relations_rowid, plum_relations_seal, plum_relations_nonce_o, source_plum_body_seal.
+        let mut plum_relations_row_v = Vec::new();
+        for plum_relations_seal_chunk in plum_relations_seal_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"SELECT plum_relations_rowid, plum_relations_seal, plum_relations_nonce_o, source_plum_body_seal
+                FROM plum_relations
+                WHERE plum_relations_seal IN ("#,
+            );
+            let mut separated = query_builder.separated(", ");
+            for plum_relations_seal in plum_relations_seal_chunk {
+                separated.push_bind(plum_relations_seal.value.as_bytes());
+            }
+            separated.push_unseparated(")");
+            plum_relations_row_v.extend(
+                query_builder
+                    .build_query_as::<(i64, Vec<u8>, Option<Vec<u8>>, Vec<u8>)>()
+                    .fetch_all(&mut *sqlite_transaction)
+                    .await?,
+            );
+        }
+
+        let mut plum_relation_flags_mapping_vm: HashMap<i64, Vec<PlumRelationFlagsMapping>> =
+            HashMap::new();
+        for plum_relations_row_chunk in plum_relations_row_v.chunks(STORE_CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"SELECT plum_relations_rowid, target_plum_head_seal, plum_relation_flags
+                FROM plum_relation_mappings
+                WHERE plum_relations_rowid IN ("#,
+            );
+            let mut separated = query_builder.separated(", ");
+            for (plum_relations_rowid, _, _, _) in plum_relations_row_chunk {
+                separated.push_bind(*plum_relations_rowid);
+            }
+            separated.push_unseparated(") ORDER BY plum_relation_mappings_rowid");
+            // Columns are plum_relations_rowid, target_plum_head_seal, plum_relation_flags.
+            for (plum_relations_rowid, target_plum_head_seal, plum_relation_flags) in query_builder
+                .build_query_as::<(i64, Vec<u8>, i64)>()
+                .fetch_all(&mut *sqlite_transaction)
+                .await?
+            {
+                if plum_relation_flags < 0 || plum_relation_flags > (u32::MAX as i64) {
+                    return Err(DatahostStorageError::InvalidValueInDB {
+                        table_name: "plum_relation_mappings",
+                        column_name: "plum_relation_flags",
+                        reason: "column value was outside of the range of u32".to_string(),
+                    });
+                }
+                let plum_relation_flags = PlumRelationFlags::try_from(plum_relation_flags as u32)
+                    .map_err(|e| DatahostStorageError::InvalidValueInDB {
+                    table_name: "plum_relation_mappings",
+                    column_name: "plum_relation_flags",
+                    reason: e.to_string(),
+                })?;
+                plum_relation_flags_mapping_vm
+                    .entry(plum_relations_rowid)
+                    .or_default()
+                    .push(PlumRelationFlagsMapping {
+                        target_plum_head_seal: seal_from_db(
+                            target_plum_head_seal,
+                            "plum_relation_mappings",
+                            "target_plum_head_seal",
+                        )?,
+                        plum_relation_flags_raw: plum_relation_flags.into(),
+                    });
+            }
+        }
+
+        let plum_relations_m = plum_relations_row_v
+            .into_iter()
+            .map(
+                |(
+                    plum_relations_rowid,
+                    plum_relations_seal,
+                    plum_relations_nonce_o,
+                    source_plum_body_seal,
+                )| {
+                    Ok((
+                        plum_relations_seal,
+                        PlumRelations {
+                            plum_relations_nonce_o: plum_relations_nonce_o.map(Nonce::from),
+                            source_plum_body_seal: seal_from_db(
+                                source_plum_body_seal,
+                                "plum_relations",
+                                "source_plum_body_seal",
+                            )?,
+                            plum_relation_flags_mapping_v: plum_relation_flags_mapping_vm
+                                .remove(&plum_relations_rowid)
+                                .unwrap_or_default(),
+                        },
+                    ))
+                },
+            )
+            .collect::<Result<HashMap<Vec<u8>, PlumRelations>, DatahostStorageError>>()?;
+        Ok(plum_relations_seal_v
+            .iter()
+            .map(|plum_relations_seal| {
+                plum_relations_m
+                    .get(plum_relations_seal.value.as_bytes())
+                    .cloned()
+            })
+            .collect())
+    }
+
+    async fn delete_plum_head(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageSQLite::delete_plum_head; plum_head_seal: {}",
+            plum_head_seal
+        );
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_head_seal_bytes = plum_head_seal.value.as_bytes();
+        sqlx::query!(
+            r#"DELETE FROM plum_heads WHERE plum_head_seal = $1"#,
+            plum_head_seal_bytes
+        )
+        .execute(sqlite_transaction)
+        .await?;
+        Ok(())
+    }
+    async fn delete_plum_metadata(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_metadata_seal: &PlumMetadataSeal,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageSQLite::delete_plum_metadata; plum_metadata_seal: {}",
+            plum_metadata_seal
+        );
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_metadata_seal_bytes = plum_metadata_seal.value.as_bytes();
+        sqlx::query!(
+            r#"DELETE FROM plum_metadatas WHERE plum_metadata_seal = $1"#,
+            plum_metadata_seal_bytes
+        )
+        .execute(sqlite_transaction)
+        .await?;
+        Ok(())
+    }
+    async fn delete_plum_relations(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_relations_seal: &PlumRelationsSeal,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageSQLite::delete_plum_relations; plum_relations_seal: {}",
+            plum_relations_seal
+        );
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        // The plum_relation_mappings rows refer to the plum_relations row, so they have to be deleted first.
+        let plum_relations_seal_bytes = plum_relations_seal.value.as_bytes();
+        sqlx::query!(
+            r#"DELETE FROM plum_relation_mappings
+            WHERE plum_relations_rowid IN (
+                SELECT plum_relations_rowid FROM plum_relations WHERE plum_relations_seal = $1
+            )"#,
+            plum_relations_seal_bytes
+        )
+        .execute(&mut *sqlite_transaction)
+        .await?;
+        let plum_relations_seal_bytes = plum_relations_seal.value.as_bytes();
+        sqlx::query!(
+            r#"DELETE FROM plum_relations WHERE plum_relations_seal = $1"#,
+            plum_relations_seal_bytes
+        )
+        .execute(&mut *sqlite_transaction)
+        .await?;
+        Ok(())
+    }
+    async fn delete_plum_body(
+        &self,
+        transaction: &mut dyn DatahostStorageTransaction,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<(), DatahostStorageError> {
+        log::trace!(
+            "DatahostStorageSQLite::delete_plum_body; plum_body_seal: {}",
+            plum_body_seal
+        );
+        let sqlite_transaction = sqlite_transaction_mut(transaction);
+        let plum_body_seal_bytes = plum_body_seal.value.as_bytes();
+        let is_in_plum_body_content_dir_o = sqlx::query!(
+            r#"SELECT plum_body_content_byte_vo IS NULL AS "is_in_plum_body_content_dir!: bool"
+            FROM plum_bodies
+            WHERE plum_body_seal = $1"#,
+            plum_body_seal_bytes
+        )
+        .fetch_optional(&mut *sqlite_transaction)
+        .await?
+        .map(|record| record.is_in_plum_body_content_dir);
+        let is_in_plum_body_content_dir = is_in_plum_body_content_dir_o == Some(true);
+        if is_in_plum_body_content_dir && self.plum_body_content_dir_o.is_none() {
+            // Deleting the row would leave the content file behind.
+            return Err(DatahostStorageError::InvalidValueInDB {
+                table_name: "plum_bodies",
+                column_name: "plum_body_content_byte_vo",
+                reason: "column value was NULL, meaning the content is stored in the plum body content dir, but no plum body content dir was specified".to_string(),
+            });
+        }
+        let plum_body_seal_bytes = plum_body_seal.value.as_bytes();
+        sqlx::query!(
+            r#"DELETE FROM plum_bodies WHERE plum_body_seal = $1"#,
+            plum_body_seal_bytes
+        )
+        .execute(&mut *sqlite_transaction)
+        .await?;
+        // If the content is in the plum body content dir, then delete that too, but only upon commit.
+        if is_in_plum_body_content_dir {
+            sqlite_transaction.delete_content_file_on_commit(plum_body_seal.clone());
+        }
+        Ok(())
+    }
 
     async fn has_path_state(
         &self,
@@ -885,9 +1905,11 @@ impl DatahostStorage for DatahostStorageSQLite {
             Ok(path_states_row) => Ok(Some({
                 PathState {
                     path: Path::from(path_states_row.path),
-                    current_state_plum_head_seal: PlumHeadSeal::from(Seal::from(Sha256Sum::from(
+                    current_state_plum_head_seal: seal_from_db(
                         path_states_row.current_state_plum_head_seal,
-                    ))),
+                        "path_states",
+                        "current_state_plum_head_seal",
+                    )?,
                 }
             })),
             Err(sqlx::Error::RowNotFound) => Ok(None),
@@ -902,6 +1924,8 @@ impl DatahostStorage for DatahostStorageSQLite {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
         let now = UnixNanoseconds::now();
 
+        let current_state_plum_head_seal_bytes =
+            path_state.current_state_plum_head_seal.value.as_bytes();
         let query_result_r = sqlx::query!(
             r#"INSERT INTO path_states (
                 row_inserted_at,
@@ -912,11 +1936,7 @@ impl DatahostStorage for DatahostStorageSQLite {
             now.value,
             now.value,
             path_state.path.value,
-            path_state
-                .current_state_plum_head_seal
-                .value
-                .sha256sum
-                .value,
+            current_state_plum_head_seal_bytes,
         )
         .execute(sqlite_transaction)
         .await;
@@ -946,17 +1966,15 @@ impl DatahostStorage for DatahostStorageSQLite {
         let sqlite_transaction = sqlite_transaction_mut(transaction);
         let now = UnixNanoseconds::now();
 
+        let current_state_plum_head_seal_bytes =
+            path_state.current_state_plum_head_seal.value.as_bytes();
         let query_result_r = sqlx::query!(
             r#"UPDATE path_states
             SET row_updated_at = $1,
                 current_state_plum_head_seal = $2
             WHERE path = $3"#,
             now.value,
-            path_state
-                .current_state_plum_head_seal
-                .value
-                .sha256sum
-                .value,
+            current_state_plum_head_seal_bytes,
             path_state.path.value
         )
         .execute(sqlite_transaction)
@@ -1,21 +1,119 @@
+use crate::{ContentFilePin, PlumBodyContentDir};
 use futures::{future::BoxFuture, stream::BoxStream};
-use idp_datahost_storage::{DatahostStorageError, DatahostStorageTransaction};
+use idp_datahost_storage::{DatahostStorageError, DatahostStorageTransaction, OnCommitCallbacks};
+use idp_proto::PlumBodySeal;
+use std::{collections::BTreeSet, sync::Arc};
 
-#[derive(
-    Debug, derive_more::Deref, derive_more::DerefMut, derive_more::From, derive_more::Into,
-)]
-pub struct DatahostStorageSQLiteTransaction(sqlx::Transaction<'static, sqlx::Sqlite>);
+#[derive(derive_more::Deref, derive_more::DerefMut)]
+pub struct DatahostStorageSQLiteTransaction {
+    #[deref]
+    #[deref_mut]
+    transaction: sqlx::Transaction<'static, sqlx::Sqlite>,
+    /// Used after commit to check whether deleted content files are still referenced (see commit).
+    pool: sqlx::SqlitePool,
+    plum_body_content_dir_o: Option<Arc<PlumBodyContentDir>>,
+    /// Pins the content files (see PlumBodyContentDir) stored in this transaction, so that no other
+    /// transaction deletes them before this one finishes.
+    content_file_pin_v: Vec<ContentFilePin>,
+    /// PlumBodySeal-s of content files whose plum_bodies rows were deleted in this transaction.  These
+    /// can only be deleted once the transaction is committed, otherwise a rollback would leave the DB
+    /// referring to content files that no longer exist.
+    plum_body_seal_to_delete_on_commit_s: BTreeSet<PlumBodySeal>,
+    on_commit_callbacks: OnCommitCallbacks,
+}
+
+impl DatahostStorageSQLiteTransaction {
+    pub(crate) fn new(
+        transaction: sqlx::Transaction<'static, sqlx::Sqlite>,
+        pool: sqlx::SqlitePool,
+        plum_body_content_dir_o: Option<Arc<PlumBodyContentDir>>,
+    ) -> Self {
+        Self {
+            transaction,
+            pool,
+            plum_body_content_dir_o,
+            content_file_pin_v: Vec::new(),
+            plum_body_seal_to_delete_on_commit_s: BTreeSet::new(),
+            on_commit_callbacks: OnCommitCallbacks::default(),
+        }
+    }
+    /// Records that the content file for the given PlumBodySeal was stored in this transaction, which
+    /// also cancels any deletion of it queued earlier in this transaction.
+    pub(crate) fn content_file_stored(&mut self, content_file_pin: ContentFilePin) {
+        self.plum_body_seal_to_delete_on_commit_s
+            .remove(content_file_pin.plum_body_seal());
+        self.content_file_pin_v.push(content_file_pin);
+    }
+    pub(crate) fn delete_content_file_on_commit(&mut self, plum_body_seal: PlumBodySeal) {
+        self.plum_body_seal_to_delete_on_commit_s
+            .insert(plum_body_seal);
+    }
+}
+
+impl std::fmt::Debug for DatahostStorageSQLiteTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("DatahostStorageSQLiteTransaction")
+            .field("transaction", &self.transaction)
+            .field(
+                "plum_body_seal_to_delete_on_commit_s",
+                &self.plum_body_seal_to_delete_on_commit_s,
+            )
+            .finish_non_exhaustive()
+    }
+}
 
 #[async_trait::async_trait]
 impl DatahostStorageTransaction for DatahostStorageSQLiteTransaction {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self as &mut dyn std::any::Any
     }
+    fn on_commit(&mut self, callback: Box<dyn FnOnce() + Send>) {
+        self.on_commit_callbacks.push(callback);
+    }
     async fn commit(self: Box<Self>) -> Result<(), DatahostStorageError> {
-        Ok((*self).0.commit().await?)
+        let DatahostStorageSQLiteTransaction {
+            transaction,
+            pool,
+            plum_body_content_dir_o,
+            content_file_pin_v,
+            plum_body_seal_to_delete_on_commit_s,
+            on_commit_callbacks,
+        } = *self;
+        transaction.commit().await?;
+        on_commit_callbacks.run();
+        if let Some(plum_body_content_dir) = plum_body_content_dir_o {
+            if plum_body_seal_to_delete_on_commit_s.is_empty() {
+                // Dropping the ContentFilePin-s releases them.
+                return Ok(());
+            }
+            // Another transaction may have stored (and even committed) the same PlumBody since the
+            // plum_bodies row was deleted, so each content file is only deleted if it's still
+            // unreferenced.
+            match pool.acquire().await {
+                Ok(mut connection) => {
+                    plum_body_content_dir
+                        .delete_content_files(
+                            &mut connection,
+                            content_file_pin_v,
+                            plum_body_seal_to_delete_on_commit_s,
+                        )
+                        .await;
+                }
+                Err(e) => {
+                    // The transaction is already committed, so this can't be an error.  The content
+                    // files are simply orphaned.
+                    log::warn!(
+                        "failed to acquire connection to delete content files after commit; error was: {}",
+                        e
+                    );
+                }
+            }
+        }
+        Ok(())
     }
     async fn rollback(self: Box<Self>) -> Result<(), DatahostStorageError> {
-        Ok((*self).0.rollback().await?)
+        // Dropping the ContentFilePin-s releases them.
+        Ok((*self).transaction.rollback().await?)
     }
 }
 
@@ -35,7 +133,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
     where
         'c: 'e,
     {
-        self.0.describe(sql)
+        self.transaction.describe(sql)
     }
     fn execute<'e, 'q: 'e, E: 'q>(
         self,
@@ -45,7 +143,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.execute(query)
+        self.transaction.execute(query)
     }
     fn execute_many<'e, 'q: 'e, E: 'q>(
         self,
@@ -55,7 +153,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.execute_many(query)
+        self.transaction.execute_many(query)
     }
     fn fetch<'e, 'q: 'e, E: 'q>(
         self,
@@ -65,7 +163,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.fetch(query)
+        self.transaction.fetch(query)
     }
     fn fetch_all<'e, 'q: 'e, E: 'q>(
         self,
@@ -75,7 +173,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.fetch_all(query)
+        self.transaction.fetch_all(query)
     }
     fn fetch_many<'e, 'q: 'e, E: 'q>(
         self,
@@ -94,7 +192,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.fetch_many(query)
+        self.transaction.fetch_many(query)
     }
     fn fetch_one<'e, 'q: 'e, E: 'q>(
         self,
@@ -104,7 +202,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.fetch_one(query)
+        self.transaction.fetch_one(query)
     }
     fn fetch_optional<'e, 'q: 'e, E: 'q>(
         self,
@@ -114,7 +212,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
         'c: 'e,
         E: sqlx::Execute<'q, Self::Database>,
     {
-        self.0.fetch_optional(query)
+        self.transaction.fetch_optional(query)
     }
     fn prepare<'e, 'q: 'e>(
         self,
@@ -126,7 +224,7 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
     where
         'c: 'e,
     {
-        self.0.prepare(query)
+        self.transaction.prepare(query)
     }
     fn prepare_with<'e, 'q: 'e>(
         self,
@@ -139,6 +237,6 @@ impl<'c> sqlx::Executor<'c> for &'c mut DatahostStorageSQLiteTransaction {
     where
         'c: 'e,
     {
-        self.0.prepare_with(sql, parameters)
+        self.transaction.prepare_with(sql, parameters)
     }
 }
@@ -1,8 +1,10 @@
 mod datahost_storage_sqlite;
 mod datahost_storage_sqlite_transaction;
+mod plum_body_content_dir;
 
 pub use datahost_storage_sqlite::DatahostStorageSQLite;
 
 pub(crate) use datahost_storage_sqlite_transaction::{
     sqlite_transaction_mut, DatahostStorageSQLiteTransaction,
 };
+pub(crate) use plum_body_content_dir::{ContentFilePin, PlumBodyContentDir};
@@ -0,0 +1,193 @@
+use idp_proto::PlumBodySeal;
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Write,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Used to make temp file names unique within this process.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Content-addressed, on-disk store for PlumBody content bytes, keyed by PlumBodySeal and sharded
+/// in the style of git objects, i.e. the content for the PlumBodySeal whose (lowercase) hex value is
+/// `0123abcd...` is stored in the file `<root_dir>/01/23abcd...`.  Files are written to a temp file
+/// first and then renamed into place, so a file is never observed in a partially written state.
+///
+/// Note that because content is addressed by PlumBodySeal, a file never has to be overwritten, and a
+/// file left behind by a transaction that was rolled back is harmless.
+///
+/// A content file that a transaction stores is pinned (see ContentFilePin) until that transaction
+/// finishes, and a content file is only deleted (see delete_content_files) if it's not pinned and no
+/// plum_bodies row refers to it.  Both happen under delete_lock, so a content file can't be deleted
+/// out from under a concurrent transaction that stores the same PlumBody.
+pub(crate) struct PlumBodyContentDir {
+    root_dir: PathBuf,
+    delete_lock: futures::lock::Mutex<()>,
+    /// Number of unfinished transactions that have stored the content for each PlumBodySeal.
+    pin_count_mm: std::sync::Mutex<HashMap<PlumBodySeal, usize>>,
+}
+
+/// Keeps the content file for a PlumBodySeal from being deleted while it exists.  This is held by the
+/// transaction that stored the content, and dropped when that transaction finishes.
+pub(crate) struct ContentFilePin {
+    plum_body_content_dir: Arc<PlumBodyContentDir>,
+    plum_body_seal: PlumBodySeal,
+}
+
+impl ContentFilePin {
+    pub(crate) fn plum_body_seal(&self) -> &PlumBodySeal {
+        &self.plum_body_seal
+    }
+}
+
+impl Drop for ContentFilePin {
+    fn drop(&mut self) {
+        let mut pin_count_m = self.plum_body_content_dir.pin_count_mm.lock().unwrap();
+        let pin_count = pin_count_m
+            .get_mut(&self.plum_body_seal)
+            .expect("programmer error: ContentFilePin without a pin count");
+        *pin_count -= 1;
+        if *pin_count == 0 {
+            pin_count_m.remove(&self.plum_body_seal);
+        }
+    }
+}
+
+impl PlumBodyContentDir {
+    pub(crate) fn new(root_dir: PathBuf) -> Self {
+        Self {
+            root_dir,
+            delete_lock: futures::lock::Mutex::new(()),
+            pin_count_mm: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+    /// Returns the path of the file that the content for the given PlumBodySeal is stored in.
+    pub(crate) fn content_file_path(&self, plum_body_seal: &PlumBodySeal) -> PathBuf {
+        let hex = plum_body_seal
+            .value
+            .as_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        let (shard, file_name) = hex.split_at(2);
+        self.root_dir.join(shard).join(file_name)
+    }
+    /// Stores the given content bytes, unless they're already present, and returns a ContentFilePin
+    /// which the storing transaction must hold until it finishes.
+    pub(crate) async fn store_content(
+        self: &Arc<Self>,
+        plum_body_seal: &PlumBodySeal,
+        content_byte_v: &[u8],
+    ) -> std::io::Result<ContentFilePin> {
+        let _delete_guard = self.delete_lock.lock().await;
+        *self
+            .pin_count_mm
+            .lock()
+            .unwrap()
+            .entry(plum_body_seal.clone())
+            .or_insert(0) += 1;
+        // If the write fails, then this unpins the content file.
+        let content_file_pin = ContentFilePin {
+            plum_body_content_dir: self.clone(),
+            plum_body_seal: plum_body_seal.clone(),
+        };
+        self.write_content_file(plum_body_seal, content_byte_v)?;
+        Ok(content_file_pin)
+    }
+    /// Deletes the content files for the given PlumBodySeal-s, except for those that are pinned by
+    /// other transactions, or that a plum_bodies row still refers to (e.g. one stored by a transaction
+    /// that has since committed).  The given ContentFilePin-s (those of the committing transaction) are
+    /// released first.  The connection is taken as a parameter so that it's acquired before
+    /// delete_lock is, otherwise this could wait on the pool while holding delete_lock.
+    pub(crate) async fn delete_content_files(
+        &self,
+        connection: &mut sqlx::SqliteConnection,
+        content_file_pin_v: Vec<ContentFilePin>,
+        plum_body_seal_s: BTreeSet<PlumBodySeal>,
+    ) {
+        let _delete_guard = self.delete_lock.lock().await;
+        std::mem::drop(content_file_pin_v);
+        for plum_body_seal in plum_body_seal_s {
+            if self
+                .pin_count_mm
+                .lock()
+                .unwrap()
+                .contains_key(&plum_body_seal)
+            {
+                continue;
+            }
+            let content_file_path = self.content_file_path(&plum_body_seal);
+            let plum_body_seal_bytes = plum_body_seal.value.as_bytes();
+            let is_referenced_r = sqlx::query!(
+                r#"SELECT EXISTS(
+                    SELECT 1 FROM plum_bodies WHERE plum_body_seal = $1
+                ) AS "is_referenced!: bool""#,
+                plum_body_seal_bytes
+            )
+            .fetch_one(&mut *connection)
+            .await
+            .map(|record| record.is_referenced);
+            match is_referenced_r {
+                Ok(false) => {}
+                Ok(true) => continue,
+                Err(e) => {
+                    // The transaction is already committed, so this can't be an error.  Leaving the
+                    // content file is the safe choice.
+                    log::warn!(
+                        "failed to check whether content file {:?} is still referenced, so not deleting it; error was: {}",
+                        content_file_path,
+                        e
+                    );
+                    continue;
+                }
+            }
+            if let Err(e) = std::fs::remove_file(&content_file_path) {
+                // The transaction is already committed, so this can't be an error.  The content file
+                // is simply orphaned.
+                log::warn!(
+                    "failed to delete content file {:?} after commit; error was: {}",
+                    content_file_path,
+                    e
+                );
+            }
+        }
+    }
+    fn write_content_file(
+        &self,
+        plum_body_seal: &PlumBodySeal,
+        content_byte_v: &[u8],
+    ) -> std::io::Result<()> {
+        let content_file_path = self.content_file_path(plum_body_seal);
+        if content_file_path.is_file() {
+            return Ok(());
+        }
+        let shard_dir = content_file_path.parent().unwrap();
+        std::fs::create_dir_all(shard_dir)?;
+        let temp_file_path = shard_dir.join(format!(
+            ".{}.tmp.{}.{}",
+            content_file_path.file_name().unwrap().to_str().unwrap(),
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let write_r = (|| {
+            let mut temp_file = std::fs::File::create(&temp_file_path)?;
+            temp_file.write_all(content_byte_v)?;
+            temp_file.sync_all()?;
+            std::fs::rename(&temp_file_path, &content_file_path)
+        })();
+        if write_r.is_err() {
+            // Don't leave the temp file lying around.  There's nothing else to do if this fails.
+            let _ = std::fs::remove_file(&temp_file_path);
+        }
+        write_r
+    }
+    /// Loads the content bytes for the given PlumBodySeal.  Note that this doesn't check the integrity
+    /// of the content, since the PlumBodySeal is computed over the whole PlumBody, not just its content.
+    pub(crate) fn load_content(&self, plum_body_seal: &PlumBodySeal) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.content_file_path(plum_body_seal))
+    }
+}
@@ -0,0 +1,783 @@
+use futures::TryStreamExt;
+use idp_datahost_storage::{
+    DatahostStorage, DatahostStorageError, PathStatesFilter, PlumFilter, PlumHeadsFilter,
+};
+use idp_datahost_storage_sqlite::DatahostStorageSQLite;
+use idp_proto::{
+    ContentClass, ContentEncoding, ContentFormat, Path, PathState, Plum, PlumBuilder, PlumHead,
+    PlumHeadSeal, Seal, SealKey, TextEncoding,
+};
+use uuid::Uuid;
+
+/// This will run once at load time (i.e. presumably before main function is called).
+#[ctor::ctor]
+fn overall_init() {
+    env_logger::init();
+}
+
+fn test_plum(name: &str) -> Plum {
+    PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &format!("{}, {}.", name, Uuid::new_v4()),
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass")
+}
+
+/// Returns a new, uniquely named dir path within the system temp dir.  The dir itself isn't created.
+fn temp_dir_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{}_{}", name, Uuid::new_v4()))
+}
+
+/// Returns the paths of all the regular files under the given dir, recursively.
+fn file_paths_under(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut file_path_v = Vec::new();
+    for dir_entry_r in std::fs::read_dir(dir).expect("pass") {
+        let path = dir_entry_r.expect("pass").path();
+        if path.is_dir() {
+            file_path_v.extend(file_paths_under(&path));
+        } else {
+            file_path_v.push(path);
+        }
+    }
+    file_path_v
+}
+
+#[tokio::test]
+async fn test_plum_body_content_dir_roundtrip() {
+    let plum_body_content_dir = temp_dir_path("test_plum_body_content_dir_roundtrip");
+    let datahost_storage = DatahostStorageSQLite::new_in_memory()
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+    let plum = test_plum("test_plum_body_content_dir_roundtrip");
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    let plum_body_seal = datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+
+    // The content should be in a single file, sharded by the first byte of the PlumBodySeal.
+    let file_path_v = file_paths_under(&plum_body_content_dir);
+    assert_eq!(file_path_v.len(), 1);
+    // The file name is the hex of the PlumBodySeal's bytes, i.e. its Hex string form without the
+    // multibase prefix.
+    let hex_string = plum_body_seal.to_string_using(TextEncoding::Hex);
+    let hex = hex_string.strip_prefix('f').expect("pass");
+    assert_eq!(
+        file_path_v[0],
+        plum_body_content_dir.join(&hex[..2]).join(&hex[2..])
+    );
+    assert_eq!(
+        std::fs::read(&file_path_v[0]).expect("pass"),
+        plum.plum_body.plum_body_content.content_byte_v
+    );
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    assert!(datahost_storage
+        .has_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+        .expect("pass"));
+    assert_eq!(
+        datahost_storage
+            .load_plum_body(transaction.as_mut(), &plum_body_seal)
+            .await
+            .expect("pass"),
+        plum.plum_body
+    );
+    transaction.commit().await.expect("pass");
+
+    std::fs::remove_dir_all(&plum_body_content_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_body_content_dir_integrity_check() {
+    let plum_body_content_dir = temp_dir_path("test_plum_body_content_dir_integrity_check");
+    let datahost_storage = DatahostStorageSQLite::new_in_memory()
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+    let plum = test_plum("test_plum_body_content_dir_integrity_check");
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    let plum_body_seal = datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+
+    // Tamper with the content file.
+    let file_path_v = file_paths_under(&plum_body_content_dir);
+    assert_eq!(file_path_v.len(), 1);
+    std::fs::write(&file_path_v[0], b"tampered content").expect("pass");
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    match datahost_storage
+        .load_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+    {
+        Err(DatahostStorageError::PlumBodyIntegrityCheckFailed(failed_plum_body_seal)) => {
+            assert_eq!(failed_plum_body_seal, plum_body_seal);
+        }
+        _ => panic!("expected DatahostStorageError::PlumBodyIntegrityCheckFailed"),
+    }
+
+    // Deleting the content file should also cause the load to fail.
+    std::fs::remove_file(&file_path_v[0]).expect("pass");
+    match datahost_storage
+        .load_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+    {
+        Err(DatahostStorageError::IoError(_)) => {}
+        _ => panic!("expected DatahostStorageError::IoError"),
+    }
+    transaction.rollback().await.expect("pass");
+
+    std::fs::remove_dir_all(&plum_body_content_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_body_content_dir_integrity_check_with_keyed_seal() {
+    let plum_body_content_dir =
+        temp_dir_path("test_plum_body_content_dir_integrity_check_with_keyed_seal");
+    let datahost_storage = DatahostStorageSQLite::new_in_memory()
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+    let seal_key = SealKey::new(
+        "test_plum_body_content_dir_integrity_check_with_keyed_seal".to_string(),
+        Uuid::new_v4().as_bytes().to_vec(),
+    );
+    let keyed_test_plum = |name: &str| {
+        PlumBuilder::new()
+            .with_seal_key(seal_key.clone())
+            .with_plum_relations_and_plum_body_content_from(
+                &format!("{}, {}.", name, Uuid::new_v4()),
+                Some(&ContentFormat::charset_us_ascii()),
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass")
+    };
+    let plum_0 = keyed_test_plum("test_plum_body_content_dir_integrity_check_with_keyed_seal 0");
+    let plum_1 = keyed_test_plum("test_plum_body_content_dir_integrity_check_with_keyed_seal 1");
+    assert!(plum_0.plum_head.plum_body_seal.value.is_keyed());
+    assert!(plum_1.plum_head.plum_body_seal.value.is_keyed());
+
+    // Store one PlumBody using store_plum_body and the other using store_plum_v, since they insert
+    // plum_bodies rows separately.
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum_0.plum_body,
+            &plum_0.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    datahost_storage
+        .store_plum_v(transaction.as_mut(), std::slice::from_ref(&plum_1))
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+
+    // The SealKey isn't needed to load an intact PlumBody.
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    for plum in [&plum_0, &plum_1] {
+        assert_eq!(
+            datahost_storage
+                .load_plum_body(transaction.as_mut(), &plum.plum_head.plum_body_seal)
+                .await
+                .expect("pass"),
+            plum.plum_body
+        );
+    }
+    transaction.commit().await.expect("pass");
+
+    // Tamper with the content files.  Even though the keyed PlumBodySeal-s can't be checked without
+    // the SealKey, the tampering should still be detected.
+    let file_path_v = file_paths_under(&plum_body_content_dir);
+    assert_eq!(file_path_v.len(), 2);
+    for file_path in file_path_v.iter() {
+        std::fs::write(file_path, b"tampered content").expect("pass");
+    }
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    for plum in [&plum_0, &plum_1] {
+        match datahost_storage
+            .load_plum_body(transaction.as_mut(), &plum.plum_head.plum_body_seal)
+            .await
+        {
+            Err(DatahostStorageError::PlumBodyIntegrityCheckFailed(failed_plum_body_seal)) => {
+                assert_eq!(failed_plum_body_seal, plum.plum_head.plum_body_seal);
+            }
+            _ => panic!("expected DatahostStorageError::PlumBodyIntegrityCheckFailed"),
+        }
+    }
+    transaction.rollback().await.expect("pass");
+
+    std::fs::remove_dir_all(&plum_body_content_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_body_stored_inline() {
+    let datahost_storage = DatahostStorageSQLite::new_in_memory().await.expect("pass");
+    let plum = test_plum("test_plum_body_stored_inline");
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    let plum_body_seal = datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(
+        datahost_storage
+            .load_plum_body(transaction.as_mut(), &plum_body_seal)
+            .await
+            .expect("pass"),
+        plum.plum_body
+    );
+    transaction.commit().await.expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_body_content_dir_delete() {
+    let plum_body_content_dir = temp_dir_path("test_plum_body_content_dir_delete");
+    let datahost_storage = DatahostStorageSQLite::new_in_memory()
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+    let plum = test_plum("test_plum_body_content_dir_delete");
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    let plum_body_seal = datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+    assert_eq!(file_paths_under(&plum_body_content_dir).len(), 1);
+
+    // The content file must survive a rolled-back delete.
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    datahost_storage
+        .delete_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+        .expect("pass");
+    transaction.rollback().await.expect("pass");
+    assert_eq!(file_paths_under(&plum_body_content_dir).len(), 1);
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    datahost_storage
+        .delete_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+        .expect("pass");
+    // The content file is only deleted upon commit.
+    assert_eq!(file_paths_under(&plum_body_content_dir).len(), 1);
+    transaction.commit().await.expect("pass");
+    assert!(file_paths_under(&plum_body_content_dir).is_empty());
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    assert!(!datahost_storage
+        .has_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+        .expect("pass"));
+    transaction.commit().await.expect("pass");
+
+    std::fs::remove_dir_all(&plum_body_content_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_body_content_dir_delete_without_plum_body_content_dir() {
+    let temp_dir = temp_dir_path("test_plum_body_content_dir_delete_without_plum_body_content_dir");
+    std::fs::create_dir_all(&temp_dir).expect("pass");
+    let database_url = format!("sqlite:{}?mode=rwc", temp_dir.join("test.db").display());
+    let plum_body_content_dir = temp_dir.join("plum_body_content_dir");
+    let plum = test_plum("test_plum_body_content_dir_delete_without_plum_body_content_dir");
+
+    let datahost_storage = DatahostStorageSQLite::connect_and_run_migrations(&database_url)
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    let plum_body_seal = datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+    drop(datahost_storage);
+
+    // Without the plum body content dir, deleting the PlumBody would orphan its content file, so
+    // it's an error, and the PlumBody is kept.
+    let datahost_storage = DatahostStorageSQLite::connect_and_run_migrations(&database_url)
+        .await
+        .expect("pass");
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    assert!(matches!(
+        datahost_storage
+            .delete_plum_body(transaction.as_mut(), &plum_body_seal)
+            .await
+            .expect_err("fail"),
+        DatahostStorageError::InvalidValueInDB {
+            table_name: "plum_bodies",
+            column_name: "plum_body_content_byte_vo",
+            ..
+        }
+    ));
+    transaction.rollback().await.expect("pass");
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    assert!(datahost_storage
+        .has_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+        .expect("pass"));
+    transaction.commit().await.expect("pass");
+    assert_eq!(file_paths_under(&plum_body_content_dir).len(), 1);
+    drop(datahost_storage);
+
+    std::fs::remove_dir_all(&temp_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_body_content_dir_delete_then_store() {
+    let plum_body_content_dir = temp_dir_path("test_plum_body_content_dir_delete_then_store");
+    let datahost_storage = DatahostStorageSQLite::new_in_memory()
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+    let plum = test_plum("test_plum_body_content_dir_delete_then_store");
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    let plum_body_seal = datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+
+    // Storing the PlumBody again in the same transaction that deleted it must cancel the deletion of
+    // its content file, since the content file is already present and so isn't rewritten.
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    datahost_storage
+        .delete_plum_body(transaction.as_mut(), &plum_body_seal)
+        .await
+        .expect("pass");
+    datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &plum.plum_body,
+            &plum.plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+    assert_eq!(file_paths_under(&plum_body_content_dir).len(), 1);
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    assert_eq!(
+        datahost_storage
+            .load_plum_body(transaction.as_mut(), &plum_body_seal)
+            .await
+            .expect("pass"),
+        plum.plum_body
+    );
+    transaction.commit().await.expect("pass");
+
+    std::fs::remove_dir_all(&plum_body_content_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_select_pagination() {
+    let datahost_storage = DatahostStorageSQLite::new_in_memory().await.expect("pass");
+    let plum_v = (0..5)
+        .map(|i| test_plum(&format!("test_select_pagination {}", i)))
+        .collect::<Vec<_>>();
+    let plum_head_seal_v = plum_v
+        .iter()
+        .map(|plum| PlumHeadSeal::from(&plum.plum_head))
+        .collect::<Vec<_>>();
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    for plum in plum_v.iter() {
+        datahost_storage
+            .store_plum(transaction.as_mut(), plum)
+            .await
+            .expect("pass");
+    }
+    for page_size in [1, 2, 5, 6] {
+        assert_eq!(
+            datahost_storage
+                .select_plum_heads(transaction.as_mut(), PlumHeadsFilter::default(), page_size)
+                .map_ok(|(_, plum_head_seal, _)| plum_head_seal)
+                .try_collect::<Vec<_>>()
+                .await
+                .expect("pass"),
+            plum_head_seal_v
+        );
+    }
+    assert_eq!(
+        datahost_storage
+            .select_plum_heads(
+                transaction.as_mut(),
+                PlumHeadsFilter {
+                    plum_filter: PlumFilter {
+                        content_class_o: Some(ContentClass::text_plain()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                2,
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("pass")
+            .len(),
+        plum_v.len()
+    );
+
+    for (i, plum_head_seal) in plum_head_seal_v.iter().enumerate() {
+        datahost_storage
+            .insert_path_state(
+                transaction.as_mut(),
+                &PathState {
+                    path: Path::from(format!("test_select_pagination-{}", i)),
+                    current_state_plum_head_seal: plum_head_seal.clone(),
+                },
+            )
+            .await
+            .expect("pass");
+    }
+    let select_page = datahost_storage
+        .select_path_states_page(transaction.as_mut(), &PathStatesFilter::default(), None, 3)
+        .await
+        .expect("pass");
+    assert_eq!(select_page.row_v.len(), 3);
+    assert!(select_page.next_cursor_o.is_some());
+    let select_page = datahost_storage
+        .select_path_states_page(
+            transaction.as_mut(),
+            &PathStatesFilter::default(),
+            select_page.next_cursor_o.as_ref(),
+            3,
+        )
+        .await
+        .expect("pass");
+    assert_eq!(select_page.row_v.len(), 2);
+    assert!(select_page.next_cursor_o.is_none());
+    assert_eq!(
+        select_page.row_v[1].2.current_state_plum_head_seal,
+        plum_head_seal_v[4]
+    );
+    transaction.commit().await.expect("pass");
+}
+
+#[tokio::test]
+async fn test_plum_filter_prefers_plum_metadata() {
+    let datahost_storage = DatahostStorageSQLite::new_in_memory().await.expect("pass");
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+
+    // A Plum can't be built with PlumMetadata and PlumBody that disagree on the content metadata,
+    // so store the components separately: text/plain PlumMetadata with an application/octet-stream
+    // PlumBody.
+    let text_plum = test_plum("test_plum_filter_prefers_plum_metadata");
+    let binary_plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &Uuid::new_v4().as_bytes().to_vec(),
+            None,
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    let plum_head = PlumHead {
+        plum_head_nonce_o: None,
+        plum_metadata_seal: text_plum.plum_head.plum_metadata_seal.clone(),
+        plum_relations_seal: binary_plum.plum_head.plum_relations_seal.clone(),
+        plum_body_seal: binary_plum.plum_head.plum_body_seal.clone(),
+    };
+    datahost_storage
+        .store_plum_metadata(
+            transaction.as_mut(),
+            &text_plum.plum_metadata,
+            &plum_head.plum_metadata_seal,
+        )
+        .await
+        .expect("pass");
+    datahost_storage
+        .store_plum_relations(
+            transaction.as_mut(),
+            &binary_plum.plum_relations,
+            &plum_head.plum_relations_seal,
+        )
+        .await
+        .expect("pass");
+    datahost_storage
+        .store_plum_body(
+            transaction.as_mut(),
+            &binary_plum.plum_body,
+            &plum_head.plum_body_seal,
+        )
+        .await
+        .expect("pass");
+    let plum_head_seal = datahost_storage
+        .store_plum_head(transaction.as_mut(), &plum_head)
+        .await
+        .expect("pass");
+
+    // The PlumMetadata determines the content class and format; the PlumBody is ignored.
+    for (content_class, should_match) in [
+        (ContentClass::text_plain(), true),
+        (
+            ContentClass::from("application/octet-stream".to_string()),
+            false,
+        ),
+    ] {
+        assert_eq!(
+            datahost_storage
+                .select_plum_head_seals_matching(
+                    transaction.as_mut(),
+                    &PlumFilter {
+                        content_class_o: Some(content_class),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .expect("pass")
+                .contains(&plum_head_seal),
+            should_match
+        );
+    }
+    assert!(datahost_storage
+        .select_plum_head_seals_matching(
+            transaction.as_mut(),
+            &PlumFilter {
+                content_format_o: Some(ContentFormat::charset_us_ascii()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("pass")
+        .contains(&plum_head_seal));
+
+    transaction.commit().await.expect("pass");
+}
+
+#[tokio::test]
+async fn test_batch_loads() {
+    let datahost_storage = DatahostStorageSQLite::new_in_memory().await.expect("pass");
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+
+    // Use more Plums than fit in a single IN clause, so that the chunking is exercised.  Every
+    // third Plum isn't stored, so that missing entries are interleaved with present ones.
+    let plum_v = (0..300)
+        .map(|i| test_plum(&format!("test_batch_loads {}", i)))
+        .collect::<Vec<Plum>>();
+    for plum in plum_v.iter().step_by(3) {
+        datahost_storage
+            .store_plum(transaction.as_mut(), plum)
+            .await
+            .expect("pass");
+    }
+    let stored_index_s = (0..plum_v.len())
+        .step_by(3)
+        .collect::<std::collections::HashSet<usize>>();
+    let is_stored = |i: usize| stored_index_s.contains(&i);
+    // Distinct Plums may share a PlumMetadata, so track which PlumMetadata-s are stored separately.
+    let stored_plum_metadata_seal_v = plum_v
+        .iter()
+        .step_by(3)
+        .map(|plum| plum.plum_head.plum_metadata_seal.clone())
+        .collect::<Vec<_>>();
+
+    // The results must correspond elementwise with the requested seals, so request them in the
+    // reverse of the order they were stored in.
+    let plum_head_seal_v = plum_v
+        .iter()
+        .rev()
+        .map(|plum| PlumHeadSeal::from(&plum.plum_head))
+        .collect::<Vec<_>>();
+    let plum_head_ov = datahost_storage
+        .load_option_plum_head_v(transaction.as_mut(), &plum_head_seal_v)
+        .await
+        .expect("pass");
+    let plum_relations_ov = datahost_storage
+        .load_option_plum_relations_v(
+            transaction.as_mut(),
+            &plum_v
+                .iter()
+                .rev()
+                .map(|plum| plum.plum_head.plum_relations_seal.clone())
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .expect("pass");
+    let has_plum_metadata_v = datahost_storage
+        .has_plum_metadata_v(
+            transaction.as_mut(),
+            &plum_v
+                .iter()
+                .rev()
+                .map(|plum| plum.plum_head.plum_metadata_seal.clone())
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .expect("pass");
+    let has_plum_body_v = datahost_storage
+        .has_plum_body_v(
+            transaction.as_mut(),
+            &plum_v
+                .iter()
+                .rev()
+                .map(|plum| plum.plum_head.plum_body_seal.clone())
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .expect("pass");
+    assert_eq!(plum_head_ov.len(), plum_v.len());
+    assert_eq!(plum_relations_ov.len(), plum_v.len());
+    assert_eq!(has_plum_metadata_v.len(), plum_v.len());
+    assert_eq!(has_plum_body_v.len(), plum_v.len());
+    for (j, plum) in plum_v.iter().rev().enumerate() {
+        let i = plum_v.len() - 1 - j;
+        if is_stored(i) {
+            assert_eq!(plum_head_ov[j].as_ref(), Some(&plum.plum_head));
+            assert_eq!(plum_relations_ov[j].as_ref(), Some(&plum.plum_relations));
+        } else {
+            assert!(plum_head_ov[j].is_none());
+            assert!(plum_relations_ov[j].is_none());
+        }
+        assert_eq!(
+            has_plum_metadata_v[j],
+            stored_plum_metadata_seal_v.contains(&plum.plum_head.plum_metadata_seal)
+        );
+        assert_eq!(has_plum_body_v[j], is_stored(i));
+    }
+
+    transaction.commit().await.expect("pass");
+}
+
+#[tokio::test]
+async fn test_store_plum_v_with_plum_body_content_dir() {
+    let plum_body_content_dir = temp_dir_path("test_store_plum_v_with_plum_body_content_dir");
+    let datahost_storage = DatahostStorageSQLite::new_in_memory()
+        .await
+        .expect("pass")
+        .with_plum_body_content_dir(&plum_body_content_dir);
+
+    // Use more Plums than fit in a single multi-row INSERT, store some of them beforehand, and
+    // include duplicates, so that the chunking and the skipping of present rows are exercised.
+    let plum_v = (0..150)
+        .map(|i| {
+            test_plum(&format!(
+                "test_store_plum_v_with_plum_body_content_dir {}",
+                i
+            ))
+        })
+        .collect::<Vec<Plum>>();
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    for plum in plum_v.iter().take(10) {
+        datahost_storage
+            .store_plum(transaction.as_mut(), plum)
+            .await
+            .expect("pass");
+    }
+    let mut stored_plum_v = plum_v.clone();
+    stored_plum_v.extend(plum_v.iter().take(20).cloned());
+    let plum_head_seal_v = datahost_storage
+        .store_plum_v(transaction.as_mut(), &stored_plum_v)
+        .await
+        .expect("pass");
+    transaction.commit().await.expect("pass");
+    assert_eq!(plum_head_seal_v.len(), stored_plum_v.len());
+
+    // Each distinct PlumBody's content is stored in exactly one file.
+    assert_eq!(file_paths_under(&plum_body_content_dir).len(), plum_v.len());
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    for (plum, plum_head_seal) in stored_plum_v.iter().zip(plum_head_seal_v.iter()) {
+        assert_eq!(plum_head_seal, &PlumHeadSeal::from(&plum.plum_head));
+        assert_eq!(
+            &datahost_storage
+                .load_plum(transaction.as_mut(), plum_head_seal)
+                .await
+                .expect("pass"),
+            plum
+        );
+    }
+    transaction.commit().await.expect("pass");
+
+    std::fs::remove_dir_all(&plum_body_content_dir).expect("pass");
+}
+
+#[tokio::test]
+async fn test_malformed_stored_seal() {
+    let datahost_storage = DatahostStorageSQLite::new_in_memory().await.expect("pass");
+    let path = Path::from("test_malformed_stored_seal".to_string());
+    // This multihash is truncated, so the stored seal can't be decoded when it's loaded.
+    let malformed_plum_head_seal = PlumHeadSeal::from(Seal {
+        sha256sum: None,
+        multihash: Some(vec![0x80]),
+        keyed_seal: None,
+    });
+
+    let mut transaction = datahost_storage.begin_transaction().await.expect("pass");
+    datahost_storage
+        .insert_path_state(
+            transaction.as_mut(),
+            &PathState {
+                path: path.clone(),
+                current_state_plum_head_seal: malformed_plum_head_seal,
+            },
+        )
+        .await
+        .expect("pass");
+    let is_invalid_value_in_db = |datahost_storage_error: DatahostStorageError| {
+        matches!(
+            datahost_storage_error,
+            DatahostStorageError::InvalidValueInDB {
+                table_name: "path_states",
+                column_name: "current_state_plum_head_seal",
+                ..
+            }
+        )
+    };
+    assert!(is_invalid_value_in_db(
+        datahost_storage
+            .load_option_path_state(transaction.as_mut(), &path)
+            .await
+            .expect_err("fail")
+    ));
+    assert!(is_invalid_value_in_db(
+        datahost_storage
+            .select_path_states_page(transaction.as_mut(), &PathStatesFilter::default(), None, 1)
+            .await
+            .expect_err("fail")
+    ));
+    transaction.commit().await.expect("pass");
+}
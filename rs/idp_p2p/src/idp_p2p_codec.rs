@@ -0,0 +1,111 @@
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use idp_proto::{PeerRequest, PeerResponse};
+use libp2p::StreamProtocol;
+use prost::Message;
+
+/// The libp2p protocol used by IDPPeer.
+pub const IDP_P2P_PROTOCOL: StreamProtocol = StreamProtocol::new("/idp/p2p/0.1.0");
+/// The maximum size of an encoded PeerRequest or PeerResponse.
+pub const IDP_P2P_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+/// The size at which IDPPeer stops adding Plums or PlumBodyChunk-s to a single StorePlumsRequest,
+/// LoadPlumsResponse, StorePlumBodyChunksRequest, or LoadPlumBodyChunksResponse.  Since a Plum whose
+/// PlumBody requires chunking is never sent whole, a batch can only exceed this by a single Plum or
+/// PlumBodyChunk, so this leaves plenty of room below IDP_P2P_MAX_MESSAGE_SIZE.
+pub const IDP_P2P_BATCH_SIZE: usize = 16 * 1024 * 1024;
+
+/// Encodes each PeerRequest and PeerResponse as a protobuf which takes up the rest of its stream,
+/// i.e. the writer closes the stream after writing it.
+#[derive(Clone, Debug, Default)]
+pub struct IDPP2PCodec;
+
+async fn read_message<M, T>(io: &mut T) -> std::io::Result<M>
+where
+    M: Message + Default,
+    T: AsyncRead + Unpin + Send,
+{
+    let mut buffer = Vec::new();
+    io.take(IDP_P2P_MAX_MESSAGE_SIZE as u64 + 1)
+        .read_to_end(&mut buffer)
+        .await?;
+    if buffer.len() > IDP_P2P_MAX_MESSAGE_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "message exceeded the maximum size of {} bytes",
+                IDP_P2P_MAX_MESSAGE_SIZE
+            ),
+        ));
+    }
+    M::decode(buffer.as_slice())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+async fn write_message<M, T>(io: &mut T, message: M) -> std::io::Result<()>
+where
+    M: Message,
+    T: AsyncWrite + Unpin + Send,
+{
+    let buffer = message.encode_to_vec();
+    if buffer.len() > IDP_P2P_MAX_MESSAGE_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "message of {} bytes exceeds the maximum size of {} bytes",
+                buffer.len(),
+                IDP_P2P_MAX_MESSAGE_SIZE
+            ),
+        ));
+    }
+    io.write_all(&buffer).await?;
+    io.close().await
+}
+
+#[async_trait::async_trait]
+impl libp2p::request_response::Codec for IDPP2PCodec {
+    type Protocol = StreamProtocol;
+    type Request = PeerRequest;
+    type Response = PeerResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> std::io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io).await
+    }
+    async fn read_response<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> std::io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io).await
+    }
+    async fn write_request<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+        req: Self::Request,
+    ) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, req).await
+    }
+    async fn write_response<T>(
+        &mut self,
+        _protocol: &Self::Protocol,
+        io: &mut T,
+        res: Self::Response,
+    ) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, res).await
+    }
+}
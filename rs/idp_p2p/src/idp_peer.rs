@@ -0,0 +1,917 @@
+use crate::{
+    idp_peer_event_loop::{Command, IDPPeerEventLoop},
+    peer_error::error_from_peer_error,
+    IDPP2PCodec, IDP_P2P_BATCH_SIZE, IDP_P2P_PROTOCOL,
+};
+use anyhow::Result;
+use async_lock::RwLock;
+use futures::channel::{mpsc, oneshot};
+use idp_core::{topologically_sorted, BranchError, BranchUpdate, Datahost, TransferReport};
+use idp_datahost_storage::DatahostStorageError;
+use idp_proto::{
+    loaded_plum, peer_request, peer_response, BranchCreateRequest, BranchDeleteRequest,
+    BranchGetHeadRequest, BranchSetHeadRequest, HasPlumsRequest, IsDependencyCompleteRequest,
+    LoadPlumBodyChunksRequest, LoadPlumsRequest, LoadedPlum, PartialPlumBody, Path, PathState,
+    PathStateGetRequest, PeerRequest, Plum, PlumBody, PlumBodyChunk, PlumBodyOffset, PlumBodySeal,
+    PlumHead, PlumHeadSeal, StorePlumBodyChunksRequest, StorePlumsRequest, PLUM_BODY_CHUNK_SIZE,
+};
+use libp2p::{
+    core::{transport::MemoryTransport, upgrade::Version},
+    identity::Keypair,
+    noise, request_response, tcp, yamux, Multiaddr, PeerId, Transport,
+};
+use prost::Message;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    sync::Arc,
+    time::Duration,
+};
+
+/// How long to wait for the response to a PeerRequest.  Because batches of up to IDP_P2P_BATCH_SIZE
+/// are transferred, this is generous.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// The maximum number of pushed Plums whose PlumHead, PlumMetadata, and PlumRelations are loaded at
+/// once, as in IDPClient::push.  Their PlumBody-s are loaded one at a time.
+const PUSH_LOAD_BATCH_SIZE: usize = 256;
+/// How long a connection to another peer is kept open while there are no requests in flight.  There
+/// is no peer discovery, so a closed connection has to be re-established using IDPPeer::dial.
+const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// A peer-to-peer alternative to IDPClient and IDPServer.  Each IDPPeer serves the push, pull, and
+/// branch operations of other peers using its Datahost (like IDPServer), and can perform those
+/// operations against other peers (like IDPClient).  The peers communicate using libp2p, over TCP
+/// (e.g. "/ip4/127.0.0.1/tcp/0") or the in-process memory transport (e.g. "/memory/1234").  There is
+/// no peer discovery; a peer must be dialed using its PeerId and an explicit Multiaddr.  Note that
+/// there is no access control yet; any connected peer may read and write.
+pub struct IDPPeer {
+    datahost_la: Arc<RwLock<Datahost>>,
+    local_peer_id: PeerId,
+    command_tx: mpsc::UnboundedSender<Command>,
+}
+
+impl IDPPeer {
+    /// Creates an IDPPeer having the identity given by keypair, and spawns (using tokio::spawn) the
+    /// task that drives its network activity, which runs until the IDPPeer is dropped.  The IDPPeer
+    /// can dial other peers right away, but only accepts connections once listen_on is called.
+    pub fn new(datahost_la: Arc<RwLock<Datahost>>, keypair: Keypair) -> Result<Self> {
+        let swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )?
+            .with_other_transport(
+                |keypair| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(MemoryTransport::default()
+                        .upgrade(Version::V1)
+                        .authenticate(noise::Config::new(keypair)?)
+                        .multiplex(yamux::Config::default()))
+                },
+            )?
+            .with_behaviour(|_| {
+                request_response::Behaviour::with_codec(
+                    IDPP2PCodec,
+                    [(IDP_P2P_PROTOCOL, request_response::ProtocolSupport::Full)],
+                    request_response::Config::default().with_request_timeout(REQUEST_TIMEOUT),
+                )
+            })?
+            .with_swarm_config(|config| {
+                config.with_idle_connection_timeout(IDLE_CONNECTION_TIMEOUT)
+            })
+            .build();
+        let local_peer_id = *swarm.local_peer_id();
+        let (command_tx, command_rx) = mpsc::unbounded();
+        tokio::spawn(IDPPeerEventLoop::new(swarm, datahost_la.clone(), command_rx).run());
+        Ok(Self {
+            datahost_la,
+            local_peer_id,
+            command_tx,
+        })
+    }
+    pub fn local_peer_id(&self) -> &PeerId {
+        &self.local_peer_id
+    }
+    /// Starts accepting connections from other peers at the given Multiaddr, and returns the address
+    /// actually listened on (which differs from multiaddr if e.g. it specifies port 0).
+    pub async fn listen_on(&self, multiaddr: Multiaddr) -> Result<Multiaddr> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.send_command(Command::ListenOn {
+            multiaddr,
+            result_tx,
+        })?;
+        result_rx.await?
+    }
+    /// Connects to the given peer at the given Multiaddr, if not already connected.  This must be
+    /// done before making any requests of that peer.
+    pub async fn dial(&self, peer_id: &PeerId, multiaddr: Multiaddr) -> Result<()> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.send_command(Command::Dial {
+            peer_id: *peer_id,
+            multiaddr,
+            result_tx,
+        })?;
+        result_rx.await?
+    }
+    fn send_command(&self, command: Command) -> Result<()> {
+        self.command_tx
+            .unbounded_send(command)
+            .map_err(|_| anyhow::anyhow!("IDPPeer {} has stopped", self.local_peer_id))
+    }
+    /// Sends a PeerRequest having the given value to the given peer, and returns the value of its
+    /// PeerResponse.  If the peer responded with a PeerError, then it's converted into an error as in
+    /// IDPClient, so the DatahostStorageError, PathStateError, or BranchError that occurred on the peer
+    /// can be recovered using anyhow::Error::downcast_ref.
+    async fn send_request(
+        &self,
+        peer_id: &PeerId,
+        peer_request_value: peer_request::Value,
+    ) -> Result<peer_response::Value> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.send_command(Command::SendRequest {
+            peer_id: *peer_id,
+            peer_request: PeerRequest {
+                value: Some(peer_request_value),
+            },
+            result_tx,
+        })?;
+        match result_rx.await??.value {
+            Some(peer_response::Value::Error(peer_error)) => Err(error_from_peer_error(peer_error)),
+            Some(peer_response_value) => Ok(peer_response_value),
+            None => Err(anyhow::anyhow!(
+                "peer {} returned a PeerResponse with no value",
+                peer_id
+            )),
+        }
+    }
+    /// Pushes the given Plum and its dependencies (i.e. the Plums it refers to via CONTENT_DEPENDENCY
+    /// or METADATA_DEPENDENCY relations, recursively) to the given peer.  As in IDPClient::push, the
+    /// Plums are offered a level at a time (using IsDependencyComplete), and only the Plums that the
+    /// peer doesn't have along with all their dependencies are recursed into.  The wanted Plums are
+    /// then sent dependencies-first, in StorePlumsRequest-s of up to IDP_P2P_BATCH_SIZE, each of which
+    /// the peer stores in its own transaction, so that the peer never has a Plum without its
+    /// dependencies.  Each PlumBody is only sent once, and one too large to fit in a single message is
+    /// sent as PlumBodyChunk-s (using StorePlumBodyChunks) before the Plums that use it.
+    pub async fn push(
+        &self,
+        peer_id: &PeerId,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<TransferReport> {
+        let mask = idp_proto::PlumRelationFlags::CONTENT_DEPENDENCY
+            | idp_proto::PlumRelationFlags::METADATA_DEPENDENCY;
+
+        // Negotiate which Plums to send.
+        let mut offered_plum_head_seal_s = HashSet::new();
+        offered_plum_head_seal_s.insert(plum_head_seal.clone());
+        let mut level_plum_head_seal_v = vec![plum_head_seal.clone()];
+        // The Plums that the peer wants, in the order they were offered.
+        let mut wanted_plum_head_seal_v = Vec::new();
+        // Maps each wanted Plum to the Plums it refers to (via masked relations).
+        let mut target_plum_head_seal_vm: HashMap<PlumHeadSeal, Vec<PlumHeadSeal>> = HashMap::new();
+        while !level_plum_head_seal_v.is_empty() {
+            let is_dependency_complete_v = self
+                .is_dependency_complete(peer_id, &level_plum_head_seal_v)
+                .await?;
+            let wanted_level_plum_head_seal_v = level_plum_head_seal_v
+                .into_iter()
+                .zip(is_dependency_complete_v)
+                .filter_map(|(offered_plum_head_seal, is_dependency_complete)| {
+                    if is_dependency_complete {
+                        None
+                    } else {
+                        Some(offered_plum_head_seal)
+                    }
+                })
+                .collect::<Vec<PlumHeadSeal>>();
+
+            let relation_target_vv = self
+                .datahost_la
+                .read()
+                .await
+                .load_relation_targets_v(&wanted_level_plum_head_seal_v, mask, None)
+                .await?;
+            let mut next_level_plum_head_seal_v = Vec::new();
+            for (wanted_plum_head_seal, relation_target_v) in
+                wanted_level_plum_head_seal_v.iter().zip(relation_target_vv)
+            {
+                let mut target_plum_head_seal_v = Vec::with_capacity(relation_target_v.len());
+                for (target_plum_head_seal, _) in relation_target_v {
+                    if offered_plum_head_seal_s.insert(target_plum_head_seal.clone()) {
+                        next_level_plum_head_seal_v.push(target_plum_head_seal.clone());
+                    }
+                    target_plum_head_seal_v.push(target_plum_head_seal);
+                }
+                target_plum_head_seal_vm
+                    .insert(wanted_plum_head_seal.clone(), target_plum_head_seal_v);
+            }
+            wanted_plum_head_seal_v.extend(wanted_level_plum_head_seal_v);
+            level_plum_head_seal_v = next_level_plum_head_seal_v;
+        }
+        log::trace!(
+            "IDPPeer::push({}, {}); peer wants {} Plums",
+            peer_id,
+            plum_head_seal,
+            wanted_plum_head_seal_v.len()
+        );
+
+        // Send the wanted Plums, dependencies first.  Each PlumBody is only loaded once it's about to
+        // be sent.
+        let mut send_plum_head_seal_v =
+            topologically_sorted(&wanted_plum_head_seal_v, &target_plum_head_seal_vm);
+        send_plum_head_seal_v.reverse();
+        let mut transfer_report = TransferReport::default();
+        let mut sent_plum_body_seal_s = HashSet::new();
+        let mut store_plums_request = StorePlumsRequest::default();
+        let mut batch_size = 0;
+        for send_plum_head_seal_c in send_plum_head_seal_v.chunks(PUSH_LOAD_BATCH_SIZE) {
+            let plum_head_metadata_and_relations_v = self
+                .datahost_la
+                .read()
+                .await
+                .load_plum_head_metadata_and_relations_v(send_plum_head_seal_c, None)
+                .await?;
+            for plum_head_metadata_and_relations in plum_head_metadata_and_relations_v {
+                let plum_body_seal = &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+                // Only the first Plum having a given PlumBody sends it.  The peer stores the Plums of
+                // each StorePlumsRequest before those having an already-sent PlumBody.
+                if sent_plum_body_seal_s.insert(plum_body_seal.clone()) {
+                    let plum_body = self
+                        .datahost_la
+                        .read()
+                        .await
+                        .load_option_plum_body(plum_body_seal, None)
+                        .await?
+                        .ok_or_else(|| anyhow::anyhow!("PlumBody {} not found", plum_body_seal))?;
+                    transfer_report.plum_body_count += 1;
+                    if plum_body.requires_chunking() {
+                        self.store_plum_body_chunks(peer_id, plum_body_seal, &plum_body)
+                            .await?;
+                        batch_size += plum_head_metadata_and_relations.encoded_len();
+                        store_plums_request
+                            .plum_head_metadata_and_relations_v
+                            .push(plum_head_metadata_and_relations);
+                    } else {
+                        let plum = Plum {
+                            plum_head: plum_head_metadata_and_relations.plum_head,
+                            plum_metadata: plum_head_metadata_and_relations.plum_metadata,
+                            plum_relations: plum_head_metadata_and_relations.plum_relations,
+                            plum_body,
+                        };
+                        batch_size += plum.encoded_len();
+                        store_plums_request.plum_v.push(plum);
+                    }
+                } else {
+                    batch_size += plum_head_metadata_and_relations.encoded_len();
+                    store_plums_request
+                        .plum_head_metadata_and_relations_v
+                        .push(plum_head_metadata_and_relations);
+                }
+                transfer_report.plum_count += 1;
+                if batch_size >= IDP_P2P_BATCH_SIZE {
+                    self.store_plums(peer_id, std::mem::take(&mut store_plums_request))
+                        .await?;
+                    batch_size = 0;
+                }
+            }
+        }
+        if batch_size > 0 {
+            self.store_plums(peer_id, store_plums_request).await?;
+        }
+
+        Ok(transfer_report)
+    }
+    /// Sends the given StorePlumsRequest to the given peer.
+    async fn store_plums(
+        &self,
+        peer_id: &PeerId,
+        store_plums_request: StorePlumsRequest,
+    ) -> Result<()> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::StorePlums(store_plums_request),
+            )
+            .await?
+        {
+            peer_response::Value::StorePlums(_) => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to StorePlums",
+                peer_id
+            )),
+        }
+    }
+    /// Sends the given PlumBody to the given peer as PlumBodyChunk-s, in StorePlumBodyChunksRequest-s
+    /// of up to IDP_P2P_BATCH_SIZE.  The PlumBodyChunk-s are produced as they're sent.
+    async fn store_plum_body_chunks(
+        &self,
+        peer_id: &PeerId,
+        plum_body_seal: &PlumBodySeal,
+        plum_body: &PlumBody,
+    ) -> Result<()> {
+        let mut plum_body_chunk_v = Vec::new();
+        let mut batch_size = 0;
+        for plum_body_chunk in
+            PlumBodyChunk::chunk_i_from(plum_body_seal, plum_body, 0, PLUM_BODY_CHUNK_SIZE)?
+        {
+            batch_size += plum_body_chunk.encoded_len();
+            plum_body_chunk_v.push(plum_body_chunk);
+            if batch_size >= IDP_P2P_BATCH_SIZE {
+                self.send_plum_body_chunks(peer_id, std::mem::take(&mut plum_body_chunk_v))
+                    .await?;
+                batch_size = 0;
+            }
+        }
+        if !plum_body_chunk_v.is_empty() {
+            self.send_plum_body_chunks(peer_id, plum_body_chunk_v)
+                .await?;
+        }
+        Ok(())
+    }
+    async fn send_plum_body_chunks(
+        &self,
+        peer_id: &PeerId,
+        plum_body_chunk_v: Vec<PlumBodyChunk>,
+    ) -> Result<()> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::StorePlumBodyChunks(StorePlumBodyChunksRequest {
+                    plum_body_chunk_v,
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::StorePlumBodyChunks(_) => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to StorePlumBodyChunks",
+                peer_id
+            )),
+        }
+    }
+    /// Pulls the given Plum and its dependencies (i.e. the Plums it refers to via CONTENT_DEPENDENCY
+    /// or METADATA_DEPENDENCY relations, recursively) from the given peer.  As in IDPClient::pull, the
+    /// Plums are requested a level at a time, and each received Plum is verified and its PlumRelations
+    /// are inspected to determine which of its dependencies to request next, skipping any that are
+    /// already present in the local Datahost.  The peer responds to each LoadPlumsRequest with as many
+    /// of the requested Plums as fit in IDP_P2P_BATCH_SIZE, and the rest are requested again.  A
+    /// PlumBody too large to fit in a single message is requested separately (using
+    /// LoadPlumBodyChunks), unless it's already present locally.  The received Plums are only stored
+    /// (in a single transaction) once all of them have been received.
+    pub async fn pull(
+        &self,
+        peer_id: &PeerId,
+        plum_head_seal: &PlumHeadSeal,
+    ) -> Result<TransferReport> {
+        let mask = idp_proto::PlumRelationFlags::CONTENT_DEPENDENCY
+            | idp_proto::PlumRelationFlags::METADATA_DEPENDENCY;
+
+        let mut visited_plum_head_seal_s = HashSet::new();
+        visited_plum_head_seal_s.insert(plum_head_seal.clone());
+        let mut candidate_plum_head_seal_v = vec![plum_head_seal.clone()];
+        // All received data, in the order it was requested.
+        let mut received_plum_v = Vec::new();
+        let mut received_plum_head_metadata_and_relations_v = Vec::new();
+        let mut received_plum_body_m = HashMap::new();
+        while !candidate_plum_head_seal_v.is_empty() {
+            let has_plum_v = self
+                .datahost_la
+                .read()
+                .await
+                .has_plum_v(&candidate_plum_head_seal_v, None)
+                .await?;
+            let level_plum_head_seal_v = candidate_plum_head_seal_v
+                .into_iter()
+                .zip(has_plum_v)
+                .filter_map(|(candidate_plum_head_seal, has_plum)| {
+                    if has_plum {
+                        None
+                    } else {
+                        Some(candidate_plum_head_seal)
+                    }
+                })
+                .collect::<Vec<PlumHeadSeal>>();
+            candidate_plum_head_seal_v = Vec::new();
+
+            let mut remaining_plum_head_seal_v = level_plum_head_seal_v.as_slice();
+            while !remaining_plum_head_seal_v.is_empty() {
+                let loaded_plum_v = self.load_plums(peer_id, remaining_plum_head_seal_v).await?;
+                let (requested_plum_head_seal_v, rest) =
+                    remaining_plum_head_seal_v.split_at(loaded_plum_v.len());
+                remaining_plum_head_seal_v = rest;
+                for (requested_plum_head_seal, loaded_plum) in
+                    requested_plum_head_seal_v.iter().zip(loaded_plum_v)
+                {
+                    let plum_relations = match loaded_plum.value {
+                        Some(loaded_plum::Value::Plum(plum)) => {
+                            // Compute the PlumHeadSeal ourselves, and check the other seals, so that
+                            // the PlumRelations can be trusted.
+                            ensure_requested(peer_id, requested_plum_head_seal, &plum.plum_head)?;
+                            plum.verify_with_seal_key_provider(
+                                self.datahost_la.read().await.seal_key_provider(),
+                            )?;
+                            let plum_relations = plum.plum_relations.clone();
+                            received_plum_v.push(plum);
+                            plum_relations
+                        }
+                        Some(loaded_plum::Value::PlumHeadMetadataAndRelations(
+                            plum_head_metadata_and_relations,
+                        )) => {
+                            ensure_requested(
+                                peer_id,
+                                requested_plum_head_seal,
+                                &plum_head_metadata_and_relations.plum_head,
+                            )?;
+                            plum_head_metadata_and_relations.verify_with_seal_key_provider(
+                                self.datahost_la.read().await.seal_key_provider(),
+                            )?;
+                            let plum_body_seal =
+                                &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+                            if !received_plum_body_m.contains_key(plum_body_seal)
+                                && !self
+                                    .datahost_la
+                                    .read()
+                                    .await
+                                    .has_plum_body(plum_body_seal, None)
+                                    .await?
+                            {
+                                let plum_body =
+                                    self.load_plum_body_chunks(peer_id, plum_body_seal).await?;
+                                plum_head_metadata_and_relations
+                                    .verify_plum_body_with_seal_key_provider(
+                                        &plum_body,
+                                        self.datahost_la.read().await.seal_key_provider(),
+                                    )?;
+                                received_plum_body_m.insert(plum_body_seal.clone(), plum_body);
+                            }
+                            let plum_relations =
+                                plum_head_metadata_and_relations.plum_relations.clone();
+                            received_plum_head_metadata_and_relations_v
+                                .push(plum_head_metadata_and_relations);
+                            plum_relations
+                        }
+                        None => {
+                            anyhow::bail!("peer {} returned a LoadedPlum with no value", peer_id);
+                        }
+                    };
+
+                    for plum_relation_flags_mapping in
+                        plum_relations.plum_relation_flags_mapping_v.iter()
+                    {
+                        let masked_relation_flags = mask
+                            & idp_proto::PlumRelationFlags::try_from(
+                                plum_relation_flags_mapping.plum_relation_flags_raw,
+                            )?;
+                        if masked_relation_flags == idp_proto::PlumRelationFlags::NONE {
+                            continue;
+                        }
+                        let target_plum_head_seal =
+                            &plum_relation_flags_mapping.target_plum_head_seal;
+                        if visited_plum_head_seal_s.insert(target_plum_head_seal.clone()) {
+                            candidate_plum_head_seal_v.push(target_plum_head_seal.clone());
+                        }
+                    }
+                }
+            }
+            log::trace!(
+                "IDPPeer::pull({}, {}); received {} Plums",
+                peer_id,
+                plum_head_seal,
+                level_plum_head_seal_v.len()
+            );
+        }
+
+        // Everything has been received, so store it all at once.  The chunked PlumBody-s are stored
+        // first so that each stored PlumHead's PlumBody is already present.
+        let datahost_g = self.datahost_la.read().await;
+        let mut transaction = datahost_g.begin_transaction().await?;
+        for (plum_body_seal, plum_body) in received_plum_body_m.iter() {
+            datahost_g
+                .store_plum_body(plum_body, plum_body_seal, Some(transaction.as_mut()))
+                .await?;
+        }
+        datahost_g
+            .store_plum_v(&received_plum_v, Some(transaction.as_mut()))
+            .await?;
+        for plum_head_metadata_and_relations in received_plum_head_metadata_and_relations_v.iter() {
+            datahost_g
+                .store_plum_head_metadata_and_relations(
+                    plum_head_metadata_and_relations,
+                    Some(transaction.as_mut()),
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(TransferReport {
+            plum_count: received_plum_v.len() + received_plum_head_metadata_and_relations_v.len(),
+            plum_body_count: received_plum_v.len() + received_plum_body_m.len(),
+        })
+    }
+    /// Requests the given Plums from the given peer, which returns a nonempty prefix of them.
+    async fn load_plums(
+        &self,
+        peer_id: &PeerId,
+        plum_head_seal_v: &[PlumHeadSeal],
+    ) -> Result<Vec<LoadedPlum>> {
+        let loaded_plum_v = match self
+            .send_request(
+                peer_id,
+                peer_request::Value::LoadPlums(LoadPlumsRequest {
+                    plum_head_seal_v: plum_head_seal_v.to_vec(),
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::LoadPlums(load_plums_response) => {
+                load_plums_response.loaded_plum_v
+            }
+            _ => {
+                anyhow::bail!(
+                    "peer {} returned an unexpected response to LoadPlums",
+                    peer_id
+                );
+            }
+        };
+        anyhow::ensure!(
+            !loaded_plum_v.is_empty() && loaded_plum_v.len() <= plum_head_seal_v.len(),
+            "peer {} returned {} Plums to LoadPlums for {} Plums",
+            peer_id,
+            loaded_plum_v.len(),
+            plum_head_seal_v.len()
+        );
+        Ok(loaded_plum_v)
+    }
+    /// Requests the PlumBodyChunk-s of the given PlumBody from the given peer until it's complete, and
+    /// returns the PlumBody, which is verified against plum_body_seal.
+    async fn load_plum_body_chunks(
+        &self,
+        peer_id: &PeerId,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Result<PlumBody> {
+        let mut partial_plum_body_o: Option<PartialPlumBody> = None;
+        loop {
+            let offset = partial_plum_body_o
+                .as_ref()
+                .map(|partial_plum_body| partial_plum_body.offset())
+                .unwrap_or(0);
+            let plum_body_chunk_v = match self
+                .send_request(
+                    peer_id,
+                    peer_request::Value::LoadPlumBodyChunks(LoadPlumBodyChunksRequest {
+                        plum_body_offset: PlumBodyOffset {
+                            plum_body_seal: plum_body_seal.clone(),
+                            offset,
+                        },
+                    }),
+                )
+                .await?
+            {
+                peer_response::Value::LoadPlumBodyChunks(load_plum_body_chunks_response) => {
+                    load_plum_body_chunks_response.plum_body_chunk_v
+                }
+                _ => {
+                    anyhow::bail!(
+                        "peer {} returned an unexpected response to LoadPlumBodyChunks",
+                        peer_id
+                    );
+                }
+            };
+            anyhow::ensure!(
+                !plum_body_chunk_v.is_empty(),
+                "peer {} returned no PlumBodyChunk-s to LoadPlumBodyChunks for PlumBody {}",
+                peer_id,
+                plum_body_seal
+            );
+            for plum_body_chunk in plum_body_chunk_v {
+                let partial_plum_body = partial_plum_body_o.get_or_insert_with(|| {
+                    PartialPlumBody::new(plum_body_seal.clone(), plum_body_chunk.total_length)
+                });
+                partial_plum_body.append_chunk(plum_body_chunk)?;
+            }
+            if partial_plum_body_o
+                .as_ref()
+                .map(|partial_plum_body| partial_plum_body.is_complete())
+                .unwrap_or(false)
+            {
+                // This verifies the reassembled PlumBody against its PlumBodySeal.
+                return Ok(partial_plum_body_o
+                    .unwrap()
+                    .into_plum_body(Some(self.datahost_la.read().await.seal_key_provider()))?);
+            }
+        }
+    }
+    /// Pushes the head of the given local branch (and therefore its history and content) to the given
+    /// peer, and then creates the branch on the peer or sets its head, as in IDPClient::push_branch
+    /// (including the check against concurrent updates, and updating the remote-tracking PathState).
+    pub async fn push_branch(&self, peer_id: &PeerId, branch_path: &Path) -> Result<BranchUpdate> {
+        let branch_head = self
+            .datahost_la
+            .read()
+            .await
+            .branch_get_head(branch_path, None)
+            .await?;
+        let transfer_report = self.push(peer_id, &branch_head).await?;
+        log::trace!(
+            "IDPPeer::push_branch({}, {:?}); pushed branch head {}; {:?}",
+            peer_id,
+            branch_path,
+            branch_head,
+            transfer_report
+        );
+
+        let branch_update = self
+            .set_remote_branch_head(peer_id, branch_path, &branch_head)
+            .await?;
+
+        self.datahost_la
+            .read()
+            .await
+            .set_remote_tracking_branch_head(&peer_id.to_string(), branch_path, &branch_head, None)
+            .await?;
+
+        Ok(branch_update)
+    }
+    /// Creates the branch on the given peer with the given (already pushed) head, or sets its head,
+    /// for push_branch.
+    async fn set_remote_branch_head(
+        &self,
+        peer_id: &PeerId,
+        branch_path: &Path,
+        branch_head: &PlumHeadSeal,
+    ) -> Result<BranchUpdate> {
+        let remote_branch_head = match self.branch_get_head(peer_id, branch_path).await {
+            Ok(remote_branch_head) => remote_branch_head,
+            Err(e) if is_path_not_found(&e) => {
+                self.branch_create(
+                    peer_id,
+                    &PathState {
+                        path: branch_path.clone(),
+                        current_state_plum_head_seal: branch_head.clone(),
+                    },
+                )
+                .await?;
+                return Ok(BranchUpdate::Created);
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        };
+        if &remote_branch_head == branch_head {
+            return Ok(BranchUpdate::Unchanged);
+        }
+
+        self.pull(peer_id, &remote_branch_head).await?;
+        let (value, branch_update) = self
+            .datahost_la
+            .read()
+            .await
+            .branch_set_head_operation(&remote_branch_head, branch_head, None)
+            .await?;
+        self.branch_set_head(
+            peer_id,
+            BranchSetHeadRequest {
+                branch_path: branch_path.clone(),
+                value: Some(value),
+                expected_current_branch_head_o: Some(remote_branch_head),
+            },
+        )
+        .await?;
+        Ok(branch_update)
+    }
+    /// Pulls the given peer's head of the given branch (and therefore its history and content), and
+    /// records it in the local Datahost as the PathState given by remote_tracking_branch_path, which
+    /// is created or updated as needed.  The local branch itself (if any) isn't modified.  Returns the
+    /// peer's branch head.
+    pub async fn fetch_branch(&self, peer_id: &PeerId, branch_path: &Path) -> Result<PlumHeadSeal> {
+        let remote_branch_head = self.branch_get_head(peer_id, branch_path).await?;
+        let transfer_report = self.pull(peer_id, &remote_branch_head).await?;
+        log::trace!(
+            "IDPPeer::fetch_branch({}, {:?}); pulled branch head {}; {:?}",
+            peer_id,
+            branch_path,
+            remote_branch_head,
+            transfer_report
+        );
+
+        self.datahost_la
+            .read()
+            .await
+            .set_remote_tracking_branch_head(
+                &peer_id.to_string(),
+                branch_path,
+                &remote_branch_head,
+                None,
+            )
+            .await?;
+
+        Ok(remote_branch_head)
+    }
+    /// Returns the path of the local PathState that fetch_branch uses to track the given peer's head
+    /// of the given branch, i.e. the PeerId, followed by '/', followed by branch_path (see
+    /// Datahost::remote_tracking_branch_path).
+    pub fn remote_tracking_branch_path(peer_id: &PeerId, branch_path: &Path) -> Path {
+        Datahost::remote_tracking_branch_path(&peer_id.to_string(), branch_path)
+    }
+    /// Creates a branch on the given peer, as in Datahost::branch_create.  The BranchNode Plum that
+    /// the branch refers to must have already been pushed.
+    pub async fn branch_create(
+        &self,
+        peer_id: &PeerId,
+        branch_path_state: &PathState,
+    ) -> Result<()> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::BranchCreate(BranchCreateRequest {
+                    branch_path_state: branch_path_state.clone(),
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::BranchCreate(_) => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to BranchCreate",
+                peer_id
+            )),
+        }
+    }
+    /// Deletes a branch on the given peer, as in Datahost::branch_delete.
+    pub async fn branch_delete(&self, peer_id: &PeerId, branch_path: &Path) -> Result<()> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::BranchDelete(BranchDeleteRequest {
+                    branch_path: branch_path.clone(),
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::BranchDelete(_) => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to BranchDelete",
+                peer_id
+            )),
+        }
+    }
+    /// Returns the head of the given branch on the given peer, as in Datahost::branch_get_head.
+    pub async fn branch_get_head(
+        &self,
+        peer_id: &PeerId,
+        branch_path: &Path,
+    ) -> Result<PlumHeadSeal> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::BranchGetHead(BranchGetHeadRequest {
+                    branch_path: branch_path.clone(),
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::BranchGetHead(branch_get_head_response) => {
+                Ok(branch_get_head_response.branch_head_plum_head_seal)
+            }
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to BranchGetHead",
+                peer_id
+            )),
+        }
+    }
+    /// Sets the head of a branch on the given peer, as in Datahost::branch_set_head.  The new branch
+    /// head must have already been pushed.  If the operation doesn't apply (e.g. a fast-forward to a
+    /// non-descendant), then the error can be downcast to the corresponding BranchError.
+    pub async fn branch_set_head(
+        &self,
+        peer_id: &PeerId,
+        branch_set_head_request: BranchSetHeadRequest,
+    ) -> Result<()> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::BranchSetHead(branch_set_head_request),
+            )
+            .await?
+        {
+            peer_response::Value::BranchSetHead(_) => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to BranchSetHead",
+                peer_id
+            )),
+        }
+    }
+    /// Returns the given peer's PathState for the given path.
+    pub async fn path_state_get(&self, peer_id: &PeerId, path: &Path) -> Result<PathState> {
+        match self
+            .send_request(
+                peer_id,
+                peer_request::Value::PathStateGet(PathStateGetRequest { path: path.clone() }),
+            )
+            .await?
+        {
+            peer_response::Value::PathStateGet(path_state_get_response) => {
+                Ok(path_state_get_response.path_state)
+            }
+            _ => Err(anyhow::anyhow!(
+                "peer {} returned an unexpected response to PathStateGet",
+                peer_id
+            )),
+        }
+    }
+    /// Returns which of the given Plums the given peer has (including their PlumBody-s), without
+    /// transferring them.  The returned Vec corresponds elementwise with plum_head_seal_v.
+    pub async fn has_plums(
+        &self,
+        peer_id: &PeerId,
+        plum_head_seal_v: &[PlumHeadSeal],
+    ) -> Result<Vec<bool>> {
+        let has_plum_v = match self
+            .send_request(
+                peer_id,
+                peer_request::Value::HasPlums(HasPlumsRequest {
+                    plum_head_seal_v: plum_head_seal_v.to_vec(),
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::HasPlums(has_plums_response) => has_plums_response.has_plum_v,
+            _ => {
+                anyhow::bail!(
+                    "peer {} returned an unexpected response to HasPlums",
+                    peer_id
+                );
+            }
+        };
+        anyhow::ensure!(
+            has_plum_v.len() == plum_head_seal_v.len(),
+            "peer {} returned {} results to HasPlums for {} Plums",
+            peer_id,
+            has_plum_v.len(),
+            plum_head_seal_v.len()
+        );
+        Ok(has_plum_v)
+    }
+    /// Returns which of the given Plums the given peer has along with all their dependencies (see
+    /// Datahost::is_dependency_complete).  The returned Vec corresponds elementwise with
+    /// plum_head_seal_v.
+    async fn is_dependency_complete(
+        &self,
+        peer_id: &PeerId,
+        plum_head_seal_v: &[PlumHeadSeal],
+    ) -> Result<Vec<bool>> {
+        let is_dependency_complete_v = match self
+            .send_request(
+                peer_id,
+                peer_request::Value::IsDependencyComplete(IsDependencyCompleteRequest {
+                    plum_head_seal_v: plum_head_seal_v.to_vec(),
+                }),
+            )
+            .await?
+        {
+            peer_response::Value::IsDependencyComplete(is_dependency_complete_response) => {
+                is_dependency_complete_response.is_dependency_complete_v
+            }
+            _ => {
+                anyhow::bail!(
+                    "peer {} returned an unexpected response to IsDependencyComplete",
+                    peer_id
+                );
+            }
+        };
+        anyhow::ensure!(
+            is_dependency_complete_v.len() == plum_head_seal_v.len(),
+            "peer {} returned {} results to IsDependencyComplete for {} Plums",
+            peer_id,
+            is_dependency_complete_v.len(),
+            plum_head_seal_v.len()
+        );
+        Ok(is_dependency_complete_v)
+    }
+}
+
+/// Returns an error unless the received PlumHead is that of the requested Plum.
+fn ensure_requested(
+    peer_id: &PeerId,
+    requested_plum_head_seal: &PlumHeadSeal,
+    received_plum_head: &PlumHead,
+) -> Result<()> {
+    let received_plum_head_seal = PlumHeadSeal::from(received_plum_head);
+    anyhow::ensure!(
+        received_plum_head_seal == *requested_plum_head_seal,
+        "peer {} sent Plum {} which wasn't requested (expected Plum {})",
+        peer_id,
+        received_plum_head_seal,
+        requested_plum_head_seal
+    );
+    Ok(())
+}
+
+/// Returns true if the error is a BranchError indicating that the branch doesn't exist.
+fn is_path_not_found(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<BranchError>(),
+        Some(BranchError::DatahostStorageError(
+            DatahostStorageError::PathNotFound(_)
+        ))
+    )
+}
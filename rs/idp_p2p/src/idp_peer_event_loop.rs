@@ -0,0 +1,509 @@
+use crate::{peer_error::peer_error_from_error, IDPP2PCodec, IDP_P2P_BATCH_SIZE};
+use anyhow::Result;
+use async_lock::RwLock;
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
+use idp_core::Datahost;
+use idp_datahost_storage::DatahostStorageError;
+use idp_proto::{
+    loaded_plum, peer_request, peer_response, BranchCreateResponse, BranchDeleteResponse,
+    BranchGetHeadResponse, BranchSetHeadResponse, HasPlumsResponse, IsDependencyCompleteResponse,
+    LoadPlumBodyChunksResponse, LoadPlumsResponse, LoadedPlum, PartialPlumBody,
+    PathStateGetResponse, PeerRequest, PeerResponse, Plum, PlumBodyChunk, PlumBodySeal,
+    PlumHeadSeal, StorePlumBodyChunksResponse, StorePlumsResponse, PLUM_BODY_CHUNK_SIZE,
+};
+use libp2p::{
+    core::transport::ListenerId,
+    request_response::{self, OutboundRequestId, ResponseChannel},
+    swarm::{dial_opts::DialOpts, SwarmEvent},
+    Multiaddr, PeerId, Swarm,
+};
+use prost::Message;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+pub(crate) type IDPP2PBehaviour = request_response::Behaviour<IDPP2PCodec>;
+/// The PlumBody-s that other peers are in the middle of sending using StorePlumBodyChunks, keyed by
+/// the sending peer, so that one peer can't interfere with another's transfer.
+type PartialPlumBodyMap = HashMap<(PeerId, PlumBodySeal), PartialPlumBody>;
+
+/// Sent by IDPPeer to its IDPPeerEventLoop, which sends the result back through result_tx.
+pub(crate) enum Command {
+    ListenOn {
+        multiaddr: Multiaddr,
+        result_tx: oneshot::Sender<Result<Multiaddr>>,
+    },
+    Dial {
+        peer_id: PeerId,
+        multiaddr: Multiaddr,
+        result_tx: oneshot::Sender<Result<()>>,
+    },
+    SendRequest {
+        peer_id: PeerId,
+        peer_request: PeerRequest,
+        result_tx: oneshot::Sender<Result<PeerResponse>>,
+    },
+}
+
+/// Drives the Swarm of an IDPPeer, carrying out the Commands sent by the IDPPeer and handling the
+/// PeerRequests sent by other peers.
+pub(crate) struct IDPPeerEventLoop {
+    swarm: Swarm<IDPP2PBehaviour>,
+    datahost_la: Arc<RwLock<Datahost>>,
+    command_rx: mpsc::UnboundedReceiver<Command>,
+    /// Each inbound PeerRequest is handled in its own task (so that a slow request doesn't hold up
+    /// the Swarm), which sends its PeerResponse back through this channel, since only the Swarm can
+    /// send it.
+    response_tx: mpsc::UnboundedSender<(ResponseChannel<PeerResponse>, PeerResponse)>,
+    response_rx: mpsc::UnboundedReceiver<(ResponseChannel<PeerResponse>, PeerResponse)>,
+    /// This is shared with the tasks handling inbound PeerRequests.  A peer's entries are removed once
+    /// there are no more connections to it.
+    partial_plum_body_mla: Arc<Mutex<PartialPlumBodyMap>>,
+    pending_listen_m: HashMap<ListenerId, oneshot::Sender<Result<Multiaddr>>>,
+    pending_dial_vm: HashMap<PeerId, Vec<oneshot::Sender<Result<()>>>>,
+    pending_request_m: HashMap<OutboundRequestId, oneshot::Sender<Result<PeerResponse>>>,
+}
+
+impl IDPPeerEventLoop {
+    pub(crate) fn new(
+        swarm: Swarm<IDPP2PBehaviour>,
+        datahost_la: Arc<RwLock<Datahost>>,
+        command_rx: mpsc::UnboundedReceiver<Command>,
+    ) -> Self {
+        let (response_tx, response_rx) = mpsc::unbounded();
+        Self {
+            swarm,
+            datahost_la,
+            command_rx,
+            response_tx,
+            response_rx,
+            partial_plum_body_mla: Arc::new(Mutex::new(HashMap::new())),
+            pending_listen_m: HashMap::new(),
+            pending_dial_vm: HashMap::new(),
+            pending_request_m: HashMap::new(),
+        }
+    }
+    /// Runs until the IDPPeer (i.e. the sender of the Commands) is dropped.
+    pub(crate) async fn run(mut self) {
+        loop {
+            tokio::select! {
+                swarm_event = self.swarm.select_next_some() => {
+                    self.handle_swarm_event(swarm_event);
+                }
+                command_o = self.command_rx.next() => match command_o {
+                    Some(command) => self.handle_command(command),
+                    None => break,
+                },
+                Some((channel, peer_response)) = self.response_rx.next() => {
+                    if self
+                        .swarm
+                        .behaviour_mut()
+                        .send_response(channel, peer_response)
+                        .is_err()
+                    {
+                        log::warn!("IDPPeer could not send a PeerResponse; the connection was closed");
+                    }
+                }
+            }
+        }
+        log::debug!("IDPPeer {} stopped", self.swarm.local_peer_id());
+    }
+    fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::ListenOn {
+                multiaddr,
+                result_tx,
+            } => match self.swarm.listen_on(multiaddr) {
+                Ok(listener_id) => {
+                    self.pending_listen_m.insert(listener_id, result_tx);
+                }
+                Err(e) => {
+                    let _ = result_tx.send(Err(e.into()));
+                }
+            },
+            Command::Dial {
+                peer_id,
+                multiaddr,
+                result_tx,
+            } => {
+                if self.swarm.is_connected(&peer_id) {
+                    let _ = result_tx.send(Ok(()));
+                    return;
+                }
+                if let Some(pending_dial_v) = self.pending_dial_vm.get_mut(&peer_id) {
+                    // A dial to this peer is already in progress.
+                    pending_dial_v.push(result_tx);
+                    return;
+                }
+                match self.swarm.dial(
+                    DialOpts::peer_id(peer_id)
+                        .addresses(vec![multiaddr])
+                        .build(),
+                ) {
+                    Ok(()) => {
+                        self.pending_dial_vm.insert(peer_id, vec![result_tx]);
+                    }
+                    Err(e) => {
+                        let _ = result_tx.send(Err(e.into()));
+                    }
+                }
+            }
+            Command::SendRequest {
+                peer_id,
+                peer_request,
+                result_tx,
+            } => {
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .send_request(&peer_id, peer_request);
+                self.pending_request_m.insert(request_id, result_tx);
+            }
+        }
+    }
+    fn handle_swarm_event(
+        &mut self,
+        swarm_event: SwarmEvent<request_response::Event<PeerRequest, PeerResponse>>,
+    ) {
+        match swarm_event {
+            SwarmEvent::NewListenAddr {
+                listener_id,
+                address,
+            } => {
+                log::debug!(
+                    "IDPPeer {} listening on {}",
+                    self.swarm.local_peer_id(),
+                    address
+                );
+                if let Some(result_tx) = self.pending_listen_m.remove(&listener_id) {
+                    let _ = result_tx.send(Ok(address));
+                }
+            }
+            SwarmEvent::ListenerClosed {
+                listener_id,
+                reason,
+                ..
+            } => {
+                if let Some(result_tx) = self.pending_listen_m.remove(&listener_id) {
+                    let _ = result_tx.send(Err(anyhow::anyhow!(
+                        "listener closed before listening; {:?}",
+                        reason
+                    )));
+                }
+            }
+            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                log::debug!(
+                    "IDPPeer {} connected to peer {}",
+                    self.swarm.local_peer_id(),
+                    peer_id
+                );
+                for result_tx in self.pending_dial_vm.remove(&peer_id).unwrap_or_default() {
+                    let _ = result_tx.send(Ok(()));
+                }
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established: 0,
+                ..
+            } => {
+                self.partial_plum_body_mla
+                    .lock()
+                    .unwrap()
+                    .retain(|(sending_peer_id, _), _| *sending_peer_id != peer_id);
+            }
+            SwarmEvent::OutgoingConnectionError {
+                peer_id: Some(peer_id),
+                error,
+                ..
+            } => {
+                for result_tx in self.pending_dial_vm.remove(&peer_id).unwrap_or_default() {
+                    let _ = result_tx.send(Err(anyhow::anyhow!(
+                        "failed to dial peer {}; {}",
+                        peer_id,
+                        error
+                    )));
+                }
+            }
+            SwarmEvent::Behaviour(request_response::Event::Message { peer, message }) => {
+                match message {
+                    request_response::Message::Request {
+                        request, channel, ..
+                    } => {
+                        log::trace!("IDPPeer received a PeerRequest from peer {}", peer);
+                        let datahost_la = self.datahost_la.clone();
+                        let partial_plum_body_mla = self.partial_plum_body_mla.clone();
+                        let response_tx = self.response_tx.clone();
+                        tokio::spawn(async move {
+                            let peer_response = handle_peer_request(
+                                &datahost_la,
+                                &partial_plum_body_mla,
+                                &peer,
+                                request,
+                            )
+                            .await;
+                            // This only fails if the IDPPeerEventLoop has stopped.
+                            let _ = response_tx.unbounded_send((channel, peer_response));
+                        });
+                    }
+                    request_response::Message::Response {
+                        request_id,
+                        response,
+                    } => {
+                        if let Some(result_tx) = self.pending_request_m.remove(&request_id) {
+                            let _ = result_tx.send(Ok(response));
+                        }
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            }) => {
+                if let Some(result_tx) = self.pending_request_m.remove(&request_id) {
+                    let _ = result_tx.send(Err(anyhow::anyhow!(
+                        "request to peer {} failed; {}",
+                        peer,
+                        error
+                    )));
+                }
+            }
+            SwarmEvent::Behaviour(request_response::Event::InboundFailure {
+                peer, error, ..
+            }) => {
+                log::warn!("request from peer {} failed; {}", peer, error);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handles a PeerRequest sent by another peer, producing exactly one PeerResponse.  Note that there is
+/// no authentication or access control; any connected peer may read and write.
+async fn handle_peer_request(
+    datahost_la: &Arc<RwLock<Datahost>>,
+    partial_plum_body_mla: &Arc<Mutex<PartialPlumBodyMap>>,
+    peer_id: &PeerId,
+    peer_request: PeerRequest,
+) -> PeerResponse {
+    let value = match handle_peer_request_value(
+        datahost_la,
+        partial_plum_body_mla,
+        peer_id,
+        peer_request.value,
+    )
+    .await
+    {
+        Ok(value) => value,
+        Err(e) => {
+            log::debug!("IDPPeer failed to handle PeerRequest; {:#}", e);
+            peer_response::Value::Error(peer_error_from_error(&e))
+        }
+    };
+    PeerResponse { value: Some(value) }
+}
+
+async fn handle_peer_request_value(
+    datahost_la: &Arc<RwLock<Datahost>>,
+    partial_plum_body_mla: &Arc<Mutex<PartialPlumBodyMap>>,
+    peer_id: &PeerId,
+    peer_request_value_o: Option<peer_request::Value>,
+) -> Result<peer_response::Value> {
+    let peer_request_value =
+        peer_request_value_o.ok_or_else(|| anyhow::anyhow!("PeerRequest is missing its value"))?;
+    let datahost_g = datahost_la.read().await;
+    let peer_response_value = match peer_request_value {
+        peer_request::Value::HasPlums(req) => {
+            let has_plum_v = datahost_g.has_plum_v(&req.plum_head_seal_v, None).await?;
+            peer_response::Value::HasPlums(HasPlumsResponse { has_plum_v })
+        }
+        peer_request::Value::IsDependencyComplete(req) => {
+            let mut is_dependency_complete_v = Vec::with_capacity(req.plum_head_seal_v.len());
+            for plum_head_seal in req.plum_head_seal_v.iter() {
+                is_dependency_complete_v.push(
+                    datahost_g
+                        .is_dependency_complete(plum_head_seal, None)
+                        .await?,
+                );
+            }
+            peer_response::Value::IsDependencyComplete(IsDependencyCompleteResponse {
+                is_dependency_complete_v,
+            })
+        }
+        peer_request::Value::LoadPlums(req) => {
+            let loaded_plum_v = load_plums(&datahost_g, &req.plum_head_seal_v).await?;
+            peer_response::Value::LoadPlums(LoadPlumsResponse { loaded_plum_v })
+        }
+        peer_request::Value::LoadPlumBodyChunks(req) => {
+            let plum_body_seal = &req.plum_body_offset.plum_body_seal;
+            let plum_body = datahost_g
+                .load_option_plum_body(plum_body_seal, None)
+                .await?
+                .ok_or_else(|| DatahostStorageError::PlumBodyNotFound(plum_body_seal.clone()))?;
+            let mut batch_size = 0;
+            let plum_body_chunk_v = PlumBodyChunk::chunk_i_from(
+                plum_body_seal,
+                &plum_body,
+                req.plum_body_offset.offset,
+                PLUM_BODY_CHUNK_SIZE,
+            )?
+            .take_while(|plum_body_chunk| {
+                let within_batch = batch_size < IDP_P2P_BATCH_SIZE;
+                batch_size += plum_body_chunk.encoded_len();
+                within_batch
+            })
+            .collect();
+            peer_response::Value::LoadPlumBodyChunks(LoadPlumBodyChunksResponse {
+                plum_body_chunk_v,
+            })
+        }
+        peer_request::Value::StorePlums(req) => {
+            let seal_key_provider = datahost_g.seal_key_provider();
+            for plum in req.plum_v.iter() {
+                plum.verify_with_seal_key_provider(seal_key_provider)
+                    .map_err(DatahostStorageError::from)?;
+            }
+            for plum_head_metadata_and_relations in req.plum_head_metadata_and_relations_v.iter() {
+                plum_head_metadata_and_relations
+                    .verify_with_seal_key_provider(seal_key_provider)
+                    .map_err(DatahostStorageError::from)?;
+            }
+            let mut transaction = datahost_g.begin_transaction().await?;
+            datahost_g
+                .store_plum_v(&req.plum_v, Some(transaction.as_mut()))
+                .await?;
+            for plum_head_metadata_and_relations in req.plum_head_metadata_and_relations_v.iter() {
+                // The PlumBody must already be present, so that this Datahost doesn't end up with a
+                // Plum that's missing its PlumBody.
+                let plum_body_seal = &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+                if !datahost_g
+                    .has_plum_body(plum_body_seal, Some(transaction.as_mut()))
+                    .await?
+                {
+                    return Err(
+                        DatahostStorageError::PlumBodyNotFound(plum_body_seal.clone()).into(),
+                    );
+                }
+                datahost_g
+                    .store_plum_head_metadata_and_relations(
+                        plum_head_metadata_and_relations,
+                        Some(transaction.as_mut()),
+                    )
+                    .await?;
+            }
+            transaction.commit().await?;
+            peer_response::Value::StorePlums(StorePlumsResponse {})
+        }
+        peer_request::Value::StorePlumBodyChunks(req) => {
+            for plum_body_chunk in req.plum_body_chunk_v {
+                store_plum_body_chunk(&datahost_g, partial_plum_body_mla, peer_id, plum_body_chunk)
+                    .await?;
+            }
+            peer_response::Value::StorePlumBodyChunks(StorePlumBodyChunksResponse {})
+        }
+        peer_request::Value::BranchCreate(req) => {
+            datahost_g
+                .branch_create(&req.branch_path_state, None)
+                .await?;
+            peer_response::Value::BranchCreate(BranchCreateResponse {})
+        }
+        peer_request::Value::BranchDelete(req) => {
+            datahost_g.branch_delete(&req.branch_path, None).await?;
+            peer_response::Value::BranchDelete(BranchDeleteResponse {})
+        }
+        peer_request::Value::BranchGetHead(req) => {
+            let branch_head_plum_head_seal =
+                datahost_g.branch_get_head(&req.branch_path, None).await?;
+            peer_response::Value::BranchGetHead(BranchGetHeadResponse {
+                branch_head_plum_head_seal,
+            })
+        }
+        peer_request::Value::BranchSetHead(req) => {
+            datahost_g.branch_set_head(req, None).await?;
+            peer_response::Value::BranchSetHead(BranchSetHeadResponse {})
+        }
+        peer_request::Value::PathStateGet(req) => {
+            let path_state = datahost_g.load_path_state(&req.path, None).await?;
+            peer_response::Value::PathStateGet(PathStateGetResponse { path_state })
+        }
+    };
+    Ok(peer_response_value)
+}
+
+/// Loads the requested Plums, in order, until the batch reaches IDP_P2P_BATCH_SIZE (but always
+/// including at least one).  A Plum whose PlumBody requires chunking is loaded without it.
+async fn load_plums(
+    datahost: &Datahost,
+    plum_head_seal_v: &[PlumHeadSeal],
+) -> Result<Vec<LoadedPlum>> {
+    let mut loaded_plum_v = Vec::new();
+    let mut batch_size = 0;
+    for plum_head_seal in plum_head_seal_v.iter() {
+        if batch_size >= IDP_P2P_BATCH_SIZE {
+            break;
+        }
+        let plum_head_metadata_and_relations = datahost
+            .load_option_plum_head_metadata_and_relations(plum_head_seal, None)
+            .await?
+            .ok_or_else(|| DatahostStorageError::PlumHeadNotFound(plum_head_seal.clone()))?;
+        let plum_body_seal = &plum_head_metadata_and_relations.plum_head.plum_body_seal;
+        let plum_body = datahost
+            .load_option_plum_body(plum_body_seal, None)
+            .await?
+            .ok_or_else(|| DatahostStorageError::PlumBodyNotFound(plum_body_seal.clone()))?;
+        let value = if plum_body.requires_chunking() {
+            loaded_plum::Value::PlumHeadMetadataAndRelations(plum_head_metadata_and_relations)
+        } else {
+            loaded_plum::Value::Plum(Plum {
+                plum_head: plum_head_metadata_and_relations.plum_head,
+                plum_metadata: plum_head_metadata_and_relations.plum_metadata,
+                plum_relations: plum_head_metadata_and_relations.plum_relations,
+                plum_body,
+            })
+        };
+        let loaded_plum = LoadedPlum { value: Some(value) };
+        batch_size += loaded_plum.encoded_len();
+        loaded_plum_v.push(loaded_plum);
+    }
+    Ok(loaded_plum_v)
+}
+
+/// Appends the PlumBodyChunk sent by the given peer to the corresponding PartialPlumBody, and once
+/// that's complete, verifies and stores the PlumBody.  A PlumBodyChunk at offset 0 (re)starts the
+/// transfer.  This is the peer-to-peer counterpart of IDPServer's handling of HereHaveAPlumBodyChunk.
+async fn store_plum_body_chunk(
+    datahost: &Datahost,
+    partial_plum_body_mla: &Arc<Mutex<PartialPlumBodyMap>>,
+    peer_id: &PeerId,
+    plum_body_chunk: PlumBodyChunk,
+) -> Result<()> {
+    let key = (*peer_id, plum_body_chunk.plum_body_seal.clone());
+    let partial_plum_body = {
+        let mut partial_plum_body_mg = partial_plum_body_mla.lock().unwrap();
+        if plum_body_chunk.offset == 0 {
+            partial_plum_body_mg.insert(
+                key.clone(),
+                PartialPlumBody::new(key.1.clone(), plum_body_chunk.total_length),
+            );
+        }
+        let partial_plum_body = partial_plum_body_mg.get_mut(&key).ok_or_else(|| {
+            anyhow::anyhow!(
+                "no transfer of PlumBody {} is in progress, so its first PlumBodyChunk must have offset 0",
+                key.1
+            )
+        })?;
+        partial_plum_body.append_chunk(plum_body_chunk)?;
+        if !partial_plum_body.is_complete() {
+            return Ok(());
+        }
+        // Whether or not the PlumBody verifies, this transfer is over.
+        partial_plum_body_mg.remove(&key).unwrap()
+    };
+    let plum_body = partial_plum_body.into_plum_body(Some(datahost.seal_key_provider()))?;
+    datahost.store_plum_body(&plum_body, &key.1, None).await?;
+    Ok(())
+}
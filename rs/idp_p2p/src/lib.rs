@@ -0,0 +1,11 @@
+mod idp_p2p_codec;
+mod idp_peer;
+mod idp_peer_event_loop;
+mod peer_error;
+
+pub use crate::{
+    idp_p2p_codec::{IDPP2PCodec, IDP_P2P_BATCH_SIZE, IDP_P2P_MAX_MESSAGE_SIZE, IDP_P2P_PROTOCOL},
+    idp_peer::IDPPeer,
+};
+// These are needed in order to use IDPPeer.
+pub use libp2p::{identity::Keypair, Multiaddr, PeerId};
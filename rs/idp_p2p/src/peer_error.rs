@@ -0,0 +1,22 @@
+use idp_core::{error_detail_from_error, error_from_error_detail};
+use idp_proto::PeerError;
+
+/// Converts an error that occurred while handling a PeerRequest into the PeerError sent back to the
+/// requesting peer.  If the error is a DatahostStorageError, PathStateError, or BranchError, then an
+/// ErrorDetail is attached so that the requesting peer can reconstruct it (see error_from_peer_error).
+pub(crate) fn peer_error_from_error(error: &anyhow::Error) -> PeerError {
+    PeerError {
+        message: format!("{:#}", error),
+        error_detail_o: error_detail_from_error(error),
+    }
+}
+
+/// Converts a PeerError returned by another peer into an error.  As in IDPClient, if the PeerError
+/// carries an ErrorDetail, then the error that it represents is reconstructed, so that it can be
+/// recovered using anyhow::Error::downcast_ref.
+pub(crate) fn error_from_peer_error(peer_error: PeerError) -> anyhow::Error {
+    error_from_error_detail(
+        peer_error.error_detail_o,
+        format!("peer returned an error: {}", peer_error.message),
+    )
+}
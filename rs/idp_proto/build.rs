@@ -13,13 +13,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .type_attribute("idp.ContentType", "#[derive(derive_more::Deref, serde::Deserialize, derive_more::From, serde::Serialize)]")
         .type_attribute("idp.Nonce", "#[derive(derive_more::Deref, serde::Deserialize, derive_more::From, serde::Serialize)]")
         .type_attribute("idp.Path", "#[derive(derive_more::Deref, serde::Deserialize, derive_more::Display, derive_more::From, serde::Serialize)]")
-        .type_attribute("idp.PlumBodySeal", "#[derive(derive_more::Deref, serde::Deserialize, derive_more::From, serde::Serialize)]")
+        .type_attribute("idp.PlumBodySeal", "#[derive(derive_more::Deref, serde::Deserialize, Eq, derive_more::From, Hash, Ord, PartialOrd, serde::Serialize)]")
         .type_attribute("idp.PlumHeadSeal", "#[derive(derive_more::Deref, serde::Deserialize, Eq, derive_more::From, Hash, Ord, PartialOrd, serde::Serialize)]")
         .type_attribute("idp.PlumMetadataSeal", "#[derive(derive_more::Deref, serde::Deserialize, Eq, derive_more::From, Hash, Ord, PartialOrd, serde::Serialize)]")
         .type_attribute("idp.PlumRelation", "#[derive(serde::Deserialize, num_derive::FromPrimitive, serde::Serialize)]")
         .type_attribute("idp.PlumRelationFlagsRaw", "#[derive(Copy, serde::Deserialize, serde::Serialize)]")
         .type_attribute("idp.PlumRelationsSeal", "#[derive(derive_more::Deref, serde::Deserialize, Eq, derive_more::From, Hash, Ord, PartialOrd, serde::Serialize)]")
-        .type_attribute("idp.Seal", "#[derive(derive_more::Deref, serde::Deserialize, Eq, derive_more::From, Hash, Ord, PartialOrd, serde::Serialize)]")
+        .type_attribute("idp.Seal", "#[derive(serde::Deserialize, Eq, Hash, Ord, PartialOrd, serde::Serialize)]")
+        // Skipping multihash and keyed_seal when they're None keeps the serialized form of SHA-256 seals unchanged.
+        .field_attribute("idp.Seal.multihash", "#[serde(default, skip_serializing_if = \"Option::is_none\")]")
+        .field_attribute("idp.Seal.keyed_seal", "#[serde(default, skip_serializing_if = \"Option::is_none\")]")
         .type_attribute("idp.Sha256Sum", "#[derive(derive_more::Deref, serde::Deserialize, Eq, derive_more::From, Hash, Ord, PartialOrd, serde::Serialize)]")
         .type_attribute("idp.UnixNanoseconds", "#[derive(Copy, derive_more::Deref, serde::Deserialize, derive_more::From, derive_more::Into, serde::Serialize)]")
         .build_client(true)
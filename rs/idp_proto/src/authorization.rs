@@ -0,0 +1,14 @@
+//! Constants shared by IDPServer and IDPClient for authenticating gRPC requests.  Each request carries
+//! its credentials in the "authorization" metadata entry, as "<scheme> <credentials>".
+
+/// The request metadata key whose value holds the request's credentials.
+pub const AUTHORIZATION_METADATA_KEY: &str = "authorization";
+/// The response metadata key whose value holds a challenge issued by the server, to be signed by the
+/// client in order to authenticate using the DIDKey scheme.
+pub const AUTHORIZATION_CHALLENGE_METADATA_KEY: &str = "idp-authorization-challenge";
+/// The authorization scheme for static bearer tokens, i.e. "Bearer <token>".
+pub const BEARER_AUTHORIZATION_SCHEME: &str = "Bearer";
+/// The authorization scheme for did:key-signed challenges, i.e. "DIDKey <challenge> <JWS>", where the
+/// JWS is a detached signature over the challenge message (see idp_sig::did_key_authorization_message).
+/// A request having only "DIDKey" asks the server to issue a challenge.
+pub const DID_KEY_AUTHORIZATION_SCHEME: &str = "DIDKey";
@@ -1,7 +1,7 @@
-use crate::{Content, Hashable};
+use crate::{Content, Hashable, SealHasher};
 
 impl Hashable for Content {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.content_metadata.update_hasher(hasher);
         self.content_byte_v.update_hasher(hasher);
@@ -1,4 +1,4 @@
-use crate::{ContentClass, Hashable};
+use crate::{ContentClass, Hashable, SealHasher};
 
 impl ContentClass {
     pub fn text_plain() -> Self {
@@ -22,7 +22,7 @@ impl ContentClass {
 }
 
 impl Hashable for ContentClass {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -1,4 +1,4 @@
-use crate::{ContentEncoding, Hashable};
+use crate::{ContentEncoding, Hashable, SealHasher};
 
 impl ContentEncoding {
     /// Convenience method.  ContentEncoding::none() (whose string repr is "") represents no encoding (i.e. no
@@ -32,7 +32,7 @@ impl ContentEncoding {
 }
 
 impl Hashable for ContentEncoding {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -1,4 +1,4 @@
-use crate::{ContentFormat, Hashable};
+use crate::{ContentFormat, Hashable, SealHasher};
 use anyhow::Result;
 
 impl ContentFormat {
@@ -22,7 +22,7 @@ impl ContentFormat {
 }
 
 impl Hashable for ContentFormat {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -1,4 +1,4 @@
-use crate::{ContentMetadata, Hashable, ContentType};
+use crate::{ContentMetadata, ContentType, Hashable, SealHasher};
 use anyhow::Result;
 
 impl ContentMetadata {
@@ -8,7 +8,7 @@ impl ContentMetadata {
 }
 
 impl Hashable for ContentMetadata {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.content_length.update_hasher(hasher);
         self.content_class.update_hasher(hasher);
@@ -1,4 +1,4 @@
-use crate::{ContentClass, ContentFormat, ContentType, Hashable};
+use crate::{ContentClass, ContentFormat, ContentType, Hashable, SealHasher};
 use anyhow::Result;
 
 impl ContentType {
@@ -56,7 +56,7 @@ impl ContentType {
 }
 
 impl Hashable for ContentType {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -0,0 +1,20 @@
+//! Conversion between ErrorDetail and the tonic::Status that carries it.
+
+use crate::ErrorDetail;
+
+impl ErrorDetail {
+    /// Produces a tonic::Status with the given code and message, which carries this ErrorDetail in
+    /// its details.
+    pub fn into_status(self, code: tonic::Code, message: String) -> tonic::Status {
+        use prost::Message;
+        tonic::Status::with_details(code, message, self.encode_to_vec().into())
+    }
+    /// Returns the ErrorDetail carried by the given tonic::Status, if any.
+    pub fn from_status(status: &tonic::Status) -> Option<Self> {
+        use prost::Message;
+        if status.details().is_empty() {
+            return None;
+        }
+        Self::decode(status.details()).ok()
+    }
+}
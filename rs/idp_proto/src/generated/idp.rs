@@ -72,22 +72,27 @@ pub struct Nonce {
     #[prost(bytes = "vec", required, tag = "1")]
     pub value: ::prost::alloc::vec::Vec<u8>,
 }
-#[derive(
-    derive_more::Deref,
-    serde::Deserialize,
-    Eq,
-    derive_more::From,
-    Hash,
-    Ord,
-    PartialOrd,
-    serde::Serialize
-)]
+/// Exactly one of the fields of Seal should be set.  An unkeyed SHA-256 seal always uses the sha256sum
+/// field (and never multihash), since that was the original (and only) form of a seal, and existing seals
+/// must remain unchanged.  See SealAlgorithm.
+#[derive(serde::Deserialize, Eq, Hash, Ord, PartialOrd, serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Seal {
-    /// TEMP HACK -- it should support more seal types, e.g. HMAC.
-    #[prost(message, required, tag = "1")]
-    pub sha256sum: Sha256Sum,
+    #[prost(message, optional, tag = "1")]
+    pub sha256sum: ::core::option::Option<Sha256Sum>,
+    /// For any other SealAlgorithm, this is the multihash of the seal, i.e. the varint-encoded
+    /// multihash code of the hash function, then the varint-encoded digest length, then the digest.
+    #[prost(bytes = "vec", optional, tag = "2")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multihash: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// For a keyed seal (see SealKey), this is the varint-encoded code 0x300000 (the start of the
+    /// multicodec private use range), then the varint-encoded length of the seal_key_id, then the UTF-8 seal_key_id, then the multihash of the
+    /// MAC (HMAC for SHA-2, and keyed mode for BLAKE3).  Only holders of the SealKey can compute or
+    /// verify a keyed seal, so it can't be used to confirm guesses of low-entropy content.
+    #[prost(bytes = "vec", optional, tag = "3")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyed_seal: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(
     derive_more::Deref,
@@ -168,7 +173,16 @@ pub struct PlumRelationsSeal {
     #[prost(message, required, tag = "1")]
     pub value: Seal,
 }
-#[derive(derive_more::Deref, serde::Deserialize, derive_more::From, serde::Serialize)]
+#[derive(
+    derive_more::Deref,
+    serde::Deserialize,
+    Eq,
+    derive_more::From,
+    Hash,
+    Ord,
+    PartialOrd,
+    serde::Serialize
+)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PlumBodySeal {
@@ -279,13 +293,15 @@ pub struct Acknowledgement {}
 /// These stream from client to server, starting with the PlumHeadSeal, and the server streams
 /// responses to say which PlumHeadSeals it already has Plums for (and therefore the client
 /// doesn't have to push the Plum or recurse on its dependencies).  Thus there won't be much
-/// wasted bandwidth.
-/// TODO: break it apart into sending plum head, plum plum_relations, plum body.  This requires
-/// the server responding with which ones are needed for a given PlumHeadSeal.
+/// wasted bandwidth.  Because many Plums share identical PlumBody-s, the client can also ask
+/// which PlumBody-s the server already has, and for those Plums, send only the PlumHead,
+/// PlumMetadata, and PlumRelations.  PlumBody-s too large to fit in a single message are sent as a
+/// sequence of PlumBodyChunk-s, after which the server has the PlumBody.  The server responds to
+/// each request with exactly one response, in order.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PushRequest {
-    #[prost(oneof = "push_request::Value", tags = "1, 2")]
+    #[prost(oneof = "push_request::Value", tags = "1, 2, 3, 4, 5")]
     pub value: ::core::option::Option<push_request::Value>,
 }
 /// Nested message and enum types in `PushRequest`.
@@ -297,6 +313,15 @@ pub mod push_request {
         ShouldISendThisPlum(super::PlumHeadSeal),
         #[prost(message, tag = "2")]
         HereHaveAPlum(super::Plum),
+        #[prost(message, tag = "3")]
+        ShouldISendThisPlumBody(super::PlumBodySeal),
+        /// The server is expected to already have the corresponding PlumBody.
+        #[prost(message, tag = "4")]
+        HereHaveAPlumHeadMetadataAndRelations(super::PlumHeadMetadataAndRelations),
+        /// Must start at offset 0 or at the offset given in send_this_plum_body_starting_at, and each
+        /// chunk must start where the previous one ended.
+        #[prost(message, tag = "5")]
+        HereHaveAPlumBodyChunk(super::PlumBodyChunk),
     }
 }
 /// TODO: Potentially could respond with a boolean, as long as the client can reliably
@@ -304,7 +329,7 @@ pub mod push_request {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PushResponse {
-    #[prost(oneof = "push_response::Value", tags = "1, 2, 3")]
+    #[prost(oneof = "push_response::Value", tags = "1, 2, 3, 4, 5, 6")]
     pub value: ::core::option::Option<push_response::Value>,
 }
 /// Nested message and enum types in `PushResponse`.
@@ -312,8 +337,10 @@ pub mod push_response {
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Value {
-        /// Response to here_have_a_plum; carries no information.  TODO: This isn't actually necessary,
-        /// as long as the request/response streaming doesn't need to be 1-to-1
+        /// Response to here_have_a_plum, here_have_a_plum_head_metadata_and_relations, and
+        /// here_have_a_plum_body_chunk (once the chunk has been accepted); carries no
+        /// information.  TODO: This isn't actually necessary, as long as the request/response streaming
+        /// doesn't need to be 1-to-1
         #[prost(message, tag = "1")]
         Ok(super::Acknowledgement),
         /// Positive response to should_i_send_this_plum.  TODO: Maybe rename to i_want_this_plum.
@@ -323,8 +350,43 @@ pub mod push_response {
         /// as long as the request/response streaming doesn't need to be 1-to-1
         #[prost(message, tag = "3")]
         DontSendThisPlum(super::PlumHeadSeal),
+        /// Positive response to should_i_send_this_plum_body.
+        #[prost(message, tag = "4")]
+        SendThisPlumBody(super::PlumBodySeal),
+        /// Negative response to should_i_send_this_plum_body.
+        #[prost(message, tag = "5")]
+        DontSendThisPlumBody(super::PlumBodySeal),
+        /// Positive response to should_i_send_this_plum_body when the server already has a prefix
+        /// of the PlumBody from an earlier, interrupted push.  The client should resume by sending
+        /// PlumBodyChunk-s starting at the given offset.
+        #[prost(message, tag = "6")]
+        SendThisPlumBodyStartingAt(super::PlumBodyOffset),
     }
 }
+/// Specifies a position within the encoded form of a PlumBody.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PlumBodyOffset {
+    #[prost(message, required, tag = "1")]
+    pub plum_body_seal: PlumBodySeal,
+    #[prost(uint64, required, tag = "2")]
+    pub offset: u64,
+}
+/// A contiguous range of the protobuf-encoded form of a PlumBody.  Once all the chunks have been
+/// received, the receiver decodes the PlumBody and verifies it against plum_body_seal.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PlumBodyChunk {
+    #[prost(message, required, tag = "1")]
+    pub plum_body_seal: PlumBodySeal,
+    /// Length of the whole encoded PlumBody, so that the receiver knows when it's complete.
+    #[prost(uint64, required, tag = "2")]
+    pub total_length: u64,
+    #[prost(uint64, required, tag = "3")]
+    pub offset: u64,
+    #[prost(bytes = "vec", required, tag = "4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PlumHeadAndRelations {
@@ -341,16 +403,31 @@ pub struct PlumHeadSealAndRelations {
     #[prost(message, required, tag = "2")]
     pub plum_relations: PlumRelations,
 }
+/// Everything in a Plum except for its PlumBody.  This is what's needed to traverse relations, and it's
+/// typically small, whereas a PlumBody may be large and is often shared by many Plums, so it's
+/// transferred separately.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PlumHeadMetadataAndRelations {
+    #[prost(message, required, tag = "1")]
+    pub plum_head: PlumHead,
+    #[prost(message, required, tag = "2")]
+    pub plum_metadata: PlumMetadata,
+    #[prost(message, required, tag = "3")]
+    pub plum_relations: PlumRelations,
+}
 /// These stream from client to server, starting with the PlumHeadSeal, and the server streams
-/// responses to say which PlumHeadSeals it already has Plums for (and therefore the client
-/// doesn't have to push the Plum or recurse on its dependencies).  Thus there won't be much
-/// wasted bandwidth.
-/// TODO: break it apart into sending plum head, plum plum_relations, plum body.  This requires
-/// the server responding with which ones are needed for a given PlumHeadSeal.
+/// back exactly one response per request, in order.  The server only sends what was requested,
+/// not its dependencies; the client inspects the received PlumRelations and requests only the
+/// dependencies that it doesn't already have.  Requesting the PlumHead, PlumMetadata, and
+/// PlumRelations separately from the PlumBody allows PlumBody-s shared by several Plums to be
+/// transferred once, and allows "shallow" pulls that don't transfer PlumBody-s at all.  The one
+/// exception to one-response-per-request is that a PlumBody too large to fit in a single message is
+/// sent as a sequence of PlumBodyChunk responses, ending with the one that completes it.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PullRequest {
-    #[prost(oneof = "pull_request::Value", tags = "1")]
+    #[prost(oneof = "pull_request::Value", tags = "1, 2, 3, 4")]
     pub value: ::core::option::Option<pull_request::Value>,
 }
 /// Nested message and enum types in `PullRequest`.
@@ -358,15 +435,21 @@ pub mod pull_request {
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Value {
-        /// TEMP HACK -- simple for now.
         #[prost(message, tag = "1")]
         IWantThisPlum(super::PlumHeadSeal),
+        #[prost(message, tag = "2")]
+        IWantThisPlumHeadMetadataAndRelations(super::PlumHeadSeal),
+        #[prost(message, tag = "3")]
+        IWantThisPlumBody(super::PlumBodySeal),
+        /// Used to resume an interrupted pull of a chunked PlumBody.
+        #[prost(message, tag = "4")]
+        IWantThisPlumBodyStartingAt(super::PlumBodyOffset),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PullResponse {
-    #[prost(oneof = "pull_response::Value", tags = "1, 2")]
+    #[prost(oneof = "pull_response::Value", tags = "1, 2, 3, 4, 5, 6")]
     pub value: ::core::option::Option<pull_response::Value>,
 }
 /// Nested message and enum types in `PullResponse`.
@@ -374,11 +457,25 @@ pub mod pull_response {
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Value {
-        /// TEMP HACK -- simple for now.
+        /// Response to i_want_this_plum.
         #[prost(message, tag = "1")]
         Plum(super::Plum),
+        /// Negative response to i_want_this_plum and i_want_this_plum_head_metadata_and_relations.
         #[prost(message, tag = "2")]
         IDontHaveThisPlum(super::PlumHeadSeal),
+        /// Response to i_want_this_plum_head_metadata_and_relations.
+        #[prost(message, tag = "3")]
+        PlumHeadMetadataAndRelations(super::PlumHeadMetadataAndRelations),
+        /// Response to i_want_this_plum_body.
+        #[prost(message, tag = "4")]
+        PlumBody(super::PlumBody),
+        /// Negative response to i_want_this_plum_body and i_want_this_plum_body_starting_at.
+        #[prost(message, tag = "5")]
+        IDontHaveThisPlumBody(super::PlumBodySeal),
+        /// Response to i_want_this_plum_body (if the PlumBody is too large for a single message)
+        /// and i_want_this_plum_body_starting_at.
+        #[prost(message, tag = "6")]
+        PlumBodyChunk(super::PlumBodyChunk),
     }
 }
 #[derive(
@@ -439,6 +536,10 @@ pub struct BranchGetHeadResponse {
 pub struct BranchSetHeadRequest {
     #[prost(message, required, tag = "1")]
     pub branch_path: Path,
+    /// If specified, then the branch head is only set if its current head is this, so that a client
+    /// that computed the operation from the branch head it read doesn't clobber a concurrent update.
+    #[prost(message, optional, tag = "6")]
+    pub expected_current_branch_head_o: ::core::option::Option<PlumHeadSeal>,
     #[prost(oneof = "branch_set_head_request::Value", tags = "2, 3, 4, 5")]
     pub value: ::core::option::Option<branch_set_head_request::Value>,
 }
@@ -460,6 +561,552 @@ pub mod branch_set_head_request {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BranchSetHeadResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PathStateGetRequest {
+    #[prost(message, required, tag = "1")]
+    pub path: Path,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PathStateGetResponse {
+    #[prost(message, required, tag = "1")]
+    pub path_state: PathState,
+}
+/// Identifies the last row of a page of results, so that the next page can begin immediately after
+/// it.  Clients should treat this as opaque, and only pass it back to get the next page.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PageCursor {
+    #[prost(message, required, tag = "1")]
+    pub row_at: UnixNanoseconds,
+    #[prost(int64, required, tag = "2")]
+    pub rowid: i64,
+}
+/// PathStates are listed in the order they were last updated.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PathStateListRequest {
+    /// If specified, only PathStates whose path begins with this are listed.
+    #[prost(string, optional, tag = "1")]
+    pub path_prefix_o: ::core::option::Option<::prost::alloc::string::String>,
+    /// Must be positive.  The server may return fewer than this many PathStates per page.
+    #[prost(uint32, required, tag = "2")]
+    pub page_size: u32,
+    /// If specified, this should be the next_page_cursor_o from the previous PathStateListResponse.
+    #[prost(message, optional, tag = "3")]
+    pub page_cursor_o: ::core::option::Option<PageCursor>,
+    /// If specified, only PathStates that were inserted or last updated at or after this time are
+    /// listed.  This allows a client to poll for changes (see last_updated_at_o in PathStateListResponse)
+    /// instead of using WatchPathStates.  Note that deleted PathStates aren't listed.
+    #[prost(message, optional, tag = "4")]
+    pub updated_at_begin_o: ::core::option::Option<UnixNanoseconds>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PathStateListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub path_state_v: ::prost::alloc::vec::Vec<PathState>,
+    /// If specified, there may be more PathStates, which can be gotten by passing this in the next
+    /// PathStateListRequest.  Note that the next page may be empty.
+    #[prost(message, optional, tag = "2")]
+    pub next_page_cursor_o: ::core::option::Option<PageCursor>,
+    /// The time at which the last PathState on this page (including any that were omitted because the
+    /// client may not read them) was inserted or last updated, if the page isn't empty.  Passing this
+    /// as updated_at_begin_o polls for subsequent changes.  Because that bound is inclusive, that
+    /// PathState is listed again.
+    #[prost(message, optional, tag = "3")]
+    pub last_updated_at_o: ::core::option::Option<UnixNanoseconds>,
+}
+/// The change made to a PathState, as reported by WatchPathStates.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PathStateChange {
+    #[prost(oneof = "path_state_change::Value", tags = "1, 2, 3")]
+    pub value: ::core::option::Option<path_state_change::Value>,
+}
+/// Nested message and enum types in `PathStateChange`.
+pub mod path_state_change {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        Inserted(super::PathState),
+        #[prost(message, tag = "2")]
+        Updated(super::PathState),
+        #[prost(message, tag = "3")]
+        Deleted(super::Path),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchPathStatesRequest {
+    /// The paths of the PathStates to watch.  If empty, all PathStates (that the client may read) are
+    /// watched.
+    #[prost(message, repeated, tag = "1")]
+    pub path_v: ::prost::alloc::vec::Vec<Path>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchPathStatesResponse {
+    #[prost(message, required, tag = "1")]
+    pub path_state_change: PathStateChange,
+}
+/// This checks for the presence of whole Plums (i.e. including PlumBody-s), as in Datahost::has_plum.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HasPlumsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub plum_head_seal_v: ::prost::alloc::vec::Vec<PlumHeadSeal>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HasPlumsResponse {
+    /// Corresponds elementwise with plum_head_seal_v in the HasPlumsRequest.
+    #[prost(bool, repeated, packed = "false", tag = "1")]
+    pub has_plum_v: ::prost::alloc::vec::Vec<bool>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PlumVerifyErrorDetail {
+    #[prost(oneof = "plum_verify_error_detail::Value", tags = "1, 2, 3, 4, 5, 6")]
+    pub value: ::core::option::Option<plum_verify_error_detail::Value>,
+}
+/// Nested message and enum types in `PlumVerifyErrorDetail`.
+pub mod plum_verify_error_detail {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ComputedPlumMetadataSealMismatch {
+        #[prost(message, required, tag = "1")]
+        pub computed_plum_metadata_seal: super::PlumMetadataSeal,
+        #[prost(message, required, tag = "2")]
+        pub expected_plum_metadata_seal: super::PlumMetadataSeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ComputedPlumRelationsSealMismatch {
+        #[prost(message, required, tag = "1")]
+        pub computed_plum_relations_seal: super::PlumRelationsSeal,
+        #[prost(message, required, tag = "2")]
+        pub expected_plum_relations_seal: super::PlumRelationsSeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ComputedPlumBodySealMismatch {
+        #[prost(message, required, tag = "1")]
+        pub computed_plum_body_seal: super::PlumBodySeal,
+        #[prost(message, required, tag = "2")]
+        pub expected_plum_body_seal: super::PlumBodySeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PlumBodySealRedundancyMismatch {
+        #[prost(message, required, tag = "1")]
+        pub plum_head_plum_body_seal: super::PlumBodySeal,
+        #[prost(message, required, tag = "2")]
+        pub plum_relations_source_plum_body_seal: super::PlumBodySeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PlumBodyContentMetadataRedundancyMismatch {
+        #[prost(message, required, tag = "1")]
+        pub plum_head_seal: super::PlumHeadSeal,
+        #[prost(message, required, tag = "2")]
+        pub plum_metadata_plum_body_content_metadata: super::ContentMetadata,
+        #[prost(message, required, tag = "3")]
+        pub plum_body_plum_body_content_metadata: super::ContentMetadata,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MissingSealKey {
+        #[prost(string, required, tag = "1")]
+        pub seal_key_id: ::prost::alloc::string::String,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        ComputedPlumMetadataSealMismatch(ComputedPlumMetadataSealMismatch),
+        #[prost(message, tag = "2")]
+        ComputedPlumRelationsSealMismatch(ComputedPlumRelationsSealMismatch),
+        #[prost(message, tag = "3")]
+        ComputedPlumBodySealMismatch(ComputedPlumBodySealMismatch),
+        #[prost(message, tag = "4")]
+        PlumBodySealRedundancyMismatch(PlumBodySealRedundancyMismatch),
+        #[prost(message, tag = "5")]
+        PlumBodyContentMetadataRedundancyMismatch(
+            PlumBodyContentMetadataRedundancyMismatch,
+        ),
+        #[prost(message, tag = "6")]
+        MissingSealKey(MissingSealKey),
+    }
+}
+/// Errors that can't be represented exactly (e.g. those coming from the DB driver) are represented
+/// by their messages, and are reconstructed as DatahostStorageError::Generic.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DatahostStorageErrorDetail {
+    #[prost(
+        oneof = "datahost_storage_error_detail::Value",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12"
+    )]
+    pub value: ::core::option::Option<datahost_storage_error_detail::Value>,
+}
+/// Nested message and enum types in `DatahostStorageErrorDetail`.
+pub mod datahost_storage_error_detail {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct InvalidValueInDb {
+        #[prost(string, required, tag = "1")]
+        pub table_name: ::prost::alloc::string::String,
+        #[prost(string, required, tag = "2")]
+        pub column_name: ::prost::alloc::string::String,
+        #[prost(string, required, tag = "3")]
+        pub reason: ::prost::alloc::string::String,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TransactionConflict {}
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(string, tag = "1")]
+        Generic(::prost::alloc::string::String),
+        #[prost(message, tag = "2")]
+        InvalidValueInDb(InvalidValueInDb),
+        #[prost(message, tag = "3")]
+        PathAlreadyExists(super::Path),
+        #[prost(message, tag = "4")]
+        PathNotFound(super::Path),
+        #[prost(message, tag = "5")]
+        PlumHeadNotFound(super::PlumHeadSeal),
+        #[prost(message, tag = "6")]
+        PlumMetadataNotFound(super::PlumMetadataSeal),
+        #[prost(message, tag = "7")]
+        PlumRelationsNotFound(super::PlumRelationsSeal),
+        #[prost(message, tag = "8")]
+        PlumBodyNotFound(super::PlumBodySeal),
+        #[prost(message, tag = "9")]
+        PlumBodyIntegrityCheckFailed(super::PlumBodySeal),
+        #[prost(string, tag = "10")]
+        IoError(::prost::alloc::string::String),
+        #[prost(message, tag = "11")]
+        PlumVerifyError(super::PlumVerifyErrorDetail),
+        #[prost(message, tag = "12")]
+        TransactionConflict(TransactionConflict),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PathStateErrorDetail {
+    #[prost(oneof = "path_state_error_detail::Value", tags = "1, 2, 3")]
+    pub value: ::core::option::Option<path_state_error_detail::Value>,
+}
+/// Nested message and enum types in `PathStateErrorDetail`.
+pub mod path_state_error_detail {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct InvalidPath {
+        #[prost(message, required, tag = "1")]
+        pub path: super::Path,
+        #[prost(string, required, tag = "2")]
+        pub reason: ::prost::alloc::string::String,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        InvalidPath(InvalidPath),
+        #[prost(message, tag = "2")]
+        PathAlreadyExists(super::Path),
+        #[prost(message, tag = "3")]
+        PlumMustAlreadyExist(super::PlumHeadSeal),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BranchErrorDetail {
+    #[prost(
+        oneof = "branch_error_detail::Value",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
+    )]
+    pub value: ::core::option::Option<branch_error_detail::Value>,
+}
+/// Nested message and enum types in `BranchErrorDetail`.
+pub mod branch_error_detail {
+    /// Used by the errors produced by the BranchSetHeadRequest operations.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct BranchHeads {
+        #[prost(message, required, tag = "1")]
+        pub current_branch_head: super::PlumHeadSeal,
+        #[prost(message, required, tag = "2")]
+        pub new_branch_head: super::PlumHeadSeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MaxAncestorDepthReached {
+        #[prost(message, required, tag = "1")]
+        pub lhs: super::PlumHeadSeal,
+        #[prost(message, required, tag = "2")]
+        pub rhs: super::PlumHeadSeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PlumIsNotABranchNode {
+        #[prost(message, required, tag = "1")]
+        pub plum_head_seal: super::PlumHeadSeal,
+        #[prost(string, required, tag = "2")]
+        pub description: ::prost::alloc::string::String,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PlumIsNotDependencyComplete {
+        #[prost(message, required, tag = "1")]
+        pub plum_head_seal: super::PlumHeadSeal,
+        #[prost(message, repeated, tag = "2")]
+        pub incomplete_plum_head_seal_v: ::prost::alloc::vec::Vec<super::PlumHeadSeal>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct UnexpectedCurrentBranchHead {
+        #[prost(message, required, tag = "1")]
+        pub current_branch_head: super::PlumHeadSeal,
+        #[prost(message, required, tag = "2")]
+        pub expected_current_branch_head: super::PlumHeadSeal,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        BranchNodeAncestorPlumIsMissing(super::PlumHeadSeal),
+        #[prost(message, tag = "2")]
+        BranchNodePlumMustAlreadyExist(super::PlumHeadSeal),
+        #[prost(message, tag = "3")]
+        DatahostStorageError(super::DatahostStorageErrorDetail),
+        #[prost(message, tag = "4")]
+        FastForwardExpectedDescendant(BranchHeads),
+        #[prost(message, tag = "5")]
+        ForkHistoryExpectedCommonAncestor(BranchHeads),
+        #[prost(string, tag = "6")]
+        InternalError(::prost::alloc::string::String),
+        #[prost(string, tag = "7")]
+        MalformedRequest(::prost::alloc::string::String),
+        #[prost(message, tag = "8")]
+        MaxAncestorDepthReached(MaxAncestorDepthReached),
+        #[prost(message, tag = "9")]
+        PathStateError(super::PathStateErrorDetail),
+        #[prost(message, tag = "10")]
+        PlumIsNotABranchNode(PlumIsNotABranchNode),
+        #[prost(message, tag = "11")]
+        PlumIsNotDependencyComplete(PlumIsNotDependencyComplete),
+        #[prost(message, tag = "12")]
+        RewindExpectedAncestor(BranchHeads),
+        #[prost(message, tag = "13")]
+        TotalRewriteExpectedNoCommonAncestor(BranchHeads),
+        #[prost(message, tag = "14")]
+        UnexpectedCurrentBranchHead(UnexpectedCurrentBranchHead),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ErrorDetail {
+    #[prost(oneof = "error_detail::Value", tags = "1, 2, 3")]
+    pub value: ::core::option::Option<error_detail::Value>,
+}
+/// Nested message and enum types in `ErrorDetail`.
+pub mod error_detail {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        DatahostStorageError(super::DatahostStorageErrorDetail),
+        #[prost(message, tag = "2")]
+        PathStateError(super::PathStateErrorDetail),
+        #[prost(message, tag = "3")]
+        BranchError(super::BranchErrorDetail),
+    }
+}
+/// Asks which of the given Plums are dependency-complete (see Datahost::is_dependency_complete).  A
+/// pushing peer doesn't send those Plums, nor recurse into their dependencies.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IsDependencyCompleteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub plum_head_seal_v: ::prost::alloc::vec::Vec<PlumHeadSeal>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IsDependencyCompleteResponse {
+    /// Corresponds elementwise with plum_head_seal_v in the IsDependencyCompleteRequest.
+    #[prost(bool, repeated, packed = "false", tag = "1")]
+    pub is_dependency_complete_v: ::prost::alloc::vec::Vec<bool>,
+}
+/// Requests whole Plums, as in Datahost::load_plum_v.  As with PullRequest, only the requested Plums
+/// are sent; the requester requests whichever of their dependencies it doesn't already have.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadPlumsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub plum_head_seal_v: ::prost::alloc::vec::Vec<PlumHeadSeal>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadedPlum {
+    #[prost(oneof = "loaded_plum::Value", tags = "1, 2")]
+    pub value: ::core::option::Option<loaded_plum::Value>,
+}
+/// Nested message and enum types in `LoadedPlum`.
+pub mod loaded_plum {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        Plum(super::Plum),
+        /// Used for a Plum whose PlumBody is too large to fit in a single message; the requester
+        /// requests that PlumBody separately, using LoadPlumBodyChunks.
+        #[prost(message, tag = "2")]
+        PlumHeadMetadataAndRelations(super::PlumHeadMetadataAndRelations),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadPlumsResponse {
+    /// Corresponds elementwise with a prefix of plum_head_seal_v in the LoadPlumsRequest.  The
+    /// responder stops adding Plums once the response reaches the batch size (but always includes at
+    /// least one), and the requester requests the rest again.
+    #[prost(message, repeated, tag = "1")]
+    pub loaded_plum_v: ::prost::alloc::vec::Vec<LoadedPlum>,
+}
+/// Requests consecutive PlumBodyChunk-s of a PlumBody, starting at the given offset.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadPlumBodyChunksRequest {
+    #[prost(message, required, tag = "1")]
+    pub plum_body_offset: PlumBodyOffset,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadPlumBodyChunksResponse {
+    /// The responder stops adding PlumBodyChunk-s once the response reaches the batch size, and the
+    /// requester requests the rest starting where the last one ends.
+    #[prost(message, repeated, tag = "1")]
+    pub plum_body_chunk_v: ::prost::alloc::vec::Vec<PlumBodyChunk>,
+}
+/// Stores the given Plums in a single transaction, as in Datahost::store_plum_v.  Each Plum is
+/// verified before anything is stored.  The PlumBody of each element of
+/// plum_head_metadata_and_relations_v must already be present (e.g. because it was sent using
+/// StorePlumBodyChunks, or as part of an element of plum_v), and is stored after plum_v.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorePlumsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub plum_v: ::prost::alloc::vec::Vec<Plum>,
+    #[prost(message, repeated, tag = "2")]
+    pub plum_head_metadata_and_relations_v: ::prost::alloc::vec::Vec<
+        PlumHeadMetadataAndRelations,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorePlumsResponse {}
+/// Sends consecutive PlumBodyChunk-s of a PlumBody.  A PlumBodyChunk at offset 0 (re)starts the
+/// transfer, and once it's complete, the PlumBody is verified and stored.  A partially transferred
+/// PlumBody is discarded once the connection to the sending peer closes.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorePlumBodyChunksRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub plum_body_chunk_v: ::prost::alloc::vec::Vec<PlumBodyChunk>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorePlumBodyChunksResponse {}
+/// The peer-to-peer analog of a failed Status.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerError {
+    #[prost(string, required, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub error_detail_o: ::core::option::Option<ErrorDetail>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerRequest {
+    #[prost(oneof = "peer_request::Value", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11")]
+    pub value: ::core::option::Option<peer_request::Value>,
+}
+/// Nested message and enum types in `PeerRequest`.
+pub mod peer_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(message, tag = "1")]
+        HasPlums(super::HasPlumsRequest),
+        #[prost(message, tag = "2")]
+        LoadPlums(super::LoadPlumsRequest),
+        #[prost(message, tag = "3")]
+        StorePlums(super::StorePlumsRequest),
+        #[prost(message, tag = "4")]
+        BranchCreate(super::BranchCreateRequest),
+        #[prost(message, tag = "5")]
+        BranchDelete(super::BranchDeleteRequest),
+        #[prost(message, tag = "6")]
+        BranchGetHead(super::BranchGetHeadRequest),
+        #[prost(message, tag = "7")]
+        BranchSetHead(super::BranchSetHeadRequest),
+        #[prost(message, tag = "8")]
+        PathStateGet(super::PathStateGetRequest),
+        #[prost(message, tag = "9")]
+        IsDependencyComplete(super::IsDependencyCompleteRequest),
+        #[prost(message, tag = "10")]
+        LoadPlumBodyChunks(super::LoadPlumBodyChunksRequest),
+        #[prost(message, tag = "11")]
+        StorePlumBodyChunks(super::StorePlumBodyChunksRequest),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerResponse {
+    #[prost(
+        oneof = "peer_response::Value",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12"
+    )]
+    pub value: ::core::option::Option<peer_response::Value>,
+}
+/// Nested message and enum types in `PeerResponse`.
+pub mod peer_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        /// Response to any PeerRequest that failed.
+        #[prost(message, tag = "1")]
+        Error(super::PeerError),
+        #[prost(message, tag = "2")]
+        HasPlums(super::HasPlumsResponse),
+        #[prost(message, tag = "3")]
+        LoadPlums(super::LoadPlumsResponse),
+        #[prost(message, tag = "4")]
+        StorePlums(super::StorePlumsResponse),
+        #[prost(message, tag = "5")]
+        BranchCreate(super::BranchCreateResponse),
+        #[prost(message, tag = "6")]
+        BranchDelete(super::BranchDeleteResponse),
+        #[prost(message, tag = "7")]
+        BranchGetHead(super::BranchGetHeadResponse),
+        #[prost(message, tag = "8")]
+        BranchSetHead(super::BranchSetHeadResponse),
+        #[prost(message, tag = "9")]
+        PathStateGet(super::PathStateGetResponse),
+        #[prost(message, tag = "10")]
+        IsDependencyComplete(super::IsDependencyCompleteResponse),
+        #[prost(message, tag = "11")]
+        LoadPlumBodyChunks(super::LoadPlumBodyChunksResponse),
+        #[prost(message, tag = "12")]
+        StorePlumBodyChunks(super::StorePlumBodyChunksResponse),
+    }
+}
 /// This defines what plum_relations are possible from one Plum to another.
 #[derive(serde::Deserialize, num_derive::FromPrimitive, serde::Serialize)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -582,7 +1229,7 @@ pub mod indoor_data_plumbing_client {
         }
         pub async fn pull(
             &mut self,
-            request: impl tonic::IntoRequest<super::PullRequest>,
+            request: impl tonic::IntoStreamingRequest<Message = super::PullRequest>,
         ) -> Result<
             tonic::Response<tonic::codec::Streaming<super::PullResponse>>,
             tonic::Status,
@@ -600,7 +1247,7 @@ pub mod indoor_data_plumbing_client {
             let path = http::uri::PathAndQuery::from_static(
                 "/idp.IndoorDataPlumbing/Pull",
             );
-            self.inner.server_streaming(request.into_request(), path, codec).await
+            self.inner.streaming(request.into_streaming_request(), path, codec).await
         }
         /// TODO: Consider moving these into a separate GRPC service
         pub async fn branch_create(
@@ -679,6 +1326,89 @@ pub mod indoor_data_plumbing_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
+        pub async fn path_state_get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PathStateGetRequest>,
+        ) -> Result<tonic::Response<super::PathStateGetResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/idp.IndoorDataPlumbing/PathStateGet",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn path_state_list(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PathStateListRequest>,
+        ) -> Result<tonic::Response<super::PathStateListResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/idp.IndoorDataPlumbing/PathStateList",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn has_plums(
+            &mut self,
+            request: impl tonic::IntoRequest<super::HasPlumsRequest>,
+        ) -> Result<tonic::Response<super::HasPlumsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/idp.IndoorDataPlumbing/HasPlums",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Streams each subsequent change to the watched PathStates, until the client ends the request.
+        /// Changes are reported once the transaction making them commits.  If the client falls too far
+        /// behind, then the stream ends with a ResourceExhausted status, after which the client should
+        /// reload the PathStates it's interested in and watch again.
+        pub async fn watch_path_states(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchPathStatesRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::WatchPathStatesResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/idp.IndoorDataPlumbing/WatchPathStates",
+            );
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -707,7 +1437,7 @@ pub mod indoor_data_plumbing_server {
             + 'static;
         async fn pull(
             &self,
-            request: tonic::Request<super::PullRequest>,
+            request: tonic::Request<tonic::Streaming<super::PullRequest>>,
         ) -> Result<tonic::Response<Self::PullStream>, tonic::Status>;
         /// TODO: Consider moving these into a separate GRPC service
         async fn branch_create(
@@ -726,6 +1456,32 @@ pub mod indoor_data_plumbing_server {
             &self,
             request: tonic::Request<super::BranchSetHeadRequest>,
         ) -> Result<tonic::Response<super::BranchSetHeadResponse>, tonic::Status>;
+        async fn path_state_get(
+            &self,
+            request: tonic::Request<super::PathStateGetRequest>,
+        ) -> Result<tonic::Response<super::PathStateGetResponse>, tonic::Status>;
+        async fn path_state_list(
+            &self,
+            request: tonic::Request<super::PathStateListRequest>,
+        ) -> Result<tonic::Response<super::PathStateListResponse>, tonic::Status>;
+        async fn has_plums(
+            &self,
+            request: tonic::Request<super::HasPlumsRequest>,
+        ) -> Result<tonic::Response<super::HasPlumsResponse>, tonic::Status>;
+        /// Server streaming response type for the WatchPathStates method.
+        type WatchPathStatesStream: futures_core::Stream<
+                Item = Result<super::WatchPathStatesResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Streams each subsequent change to the watched PathStates, until the client ends the request.
+        /// Changes are reported once the transaction making them commits.  If the client falls too far
+        /// behind, then the stream ends with a ResourceExhausted status, after which the client should
+        /// reload the PathStates it's interested in and watch again.
+        async fn watch_path_states(
+            &self,
+            request: tonic::Request<super::WatchPathStatesRequest>,
+        ) -> Result<tonic::Response<Self::WatchPathStatesStream>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct IndoorDataPlumbingServer<T: IndoorDataPlumbing> {
@@ -830,7 +1586,7 @@ pub mod indoor_data_plumbing_server {
                     struct PullSvc<T: IndoorDataPlumbing>(pub Arc<T>);
                     impl<
                         T: IndoorDataPlumbing,
-                    > tonic::server::ServerStreamingService<super::PullRequest>
+                    > tonic::server::StreamingService<super::PullRequest>
                     for PullSvc<T> {
                         type Response = super::PullResponse;
                         type ResponseStream = T::PullStream;
@@ -840,7 +1596,7 @@ pub mod indoor_data_plumbing_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PullRequest>,
+                            request: tonic::Request<tonic::Streaming<super::PullRequest>>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move { (*inner).pull(request).await };
@@ -859,7 +1615,7 @@ pub mod indoor_data_plumbing_server {
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.server_streaming(method, req).await;
+                        let res = grpc.streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
@@ -1024,6 +1780,166 @@ pub mod indoor_data_plumbing_server {
                     };
                     Box::pin(fut)
                 }
+                "/idp.IndoorDataPlumbing/PathStateGet" => {
+                    #[allow(non_camel_case_types)]
+                    struct PathStateGetSvc<T: IndoorDataPlumbing>(pub Arc<T>);
+                    impl<
+                        T: IndoorDataPlumbing,
+                    > tonic::server::UnaryService<super::PathStateGetRequest>
+                    for PathStateGetSvc<T> {
+                        type Response = super::PathStateGetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PathStateGetRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).path_state_get(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PathStateGetSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/idp.IndoorDataPlumbing/PathStateList" => {
+                    #[allow(non_camel_case_types)]
+                    struct PathStateListSvc<T: IndoorDataPlumbing>(pub Arc<T>);
+                    impl<
+                        T: IndoorDataPlumbing,
+                    > tonic::server::UnaryService<super::PathStateListRequest>
+                    for PathStateListSvc<T> {
+                        type Response = super::PathStateListResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PathStateListRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).path_state_list(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PathStateListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/idp.IndoorDataPlumbing/HasPlums" => {
+                    #[allow(non_camel_case_types)]
+                    struct HasPlumsSvc<T: IndoorDataPlumbing>(pub Arc<T>);
+                    impl<
+                        T: IndoorDataPlumbing,
+                    > tonic::server::UnaryService<super::HasPlumsRequest>
+                    for HasPlumsSvc<T> {
+                        type Response = super::HasPlumsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::HasPlumsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).has_plums(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = HasPlumsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/idp.IndoorDataPlumbing/WatchPathStates" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchPathStatesSvc<T: IndoorDataPlumbing>(pub Arc<T>);
+                    impl<
+                        T: IndoorDataPlumbing,
+                    > tonic::server::ServerStreamingService<
+                        super::WatchPathStatesRequest,
+                    > for WatchPathStatesSvc<T> {
+                        type Response = super::WatchPathStatesResponse;
+                        type ResponseStream = T::WatchPathStatesStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchPathStatesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).watch_path_states(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchPathStatesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -1,11 +1,13 @@
-use sha2::Digest;
+use crate::SealHasher;
 
+/// Feeds a canonical byte representation of a value into a SealHasher, which is how Seal-s are
+/// computed (see SealAlgorithm::compute_seal).
 pub trait Hashable {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256);
+    fn update_hasher(&self, hasher: &mut dyn SealHasher);
 }
 
 impl Hashable for &str {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"String");
         self.len().update_hasher(hasher);
         hasher.update(self.as_bytes());
@@ -13,7 +15,7 @@ impl Hashable for &str {
 }
 
 impl Hashable for String {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"String");
         self.len().update_hasher(hasher);
         hasher.update(self.as_bytes());
@@ -21,7 +23,7 @@ impl Hashable for String {
 }
 
 impl Hashable for [u8] {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"Bytes");
         self.len().update_hasher(hasher);
         hasher.update(self);
@@ -29,7 +31,7 @@ impl Hashable for [u8] {
 }
 
 impl Hashable for Vec<u8> {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"Bytes");
         self.len().update_hasher(hasher);
         hasher.update(self.as_slice());
@@ -38,7 +40,7 @@ impl Hashable for Vec<u8> {
 
 impl<H: Hashable> Hashable for Option<H> {
     /// This particular definition makes None and Some([]) produce distinct hashes.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"Option");
         if let Some(value) = self {
             hasher.update(b"\x01");
@@ -52,7 +54,7 @@ impl<H: Hashable> Hashable for Option<H> {
 /// Note that u8 does not implement Hashable.
 impl<H: Hashable> Hashable for Vec<H> {
     /// This particular definition makes None and Some([]) produce distinct hashes.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"Array");
         (self.len() as u64).update_hasher(hasher);
         for element in self.iter() {
@@ -64,7 +66,7 @@ impl<H: Hashable> Hashable for Vec<H> {
 /// Note that u8 does not implement Hashable.
 impl<H: Hashable> Hashable for [H] {
     /// This particular definition makes None and Some([]) produce distinct hashes.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update(b"Array");
         (self.len() as u64).update_hasher(hasher);
         for element in self.iter() {
@@ -75,7 +77,7 @@ impl<H: Hashable> Hashable for [H] {
 
 impl Hashable for u32 {
     /// Generally all values that have an endianness are represented in little-endian order.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update("u32".as_bytes());
         hasher.update(&self.to_le_bytes());
     }
@@ -83,7 +85,7 @@ impl Hashable for u32 {
 
 impl Hashable for u64 {
     /// Generally all values that have an endianness are represented in little-endian order.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         hasher.update("u64".as_bytes());
         hasher.update(&self.to_le_bytes());
     }
@@ -91,7 +93,7 @@ impl Hashable for u64 {
 
 impl Hashable for usize {
     /// This always represents a usize as u64 (and therefore in little-endian order).
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         (*self as u64).update_hasher(hasher);
     }
 }
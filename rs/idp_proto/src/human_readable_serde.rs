@@ -0,0 +1,59 @@
+/// A type that has a canonical string form (Display and FromStr), which can be opted into, in place
+/// of the form that serde derives for it, by human-readable serializations (e.g. JSON) of a field
+/// of that type using `#[serde(with = "idp_proto::human_readable_str")]`.  Without that, the type
+/// is serialized in its derived form by every format, so that the content of existing PlumBody-s
+/// (and therefore their PlumBodySeal-s) doesn't change.
+pub trait CanonicalStr:
+    std::fmt::Display + std::str::FromStr + serde::Serialize + serde::de::DeserializeOwned
+{
+    /// Returns true iff this value's Display string parses back to an equal value.  If not, then
+    /// human_readable_str serializes it in its derived form, so that serialization is lossless.
+    fn has_canonical_str(&self) -> bool {
+        true
+    }
+}
+
+/// For use with `#[serde(with = "idp_proto::human_readable_str")]` on a field whose type implements
+/// CanonicalStr.  If the serializer is human-readable, then the field is serialized as its Display
+/// string (unless it has no canonical string form).  Otherwise (e.g. msgpack) it's serialized in its
+/// derived form.  When deserializing from a human-readable format, the derived form is also
+/// accepted, so that a field can opt in without breaking previously serialized values.
+pub mod human_readable_str {
+    use super::CanonicalStr;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: CanonicalStr,
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() && value.has_canonical_str() {
+            serializer.collect_str(value)
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: CanonicalStr,
+        T::Err: std::fmt::Display,
+        D: serde::Deserializer<'de>,
+    {
+        use serde::Deserialize;
+
+        if deserializer.is_human_readable() {
+            #[derive(serde::Deserialize)]
+            #[serde(untagged)]
+            enum StrOrDerived<T> {
+                Str(String),
+                Derived(T),
+            }
+            match StrOrDerived::<T>::deserialize(deserializer)? {
+                StrOrDerived::Str(s) => T::from_str(&s).map_err(serde::de::Error::custom),
+                StrOrDerived::Derived(value) => Ok(value),
+            }
+        } else {
+            T::deserialize(deserializer)
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod authorization;
 mod content;
 mod content_class;
 mod content_classifiable;
@@ -6,15 +7,23 @@ mod content_format;
 mod content_metadata;
 mod content_type;
 mod deserializable;
+#[cfg(feature = "tonic")]
+mod error_detail_status;
 mod generated;
 mod hashable;
+mod human_readable_serde;
+mod malformed_error_detail;
 mod nonce;
+mod partial_plum_body;
 mod path;
 mod plum;
 mod plum_body;
+mod plum_body_chunk;
+mod plum_body_chunk_error;
 mod plum_body_seal;
 mod plum_builder;
 mod plum_head;
+mod plum_head_metadata_and_relations;
 mod plum_head_seal;
 mod plum_metadata;
 mod plum_metadata_seal;
@@ -28,34 +37,67 @@ mod plum_relations_builder;
 mod plum_relations_seal;
 mod plum_verify_error;
 mod seal;
+mod seal_algorithm;
+mod seal_error;
+mod seal_hasher;
+mod seal_key;
+mod seal_key_provider;
 mod serializable;
 mod sha256sum;
+mod text_decode_error;
+mod text_encoding;
 mod unix_nanoseconds;
 
 pub use crate::{
+    authorization::{
+        AUTHORIZATION_CHALLENGE_METADATA_KEY, AUTHORIZATION_METADATA_KEY,
+        BEARER_AUTHORIZATION_SCHEME, DID_KEY_AUTHORIZATION_SCHEME,
+    },
     content_classifiable::ContentClassifiable,
     content_format::validate_is_serde_format,
     deserializable::{
         decode_and_deserialize_from_content, deserialize_using_serde_format, Deserializable,
     },
     generated::idp::{
-        branch_set_head_request, pull_request, pull_response, push_request, push_response,
+        branch_error_detail, branch_set_head_request, datahost_storage_error_detail, error_detail,
+        loaded_plum, path_state_change, path_state_error_detail, peer_request, peer_response,
+        plum_verify_error_detail, pull_request, pull_response, push_request, push_response,
         Acknowledgement, BranchCreateRequest, BranchCreateResponse, BranchDeleteRequest,
-        BranchDeleteResponse, BranchGetHeadRequest, BranchGetHeadResponse, BranchSetHeadRequest,
-        BranchSetHeadResponse, Content, ContentClass, ContentEncoding, ContentFormat,
-        ContentMetadata, ContentType, Nonce, Path, PathState, Plum, PlumBody, PlumBodySeal,
-        PlumHead, PlumHeadAndRelations, PlumHeadSeal, PlumHeadSealAndRelations, PlumMetadata,
+        BranchDeleteResponse, BranchErrorDetail, BranchGetHeadRequest, BranchGetHeadResponse,
+        BranchSetHeadRequest, BranchSetHeadResponse, Content, ContentClass, ContentEncoding,
+        ContentFormat, ContentMetadata, ContentType, DatahostStorageErrorDetail, ErrorDetail,
+        HasPlumsRequest, HasPlumsResponse, IsDependencyCompleteRequest,
+        IsDependencyCompleteResponse, LoadPlumBodyChunksRequest, LoadPlumBodyChunksResponse,
+        LoadPlumsRequest, LoadPlumsResponse, LoadedPlum, Nonce, PageCursor, Path, PathState,
+        PathStateChange, PathStateErrorDetail, PathStateGetRequest, PathStateGetResponse,
+        PathStateListRequest, PathStateListResponse, PeerError, PeerRequest, PeerResponse, Plum,
+        PlumBody, PlumBodyChunk, PlumBodyOffset, PlumBodySeal, PlumHead, PlumHeadAndRelations,
+        PlumHeadMetadataAndRelations, PlumHeadSeal, PlumHeadSealAndRelations, PlumMetadata,
         PlumMetadataSeal, PlumRelation, PlumRelationFlagsMapping, PlumRelationFlagsRaw,
-        PlumRelations, PlumRelationsSeal, PullRequest, PullResponse, PushRequest, PushResponse,
-        Seal, Sha256Sum, UnixNanoseconds,
+        PlumRelations, PlumRelationsSeal, PlumVerifyErrorDetail, PullRequest, PullResponse,
+        PushRequest, PushResponse, Seal, Sha256Sum, StorePlumBodyChunksRequest,
+        StorePlumBodyChunksResponse, StorePlumsRequest, StorePlumsResponse, UnixNanoseconds,
+        WatchPathStatesRequest, WatchPathStatesResponse,
     },
     hashable::Hashable,
+    human_readable_serde::{human_readable_str, CanonicalStr},
+    malformed_error_detail::MalformedErrorDetail,
+    partial_plum_body::PartialPlumBody,
+    plum_body_chunk::PLUM_BODY_CHUNK_SIZE,
+    plum_body_chunk_error::PlumBodyChunkError,
     plum_builder::PlumBuilder,
     plum_relation_flags::PlumRelationFlags,
     plum_relational::PlumRelational,
     plum_relations_builder::PlumRelationsBuilder,
     plum_verify_error::PlumVerifyError,
+    seal_algorithm::SealAlgorithm,
+    seal_error::SealError,
+    seal_hasher::SealHasher,
+    seal_key::SealKey,
+    seal_key_provider::SealKeyProvider,
     serializable::{serialize_and_encode_to_content, serialize_using_serde_format, Serializable},
+    text_decode_error::TextDecodeError,
+    text_encoding::TextEncoding,
 };
 
 #[cfg(feature = "client")]
@@ -0,0 +1,8 @@
+/// Produced when an ErrorDetail (or one of its constituents) can't be converted into the error that
+/// it represents.  This happens if its oneof value is missing, e.g. because it was produced by a newer
+/// IDPServer having error variants that this version doesn't know about.
+#[derive(Clone, Debug, thiserror::Error)]
+#[error("{message_name} had no value")]
+pub struct MalformedErrorDetail {
+    pub message_name: &'static str,
+}
@@ -1,4 +1,4 @@
-use crate::{Hashable, Nonce};
+use crate::{CanonicalStr, Hashable, Nonce, SealHasher, TextDecodeError, TextEncoding};
 
 impl Nonce {
     #[cfg(feature = "nonce-generate")]
@@ -11,19 +11,31 @@ impl Nonce {
         rng.fill(byte_v.as_mut_slice());
         Self { value: byte_v }
     }
+    /// Returns the canonical string form of this Nonce, which is the given TextEncoding of its bytes.
+    pub fn to_string_using(&self, text_encoding: TextEncoding) -> String {
+        text_encoding.encode(&self.value)
+    }
 }
 
 impl std::fmt::Display for Nonce {
+    /// Displays the canonical string form of this Nonce, using the default TextEncoding.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        for byte in &self.value {
-            write!(f, "{:02X}", byte)?
-        }
-        Ok(())
+        f.write_str(&self.to_string_using(TextEncoding::default()))
     }
 }
 
+impl std::str::FromStr for Nonce {
+    type Err = TextDecodeError;
+    /// Parses the canonical string form of a Nonce (in any TextEncoding).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Nonce::from(TextEncoding::decode(s)?))
+    }
+}
+
+impl CanonicalStr for Nonce {}
+
 impl Hashable for Nonce {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
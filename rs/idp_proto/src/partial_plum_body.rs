@@ -0,0 +1,119 @@
+use crate::{PlumBody, PlumBodyChunk, PlumBodyChunkError, PlumBodySeal, SealKeyProvider};
+
+/// Accumulates the PlumBodyChunk-s of a single PlumBody, in order, until it's complete, at which
+/// point it can be decoded and verified.  If a transfer is interrupted, the PartialPlumBody can be
+/// kept so that the transfer resumes from offset() instead of starting over.
+#[derive(Clone, Debug)]
+pub struct PartialPlumBody {
+    plum_body_seal: PlumBodySeal,
+    total_length: u64,
+    encoded_byte_v: Vec<u8>,
+}
+
+impl PartialPlumBody {
+    pub fn new(plum_body_seal: PlumBodySeal, total_length: u64) -> Self {
+        Self {
+            plum_body_seal,
+            total_length,
+            encoded_byte_v: Vec::new(),
+        }
+    }
+    pub fn plum_body_seal(&self) -> &PlumBodySeal {
+        &self.plum_body_seal
+    }
+    pub fn total_length(&self) -> u64 {
+        self.total_length
+    }
+    /// The number of bytes received so far, which is the offset at which the next PlumBodyChunk
+    /// must start.
+    pub fn offset(&self) -> u64 {
+        self.encoded_byte_v.len() as u64
+    }
+    pub fn is_complete(&self) -> bool {
+        self.offset() == self.total_length
+    }
+    pub fn append_chunk(
+        &mut self,
+        plum_body_chunk: PlumBodyChunk,
+    ) -> Result<(), PlumBodyChunkError> {
+        if plum_body_chunk.plum_body_seal != self.plum_body_seal {
+            return Err(PlumBodyChunkError::PlumBodySealMismatch {
+                expected_plum_body_seal: Box::new(self.plum_body_seal.clone()),
+                plum_body_seal: Box::new(plum_body_chunk.plum_body_seal),
+            });
+        }
+        if plum_body_chunk.total_length != self.total_length {
+            return Err(PlumBodyChunkError::TotalLengthMismatch {
+                plum_body_seal: self.plum_body_seal.clone(),
+                expected_total_length: self.total_length,
+                total_length: plum_body_chunk.total_length,
+            });
+        }
+        if plum_body_chunk.offset != self.offset() {
+            return Err(PlumBodyChunkError::OffsetMismatch {
+                plum_body_seal: self.plum_body_seal.clone(),
+                expected_offset: self.offset(),
+                offset: plum_body_chunk.offset,
+            });
+        }
+        let end_offset = plum_body_chunk.offset + plum_body_chunk.data.len() as u64;
+        if end_offset > self.total_length {
+            return Err(PlumBodyChunkError::OffsetOutOfRange {
+                plum_body_seal: self.plum_body_seal.clone(),
+                offset: end_offset,
+                total_length: self.total_length,
+            });
+        }
+        self.encoded_byte_v.extend_from_slice(&plum_body_chunk.data);
+        Ok(())
+    }
+    /// Decode the complete PlumBody and verify that its computed PlumBodySeal is the expected one.  A
+    /// keyed PlumBodySeal is computed using the SealKey from seal_key_provider_o.  If the PlumBodySeal
+    /// can't be computed (e.g. because it's keyed and seal_key_provider_o is None or doesn't have the
+    /// SealKey), then that's an error.
+    pub fn into_plum_body(
+        self,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<PlumBody, PlumBodyChunkError> {
+        if !self.is_complete() {
+            return Err(PlumBodyChunkError::Incomplete {
+                plum_body_seal: self.plum_body_seal,
+                offset: self.encoded_byte_v.len() as u64,
+                total_length: self.total_length,
+            });
+        }
+        use prost::Message;
+        let plum_body = PlumBody::decode(self.encoded_byte_v.as_slice()).map_err(|e| {
+            PlumBodyChunkError::DecodeFailed {
+                plum_body_seal: self.plum_body_seal.clone(),
+                description: e.to_string(),
+            }
+        })?;
+        // A PlumBody whose seal can't be checked is rejected, rather than being trusted.
+        let computed_seal = match self
+            .plum_body_seal
+            .value
+            .compute_matching(&plum_body, seal_key_provider_o)
+        {
+            Ok(Some(computed_seal)) => computed_seal,
+            Ok(None) => {
+                return Err(PlumBodyChunkError::UnverifiableKeyedPlumBodySeal {
+                    plum_body_seal: self.plum_body_seal,
+                });
+            }
+            Err(e) => {
+                return Err(PlumBodyChunkError::PlumBodySealComputationFailed {
+                    plum_body_seal: self.plum_body_seal,
+                    description: e.to_string(),
+                });
+            }
+        };
+        if !computed_seal.constant_time_eq(&self.plum_body_seal.value) {
+            return Err(PlumBodyChunkError::ComputedPlumBodySealMismatch {
+                computed_plum_body_seal: Box::new(PlumBodySeal::from(computed_seal)),
+                expected_plum_body_seal: Box::new(self.plum_body_seal),
+            });
+        }
+        Ok(plum_body)
+    }
+}
@@ -1,23 +1,40 @@
-use crate::{
-    Plum, PlumBodySeal, PlumHeadSeal, PlumMetadataSeal, PlumRelationsSeal, PlumVerifyError,
-};
+use crate::{Plum, PlumHeadSeal, PlumVerifyError, SealKeyProvider};
 
 impl Plum {
     /// Verify that the seals in the PlumHead match the computed seals of its components,
-    /// and verify all other constraints between the components.
+    /// and verify all other constraints between the components.  A keyed seal (see SealKey) can only
+    /// be verified using its SealKey, so keyed seals aren't checked by this; use
+    /// verify_with_seal_key_provider to check them too.
     pub fn verify(&self) -> Result<(), PlumVerifyError> {
+        self.verify_impl(None)
+    }
+    /// Same as verify, except that keyed seals are also checked, using the SealKey-s from the given
+    /// SealKeyProvider.  If a needed SealKey isn't provided, then that's an error.
+    pub fn verify_with_seal_key_provider(
+        &self,
+        seal_key_provider: &dyn SealKeyProvider,
+    ) -> Result<(), PlumVerifyError> {
+        self.verify_impl(Some(seal_key_provider))
+    }
+    fn verify_impl(
+        &self,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<(), PlumVerifyError> {
         //
-        // First, compute the seals from this Plum's actual content, and verify the seals.
+        // First, compute the seals from this Plum's actual content, and verify the seals.  Each seal
+        // is computed in the same way as the corresponding seal in the PlumHead (see
+        // Seal::compute_matching).
         //
 
-        let computed_plum_metadata_seal = PlumMetadataSeal::from(&self.plum_metadata);
-        let computed_plum_relations_seal = PlumRelationsSeal::from(&self.plum_relations);
-        let computed_plum_body_seal = PlumBodySeal::from(&self.plum_body);
-        self.plum_head.verify_seals(
-            &computed_plum_metadata_seal,
-            &computed_plum_relations_seal,
-            &computed_plum_body_seal,
-        )?;
+        self.plum_head
+            .plum_metadata_seal
+            .verify(&self.plum_metadata, seal_key_provider_o)?;
+        self.plum_head
+            .plum_relations_seal
+            .verify(&self.plum_relations, seal_key_provider_o)?;
+        self.plum_head
+            .plum_body_seal
+            .verify(&self.plum_body, seal_key_provider_o)?;
 
         //
         // Then verify all the various higher-order constraints between the components.
@@ -26,11 +43,10 @@ impl Plum {
         // The PlumRelations source_plum_body_seal must match the PlumHead plum_body_seal.
         if self.plum_relations.source_plum_body_seal != self.plum_head.plum_body_seal {
             return Err(PlumVerifyError::PlumBodySealRedundancyMismatch {
-                plum_head_plum_body_seal: self.plum_head.plum_body_seal.clone(),
-                plum_relations_source_plum_body_seal: self
-                    .plum_relations
-                    .source_plum_body_seal
-                    .clone(),
+                plum_head_plum_body_seal: Box::new(self.plum_head.plum_body_seal.clone()),
+                plum_relations_source_plum_body_seal: Box::new(
+                    self.plum_relations.source_plum_body_seal.clone(),
+                ),
             });
         }
         // If PlumMetadata contains a PlumBodyContentMetadata, then it must match that of the PlumBody.
@@ -43,13 +59,12 @@ impl Plum {
                 let plum_head_seal = PlumHeadSeal::from(self);
                 return Err(PlumVerifyError::PlumBodyContentMetadataRedundancyMismatch {
                     plum_head_seal,
-                    plum_metadata_plum_body_content_metadata:
+                    plum_metadata_plum_body_content_metadata: Box::new(
                         plum_metadata_plum_body_content_metadata.clone(),
-                    plum_body_plum_body_content_metadata: self
-                        .plum_body
-                        .plum_body_content
-                        .content_metadata
-                        .clone(),
+                    ),
+                    plum_body_plum_body_content_metadata: Box::new(
+                        self.plum_body.plum_body_content.content_metadata.clone(),
+                    ),
                 });
             }
         }
@@ -1,9 +1,18 @@
-use crate::{Hashable, PlumBody};
+use crate::{Hashable, PlumBody, SealHasher, PLUM_BODY_CHUNK_SIZE};
 
 impl Hashable for PlumBody {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.plum_body_nonce_o.update_hasher(hasher);
         self.plum_body_content.update_hasher(hasher);
     }
 }
+
+impl PlumBody {
+    /// Returns true iff this PlumBody is too large to be transferred in a single message, in which
+    /// case it should be transferred as a sequence of PlumBodyChunk-s.  See PLUM_BODY_CHUNK_SIZE.
+    pub fn requires_chunking(&self) -> bool {
+        use prost::Message;
+        self.encoded_len() > PLUM_BODY_CHUNK_SIZE
+    }
+}
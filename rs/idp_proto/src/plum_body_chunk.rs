@@ -0,0 +1,59 @@
+use crate::{PlumBody, PlumBodyChunk, PlumBodyChunkError, PlumBodySeal};
+
+/// PlumBody-s whose encoded length exceeds this are transferred as a sequence of PlumBodyChunk-s,
+/// each carrying at most this many bytes.  This is comfortably under tonic's default maximum
+/// decoded message size of 4 MiB.
+pub const PLUM_BODY_CHUNK_SIZE: usize = 1 << 20;
+
+impl PlumBodyChunk {
+    /// Splits the encoded form of plum_body into PlumBodyChunk-s of at most chunk_size bytes each,
+    /// starting at the given offset (which is nonzero when resuming an interrupted transfer), which
+    /// must be less than the encoded length.  The PlumBodyChunk-s are produced lazily, so that only
+    /// the ones that are about to be sent are held in memory (in addition to the encoded PlumBody).
+    /// plum_body_seal must be the PlumBodySeal of plum_body; it's passed in so that it doesn't
+    /// have to be recomputed.
+    pub fn chunk_i_from(
+        plum_body_seal: &PlumBodySeal,
+        plum_body: &PlumBody,
+        offset: u64,
+        chunk_size: usize,
+    ) -> Result<impl Iterator<Item = PlumBodyChunk> + Send + 'static, PlumBodyChunkError> {
+        assert!(
+            chunk_size > 0,
+            "programmer error: chunk_size must be positive"
+        );
+        use prost::Message;
+        let encoded_byte_v = plum_body.encode_to_vec();
+        let total_length = encoded_byte_v.len() as u64;
+        // Because an encoded PlumBody is never empty, this always produces at least one chunk.
+        if offset >= total_length {
+            return Err(PlumBodyChunkError::OffsetOutOfRange {
+                plum_body_seal: plum_body_seal.clone(),
+                offset,
+                total_length,
+            });
+        }
+
+        let plum_body_seal = plum_body_seal.clone();
+        Ok((offset..total_length)
+            .step_by(chunk_size)
+            .map(move |chunk_offset| {
+                let chunk_end = std::cmp::min(chunk_offset + chunk_size as u64, total_length);
+                PlumBodyChunk {
+                    plum_body_seal: plum_body_seal.clone(),
+                    total_length,
+                    offset: chunk_offset,
+                    data: encoded_byte_v[chunk_offset as usize..chunk_end as usize].to_vec(),
+                }
+            }))
+    }
+    /// Like chunk_i_from, but produces all the PlumBodyChunk-s at once.
+    pub fn chunk_v_from(
+        plum_body_seal: &PlumBodySeal,
+        plum_body: &PlumBody,
+        offset: u64,
+        chunk_size: usize,
+    ) -> Result<Vec<PlumBodyChunk>, PlumBodyChunkError> {
+        Ok(Self::chunk_i_from(plum_body_seal, plum_body, offset, chunk_size)?.collect())
+    }
+}
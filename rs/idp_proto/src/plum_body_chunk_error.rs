@@ -0,0 +1,55 @@
+use crate::PlumBodySeal;
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum PlumBodyChunkError {
+    #[error("expected a PlumBodyChunk for PlumBody {expected_plum_body_seal} but got one for PlumBody {plum_body_seal}")]
+    PlumBodySealMismatch {
+        // These are boxed so that PlumBodyChunkError stays small.
+        expected_plum_body_seal: Box<PlumBodySeal>,
+        plum_body_seal: Box<PlumBodySeal>,
+    },
+    #[error("PlumBody {plum_body_seal} was expected to have total length {expected_total_length} but PlumBodyChunk had total_length {total_length}")]
+    TotalLengthMismatch {
+        plum_body_seal: PlumBodySeal,
+        expected_total_length: u64,
+        total_length: u64,
+    },
+    #[error("PlumBody {plum_body_seal} expected a PlumBodyChunk at offset {expected_offset} but got one at offset {offset}")]
+    OffsetMismatch {
+        plum_body_seal: PlumBodySeal,
+        expected_offset: u64,
+        offset: u64,
+    },
+    #[error(
+        "PlumBody {plum_body_seal} has total length {total_length}, so offset {offset} is out of range"
+    )]
+    OffsetOutOfRange {
+        plum_body_seal: PlumBodySeal,
+        offset: u64,
+        total_length: u64,
+    },
+    #[error("PlumBody {plum_body_seal} is incomplete; have {offset} of {total_length} bytes")]
+    Incomplete {
+        plum_body_seal: PlumBodySeal,
+        offset: u64,
+        total_length: u64,
+    },
+    #[error("PlumBody {plum_body_seal} failed to decode; {description}")]
+    DecodeFailed {
+        plum_body_seal: PlumBodySeal,
+        description: String,
+    },
+    #[error("computed PlumBodySeal of reassembled PlumBody was {computed_plum_body_seal} but expected {expected_plum_body_seal}")]
+    ComputedPlumBodySealMismatch {
+        // These are boxed so that PlumBodyChunkError stays small.
+        computed_plum_body_seal: Box<PlumBodySeal>,
+        expected_plum_body_seal: Box<PlumBodySeal>,
+    },
+    #[error("PlumBodySeal of reassembled PlumBody {plum_body_seal} could not be computed; {description}")]
+    PlumBodySealComputationFailed {
+        plum_body_seal: PlumBodySeal,
+        description: String,
+    },
+    #[error("reassembled PlumBody {plum_body_seal} has a keyed PlumBodySeal, which can't be checked without its SealKey")]
+    UnverifiableKeyedPlumBodySeal { plum_body_seal: PlumBodySeal },
+}
@@ -1,4 +1,47 @@
-use crate::{Hashable, PlumBody, PlumBodySeal, Seal, Sha256Sum};
+use crate::{
+    CanonicalStr, Hashable, PlumBody, PlumBodySeal, PlumVerifyError, Seal, SealAlgorithm,
+    SealError, SealHasher, SealKey, SealKeyProvider, TextDecodeError, TextEncoding,
+};
+
+impl PlumBodySeal {
+    /// Computes the PlumBodySeal of the given PlumBody using the given SealAlgorithm.
+    pub fn compute(plum_body: &PlumBody, seal_algorithm: SealAlgorithm) -> Self {
+        PlumBodySeal::from(seal_algorithm.compute_seal(plum_body))
+    }
+    /// Computes the keyed PlumBodySeal of the given PlumBody under the given SealKey using the given
+    /// SealAlgorithm.
+    pub fn compute_keyed(
+        plum_body: &PlumBody,
+        seal_algorithm: SealAlgorithm,
+        seal_key: &SealKey,
+    ) -> Self {
+        PlumBodySeal::from(seal_algorithm.compute_keyed_seal(seal_key, plum_body))
+    }
+    /// Verifies that this is the PlumBodySeal of the given PlumBody.  A keyed seal can only be verified using its
+    /// SealKey, so if seal_key_provider_o is None, then a keyed seal isn't checked.
+    pub fn verify(
+        &self,
+        plum_body: &PlumBody,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<(), PlumVerifyError> {
+        if let Some(computed_seal) = self
+            .value
+            .compute_matching(plum_body, seal_key_provider_o)?
+        {
+            if !computed_seal.constant_time_eq(&self.value) {
+                return Err(PlumVerifyError::ComputedPlumBodySealMismatch {
+                    computed_plum_body_seal: Box::new(PlumBodySeal::from(computed_seal)),
+                    expected_plum_body_seal: Box::new(self.clone()),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Returns the canonical string form of this PlumBodySeal (see Seal::to_string_using).
+    pub fn to_string_using(&self, text_encoding: TextEncoding) -> String {
+        self.value.to_string_using(text_encoding)
+    }
+}
 
 impl std::fmt::Display for PlumBodySeal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -6,30 +49,38 @@ impl std::fmt::Display for PlumBodySeal {
     }
 }
 
+impl std::str::FromStr for PlumBodySeal {
+    type Err = TextDecodeError;
+    /// Parses the canonical string form of a Seal (see Seal's FromStr).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(PlumBodySeal::from(s.parse::<Seal>()?))
+    }
+}
+
 impl From<&PlumBody> for PlumBodySeal {
+    /// This uses the default SealAlgorithm; use PlumBodySeal::compute to specify it.
     fn from(plum_body: &PlumBody) -> PlumBodySeal {
-        use sha2::Digest;
-
-        // For now, a seal is only the Sha256Sum, but it could be other stuff later.
-        let mut hasher = sha2::Sha256::new();
-
-        plum_body.update_hasher(&mut hasher);
+        PlumBodySeal::compute(plum_body, SealAlgorithm::default())
+    }
+}
 
-        PlumBodySeal::from(Seal::from(Sha256Sum::from(hasher.finalize().to_vec())))
+impl TryFrom<Vec<u8>> for PlumBodySeal {
+    type Error = SealError;
+    /// Inverse of Seal::as_bytes (i.e. this is how a PlumBodySeal is loaded from storage).
+    fn try_from(byte_v: Vec<u8>) -> Result<Self, Self::Error> {
+        Ok(PlumBodySeal::from(Seal::try_from_bytes(&byte_v)?))
     }
 }
 
-impl From<Vec<u8>> for PlumBodySeal {
-    fn from(byte_v: Vec<u8>) -> PlumBodySeal {
-        if byte_v.len() != 32 {
-            panic!("programmer error: PlumBodySeal must be 32 bytes long");
-        }
-        PlumBodySeal::from(Seal::from(Sha256Sum::from(byte_v)))
+impl CanonicalStr for PlumBodySeal {
+    /// A PlumBodySeal that isn't canonical (see Seal::is_canonical) has no canonical string form.
+    fn has_canonical_str(&self) -> bool {
+        self.value.is_canonical()
     }
 }
 
 impl Hashable for PlumBodySeal {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
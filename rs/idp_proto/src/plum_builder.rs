@@ -1,7 +1,7 @@
 use crate::{
     serialize_and_encode_to_content, Content, ContentEncoding, ContentFormat, Nonce, Plum,
     PlumBody, PlumBodySeal, PlumHead, PlumMetadata, PlumMetadataSeal, PlumRelational,
-    PlumRelationsBuilder, PlumRelationsSeal, Serializable, UnixNanoseconds,
+    PlumRelationsBuilder, PlumRelationsSeal, SealAlgorithm, SealKey, Serializable, UnixNanoseconds,
 };
 use anyhow::Result;
 
@@ -14,6 +14,8 @@ pub struct PlumBuilder {
     plum_relations_builder: PlumRelationsBuilder,
     plum_body_nonce_o: Option<Nonce>,
     plum_body_content_o: Option<Content>,
+    seal_algorithm: SealAlgorithm,
+    seal_key_o: Option<SealKey>,
 }
 
 impl Default for PlumBuilder {
@@ -27,6 +29,8 @@ impl Default for PlumBuilder {
             plum_relations_builder: PlumRelationsBuilder::new(),
             plum_body_nonce_o: None,
             plum_body_content_o: None,
+            seal_algorithm: SealAlgorithm::default(),
+            seal_key_o: None,
         }
     }
 }
@@ -47,13 +51,23 @@ impl PlumBuilder {
             plum_body_nonce_o: self.plum_body_nonce_o,
             plum_body_content: self.plum_body_content_o.unwrap(),
         };
-        let plum_body_seal = PlumBodySeal::from(&plum_body);
+        let plum_body_seal = match self.seal_key_o.as_ref() {
+            Some(seal_key) => {
+                PlumBodySeal::compute_keyed(&plum_body, self.seal_algorithm, seal_key)
+            }
+            None => PlumBodySeal::compute(&plum_body, self.seal_algorithm),
+        };
 
         self.plum_relations_builder = self
             .plum_relations_builder
             .with_source_plum_body_seal(plum_body_seal.clone());
         let plum_relations = self.plum_relations_builder.build()?;
-        let plum_relations_seal = PlumRelationsSeal::from(&plum_relations);
+        let plum_relations_seal = match self.seal_key_o.as_ref() {
+            Some(seal_key) => {
+                PlumRelationsSeal::compute_keyed(&plum_relations, self.seal_algorithm, seal_key)
+            }
+            None => PlumRelationsSeal::compute(&plum_relations, self.seal_algorithm),
+        };
 
         let plum_body_content_metadata_o =
             if self.plum_metadata_should_include_plum_body_content_metadata {
@@ -68,7 +82,12 @@ impl PlumBuilder {
             plum_body_content_metadata_o,
             additional_content_o: self.plum_metadata_additional_content_o,
         };
-        let plum_metadata_seal = PlumMetadataSeal::from(&plum_metadata);
+        let plum_metadata_seal = match self.seal_key_o.as_ref() {
+            Some(seal_key) => {
+                PlumMetadataSeal::compute_keyed(&plum_metadata, self.seal_algorithm, seal_key)
+            }
+            None => PlumMetadataSeal::compute(&plum_metadata, self.seal_algorithm),
+        };
 
         let plum_head = PlumHead {
             plum_head_nonce_o: self.plum_head_nonce_o,
@@ -129,6 +148,22 @@ impl PlumBuilder {
         self.plum_body_nonce_o = Some(plum_body_nonce);
         self
     }
+    /// Specifies the SealAlgorithm used to compute all of the Plum's seals (including its PlumHeadSeal,
+    /// which is computed using the SealAlgorithm of plum_body_seal).  Default is SealAlgorithm::Sha256.
+    pub fn with_seal_algorithm(mut self, seal_algorithm: SealAlgorithm) -> Self {
+        self.seal_algorithm = seal_algorithm;
+        self
+    }
+    /// Specifies that the Plum's PlumMetadataSeal, PlumRelationsSeal, and PlumBodySeal should be keyed
+    /// seals computed under the given SealKey (using the SealAlgorithm specified by with_seal_algorithm),
+    /// so that outsiders can't confirm guesses of low-entropy private content.  The PlumHeadSeal
+    /// remains unkeyed, since it's the address of the Plum and anyone storing it must be able to
+    /// compute it, but since it's computed from the keyed seals, it reveals nothing about the content
+    /// either.  Default is no SealKey.
+    pub fn with_seal_key(mut self, seal_key: SealKey) -> Self {
+        self.seal_key_o = Some(seal_key);
+        self
+    }
     /// Convenience method which derives the plum relations and the plum body content from the given
     /// value.  This does not alter the plum relations nonce or the plum body nonce.
     pub fn with_plum_relations_and_plum_body_content_from<'a, T>(
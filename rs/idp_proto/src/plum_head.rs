@@ -1,5 +1,6 @@
 use crate::{
     Hashable, PlumBodySeal, PlumHead, PlumMetadataSeal, PlumRelationsSeal, PlumVerifyError,
+    SealHasher,
 };
 
 impl PlumHead {
@@ -7,10 +8,13 @@ impl PlumHead {
         &self,
         computed_plum_metadata_seal: &PlumMetadataSeal,
     ) -> Result<(), PlumVerifyError> {
-        if *computed_plum_metadata_seal != self.plum_metadata_seal {
+        if !computed_plum_metadata_seal
+            .value
+            .constant_time_eq(&self.plum_metadata_seal.value)
+        {
             return Err(PlumVerifyError::ComputedPlumMetadataSealMismatch {
-                computed_plum_metadata_seal: computed_plum_metadata_seal.clone(),
-                expected_plum_metadata_seal: self.plum_metadata_seal.clone(),
+                computed_plum_metadata_seal: Box::new(computed_plum_metadata_seal.clone()),
+                expected_plum_metadata_seal: Box::new(self.plum_metadata_seal.clone()),
             });
         }
         Ok(())
@@ -19,10 +23,13 @@ impl PlumHead {
         &self,
         computed_plum_relations_seal: &PlumRelationsSeal,
     ) -> Result<(), PlumVerifyError> {
-        if *computed_plum_relations_seal != self.plum_relations_seal {
+        if !computed_plum_relations_seal
+            .value
+            .constant_time_eq(&self.plum_relations_seal.value)
+        {
             return Err(PlumVerifyError::ComputedPlumRelationsSealMismatch {
-                computed_plum_relations_seal: computed_plum_relations_seal.clone(),
-                expected_plum_relations_seal: self.plum_relations_seal.clone(),
+                computed_plum_relations_seal: Box::new(computed_plum_relations_seal.clone()),
+                expected_plum_relations_seal: Box::new(self.plum_relations_seal.clone()),
             });
         }
         Ok(())
@@ -31,10 +38,13 @@ impl PlumHead {
         &self,
         computed_plum_body_seal: &PlumBodySeal,
     ) -> Result<(), PlumVerifyError> {
-        if *computed_plum_body_seal != self.plum_body_seal {
+        if !computed_plum_body_seal
+            .value
+            .constant_time_eq(&self.plum_body_seal.value)
+        {
             return Err(PlumVerifyError::ComputedPlumBodySealMismatch {
-                computed_plum_body_seal: computed_plum_body_seal.clone(),
-                expected_plum_body_seal: self.plum_body_seal.clone(),
+                computed_plum_body_seal: Box::new(computed_plum_body_seal.clone()),
+                expected_plum_body_seal: Box::new(self.plum_body_seal.clone()),
             });
         }
         Ok(())
@@ -54,7 +64,7 @@ impl PlumHead {
 }
 
 impl Hashable for PlumHead {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.plum_head_nonce_o.update_hasher(hasher);
         self.plum_metadata_seal.update_hasher(hasher);
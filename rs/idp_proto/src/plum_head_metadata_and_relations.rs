@@ -0,0 +1,97 @@
+use crate::{
+    Plum, PlumBody, PlumHeadMetadataAndRelations, PlumHeadSeal, PlumVerifyError, SealKeyProvider,
+};
+
+impl PlumHeadMetadataAndRelations {
+    /// Verify that the seals in the PlumHead match the computed seals of the PlumMetadata and
+    /// PlumRelations, and verify the constraints between them that don't involve the PlumBody.
+    /// As with Plum::verify, keyed seals aren't checked.
+    pub fn verify(&self) -> Result<(), PlumVerifyError> {
+        self.verify_impl(None)
+    }
+    /// Same as verify, except that keyed seals are also checked, as in
+    /// Plum::verify_with_seal_key_provider.
+    pub fn verify_with_seal_key_provider(
+        &self,
+        seal_key_provider: &dyn SealKeyProvider,
+    ) -> Result<(), PlumVerifyError> {
+        self.verify_impl(Some(seal_key_provider))
+    }
+    fn verify_impl(
+        &self,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<(), PlumVerifyError> {
+        self.plum_head
+            .plum_metadata_seal
+            .verify(&self.plum_metadata, seal_key_provider_o)?;
+        self.plum_head
+            .plum_relations_seal
+            .verify(&self.plum_relations, seal_key_provider_o)?;
+
+        // The PlumRelations source_plum_body_seal must match the PlumHead plum_body_seal.
+        if self.plum_relations.source_plum_body_seal != self.plum_head.plum_body_seal {
+            return Err(PlumVerifyError::PlumBodySealRedundancyMismatch {
+                plum_head_plum_body_seal: Box::new(self.plum_head.plum_body_seal.clone()),
+                plum_relations_source_plum_body_seal: Box::new(
+                    self.plum_relations.source_plum_body_seal.clone(),
+                ),
+            });
+        }
+
+        Ok(())
+    }
+    /// Verify the constraints between this and the given PlumBody, i.e. the ones that verify doesn't
+    /// check.  Together, verify and verify_plum_body are equivalent to Plum::verify.
+    pub fn verify_plum_body(&self, plum_body: &PlumBody) -> Result<(), PlumVerifyError> {
+        self.verify_plum_body_impl(plum_body, None)
+    }
+    /// Same as verify_plum_body, except that a keyed PlumBodySeal is also checked, as in
+    /// Plum::verify_with_seal_key_provider.
+    pub fn verify_plum_body_with_seal_key_provider(
+        &self,
+        plum_body: &PlumBody,
+        seal_key_provider: &dyn SealKeyProvider,
+    ) -> Result<(), PlumVerifyError> {
+        self.verify_plum_body_impl(plum_body, Some(seal_key_provider))
+    }
+    fn verify_plum_body_impl(
+        &self,
+        plum_body: &PlumBody,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<(), PlumVerifyError> {
+        self.plum_head
+            .plum_body_seal
+            .verify(plum_body, seal_key_provider_o)?;
+
+        // If PlumMetadata contains a PlumBodyContentMetadata, then it must match that of the PlumBody.
+        if let Some(plum_metadata_plum_body_content_metadata) =
+            self.plum_metadata.plum_body_content_metadata_o.as_ref()
+        {
+            if *plum_metadata_plum_body_content_metadata
+                != plum_body.plum_body_content.content_metadata
+            {
+                return Err(PlumVerifyError::PlumBodyContentMetadataRedundancyMismatch {
+                    plum_head_seal: PlumHeadSeal::from(&self.plum_head),
+                    plum_metadata_plum_body_content_metadata: Box::new(
+                        plum_metadata_plum_body_content_metadata.clone(),
+                    ),
+                    plum_body_plum_body_content_metadata: Box::new(
+                        plum_body.plum_body_content.content_metadata.clone(),
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Plum> for PlumHeadMetadataAndRelations {
+    fn from(plum: Plum) -> Self {
+        Self {
+            plum_head: plum.plum_head,
+            plum_metadata: plum.plum_metadata,
+            plum_relations: plum.plum_relations,
+        }
+    }
+}
@@ -1,4 +1,18 @@
-use crate::{Hashable, Plum, PlumHead, PlumHeadSeal, Seal, Sha256Sum};
+use crate::{
+    CanonicalStr, Hashable, Plum, PlumHead, PlumHeadSeal, Seal, SealAlgorithm, SealError,
+    SealHasher, TextDecodeError, TextEncoding,
+};
+
+impl PlumHeadSeal {
+    /// Computes the PlumHeadSeal of the given PlumHead using the given SealAlgorithm.
+    pub fn compute(plum_head: &PlumHead, seal_algorithm: SealAlgorithm) -> Self {
+        PlumHeadSeal::from(seal_algorithm.compute_seal(plum_head))
+    }
+    /// Returns the canonical string form of this PlumHeadSeal (see Seal::to_string_using).
+    pub fn to_string_using(&self, text_encoding: TextEncoding) -> String {
+        self.value.to_string_using(text_encoding)
+    }
+}
 
 impl std::fmt::Display for PlumHeadSeal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -6,6 +20,14 @@ impl std::fmt::Display for PlumHeadSeal {
     }
 }
 
+impl std::str::FromStr for PlumHeadSeal {
+    type Err = TextDecodeError;
+    /// Parses the canonical string form of a Seal (see Seal's FromStr).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(PlumHeadSeal::from(s.parse::<Seal>()?))
+    }
+}
+
 impl From<&Plum> for PlumHeadSeal {
     fn from(plum: &Plum) -> PlumHeadSeal {
         PlumHeadSeal::from(&plum.plum_head)
@@ -13,29 +35,37 @@ impl From<&Plum> for PlumHeadSeal {
 }
 
 impl From<&PlumHead> for PlumHeadSeal {
+    /// A PlumHeadSeal is computed using the SealAlgorithm of the PlumHead's plum_body_seal (PlumBuilder
+    /// uses the same SealAlgorithm for all of a Plum's seals), so that it's determined by the PlumHead
+    /// alone.  If plum_body_seal is malformed, then the default SealAlgorithm is used.  A PlumHeadSeal
+    /// is never keyed, even if plum_body_seal is (see PlumBuilder::with_seal_key).
     fn from(plum_head: &PlumHead) -> PlumHeadSeal {
-        use sha2::Digest;
-
-        // For now, a seal is only the Sha256Sum, but it could be other stuff later.
-        let mut hasher = sha2::Sha256::new();
-
-        plum_head.update_hasher(&mut hasher);
+        let seal_algorithm = plum_head
+            .plum_body_seal
+            .value
+            .seal_algorithm()
+            .unwrap_or_default();
+        PlumHeadSeal::compute(plum_head, seal_algorithm)
+    }
+}
 
-        PlumHeadSeal::from(Seal::from(Sha256Sum::from(hasher.finalize().to_vec())))
+impl TryFrom<Vec<u8>> for PlumHeadSeal {
+    type Error = SealError;
+    /// Inverse of Seal::as_bytes (i.e. this is how a PlumHeadSeal is loaded from storage).
+    fn try_from(byte_v: Vec<u8>) -> Result<Self, Self::Error> {
+        Ok(PlumHeadSeal::from(Seal::try_from_bytes(&byte_v)?))
     }
 }
 
-impl From<Vec<u8>> for PlumHeadSeal {
-    fn from(byte_v: Vec<u8>) -> PlumHeadSeal {
-        if byte_v.len() != 32 {
-            panic!("programmer error: PlumHeadSeal must be 32 bytes long");
-        }
-        PlumHeadSeal::from(Seal::from(Sha256Sum::from(byte_v)))
+impl CanonicalStr for PlumHeadSeal {
+    /// A PlumHeadSeal that isn't canonical (see Seal::is_canonical) has no canonical string form.
+    fn has_canonical_str(&self) -> bool {
+        self.value.is_canonical()
     }
 }
 
 impl Hashable for PlumHeadSeal {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -1,4 +1,4 @@
-use crate::{Hashable, PlumMetadata};
+use crate::{Hashable, PlumMetadata, SealHasher};
 
 impl PlumMetadata {
     pub fn empty() -> Self {
@@ -12,7 +12,7 @@ impl PlumMetadata {
 }
 
 impl Hashable for PlumMetadata {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.plum_metadata_nonce_o.update_hasher(hasher);
         self.plum_created_at_o.update_hasher(hasher);
@@ -1,4 +1,47 @@
-use crate::{Hashable, Plum, PlumMetadata, PlumMetadataSeal, Seal, Sha256Sum};
+use crate::{
+    CanonicalStr, Hashable, Plum, PlumMetadata, PlumMetadataSeal, PlumVerifyError, Seal,
+    SealAlgorithm, SealError, SealHasher, SealKey, SealKeyProvider, TextDecodeError, TextEncoding,
+};
+
+impl PlumMetadataSeal {
+    /// Computes the PlumMetadataSeal of the given PlumMetadata using the given SealAlgorithm.
+    pub fn compute(plum_metadata: &PlumMetadata, seal_algorithm: SealAlgorithm) -> Self {
+        PlumMetadataSeal::from(seal_algorithm.compute_seal(plum_metadata))
+    }
+    /// Computes the keyed PlumMetadataSeal of the given PlumMetadata under the given SealKey using the given
+    /// SealAlgorithm.
+    pub fn compute_keyed(
+        plum_metadata: &PlumMetadata,
+        seal_algorithm: SealAlgorithm,
+        seal_key: &SealKey,
+    ) -> Self {
+        PlumMetadataSeal::from(seal_algorithm.compute_keyed_seal(seal_key, plum_metadata))
+    }
+    /// Verifies that this is the PlumMetadataSeal of the given PlumMetadata.  A keyed seal can only be verified using its
+    /// SealKey, so if seal_key_provider_o is None, then a keyed seal isn't checked.
+    pub fn verify(
+        &self,
+        plum_metadata: &PlumMetadata,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<(), PlumVerifyError> {
+        if let Some(computed_seal) = self
+            .value
+            .compute_matching(plum_metadata, seal_key_provider_o)?
+        {
+            if !computed_seal.constant_time_eq(&self.value) {
+                return Err(PlumVerifyError::ComputedPlumMetadataSealMismatch {
+                    computed_plum_metadata_seal: Box::new(PlumMetadataSeal::from(computed_seal)),
+                    expected_plum_metadata_seal: Box::new(self.clone()),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Returns the canonical string form of this PlumMetadataSeal (see Seal::to_string_using).
+    pub fn to_string_using(&self, text_encoding: TextEncoding) -> String {
+        self.value.to_string_using(text_encoding)
+    }
+}
 
 impl std::fmt::Display for PlumMetadataSeal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -6,36 +49,52 @@ impl std::fmt::Display for PlumMetadataSeal {
     }
 }
 
+impl std::str::FromStr for PlumMetadataSeal {
+    type Err = TextDecodeError;
+    /// Parses the canonical string form of a Seal (see Seal's FromStr).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(PlumMetadataSeal::from(s.parse::<Seal>()?))
+    }
+}
+
 impl From<&Plum> for PlumMetadataSeal {
+    /// This uses the SealAlgorithm of the corresponding seal in the Plum's PlumHead.  The result is
+    /// never keyed; use PlumMetadataSeal::verify to check a keyed seal.
     fn from(plum: &Plum) -> PlumMetadataSeal {
-        PlumMetadataSeal::from(&plum.plum_metadata)
+        let seal_algorithm = plum
+            .plum_head
+            .plum_metadata_seal
+            .value
+            .seal_algorithm()
+            .unwrap_or_default();
+        PlumMetadataSeal::compute(&plum.plum_metadata, seal_algorithm)
     }
 }
 
 impl From<&PlumMetadata> for PlumMetadataSeal {
+    /// This uses the default SealAlgorithm; use PlumMetadataSeal::compute to specify it.
     fn from(plum_metadata: &PlumMetadata) -> PlumMetadataSeal {
-        use sha2::Digest;
-
-        // For now, a seal is only the Sha256Sum, but it could be other stuff later.
-        let mut hasher = sha2::Sha256::new();
-
-        plum_metadata.update_hasher(&mut hasher);
+        PlumMetadataSeal::compute(plum_metadata, SealAlgorithm::default())
+    }
+}
 
-        PlumMetadataSeal::from(Seal::from(Sha256Sum::from(hasher.finalize().to_vec())))
+impl TryFrom<Vec<u8>> for PlumMetadataSeal {
+    type Error = SealError;
+    /// Inverse of Seal::as_bytes (i.e. this is how a PlumMetadataSeal is loaded from storage).
+    fn try_from(byte_v: Vec<u8>) -> Result<Self, Self::Error> {
+        Ok(PlumMetadataSeal::from(Seal::try_from_bytes(&byte_v)?))
     }
 }
 
-impl From<Vec<u8>> for PlumMetadataSeal {
-    fn from(byte_v: Vec<u8>) -> PlumMetadataSeal {
-        if byte_v.len() != 32 {
-            panic!("programmer error: PlumMetadataSeal must be 32 bytes long");
-        }
-        PlumMetadataSeal::from(Seal::from(Sha256Sum::from(byte_v)))
+impl CanonicalStr for PlumMetadataSeal {
+    /// A PlumMetadataSeal that isn't canonical (see Seal::is_canonical) has no canonical string form.
+    fn has_canonical_str(&self) -> bool {
+        self.value.is_canonical()
     }
 }
 
 impl Hashable for PlumMetadataSeal {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
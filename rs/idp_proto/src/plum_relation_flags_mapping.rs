@@ -1,7 +1,7 @@
-use crate::{Hashable, PlumRelationFlagsMapping};
+use crate::{Hashable, PlumRelationFlagsMapping, SealHasher};
 
 impl Hashable for PlumRelationFlagsMapping {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.target_plum_head_seal.update_hasher(hasher);
         self.plum_relation_flags_raw.update_hasher(hasher);
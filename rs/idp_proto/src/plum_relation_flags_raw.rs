@@ -1,8 +1,8 @@
-use crate::{Hashable, PlumRelationFlagsRaw};
+use crate::{Hashable, PlumRelationFlagsRaw, SealHasher};
 
 impl Hashable for PlumRelationFlagsRaw {
     /// PlumRelationFlagsRaw is a u32, which is hashed in little-endian byte order.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.to_le_bytes().as_slice().update_hasher(hasher);
     }
 }
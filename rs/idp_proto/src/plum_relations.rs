@@ -1,7 +1,7 @@
-use crate::{Hashable, PlumRelations};
+use crate::{Hashable, PlumRelations, SealHasher};
 
 impl Hashable for PlumRelations {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.plum_relations_nonce_o.update_hasher(hasher);
         self.source_plum_body_seal.update_hasher(hasher);
@@ -1,4 +1,47 @@
-use crate::{Hashable, PlumRelations, PlumRelationsSeal, Seal, Sha256Sum};
+use crate::{
+    CanonicalStr, Hashable, PlumRelations, PlumRelationsSeal, PlumVerifyError, Seal, SealAlgorithm,
+    SealError, SealHasher, SealKey, SealKeyProvider, TextDecodeError, TextEncoding,
+};
+
+impl PlumRelationsSeal {
+    /// Computes the PlumRelationsSeal of the given PlumRelations using the given SealAlgorithm.
+    pub fn compute(plum_relations: &PlumRelations, seal_algorithm: SealAlgorithm) -> Self {
+        PlumRelationsSeal::from(seal_algorithm.compute_seal(plum_relations))
+    }
+    /// Computes the keyed PlumRelationsSeal of the given PlumRelations under the given SealKey using the given
+    /// SealAlgorithm.
+    pub fn compute_keyed(
+        plum_relations: &PlumRelations,
+        seal_algorithm: SealAlgorithm,
+        seal_key: &SealKey,
+    ) -> Self {
+        PlumRelationsSeal::from(seal_algorithm.compute_keyed_seal(seal_key, plum_relations))
+    }
+    /// Verifies that this is the PlumRelationsSeal of the given PlumRelations.  A keyed seal can only be verified using its
+    /// SealKey, so if seal_key_provider_o is None, then a keyed seal isn't checked.
+    pub fn verify(
+        &self,
+        plum_relations: &PlumRelations,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<(), PlumVerifyError> {
+        if let Some(computed_seal) = self
+            .value
+            .compute_matching(plum_relations, seal_key_provider_o)?
+        {
+            if !computed_seal.constant_time_eq(&self.value) {
+                return Err(PlumVerifyError::ComputedPlumRelationsSealMismatch {
+                    computed_plum_relations_seal: Box::new(PlumRelationsSeal::from(computed_seal)),
+                    expected_plum_relations_seal: Box::new(self.clone()),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Returns the canonical string form of this PlumRelationsSeal (see Seal::to_string_using).
+    pub fn to_string_using(&self, text_encoding: TextEncoding) -> String {
+        self.value.to_string_using(text_encoding)
+    }
+}
 
 impl std::fmt::Display for PlumRelationsSeal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -6,30 +49,38 @@ impl std::fmt::Display for PlumRelationsSeal {
     }
 }
 
+impl std::str::FromStr for PlumRelationsSeal {
+    type Err = TextDecodeError;
+    /// Parses the canonical string form of a Seal (see Seal's FromStr).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(PlumRelationsSeal::from(s.parse::<Seal>()?))
+    }
+}
+
 impl From<&PlumRelations> for PlumRelationsSeal {
+    /// This uses the default SealAlgorithm; use PlumRelationsSeal::compute to specify it.
     fn from(plum_relations: &PlumRelations) -> PlumRelationsSeal {
-        use sha2::Digest;
-
-        // For now, a seal is only the Sha256Sum, but it could be other stuff later.
-        let mut hasher = sha2::Sha256::new();
-
-        plum_relations.update_hasher(&mut hasher);
+        PlumRelationsSeal::compute(plum_relations, SealAlgorithm::default())
+    }
+}
 
-        PlumRelationsSeal::from(Seal::from(Sha256Sum::from(hasher.finalize().to_vec())))
+impl TryFrom<Vec<u8>> for PlumRelationsSeal {
+    type Error = SealError;
+    /// Inverse of Seal::as_bytes (i.e. this is how a PlumRelationsSeal is loaded from storage).
+    fn try_from(byte_v: Vec<u8>) -> Result<Self, Self::Error> {
+        Ok(PlumRelationsSeal::from(Seal::try_from_bytes(&byte_v)?))
     }
 }
 
-impl From<Vec<u8>> for PlumRelationsSeal {
-    fn from(byte_v: Vec<u8>) -> PlumRelationsSeal {
-        if byte_v.len() != 32 {
-            panic!("programmer error: PlumRelationsSeal must be 32 bytes long");
-        }
-        PlumRelationsSeal::from(Seal::from(Sha256Sum::from(byte_v)))
+impl CanonicalStr for PlumRelationsSeal {
+    /// A PlumRelationsSeal that isn't canonical (see Seal::is_canonical) has no canonical string form.
+    fn has_canonical_str(&self) -> bool {
+        self.value.is_canonical()
     }
 }
 
 impl Hashable for PlumRelationsSeal {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -1,31 +1,168 @@
-use crate::{ContentMetadata, PlumBodySeal, PlumHeadSeal, PlumMetadataSeal, PlumRelationsSeal};
+use crate::{
+    plum_verify_error_detail, ContentMetadata, MalformedErrorDetail, PlumBodySeal, PlumHeadSeal,
+    PlumMetadataSeal, PlumRelationsSeal, PlumVerifyErrorDetail, SealError,
+};
 
 #[derive(Clone, Debug, thiserror::Error)]
 pub enum PlumVerifyError {
     #[error("computed PlumMetadataSeal was {computed_plum_metadata_seal} but value represented in PlumHead was {expected_plum_metadata_seal}")]
     ComputedPlumMetadataSealMismatch {
-        computed_plum_metadata_seal: PlumMetadataSeal,
-        expected_plum_metadata_seal: PlumMetadataSeal,
+        // These are boxed so that PlumVerifyError stays small.
+        computed_plum_metadata_seal: Box<PlumMetadataSeal>,
+        expected_plum_metadata_seal: Box<PlumMetadataSeal>,
     },
     #[error("computed PlumRelationsSeal was {computed_plum_relations_seal} but value represented in PlumHead was {expected_plum_relations_seal}")]
     ComputedPlumRelationsSealMismatch {
-        computed_plum_relations_seal: PlumRelationsSeal,
-        expected_plum_relations_seal: PlumRelationsSeal,
+        // These are boxed so that PlumVerifyError stays small.
+        computed_plum_relations_seal: Box<PlumRelationsSeal>,
+        expected_plum_relations_seal: Box<PlumRelationsSeal>,
     },
     #[error("computed PlumBodySeal was {computed_plum_body_seal} but value represented in PlumHead was {expected_plum_body_seal}")]
     ComputedPlumBodySealMismatch {
-        computed_plum_body_seal: PlumBodySeal,
-        expected_plum_body_seal: PlumBodySeal,
+        // These are boxed so that PlumVerifyError stays small.
+        computed_plum_body_seal: Box<PlumBodySeal>,
+        expected_plum_body_seal: Box<PlumBodySeal>,
     },
     #[error("plum_body_seal in PlumHead was {plum_head_plum_body_seal} but source_plum_body_seal in PlumRelations was {plum_relations_source_plum_body_seal}")]
     PlumBodySealRedundancyMismatch {
-        plum_head_plum_body_seal: PlumBodySeal,
-        plum_relations_source_plum_body_seal: PlumBodySeal,
+        // These are boxed so that PlumVerifyError stays small.
+        plum_head_plum_body_seal: Box<PlumBodySeal>,
+        plum_relations_source_plum_body_seal: Box<PlumBodySeal>,
     },
     #[error("Plum {plum_head_seal} had a mismatch between plum_body_content_metadata in PlumMetadata which was {plum_metadata_plum_body_content_metadata:?} while plum_body_content.metadata in PlumBody was {plum_body_plum_body_content_metadata:?}")]
     PlumBodyContentMetadataRedundancyMismatch {
         plum_head_seal: PlumHeadSeal,
-        plum_metadata_plum_body_content_metadata: ContentMetadata,
-        plum_body_plum_body_content_metadata: ContentMetadata,
+        // These are boxed so that PlumVerifyError stays small.
+        plum_metadata_plum_body_content_metadata: Box<ContentMetadata>,
+        plum_body_plum_body_content_metadata: Box<ContentMetadata>,
     },
+    #[error("SealKey {seal_key_id:?} is needed to verify keyed seal, but was not provided")]
+    MissingSealKey { seal_key_id: String },
+}
+
+impl From<SealError> for PlumVerifyError {
+    /// Seal::compute_matching can only fail because of a missing SealKey.
+    fn from(seal_error: SealError) -> Self {
+        match seal_error {
+            SealError::MissingSealKey { seal_key_id } => {
+                PlumVerifyError::MissingSealKey { seal_key_id }
+            }
+            _ => panic!(
+                "programmer error: unexpected SealError while verifying; {}",
+                seal_error
+            ),
+        }
+    }
+}
+
+impl From<&PlumVerifyError> for PlumVerifyErrorDetail {
+    fn from(plum_verify_error: &PlumVerifyError) -> Self {
+        use plum_verify_error_detail::Value;
+        let value = match plum_verify_error.clone() {
+            PlumVerifyError::ComputedPlumMetadataSealMismatch {
+                computed_plum_metadata_seal,
+                expected_plum_metadata_seal,
+            } => Value::ComputedPlumMetadataSealMismatch(
+                plum_verify_error_detail::ComputedPlumMetadataSealMismatch {
+                    computed_plum_metadata_seal: *computed_plum_metadata_seal,
+                    expected_plum_metadata_seal: *expected_plum_metadata_seal,
+                },
+            ),
+            PlumVerifyError::ComputedPlumRelationsSealMismatch {
+                computed_plum_relations_seal,
+                expected_plum_relations_seal,
+            } => Value::ComputedPlumRelationsSealMismatch(
+                plum_verify_error_detail::ComputedPlumRelationsSealMismatch {
+                    computed_plum_relations_seal: *computed_plum_relations_seal,
+                    expected_plum_relations_seal: *expected_plum_relations_seal,
+                },
+            ),
+            PlumVerifyError::ComputedPlumBodySealMismatch {
+                computed_plum_body_seal,
+                expected_plum_body_seal,
+            } => Value::ComputedPlumBodySealMismatch(
+                plum_verify_error_detail::ComputedPlumBodySealMismatch {
+                    computed_plum_body_seal: *computed_plum_body_seal,
+                    expected_plum_body_seal: *expected_plum_body_seal,
+                },
+            ),
+            PlumVerifyError::PlumBodySealRedundancyMismatch {
+                plum_head_plum_body_seal,
+                plum_relations_source_plum_body_seal,
+            } => Value::PlumBodySealRedundancyMismatch(
+                plum_verify_error_detail::PlumBodySealRedundancyMismatch {
+                    plum_head_plum_body_seal: *plum_head_plum_body_seal,
+                    plum_relations_source_plum_body_seal: *plum_relations_source_plum_body_seal,
+                },
+            ),
+            PlumVerifyError::PlumBodyContentMetadataRedundancyMismatch {
+                plum_head_seal,
+                plum_metadata_plum_body_content_metadata,
+                plum_body_plum_body_content_metadata,
+            } => Value::PlumBodyContentMetadataRedundancyMismatch(
+                plum_verify_error_detail::PlumBodyContentMetadataRedundancyMismatch {
+                    plum_head_seal,
+                    plum_metadata_plum_body_content_metadata:
+                        *plum_metadata_plum_body_content_metadata,
+                    plum_body_plum_body_content_metadata: *plum_body_plum_body_content_metadata,
+                },
+            ),
+            PlumVerifyError::MissingSealKey { seal_key_id } => {
+                Value::MissingSealKey(plum_verify_error_detail::MissingSealKey { seal_key_id })
+            }
+        };
+        Self { value: Some(value) }
+    }
+}
+
+impl TryFrom<PlumVerifyErrorDetail> for PlumVerifyError {
+    type Error = MalformedErrorDetail;
+    fn try_from(plum_verify_error_detail: PlumVerifyErrorDetail) -> Result<Self, Self::Error> {
+        use plum_verify_error_detail::Value;
+        let value = plum_verify_error_detail.value.ok_or(MalformedErrorDetail {
+            message_name: "PlumVerifyErrorDetail",
+        })?;
+        Ok(match value {
+            Value::ComputedPlumMetadataSealMismatch(x) => {
+                PlumVerifyError::ComputedPlumMetadataSealMismatch {
+                    computed_plum_metadata_seal: Box::new(x.computed_plum_metadata_seal),
+                    expected_plum_metadata_seal: Box::new(x.expected_plum_metadata_seal),
+                }
+            }
+            Value::ComputedPlumRelationsSealMismatch(x) => {
+                PlumVerifyError::ComputedPlumRelationsSealMismatch {
+                    computed_plum_relations_seal: Box::new(x.computed_plum_relations_seal),
+                    expected_plum_relations_seal: Box::new(x.expected_plum_relations_seal),
+                }
+            }
+            Value::ComputedPlumBodySealMismatch(x) => {
+                PlumVerifyError::ComputedPlumBodySealMismatch {
+                    computed_plum_body_seal: Box::new(x.computed_plum_body_seal),
+                    expected_plum_body_seal: Box::new(x.expected_plum_body_seal),
+                }
+            }
+            Value::PlumBodySealRedundancyMismatch(x) => {
+                PlumVerifyError::PlumBodySealRedundancyMismatch {
+                    plum_head_plum_body_seal: Box::new(x.plum_head_plum_body_seal),
+                    plum_relations_source_plum_body_seal: Box::new(
+                        x.plum_relations_source_plum_body_seal,
+                    ),
+                }
+            }
+            Value::PlumBodyContentMetadataRedundancyMismatch(x) => {
+                PlumVerifyError::PlumBodyContentMetadataRedundancyMismatch {
+                    plum_head_seal: x.plum_head_seal,
+                    plum_metadata_plum_body_content_metadata: Box::new(
+                        x.plum_metadata_plum_body_content_metadata,
+                    ),
+                    plum_body_plum_body_content_metadata: Box::new(
+                        x.plum_body_plum_body_content_metadata,
+                    ),
+                }
+            }
+            Value::MissingSealKey(x) => PlumVerifyError::MissingSealKey {
+                seal_key_id: x.seal_key_id,
+            },
+        })
+    }
 }
@@ -1,13 +1,325 @@
-use crate::{Hashable, Seal};
+use crate::{
+    CanonicalStr, Hashable, Seal, SealAlgorithm, SealError, SealHasher, SealKeyProvider, Sha256Sum,
+    TextDecodeError, TextEncoding,
+};
+
+/// The varint-encoded code that begins the keyed_seal field of a Seal.  This is the start of the
+/// multicodec private use range, so it can't collide with the multihash code of any SealAlgorithm,
+/// and therefore Seal::as_bytes is unambiguous.
+const KEYED_SEAL_CODE: u64 = 0x300000;
+
+impl Seal {
+    /// Creates a Seal from the digest computed by the given SealAlgorithm.  The digest must have the
+    /// correct length.
+    pub(crate) fn from_digest(seal_algorithm: SealAlgorithm, digest: &[u8]) -> Self {
+        debug_assert_eq!(digest.len(), seal_algorithm.digest_len());
+        match seal_algorithm {
+            SealAlgorithm::Sha256 => Seal::from(Sha256Sum::from(digest.to_vec())),
+            _ => Seal {
+                sha256sum: None,
+                multihash: Some(multihash_from_digest(seal_algorithm, digest)),
+                keyed_seal: None,
+            },
+        }
+    }
+    /// Creates a keyed Seal from the MAC computed by the given SealAlgorithm under the SealKey having
+    /// the given seal_key_id.  The digest must have the correct length.
+    pub(crate) fn from_keyed_digest(
+        seal_key_id: &str,
+        seal_algorithm: SealAlgorithm,
+        digest: &[u8],
+    ) -> Self {
+        debug_assert_eq!(digest.len(), seal_algorithm.digest_len());
+        let mut keyed_seal = Vec::with_capacity(seal_key_id.len() + digest.len() + 8);
+        write_varint(&mut keyed_seal, KEYED_SEAL_CODE);
+        write_varint(&mut keyed_seal, seal_key_id.len() as u64);
+        keyed_seal.extend_from_slice(seal_key_id.as_bytes());
+        keyed_seal.extend_from_slice(&multihash_from_digest(seal_algorithm, digest));
+        Seal {
+            sha256sum: None,
+            multihash: None,
+            keyed_seal: Some(keyed_seal),
+        }
+    }
+    /// Returns the SealAlgorithm that was used to compute this Seal.  This is an error only if the
+    /// Seal is malformed or uses an unsupported hash function.
+    pub fn seal_algorithm(&self) -> Result<SealAlgorithm, SealError> {
+        if self.sha256sum.is_some() {
+            Ok(SealAlgorithm::Sha256)
+        } else if let Some(keyed_seal) = self.keyed_seal.as_ref() {
+            let (_, seal_algorithm, _) = parse_keyed_seal(keyed_seal)?;
+            Ok(seal_algorithm)
+        } else {
+            let (seal_algorithm, _) = parse_multihash(self.multihash_bytes())?;
+            Ok(seal_algorithm)
+        }
+    }
+    /// Returns true iff this is a keyed seal (see SealKey).
+    pub fn is_keyed(&self) -> bool {
+        self.keyed_seal.is_some()
+    }
+    /// Returns the seal_key_id of the SealKey that this keyed seal was computed under.  This is None
+    /// if this isn't a keyed seal, or if it's malformed.
+    pub fn seal_key_id_o(&self) -> Option<&str> {
+        let keyed_seal = self.keyed_seal.as_ref()?;
+        parse_keyed_seal(keyed_seal)
+            .ok()
+            .map(|(seal_key_id, _, _)| seal_key_id)
+    }
+    /// Returns the digest computed by this Seal's SealAlgorithm.  This is empty only if the Seal is
+    /// malformed.
+    pub fn digest(&self) -> &[u8] {
+        if let Some(sha256sum) = self.sha256sum.as_ref() {
+            sha256sum.value.as_slice()
+        } else if let Some(keyed_seal) = self.keyed_seal.as_ref() {
+            parse_keyed_seal(keyed_seal)
+                .map(|(_, _, digest)| digest)
+                .unwrap_or_default()
+        } else {
+            parse_multihash(self.multihash_bytes())
+                .map(|(_, digest)| digest)
+                .unwrap_or_default()
+        }
+    }
+    /// Returns the bytes that identify this Seal, which is the form that seals are stored in by
+    /// DatahostStorage implementations.  A SHA-256 seal is its 32-byte digest (which is how seals were
+    /// always stored), a keyed seal is its keyed_seal field, and any other seal is its multihash.
+    /// Neither of the latter two is ever 32 bytes long, and they begin with different codes, so
+    /// there is no ambiguity between them.  See try_from_bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        if let Some(sha256sum) = self.sha256sum.as_ref() {
+            sha256sum.value.as_slice()
+        } else if let Some(keyed_seal) = self.keyed_seal.as_ref() {
+            keyed_seal.as_slice()
+        } else {
+            self.multihash_bytes()
+        }
+    }
+    /// Inverse of as_bytes.
+    pub fn try_from_bytes(byte_v: &[u8]) -> Result<Self, SealError> {
+        if byte_v.len() == SealAlgorithm::Sha256.digest_len() {
+            Ok(Seal::from(Sha256Sum::from(byte_v.to_vec())))
+        } else if matches!(read_varint(byte_v), Ok((KEYED_SEAL_CODE, _))) {
+            parse_keyed_seal(byte_v)?;
+            Ok(Seal {
+                sha256sum: None,
+                multihash: None,
+                keyed_seal: Some(byte_v.to_vec()),
+            })
+        } else {
+            Self::try_from_multihash(byte_v)
+        }
+    }
+    /// Returns true iff this Seal is in the form that try_from_bytes produces, which is the case for
+    /// any well-formed Seal.  Only then can its string form (see to_string_using) be parsed.
+    pub fn is_canonical(&self) -> bool {
+        Seal::try_from_bytes(self.as_bytes()).as_ref() == Ok(self)
+    }
+    /// Returns the canonical string form of this Seal, which is the given TextEncoding of as_bytes.
+    /// This is self-describing, i.e. the SealAlgorithm (and for a keyed seal, the seal_key_id) can
+    /// be recovered from it, and FromStr parses it regardless of which TextEncoding was used.
+    pub fn to_string_using(&self, text_encoding: TextEncoding) -> String {
+        text_encoding.encode(self.as_bytes())
+    }
+    /// Returns the multihash of this Seal (including if it's a SHA-256 seal).  For a keyed seal, this
+    /// is the multihash of its MAC, which doesn't include the seal_key_id, so try_from_multihash won't
+    /// reproduce the keyed seal.
+    pub fn to_multihash(&self) -> Vec<u8> {
+        if let Some(sha256sum) = self.sha256sum.as_ref() {
+            multihash_from_digest(SealAlgorithm::Sha256, sha256sum.value.as_slice())
+        } else if let Some(keyed_seal) = self.keyed_seal.as_ref() {
+            // A malformed keyed seal produces an empty (and therefore malformed) multihash.
+            parse_keyed_seal(keyed_seal)
+                .map(|(_, seal_algorithm, digest)| multihash_from_digest(seal_algorithm, digest))
+                .unwrap_or_default()
+        } else {
+            self.multihash_bytes().to_vec()
+        }
+    }
+    /// Inverse of to_multihash.  Note that a SHA-256 multihash produces a Seal using the sha256sum
+    /// field, so that there is only one representation for each Seal.
+    pub fn try_from_multihash(multihash: &[u8]) -> Result<Self, SealError> {
+        let (seal_algorithm, digest) = parse_multihash(multihash)?;
+        Ok(Self::from_digest(seal_algorithm, digest))
+    }
+    /// Computes the Seal of the given Hashable in the same way that this Seal was computed, i.e. using
+    /// the same SealAlgorithm, and for a keyed seal, the same SealKey, so that the result can be
+    /// compared against this Seal.  If this Seal is malformed, then the default SealAlgorithm is used
+    /// (and so the computed Seal simply won't match).
+    ///
+    /// If this is a keyed seal and seal_key_provider_o is None, then the Seal can't be computed, and
+    /// None is returned.  If the SealKeyProvider doesn't have the SealKey, then that's an error.
+    pub fn compute_matching<H: Hashable + ?Sized>(
+        &self,
+        hashable: &H,
+        seal_key_provider_o: Option<&dyn SealKeyProvider>,
+    ) -> Result<Option<Seal>, SealError> {
+        let keyed_seal = match self.keyed_seal.as_ref() {
+            Some(keyed_seal) => keyed_seal,
+            None => {
+                let seal_algorithm = self.seal_algorithm().unwrap_or_default();
+                return Ok(Some(seal_algorithm.compute_seal(hashable)));
+            }
+        };
+        let (seal_key_id, seal_algorithm, _) = match parse_keyed_seal(keyed_seal) {
+            Ok(parsed) => parsed,
+            Err(_) => return Ok(Some(SealAlgorithm::default().compute_seal(hashable))),
+        };
+        let seal_key_provider = match seal_key_provider_o {
+            Some(seal_key_provider) => seal_key_provider,
+            None => return Ok(None),
+        };
+        let seal_key =
+            seal_key_provider
+                .seal_key(seal_key_id)
+                .ok_or_else(|| SealError::MissingSealKey {
+                    seal_key_id: seal_key_id.to_string(),
+                })?;
+        Ok(Some(seal_algorithm.compute_keyed_seal(seal_key, hashable)))
+    }
+    /// Returns true iff this Seal has the same bytes (see as_bytes) as the given Seal, comparing them
+    /// in constant time.  This is how a computed Seal is checked against an expected one, since for a
+    /// keyed seal, a comparison that returns at the first differing byte would leak how much of a
+    /// forged MAC is correct.
+    pub fn constant_time_eq(&self, other: &Seal) -> bool {
+        use subtle::ConstantTimeEq;
+        self.as_bytes().ct_eq(other.as_bytes()).into()
+    }
+    fn multihash_bytes(&self) -> &[u8] {
+        self.multihash.as_deref().unwrap_or_default()
+    }
+}
 
 impl std::fmt::Display for Seal {
+    /// Displays the canonical string form of this Seal, using the default TextEncoding (see
+    /// Seal::to_string_using).
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        write!(f, "{}", self.sha256sum)
+        f.write_str(&self.to_string_using(TextEncoding::default()))
+    }
+}
+
+impl std::str::FromStr for Seal {
+    type Err = TextDecodeError;
+    /// Parses the canonical string form of a Seal (in any TextEncoding; see Seal::to_string_using).
+    /// For compatibility with the way that seals were displayed before there were other
+    /// SealAlgorithm-s, exactly 64 hex digits (in either case) is also accepted as a SHA-256 seal.
+    /// That can't be confused with a multibase string, since no well-formed Seal has a multibase
+    /// encoding of that length which is entirely hex digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 2 * SealAlgorithm::Sha256.digest_len()
+            && s.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            let byte_v = TextEncoding::decode(&format!("f{}", s.to_ascii_lowercase()))?;
+            return Ok(Seal::from(Sha256Sum::from(byte_v)));
+        }
+        Ok(Seal::try_from_bytes(&TextEncoding::decode(s)?)?)
+    }
+}
+
+impl From<Sha256Sum> for Seal {
+    fn from(sha256sum: Sha256Sum) -> Self {
+        Seal {
+            sha256sum: Some(sha256sum),
+            multihash: None,
+            keyed_seal: None,
+        }
+    }
+}
+
+impl CanonicalStr for Seal {
+    /// A Seal that isn't canonical (see Seal::is_canonical) has no canonical string form.
+    fn has_canonical_str(&self) -> bool {
+        self.is_canonical()
     }
 }
 
 impl Hashable for Seal {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
-        self.value.update_hasher(hasher);
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
+        // NOTE: A SHA-256 seal must be hashed as just its digest, as it was before there were other
+        // SealAlgorithm-s, so that the PlumHeadSeal-s of existing Plums don't change.
+        if let Some(sha256sum) = self.sha256sum.as_ref() {
+            sha256sum.update_hasher(hasher);
+        } else if let Some(keyed_seal) = self.keyed_seal.as_ref() {
+            hasher.update(b"KeyedSeal");
+            keyed_seal.update_hasher(hasher);
+        } else {
+            hasher.update(b"Multihash");
+            self.multihash_bytes().update_hasher(hasher);
+        }
+    }
+}
+
+fn multihash_from_digest(seal_algorithm: SealAlgorithm, digest: &[u8]) -> Vec<u8> {
+    let mut multihash = Vec::with_capacity(digest.len() + 4);
+    write_varint(&mut multihash, seal_algorithm.multihash_code());
+    write_varint(&mut multihash, digest.len() as u64);
+    multihash.extend_from_slice(digest);
+    multihash
+}
+
+/// Returns the SealAlgorithm and digest of the given multihash.
+fn parse_multihash(multihash: &[u8]) -> Result<(SealAlgorithm, &[u8]), SealError> {
+    let (multihash_code, remaining) = read_varint(multihash)?;
+    let (digest_len, digest) = read_varint(remaining)?;
+    if digest_len != digest.len() as u64 {
+        return Err(SealError::MalformedMultihash {
+            reason: "digest length doesn't match the length of the digest",
+        });
+    }
+    let seal_algorithm = SealAlgorithm::from_multihash_code(multihash_code)?;
+    if digest.len() != seal_algorithm.digest_len() {
+        return Err(SealError::DigestLengthMismatch {
+            seal_algorithm,
+            expected_digest_len: seal_algorithm.digest_len(),
+            digest_len: digest.len(),
+        });
+    }
+    Ok((seal_algorithm, digest))
+}
+
+/// Returns the seal_key_id, SealAlgorithm, and digest of the given keyed_seal (see Seal::as_bytes).
+fn parse_keyed_seal(keyed_seal: &[u8]) -> Result<(&str, SealAlgorithm, &[u8]), SealError> {
+    let (keyed_seal_code, remaining) = read_varint(keyed_seal)?;
+    if keyed_seal_code != KEYED_SEAL_CODE {
+        return Err(SealError::MalformedKeyedSeal {
+            reason: "it doesn't begin with KEYED_SEAL_CODE",
+        });
+    }
+    let (seal_key_id_len, remaining) = read_varint(remaining)?;
+    if seal_key_id_len > remaining.len() as u64 {
+        return Err(SealError::MalformedKeyedSeal {
+            reason: "seal_key_id length exceeds the remaining length",
+        });
+    }
+    let (seal_key_id_bytes, multihash) = remaining.split_at(seal_key_id_len as usize);
+    let seal_key_id =
+        std::str::from_utf8(seal_key_id_bytes).map_err(|_| SealError::MalformedKeyedSeal {
+            reason: "seal_key_id is not valid UTF-8",
+        })?;
+    let (seal_algorithm, digest) = parse_multihash(multihash)?;
+    Ok((seal_key_id, seal_algorithm, digest))
+}
+
+/// Writes the unsigned varint (as used by multihash) encoding of the given value.
+fn write_varint(byte_v: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        byte_v.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    byte_v.push(value as u8);
+}
+
+/// Reads an unsigned varint (as used by multihash), returning its value and the remaining bytes.
+fn read_varint(bytes: &[u8]) -> Result<(u64, &[u8]), SealError> {
+    let mut value = 0u64;
+    // multihash limits varints to 9 bytes.
+    for (i, byte) in bytes.iter().take(9).enumerate() {
+        value |= ((byte & 0x7F) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, &bytes[i + 1..]));
+        }
     }
+    Err(SealError::MalformedMultihash {
+        reason: "truncated or overlong varint",
+    })
 }
@@ -0,0 +1,119 @@
+use crate::{Hashable, Seal, SealError, SealKey, Sha256Sum};
+
+/// Specifies the hash function used to compute a Seal.  Each one corresponds to a multihash code
+/// (see https://github.com/multiformats/multicodec/blob/master/table.csv), and the Seal of any
+/// SealAlgorithm other than Sha256 is represented as a multihash.  The default is Sha256, which
+/// was the original (and only) SealAlgorithm.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SealAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl SealAlgorithm {
+    /// Returns the multihash code of this SealAlgorithm's hash function.
+    pub fn multihash_code(self) -> u64 {
+        match self {
+            SealAlgorithm::Sha256 => 0x12,
+            SealAlgorithm::Sha512 => 0x13,
+            SealAlgorithm::Blake3 => 0x1e,
+        }
+    }
+    /// Returns the SealAlgorithm having the given multihash code, if it's supported.
+    pub fn from_multihash_code(multihash_code: u64) -> Result<Self, SealError> {
+        match multihash_code {
+            0x12 => Ok(SealAlgorithm::Sha256),
+            0x13 => Ok(SealAlgorithm::Sha512),
+            0x1e => Ok(SealAlgorithm::Blake3),
+            _ => Err(SealError::UnsupportedMultihashCode(multihash_code)),
+        }
+    }
+    /// Returns the length of this SealAlgorithm's digest, in bytes.
+    pub fn digest_len(self) -> usize {
+        match self {
+            SealAlgorithm::Sha256 => 32,
+            SealAlgorithm::Sha512 => 64,
+            SealAlgorithm::Blake3 => 32,
+        }
+    }
+    /// Returns the name of this SealAlgorithm's hash function, as it appears in the multihash table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SealAlgorithm::Sha256 => "sha2-256",
+            SealAlgorithm::Sha512 => "sha2-512",
+            SealAlgorithm::Blake3 => "blake3",
+        }
+    }
+    /// Computes the Seal of the given Hashable using this SealAlgorithm.
+    pub fn compute_seal<H: Hashable + ?Sized>(self, hashable: &H) -> Seal {
+        match self {
+            SealAlgorithm::Sha256 => {
+                use sha2::Digest;
+                let mut hasher = sha2::Sha256::new();
+                hashable.update_hasher(&mut hasher);
+                Seal::from(Sha256Sum::from(hasher.finalize().to_vec()))
+            }
+            SealAlgorithm::Sha512 => {
+                use sha2::Digest;
+                let mut hasher = sha2::Sha512::new();
+                hashable.update_hasher(&mut hasher);
+                Seal::from_digest(self, &hasher.finalize())
+            }
+            SealAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                hashable.update_hasher(&mut hasher);
+                Seal::from_digest(self, hasher.finalize().as_bytes())
+            }
+        }
+    }
+    /// Computes the keyed Seal of the given Hashable under the given SealKey using this SealAlgorithm.
+    /// For SHA-2, this is HMAC.  For BLAKE3, this is its keyed mode, using a key derived from the
+    /// SealKey's secret (since BLAKE3 keys must be exactly 32 bytes).
+    pub fn compute_keyed_seal<H: Hashable + ?Sized>(
+        self,
+        seal_key: &SealKey,
+        hashable: &H,
+    ) -> Seal {
+        use hmac::{Mac, NewMac};
+        let digest = match self {
+            SealAlgorithm::Sha256 => {
+                let mut hasher = hmac::Hmac::<sha2::Sha256>::new_from_slice(seal_key.secret())
+                    .expect("HMAC accepts keys of any length");
+                hashable.update_hasher(&mut hasher);
+                hasher.finalize().into_bytes().to_vec()
+            }
+            SealAlgorithm::Sha512 => {
+                let mut hasher = hmac::Hmac::<sha2::Sha512>::new_from_slice(seal_key.secret())
+                    .expect("HMAC accepts keys of any length");
+                hashable.update_hasher(&mut hasher);
+                hasher.finalize().into_bytes().to_vec()
+            }
+            SealAlgorithm::Blake3 => {
+                let key = blake3::derive_key(BLAKE3_SEAL_KEY_CONTEXT, seal_key.secret());
+                let mut hasher = blake3::Hasher::new_keyed(&key);
+                hashable.update_hasher(&mut hasher);
+                hasher.finalize().as_bytes().to_vec()
+            }
+        };
+        Seal::from_keyed_digest(seal_key.seal_key_id(), self, &digest)
+    }
+}
+
+/// The BLAKE3 key derivation context used to derive a BLAKE3 key from a SealKey's secret.
+/// NOTE: This must NOT be changed, or existing keyed BLAKE3 seals will no longer verify!
+const BLAKE3_SEAL_KEY_CONTEXT: &str = "idp 2023 SealKey BLAKE3 keyed seal";
+
+// Deriving Default would require #[default], which needs Rust 1.62, and idp_core supports 1.61.
+#[allow(clippy::derivable_impls)]
+impl Default for SealAlgorithm {
+    fn default() -> Self {
+        SealAlgorithm::Sha256
+    }
+}
+
+impl std::fmt::Display for SealAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,19 @@
+use crate::SealAlgorithm;
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum SealError {
+    #[error("malformed multihash; {reason}")]
+    MalformedMultihash { reason: &'static str },
+    #[error("unsupported multihash code 0x{0:x}")]
+    UnsupportedMultihashCode(u64),
+    #[error("{seal_algorithm} digest must be {expected_digest_len} bytes long, but was {digest_len} bytes long")]
+    DigestLengthMismatch {
+        seal_algorithm: SealAlgorithm,
+        expected_digest_len: usize,
+        digest_len: usize,
+    },
+    #[error("malformed keyed seal; {reason}")]
+    MalformedKeyedSeal { reason: &'static str },
+    #[error("SealKey {seal_key_id:?} is needed to verify keyed seal, but was not provided")]
+    MissingSealKey { seal_key_id: String },
+}
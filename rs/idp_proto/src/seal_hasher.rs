@@ -0,0 +1,36 @@
+/// The hash function state that Hashable::update_hasher feeds data into.  There is one for each
+/// SealAlgorithm (see SealAlgorithm::compute_seal), and one for each keyed SealAlgorithm (see
+/// SealAlgorithm::compute_keyed_seal).
+pub trait SealHasher {
+    fn update(&mut self, data: &[u8]);
+}
+
+impl SealHasher for sha2::Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        sha2::Digest::update(self, data);
+    }
+}
+
+impl SealHasher for sha2::Sha512 {
+    fn update(&mut self, data: &[u8]) {
+        sha2::Digest::update(self, data);
+    }
+}
+
+impl SealHasher for hmac::Hmac<sha2::Sha256> {
+    fn update(&mut self, data: &[u8]) {
+        hmac::Mac::update(self, data);
+    }
+}
+
+impl SealHasher for hmac::Hmac<sha2::Sha512> {
+    fn update(&mut self, data: &[u8]) {
+        hmac::Mac::update(self, data);
+    }
+}
+
+impl SealHasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+}
@@ -0,0 +1,34 @@
+/// A secret key under which keyed seals are computed (see PlumBuilder::with_seal_key), typically
+/// a per-Datahost or per-group secret.  Only holders of the SealKey can compute or verify a keyed
+/// seal, so unlike an unkeyed seal, a keyed seal can't be used by outsiders to confirm a guess of
+/// the content it seals.  The seal_key_id is recorded in each keyed seal so that the verifier knows
+/// which SealKey to use (see SealKeyProvider), so it must not itself be secret.
+#[derive(Clone)]
+pub struct SealKey {
+    seal_key_id: String,
+    secret: Vec<u8>,
+}
+
+impl SealKey {
+    pub fn new(seal_key_id: String, secret: Vec<u8>) -> Self {
+        Self {
+            seal_key_id,
+            secret,
+        }
+    }
+    pub fn seal_key_id(&self) -> &str {
+        self.seal_key_id.as_str()
+    }
+    pub(crate) fn secret(&self) -> &[u8] {
+        self.secret.as_slice()
+    }
+}
+
+impl std::fmt::Debug for SealKey {
+    /// The secret is deliberately not printed.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("SealKey")
+            .field("seal_key_id", &self.seal_key_id)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,44 @@
+use crate::SealKey;
+
+/// Provides the SealKey-s needed to verify keyed seals (see Plum::verify_with_seal_key_provider).
+pub trait SealKeyProvider {
+    /// Returns the SealKey having the given seal_key_id, if this SealKeyProvider has it.
+    fn seal_key(&self, seal_key_id: &str) -> Option<&SealKey>;
+}
+
+impl SealKeyProvider for SealKey {
+    fn seal_key(&self, seal_key_id: &str) -> Option<&SealKey> {
+        if self.seal_key_id() == seal_key_id {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl SealKeyProvider for [SealKey] {
+    fn seal_key(&self, seal_key_id: &str) -> Option<&SealKey> {
+        self.iter()
+            .find(|seal_key| seal_key.seal_key_id() == seal_key_id)
+    }
+}
+
+impl SealKeyProvider for Vec<SealKey> {
+    fn seal_key(&self, seal_key_id: &str) -> Option<&SealKey> {
+        self.as_slice().seal_key(seal_key_id)
+    }
+}
+
+impl<P: SealKeyProvider + ?Sized> SealKeyProvider for std::sync::Arc<P> {
+    fn seal_key(&self, seal_key_id: &str) -> Option<&SealKey> {
+        self.as_ref().seal_key(seal_key_id)
+    }
+}
+
+/// None provides no SealKey-s, so Plum::verify_with_seal_key_provider rejects any keyed seal.
+impl<P: SealKeyProvider> SealKeyProvider for Option<P> {
+    fn seal_key(&self, seal_key_id: &str) -> Option<&SealKey> {
+        self.as_ref()
+            .and_then(|seal_key_provider| seal_key_provider.seal_key(seal_key_id))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Hashable, Sha256Sum};
+use crate::{Hashable, SealHasher, Sha256Sum};
 
 impl std::fmt::Display for Sha256Sum {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -10,7 +10,7 @@ impl std::fmt::Display for Sha256Sum {
 }
 
 impl Hashable for Sha256Sum {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.value.update_hasher(hasher);
     }
 }
@@ -0,0 +1,9 @@
+use crate::SealError;
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum TextDecodeError {
+    #[error("malformed multibase string; {reason}")]
+    MalformedMultibase { reason: String },
+    #[error(transparent)]
+    MalformedSeal(#[from] SealError),
+}
@@ -0,0 +1,46 @@
+use crate::TextDecodeError;
+
+/// Specifies how the bytes of a seal or Nonce are printed as text (see e.g. Seal::to_string_using).
+/// Each is a multibase encoding (see https://github.com/multiformats/multibase), so the printed form
+/// begins with a character identifying the encoding, and can be parsed without knowing which one was
+/// used.  The default is Hex, which is what Display uses.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TextEncoding {
+    /// Lowercase hex, prefixed with 'f'.
+    Hex,
+    /// Lowercase RFC 4648 base32 without padding, prefixed with 'b'.
+    Base32,
+    /// Bitcoin-alphabet base58, prefixed with 'z'.
+    Base58,
+}
+
+impl TextEncoding {
+    /// Encodes the given bytes using this TextEncoding.
+    pub fn encode(self, bytes: &[u8]) -> String {
+        multibase::encode(self.multibase_base(), bytes)
+    }
+    /// Decodes the given multibase string.  Any multibase encoding is accepted, not just the ones
+    /// that TextEncoding produces.
+    pub fn decode(s: &str) -> Result<Vec<u8>, TextDecodeError> {
+        let (_, byte_v) =
+            multibase::decode(s).map_err(|e| TextDecodeError::MalformedMultibase {
+                reason: e.to_string(),
+            })?;
+        Ok(byte_v)
+    }
+    fn multibase_base(self) -> multibase::Base {
+        match self {
+            TextEncoding::Hex => multibase::Base::Base16Lower,
+            TextEncoding::Base32 => multibase::Base::Base32Lower,
+            TextEncoding::Base58 => multibase::Base::Base58Btc,
+        }
+    }
+}
+
+// Not derived, for the same reason as SealAlgorithm's Default.
+#[allow(clippy::derivable_impls)]
+impl Default for TextEncoding {
+    fn default() -> Self {
+        TextEncoding::Hex
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Hashable, UnixNanoseconds};
+use crate::{Hashable, SealHasher, UnixNanoseconds};
 
 impl UnixNanoseconds {
     pub fn now() -> Self {
@@ -39,7 +39,7 @@ impl From<UnixNanoseconds> for chrono::DateTime<chrono::Local> {
 
 impl Hashable for UnixNanoseconds {
     /// Explicitly use little-endian byte order.
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn SealHasher) {
         self.to_le_bytes().update_hasher(hasher);
     }
 }
@@ -1,7 +1,9 @@
 use idp_proto::{
     Content, ContentClass, ContentClassifiable, ContentEncoding, ContentFormat, ContentMetadata,
-    Nonce, Plum, PlumBodySeal, PlumBuilder, PlumHeadSeal, PlumRelationFlags, PlumRelationsBuilder,
-    UnixNanoseconds,
+    Nonce, PartialPlumBody, Plum, PlumBody, PlumBodyChunk, PlumBodyChunkError, PlumBodySeal,
+    PlumBuilder, PlumHeadSeal, PlumMetadataSeal, PlumRelationFlags, PlumRelationsBuilder,
+    PlumRelationsSeal, PlumVerifyError, Seal, SealAlgorithm, SealError, SealKey, SealKeyProvider,
+    Sha256Sum, TextDecodeError, TextEncoding, UnixNanoseconds,
 };
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
@@ -105,11 +107,9 @@ fn test_contentifiable_1() {
         name: "test_contentifiable_1_name".to_string(),
         // Just a made-up PlumHeadSeal.
         dependency: PlumHeadSeal {
-            value: idp_proto::Seal {
-                sha256sum: idp_proto::Sha256Sum {
-                    value: vec![234, 145, 182, 104],
-                },
-            },
+            value: idp_proto::Seal::from(idp_proto::Sha256Sum {
+                value: vec![234, 145, 182, 104],
+            }),
         },
     };
 
@@ -156,11 +156,9 @@ fn test_contentifiable_2() {
         name: "test_contentifiable_2_name".to_string(),
         // Just a made-up PlumHeadSeal.
         dependency: PlumHeadSeal {
-            value: idp_proto::Seal {
-                sha256sum: idp_proto::Sha256Sum {
-                    value: vec![123, 45, 82, 24],
-                },
-            },
+            value: idp_proto::Seal::from(idp_proto::Sha256Sum {
+                value: vec![123, 45, 82, 24],
+            }),
         },
     };
 
@@ -292,11 +290,9 @@ fn test_contentifiable_3() {
 fn test_plum_relations_builder() {
     // Just a made-up PlumHeadSeal.
     let dependency_plum_head_seal = PlumHeadSeal {
-        value: idp_proto::Seal {
-            sha256sum: idp_proto::Sha256Sum {
-                value: vec![23, 133, 144, 211],
-            },
-        },
+        value: idp_proto::Seal::from(idp_proto::Sha256Sum {
+            value: vec![23, 133, 144, 211],
+        }),
     };
     let dummy_data = DummyData {
         name: "test_plum_relations_builder_name".to_string(),
@@ -305,11 +301,9 @@ fn test_plum_relations_builder() {
 
     // Make up a PlumBodySeal (this would not validate in a real Plum).
     let plum_body_seal = PlumBodySeal {
-        value: idp_proto::Seal {
-            sha256sum: idp_proto::Sha256Sum {
-                value: vec![13, 5, 8, 4],
-            },
-        },
+        value: idp_proto::Seal::from(idp_proto::Sha256Sum {
+            value: vec![13, 5, 8, 4],
+        }),
     };
 
     let plum_relations = PlumRelationsBuilder::new()
@@ -364,11 +358,9 @@ fn create_test_content_and_plum(
 ) -> (DummyData, Plum) {
     // Just a made-up PlumHeadSeal.
     let dependency_plum_head_seal = PlumHeadSeal {
-        value: idp_proto::Seal {
-            sha256sum: idp_proto::Sha256Sum {
-                value: vec![2, 33, 44, 111],
-            },
-        },
+        value: idp_proto::Seal::from(idp_proto::Sha256Sum {
+            value: vec![2, 33, 44, 111],
+        }),
     };
     let dummy_data = DummyData {
         name: "test_plum_builder_name".to_string(),
@@ -417,11 +409,9 @@ fn create_test_content_and_plum(
 fn test_invalid_requested_content_format_0() {
     // Just a made-up PlumHeadSeal.
     let dependency_plum_head_seal = PlumHeadSeal {
-        value: idp_proto::Seal {
-            sha256sum: idp_proto::Sha256Sum {
-                value: vec![0, 2, 5, 9],
-            },
-        },
+        value: idp_proto::Seal::from(idp_proto::Sha256Sum {
+            value: vec![0, 2, 5, 9],
+        }),
     };
     let dummy_data = DummyData {
         name: "test_invalid_requested_content_format_0_name".to_string(),
@@ -530,3 +520,583 @@ fn test_plum_builder() {
     log::debug!("test_case_count: {}", test_case_count);
     assert_eq!(plum_head_seal_s.len(), test_case_count);
 }
+
+#[test]
+fn test_seal_multihash_roundtrip() {
+    for seal_algorithm in [
+        SealAlgorithm::Sha256,
+        SealAlgorithm::Sha512,
+        SealAlgorithm::Blake3,
+    ] {
+        let seal = seal_algorithm.compute_seal("blah blah".as_bytes());
+        log::debug!("seal: {}", seal);
+        assert_eq!(seal.seal_algorithm().expect("pass"), seal_algorithm);
+        assert_eq!(seal.digest().len(), seal_algorithm.digest_len());
+
+        let multihash = seal.to_multihash();
+        assert_eq!(multihash[0] as u64, seal_algorithm.multihash_code());
+        assert_eq!(multihash[1] as usize, seal_algorithm.digest_len());
+        assert_eq!(&multihash[2..], seal.digest());
+        assert_eq!(Seal::try_from_multihash(&multihash).expect("pass"), seal);
+        assert_eq!(Seal::try_from_bytes(seal.as_bytes()).expect("pass"), seal);
+
+        match seal_algorithm {
+            SealAlgorithm::Sha256 => {
+                // SHA-256 seals are represented exactly as they were before there were other
+                // SealAlgorithm-s.
+                assert!(seal.sha256sum.is_some());
+                assert!(seal.multihash.is_none());
+                assert_eq!(seal.as_bytes(), seal.digest());
+                // The way that SHA-256 seals used to be displayed is still accepted.
+                let legacy_string = seal.sha256sum.as_ref().expect("pass").to_string();
+                assert_eq!(legacy_string.parse::<Seal>().expect("pass"), seal);
+            }
+            _ => {
+                assert!(seal.sha256sum.is_none());
+                assert_eq!(seal.as_bytes(), multihash.as_slice());
+                assert!(seal
+                    .to_string()
+                    .starts_with(&format!("f{:02x}", seal_algorithm.multihash_code())));
+            }
+        }
+    }
+
+    // Malformed multihashes.
+    assert_eq!(
+        Seal::try_from_multihash(&[0x99, 0x01, 0x00]).expect_err("fail"),
+        SealError::UnsupportedMultihashCode(0x99)
+    );
+    assert!(matches!(
+        Seal::try_from_multihash(&[0x1e, 0x02, 0x00, 0x00]).expect_err("fail"),
+        SealError::DigestLengthMismatch { .. }
+    ));
+    assert!(matches!(
+        Seal::try_from_multihash(&[0x1e, 0x20, 0x00]).expect_err("fail"),
+        SealError::MalformedMultihash { .. }
+    ));
+    assert!(matches!(
+        Seal::try_from_bytes(&[0x80]).expect_err("fail"),
+        SealError::MalformedMultihash { .. }
+    ));
+}
+
+#[test]
+fn test_plum_builder_seal_algorithm() {
+    let dummy_data = DummyData {
+        name: "test_plum_builder_seal_algorithm".to_string(),
+        dependency: PlumHeadSeal {
+            value: Seal::from(Sha256Sum {
+                value: vec![2, 33, 44, 111],
+            }),
+        },
+    };
+    let build_plum = |seal_algorithm_o: Option<SealAlgorithm>| {
+        let mut plum_builder = PlumBuilder::new().with_plum_created_at(UnixNanoseconds::from(0));
+        if let Some(seal_algorithm) = seal_algorithm_o {
+            plum_builder = plum_builder.with_seal_algorithm(seal_algorithm);
+        }
+        plum_builder
+            .with_plum_relations_and_plum_body_content_from(
+                &dummy_data,
+                Some(&ContentFormat::json()),
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass")
+    };
+
+    // Explicitly using the default SealAlgorithm produces the same Plum as not specifying it.
+    let default_plum = build_plum(None);
+    assert_eq!(build_plum(Some(SealAlgorithm::Sha256)), default_plum);
+
+    let mut plum_head_seal_s = HashSet::new();
+    for seal_algorithm in [
+        SealAlgorithm::Sha256,
+        SealAlgorithm::Sha512,
+        SealAlgorithm::Blake3,
+    ] {
+        let plum = build_plum(Some(seal_algorithm));
+        log::debug!("plum: {:?}", plum);
+        plum.verify().expect("pass");
+
+        let plum_head_seal = PlumHeadSeal::from(&plum.plum_head);
+        assert_eq!(
+            plum_head_seal.value.seal_algorithm().expect("pass"),
+            seal_algorithm
+        );
+        assert_eq!(
+            plum.plum_head
+                .plum_body_seal
+                .value
+                .seal_algorithm()
+                .expect("pass"),
+            seal_algorithm
+        );
+        assert_eq!(
+            plum.plum_head.plum_body_seal,
+            PlumBodySeal::compute(&plum.plum_body, seal_algorithm)
+        );
+        plum_head_seal_s.insert(plum_head_seal);
+
+        // Tampering with the PlumBody must be detected.
+        let mut tampered_plum = plum.clone();
+        tampered_plum
+            .plum_body
+            .plum_body_content
+            .content_byte_v
+            .push(b' ');
+        tampered_plum.verify().expect_err("fail");
+    }
+    // Each SealAlgorithm produces a distinct PlumHeadSeal.
+    assert_eq!(plum_head_seal_s.len(), 3);
+}
+
+#[test]
+fn test_plum_builder_seal_key() {
+    // This is the kind of low-entropy content that an outsider could guess.
+    let content = "salary: 100000".to_string();
+    let build_plum = |seal_algorithm: SealAlgorithm, seal_key_o: Option<&SealKey>| {
+        let mut plum_builder = PlumBuilder::new().with_seal_algorithm(seal_algorithm);
+        if let Some(seal_key) = seal_key_o {
+            plum_builder = plum_builder.with_seal_key(seal_key.clone());
+        }
+        plum_builder
+            .with_plum_relations_and_plum_body_content_from(
+                &content,
+                Some(&ContentFormat::charset_us_ascii()),
+                ContentEncoding::none(),
+            )
+            .expect("pass")
+            .build()
+            .expect("pass")
+    };
+    let seal_key = SealKey::new("group-0".to_string(), b"the group-0 secret".to_vec());
+    // An outsider who guesses the seal_key_id but not the secret.
+    let wrong_seal_key = SealKey::new("group-0".to_string(), b"a guessed secret".to_vec());
+    let other_seal_key = SealKey::new("group-1".to_string(), b"the group-1 secret".to_vec());
+    // The secret must not be printed.
+    assert!(!format!("{:?}", seal_key).contains("secret"));
+
+    for seal_algorithm in [
+        SealAlgorithm::Sha256,
+        SealAlgorithm::Sha512,
+        SealAlgorithm::Blake3,
+    ] {
+        let plum = build_plum(seal_algorithm, Some(&seal_key));
+        log::debug!("plum: {:?}", plum);
+
+        for seal in [
+            &plum.plum_head.plum_metadata_seal.value,
+            &plum.plum_head.plum_relations_seal.value,
+            &plum.plum_head.plum_body_seal.value,
+        ] {
+            assert!(seal.is_keyed());
+            assert_eq!(seal.seal_key_id_o(), Some("group-0"));
+            assert_eq!(seal.seal_algorithm().expect("pass"), seal_algorithm);
+            assert_eq!(seal.digest().len(), seal_algorithm.digest_len());
+            assert_eq!(Seal::try_from_bytes(seal.as_bytes()).expect("pass"), *seal);
+            assert_eq!(seal.to_string().parse::<Seal>().expect("pass"), *seal);
+        }
+        // The PlumHeadSeal is never keyed.
+        let plum_head_seal = PlumHeadSeal::from(&plum.plum_head);
+        assert!(!plum_head_seal.value.is_keyed());
+        assert_eq!(
+            plum_head_seal.value.seal_algorithm().expect("pass"),
+            seal_algorithm
+        );
+
+        // Without a SealKeyProvider, keyed seals aren't checked.
+        plum.verify().expect("pass");
+        plum.verify_with_seal_key_provider(&seal_key).expect("pass");
+        plum.verify_with_seal_key_provider(&vec![other_seal_key.clone(), seal_key.clone()])
+            .expect("pass");
+        match plum
+            .verify_with_seal_key_provider(&other_seal_key)
+            .expect_err("fail")
+        {
+            PlumVerifyError::MissingSealKey { seal_key_id } => assert_eq!(seal_key_id, "group-0"),
+            e => panic!("unexpected error: {}", e),
+        }
+        // A SealKeyProvider that's None provides no SealKey-s, so keyed seals are rejected.
+        assert!(matches!(
+            plum.verify_with_seal_key_provider(&None::<SealKey>)
+                .expect_err("fail"),
+            PlumVerifyError::MissingSealKey { .. }
+        ));
+        assert!(matches!(
+            plum.verify_with_seal_key_provider(&wrong_seal_key)
+                .expect_err("fail"),
+            PlumVerifyError::ComputedPlumMetadataSealMismatch { .. }
+        ));
+
+        // An outsider who guesses the content can't confirm it, whether or not they use a SealKey.
+        let guessed_plum = build_plum(seal_algorithm, None);
+        assert_ne!(
+            guessed_plum.plum_head.plum_body_seal,
+            plum.plum_head.plum_body_seal
+        );
+        let guessed_plum = build_plum(seal_algorithm, Some(&wrong_seal_key));
+        assert_ne!(
+            guessed_plum.plum_head.plum_body_seal,
+            plum.plum_head.plum_body_seal
+        );
+        // Whereas the holder of the SealKey reproduces the same Plum.
+        assert_eq!(build_plum(seal_algorithm, Some(&seal_key)), plum);
+
+        // Tampering with the PlumBody is detected by the holder of the SealKey.
+        let mut tampered_plum = plum.clone();
+        tampered_plum
+            .plum_body
+            .plum_body_content
+            .content_byte_v
+            .push(b' ');
+        assert!(matches!(
+            tampered_plum
+                .verify_with_seal_key_provider(&seal_key)
+                .expect_err("fail"),
+            PlumVerifyError::ComputedPlumBodySealMismatch { .. }
+        ));
+    }
+
+    // Malformed keyed seals.
+    assert!(matches!(
+        Seal::try_from_bytes(&[0x80, 0x80, 0xc0, 0x01, 0x05, b'a']).expect_err("fail"),
+        SealError::MalformedKeyedSeal { .. }
+    ));
+    assert!(matches!(
+        Seal::try_from_bytes(&[0x80, 0x80, 0xc0, 0x01, 0x01, 0xff, 0x1e, 0x00]).expect_err("fail"),
+        SealError::MalformedKeyedSeal { .. }
+    ));
+}
+
+#[test]
+fn test_seal_text_encoding_roundtrip() {
+    let seal_key = SealKey::new("group-0".to_string(), b"the group-0 secret".to_vec());
+    for seal_algorithm in [
+        SealAlgorithm::Sha256,
+        SealAlgorithm::Sha512,
+        SealAlgorithm::Blake3,
+    ] {
+        for seal_key_o in [None, Some(&seal_key)] {
+            let mut plum_builder = PlumBuilder::new().with_seal_algorithm(seal_algorithm);
+            if let Some(seal_key) = seal_key_o {
+                plum_builder = plum_builder.with_seal_key(seal_key.clone());
+            }
+            let plum = plum_builder
+                .with_plum_relations_and_plum_body_content_from(
+                    &"test_seal_text_encoding_roundtrip".to_string(),
+                    Some(&ContentFormat::charset_us_ascii()),
+                    ContentEncoding::none(),
+                )
+                .expect("pass")
+                .build()
+                .expect("pass");
+            let plum_head_seal = PlumHeadSeal::from(&plum);
+
+            for (text_encoding, prefix) in [
+                (TextEncoding::Hex, 'f'),
+                (TextEncoding::Base32, 'b'),
+                (TextEncoding::Base58, 'z'),
+            ] {
+                let seal_string = plum_head_seal.to_string_using(text_encoding);
+                log::debug!("{:?} seal_string: {}", text_encoding, seal_string);
+                assert!(seal_string.starts_with(prefix));
+                assert_eq!(
+                    seal_string.parse::<PlumHeadSeal>().expect("pass"),
+                    plum_head_seal
+                );
+
+                let seal_string = plum
+                    .plum_head
+                    .plum_metadata_seal
+                    .to_string_using(text_encoding);
+                assert_eq!(
+                    seal_string.parse::<PlumMetadataSeal>().expect("pass"),
+                    plum.plum_head.plum_metadata_seal
+                );
+                let seal_string = plum
+                    .plum_head
+                    .plum_relations_seal
+                    .to_string_using(text_encoding);
+                assert_eq!(
+                    seal_string.parse::<PlumRelationsSeal>().expect("pass"),
+                    plum.plum_head.plum_relations_seal
+                );
+                let seal_string = plum.plum_head.plum_body_seal.to_string_using(text_encoding);
+                let plum_body_seal = seal_string.parse::<PlumBodySeal>().expect("pass");
+                assert_eq!(plum_body_seal, plum.plum_head.plum_body_seal);
+                assert_eq!(
+                    plum_body_seal.value.seal_key_id_o(),
+                    seal_key_o.map(|seal_key| seal_key.seal_key_id())
+                );
+            }
+            // Display uses the Hex TextEncoding.
+            assert_eq!(
+                plum_head_seal.to_string(),
+                plum_head_seal.to_string_using(TextEncoding::Hex)
+            );
+        }
+    }
+
+    // Malformed strings.
+    assert!(matches!(
+        "".parse::<Seal>().expect_err("fail"),
+        TextDecodeError::MalformedMultibase { .. }
+    ));
+    assert!(matches!(
+        "f0g".parse::<Seal>().expect_err("fail"),
+        TextDecodeError::MalformedMultibase { .. }
+    ));
+    assert_eq!(
+        "f990100".parse::<PlumHeadSeal>().expect_err("fail"),
+        TextDecodeError::MalformedSeal(SealError::UnsupportedMultihashCode(0x99))
+    );
+}
+
+#[test]
+fn test_seal_serde_compatibility() {
+    // The forms that serde derived for Seal and PlumHeadSeal before they had string forms.
+    #[derive(serde::Deserialize, serde::Serialize)]
+    struct LegacySeal {
+        sha256sum: Option<Sha256Sum>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        multihash: Option<Vec<u8>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keyed_seal: Option<Vec<u8>>,
+    }
+    #[derive(serde::Deserialize, serde::Serialize)]
+    struct LegacyPlumHeadSeal {
+        value: LegacySeal,
+    }
+
+    for seal_algorithm in [SealAlgorithm::Sha256, SealAlgorithm::Blake3] {
+        let plum_head_seal =
+            PlumHeadSeal::from(seal_algorithm.compute_seal("blah blah".as_bytes()));
+        let legacy_plum_head_seal = LegacyPlumHeadSeal {
+            value: LegacySeal {
+                sha256sum: plum_head_seal.value.sha256sum.clone(),
+                multihash: plum_head_seal.value.multihash.clone(),
+                keyed_seal: plum_head_seal.value.keyed_seal.clone(),
+            },
+        };
+
+        // Both human-readable and non-human-readable forms are unchanged, so PlumBody-s containing
+        // seals are too.
+        let json = serde_json::to_string(&plum_head_seal).expect("pass");
+        assert_eq!(
+            json,
+            serde_json::to_string(&legacy_plum_head_seal).expect("pass")
+        );
+        assert_eq!(
+            serde_json::from_str::<PlumHeadSeal>(&json).expect("pass"),
+            plum_head_seal
+        );
+        let msgpack = rmp_serde::to_vec(&plum_head_seal).expect("pass");
+        assert_eq!(
+            msgpack,
+            rmp_serde::to_vec(&legacy_plum_head_seal).expect("pass")
+        );
+        assert_eq!(
+            rmp_serde::from_slice::<PlumHeadSeal>(&msgpack).expect("pass"),
+            plum_head_seal
+        );
+    }
+}
+
+#[test]
+fn test_seal_json_content_byte_stability() {
+    let dummy_data = DummyData {
+        name: "stable".to_string(),
+        dependency: PlumHeadSeal::from(Seal::from(Sha256Sum::from(vec![1, 2, 3]))),
+    };
+    let content = idp_proto::serialize_and_encode_to_content(
+        &dummy_data,
+        Some(&ContentFormat::json()),
+        ContentEncoding::identity(),
+    )
+    .expect("pass");
+    assert_eq!(
+        content.content_byte_v.as_slice(),
+        br#"{"name":"stable","dependency":{"value":{"sha256sum":{"value":[1,2,3]}}}}"#
+    );
+
+    // Therefore the PlumBodySeal of a PlumBody with that content is stable too.
+    let plum_body_seal = PlumBodySeal::compute(
+        &PlumBody {
+            plum_body_nonce_o: None,
+            plum_body_content: content,
+        },
+        SealAlgorithm::Sha256,
+    );
+    assert_eq!(
+        plum_body_seal.to_string(),
+        "f21ead7ca0daa79b36b29e526be4ab2c2bd0aee34eb79c4e74e1a2c3acf73ba01"
+    );
+}
+
+#[test]
+fn test_human_readable_str() {
+    #[derive(Debug, serde::Deserialize, PartialEq, serde::Serialize)]
+    struct OptedIn {
+        #[serde(with = "idp_proto::human_readable_str")]
+        plum_head_seal: PlumHeadSeal,
+        #[serde(with = "idp_proto::human_readable_str")]
+        nonce: Nonce,
+    }
+
+    let opted_in = OptedIn {
+        plum_head_seal: PlumHeadSeal::from(
+            SealAlgorithm::Blake3.compute_seal("blah blah".as_bytes()),
+        ),
+        nonce: Nonce::from(vec![0x00, 0x01, 0xab, 0xff]),
+    };
+
+    // Human-readable formats use the string form.
+    let json = serde_json::to_string(&opted_in).expect("pass");
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"plum_head_seal":"{}","nonce":"f0001abff"}}"#,
+            opted_in.plum_head_seal
+        )
+    );
+    assert_eq!(
+        serde_json::from_str::<OptedIn>(&json).expect("pass"),
+        opted_in
+    );
+    // The derived form can still be deserialized.
+    let derived_json = format!(
+        r#"{{"plum_head_seal":{},"nonce":{}}}"#,
+        serde_json::to_string(&opted_in.plum_head_seal).expect("pass"),
+        serde_json::to_string(&opted_in.nonce).expect("pass")
+    );
+    assert_eq!(
+        serde_json::from_str::<OptedIn>(&derived_json).expect("pass"),
+        opted_in
+    );
+
+    // Non-human-readable formats use the derived form.
+    let msgpack = rmp_serde::to_vec(&opted_in).expect("pass");
+    assert_eq!(
+        msgpack,
+        rmp_serde::to_vec(&(&opted_in.plum_head_seal, &opted_in.nonce)).expect("pass")
+    );
+    assert_eq!(
+        rmp_serde::from_slice::<OptedIn>(&msgpack).expect("pass"),
+        opted_in
+    );
+
+    // A malformed seal has no canonical string form, so it's serialized in the derived form, even
+    // by human-readable formats.
+    let malformed = OptedIn {
+        plum_head_seal: PlumHeadSeal::from(Seal::from(Sha256Sum::from(vec![1, 2, 3]))),
+        nonce: Nonce::from(vec![]),
+    };
+    assert!(!malformed.plum_head_seal.value.is_canonical());
+    let json = serde_json::to_string(&malformed).expect("pass");
+    assert_eq!(
+        json,
+        r#"{"plum_head_seal":{"value":{"sha256sum":{"value":[1,2,3]}}},"nonce":"f"}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<OptedIn>(&json).expect("pass"),
+        malformed
+    );
+}
+
+#[test]
+fn test_nonce_text_encoding_roundtrip() {
+    let nonce = Nonce::from(vec![0x00, 0x01, 0xab, 0xff]);
+    assert_eq!(nonce.to_string(), "f0001abff");
+    for text_encoding in [
+        TextEncoding::Hex,
+        TextEncoding::Base32,
+        TextEncoding::Base58,
+    ] {
+        let nonce_string = nonce.to_string_using(text_encoding);
+        assert_eq!(
+            nonce_string.parse::<Nonce>().expect("pass").value,
+            nonce.value
+        );
+    }
+
+    // The serialized forms are unchanged (see test_human_readable_str for the opt-in string form).
+    assert_eq!(
+        serde_json::to_string(&nonce).expect("pass"),
+        r#"{"value":[0,1,171,255]}"#
+    );
+    assert_eq!(
+        rmp_serde::to_vec(&nonce).expect("pass"),
+        rmp_serde::to_vec(&(vec![0x00u8, 0x01, 0xab, 0xff],)).expect("pass")
+    );
+
+    assert!(matches!(
+        "0001abff".parse::<Nonce>().expect_err("fail"),
+        TextDecodeError::MalformedMultibase { .. }
+    ));
+}
+
+#[test]
+fn test_partial_plum_body_seal_check() {
+    let content = format!("test_partial_plum_body_seal_check {}", Uuid::new_v4());
+    let seal_key = SealKey::new("group-0".to_string(), b"the group-0 secret".to_vec());
+    let other_seal_key = SealKey::new("group-1".to_string(), b"the group-1 secret".to_vec());
+    let reassembled = |plum: &Plum, seal_key_provider_o: Option<&dyn SealKeyProvider>| {
+        let plum_body_seal = plum.plum_head.plum_body_seal.clone();
+        // Use small chunks so that there are several of them.
+        let plum_body_chunk_v =
+            PlumBodyChunk::chunk_v_from(&plum_body_seal, &plum.plum_body, 0, 7).expect("pass");
+        assert!(plum_body_chunk_v.len() > 1);
+        let mut partial_plum_body =
+            PartialPlumBody::new(plum_body_seal, plum_body_chunk_v[0].total_length);
+        for plum_body_chunk in plum_body_chunk_v {
+            partial_plum_body
+                .append_chunk(plum_body_chunk)
+                .expect("pass");
+        }
+        partial_plum_body.into_plum_body(seal_key_provider_o)
+    };
+
+    // An unkeyed PlumBodySeal is checked.
+    let plum = PlumBuilder::new()
+        .with_plum_relations_and_plum_body_content_from(
+            &content,
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    assert_eq!(reassembled(&plum, None).expect("pass"), plum.plum_body);
+
+    // A keyed PlumBodySeal can't be checked without its SealKey, so the PlumBody is rejected.
+    let keyed_plum = PlumBuilder::new()
+        .with_seal_key(seal_key.clone())
+        .with_plum_relations_and_plum_body_content_from(
+            &content,
+            Some(&ContentFormat::charset_us_ascii()),
+            ContentEncoding::none(),
+        )
+        .expect("pass")
+        .build()
+        .expect("pass");
+    assert!(matches!(
+        reassembled(&keyed_plum, None).expect_err("fail"),
+        PlumBodyChunkError::UnverifiableKeyedPlumBodySeal { .. }
+    ));
+    assert!(matches!(
+        reassembled(&keyed_plum, Some(&other_seal_key)).expect_err("fail"),
+        PlumBodyChunkError::PlumBodySealComputationFailed { .. }
+    ));
+    // With its SealKey, a keyed PlumBodySeal is checked.
+    assert_eq!(
+        reassembled(&keyed_plum, Some(&seal_key)).expect("pass"),
+        keyed_plum.plum_body
+    );
+    let wrong_seal_key = SealKey::new("group-0".to_string(), b"not the group-0 secret".to_vec());
+    assert!(matches!(
+        reassembled(&keyed_plum, Some(&wrong_seal_key)).expect_err("fail"),
+        PlumBodyChunkError::ComputedPlumBodySealMismatch { .. }
+    ));
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+/// The identity of a client, as established by an Authenticator.  Each authenticated request carries
+/// its AuthenticatedIdentity in its extensions (see tonic::Request::extensions), so that the request
+/// handlers can retrieve it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AuthenticatedIdentity {
+    pub value: String,
+}
+
+impl std::fmt::Display for AuthenticatedIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// Returned by Authenticator::authenticate when a request's credentials are missing or invalid.
+#[derive(Debug)]
+pub struct AuthenticationError {
+    pub message: String,
+    /// A challenge which the client can answer in order to authenticate, if the Authenticator issues
+    /// challenges.  It's sent to the client in the AUTHORIZATION_CHALLENGE_METADATA_KEY metadata entry
+    /// of the Unauthenticated status.
+    pub challenge_o: Option<String>,
+}
+
+impl From<AuthenticationError> for tonic::Status {
+    fn from(authentication_error: AuthenticationError) -> Self {
+        let mut status = tonic::Status::unauthenticated(authentication_error.message);
+        if let Some(challenge) = authentication_error.challenge_o {
+            // The challenge is generated by the Authenticator, so it's expected to be valid ASCII.
+            match challenge.parse() {
+                Ok(challenge_metadata_value) => {
+                    status.metadata_mut().insert(
+                        idp_proto::AUTHORIZATION_CHALLENGE_METADATA_KEY,
+                        challenge_metadata_value,
+                    );
+                }
+                Err(_) => {
+                    log::error!(
+                        "Authenticator issued a challenge that isn't valid ASCII: {:?}",
+                        challenge
+                    );
+                }
+            }
+        }
+        status
+    }
+}
+
+/// Determines the identity of the client making each request to IDPServer (see
+/// IDPServer::with_authenticator).  Requests that fail authentication are rejected with an
+/// Unauthenticated status before reaching IDPServer.
+pub trait Authenticator: Send + Sync {
+    /// Authenticates a request, given the value of its "authorization" metadata entry (if present).
+    /// This is called from a tonic interceptor, which is synchronous, so it must not block.
+    fn authenticate(
+        &self,
+        authorization_o: Option<&str>,
+    ) -> Result<AuthenticatedIdentity, AuthenticationError>;
+}
+
+/// Consults an Authenticator on each request, storing the resulting AuthenticatedIdentity in the
+/// request's extensions.
+#[derive(Clone)]
+pub(crate) struct AuthenticationInterceptor {
+    pub(crate) authenticator_a: Arc<dyn Authenticator>,
+}
+
+impl tonic::service::Interceptor for AuthenticationInterceptor {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        let authorization_o = match request
+            .metadata()
+            .get(idp_proto::AUTHORIZATION_METADATA_KEY)
+        {
+            Some(authorization) => Some(authorization.to_str().map_err(|_| {
+                tonic::Status::unauthenticated("authorization metadata is not valid ASCII")
+            })?),
+            None => None,
+        };
+        let authenticated_identity = self.authenticator_a.authenticate(authorization_o)?;
+        log::trace!(
+            "AuthenticationInterceptor; authenticated as {}",
+            authenticated_identity
+        );
+        request.extensions_mut().insert(authenticated_identity);
+        Ok(request)
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{AuthenticatedIdentity, AuthenticationError, Authenticator};
+use std::collections::HashMap;
+
+/// Authenticates requests having a static bearer token, i.e. "Bearer <token>", where each accepted
+/// token corresponds to an identity.
+pub struct BearerTokenAuthenticator {
+    /// Maps each accepted token to its identity.
+    identity_m: HashMap<String, AuthenticatedIdentity>,
+}
+
+impl BearerTokenAuthenticator {
+    /// Creates a BearerTokenAuthenticator from a map from identity to that identity's token.  Tokens
+    /// must be nonempty and distinct.
+    pub fn new(token_m: HashMap<String, String>) -> anyhow::Result<Self> {
+        let mut identity_m = HashMap::with_capacity(token_m.len());
+        for (identity, token) in token_m.into_iter() {
+            anyhow::ensure!(
+                !token.is_empty(),
+                "bearer token for identity {:?} is empty",
+                identity
+            );
+            anyhow::ensure!(
+                !token.contains(char::is_whitespace),
+                "bearer token for identity {:?} contains whitespace",
+                identity
+            );
+            if let Some(other_identity) =
+                identity_m.insert(token, AuthenticatedIdentity { value: identity })
+            {
+                anyhow::bail!(
+                    "identity {:?} has the same bearer token as another identity",
+                    other_identity.value
+                );
+            }
+        }
+        Ok(Self { identity_m })
+    }
+    /// Loads a BearerTokenAuthenticator from a JSON config file consisting of an object mapping each
+    /// identity to its token, e.g. `{ "alice": "<alice's token>", "bob": "<bob's token>" }`.
+    pub fn load_from_config_file(config_path: &std::path::Path) -> anyhow::Result<Self> {
+        let config_file = std::fs::File::open(config_path).map_err(|e| {
+            anyhow::anyhow!(
+                "failed to open bearer token config file {:?}; error was {}",
+                config_path,
+                e
+            )
+        })?;
+        let token_m: HashMap<String, String> =
+            serde_json::from_reader(std::io::BufReader::new(config_file)).map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to parse bearer token config file {:?}; error was {}",
+                    config_path,
+                    e
+                )
+            })?;
+        Self::new(token_m)
+    }
+}
+
+impl Authenticator for BearerTokenAuthenticator {
+    fn authenticate(
+        &self,
+        authorization_o: Option<&str>,
+    ) -> Result<AuthenticatedIdentity, AuthenticationError> {
+        let authorization = authorization_o.ok_or_else(|| AuthenticationError {
+            message: "missing authorization".to_string(),
+            challenge_o: None,
+        })?;
+        let token = match authorization.split_once(' ') {
+            Some((scheme, token)) if scheme == idp_proto::BEARER_AUTHORIZATION_SCHEME => token,
+            _ => {
+                return Err(AuthenticationError {
+                    message: format!(
+                        "expected {} authorization scheme",
+                        idp_proto::BEARER_AUTHORIZATION_SCHEME
+                    ),
+                    challenge_o: None,
+                });
+            }
+        };
+        self.identity_m
+            .get(token)
+            .cloned()
+            .ok_or_else(|| AuthenticationError {
+                message: "invalid bearer token".to_string(),
+                challenge_o: None,
+            })
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{AuthenticatedIdentity, AuthenticationError, Authenticator};
+use futures::FutureExt;
+use hmac::{Mac, NewMac};
+use idp_proto::{TextEncoding, UnixNanoseconds};
+use std::time::Duration;
+
+type ChallengeMac = hmac::Hmac<sha2::Sha256>;
+
+/// Authenticates requests using did:key-signed challenges.  A client lacking a valid answer to a
+/// challenge is rejected with a freshly issued challenge, which it signs with its did:key (see
+/// idp_sig::did_key_authorization), and the resulting "DIDKey <challenge> <JWS>" authorization is then
+/// accepted as the signer's DID until the session expires.  The identity is the signer's DID (without
+/// the key fragment).
+///
+/// The signed message includes this server's audience (e.g. its URL or DID), so that an answer to a
+/// challenge can't be relayed to a different server.  Challenges are stateless: each one carries its
+/// issue time and an HMAC over it under a secret generated when the DIDKeyAuthenticator is created,
+/// so nothing needs to be stored per challenge or per session.  Consequently, restarting the server
+/// ends all sessions.
+pub struct DIDKeyAuthenticator {
+    audience: String,
+    session_duration: Duration,
+    challenge_mac_secret: Vec<u8>,
+}
+
+impl DIDKeyAuthenticator {
+    /// The audience identifies this server (e.g. its URL or DID), and must match the audience that
+    /// clients sign their answers for (see idp_sig::DIDKeyCredentialProvider).
+    pub fn new(audience: String, session_duration: Duration) -> Self {
+        Self {
+            audience,
+            session_duration,
+            challenge_mac_secret: idp_proto::Nonce::generate().value,
+        }
+    }
+    fn challenge_mac(&self, issued_at: i64, nonce: &str) -> ChallengeMac {
+        let mut challenge_mac = ChallengeMac::new_from_slice(&self.challenge_mac_secret)
+            .expect("programmer error: HMAC accepts keys of any length");
+        challenge_mac.update(format!("{}.{}", issued_at, nonce).as_bytes());
+        challenge_mac
+    }
+    /// A challenge has the form "<issued_at>.<nonce>.<MAC>", where issued_at is in Unix nanoseconds.
+    fn issue_challenge(&self, message: String) -> AuthenticationError {
+        let issued_at = UnixNanoseconds::now().value;
+        let nonce = idp_proto::Nonce::generate().to_string();
+        let mac = self
+            .challenge_mac(issued_at, &nonce)
+            .finalize()
+            .into_bytes();
+        AuthenticationError {
+            message,
+            challenge_o: Some(format!(
+                "{}.{}.{}",
+                issued_at,
+                nonce,
+                TextEncoding::Hex.encode(&mac)
+            )),
+        }
+    }
+    /// Checks that the given challenge was issued by this DIDKeyAuthenticator and that its session
+    /// hasn't expired.
+    fn check_challenge(&self, challenge: &str) -> Result<(), String> {
+        let mut part_i = challenge.split('.');
+        let (issued_at, nonce, mac) =
+            match (part_i.next(), part_i.next(), part_i.next(), part_i.next()) {
+                (Some(issued_at), Some(nonce), Some(mac), None) => (issued_at, nonce, mac),
+                _ => {
+                    return Err("unknown or expired challenge".to_string());
+                }
+            };
+        let issued_at = issued_at
+            .parse::<i64>()
+            .map_err(|_| "unknown or expired challenge".to_string())?;
+        let mac =
+            TextEncoding::decode(mac).map_err(|_| "unknown or expired challenge".to_string())?;
+        // Mac::verify compares in constant time.
+        self.challenge_mac(issued_at, nonce)
+            .verify(&mac)
+            .map_err(|_| "unknown or expired challenge".to_string())?;
+        let elapsed_nanos = UnixNanoseconds::now().value.saturating_sub(issued_at);
+        if elapsed_nanos < 0 || elapsed_nanos as u128 >= self.session_duration.as_nanos() {
+            return Err("unknown or expired challenge".to_string());
+        }
+        Ok(())
+    }
+    /// Returns the identity of the signer of the answer to the given challenge, or an error message.
+    fn check_answer(&self, challenge: &str, jws: &str) -> Result<AuthenticatedIdentity, String> {
+        self.check_challenge(challenge)?;
+        // did:key resolution doesn't do any I/O, so verification completes immediately, and therefore
+        // won't block the interceptor.
+        let signer_did_fragment_url = idp_sig::JWS::from(jws.to_string())
+            .verify_and_extract_signer(&idp_sig::did_key_authorization_message(
+                &self.audience,
+                challenge,
+            ))
+            .now_or_never()
+            .ok_or_else(|| "JWS verification did not complete immediately".to_string())?
+            .map_err(|e| format!("JWS verification failed; error was {}", e))?;
+        Ok(AuthenticatedIdentity {
+            value: signer_did_fragment_url.did,
+        })
+    }
+}
+
+impl Authenticator for DIDKeyAuthenticator {
+    fn authenticate(
+        &self,
+        authorization_o: Option<&str>,
+    ) -> Result<AuthenticatedIdentity, AuthenticationError> {
+        let authorization = match authorization_o {
+            Some(authorization) => authorization,
+            None => {
+                return Err(self.issue_challenge("missing authorization".to_string()));
+            }
+        };
+        let mut token_i = authorization.split(' ');
+        if token_i.next() != Some(idp_proto::DID_KEY_AUTHORIZATION_SCHEME) {
+            return Err(self.issue_challenge(format!(
+                "expected {} authorization scheme",
+                idp_proto::DID_KEY_AUTHORIZATION_SCHEME
+            )));
+        }
+        match (token_i.next(), token_i.next(), token_i.next()) {
+            (None, _, _) => Err(self.issue_challenge("challenge requested".to_string())),
+            (Some(challenge), Some(jws), None) => self
+                .check_answer(challenge, jws)
+                .map_err(|message| self.issue_challenge(message)),
+            _ => Err(self.issue_challenge(format!(
+                "malformed {} authorization",
+                idp_proto::DID_KEY_AUTHORIZATION_SCHEME
+            ))),
+        }
+    }
+}
@@ -1,54 +1,286 @@
+use crate::{
+    authenticator::AuthenticationInterceptor,
+    partial_plum_body_store::{PartialPlumBodyLimits, PartialPlumBodyStore},
+    AuthenticatedIdentity, Authenticator, ServerTLSConfig,
+};
 use async_lock::RwLock;
-use idp_core::Datahost;
+use futures::FutureExt;
+use idp_core::{AccessPolicy, BranchError, BranchNode, Datahost, PathStateError, Permission};
+use idp_datahost_storage::{DatahostStorageError, PathStatesFilter, SelectCursor};
 use idp_proto::{
     BranchCreateRequest, BranchCreateResponse, BranchDeleteRequest, BranchDeleteResponse,
     BranchGetHeadRequest, BranchGetHeadResponse, BranchSetHeadRequest, BranchSetHeadResponse,
-    IndoorDataPlumbing, IndoorDataPlumbingServer, PlumHeadSeal, PullRequest, PullResponse,
-    PushRequest, PushResponse,
+    HasPlumsRequest, HasPlumsResponse, IndoorDataPlumbing, IndoorDataPlumbingServer, PageCursor,
+    Path, PathStateGetRequest, PathStateGetResponse, PathStateListRequest, PathStateListResponse,
+    Plum, PlumBodyChunk, PlumBodyOffset, PlumBodySeal, PlumHeadSeal, PlumVerifyError, PullRequest,
+    PullResponse, PushRequest, PushResponse, WatchPathStatesRequest, WatchPathStatesResponse,
+    PLUM_BODY_CHUNK_SIZE,
 };
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc, time::Duration};
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 
-// Cloning IDPServer simply clones the Arc<RwLock<Datahost>>.  This struct should not contain anything else.
+/// The maximum number of pushed Plums to store at once using Datahost::store_plum_v.
+const PUSH_STORE_BATCH_SIZE: usize = 256;
+/// The capacity of the channel of PullResponse-s, which bounds the number of PlumBodyChunk-s (each
+/// up to PLUM_BODY_CHUNK_SIZE bytes) of a pulled PlumBody that are held in memory at once.
+const PULL_RESPONSE_CHANNEL_CAPACITY: usize = 16;
+/// The lazily-produced responses to a single PullRequest (see IDPServer::handle_pull_request).
+type PullResponseIterator = Box<dyn Iterator<Item = PullResponse> + Send>;
+/// An AccessPolicy, along with the head of the access policy branch that it was loaded from.
+type CachedAccessPolicy = (PlumHeadSeal, Arc<AccessPolicy>);
+/// The maximum number of PathStates returned per PathStateList page, regardless of the requested
+/// page_size.
+const PATH_STATE_LIST_MAX_PAGE_SIZE: u32 = 1024;
+
+// Cloning IDPServer simply clones the Arc-s inside.  This struct should not contain anything else.
 #[derive(Clone)]
 pub struct IDPServer {
     datahost_la: Arc<RwLock<Datahost>>,
+    /// If present, every request must be authenticated by this Authenticator.
+    authenticator_ao: Option<Arc<dyn Authenticator>>,
+    /// If present, requests are governed by the AccessPolicy that's the content of the head of this
+    /// branch.
+    access_policy_branch_path_ao: Option<Arc<Path>>,
+    /// The most recently loaded AccessPolicy, keyed by the head of the access policy branch that it
+    /// was loaded from, so that it's only reloaded when that branch's head changes.
+    access_policy_cache_la: Arc<RwLock<Option<CachedAccessPolicy>>>,
+    /// If present, the server only accepts TLS connections.
+    tls_config_ao: Option<Arc<ServerTLSConfig>>,
+    /// Limits on the PlumBody-s being pushed in PlumBodyChunk-s.
+    partial_plum_body_limits_a: Arc<PartialPlumBodyLimits>,
+    /// The PlumBody-s being pushed in PlumBodyChunk-s, kept across pushes and connections so that
+    /// interrupted pushes can be resumed.
+    partial_plum_body_store_la: Arc<RwLock<PartialPlumBodyStore>>,
 }
 
 impl IDPServer {
     pub fn new(datahost_la: Arc<RwLock<Datahost>>) -> Self {
-        IDPServer { datahost_la }
+        IDPServer {
+            datahost_la,
+            authenticator_ao: None,
+            access_policy_branch_path_ao: None,
+            access_policy_cache_la: Arc::new(RwLock::new(None)),
+            tls_config_ao: None,
+            partial_plum_body_limits_a: Arc::new(PartialPlumBodyLimits::default()),
+            partial_plum_body_store_la: Arc::new(RwLock::new(PartialPlumBodyStore::default())),
+        }
+    }
+    /// Requires every request to be authenticated by the given Authenticator.  The AuthenticatedIdentity
+    /// it produces is stored in the request's extensions.
+    pub fn with_authenticator(mut self, authenticator_a: Arc<dyn Authenticator>) -> Self {
+        self.authenticator_ao = Some(authenticator_a);
+        self
+    }
+    /// Governs requests using the AccessPolicy that's the content of the head of the given branch, which
+    /// must already exist.  The head of that branch is checked for each request, so setting it (as
+    /// permitted by the AccessPolicy itself) takes effect immediately.  Setting it to a Plum whose
+    /// content isn't an AccessPolicy is rejected with an InvalidArgument status.  Requests that aren't
+    /// permitted are rejected with a PermissionDenied status.  Without an AccessPolicy, all requests are
+    /// permitted.
+    pub fn with_access_policy_branch(mut self, access_policy_branch_path: Path) -> Self {
+        self.access_policy_branch_path_ao = Some(Arc::new(access_policy_branch_path));
+        self
+    }
+    /// Only accepts TLS connections, using the given ServerTLSConfig (which may also require clients to
+    /// present certificates).  Clients then connect using an "https://" URL.
+    pub fn with_tls_config(mut self, tls_config: ServerTLSConfig) -> Self {
+        self.tls_config_ao = Some(Arc::new(tls_config));
+        self
+    }
+    /// Rejects pushes of PlumBody-s (in PlumBodyChunk-s) whose total_length exceeds the given maximum
+    /// with an InvalidArgument status.  Each such PlumBody is held in memory in its entirety while it's
+    /// being pushed, and while it's being verified and stored.  Defaults to 256 MiB.
+    pub fn with_max_plum_body_length(mut self, max_plum_body_length: u64) -> Self {
+        Arc::make_mut(&mut self.partial_plum_body_limits_a).max_plum_body_length =
+            max_plum_body_length;
+        self
+    }
+    /// Limits the number of PlumBody-s that can be partially pushed (in PlumBodyChunk-s) at once, and
+    /// the sum of their total_length-s, across all clients.  Starting a push that would exceed either
+    /// limit is rejected with a ResourceExhausted status.  Defaults to 64 PlumBody-s and 1 GiB.
+    pub fn with_partial_plum_body_limits(
+        mut self,
+        max_partial_plum_body_count: usize,
+        max_partial_plum_body_total_length: u64,
+    ) -> Self {
+        let partial_plum_body_limits = Arc::make_mut(&mut self.partial_plum_body_limits_a);
+        partial_plum_body_limits.max_partial_plum_body_count = max_partial_plum_body_count;
+        partial_plum_body_limits.max_partial_plum_body_total_length =
+            max_partial_plum_body_total_length;
+        self
+    }
+    /// Drops a partially pushed PlumBody once it hasn't received a PlumBodyChunk for the given
+    /// duration, after which its push starts over.  Defaults to 1 hour.
+    pub fn with_partial_plum_body_expiry(mut self, partial_plum_body_expiry: Duration) -> Self {
+        Arc::make_mut(&mut self.partial_plum_body_limits_a).partial_plum_body_expiry =
+            partial_plum_body_expiry;
+        self
     }
     pub async fn listen_on(
         &self,
         addr: std::net::SocketAddr,
     ) -> Result<(), tonic::transport::Error> {
-        tonic::transport::Server::builder()
-            .add_service(IndoorDataPlumbingServer::new(self.clone()))
-            // .add_service(IndoorDataPlumbingServer::with_interceptor(self.clone(), {
-            //     let srv = self.clone();
-            //     move |req: tonic::Request<()>| -> Result<tonic::Request<()>, tonic::Status> {
-            //         srv.verify_authentication(req)
-            //     }
-            // }))
-            .serve(addr)
+        let mut server_builder = tonic::transport::Server::builder();
+        if let Some(tls_config) = self.tls_config_ao.as_deref() {
+            server_builder = server_builder.tls_config(tls_config.to_tonic_server_tls_config())?;
+        }
+        match self.authenticator_ao.clone() {
+            Some(authenticator_a) => {
+                server_builder
+                    .add_service(IndoorDataPlumbingServer::with_interceptor(
+                        self.clone(),
+                        AuthenticationInterceptor { authenticator_a },
+                    ))
+                    .serve(addr)
+                    .await
+            }
+            None => {
+                server_builder
+                    .add_service(IndoorDataPlumbingServer::new(self.clone()))
+                    .serve(addr)
+                    .await
+            }
+        }
+    }
+    /// Loads the AccessPolicy that's the content of the given head of the access policy branch.
+    async fn load_access_policy_at(
+        datahost: &Datahost,
+        access_policy_branch_head: &PlumHeadSeal,
+    ) -> anyhow::Result<AccessPolicy> {
+        let branch_node: BranchNode = datahost
+            .load_plum_and_decode_and_deserialize(access_policy_branch_head, None)
+            .await?;
+        let access_policy_plum_head_seal = branch_node
+            .content_o
+            .ok_or_else(|| anyhow::anyhow!("access policy branch node has no content"))?;
+        let access_policy = datahost
+            .load_plum_and_decode_and_deserialize(&access_policy_plum_head_seal, None)
+            .await?;
+        Ok(access_policy)
+    }
+    /// Loads the AccessPolicy (see IDPServer::with_access_policy_branch), if there is one.  It's only
+    /// actually loaded if the head of the access policy branch has changed since it was last loaded.
+    async fn load_access_policy_o(&self) -> anyhow::Result<Option<Arc<AccessPolicy>>> {
+        let access_policy_branch_path = match self.access_policy_branch_path_ao.as_deref() {
+            Some(access_policy_branch_path) => access_policy_branch_path,
+            None => {
+                return Ok(None);
+            }
+        };
+        let datahost = self.datahost_la.read().await;
+        let branch_head = datahost
+            .branch_get_head(access_policy_branch_path, None)
+            .await?;
+        if let Some((cached_branch_head, access_policy_a)) =
+            self.access_policy_cache_la.read().await.as_ref()
+        {
+            if *cached_branch_head == branch_head {
+                return Ok(Some(access_policy_a.clone()));
+            }
+        }
+        let access_policy_a = Arc::new(
+            Self::load_access_policy_at(&datahost, &branch_head)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "head of access policy branch {:?} has no AccessPolicy as its content; error was {}",
+                        access_policy_branch_path.value,
+                        e
+                    )
+                })?,
+        );
+        *self.access_policy_cache_la.write().await = Some((branch_head, access_policy_a.clone()));
+        Ok(Some(access_policy_a))
+    }
+    /// Loads the AccessPolicy (if there is one) for the purpose of authorizing a request.
+    async fn load_access_policy_for_request(
+        &self,
+    ) -> Result<Option<Arc<AccessPolicy>>, tonic::Status> {
+        self.load_access_policy_o().await.map_err(|e| {
+            log::error!("IDPServer failed to load access policy; error was {}", e);
+            tonic::Status::internal(format!("failed to load access policy; error was {}", e))
+        })
+    }
+    /// If the given path is that of the access policy branch, returns an InvalidArgument status unless
+    /// the given new head of it has an AccessPolicy as its content, so that the server can't be left
+    /// without a usable AccessPolicy.
+    async fn validate_access_policy_branch_head(
+        &self,
+        path: &Path,
+        new_branch_head: &PlumHeadSeal,
+    ) -> Result<(), tonic::Status> {
+        if self.access_policy_branch_path_ao.as_deref() != Some(path) {
+            return Ok(());
+        }
+        Self::load_access_policy_at(&*self.datahost_la.read().await, new_branch_head)
             .await
+            .map_err(|e| {
+                tonic::Status::invalid_argument(format!(
+                    "new head {} of access policy branch {:?} has no AccessPolicy as its content; error was {}",
+                    new_branch_head, path.value, e
+                ))
+            })?;
+        Ok(())
+    }
+    /// Returns a PermissionDenied status unless the client having the given AuthenticatedIdentity (or
+    /// an unauthenticated client, if None) has the given Permission on the given path.
+    async fn check_permission(
+        &self,
+        authenticated_identity_o: Option<&AuthenticatedIdentity>,
+        path: &str,
+        permission: Permission,
+    ) -> Result<(), tonic::Status> {
+        let access_policy_a = match self.load_access_policy_for_request().await? {
+            Some(access_policy_a) => access_policy_a,
+            None => {
+                return Ok(());
+            }
+        };
+        if access_policy_a.permits(
+            authenticated_identity_o.map(|identity| identity.value.as_str()),
+            path,
+            permission,
+        ) {
+            Ok(())
+        } else {
+            Err(permission_denied(
+                authenticated_identity_o,
+                permission,
+                &format!("on path {:?}", path),
+            ))
+        }
     }
-    pub fn verify_authentication(
+    /// Returns a PermissionDenied status unless the client having the given AuthenticatedIdentity (or
+    /// an unauthenticated client, if None) has the given Permission on Plums (see
+    /// AccessPolicy::plum_acl).
+    async fn check_plum_permission(
         &self,
-        req: tonic::Request<()>,
-    ) -> Result<tonic::Request<()>, tonic::Status> {
-        // TEMP HACK
-        let token = tonic::metadata::MetadataValue::from_static("Bearer some-secret-token");
-
-        match req.metadata().get("authorization") {
-            Some(t) if token == t => Ok(req),
-            _ => Err(tonic::Status::unauthenticated("No valid auth token")),
+        authenticated_identity_o: Option<&AuthenticatedIdentity>,
+        permission: Permission,
+    ) -> Result<(), tonic::Status> {
+        let access_policy_a = match self.load_access_policy_for_request().await? {
+            Some(access_policy_a) => access_policy_a,
+            None => {
+                return Ok(());
+            }
+        };
+        if access_policy_a.permits_plum_access(
+            authenticated_identity_o.map(|identity| identity.value.as_str()),
+            permission,
+        ) {
+            Ok(())
+        } else {
+            Err(permission_denied(
+                authenticated_identity_o,
+                permission,
+                "on Plums",
+            ))
         }
     }
     async fn handle_push_request(
         &self,
         push_request: PushRequest,
+        authenticated_identity_o: Option<&AuthenticatedIdentity>,
     ) -> Result<PushResponse, tonic::Status> {
         log::debug!(
             "IDPServer::handle_push_request; push_request: {:?}",
@@ -68,13 +300,16 @@ impl IDPServer {
                     .datahost_la
                     .read()
                     .await
-                    .has_plum(&plum_head_seal, None)
-                    .await?
+                    .is_dependency_complete(&plum_head_seal, None)
+                    .await
+                    .map_err(status_from_error)?
                 {
-                    // If the Datahost already has this Plum, the client shouldn't send it.
+                    // If the Datahost already has this Plum and all its dependencies, the client
+                    // shouldn't send it, and won't recurse into its dependencies either.
                     idp_proto::push_response::Value::DontSendThisPlum(plum_head_seal)
                 } else {
-                    // If the Datahost doesn't have this Plum, the client should send it.
+                    // Otherwise the client should send it (even if this Datahost has the Plum itself,
+                    // since the client needs to recurse into its dependencies; storing it is idempotent).
                     idp_proto::push_response::Value::SendThisPlum(plum_head_seal)
                 };
                 Ok(PushResponse { value: Some(value) })
@@ -84,12 +319,96 @@ impl IDPServer {
                     "IDPServer::handle_push_request; got HereHaveAPlum(with plum head seal {})",
                     PlumHeadSeal::from(&plum.plum_head)
                 );
-                self.datahost_la
+                let datahost_g = self.datahost_la.read().await;
+                plum.verify_with_seal_key_provider(datahost_g.seal_key_provider())
+                    .map_err(|e| status_from_error(e.into()))?;
+                datahost_g
+                    .store_plum(&plum, None)
+                    .await
+                    .map_err(status_from_error)?;
+                Ok(PushResponse {
+                    value: Some(idp_proto::push_response::Value::Ok(
+                        idp_proto::Acknowledgement {},
+                    )),
+                })
+            }
+            idp_proto::push_request::Value::ShouldISendThisPlumBody(plum_body_seal) => {
+                log::debug!(
+                    "IDPServer::handle_push_request; got ShouldISendThisPlumBody({})",
+                    plum_body_seal
+                );
+                let value = if self
+                    .datahost_la
                     .read()
                     .await
-                    .store_plum(&plum, None)
+                    .has_plum_body(&plum_body_seal, None)
+                    .await?
+                {
+                    idp_proto::push_response::Value::DontSendThisPlumBody(plum_body_seal)
+                } else if let Some(offset) = self
+                    .partial_plum_body_store_la
+                    .write()
                     .await
-                    .map_err(|e| tonic::Status::internal(e.to_string()))?;
+                    .resume_offset_o(
+                        &self.partial_plum_body_limits_a,
+                        authenticated_identity_o,
+                        &plum_body_seal,
+                    )
+                {
+                    // Resume the interrupted push of this PlumBody.
+                    idp_proto::push_response::Value::SendThisPlumBodyStartingAt(PlumBodyOffset {
+                        plum_body_seal,
+                        offset,
+                    })
+                } else {
+                    idp_proto::push_response::Value::SendThisPlumBody(plum_body_seal)
+                };
+                Ok(PushResponse { value: Some(value) })
+            }
+            idp_proto::push_request::Value::HereHaveAPlumHeadMetadataAndRelations(
+                plum_head_metadata_and_relations,
+            ) => {
+                log::debug!(
+                    "IDPServer::handle_push_request; got HereHaveAPlumHeadMetadataAndRelations(with plum head seal {})",
+                    PlumHeadSeal::from(&plum_head_metadata_and_relations.plum_head)
+                );
+                let datahost_g = self.datahost_la.read().await;
+                plum_head_metadata_and_relations
+                    .verify_with_seal_key_provider(datahost_g.seal_key_provider())
+                    .map_err(|e| status_from_error(e.into()))?;
+                // The client should only send this if the PlumBody is already present, so that this
+                // Datahost doesn't end up with a Plum that's missing its PlumBody.
+                if !datahost_g
+                    .has_plum_body(
+                        &plum_head_metadata_and_relations.plum_head.plum_body_seal,
+                        None,
+                    )
+                    .await?
+                {
+                    return Err(tonic::Status::failed_precondition(format!(
+                        "PlumBody {} is not present",
+                        plum_head_metadata_and_relations.plum_head.plum_body_seal
+                    )));
+                }
+                datahost_g
+                    .store_plum_head_metadata_and_relations(&plum_head_metadata_and_relations, None)
+                    .await
+                    .map_err(status_from_error)?;
+                Ok(PushResponse {
+                    value: Some(idp_proto::push_response::Value::Ok(
+                        idp_proto::Acknowledgement {},
+                    )),
+                })
+            }
+            idp_proto::push_request::Value::HereHaveAPlumBodyChunk(plum_body_chunk) => {
+                log::debug!(
+                    "IDPServer::handle_push_request; got HereHaveAPlumBodyChunk(plum body seal {}, offset {}, length {})",
+                    plum_body_chunk.plum_body_seal,
+                    plum_body_chunk.offset,
+                    plum_body_chunk.data.len()
+                );
+                self.handle_here_have_a_plum_body_chunk(plum_body_chunk, authenticated_identity_o)
+                    .await?;
                 Ok(PushResponse {
                     value: Some(idp_proto::push_response::Value::Ok(
                         idp_proto::Acknowledgement {},
@@ -98,85 +417,137 @@ impl IDPServer {
             }
         }
     }
-    // TODO: Make this streaming
+    /// Appends the PlumBodyChunk to the corresponding PartialPlumBody, and once that's complete,
+    /// verifies and stores the PlumBody.  A PlumBodyChunk at offset 0 (re)starts the transfer.  The
+    /// PartialPlumBody-s are kept per client (by AuthenticatedIdentity) across pushes and connections,
+    /// subject to the limits in partial_plum_body_limits_a.  Note that the whole PlumBody is held in
+    /// memory while it's being pushed, and while it's being verified and stored.
+    async fn handle_here_have_a_plum_body_chunk(
+        &self,
+        plum_body_chunk: PlumBodyChunk,
+        authenticated_identity_o: Option<&AuthenticatedIdentity>,
+    ) -> Result<(), tonic::Status> {
+        let plum_body_seal = plum_body_chunk.plum_body_seal.clone();
+        // Whether or not the PlumBody verifies, once it's complete, this transfer is over.
+        let partial_plum_body = match self.partial_plum_body_store_la.write().await.append_chunk(
+            &self.partial_plum_body_limits_a,
+            authenticated_identity_o,
+            plum_body_chunk,
+        )? {
+            Some(partial_plum_body) => partial_plum_body,
+            None => {
+                return Ok(());
+            }
+        };
+        let datahost_g = self.datahost_la.read().await;
+        let plum_body = partial_plum_body
+            .into_plum_body(Some(datahost_g.seal_key_provider()))
+            .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+        datahost_g
+            .store_plum_body(&plum_body, &plum_body_seal, None)
+            .await
+            .map_err(status_from_error)?;
+        Ok(())
+    }
+    /// Handles a batch of HereHaveAPlum requests by storing all the Plums at once, producing one
+    /// response per Plum.  Keyed seals are verified using the Datahost's SealKeyProvider.  If verifying
+    /// or storing fails, then nothing is stored, and a single error is produced.
+    async fn handle_here_have_a_plum_v(
+        &self,
+        plum_v: Vec<Plum>,
+    ) -> Vec<Result<PushResponse, tonic::Status>> {
+        log::debug!(
+            "IDPServer::handle_here_have_a_plum_v; got {} HereHaveAPlum requests",
+            plum_v.len()
+        );
+        let datahost_g = self.datahost_la.read().await;
+        for plum in plum_v.iter() {
+            if let Err(e) = plum.verify_with_seal_key_provider(datahost_g.seal_key_provider()) {
+                return vec![Err(status_from_error(e.into()))];
+            }
+        }
+        match datahost_g.store_plum_v(&plum_v, None).await {
+            Ok(_) => {
+                let mut push_response_rv = Vec::with_capacity(plum_v.len());
+                for _ in plum_v.iter() {
+                    push_response_rv.push(Ok(PushResponse {
+                        value: Some(idp_proto::push_response::Value::Ok(
+                            idp_proto::Acknowledgement {},
+                        )),
+                    }));
+                }
+                push_response_rv
+            }
+            Err(e) => vec![Err(status_from_error(e))],
+        }
+    }
+    /// Handles a single PullRequest.  Only the requested Plum is sent; it's up to the client to request
+    /// whichever of its dependencies it doesn't already have.  There is exactly one response, except
+    /// for a chunked PlumBody, which produces one response per PlumBodyChunk.  The responses are
+    /// produced lazily, so that the PlumBodyChunk-s of a large PlumBody are only produced as they
+    /// can be sent.
     async fn handle_pull_request(
         &self,
         pull_request: PullRequest,
-    ) -> Result<Vec<PullResponse>, tonic::Status> {
+    ) -> Result<PullResponseIterator, tonic::Status> {
         log::debug!(
             "IDPServer::handle_pull_request; pull_request: {:?}",
             pull_request
         );
 
         match pull_request.value.ok_or_else(|| {
-            tonic::Status::invalid_argument("malformed PullRequest; encountered 'None' in request")
+            tonic::Status::invalid_argument(
+                "malformed PullRequest; encountered 'None' in request stream",
+            )
         })? {
             idp_proto::pull_request::Value::IWantThisPlum(plum_head_seal) => {
-                // TODO: Stream the results from the DB query instead of reading the whole thing into memory.
-                let plum_relation_flags_m = self
+                let value = if let Some(plum) = self
                     .datahost_la
                     .read()
                     .await
-                    .accumulated_relations_recursive(
-                        &plum_head_seal,
-                        idp_proto::PlumRelationFlags::CONTENT_DEPENDENCY
-                            | idp_proto::PlumRelationFlags::METADATA_DEPENDENCY,
-                        None,
+                    .load_option_plum(&plum_head_seal, None)
+                    .await
+                    .map_err(status_from_error)?
+                {
+                    idp_proto::pull_response::Value::Plum(plum)
+                } else {
+                    idp_proto::pull_response::Value::IDontHaveThisPlum(plum_head_seal)
+                };
+                Ok(Box::new(std::iter::once(PullResponse {
+                    value: Some(value),
+                })))
+            }
+            idp_proto::pull_request::Value::IWantThisPlumHeadMetadataAndRelations(
+                plum_head_seal,
+            ) => {
+                let value = if let Some(plum_head_metadata_and_relations) = self
+                    .datahost_la
+                    .read()
+                    .await
+                    .load_option_plum_head_metadata_and_relations(&plum_head_seal, None)
+                    .await
+                    .map_err(status_from_error)?
+                {
+                    idp_proto::pull_response::Value::PlumHeadMetadataAndRelations(
+                        plum_head_metadata_and_relations,
                     )
+                } else {
+                    idp_proto::pull_response::Value::IDontHaveThisPlum(plum_head_seal)
+                };
+                Ok(Box::new(std::iter::once(PullResponse {
+                    value: Some(value),
+                })))
+            }
+            idp_proto::pull_request::Value::IWantThisPlumBody(plum_body_seal) => {
+                self.handle_i_want_this_plum_body(plum_body_seal, None)
                     .await
-                    .map_err(|e| tonic::Status::internal(e.to_string()))?;
-                log::trace!(
-                    "IDPServer::handle_pull_request({}); plum_relation_flags_m ({} entries):",
-                    plum_head_seal,
-                    plum_relation_flags_m.len()
-                );
-                for plum_relation_flags in plum_relation_flags_m.iter() {
-                    log::trace!(
-                        "    {} -> {:?}",
-                        plum_relation_flags.0,
-                        plum_relation_flags.1
-                    );
-                }
-
-                // Accumulated plum_relations for a plum should not include the plum itself.
-                assert!(!plum_relation_flags_m.contains_key(&plum_head_seal));
-                // NOTE: Because the plum_relations are collected via HashMap, they will be in a random order,
-                // which is not ideal for short-circuiting push operations when the target Datahost already
-                // has most of the Plum-s.  One solution to this is to accumulate the plum_relations and store
-                // them in (probably) a breadth-first traversal order.  A better solution would be to stream
-                // the plum_relations accumulations and only bother recursing on Plum-s that the target Datahost
-                // asks to receive.
-                let mut accumulated_plum_head_seal_v =
-                    Vec::with_capacity(plum_relation_flags_m.len() + 1);
-                // First, add this Plum.
-                accumulated_plum_head_seal_v.push(plum_head_seal.clone());
-                accumulated_plum_head_seal_v
-                    .extend(plum_relation_flags_m.keys().into_iter().cloned());
-
-                let mut pull_response_v = Vec::with_capacity(accumulated_plum_head_seal_v.len());
-
-                for accumulated_plum_head_seal in accumulated_plum_head_seal_v.into_iter() {
-                    if let Some(plum) = self
-                        .datahost_la
-                        .read()
-                        .await
-                        .load_option_plum(&accumulated_plum_head_seal, None)
-                        .await
-                        .map_err(|e| tonic::Status::internal(e.to_string()))?
-                    {
-                        pull_response_v.push(idp_proto::PullResponse {
-                            value: Some(idp_proto::pull_response::Value::Plum(plum)),
-                        });
-                    } else {
-                        pull_response_v.push(idp_proto::PullResponse {
-                            value: Some(idp_proto::pull_response::Value::IDontHaveThisPlum(
-                                accumulated_plum_head_seal,
-                            )),
-                        });
-                    }
-                }
-
-                Ok(pull_response_v)
+            }
+            idp_proto::pull_request::Value::IWantThisPlumBodyStartingAt(plum_body_offset) => {
+                self.handle_i_want_this_plum_body(
+                    plum_body_offset.plum_body_seal,
+                    Some(plum_body_offset.offset),
+                )
+                .await
             }
         }
 
@@ -254,6 +625,52 @@ impl IDPServer {
         //     }
         // }
     }
+    /// Responds with the requested PlumBody, either whole or (if it's too large for a single message,
+    /// or if offset_o is specified) as a sequence of PlumBodyChunk-s starting at the given offset.
+    /// Note that the whole PlumBody is loaded into memory, even if only some of its PlumBodyChunk-s
+    /// are sent.
+    async fn handle_i_want_this_plum_body(
+        &self,
+        plum_body_seal: PlumBodySeal,
+        offset_o: Option<u64>,
+    ) -> Result<PullResponseIterator, tonic::Status> {
+        let plum_body = match self
+            .datahost_la
+            .read()
+            .await
+            .load_option_plum_body(&plum_body_seal, None)
+            .await
+            .map_err(status_from_error)?
+        {
+            Some(plum_body) => plum_body,
+            None => {
+                return Ok(Box::new(std::iter::once(PullResponse {
+                    value: Some(idp_proto::pull_response::Value::IDontHaveThisPlumBody(
+                        plum_body_seal,
+                    )),
+                })));
+            }
+        };
+        if offset_o.is_none() && !plum_body.requires_chunking() {
+            return Ok(Box::new(std::iter::once(PullResponse {
+                value: Some(idp_proto::pull_response::Value::PlumBody(plum_body)),
+            })));
+        }
+        let plum_body_chunk_i = PlumBodyChunk::chunk_i_from(
+            &plum_body_seal,
+            &plum_body,
+            offset_o.unwrap_or(0),
+            PLUM_BODY_CHUNK_SIZE,
+        )
+        .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+        Ok(Box::new(plum_body_chunk_i.map(|plum_body_chunk| {
+            PullResponse {
+                value: Some(idp_proto::pull_response::Value::PlumBodyChunk(
+                    plum_body_chunk,
+                )),
+            }
+        })))
+    }
 }
 
 // type IndoorDataPlumbingResult<T> = Result<tonic::Response<T>, tonic::Status>;
@@ -266,6 +683,9 @@ impl IndoorDataPlumbing for IDPServer {
         &self,
         request: tonic::Request<tonic::Streaming<PushRequest>>,
     ) -> Result<tonic::Response<Self::PushStream>, tonic::Status> {
+        self.check_plum_permission(request.extensions().get(), Permission::Push)
+            .await?;
+        let authenticated_identity_o = request.extensions().get::<AuthenticatedIdentity>().cloned();
         let mut in_stream = request.into_inner();
         let (tx, rx) = tokio::sync::mpsc::channel(128);
 
@@ -275,11 +695,23 @@ impl IndoorDataPlumbing for IDPServer {
         // to mapped version of `in_stream`.
         {
             // Make a clone of IDPServer to move into the async closure.  This simply clones the
-            // Arc<RwLock<Datahost>> inside.
+            // Arc-s inside.
             let idp_server = self.clone();
             tokio::spawn(async move {
                 // use futures::StreamExt;
-                while let Some(push_request_r) = in_stream.next().await {
+                // A request that was received while batching HereHaveAPlum requests (see below), but which
+                // couldn't be included in the batch.
+                let mut deferred_push_request_ro = None;
+                let mut in_stream_ended = false;
+                loop {
+                    let push_request_r = match deferred_push_request_ro.take() {
+                        Some(push_request_r) => push_request_r,
+                        None if in_stream_ended => break,
+                        None => match in_stream.next().await {
+                            Some(push_request_r) => push_request_r,
+                            None => break,
+                        },
+                    };
                     match push_request_r {
                         Ok(push_request) => {
                             // tx.send(idp_server.handle_push_request(push_request))
@@ -289,19 +721,60 @@ impl IndoorDataPlumbing for IDPServer {
                             // tx.send(idp_server.handle_push_request(push_request))
                             //     .await?;
 
-                            match tx
-                                .send(idp_server.handle_push_request(push_request).await)
-                                .await
-                            {
-                                Ok(()) => {}
-                                Err(error_f) => {
-                                    // TODO: Send this error through the tx?  Though that may not work
-                                    // if this send failed.  Maybe break here instead.
-                                    // error_f.0.expect("tx error");
-                                    error_f.0.expect("tx error");
+                            let push_response_rv = match push_request.value {
+                                Some(idp_proto::push_request::Value::HereHaveAPlum(plum)) => {
+                                    // Batch together the HereHaveAPlum requests which have already been
+                                    // received (without waiting for more), so they can be stored at once.
+                                    let mut plum_v = vec![plum];
+                                    while plum_v.len() < PUSH_STORE_BATCH_SIZE {
+                                        match in_stream.next().now_or_never() {
+                                            Some(Some(Ok(PushRequest {
+                                                value:
+                                                    Some(idp_proto::push_request::Value::HereHaveAPlum(
+                                                        plum,
+                                                    )),
+                                            }))) => {
+                                                plum_v.push(plum);
+                                            }
+                                            Some(Some(push_request_r)) => {
+                                                deferred_push_request_ro = Some(push_request_r);
+                                                break;
+                                            }
+                                            Some(None) => {
+                                                in_stream_ended = true;
+                                                break;
+                                            }
+                                            // Nothing else has been received yet.
+                                            None => {
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    idp_server.handle_here_have_a_plum_v(plum_v).await
+                                }
+                                value => {
+                                    vec![
+                                        idp_server
+                                            .handle_push_request(
+                                                PushRequest { value },
+                                                authenticated_identity_o.as_ref(),
+                                            )
+                                            .await,
+                                    ]
+                                }
+                            };
+                            // A failed send means the response stream was dropped, e.g. because the
+                            // client disconnected, so there's no one left to respond to.
+                            let mut response_stream_dropped = false;
+                            for push_response_r in push_response_rv {
+                                if tx.send(push_response_r).await.is_err() {
+                                    response_stream_dropped = true;
+                                    break;
                                 }
                             }
-                            // .expect("working rx");
+                            if response_stream_dropped {
+                                break;
+                            }
                         }
                         Err(err) => {
                             if let Some(io_err) = match_for_io_error(&err) {
@@ -337,73 +810,65 @@ impl IndoorDataPlumbing for IDPServer {
         std::pin::Pin<Box<dyn futures::Stream<Item = Result<PullResponse, tonic::Status>> + Send>>;
     async fn pull(
         &self,
-        request: tonic::Request<PullRequest>,
+        request: tonic::Request<tonic::Streaming<PullRequest>>,
     ) -> Result<tonic::Response<Self::PullStream>, tonic::Status> {
-        let pull_request = request.into_inner();
+        self.check_plum_permission(request.extensions().get(), Permission::Read)
+            .await?;
+        let mut in_stream = request.into_inner();
+        let (tx, rx) = tokio::sync::mpsc::channel(PULL_RESPONSE_CHANNEL_CAPACITY);
 
-        // let (tx, rx) = tokio::sync::mpsc::channel(128);
+        // See IDPServer::push regarding the spawn.
+        {
+            // Make a clone of IDPServer to move into the async closure.  This simply clones the
+            // Arc-s inside.
+            let idp_server = self.clone();
+            tokio::spawn(async move {
+                while let Some(pull_request_r) = in_stream.next().await {
+                    match pull_request_r {
+                        Ok(pull_request) => {
+                            let pull_response_ri: Box<dyn Iterator<Item = _> + Send> =
+                                match idp_server.handle_pull_request(pull_request).await {
+                                    Ok(pull_response_i) => Box::new(pull_response_i.map(Ok)),
+                                    Err(status) => Box::new(std::iter::once(Err(status))),
+                                };
+                            // The channel is bounded, so the PlumBodyChunk-s of a large PlumBody are
+                            // only produced as fast as the client receives them.
+                            let mut response_stream_dropped = false;
+                            for pull_response_r in pull_response_ri {
+                                if tx.send(pull_response_r).await.is_err() {
+                                    response_stream_dropped = true;
+                                    break;
+                                }
+                            }
+                            if response_stream_dropped {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            if let Some(io_err) = match_for_io_error(&err) {
+                                if io_err.kind() == std::io::ErrorKind::BrokenPipe {
+                                    // here you can handle special case when client
+                                    // disconnected in unexpected way
+                                    eprintln!("\tclient disconnected: broken pipe");
+                                    break;
+                                }
+                            }
 
-        let pull_response_v = self
-            .handle_pull_request(pull_request)
-            .await?
-            .into_iter()
-            .map(|pull_response| Ok(pull_response))
-            .collect::<Vec<_>>();
+                            match tx.send(Err(err)).await {
+                                Ok(_) => (),
+                                Err(_err) => break, // response was dropped
+                            }
+                        }
+                    }
+                }
+            });
+        }
 
-        // let out_stream = ReceiverStream::new(rx);
+        let out_stream = ReceiverStream::new(rx);
 
-        // Ok(tonic::Response::new(Box::pin(out_stream) as std::pin::Pin<Box<dyn futures::Stream<Item = Result<PullResponse, tonic::Status>> + Send>>))
         Ok(tonic::Response::new(
-            Box::pin(tokio_stream::iter(pull_response_v)) as Self::PullStream,
+            Box::pin(out_stream) as Self::PullStream
         ))
-
-        // let mut in_stream = request.into_inner();
-        // let (tx, rx) = tokio::sync::mpsc::channel(128);
-
-        // // this spawn here is required if you want to handle connection error.
-        // // If we just map `in_stream` and write it back as `out_stream` the `out_stream`
-        // // will be dropped when connection error occurs and error will never be propagated
-        // // to mapped version of `in_stream`.
-        // {
-        //     // Make a clone of IDPServer to move into the async closure.  This simply clones the
-        //     // Arc<RwLock<Datahost>> inside.
-        //     let idp_server = self.clone();
-        //     tokio::spawn(async move {
-        //         // use futures::StreamExt;
-        //         while let Some(pull_request_r) = in_stream.next().await {
-        //             match pull_request_r {
-        //                 Ok(pull_request) => {
-        //                     tx.send(idp_server.handle_pull_request(pull_request))
-        //                         .await
-        //                         .expect("working rx");
-        //                 }
-        //                 Err(err) => {
-        //                     if let Some(io_err) = match_for_io_error(&err) {
-        //                         if io_err.kind() == std::io::ErrorKind::BrokenPipe {
-        //                             // here you can handle special case when client
-        //                             // disconnected in unexpected way
-        //                             eprintln!("\tclient disconnected: broken pipe");
-        //                             break;
-        //                         }
-        //                     }
-
-        //                     match tx.send(Err(err)).await {
-        //                         Ok(_) => (),
-        //                         Err(_err) => break, // response was dropped
-        //                     }
-        //                 }
-        //             }
-        //         }
-        //         println!("\tstream ended");
-        //     });
-        // }
-
-        // let out_stream = ReceiverStream::new(rx);
-
-        // // Ok(tonic::Response::new(Box::pin(out_stream) as std::pin::Pin<Box<dyn futures::Stream<Item = Result<PullResponse, tonic::Status>> + Send>>))
-        // Ok(tonic::Response::new(
-        //     Box::pin(out_stream) as Self::PullStream
-        // ))
     }
 
     // async fn del(
@@ -417,7 +882,18 @@ impl IndoorDataPlumbing for IDPServer {
         &self,
         request: tonic::Request<BranchCreateRequest>,
     ) -> Result<tonic::Response<BranchCreateResponse>, tonic::Status> {
+        self.check_permission(
+            request.extensions().get(),
+            &request.get_ref().branch_path_state.path.value,
+            Permission::BranchCreate,
+        )
+        .await?;
         let req = request.into_inner();
+        self.validate_access_policy_branch_head(
+            &req.branch_path_state.path,
+            &req.branch_path_state.current_state_plum_head_seal,
+        )
+        .await?;
 
         self.datahost_la
             .read()
@@ -431,6 +907,12 @@ impl IndoorDataPlumbing for IDPServer {
         &self,
         request: tonic::Request<BranchDeleteRequest>,
     ) -> Result<tonic::Response<BranchDeleteResponse>, tonic::Status> {
+        self.check_permission(
+            request.extensions().get(),
+            &request.get_ref().branch_path.value,
+            Permission::Delete,
+        )
+        .await?;
         let req = request.into_inner();
 
         self.datahost_la
@@ -445,6 +927,12 @@ impl IndoorDataPlumbing for IDPServer {
         &self,
         request: tonic::Request<BranchGetHeadRequest>,
     ) -> Result<tonic::Response<BranchGetHeadResponse>, tonic::Status> {
+        self.check_permission(
+            request.extensions().get(),
+            &request.get_ref().branch_path.value,
+            Permission::Read,
+        )
+        .await?;
         let req = request.into_inner();
 
         let branch_head_plum_head_seal = self
@@ -462,7 +950,24 @@ impl IndoorDataPlumbing for IDPServer {
         &self,
         request: tonic::Request<BranchSetHeadRequest>,
     ) -> Result<tonic::Response<BranchSetHeadResponse>, tonic::Status> {
+        self.check_permission(
+            request.extensions().get(),
+            &request.get_ref().branch_path.value,
+            Permission::BranchSetHead,
+        )
+        .await?;
         let req = request.into_inner();
+        // A request lacking a value is rejected by Datahost::branch_set_head.
+        if let Some(
+            idp_proto::branch_set_head_request::Value::BranchFastForwardTo(new_branch_head)
+            | idp_proto::branch_set_head_request::Value::BranchRewindTo(new_branch_head)
+            | idp_proto::branch_set_head_request::Value::BranchForkHistoryTo(new_branch_head)
+            | idp_proto::branch_set_head_request::Value::BranchTotallyRewriteTo(new_branch_head),
+        ) = req.value.as_ref()
+        {
+            self.validate_access_policy_branch_head(&req.branch_path, new_branch_head)
+                .await?;
+        }
 
         self.datahost_la
             .read()
@@ -472,6 +977,214 @@ impl IndoorDataPlumbing for IDPServer {
 
         Ok(tonic::Response::new(BranchSetHeadResponse {}))
     }
+
+    async fn path_state_get(
+        &self,
+        request: tonic::Request<PathStateGetRequest>,
+    ) -> Result<tonic::Response<PathStateGetResponse>, tonic::Status> {
+        self.check_permission(
+            request.extensions().get(),
+            &request.get_ref().path.value,
+            Permission::Read,
+        )
+        .await?;
+        let req = request.into_inner();
+
+        let path_state = self
+            .datahost_la
+            .read()
+            .await
+            .load_path_state(&req.path, None)
+            .await?;
+
+        Ok(tonic::Response::new(PathStateGetResponse { path_state }))
+    }
+    async fn path_state_list(
+        &self,
+        request: tonic::Request<PathStateListRequest>,
+    ) -> Result<tonic::Response<PathStateListResponse>, tonic::Status> {
+        // Rather than requiring a Permission for the whole listing, PathStates that the client may not
+        // read are omitted from it.
+        let access_policy_ao = self.load_access_policy_for_request().await?;
+        let identity_o = request
+            .extensions()
+            .get::<AuthenticatedIdentity>()
+            .map(|authenticated_identity| authenticated_identity.value.clone());
+        let req = request.into_inner();
+        if req.page_size == 0 {
+            return Err(tonic::Status::invalid_argument(
+                "page_size must be positive",
+            ));
+        }
+
+        let filter = PathStatesFilter {
+            updated_at_begin_o: req.updated_at_begin_o,
+            path_prefix_o: req.path_prefix_o,
+            ..Default::default()
+        };
+        let cursor_o = req.page_cursor_o.map(SelectCursor::from);
+        let select_page = self
+            .datahost_la
+            .read()
+            .await
+            .select_path_states_page(
+                &filter,
+                cursor_o.as_ref(),
+                req.page_size.min(PATH_STATE_LIST_MAX_PAGE_SIZE),
+                None,
+            )
+            .await
+            .map_err(status_from_error)?;
+        let last_updated_at_o = select_page
+            .row_v
+            .last()
+            .map(|(_, row_updated_at, _)| *row_updated_at);
+
+        Ok(tonic::Response::new(PathStateListResponse {
+            path_state_v: select_page
+                .row_v
+                .into_iter()
+                .map(|(_, _, path_state)| path_state)
+                .filter(|path_state| {
+                    access_policy_ao
+                        .as_ref()
+                        .map(|access_policy| {
+                            access_policy.permits(
+                                identity_o.as_deref(),
+                                &path_state.path.value,
+                                Permission::Read,
+                            )
+                        })
+                        .unwrap_or(true)
+                })
+                .collect(),
+            next_page_cursor_o: select_page.next_cursor_o.map(PageCursor::from),
+            last_updated_at_o,
+        }))
+    }
+    async fn has_plums(
+        &self,
+        request: tonic::Request<HasPlumsRequest>,
+    ) -> Result<tonic::Response<HasPlumsResponse>, tonic::Status> {
+        self.check_plum_permission(request.extensions().get(), Permission::Read)
+            .await?;
+        let req = request.into_inner();
+
+        let has_plum_v = self
+            .datahost_la
+            .read()
+            .await
+            .has_plum_v(&req.plum_head_seal_v, None)
+            .await
+            .map_err(status_from_error)?;
+
+        Ok(tonic::Response::new(HasPlumsResponse { has_plum_v }))
+    }
+
+    type WatchPathStatesStream = std::pin::Pin<
+        Box<dyn futures::Stream<Item = Result<WatchPathStatesResponse, tonic::Status>> + Send>,
+    >;
+    async fn watch_path_states(
+        &self,
+        request: tonic::Request<WatchPathStatesRequest>,
+    ) -> Result<tonic::Response<Self::WatchPathStatesStream>, tonic::Status> {
+        // Each watched path requires Read permission.  If all PathStates are watched, then (as in
+        // path_state_list) changes to PathStates that the client may not read are omitted.  Note that
+        // the AccessPolicy in effect when the watch begins applies for the duration of the watch.
+        for path in request.get_ref().path_v.iter() {
+            self.check_permission(request.extensions().get(), &path.value, Permission::Read)
+                .await?;
+        }
+        let access_policy_ao = self.load_access_policy_for_request().await?;
+        let identity_o = request
+            .extensions()
+            .get::<AuthenticatedIdentity>()
+            .map(|authenticated_identity| authenticated_identity.value.clone());
+        let req = request.into_inner();
+        let watched_path_s: HashSet<String> =
+            req.path_v.into_iter().map(|path| path.value).collect();
+
+        let out_stream = self
+            .datahost_la
+            .read()
+            .await
+            .subscribe_to_path_state_changes()
+            .filter_map(move |path_state_change| {
+                let path = match path_state_change.value.as_ref()? {
+                    idp_proto::path_state_change::Value::Inserted(path_state)
+                    | idp_proto::path_state_change::Value::Updated(path_state) => {
+                        &path_state.path.value
+                    }
+                    idp_proto::path_state_change::Value::Deleted(path) => &path.value,
+                };
+                let is_watched = watched_path_s.is_empty() || watched_path_s.contains(path);
+                let is_readable = access_policy_ao
+                    .as_ref()
+                    .map(|access_policy| {
+                        access_policy.permits(identity_o.as_deref(), path, Permission::Read)
+                    })
+                    .unwrap_or(true);
+                if is_watched && is_readable {
+                    log::debug!(
+                        "IDPServer::watch_path_states; sending {:?}",
+                        path_state_change
+                    );
+                    Some(Ok(WatchPathStatesResponse { path_state_change }))
+                } else {
+                    None
+                }
+            })
+            // The subscription only ends if it fell too far behind (see
+            // Datahost::subscribe_to_path_state_changes).
+            .chain(tokio_stream::once(Err(tonic::Status::resource_exhausted(
+                "fell too far behind the PathState changes; reload the PathStates and watch again",
+            ))));
+
+        Ok(tonic::Response::new(
+            Box::pin(out_stream) as Self::WatchPathStatesStream
+        ))
+    }
+}
+
+/// Converts an error into a tonic::Status.  If the error is (or has context attached to) one of the
+/// errors that has a `From<…> for tonic::Status` impl, then that is used, so that the Status has the
+/// appropriate code and carries an ErrorDetail from which IDPClient reconstructs the error.  A
+/// PlumVerifyError means that the client sent a malformed Plum, so it produces InvalidArgument.
+/// Anything else produces Internal.  This is analogous to idp_p2p's peer_error_from_error.
+fn status_from_error(error: anyhow::Error) -> tonic::Status {
+    let error = match error.downcast::<DatahostStorageError>() {
+        Ok(datahost_storage_error) => return datahost_storage_error.into(),
+        Err(error) => error,
+    };
+    let error = match error.downcast::<PathStateError>() {
+        Ok(path_state_error) => return path_state_error.into(),
+        Err(error) => error,
+    };
+    let error = match error.downcast::<BranchError>() {
+        Ok(branch_error) => return branch_error.into(),
+        Err(error) => error,
+    };
+    match error.downcast::<PlumVerifyError>() {
+        Ok(plum_verify_error) => tonic::Status::invalid_argument(plum_verify_error.to_string()),
+        Err(error) => tonic::Status::internal(format!("{:#}", error)),
+    }
+}
+
+/// Returns the PermissionDenied status for a client lacking the given Permission on the described
+/// scope (e.g. a path).
+fn permission_denied(
+    authenticated_identity_o: Option<&AuthenticatedIdentity>,
+    permission: Permission,
+    scope_description: &str,
+) -> tonic::Status {
+    let identity_description = match authenticated_identity_o {
+        Some(authenticated_identity) => format!("{:?}", authenticated_identity.value),
+        None => "unauthenticated client".to_string(),
+    };
+    tonic::Status::permission_denied(format!(
+        "{} does not have {:?} permission {}",
+        identity_description, permission, scope_description
+    ))
 }
 
 /// Reference: https://github.com/hyperium/tonic/blob/82770713b58892203a83c307729b3e7bebe574e3/examples/src/streaming/server.rs
@@ -1,3 +1,14 @@
+mod authenticator;
+mod bearer_token_authenticator;
+mod did_key_authenticator;
 mod idp_server;
+mod partial_plum_body_store;
+mod server_tls_config;
 
-pub use crate::idp_server::IDPServer;
+pub use crate::{
+    authenticator::{AuthenticatedIdentity, AuthenticationError, Authenticator},
+    bearer_token_authenticator::BearerTokenAuthenticator,
+    did_key_authenticator::DIDKeyAuthenticator,
+    idp_server::IDPServer,
+    server_tls_config::ServerTLSConfig,
+};
@@ -0,0 +1,214 @@
+use crate::AuthenticatedIdentity;
+use idp_proto::{PartialPlumBody, PlumBodyChunk, PlumBodyChunkError, PlumBodySeal};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The default maximum total_length of a PlumBody pushed in PlumBodyChunk-s.
+pub(crate) const DEFAULT_MAX_PLUM_BODY_LENGTH: u64 = 256 * 1024 * 1024;
+/// The default maximum number of PartialPlumBody-s kept at once.
+pub(crate) const DEFAULT_MAX_PARTIAL_PLUM_BODY_COUNT: usize = 64;
+/// The default maximum sum of the total_length-s of the PartialPlumBody-s kept at once.
+pub(crate) const DEFAULT_MAX_PARTIAL_PLUM_BODY_TOTAL_LENGTH: u64 = 1024 * 1024 * 1024;
+/// The default duration after which a PartialPlumBody that hasn't received a PlumBodyChunk is
+/// dropped.
+pub(crate) const DEFAULT_PARTIAL_PLUM_BODY_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// Limits on the PlumBody-s being pushed in PlumBodyChunk-s.  A PartialPlumBody accumulates its
+/// whole PlumBody in memory (and the reassembled PlumBody is then stored all at once), so these bound
+/// the memory that pushes can use, regardless of how many clients are pushing.
+#[derive(Clone, Debug)]
+pub(crate) struct PartialPlumBodyLimits {
+    pub(crate) max_plum_body_length: u64,
+    pub(crate) max_partial_plum_body_count: usize,
+    pub(crate) max_partial_plum_body_total_length: u64,
+    pub(crate) partial_plum_body_expiry: Duration,
+}
+
+impl Default for PartialPlumBodyLimits {
+    fn default() -> Self {
+        Self {
+            max_plum_body_length: DEFAULT_MAX_PLUM_BODY_LENGTH,
+            max_partial_plum_body_count: DEFAULT_MAX_PARTIAL_PLUM_BODY_COUNT,
+            max_partial_plum_body_total_length: DEFAULT_MAX_PARTIAL_PLUM_BODY_TOTAL_LENGTH,
+            partial_plum_body_expiry: DEFAULT_PARTIAL_PLUM_BODY_EXPIRY,
+        }
+    }
+}
+
+/// Returned by PartialPlumBodyStore::append_chunk when a PlumBodyChunk is rejected.
+#[derive(Debug)]
+pub(crate) enum PartialPlumBodyStoreError {
+    PlumBodyTooLong {
+        plum_body_seal: PlumBodySeal,
+        total_length: u64,
+        max_plum_body_length: u64,
+    },
+    LimitsExceeded {
+        plum_body_seal: PlumBodySeal,
+        total_length: u64,
+    },
+    NotInProgress {
+        plum_body_seal: PlumBodySeal,
+    },
+    PlumBodyChunkError(PlumBodyChunkError),
+}
+
+impl From<PartialPlumBodyStoreError> for tonic::Status {
+    fn from(partial_plum_body_store_error: PartialPlumBodyStoreError) -> Self {
+        match partial_plum_body_store_error {
+            PartialPlumBodyStoreError::PlumBodyTooLong {
+                plum_body_seal,
+                total_length,
+                max_plum_body_length,
+            } => tonic::Status::invalid_argument(format!(
+                "PlumBody {} has total_length {}, which exceeds the maximum of {}",
+                plum_body_seal, total_length, max_plum_body_length
+            )),
+            PartialPlumBodyStoreError::LimitsExceeded {
+                plum_body_seal,
+                total_length,
+            } => tonic::Status::resource_exhausted(format!(
+                "too many PlumBody-s are being pushed to accept PlumBody {} (of total_length {}); try again later",
+                plum_body_seal, total_length
+            )),
+            PartialPlumBodyStoreError::NotInProgress { plum_body_seal } => {
+                tonic::Status::failed_precondition(format!(
+                    "no push of PlumBody {} is in progress, so its first PlumBodyChunk must have offset 0",
+                    plum_body_seal
+                ))
+            }
+            PartialPlumBodyStoreError::PlumBodyChunkError(plum_body_chunk_error) => {
+                tonic::Status::invalid_argument(plum_body_chunk_error.to_string())
+            }
+        }
+    }
+}
+
+/// Identifies a PartialPlumBody by the client pushing it (None for an unauthenticated client) and its
+/// PlumBodySeal, so that a client can only resume its own pushes.
+type PartialPlumBodyKey = (Option<AuthenticatedIdentity>, PlumBodySeal);
+
+#[derive(Debug)]
+struct PartialPlumBodyEntry {
+    partial_plum_body: PartialPlumBody,
+    /// When the PartialPlumBody was created or last received a PlumBodyChunk.
+    updated_at: Instant,
+}
+
+/// The PlumBody-s whose PlumBodyChunk-s are being pushed to IDPServer, kept independently of any
+/// connection, so that a push that was interrupted (e.g. because the client cancelled it or the
+/// connection dropped) can be resumed where it left off by a later push, even over a new connection.
+/// A PartialPlumBody is dropped once it's complete, or once it has expired (see
+/// PartialPlumBodyLimits::partial_plum_body_expiry).
+#[derive(Debug, Default)]
+pub(crate) struct PartialPlumBodyStore {
+    entry_m: HashMap<PartialPlumBodyKey, PartialPlumBodyEntry>,
+    /// The sum of the total_length-s of the PartialPlumBody-s in entry_m.  The total_length is counted
+    /// up front, since that's how large the PartialPlumBody will grow.
+    total_length: u64,
+}
+
+impl PartialPlumBodyStore {
+    /// Returns the offset at which the given client's push of the given PlumBody should resume, if
+    /// there's an unexpired PartialPlumBody for it.
+    pub(crate) fn resume_offset_o(
+        &mut self,
+        limits: &PartialPlumBodyLimits,
+        authenticated_identity_o: Option<&AuthenticatedIdentity>,
+        plum_body_seal: &PlumBodySeal,
+    ) -> Option<u64> {
+        self.remove_expired(limits);
+        self.entry_m
+            .get(&(authenticated_identity_o.cloned(), plum_body_seal.clone()))
+            .map(|entry| entry.partial_plum_body.offset())
+    }
+    /// Appends the PlumBodyChunk to the given client's PartialPlumBody for it, and returns the
+    /// PartialPlumBody once it's complete (at which point it's no longer kept).  A PlumBodyChunk at
+    /// offset 0 (re)starts the transfer, and is rejected if its total_length exceeds the maximum
+    /// PlumBody length, or if that would exceed the limits on PartialPlumBody-s.
+    pub(crate) fn append_chunk(
+        &mut self,
+        limits: &PartialPlumBodyLimits,
+        authenticated_identity_o: Option<&AuthenticatedIdentity>,
+        plum_body_chunk: PlumBodyChunk,
+    ) -> Result<Option<PartialPlumBody>, PartialPlumBodyStoreError> {
+        self.remove_expired(limits);
+        let key = (
+            authenticated_identity_o.cloned(),
+            plum_body_chunk.plum_body_seal.clone(),
+        );
+        if plum_body_chunk.offset == 0 {
+            self.remove(&key);
+            self.check_limits(limits, &plum_body_chunk)?;
+            self.total_length += plum_body_chunk.total_length;
+            self.entry_m.insert(
+                key.clone(),
+                PartialPlumBodyEntry {
+                    partial_plum_body: PartialPlumBody::new(
+                        plum_body_chunk.plum_body_seal.clone(),
+                        plum_body_chunk.total_length,
+                    ),
+                    updated_at: Instant::now(),
+                },
+            );
+        }
+        let entry =
+            self.entry_m
+                .get_mut(&key)
+                .ok_or_else(|| PartialPlumBodyStoreError::NotInProgress {
+                    plum_body_seal: key.1.clone(),
+                })?;
+        entry
+            .partial_plum_body
+            .append_chunk(plum_body_chunk)
+            .map_err(PartialPlumBodyStoreError::PlumBodyChunkError)?;
+        entry.updated_at = Instant::now();
+        if entry.partial_plum_body.is_complete() {
+            Ok(self.remove(&key))
+        } else {
+            Ok(None)
+        }
+    }
+    fn check_limits(
+        &self,
+        limits: &PartialPlumBodyLimits,
+        plum_body_chunk: &PlumBodyChunk,
+    ) -> Result<(), PartialPlumBodyStoreError> {
+        if plum_body_chunk.total_length > limits.max_plum_body_length {
+            return Err(PartialPlumBodyStoreError::PlumBodyTooLong {
+                plum_body_seal: plum_body_chunk.plum_body_seal.clone(),
+                total_length: plum_body_chunk.total_length,
+                max_plum_body_length: limits.max_plum_body_length,
+            });
+        }
+        if self.entry_m.len() >= limits.max_partial_plum_body_count
+            || self.total_length + plum_body_chunk.total_length
+                > limits.max_partial_plum_body_total_length
+        {
+            return Err(PartialPlumBodyStoreError::LimitsExceeded {
+                plum_body_seal: plum_body_chunk.plum_body_seal.clone(),
+                total_length: plum_body_chunk.total_length,
+            });
+        }
+        Ok(())
+    }
+    fn remove(&mut self, key: &PartialPlumBodyKey) -> Option<PartialPlumBody> {
+        let entry = self.entry_m.remove(key)?;
+        self.total_length -= entry.partial_plum_body.total_length();
+        Some(entry.partial_plum_body)
+    }
+    fn remove_expired(&mut self, limits: &PartialPlumBodyLimits) {
+        let partial_plum_body_expiry = limits.partial_plum_body_expiry;
+        let mut expired_total_length = 0;
+        self.entry_m.retain(|_, entry| {
+            let expired = entry.updated_at.elapsed() >= partial_plum_body_expiry;
+            if expired {
+                expired_total_length += entry.partial_plum_body.total_length();
+            }
+            !expired
+        });
+        self.total_length -= expired_total_length;
+    }
+}
@@ -0,0 +1,38 @@
+/// TLS configuration for IDPServer (see IDPServer::with_tls_config).
+#[derive(Clone, Debug)]
+pub struct ServerTLSConfig {
+    /// PEM-encoded certificate chain and private key of the server.
+    cert_pem: Vec<u8>,
+    key_pem: Vec<u8>,
+    /// PEM-encoded CA certificates, one of which must have issued each client's certificate.  If
+    /// present, clients must present a certificate (i.e. mutual TLS is required).
+    client_ca_bundle_pem_o: Option<Vec<u8>>,
+}
+
+impl ServerTLSConfig {
+    /// Uses the given PEM-encoded certificate chain and (PKCS8 or RSA) private key.
+    pub fn new(cert_pem: Vec<u8>, key_pem: Vec<u8>) -> Self {
+        Self {
+            cert_pem,
+            key_pem,
+            client_ca_bundle_pem_o: None,
+        }
+    }
+    /// Requires mutual TLS (mTLS), where each client must present a certificate issued by one of
+    /// the CAs in the given PEM-encoded CA bundle.
+    pub fn with_client_ca_bundle(mut self, client_ca_bundle_pem: Vec<u8>) -> Self {
+        self.client_ca_bundle_pem_o = Some(client_ca_bundle_pem);
+        self
+    }
+    pub(crate) fn to_tonic_server_tls_config(&self) -> tonic::transport::ServerTlsConfig {
+        let mut server_tls_config = tonic::transport::ServerTlsConfig::new().identity(
+            tonic::transport::Identity::from_pem(&self.cert_pem, &self.key_pem),
+        );
+        if let Some(client_ca_bundle_pem) = self.client_ca_bundle_pem_o.as_ref() {
+            server_tls_config = server_tls_config.client_ca_root(
+                tonic::transport::Certificate::from_pem(client_ca_bundle_pem),
+            );
+        }
+        server_tls_config
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{jws_sign, Result, JWS};
+
+/// Produce the message that a client signs in order to answer an authorization challenge issued by an
+/// IDPServer (see idp_proto::DID_KEY_AUTHORIZATION_SCHEME).  The challenge is prefixed so that a server
+/// can't use a challenge to obtain the client's signature on some other message (e.g. a PlumSigContent).
+/// The audience identifies the server that the client intends to authenticate to (e.g. its URL or DID),
+/// and is checked by that server, so that a server can't relay a challenge from another server and use
+/// the answer to authenticate to it as the client.
+pub fn did_key_authorization_message(audience: &str, challenge: &str) -> Vec<u8> {
+    format!(
+        "IDP authorization challenge for {}: {}",
+        audience, challenge
+    )
+    .into_bytes()
+}
+
+/// Sign the given authorization challenge for the given audience, producing the value of the
+/// "authorization" metadata entry, i.e. "DIDKey <challenge> <JWS>".
+pub async fn did_key_authorization(
+    priv_jwk: &ssi_jwk::JWK,
+    audience: &str,
+    challenge: &str,
+) -> Result<String> {
+    let jws: JWS = jws_sign(
+        priv_jwk,
+        &did_key_authorization_message(audience, challenge),
+    )
+    .await?;
+    Ok(format!(
+        "{} {} {}",
+        idp_proto::DID_KEY_AUTHORIZATION_SCHEME,
+        challenge,
+        jws
+    ))
+}
+
+/// Authenticates IDPClient as the did:key of the given priv JWK, by signing the challenges issued by
+/// the server (see idp_server::DIDKeyAuthenticator).  The JWK's key_id must be set to its did:key
+/// fragment URL (as KeyType::generate_priv_jwk does).
+#[cfg(feature = "client")]
+pub struct DIDKeyCredentialProvider {
+    priv_jwk: ssi_jwk::JWK,
+    audience: String,
+}
+
+#[cfg(feature = "client")]
+impl DIDKeyCredentialProvider {
+    /// The audience identifies the server to authenticate to (e.g. its URL or DID), and must match the
+    /// audience that the server's DIDKeyAuthenticator was created with.
+    pub fn new(priv_jwk: ssi_jwk::JWK, audience: String) -> Self {
+        Self { priv_jwk, audience }
+    }
+}
+
+#[cfg(feature = "client")]
+#[async_trait::async_trait]
+impl idp_core::CredentialProvider for DIDKeyCredentialProvider {
+    async fn authorization(&self, challenge_o: Option<&str>) -> Result<String> {
+        match challenge_o {
+            Some(challenge) => {
+                did_key_authorization(&self.priv_jwk, &self.audience, challenge).await
+            }
+            // Ask the server to issue a challenge.
+            None => Ok(idp_proto::DID_KEY_AUTHORIZATION_SCHEME.to_string()),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod did;
+mod did_key_authorization;
 mod jws;
 mod key_type;
 mod owned_data;
@@ -8,6 +9,7 @@ mod plum_sig_content_hash;
 
 pub use crate::{
     did::{did_key_from_jwk, did_resolver, with_multibase_fragment},
+    did_key_authorization::{did_key_authorization, did_key_authorization_message},
     jws::{jws_sign, JWS},
     key_type::KeyType,
     owned_data::OwnedData,
@@ -16,3 +18,6 @@ pub use crate::{
     plum_sig_content_hash::PlumSigContentHash,
 };
 pub use anyhow::{Error, Result};
+
+#[cfg(feature = "client")]
+pub use crate::did_key_authorization::DIDKeyCredentialProvider;
@@ -269,7 +269,7 @@ impl idp_proto::Serializable for PlumSig {
 }
 
 impl idp_proto::Hashable for PlumSig {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn idp_proto::SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.content.update_hasher(hasher);
         self.signature.update_hasher(hasher);
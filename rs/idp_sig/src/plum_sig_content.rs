@@ -35,7 +35,7 @@ impl PlumSigContent {
 }
 
 impl idp_proto::Hashable for PlumSigContent {
-    fn update_hasher(&self, hasher: &mut sha2::Sha256) {
+    fn update_hasher(&self, hasher: &mut dyn idp_proto::SealHasher) {
         // NOTE: The specific order and form of this hashing must NOT be changed!
         self.nonce.update_hasher(hasher);
         self.plum.update_hasher(hasher);